@@ -1,3 +1,7 @@
 fn main() {
+    // 可选的 gRPC 管理接口 (需要本机安装 protoc)
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/admin.proto").expect("编译 proto/admin.proto 失败");
+
     tauri_build::build()
 }
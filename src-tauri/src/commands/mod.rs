@@ -6,6 +6,10 @@ use tauri::{Emitter, Manager};
 pub mod proxy;
 // 导出 autostart 命令
 pub mod autostart;
+// 导出后台服务命令
+pub mod service;
+// 导出配置档命令
+pub mod profile;
 
 /// 列出所有账号
 #[tauri::command]
@@ -42,6 +46,7 @@ pub async fn add_account(
         modules::upsert_account(user_info.email.clone(), user_info.get_display_name(), token)?;
 
     modules::logger::log_info(&format!("添加账号成功: {}", account.email));
+    record_account_add(&account, "refresh_token");
 
     // 5. 自动触发刷新额度
     let mut account = account;
@@ -56,6 +61,15 @@ pub async fn add_account(
     Ok(account)
 }
 
+fn record_account_add(account: &Account, source: &str) {
+    modules::audit::record(
+        modules::audit::AuditActor::Gui,
+        modules::audit::AuditAction::AccountAdd,
+        Some(&account.id),
+        Some(serde_json::json!({ "email": account.email, "source": source })),
+    );
+}
+
 /// 删除账号
 #[tauri::command]
 pub async fn delete_account(app: tauri::AppHandle, account_id: String) -> Result<(), String> {
@@ -65,6 +79,12 @@ pub async fn delete_account(app: tauri::AppHandle, account_id: String) -> Result
         e
     })?;
     modules::logger::log_info(&format!("账号删除成功: {}", account_id));
+    modules::audit::record(
+        modules::audit::AuditActor::Gui,
+        modules::audit::AuditAction::AccountDelete,
+        Some(&account_id),
+        None,
+    );
 
     // 强制同步托盘
     crate::modules::tray::update_tray_menus(&app);
@@ -85,12 +105,60 @@ pub async fn delete_accounts(
         modules::logger::log_error(&format!("批量删除失败: {}", e));
         e
     })?;
+    modules::audit::record(
+        modules::audit::AuditActor::Gui,
+        modules::audit::AuditAction::AccountDelete,
+        None,
+        Some(serde_json::json!({ "account_ids": account_ids })),
+    );
 
     // 强制同步托盘
     crate::modules::tray::update_tray_menus(&app);
     Ok(())
 }
 
+/// 列出回收站中的账号
+#[tauri::command]
+pub async fn list_trashed_accounts() -> Result<Vec<modules::account::TrashedAccount>, String> {
+    modules::account::list_trash()
+}
+
+/// 从回收站恢复账号
+#[tauri::command]
+pub async fn restore_account(app: tauri::AppHandle, account_id: String) -> Result<Account, String> {
+    let account = modules::account::restore_account(&account_id).map_err(|e| {
+        modules::logger::log_error(&format!("恢复账号失败: {}", e));
+        e
+    })?;
+    modules::logger::log_info(&format!("账号已从回收站恢复: {}", account.email));
+    modules::audit::record(
+        modules::audit::AuditActor::Gui,
+        modules::audit::AuditAction::AccountRestore,
+        Some(&account.id),
+        Some(serde_json::json!({ "email": account.email })),
+    );
+
+    crate::modules::tray::update_tray_menus(&app);
+    Ok(account)
+}
+
+/// 永久删除回收站中的账号 (`older_than_secs` 为空时清空回收站)，返回删除数量
+#[tauri::command]
+pub async fn purge_trashed_accounts(older_than_secs: Option<i64>) -> Result<usize, String> {
+    let purged = modules::account::purge_trash(older_than_secs, chrono::Utc::now().timestamp())?;
+    if !purged.is_empty() {
+        modules::audit::record(
+            modules::audit::AuditActor::Gui,
+            modules::audit::AuditAction::AccountPurge,
+            None,
+            Some(serde_json::json!({
+                "account_ids": purged.iter().map(|t| t.account.id.clone()).collect::<Vec<_>>()
+            })),
+        );
+    }
+    Ok(purged.len())
+}
+
 /// 重新排序账号列表
 /// 根据传入的账号ID数组顺序更新账号排列
 #[tauri::command]
@@ -107,6 +175,12 @@ pub async fn reorder_accounts(account_ids: Vec<String>) -> Result<(), String> {
 pub async fn switch_account(app: tauri::AppHandle, account_id: String) -> Result<(), String> {
     let res = modules::switch_account(&account_id).await;
     if res.is_ok() {
+        modules::audit::record(
+            modules::audit::AuditActor::Gui,
+            modules::audit::AuditAction::AccountSwitch,
+            Some(&account_id),
+            None,
+        );
         crate::modules::tray::update_tray_menus(&app);
     }
     res
@@ -177,34 +251,35 @@ pub async fn fetch_account_quota(
 
 #[derive(serde::Serialize)]
 pub struct RefreshStats {
-    total: usize,
-    success: usize,
-    failed: usize,
-    details: Vec<String>,
+    pub total: usize,
+    pub success: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub details: Vec<String>,
 }
 
-/// 刷新所有账号配额
+/// 刷新所有账号配额，可按过期时间、Forbidden 状态或指定账号筛选
 #[tauri::command]
-pub async fn refresh_all_quotas() -> Result<RefreshStats, String> {
-    modules::logger::log_info("开始批量刷新所有账号配额");
+pub async fn refresh_all_quotas(
+    filter: Option<modules::account::RefreshFilter>,
+) -> Result<RefreshStats, String> {
+    let filter = filter.unwrap_or_default();
+    modules::logger::log_info(&format!("开始批量刷新账号配额 (筛选: {:?})", filter));
     let accounts = modules::list_accounts()?;
+    let now = chrono::Utc::now().timestamp();
 
     let mut success = 0;
     let mut failed = 0;
+    let mut skipped = 0;
     let mut details = Vec::new();
 
     // 串行处理以确保持久化安全 (SQLite)
     for mut account in accounts {
-        if account.disabled {
-            modules::logger::log_info(&format!("  - Skipping {} (Disabled)", account.email));
+        if let Some(reason) = filter.skip_reason(&account, now) {
+            modules::logger::log_info(&format!("  - Skipping {} ({})", account.email, reason));
+            skipped += 1;
             continue;
         }
-        if let Some(ref q) = account.quota {
-            if q.is_forbidden {
-                modules::logger::log_info(&format!("  - Skipping {} (Forbidden)", account.email));
-                continue;
-            }
-        }
 
         modules::logger::log_info(&format!("  - Processing {}", account.email));
 
@@ -231,11 +306,15 @@ pub async fn refresh_all_quotas() -> Result<RefreshStats, String> {
         }
     }
 
-    modules::logger::log_info(&format!("批量刷新完成: {} 成功, {} 失败", success, failed));
+    modules::logger::log_info(&format!(
+        "批量刷新完成: {} 成功, {} 失败, {} 跳过",
+        success, failed, skipped
+    ));
     Ok(RefreshStats {
         total: success + failed,
         success,
         failed,
+        skipped,
         details,
     })
 }
@@ -253,11 +332,23 @@ pub async fn save_config(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
     config: AppConfig,
 ) -> Result<(), String> {
+    let previous = modules::load_app_config().ok();
     modules::save_app_config(&config)?;
+    if let Some(previous) = previous {
+        modules::audit::record_config_change(modules::audit::AuditActor::Gui, &previous, &config);
+    }
 
     // 通知托盘配置已更新
     let _ = app.emit("config://updated", ());
 
+    // 客户端标识档案同时用于配额查询等非反代请求，不依赖反代服务是否运行
+    crate::proxy::upstream::profiles::configure(&config.proxy.client_profiles);
+
+    // 请求日志的请求体采样 (监控器在反代停止后仍保留)
+    if let Some(monitor) = proxy_state.monitor.read().await.as_ref() {
+        monitor.set_body_capture(&config.proxy.body_capture);
+    }
+
     // 热更新正在运行的服务
     let instance_lock = proxy_state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
@@ -272,12 +363,148 @@ pub async fn save_config(
         instance.axum_server.update_security(&config.proxy).await;
         // 更新 z.ai 配置
         instance.axum_server.update_zai(&config.proxy).await;
+        // 更新超时配置
+        instance.axum_server.update_timeouts(&config.proxy).await;
+        instance.axum_server.update_response_headers(&config.proxy).await;
+        instance.axum_server.update_compression(&config.proxy).await;
+        instance.axum_server.update_validation(&config.proxy).await;
+        instance.axum_server.update_param_policy(&config.proxy).await;
+        instance.axum_server.update_client_rate_limit(&config.proxy);
+        instance.axum_server.update_auth_lockout(&config.proxy);
+        instance.axum_server.update_trusted_proxies(&config.proxy);
+        instance.axum_server.update_batches(&config.proxy);
+        instance.axum_server.update_load_shedding(&config.proxy);
+        instance.axum_server.update_debug_endpoints(&config.proxy);
+        instance.axum_server.update_mirror(&config.proxy);
+        instance
+            .token_manager
+            .update_quota_thresholds(config.proxy.quota_thresholds.clone())
+            .await;
+        instance
+            .token_manager
+            .update_usage_caps(config.proxy.usage_caps.clone())
+            .await;
+        instance
+            .token_manager
+            .update_team_routing(config.proxy.team_routing.clone())
+            .await;
+        instance
+            .token_manager
+            .update_tier_policy(config.proxy.tier_policy.clone())
+            .await;
+        instance
+            .token_manager
+            .update_schedule_routing(config.proxy.schedule_routing.clone())
+            .await;
+        instance
+            .token_manager
+            .update_retry_policy(config.proxy.retry_policy.clone())
+            .await;
+        instance
+            .token_manager
+            .update_quota_cache(config.proxy.quota_cache.clone())
+            .await;
+        instance
+            .token_manager
+            .update_model_capabilities(config.proxy.model_capabilities.clone())
+            .await;
+        instance
+            .token_manager
+            .update_context_overflow(config.proxy.context_overflow.clone())
+            .await;
+        instance
+            .token_manager
+            .update_model_list(config.proxy.model_list.clone())
+            .await;
+        crate::proxy::cluster::configure(&instance.token_manager, &config.proxy.cluster);
+        // 更新上游连接池配置 (z.ai 等共享客户端立即生效，主上游客户端重启服务后生效)
+        crate::proxy::upstream::pool::global().configure(&config.proxy.upstream_pool);
+        crate::proxy::upstream::rate_limit::global().configure(&config.proxy.upstream_rate_limit);
+        crate::proxy::stream_resume::configure(&config.proxy.stream_resumption);
+        crate::proxy::events::publish(crate::proxy::events::ProxyEvent::ConfigReloaded);
         tracing::debug!("已同步热更新反代服务配置");
     }
+    drop(instance_lock);
+
+    // 主监听地址变化: 先绑定新地址再停止旧监听器，在途会话不中断
+    let mut instance_lock = proxy_state.instance.write().await;
+    if let Some(instance) = instance_lock.as_mut() {
+        let rebound = instance
+            .axum_server
+            .rebind(&config.proxy)
+            .await
+            .map_err(|e| format!("配置已保存，但切换监听地址失败: {}", e))?;
+        if rebound {
+            instance.config.port = config.proxy.port;
+            instance.config.allow_lan_access = config.proxy.allow_lan_access;
+            instance.config.listen_tcp = config.proxy.listen_tcp;
+        }
+    }
 
     Ok(())
 }
 
+/// 列出所有可设置的配置项 (点分路径、类型、当前值与默认值)
+#[tauri::command]
+pub async fn list_config_keys() -> Result<Vec<modules::config::ConfigKeyInfo>, String> {
+    modules::config::list_config_keys()
+}
+
+/// 读取单个配置项
+#[tauri::command]
+pub async fn get_config_value(key: String) -> Result<serde_json::Value, String> {
+    modules::config::get_config_value(&key)
+}
+
+/// 设置单个配置项
+#[tauri::command]
+pub async fn set_config_value(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    key: String,
+    value: String,
+) -> Result<(), String> {
+    let config = modules::config::set_config_value(&key, &value)?;
+    save_config(app, proxy_state, config).await
+}
+
+/// 将单个配置项恢复为默认值
+#[tauri::command]
+pub async fn unset_config_value(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    key: String,
+) -> Result<(), String> {
+    let config = modules::config::unset_config_value(&key)?;
+    save_config(app, proxy_state, config).await
+}
+
+/// 查看审计日志 (最近 `limit` 条，可按动作过滤，如 `account_delete`)
+#[tauri::command]
+pub async fn get_audit_log(
+    limit: Option<usize>,
+    action: Option<String>,
+) -> Result<Vec<modules::audit::AuditEntry>, String> {
+    modules::audit::read_entries(limit.unwrap_or(100), action.as_deref())
+}
+
+/// 校验当前配置 (端口、API Key、模型映射、上游代理、z.ai)
+#[tauri::command]
+pub async fn validate_config(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+) -> Result<modules::config_validation::ValidationReport, String> {
+    let config = modules::load_app_config()?;
+    // 反代已在同一端口运行时跳过端口占用检测
+    let running_port = proxy_state
+        .instance
+        .read()
+        .await
+        .as_ref()
+        .map(|instance| instance.config.port);
+    let check_port = running_port != Some(config.proxy.port);
+    Ok(modules::config_validation::validate_config(&config, check_port).await)
+}
+
 // --- OAuth 命令 ---
 
 #[tauri::command]
@@ -332,6 +559,7 @@ pub async fn start_oauth_login(app_handle: tauri::AppHandle) -> Result<Account,
         user_info.get_display_name(),
         token_data,
     )?;
+    record_account_add(&account, "oauth");
 
     // 7. 自动触发刷新额度
     let _ = internal_refresh_account_quota(&app_handle, &mut account).await;
@@ -398,6 +626,7 @@ pub async fn complete_oauth_login(app_handle: tauri::AppHandle) -> Result<Accoun
         user_info.get_display_name(),
         token_data,
     )?;
+    record_account_add(&account, "oauth");
 
     // 7. 自动触发刷新额度
     let _ = internal_refresh_account_quota(&app_handle, &mut account).await;
@@ -694,16 +923,11 @@ pub async fn toggle_proxy_status(
         if enable { "启用" } else { "禁用" }
     ));
 
-    // 1. 读取账号文件
-    let data_dir = modules::account::get_data_dir()?;
-    let account_path = data_dir.join("accounts").join(format!("{}.json", account_id));
-
-    if !account_path.exists() {
-        return Err(format!("账号文件不存在: {}", account_id));
-    }
-
-    let content = std::fs::read_to_string(&account_path)
-        .map_err(|e| format!("读取账号文件失败: {}", e))?;
+    // 1. 从存储后端读取账号
+    let storage = modules::storage::backend();
+    let content = storage
+        .read(modules::storage::Collection::Accounts, &account_id)?
+        .ok_or_else(|| format!("账号文件不存在: {}", account_id))?;
 
     let mut account_json: serde_json::Value = serde_json::from_str(&content)
         .map_err(|e| format!("解析账号文件失败: {}", e))?;
@@ -724,8 +948,11 @@ pub async fn toggle_proxy_status(
         );
     }
 
-    // 3. 保存到磁盘
-    std::fs::write(&account_path, serde_json::to_string_pretty(&account_json).unwrap())
+    // 3. 写回存储后端
+    let json = serde_json::to_string_pretty(&account_json)
+        .map_err(|e| format!("序列化账号文件失败: {}", e))?;
+    storage
+        .write(modules::storage::Collection::Accounts, &account_id, &json)
         .map_err(|e| format!("写入账号文件失败: {}", e))?;
 
     modules::logger::log_info(&format!(
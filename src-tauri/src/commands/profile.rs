@@ -0,0 +1,26 @@
+// 配置档命令 (多套独立的数据目录、账号与反代配置)
+use crate::modules::profile::{self, ProfileInfo};
+
+/// 列出所有配置档
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<ProfileInfo>, String> {
+    profile::list_profiles()
+}
+
+/// 创建配置档
+#[tauri::command]
+pub async fn create_profile(name: String) -> Result<ProfileInfo, String> {
+    profile::create_profile(name.trim())
+}
+
+/// 切换默认配置档 (重启应用后生效)
+#[tauri::command]
+pub async fn switch_profile(name: String) -> Result<(), String> {
+    profile::switch_profile(name.trim())
+}
+
+/// 获取当前进程使用的配置档
+#[tauri::command]
+pub async fn get_active_profile() -> Result<String, String> {
+    Ok(profile::get_active_profile())
+}
@@ -4,7 +4,7 @@ use tokio::sync::RwLock;
 use serde::{Serialize, Deserialize};
 use crate::proxy::{ProxyConfig, TokenManager};
 use tokio::time::Duration;
-use crate::proxy::monitor::{ProxyMonitor, ProxyRequestLog, ProxyStats};
+use crate::proxy::monitor::{ProxyMonitor, ProxyRequestLog, ProxyStats, SessionSummary};
 
 
 /// 反代服务状态
@@ -14,12 +14,48 @@ pub struct ProxyStatus {
     pub port: u16,
     pub base_url: String,
     pub active_accounts: usize,
+    /// 配额耗尽、等待重置的账号模型
+    #[serde(default)]
+    pub quota_resets: Vec<QuotaResetInfo>,
+    /// 脱敏后的 API Key (仅前缀)
+    #[serde(default)]
+    pub api_key_hint: String,
+}
+
+/// 等待配额重置的账号模型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaResetInfo {
+    pub email: String,
+    pub model: String,
+    /// 重置时间 (Unix 秒)
+    pub reset_at: i64,
+    /// 重置倒计时，如 `2h05m`
+    pub resets_in: String,
+}
+
+impl QuotaResetInfo {
+    fn collect(token_manager: &TokenManager) -> Vec<Self> {
+        let now = chrono::Utc::now().timestamp();
+        token_manager
+            .pending_quota_resets()
+            .into_iter()
+            .map(|(email, model, reset_at)| Self {
+                email,
+                model,
+                reset_at,
+                resets_in: crate::models::quota::format_countdown(reset_at - now),
+            })
+            .collect()
+    }
 }
 
 /// 反代服务全局状态
+#[derive(Clone)]
 pub struct ProxyServiceState {
     pub instance: Arc<RwLock<Option<ProxyServiceInstance>>>,
     pub monitor: Arc<RwLock<Option<Arc<ProxyMonitor>>>>,
+    /// gRPC 管理接口的停止信号 (随进程运行，反代启停不影响)
+    pub control_plane: Arc<std::sync::Mutex<Option<tokio::sync::oneshot::Sender<()>>>>,
 }
 
 /// 反代服务实例
@@ -35,17 +71,21 @@ impl ProxyServiceState {
         Self {
             instance: Arc::new(RwLock::new(None)),
             monitor: Arc::new(RwLock::new(None)),
+            control_plane: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 }
 
-/// 启动反代服务
-#[tauri::command]
-pub async fn start_proxy_service(
-    config: ProxyConfig,
-    state: State<'_, ProxyServiceState>,
-    app_handle: tauri::AppHandle,
-) -> Result<ProxyStatus, String> {
+/// 读取启动反代所用的配置 (GUI 为配置文件，无头模式另含命令行覆盖)
+pub type ConfigLoader = Arc<dyn Fn() -> Result<ProxyConfig, String> + Send + Sync>;
+
+/// 启动反代实例并记录审计日志，返回可用账号数 (GUI 命令、无头模式与 gRPC 管理接口共用)
+pub async fn start_instance(
+    state: &ProxyServiceState,
+    config: &ProxyConfig,
+    app_handle: Option<tauri::AppHandle>,
+    actor: crate::modules::audit::AuditActor,
+) -> Result<usize, String> {
     let mut instance_lock = state.instance.write().await;
     
     // 防止重复启动
@@ -54,33 +94,248 @@ pub async fn start_proxy_service(
     }
 
     // Ensure monitor exists
-    {
+    let monitor = {
         let mut monitor_lock = state.monitor.write().await;
-        if monitor_lock.is_none() {
-            *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000, Some(app_handle.clone()))));
-        }
+        let monitor = monitor_lock
+            .get_or_insert_with(|| Arc::new(ProxyMonitor::new(1000, app_handle)))
+            .clone();
         // Sync enabled state from config
-        if let Some(monitor) = monitor_lock.as_ref() {
-            monitor.set_enabled(config.enable_logging);
+        monitor.set_enabled(config.enable_logging);
+        monitor.set_body_capture(&config.body_capture);
+        monitor
+    };
+
+    let (instance, active_accounts) = create_proxy_instance(config, monitor).await?;
+    
+    *instance_lock = Some(instance);
+    crate::modules::audit::record(
+        actor,
+        crate::modules::audit::AuditAction::ProxyStart,
+        Some(&format!("{}:{}", config.get_bind_address(), config.port)),
+        None,
+    );
+    Ok(active_accounts)
+}
+
+/// 停止反代实例；`drain_timeout` 为空时立即关闭连接
+pub async fn stop_instance(
+    state: &ProxyServiceState,
+    drain_timeout: Option<Duration>,
+    actor: crate::modules::audit::AuditActor,
+) -> Result<(), String> {
+    let Some(instance) = state.instance.write().await.take() else {
+        return Err("服务未运行".to_string());
+    };
+    match drain_timeout {
+        Some(timeout) => instance.axum_server.stop_with_drain(timeout),
+        None => instance.axum_server.stop(),
+    }
+    // 等待服务器任务完成
+    instance.server_handle.await.ok();
+    crate::modules::audit::record(
+        actor,
+        crate::modules::audit::AuditAction::ProxyStop,
+        Some(&format!("{}:{}", instance.config.get_bind_address(), instance.config.port)),
+        None,
+    );
+    Ok(())
+}
+
+/// 按配置启动 gRPC 管理接口 (已在运行时忽略)
+pub async fn ensure_control_plane(
+    state: &ProxyServiceState,
+    grpc: &crate::proxy::config::GrpcConfig,
+    app_handle: Option<tauri::AppHandle>,
+    load_config: ConfigLoader,
+) -> Result<(), String> {
+    if !grpc.enabled {
+        return Ok(());
+    }
+    #[cfg(feature = "grpc")]
+    {
+        if state.control_plane.lock().unwrap().is_some() {
+            return Ok(());
         }
+        let control = Arc::new(ServiceControl {
+            state: state.clone(),
+            app_handle,
+            load_config,
+        });
+        let shutdown = crate::proxy::grpc::start(grpc, control).await?;
+        *state.control_plane.lock().unwrap() = Some(shutdown);
+    }
+    #[cfg(not(feature = "grpc"))]
+    {
+        let _ = (state, app_handle, load_config);
+        tracing::warn!("已配置 gRPC 管理接口，但当前版本未以 grpc 特性编译，忽略");
+    }
+    Ok(())
+}
+
+/// gRPC 管理接口对反代实例的生命周期控制
+#[cfg(feature = "grpc")]
+struct ServiceControl {
+    state: ProxyServiceState,
+    app_handle: Option<tauri::AppHandle>,
+    load_config: ConfigLoader,
+}
+
+#[cfg(feature = "grpc")]
+impl crate::proxy::grpc::ProxyControl for ServiceControl {
+    fn security(&self) -> Option<crate::proxy::ProxySecurityConfig> {
+        // 运行中使用实例的当前鉴权配置 (含热更新与轮换)，否则读取配置
+        if let Ok(instance) = self.state.instance.try_read() {
+            if let Some(instance) = instance.as_ref() {
+                if let Ok(global) = instance.axum_server.security().global.try_read() {
+                    return Some(global.clone());
+                }
+            }
+        }
+        (self.load_config)()
+            .ok()
+            .map(|config| crate::proxy::ProxySecurityConfig::from_proxy_config(&config))
+    }
+
+    fn runtime(&self) -> futures::future::BoxFuture<'_, Option<crate::proxy::grpc::ProxyRuntime>> {
+        Box::pin(async move {
+            let instance = self.state.instance.read().await;
+            let instance = instance.as_ref()?;
+            let monitor = self.state.monitor.read().await.clone()?;
+            Some(crate::proxy::grpc::ProxyRuntime {
+                port: instance.config.port,
+                token_manager: instance.token_manager.clone(),
+                monitor,
+                security: instance.axum_server.security(),
+            })
+        })
+    }
+
+    fn start(&self) -> futures::future::BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            let config = (self.load_config)()?;
+            start_instance(
+                &self.state,
+                &config,
+                self.app_handle.clone(),
+                crate::modules::audit::AuditActor::AdminApi,
+            )
+            .await
+            .map(|_| ())
+        })
+    }
+
+    fn stop(&self) -> futures::future::BoxFuture<'_, Result<(), String>> {
+        Box::pin(stop_instance(
+            &self.state,
+            None,
+            crate::modules::audit::AuditActor::AdminApi,
+        ))
+    }
+}
+
+/// 启动反代服务
+#[tauri::command]
+pub async fn start_proxy_service(
+    config: ProxyConfig,
+    state: State<'_, ProxyServiceState>,
+    app_handle: tauri::AppHandle,
+) -> Result<ProxyStatus, String> {
+    let active_accounts = start_instance(
+        &state,
+        &config,
+        Some(app_handle.clone()),
+        crate::modules::audit::AuditActor::Gui,
+    )
+    .await?;
+
+    // 保存配置到全局 AppConfig
+    let mut app_config = crate::modules::config::load_app_config().map_err(|e| e)?;
+    app_config.proxy = config.clone();
+    crate::modules::config::save_app_config(&app_config).map_err(|e| e)?;
+    if let Err(e) = ensure_control_plane(&state, &config.grpc, Some(app_handle), gui_config_loader()).await {
+        tracing::error!("启动 gRPC 管理接口失败: {}", e);
     }
     
-    let monitor = state.monitor.read().await.as_ref().unwrap().clone();
-    
+    Ok(ProxyStatus {
+        running: true,
+        port: config.port,
+        base_url: format!("http://127.0.0.1:{}", config.port),
+        active_accounts,
+        quota_resets: Vec::new(),
+        api_key_hint: crate::proxy::secrets::redact_secret(&config.api_key),
+    })
+}
+
+/// GUI 从配置文件读取反代配置
+pub fn gui_config_loader() -> ConfigLoader {
+    Arc::new(|| crate::modules::config::load_app_config().map(|config| config.proxy))
+}
+
+/// 创建反代服务实例 (加载账号并启动 Axum 服务器)，GUI 与无头模式共用
+pub async fn create_proxy_instance(
+    config: &ProxyConfig,
+    monitor: Arc<ProxyMonitor>,
+) -> Result<(ProxyServiceInstance, usize), String> {
+    create_proxy_instance_in(config, monitor, crate::modules::storage::backend()).await
+}
+
+/// 使用指定存储后端中的账号创建反代服务实例 (嵌入式 API 使用)
+pub async fn create_proxy_instance_in(
+    config: &ProxyConfig,
+    monitor: Arc<ProxyMonitor>,
+    storage: Arc<dyn crate::modules::storage::StorageBackend>,
+) -> Result<(ProxyServiceInstance, usize), String> {
     // 2. 初始化 Token 管理器
-    let app_data_dir = crate::modules::account::get_data_dir()?;
-    // Ensure accounts dir exists even if the user will only use non-Google providers (e.g. z.ai).
-    let _ = crate::modules::account::get_accounts_dir()?;
-    let accounts_dir = app_data_dir.clone();
-    
-    let token_manager = Arc::new(TokenManager::new(accounts_dir));
+    let token_manager = Arc::new(TokenManager::new(storage));
     // 同步 UI 传递的调度配置
     token_manager.update_sticky_config(config.scheduling.clone()).await;
+    token_manager
+        .update_quota_thresholds(config.quota_thresholds.clone())
+        .await;
+    token_manager.update_usage_caps(config.usage_caps.clone()).await;
+    token_manager.update_team_routing(config.team_routing.clone()).await;
+    token_manager.update_tier_policy(config.tier_policy.clone()).await;
+    token_manager.update_schedule_routing(config.schedule_routing.clone()).await;
+    token_manager.update_retry_policy(config.retry_policy.clone()).await;
+    token_manager.update_quota_cache(config.quota_cache.clone()).await;
+    token_manager
+        .update_model_capabilities(config.model_capabilities.clone())
+        .await;
+    token_manager
+        .update_context_overflow(config.context_overflow.clone())
+        .await;
+    token_manager.update_model_list(config.model_list.clone()).await;
+    crate::proxy::cluster::configure(&token_manager, &config.cluster);
     
     // 3. 加载账号
-    let active_accounts = token_manager.load_accounts().await
+    let mut active_accounts = token_manager.load_accounts().await
         .map_err(|e| format!("加载账号失败: {}", e))?;
+    if config.warmup_on_start && active_accounts > 0 {
+        let results = crate::proxy::warmup::warmup_accounts(&token_manager).await;
+        tracing::info!("账号预热完成:\n{}", crate::proxy::warmup::format_summary(&results));
+        active_accounts = token_manager.len();
+    }
+    // 后台预刷新即将过期的 token
+    token_manager.start_auto_refresh();
+    token_manager.start_quota_revalidation();
+    // 定期检查 Forbidden 账号是否已恢复
+    if config.account_recovery.enabled {
+        crate::proxy::account_recovery::start(&token_manager, monitor.clone(), config.account_recovery.interval_secs);
+    }
     
+    // 逐条检查模型映射，避免配置错误到运行时才表现为上游 404
+    let mapping_issues = crate::proxy::mapping_check::check(config, &token_manager.served_models());
+    for issue in &mapping_issues {
+        tracing::warn!("模型映射检查: {}", issue);
+    }
+    if config.strict_mappings && !mapping_issues.is_empty() {
+        return Err(format!(
+            "模型映射检查发现 {} 个问题 (已开启 strict_mappings): {}",
+            mapping_issues.len(),
+            mapping_issues.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("; ")
+        ));
+    }
+
     if active_accounts == 0 {
         let zai_enabled = config.zai.enabled
             && !matches!(config.zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Off);
@@ -89,6 +344,12 @@ pub async fn start_proxy_service(
         }
     }
     
+    // 应用上游连接池配置 (需在创建上游客户端之前)
+    crate::proxy::upstream::pool::global().configure(&config.upstream_pool);
+    crate::proxy::upstream::profiles::configure(&config.client_profiles);
+    crate::proxy::upstream::rate_limit::global().configure(&config.upstream_rate_limit);
+    crate::proxy::stream_resume::configure(&config.stream_resumption);
+
     // 启动 Axum 服务器
     let (axum_server, server_handle) =
         match crate::proxy::AxumServer::start(
@@ -98,10 +359,25 @@ pub async fn start_proxy_service(
             config.anthropic_mapping.clone(),
             config.openai_mapping.clone(),
             config.custom_mapping.clone(),
-            config.request_timeout,
+            config.mapping_rules.clone(),
+            crate::proxy::timeouts::RouteTimeouts::from_proxy_config(config),
             config.upstream_proxy.clone(),
-            crate::proxy::ProxySecurityConfig::from_proxy_config(&config),
+            crate::proxy::ProxySecurityConfig::from_proxy_config(config),
             config.zai.clone(),
+            config.client_rate_limit.clone(),
+            config.auth_lockout.clone(),
+            config.trusted_proxies.clone(),
+            config.batches.clone(),
+            config.load_shedding.clone(),
+            config.listen_tcp,
+            config.listeners.clone(),
+            config.admin_listen.clone(),
+            config.enable_debug_endpoints,
+            config.response_headers.clone(),
+            config.mirror.clone(),
+            config.compression.clone(),
+            config.request_validation.clone(),
+            config.param_policy.clone(),
             monitor.clone(),
 
         ).await {
@@ -117,20 +393,7 @@ pub async fn start_proxy_service(
         server_handle,
     };
     
-    *instance_lock = Some(instance);
-    
-
-    // 保存配置到全局 AppConfig
-    let mut app_config = crate::modules::config::load_app_config().map_err(|e| e)?;
-    app_config.proxy = config.clone();
-    crate::modules::config::save_app_config(&app_config).map_err(|e| e)?;
-    
-    Ok(ProxyStatus {
-        running: true,
-        port: config.port,
-        base_url: format!("http://127.0.0.1:{}", config.port),
-        active_accounts,
-    })
+    Ok((instance, active_accounts))
 }
 
 /// 停止反代服务
@@ -138,20 +401,7 @@ pub async fn start_proxy_service(
 pub async fn stop_proxy_service(
     state: State<'_, ProxyServiceState>,
 ) -> Result<(), String> {
-    let mut instance_lock = state.instance.write().await;
-    
-    if instance_lock.is_none() {
-        return Err("服务未运行".to_string());
-    }
-    
-    // 停止 Axum 服务器
-    if let Some(instance) = instance_lock.take() {
-        instance.axum_server.stop();
-        // 等待服务器任务完成
-        instance.server_handle.await.ok();
-    }
-    
-    Ok(())
+    stop_instance(&state, None, crate::modules::audit::AuditActor::Gui).await
 }
 
 /// 获取反代服务状态
@@ -167,12 +417,16 @@ pub async fn get_proxy_status(
             port: instance.config.port,
             base_url: format!("http://127.0.0.1:{}", instance.config.port),
             active_accounts: instance.token_manager.len(),
+            quota_resets: QuotaResetInfo::collect(&instance.token_manager),
+            api_key_hint: crate::proxy::secrets::redact_secret(&instance.config.api_key),
         }),
         None => Ok(ProxyStatus {
             running: false,
             port: 0,
             base_url: String::new(),
             active_accounts: 0,
+            quota_resets: Vec::new(),
+            api_key_hint: String::new(),
         }),
     }
 }
@@ -204,6 +458,56 @@ pub async fn get_proxy_logs(
     }
 }
 
+/// 按会话 (对话) 汇总请求数与 Token 用量
+#[tauri::command]
+pub async fn get_proxy_sessions(
+    state: State<'_, ProxyServiceState>,
+    limit: Option<usize>,
+) -> Result<Vec<SessionSummary>, String> {
+    let monitor_lock = state.monitor.read().await;
+    if let Some(monitor) = monitor_lock.as_ref() {
+        Ok(monitor.get_sessions(limit.unwrap_or(50)).await)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// 获取单个会话内的请求日志
+#[tauri::command]
+pub async fn get_proxy_session_logs(
+    state: State<'_, ProxyServiceState>,
+    session_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<ProxyRequestLog>, String> {
+    let monitor_lock = state.monitor.read().await;
+    if let Some(monitor) = monitor_lock.as_ref() {
+        Ok(monitor.get_session_logs(&session_id, limit.unwrap_or(500)).await)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// 用量报告 (最近 `since_secs` 秒，默认 7 天)，按配置的单价表估算等值费用
+#[tauri::command]
+pub async fn get_usage_report(since_secs: Option<i64>) -> Result<crate::proxy::usage_report::UsageReport, String> {
+    let since = chrono::Utc::now().timestamp_millis() - since_secs.unwrap_or(7 * 86400) * 1000;
+    let rows = crate::modules::proxy_db::get_usage_rows(since)?;
+    let pricing = crate::modules::config::load_app_config()?.proxy.pricing;
+    Ok(crate::proxy::usage_report::build(&rows, Some(&pricing), since))
+}
+
+/// 用量趋势 (按小时或按天汇总，默认最近 30 天按天)
+#[tauri::command]
+pub async fn get_usage_trend(
+    period: Option<crate::proxy::stats::Period>,
+    since_secs: Option<i64>,
+) -> Result<Vec<crate::proxy::stats::TrendPoint>, String> {
+    let period = period.unwrap_or(crate::proxy::stats::Period::Day);
+    let since = chrono::Utc::now().timestamp_millis() - since_secs.unwrap_or(30 * 86400) * 1000;
+    let rollups = crate::modules::proxy_db::get_rollups(period, since)?;
+    Ok(crate::proxy::stats::trend(&rollups))
+}
+
 /// 设置监控开启状态
 #[tauri::command]
 pub async fn set_proxy_monitor_enabled(
@@ -232,7 +536,34 @@ pub async fn clear_proxy_logs(
 /// 生成 API Key
 #[tauri::command]
 pub fn generate_api_key() -> String {
-    format!("sk-{}", uuid::Uuid::new_v4().simple())
+    crate::proxy::secrets::generate_api_key()
+}
+
+/// 轮换 API Key: 旧密钥在宽限期内继续有效，新密钥明文仅返回一次
+#[tauri::command]
+pub async fn rotate_api_key(
+    grace_secs: Option<u64>,
+    state: State<'_, ProxyServiceState>,
+) -> Result<crate::proxy::key_rotation::KeyRotation, String> {
+    let (rotation, config) = crate::proxy::key_rotation::rotate_and_save(
+        grace_secs,
+        crate::modules::audit::AuditActor::Gui,
+    )
+    .await?;
+    if let Some(instance) = state.instance.read().await.as_ref() {
+        instance.axum_server.update_security(&config).await;
+    }
+    Ok(rotation)
+}
+
+/// 导出常用客户端 (cline / continue / aider / claude-code / librechat) 的接入配置
+#[tauri::command]
+pub async fn export_client_config(
+    tool: String,
+) -> Result<crate::modules::client_config::ClientConfigExport, String> {
+    let tool: crate::modules::client_config::ClientTool = tool.parse()?;
+    let config = crate::modules::config::load_app_config()?.proxy;
+    crate::modules::client_config::export_client_config(tool, &config).await
 }
 
 /// 重新加载账号（当主应用添加/删除账号时调用）
@@ -272,6 +603,7 @@ pub async fn update_model_mapping(
     app_config.proxy.anthropic_mapping = config.anthropic_mapping;
     app_config.proxy.openai_mapping = config.openai_mapping;
     app_config.proxy.custom_mapping = config.custom_mapping;
+    app_config.proxy.mapping_rules = config.mapping_rules;
     crate::modules::config::save_app_config(&app_config).map_err(|e| e)?;
     
     Ok(())
@@ -0,0 +1,21 @@
+// 后台服务命令 (systemd / launchd / Windows 计划任务)
+use crate::modules::service::{self, ServiceInstallResult, ServiceOptions};
+
+/// 安装后台服务，使反代在登录/开机后常驻运行
+#[tauri::command]
+pub async fn install_proxy_service(
+    port: Option<u16>,
+    extra_args: Option<Vec<String>>,
+) -> Result<ServiceInstallResult, String> {
+    let options = ServiceOptions {
+        port,
+        extra_args: extra_args.unwrap_or_default(),
+    };
+    service::install_service(&options)
+}
+
+/// 卸载后台服务
+#[tauri::command]
+pub async fn uninstall_proxy_service() -> Result<(), String> {
+    service::uninstall_service()
+}
@@ -40,3 +40,92 @@ impl Serialize for AppError {
 
 // 为 Result 实现别名，简化使用
 pub type AppResult<T> = Result<T, AppError>;
+
+/// 无头模式 (命令行) 的错误分类，每类对应固定的进程退出码，便于脚本按失败类型分支处理
+///
+/// | 退出码 | 分类 |
+/// |---|---|
+/// | 1 | 一般失败 (部分账号刷新失败、重放结果与原始响应不一致等) |
+/// | 2 | 参数错误 |
+/// | 3 | 认证失败 (缺少/错误的 API Key) |
+/// | 4 | 网络错误 (无法连接反代或上游) |
+/// | 5 | 对象不存在 (账号、日志记录等) |
+/// | 6 | 配置无效 |
+/// | 7 | 冲突 (对象已存在) |
+/// | 8 | 本地存储读写失败 (数据目录、数据库) |
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("{0}")]
+    Failed(String),
+
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("{0}")]
+    Auth(String),
+
+    #[error("{0}")]
+    Network(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    ConfigInvalid(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    Storage(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Failed(_) => 1,
+            Self::Usage(_) => 2,
+            Self::Auth(_) => 3,
+            Self::Network(_) => 4,
+            Self::NotFound(_) => 5,
+            Self::ConfigInvalid(_) => 6,
+            Self::Conflict(_) => 7,
+            Self::Storage(_) => 8,
+        }
+    }
+
+    /// 稳定的英文分类名，输出在错误信息前缀中 (`error[not_found]: ...`)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Failed(_) => "failed",
+            Self::Usage(_) => "usage",
+            Self::Auth(_) => "auth",
+            Self::Network(_) => "network",
+            Self::NotFound(_) => "not_found",
+            Self::ConfigInvalid(_) => "config_invalid",
+            Self::Conflict(_) => "conflict",
+            Self::Storage(_) => "storage",
+        }
+    }
+
+    /// 按 HTTP 状态码分类管理接口返回的错误
+    pub fn from_status(status: reqwest::StatusCode, message: String) -> Self {
+        match status.as_u16() {
+            401 | 403 => Self::Auth(message),
+            404 => Self::NotFound(message),
+            400 | 422 => Self::Usage(message),
+            _ => Self::Failed(message),
+        }
+    }
+}
+
+impl From<reqwest::Error> for CliError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status() {
+            Some(status) => Self::from_status(status, e.to_string()),
+            None => Self::Network(e.to_string()),
+        }
+    }
+}
+
+pub type CliResult<T> = Result<T, CliError>;
@@ -0,0 +1,3072 @@
+// 无头模式 (不启动 Tauri 窗口)，用于容器 / 服务器部署反代服务
+//
+// 用法: antigravity_tools --headless [--config <path>] [--data-dir <path>]
+//                          [--port <port>] [--allow-lan] [--drain-timeout <secs>]
+//                          [--uds <path>]  (仅监听 Unix 套接字，不开放 TCP 端口)
+//                          [--warmup]  (启动前预热账号: 刷新 token 并验证可用性，输出汇总表)
+//                          [--strict-mappings]  (模型映射目标为空、未知或没有账号可用时拒绝启动)
+//       antigravity_tools --headless --install-service [--port <port>] [--allow-lan] [--uds <path>] [--warmup] ...
+//                          (安装后台服务 (systemd user unit / launchd / Windows 登录时运行的计划任务，并非 Windows 服务)，
+//                          以无头模式及所给参数常驻运行反代；systemd 用户服务需 loginctl enable-linger 才能在注销后继续运行)
+//       antigravity_tools --headless --uninstall-service  (卸载后台服务)
+//       antigravity_tools --headless --init  (交互式初始化: 添加首个账号、端口、API Key、局域网/TLS，并写入配置)
+//       antigravity_tools --headless --models-info <model>  (查看模型映射目标与能力: 上下文长度、视觉、工具、思考)
+//       antigravity_tools --headless --validate [--config <path>]  (校验配置，存在错误时退出码为 6)
+//       antigravity_tools --headless --doctor [--json]
+//                          (部署诊断: 端口、数据目录可写、可用账号、refresh_token 有效性、上游连通性 (直连与经上游代理)、
+//                          时钟偏差与 TLS 证书有效期，输出检查报告与修复建议；存在失败项时退出码为 1)
+//       antigravity_tools --headless --config-convert <toml|yaml|json> [--output <path>]
+//                          (转换配置文件格式；省略 --output 时写入数据目录下的 config.toml / config.yaml，
+//                          原文件重命名为 .bak。数据目录中的 config.toml / config.yaml 优先于 gui_config.json)
+//       antigravity_tools --headless --profile-list  (列出配置档，* 为当前使用的配置档)
+//       antigravity_tools --headless --profile-create <name>  (创建配置档: 独立的数据目录、账号与反代配置)
+//       antigravity_tools --headless --profile-switch <name>
+//                          (设置默认配置档，下次启动生效；--profile <name> 仅对本次启动生效。
+//                          不同配置档的 GUI 实例可以同时运行)
+//       antigravity_tools --headless --config-keys  (列出所有可设置的配置项: 点分路径、类型、当前值与默认值)
+//       antigravity_tools --headless --config-get <key>  (读取单个配置项，密钥已脱敏)
+//       antigravity_tools --headless --config-set <key> <value>  (设置单个配置项，数组/映射/可空项使用 JSON)
+//       antigravity_tools --headless --config-unset <key>  (将单个配置项恢复为默认值)
+//                          (set/unset 先校验修改后的配置再写入，并记录审计日志)
+//       antigravity_tools --headless --config-export <path> [--secrets keep|strip|encrypt]
+//                          (导出完整配置；strip 移除密钥字段，encrypt 以口令加密密钥字段)
+//       antigravity_tools --headless --export-config --tool <cline|continue|aider|claude-code|librechat>
+//                          (按当前反代配置生成客户端工具的配置并输出到标准输出，建议的保存位置输出到 stderr；
+//                          地址取主端口，未监听 TCP 端口时取第一个 TCP 附加监听器)
+//       antigravity_tools --headless --config-import <path> [--dry-run] [--yes]
+//                          (导入配置: 校验并显示与当前配置的差异，确认后写入；被移除的密钥字段保留本机现有值)
+//       antigravity_tools --headless --audit-show [--limit <n>] [--action <action>]  (查看审计日志)
+//       antigravity_tools --headless --account-list [--tier <free|pro|ultra>] [--wide]
+//                          (查看账号配额与重置倒计时，--wide 同时显示账号 ID、备注、元数据与最近一次上游错误)
+//       antigravity_tools --headless --account-login [--remote-auth]
+//                          (通过 Google 登录添加账号；--remote-auth 用于没有浏览器的服务器: 在任意设备上打开链接登录后，
+//                          粘贴浏览器地址栏中的回调地址 (完整地址、仅查询参数或授权码均可)，或用 ssh -L 转发回调端口自动完成。
+//                          Google 的设备码流程不支持 cloud-platform 权限，因此不提供设备码轮询)
+//       antigravity_tools --headless --account-import-from-ide [--db <state.vscdb>]... [--yes]
+//                          (从本机 Antigravity IDE 的本地数据库读取已登录账号的 refresh_token，确认后导入)
+//       antigravity_tools --headless --account-switch <id|email> [--apply-ide [--yes]]
+//                          (切换当前账号；默认仅影响反代，--apply-ide 同时将凭据写入 IDE (先备份数据库并关闭 IDE))
+//       antigravity_tools --headless --account-show <id|email>
+//                          (账号详情: token 到期时间、各模型配额、近 30 天用量、备注、上游代理、冷却状态与最近错误)
+//       antigravity_tools --headless --account-quota <id|email> --model <model> [--json]
+//                          (输出账号指定模型的剩余配额、模型限制与重置时间，--json 输出机器可读格式，供定时任务判断)
+//       antigravity_tools --headless --account-note <id|email> ["<备注>"] [--meta key=value]...
+//                          (设置账号备注与元数据，备注为空字符串时清除，key= 删除该元数据)
+//       antigravity_tools --headless --account-profile <id|email> [<档案名称>]
+//                          (指定账号的上游客户端标识档案，省略名称时恢复为全局默认档案)
+//       antigravity_tools --headless --account-rotate-device <id|email>
+//                          (重新生成账号的设备标识 (machine_id 与会话 ID)，反代重新加载账号后生效)
+//       antigravity_tools --headless --account-health [--json]
+//                          (Token 健康报告: access_token 到期、最近成功刷新、连续刷新失败与 refresh_token 估计年龄，
+//                           标记即将失效的账号；存在严重问题时以非零状态退出)
+//       antigravity_tools --headless --account-refresh [--only-stale <30m|3600>] [--only-forbidden]
+//                          [--account <id|email>]...  (按条件刷新账号配额，避免频繁请求配额接口)
+//       antigravity_tools --headless --account-delete <id|email>  (删除账号，移入回收站)
+//       antigravity_tools --headless --account-trash  (查看回收站)
+//       antigravity_tools --headless --account-restore <id>  (从回收站恢复账号)
+//       antigravity_tools --headless --account-purge [--older-than <30d>]  (永久删除回收站中的账号)
+//       antigravity_tools --headless --account-snapshot-save <name>  (保存账号池快照: 各账号配额与状态)
+//       antigravity_tools --headless --account-snapshot-diff <a> <b>  (对比两次快照，按账号与模型列出消耗)
+//       antigravity_tools --headless --account-snapshot-list  (查看已保存的快照)
+//       antigravity_tools --headless --logs-tail [--follow] [--filter model=gemini-2.0-pro]...
+//                          [--limit <n>] [--url http://127.0.0.1:8045]  (查看/实时跟踪请求日志)
+//       antigravity_tools --headless --logs-replay <request-id> [--account <id|email>] [--url ...]
+//                          (通过运行中的反代重放请求并与原始响应对比)
+//       antigravity_tools --headless --logs-summary [--since <24h>]
+//                          (分析持久化的请求日志: 错误突增、失败率异常的账号、按模型的延迟退化与发送格式错误请求的客户端，
+//                          判定阈值见 proxy.log_anomalies)
+//       antigravity_tools --headless --logs-export [--format jsonl|parquet] [--since <7d>] [--until <1d>]
+//                          [--output <path>] [--with-bodies]
+//                          (按时间范围批量导出请求日志，供 DuckDB / pandas 等分析；默认输出 JSON Lines 到标准输出)
+//       antigravity_tools --headless --logs-export-conversation <session-id> [--format markdown|json]
+//                          (从请求日志还原会话的完整对话: 消息、模型回复与工具调用)
+//       antigravity_tools --headless --bench [--requests <n>] [--concurrency <n>] [--model <model>] [--url ...]
+//                          (向运行中的反代发送合成请求压测，输出吞吐、延迟分位数与账号分布)
+//       antigravity_tools --headless --status [--remote http://10.0.0.2:8045]
+//                          (查询运行中实例的运行时长、在途请求、请求计数与账号池健康状况)
+//       antigravity_tools --headless --proxy-active [--cancel <request-id>] [--remote http://10.0.0.2:8045]
+//                          (列出运行中实例的在途请求: 模型、账号、持续时间与客户端密钥；--cancel 取消卡住的请求)
+//       antigravity_tools --headless --models-stats [--remote http://10.0.0.2:8045] [--json]
+//                          (运行中实例最近 15 分钟各上游模型的延迟分位数、成功率与吞吐，用于比较映射目标的表现)
+//       antigravity_tools --headless --log-filter [<规则>|reset] [--duration <10m>] [--remote http://10.0.0.2:8045]
+//                          (查看或调整运行中实例的日志过滤规则，如 proxy::mappers=debug，附加在默认规则之上，
+//                          --duration 到期后自动恢复；省略规则时显示当前规则，reset 立即恢复默认)
+//       antigravity_tools --headless --usage-report [--since <7d>] [--costs]
+//                          (按模型/账号/API Key 汇总 Token 用量，--costs 按 proxy.pricing 估算等值费用)
+//       antigravity_tools --headless --usage-trend [--since <30d>] [--hourly]
+//                          (按天 / 小时输出请求数、错误数与 Token 用量趋势，读取持久化的统计汇总)
+//       antigravity_tools --headless --hash-api-key  (生成新的 API Key 并哈希存储，明文仅显示一次)
+//       antigravity_tools --headless --create-api-key <name> [--scope chat-only|no-embeddings|no-admin|read-only-stats|pin-account]...
+//                          [--key <value> [--force]]
+//                          (创建带权限范围的附加 API Key，明文仅显示一次；默认随机生成 agk- 前缀的密钥，
+//                          --key 使用自定义密钥，强度不足时拒绝，除非指定 --force)
+//       antigravity_tools --headless --rotate-api-key [--grace <secs>]
+//                          (轮换 API Key，旧密钥在宽限期内继续有效；运行中的实例可调用 POST /admin/keys/rotate)
+//
+// API Key 哈希存储时，日志查看/重放等需通过环境变量 ANTIGRAVITY_API_KEY 提供明文密钥
+// 加密导出 / 导入的口令可通过环境变量 ANTIGRAVITY_CONFIG_PASSPHRASE 提供，未设置时交互式输入
+//
+// 输出语言: --lang <zh|en> (或环境变量 ANTIGRAVITY_LANG)，默认跟随配置中的界面语言
+//
+// 每次最多指定一个操作参数 (如 --account-list 与 --config-get 不能同时使用)，否则以参数错误退出
+//
+// 退出码: 0 成功，1 一般失败，2 参数错误，3 认证失败，4 网络错误，5 对象不存在，6 配置无效，
+//         7 冲突 (对象已存在)，8 本地存储读写失败；错误信息以 `error[<分类>]:` 开头输出到 stderr
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::error::{CliError, CliResult};
+use crate::modules;
+use crate::modules::i18n::cli_text as t;
+
+/// 收到停止信号后等待在途请求完成的默认时长
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+
+/// 账号详情中统计用量的时长
+const ACCOUNT_USAGE_WINDOW_SECS: i64 = 30 * 86400;
+/// 账号详情中显示的最近错误条数
+const ACCOUNT_RECENT_ERRORS: usize = 10;
+/// 导出会话记录时最多读取的请求数
+const MAX_EXPORT_REQUESTS: usize = 10_000;
+/// 日志异常分析最多读取的请求数
+const MAX_SUMMARY_REQUESTS: usize = 500_000;
+/// 互斥的操作参数，每次最多指定一个 (其余参数为启动选项或操作的修饰项)
+const ACTION_FLAGS: &[&str] = &[
+    "--validate",
+    "--doctor",
+    "--init",
+    "--install-service",
+    "--uninstall-service",
+    "--models-info",
+    "--config-convert",
+    "--config-export",
+    "--config-import",
+    "--config-keys",
+    "--config-get",
+    "--config-set",
+    "--config-unset",
+    "--export-config",
+    "--profile-list",
+    "--profile-create",
+    "--profile-switch",
+    "--audit-show",
+    "--account-list",
+    "--account-login",
+    "--account-import-from-ide",
+    "--account-switch",
+    "--account-show",
+    "--account-quota",
+    "--account-note",
+    "--account-profile",
+    "--account-rotate-device",
+    "--account-health",
+    "--account-refresh",
+    "--account-delete",
+    "--account-trash",
+    "--account-restore",
+    "--account-purge",
+    "--account-snapshot-save",
+    "--account-snapshot-diff",
+    "--account-snapshot-list",
+    "--logs-tail",
+    "--logs-replay",
+    "--logs-summary",
+    "--logs-export",
+    "--logs-export-conversation",
+    "--bench",
+    "--status",
+    "--proxy-active",
+    "--models-stats",
+    "--log-filter",
+    "--usage-report",
+    "--usage-trend",
+    "--hash-api-key",
+    "--create-api-key",
+    "--rotate-api-key",
+];
+
+/// 无头模式启动参数
+#[derive(Debug)]
+struct HeadlessOptions {
+    /// 配置文件路径 (按扩展名识别 JSON / TOML / YAML)，默认为数据目录下的 config.toml、config.yaml 或 gui_config.json
+    config_path: Option<PathBuf>,
+    /// 数据根目录 (账号、日志、数据库)，默认为 ~/.antigravity_tools
+    data_dir: Option<PathBuf>,
+    /// 覆盖配置中的监听端口
+    port: Option<u16>,
+    /// 监听 0.0.0.0 (容器内必须开启才能从外部访问)
+    allow_lan: bool,
+    /// 仅监听该 Unix 套接字
+    uds: Option<String>,
+    /// 启动时预热账号
+    warmup: bool,
+    /// 模型映射检查有问题时拒绝启动
+    strict_mappings: bool,
+    /// 排空超时
+    drain_timeout: Duration,
+    /// 仅校验配置后退出
+    validate_only: bool,
+    /// 运行部署诊断后退出: 是否输出 JSON
+    doctor: Option<bool>,
+    /// 运行交互式初始化向导后退出
+    init: bool,
+    /// 输出审计日志后退出: (条数, 动作过滤)
+    audit_show: Option<(usize, Option<String>)>,
+    /// 查看请求日志
+    logs_tail: Option<LogsTailOptions>,
+    /// 输出账号配额与重置倒计时后退出
+    account_list: bool,
+    /// 账号列表显示 ID、备注与元数据
+    account_wide: bool,
+    /// 通过 Google 登录添加账号: 是否使用远程授权 (在其他设备上完成登录)
+    account_login: Option<bool>,
+    /// 从 IDE 本地数据库导入账号: (指定的数据库路径，为空时自动查找; 是否跳过确认)
+    account_import_ide: Option<(Vec<PathBuf>, bool)>,
+    /// 切换当前账号: (ID 或邮箱, 是否写入 IDE, 是否跳过确认)
+    account_switch: Option<(String, bool, bool)>,
+    /// 输出单个账号详情后退出 (ID 或邮箱)
+    account_show: Option<String>,
+    /// 输出账号指定模型的配额后退出: (ID 或邮箱, 模型, 是否输出 JSON)
+    account_quota: Option<(String, String, bool)>,
+    /// 更新账号备注后退出: (ID 或邮箱, 备注, 元数据修改 (值为空表示删除))
+    account_note: Option<(String, Option<String>, Vec<(String, Option<String>)>)>,
+    /// 设置账号客户端标识档案后退出: (ID 或邮箱, 档案名称 (None 恢复默认))
+    account_profile: Option<(String, Option<String>)>,
+    /// 轮换账号设备标识后退出 (ID 或邮箱)
+    account_rotate_device: Option<String>,
+    /// 输出账号 Token 健康报告后退出 (是否输出 JSON)
+    account_health: Option<bool>,
+    /// 输出模型能力后退出
+    models_info: Option<String>,
+    /// 账号列表仅显示指定订阅等级
+    account_tier: Option<crate::models::SubscriptionTier>,
+    /// 重放请求: (日志 ID, 指定账号, 反代地址)
+    logs_replay: Option<(String, Option<String>, Option<String>)>,
+    /// 导出会话记录: (会话 ID, 是否输出 JSON)
+    logs_export: Option<(String, bool)>,
+    /// 日志异常分析: 统计时长 (秒)
+    logs_summary: Option<i64>,
+    /// 批量导出请求日志
+    logs_export_bulk: Option<LogsExportArgs>,
+    /// 生成哈希存储的新 API Key 后退出
+    hash_api_key: bool,
+    /// 安装后台服务后退出 (服务沿用本次的启动参数)
+    install_service: bool,
+    /// 卸载后台服务后退出
+    uninstall_service: bool,
+    /// 创建附加 API Key 后退出
+    create_api_key: Option<CreateKeyArgs>,
+    /// 轮换 API Key 后退出: 旧密钥宽限期 (秒，缺省使用配置值)
+    rotate_api_key: Option<Option<u64>>,
+    /// 按条件刷新账号配额后退出
+    account_refresh: Option<modules::account::RefreshFilter>,
+    /// 回收站操作
+    account_trash: Option<TrashCommand>,
+    /// 账号池快照操作
+    account_snapshot: Option<SnapshotCommand>,
+    /// 压测
+    bench: Option<BenchArgs>,
+    /// 用量报告: (统计时长 秒, 是否估算费用)
+    usage_report: Option<(i64, bool)>,
+    /// 用量趋势: (统计时长 秒, 汇总粒度)
+    usage_trend: Option<(i64, crate::proxy::stats::Period)>,
+    /// 查询运行中实例的状态: 实例地址，缺省为本机配置端口
+    status: Option<Option<String>>,
+    /// 查询 / 取消在途请求: (实例地址, 要取消的请求 ID)
+    proxy_active: Option<(Option<String>, Option<String>)>,
+    /// 查询运行中实例的模型统计: (实例地址, 是否输出 JSON)
+    models_stats: Option<(Option<String>, bool)>,
+    /// 调整运行中实例的日志过滤规则后退出: (操作, 远程实例地址)
+    log_filter: Option<(LogFilterCommand, Option<String>)>,
+    /// 转换配置文件格式后退出: (目标格式, 输出路径)
+    config_convert: Option<(modules::config::ConfigFormat, Option<PathBuf>)>,
+    /// 导出配置后退出: (输出路径, 密钥处理方式)
+    config_export: Option<(PathBuf, modules::config_export::SecretMode)>,
+    /// 导入配置后退出: (文件路径, 仅预览, 跳过确认)
+    config_import: Option<(PathBuf, bool, bool)>,
+    /// 查看 / 修改单个配置项后退出
+    config_value: Option<ConfigValueCommand>,
+    /// 管理配置档后退出
+    profile_command: Option<ProfileCommand>,
+    /// 输出客户端工具配置后退出
+    export_client: Option<modules::client_config::ClientTool>,
+}
+
+#[derive(Debug)]
+struct BenchArgs {
+    requests: usize,
+    concurrency: usize,
+    model: String,
+    url: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum LogFilterCommand {
+    Show,
+    /// 附加规则与自动恢复前的秒数
+    Set(String, Option<i64>),
+    Reset,
+}
+
+#[derive(Debug)]
+enum TrashCommand {
+    /// 删除账号 (移入回收站)
+    Delete(String),
+    List,
+    Restore(String),
+    /// 永久删除: 仅删除超过该时长 (秒) 的账号，为空时清空回收站
+    Purge(Option<i64>),
+}
+
+#[derive(Debug)]
+enum ConfigValueCommand {
+    Keys,
+    Get(String),
+    /// (配置项, 值)
+    Set(String, String),
+    Unset(String),
+}
+
+#[derive(Debug)]
+enum ProfileCommand {
+    List,
+    Create(String),
+    /// 设置默认配置档 (下次启动生效)
+    Switch(String),
+}
+
+#[derive(Debug)]
+enum SnapshotCommand {
+    Save(String),
+    /// 对比两次快照: (之前, 之后)
+    Diff(String, String),
+    List,
+}
+
+#[derive(Debug)]
+struct CreateKeyArgs {
+    name: String,
+    scopes: Vec<crate::proxy::config::KeyScope>,
+    /// 自定义密钥，缺省随机生成
+    key: Option<String>,
+    /// 接受强度不足的自定义密钥
+    force: bool,
+}
+
+#[derive(Debug)]
+struct LogsExportArgs {
+    format: crate::proxy::log_export::ExportFormat,
+    /// 起始时间: 距今秒数，缺省导出全部历史
+    since: Option<i64>,
+    /// 截止时间: 距今秒数，缺省到当前
+    until: Option<i64>,
+    /// 输出文件，缺省为标准输出
+    output: Option<PathBuf>,
+    /// 是否包含请求 / 响应体
+    with_bodies: bool,
+}
+
+#[derive(Debug)]
+struct LogsTailOptions {
+    /// 连接运行中的反代持续输出新日志
+    follow: bool,
+    /// `key=value` 过滤条件
+    filters: Vec<String>,
+    /// 先输出的历史条数
+    lines: usize,
+    /// 反代地址，默认取配置中的端口
+    url: Option<String>,
+}
+
+/// 是否以无头模式启动
+pub fn is_headless(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "--headless")
+}
+
+/// 读取参数值，支持 `--flag value` 与 `--flag=value`
+fn take_value<'a, I: Iterator<Item = &'a String>>(
+    flag: &str,
+    inline: Option<&'a str>,
+    iter: &mut I,
+) -> Result<&'a str, String> {
+    inline
+        .or_else(|| iter.next().map(|s| s.as_str()))
+        .ok_or_else(|| t("missing_value", &[("flag", &flag)]))
+}
+
+/// `--lang <zh|en>` 的取值 (需在解析其他参数之前确定，以便参数错误也按该语言输出)
+fn lang_arg(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(value) = arg.strip_prefix("--lang=") {
+            return Some(value.to_string());
+        }
+        if arg == "--lang" {
+            return iter.next().cloned();
+        }
+    }
+    None
+}
+
+fn parse_args(args: &[String]) -> Result<HeadlessOptions, String> {
+    let mut options = HeadlessOptions {
+        config_path: std::env::var_os("ANTIGRAVITY_CONFIG").map(PathBuf::from),
+        data_dir: std::env::var_os("ANTIGRAVITY_DATA_DIR").map(PathBuf::from),
+        port: None,
+        allow_lan: false,
+        uds: None,
+        warmup: false,
+        strict_mappings: false,
+        drain_timeout: Duration::from_secs(DEFAULT_DRAIN_TIMEOUT_SECS),
+        validate_only: false,
+        doctor: None,
+        init: false,
+        audit_show: None,
+        logs_tail: None,
+        account_list: false,
+        account_wide: false,
+        account_note: None,
+        account_rotate_device: None,
+        account_health: None,
+        account_profile: None,
+        account_show: None,
+        account_quota: None,
+        account_login: None,
+        account_import_ide: None,
+        account_switch: None,
+        account_tier: None,
+        models_info: None,
+        logs_replay: None,
+        logs_export: None,
+        logs_summary: None,
+        logs_export_bulk: None,
+        hash_api_key: false,
+        install_service: false,
+        uninstall_service: false,
+        create_api_key: None,
+        rotate_api_key: None,
+        account_refresh: None,
+        account_trash: None,
+        account_snapshot: None,
+        bench: None,
+        usage_report: None,
+        usage_trend: None,
+        status: None,
+        proxy_active: None,
+        models_stats: None,
+        log_filter: None,
+        config_convert: None,
+        config_export: None,
+        config_import: None,
+        config_value: None,
+        profile_command: None,
+        export_client: None,
+    };
+    let mut limit = None;
+    let mut audit_action = None;
+    let mut audit_show = false;
+    let mut logs_tail = false;
+    let mut follow = false;
+    let mut filters = Vec::new();
+    let mut url = None;
+    let mut replay_id = None;
+    let mut export_session = None;
+    let mut format = None;
+    let mut logs_export = false;
+    let mut until = None;
+    let mut output = None;
+    let mut with_bodies = false;
+    let mut key_name = None;
+    let mut scopes = Vec::new();
+    let mut custom_key = None;
+    let mut force = false;
+    let mut accounts = Vec::new();
+    let mut switch_target = None;
+    let mut apply_ide = false;
+    let mut import_ide = false;
+    let mut account_login = false;
+    let mut remote_auth = false;
+    let mut db_paths = Vec::new();
+    let mut assume_yes = false;
+    let mut note_target = None;
+    let mut metadata = Vec::new();
+    let mut rotate = false;
+    let mut refresh = false;
+    let mut refresh_filter = modules::account::RefreshFilter::default();
+    let mut grace = None;
+    let mut purge = false;
+    let mut bench = false;
+    let mut usage_report = false;
+    let mut usage_trend = false;
+    let mut hourly = false;
+    let mut status = false;
+    let mut proxy_active = false;
+    let mut models_stats = false;
+    let mut log_filter = None;
+    let mut log_filter_duration = None;
+    let mut secret_mode = None;
+    let mut doctor = false;
+    let mut account_health = false;
+    let mut dry_run = false;
+    let mut cancel_id = None;
+    let mut costs = false;
+    let mut logs_summary = false;
+    let mut since = None;
+    let mut bench_requests = 100;
+    let mut bench_concurrency = 10;
+    let mut model = None;
+    let mut quota_target = None;
+    let mut json = false;
+    let mut older_than = None;
+    let mut export_client = false;
+    let mut client_tool = None;
+    let mut actions: Vec<&str> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        if ACTION_FLAGS.contains(&flag) && !actions.contains(&flag) {
+            actions.push(flag);
+        }
+
+        match flag {
+            "--config" => {
+                options.config_path = Some(PathBuf::from(take_value(flag, inline, &mut iter)?));
+            }
+            "--data-dir" => {
+                options.data_dir = Some(PathBuf::from(take_value(flag, inline, &mut iter)?));
+            }
+            "--port" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                options.port = Some(
+                    value
+                        .parse()
+                        .map_err(|_| t("invalid_port", &[("value", &value)]))?,
+                );
+            }
+            "--drain-timeout" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                let secs: u64 = value
+                    .parse()
+                    .map_err(|_| t("invalid_drain_timeout", &[("value", &value)]))?;
+                options.drain_timeout = Duration::from_secs(secs);
+            }
+            "--allow-lan" => options.allow_lan = true,
+            "--warmup" => options.warmup = true,
+            "--strict-mappings" => options.strict_mappings = true,
+            "--uds" => options.uds = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--validate" => options.validate_only = true,
+            "--doctor" => doctor = true,
+            "--config-convert" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                let format = modules::config::ConfigFormat::parse(value)
+                    .ok_or_else(|| t("invalid_config_format", &[("value", &value)]))?;
+                options.config_convert = Some((format, None));
+            }
+            "--config-export" => {
+                let path = PathBuf::from(take_value(flag, inline, &mut iter)?);
+                options.config_export = Some((path, modules::config_export::SecretMode::Plain));
+            }
+            "--secrets" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                secret_mode = Some(
+                    modules::config_export::SecretMode::parse(value)
+                        .ok_or_else(|| t("invalid_secret_mode", &[("value", &value)]))?,
+                );
+            }
+            "--profile-list" => options.profile_command = Some(ProfileCommand::List),
+            "--profile-create" => {
+                options.profile_command = Some(ProfileCommand::Create(take_value(flag, inline, &mut iter)?.to_string()))
+            }
+            "--profile-switch" => {
+                options.profile_command = Some(ProfileCommand::Switch(take_value(flag, inline, &mut iter)?.to_string()))
+            }
+            "--config-keys" => options.config_value = Some(ConfigValueCommand::Keys),
+            "--config-get" => {
+                options.config_value = Some(ConfigValueCommand::Get(take_value(flag, inline, &mut iter)?.to_string()))
+            }
+            "--config-set" => {
+                let key = take_value(flag, inline, &mut iter)?.to_string();
+                let value = iter
+                    .next()
+                    .ok_or_else(|| t("missing_value", &[("flag", &flag)]))?
+                    .to_string();
+                options.config_value = Some(ConfigValueCommand::Set(key, value));
+            }
+            "--config-unset" => {
+                options.config_value = Some(ConfigValueCommand::Unset(take_value(flag, inline, &mut iter)?.to_string()))
+            }
+            "--export-config" => export_client = true,
+            "--tool" => client_tool = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--config-import" => {
+                options.config_import = Some((PathBuf::from(take_value(flag, inline, &mut iter)?), false, false));
+            }
+            "--dry-run" => dry_run = true,
+            "--init" => options.init = true,
+            "--audit-show" => audit_show = true,
+            "--account-list" => options.account_list = true,
+            "--wide" => options.account_wide = true,
+            "--account-switch" => switch_target = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--apply-ide" => apply_ide = true,
+            "--account-import-from-ide" => import_ide = true,
+            "--account-login" => account_login = true,
+            "--remote-auth" => remote_auth = true,
+            "--db" => db_paths.push(PathBuf::from(take_value(flag, inline, &mut iter)?)),
+            "--yes" | "-y" => assume_yes = true,
+            "--account-show" => options.account_show = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--account-quota" => quota_target = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--json" => json = true,
+            "--account-profile" => {
+                let target = take_value(flag, inline, &mut iter)?.to_string();
+                let profile = match iter.clone().next() {
+                    Some(next) if !next.starts_with("--") => iter.next().cloned(),
+                    _ => None,
+                };
+                options.account_profile = Some((target, profile));
+            }
+            "--account-rotate-device" => {
+                options.account_rotate_device = Some(take_value(flag, inline, &mut iter)?.to_string())
+            }
+            "--account-health" => account_health = true,
+            "--account-note" => {
+                let target = take_value(flag, inline, &mut iter)?.to_string();
+                // 备注为可选的下一个参数 (省略时仅修改元数据)
+                let note = match iter.clone().next() {
+                    Some(next) if !next.starts_with("--") => iter.next().cloned(),
+                    _ => None,
+                };
+                note_target = Some((target, note));
+            }
+            "--meta" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                let (key, val) = value
+                    .split_once('=')
+                    .filter(|(key, _)| !key.trim().is_empty())
+                    .ok_or_else(|| t("invalid_metadata", &[("value", &value)]))?;
+                metadata.push((key.trim().to_string(), (!val.is_empty()).then(|| val.to_string())));
+            }
+            "--models-info" => options.models_info = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--tier" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                options.account_tier = Some(crate::models::SubscriptionTier::parse(value));
+            }
+            "--hash-api-key" => options.hash_api_key = true,
+            "--install-service" => options.install_service = true,
+            "--uninstall-service" => options.uninstall_service = true,
+            "--rotate-api-key" => rotate = true,
+            "--create-api-key" => key_name = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--key" => custom_key = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--force" => force = true,
+            "--scope" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                let scope = crate::proxy::config::KeyScope::parse(value)
+                    .ok_or_else(|| t("invalid_scope", &[("value", &value)]))?;
+                if !scopes.contains(&scope) {
+                    scopes.push(scope);
+                }
+            }
+            "--grace" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                grace = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| t("invalid_grace", &[("value", &value)]))?,
+                );
+            }
+            "--limit" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                limit = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| t("invalid_limit", &[("value", &value)]))?,
+                );
+            }
+            "--logs-tail" => logs_tail = true,
+            "--logs-summary" => logs_summary = true,
+            "--follow" | "-f" => follow = true,
+            "--filter" => filters.push(take_value(flag, inline, &mut iter)?.to_string()),
+            "--url" | "--remote" => url = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--status" => status = true,
+            "--proxy-active" => proxy_active = true,
+            "--models-stats" => models_stats = true,
+            "--log-filter" => {
+                let rules = match inline {
+                    Some(value) => Some(value.to_string()),
+                    None => match iter.clone().next() {
+                        Some(next) if !next.starts_with("--") => iter.next().cloned(),
+                        _ => None,
+                    },
+                };
+                log_filter = Some(rules);
+            }
+            "--duration" => log_filter_duration = Some(parse_age_secs(take_value(flag, inline, &mut iter)?)?),
+            "--cancel" => cancel_id = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--logs-replay" => replay_id = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--logs-export-conversation" => {
+                export_session = Some(take_value(flag, inline, &mut iter)?.to_string());
+            }
+            "--logs-export" => logs_export = true,
+            "--format" => format = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--until" => until = Some(parse_age_secs(take_value(flag, inline, &mut iter)?)?),
+            "--output" | "-o" => output = Some(PathBuf::from(take_value(flag, inline, &mut iter)?)),
+            "--with-bodies" => with_bodies = true,
+            "--account" => accounts.push(take_value(flag, inline, &mut iter)?.to_string()),
+            "--account-refresh" => refresh = true,
+            "--account-delete" => {
+                options.account_trash = Some(TrashCommand::Delete(take_value(flag, inline, &mut iter)?.to_string()));
+            }
+            "--account-trash" => options.account_trash = Some(TrashCommand::List),
+            "--account-restore" => {
+                options.account_trash = Some(TrashCommand::Restore(take_value(flag, inline, &mut iter)?.to_string()));
+            }
+            "--account-purge" => purge = true,
+            "--account-snapshot-save" => {
+                options.account_snapshot = Some(SnapshotCommand::Save(take_value(flag, inline, &mut iter)?.to_string()));
+            }
+            "--account-snapshot-diff" => {
+                let from = take_value(flag, inline, &mut iter)?.to_string();
+                let to = take_value(flag, None, &mut iter)?.to_string();
+                options.account_snapshot = Some(SnapshotCommand::Diff(from, to));
+            }
+            "--account-snapshot-list" => options.account_snapshot = Some(SnapshotCommand::List),
+            "--bench" => bench = true,
+            "--usage-report" => usage_report = true,
+            "--usage-trend" => usage_trend = true,
+            "--hourly" => hourly = true,
+            "--costs" => costs = true,
+            "--since" => since = Some(parse_age_secs(take_value(flag, inline, &mut iter)?)?),
+            "--requests" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                bench_requests = value
+                    .parse()
+                    .map_err(|_| t("invalid_limit", &[("value", &value)]))?;
+            }
+            "--concurrency" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                bench_concurrency = value
+                    .parse()
+                    .map_err(|_| t("invalid_limit", &[("value", &value)]))?;
+            }
+            "--model" => model = Some(take_value(flag, inline, &mut iter)?.to_string()),
+            "--older-than" => older_than = Some(parse_age_secs(take_value(flag, inline, &mut iter)?)?),
+            "--only-forbidden" => refresh_filter.only_forbidden = true,
+            "--only-stale" => {
+                let value = take_value(flag, inline, &mut iter)?;
+                refresh_filter.stale_after_secs = Some(parse_age_secs(value)?);
+            }
+            "--action" => {
+                audit_action = Some(take_value(flag, inline, &mut iter)?.to_string());
+            }
+            // 已由 lang_arg 提前解析
+            "--lang" => {
+                take_value(flag, inline, &mut iter)?;
+            }
+            // --headless 与 --profile 由 lib::run 统一解析，跳过其取值
+            "--headless" => {}
+            "--profile" => {
+                if inline.is_none() {
+                    iter.next();
+                }
+            }
+            // 拼写错误的参数不能落到默认的启动反代
+            _ if flag.starts_with('-') => return Err(t("unknown_flag", &[("flag", &flag)])),
+            _ => return Err(t("unexpected_argument", &[("value", &arg)])),
+        }
+    }
+
+    // 多个操作不能按判断顺序静默只执行其中一个
+    if actions.len() > 1 {
+        return Err(t("conflicting_actions", &[("flags", &actions.join(", "))]));
+    }
+    if rotate {
+        options.rotate_api_key = Some(grace);
+    }
+    if audit_show {
+        options.audit_show = Some((limit.unwrap_or(50), audit_action));
+    }
+    if let Some(id) = replay_id {
+        options.logs_replay = Some((id, accounts.last().cloned(), url.clone()));
+    }
+    if let Some(name) = key_name {
+        options.create_api_key = Some(CreateKeyArgs {
+            name,
+            scopes,
+            key: custom_key,
+            force,
+        });
+    }
+    if let Some(target) = switch_target {
+        options.account_switch = Some((target, apply_ide, assume_yes));
+    }
+    if import_ide {
+        options.account_import_ide = Some((db_paths, assume_yes));
+    }
+    if account_login {
+        options.account_login = Some(remote_auth);
+    }
+    if let Some((target, note)) = note_target {
+        options.account_note = Some((target, note, metadata));
+    }
+    if doctor {
+        options.doctor = Some(json);
+    }
+    if export_client {
+        let tool = client_tool.ok_or_else(|| t("missing_flag", &[("flag", &"--tool")]))?;
+        options.export_client = Some(tool.parse()?);
+    }
+    if account_health {
+        options.account_health = Some(json);
+    }
+    if let Some((_, path)) = options.config_convert.as_mut() {
+        *path = output.clone();
+    }
+    if let Some(session) = export_session {
+        let export_json = match format.as_deref() {
+            Some("json") => true,
+            None | Some("markdown") | Some("md") => false,
+            Some(value) => return Err(t("invalid_format", &[("value", &value)])),
+        };
+        options.logs_export = Some((session, export_json));
+    } else if logs_export {
+        let format = match format.as_deref() {
+            None => crate::proxy::log_export::ExportFormat::Jsonl,
+            Some(value) => crate::proxy::log_export::ExportFormat::parse(value)
+                .ok_or_else(|| t("invalid_export_format", &[("value", &value)]))?,
+        };
+        options.logs_export_bulk = Some(LogsExportArgs {
+            format,
+            since,
+            until,
+            output,
+            with_bodies,
+        });
+    }
+    if let Some(target) = quota_target {
+        let model = model.clone().ok_or_else(|| t("missing_flag", &[("flag", &"--model")]))?;
+        options.account_quota = Some((target, model, json));
+    }
+    if status {
+        options.status = Some(url.clone());
+    }
+    if let (Some((_, mode)), Some(secrets)) = (options.config_export.as_mut(), secret_mode) {
+        *mode = secrets;
+    }
+    if let Some((_, preview, yes)) = options.config_import.as_mut() {
+        *preview = dry_run;
+        *yes = assume_yes;
+    }
+    if let Some(rules) = log_filter {
+        let command = match rules.as_deref() {
+            None => LogFilterCommand::Show,
+            Some("reset") => LogFilterCommand::Reset,
+            Some(rules) => LogFilterCommand::Set(rules.to_string(), log_filter_duration),
+        };
+        options.log_filter = Some((command, url.clone()));
+    }
+    if proxy_active {
+        options.proxy_active = Some((url.clone(), cancel_id));
+    }
+    if models_stats {
+        options.models_stats = Some((url.clone(), json));
+    }
+    if usage_report {
+        options.usage_report = Some((since.unwrap_or(7 * 86400), costs));
+    }
+    if usage_trend {
+        let period = if hourly {
+            crate::proxy::stats::Period::Hour
+        } else {
+            crate::proxy::stats::Period::Day
+        };
+        options.usage_trend = Some((since.unwrap_or(7 * 86400), period));
+    }
+    if logs_summary {
+        options.logs_summary = Some(since.unwrap_or(86400));
+    }
+    if bench {
+        options.bench = Some(BenchArgs {
+            requests: bench_requests,
+            concurrency: bench_concurrency,
+            model: model.clone().unwrap_or_else(|| "gemini-2.5-flash".to_string()),
+            url: url.clone(),
+        });
+    }
+    if purge {
+        options.account_trash = Some(TrashCommand::Purge(older_than));
+    }
+    if refresh {
+        refresh_filter.accounts = accounts;
+        options.account_refresh = Some(refresh_filter);
+    }
+    if logs_tail {
+        options.logs_tail = Some(LogsTailOptions {
+            follow,
+            filters,
+            lines: limit.unwrap_or(20),
+            url,
+        });
+    }
+    Ok(options)
+}
+
+/// 批量导出请求日志: 一次查询逐行读出，边读边写
+fn logs_export(args: &LogsExportArgs) -> CliResult<()> {
+    use crate::proxy::log_export::LogWriter;
+    use std::io::Write;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let since = args.since.map(|secs| now - secs * 1000).unwrap_or(0);
+    let until = args.until.map(|secs| now - secs * 1000).unwrap_or(i64::MAX);
+    modules::proxy_db::init_db().map_err(CliError::Storage)?;
+
+    let out: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .map_err(|e| CliError::Failed(t("export_write_failed", &[("path", &path.display()), ("error", &e)])))?;
+            Box::new(std::io::BufWriter::new(file))
+        }
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+    let mut writer = LogWriter::new(args.format, out).map_err(CliError::Failed)?;
+    let count = modules::proxy_db::for_each_log(since, until, args.with_bodies, |log| writer.write(&log))
+        .map_err(CliError::Storage)?;
+    writer.finish().map_err(CliError::Failed)?;
+
+    if let Some(path) = &args.output {
+        eprintln!("{}", t("logs_exported", &[("count", &count), ("path", &path.display())]));
+    }
+    Ok(())
+}
+
+/// 解析时长: 纯数字为秒，也支持 `30m`、`2h`、`1h30m`、`30d`
+fn parse_age_secs(value: &str) -> Result<i64, String> {
+    let days = value
+        .strip_suffix('d')
+        .and_then(|d| d.parse::<i64>().ok())
+        .map(|d| d * 86400);
+    value
+        .parse::<i64>()
+        .ok()
+        .or(days)
+        .or_else(|| crate::proxy::upstream::retry::parse_duration_ms(value).map(|ms| (ms / 1000) as i64))
+        .filter(|secs| *secs >= 0)
+        .ok_or_else(|| t("invalid_duration", &[("value", &value)]))
+}
+
+/// 无头模式入口，返回进程退出码 (见 `crate::error::CliError`)
+pub fn run(args: &[String]) -> i32 {
+    match execute(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error[{}]: {}", e.kind(), e);
+            e.exit_code()
+        }
+    }
+}
+
+fn execute(args: &[String]) -> CliResult<()> {
+    // 输出语言: --lang > 环境变量 ANTIGRAVITY_LANG > 配置中的 language
+    let explicit_lang = lang_arg(args).or_else(|| std::env::var("ANTIGRAVITY_LANG").ok());
+    if let Some(lang) = &explicit_lang {
+        modules::i18n::set_cli_language(lang);
+    }
+
+    let options = parse_args(args).map_err(CliError::Usage)?;
+
+    if let Some(dir) = options.data_dir.clone() {
+        modules::account::set_data_dir_override(dir).map_err(CliError::Usage)?;
+    }
+    if let Some(path) = options.config_path.clone() {
+        modules::config::set_config_path_override(path).map_err(CliError::Usage)?;
+    }
+    if explicit_lang.is_none() {
+        if let Ok(config) = modules::config::load_app_config() {
+            modules::i18n::set_cli_language(&config.language);
+        }
+    }
+
+    if let Some((limit, action)) = &options.audit_show {
+        let entries = modules::audit::read_entries(*limit, action.as_deref()).map_err(CliError::Storage)?;
+        print!("{}", modules::audit::format_entries(&entries));
+        return Ok(());
+    }
+
+    if options.install_service {
+        return install_service(&options);
+    }
+
+    if options.uninstall_service {
+        modules::service::uninstall_service().map_err(CliError::Failed)?;
+        println!("{}", t("service_uninstalled", &[]));
+        return Ok(());
+    }
+
+    if options.hash_api_key {
+        let key = create_hashed_api_key().map_err(CliError::ConfigInvalid)?;
+        println!("{}\n{}", t("new_api_key", &[]), key);
+        return Ok(());
+    }
+
+    if let Some(args) = &options.create_api_key {
+        let key = create_scoped_api_key(args)?;
+        println!("{}\n{}", t("new_api_key", &[]), key);
+        return Ok(());
+    }
+
+    if options.account_list {
+        let mut accounts = modules::account::list_accounts().map_err(CliError::Storage)?;
+        if let Some(tier) = &options.account_tier {
+            accounts.retain(|a| a.quota.as_ref().and_then(|q| q.subscription_tier.as_ref()) == Some(tier));
+        }
+        print!("{}", format_account_list(&accounts, chrono::Utc::now().timestamp(), options.account_wide));
+        return Ok(());
+    }
+
+    if let Some((target, model, json)) = &options.account_quota {
+        return account_quota(target, model, *json);
+    }
+
+    if let Some((target, note, metadata)) = &options.account_note {
+        return account_note(target, note.as_deref(), metadata);
+    }
+
+    if let Some((target, profile)) = &options.account_profile {
+        let config = load_config(&options).map_err(CliError::ConfigInvalid)?.proxy;
+        return account_profile(target, profile.as_deref(), &config.client_profiles);
+    }
+
+    if let Some(target) = &options.account_rotate_device {
+        return account_rotate_device(target);
+    }
+
+    if let Some(json) = options.account_health {
+        return account_health(json);
+    }
+
+    if let Some(model) = &options.models_info {
+        let config = load_config(&options).map_err(CliError::ConfigInvalid)?.proxy;
+        print!("{}", models_info(model, &config).map_err(CliError::Storage)?);
+        return Ok(());
+    }
+
+    if let Some(command) = &options.account_trash {
+        return account_trash(command);
+    }
+
+    if let Some(command) = &options.account_snapshot {
+        return account_snapshot(command);
+    }
+
+    if let Some(command) = &options.profile_command {
+        return profile_command(command);
+    }
+
+    if let Some((since_secs, costs)) = options.usage_report {
+        let since = chrono::Utc::now().timestamp_millis() - since_secs * 1000;
+        // 旧版本的日志数据库尚无统计汇总表: 先建表并从请求日志回填
+        modules::proxy_db::init_db().map_err(CliError::Storage)?;
+        let rows = modules::proxy_db::get_usage_rows(since).map_err(CliError::Storage)?;
+        let pricing = if costs {
+            Some(load_config(&options).map_err(CliError::ConfigInvalid)?.proxy.pricing)
+        } else {
+            None
+        };
+        let report = crate::proxy::usage_report::build(&rows, pricing.as_ref(), since);
+        print!("{}", crate::proxy::usage_report::format_report(&report));
+        return Ok(());
+    }
+
+    if let Some((since_secs, period)) = options.usage_trend {
+        let since = chrono::Utc::now().timestamp_millis() - since_secs * 1000;
+        modules::proxy_db::init_db().map_err(CliError::Storage)?;
+        let rollups = modules::proxy_db::get_rollups(period, since).map_err(CliError::Storage)?;
+        let points = crate::proxy::stats::trend(&rollups);
+        print!("{}", crate::proxy::stats::format_trend(period, &points));
+        return Ok(());
+    }
+
+    if let Some(since_secs) = options.logs_summary {
+        let since = chrono::Utc::now().timestamp_millis() - since_secs * 1000;
+        let thresholds = load_config(&options).map_err(CliError::ConfigInvalid)?.proxy.log_anomalies;
+        modules::proxy_db::init_db().map_err(CliError::Storage)?;
+        let logs = modules::proxy_db::get_logs_since(since, MAX_SUMMARY_REQUESTS).map_err(CliError::Storage)?;
+        let report = crate::proxy::anomalies::analyze(&logs, &thresholds);
+        print!("{}", crate::proxy::anomalies::format_report(&report));
+        return Ok(());
+    }
+
+    if let Some(args) = &options.logs_export_bulk {
+        return logs_export(args);
+    }
+
+    if let Some((format, output)) = &options.config_convert {
+        return config_convert(*format, output.as_deref());
+    }
+    if let Some((path, mode)) = &options.config_export {
+        return config_export(path, *mode);
+    }
+
+    if let Some((session_id, json)) = &options.logs_export {
+        let logs = modules::proxy_db::get_session_logs(session_id, MAX_EXPORT_REQUESTS).map_err(CliError::Storage)?;
+        if logs.is_empty() {
+            return Err(CliError::NotFound(t("session_not_found", &[("session", session_id)])));
+        }
+        let transcript = crate::proxy::transcript::build(session_id, &logs);
+        if *json {
+            let output = serde_json::to_string_pretty(&transcript).map_err(|e| CliError::Failed(e.to_string()))?;
+            println!("{}", output);
+        } else {
+            print!("{}", crate::proxy::transcript::format_markdown(&transcript));
+        }
+        return Ok(());
+    }
+
+    // 校验与日志查看模式仅输出结果，避免与 JSON 日志混在一起
+    if !options.validate_only
+        && options.doctor.is_none()
+        && !options.init
+        && options.logs_tail.is_none()
+        && options.logs_replay.is_none()
+        && options.rotate_api_key.is_none()
+        && options.account_refresh.is_none()
+        && options.bench.is_none()
+        && options.status.is_none()
+        && options.proxy_active.is_none()
+        && options.models_stats.is_none()
+        && options.log_filter.is_none()
+        && options.config_import.is_none()
+        && options.config_value.is_none()
+        && options.export_client.is_none()
+        && options.account_show.is_none()
+        && options.account_import_ide.is_none()
+        && options.account_login.is_none()
+        && options.account_switch.is_none()
+    {
+        modules::logger::init_json_logger();
+    }
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| CliError::Failed(t("runtime_failed", &[("error", &e)])))?;
+
+    if options.validate_only {
+        return runtime.block_on(validate(options));
+    }
+    if let Some(json) = options.doctor {
+        return runtime.block_on(doctor(&options, json));
+    }
+    if options.init {
+        return runtime.block_on(init_wizard(options));
+    }
+    if options.logs_tail.is_some() {
+        return runtime.block_on(logs_tail(options));
+    }
+    if options.logs_replay.is_some() {
+        return runtime.block_on(logs_replay(options));
+    }
+    if let Some(grace) = options.rotate_api_key {
+        return runtime.block_on(rotate_api_key(grace));
+    }
+    if let Some(filter) = options.account_refresh.clone() {
+        return runtime.block_on(account_refresh(filter));
+    }
+    if options.bench.is_some() {
+        return runtime.block_on(bench(options));
+    }
+    if options.status.is_some() {
+        return runtime.block_on(proxy_status(options));
+    }
+    if options.proxy_active.is_some() {
+        return runtime.block_on(proxy_active(options));
+    }
+    if options.models_stats.is_some() {
+        return runtime.block_on(models_stats(options));
+    }
+    if options.log_filter.is_some() {
+        return runtime.block_on(log_filter(options));
+    }
+    if let Some((path, dry_run, assume_yes)) = options.config_import.clone() {
+        return runtime.block_on(config_import(&path, dry_run, assume_yes));
+    }
+    if let Some(command) = &options.config_value {
+        return runtime.block_on(config_value(command));
+    }
+    if let Some(tool) = options.export_client {
+        return runtime.block_on(export_client_config(&options, tool));
+    }
+    if options.account_show.is_some() {
+        return runtime.block_on(account_show(options));
+    }
+    if let Some((target, apply_ide, assume_yes)) = options.account_switch.clone() {
+        return runtime.block_on(account_switch(&target, apply_ide, assume_yes));
+    }
+    if let Some((paths, assume_yes)) = options.account_import_ide.clone() {
+        return runtime.block_on(account_import_from_ide(paths, assume_yes));
+    }
+    if let Some(remote_auth) = options.account_login {
+        return runtime.block_on(account_login(remote_auth));
+    }
+
+    runtime.block_on(serve(options)).map_err(|e| {
+        error!("无头模式运行失败: {}", e);
+        CliError::Failed(e)
+    })
+}
+
+/// 交互式提问，直接回车 (或输入结束) 时采用默认值
+fn prompt(question: &str, default: &str) -> String {
+    use std::io::Write;
+    if default.is_empty() {
+        print!("{}: ", question);
+    } else {
+        print!("{} [{}]: ", question, default);
+    }
+    let _ = std::io::stdout().flush();
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(n) if n > 0 && !line.trim().is_empty() => line.trim().to_string(),
+        _ => default.to_string(),
+    }
+}
+
+/// 是/否提问
+fn confirm(question: &str, default: bool) -> bool {
+    let answer = prompt(question, if default { "Y/n" } else { "y/N" });
+    match answer.to_lowercase().as_str() {
+        "y" | "yes" | "是" => true,
+        "n" | "no" | "否" => false,
+        _ => default,
+    }
+}
+
+/// 初始化向导: 依次添加首个账号、选择端口、局域网访问、生成 API Key、TLS 监听，最后写入配置
+async fn init_wizard(options: HeadlessOptions) -> CliResult<()> {
+    let mut config = load_config(&options).map_err(CliError::ConfigInvalid)?;
+    println!("{}\n", t("init_welcome", &[]));
+
+    // 1. 账号
+    let accounts = modules::account::list_accounts().map_err(CliError::Storage)?;
+    if !accounts.is_empty() {
+        println!("{}", t("init_accounts_existing", &[("count", &accounts.len())]));
+    }
+    let choice = prompt(&t("init_account_prompt", &[]), if accounts.is_empty() { "1" } else { "3" });
+    let added = match choice.as_str() {
+        "1" => {
+            let token = modules::oauth_server::loopback_oauth_flow(|url| {
+                println!("{}", t("init_oauth_open", &[("url", &url)]));
+            })
+            .await
+            .map_err(CliError::Auth)?;
+            let refresh_token = token
+                .refresh_token
+                .clone()
+                .ok_or_else(|| CliError::Auth(t("init_no_refresh_token", &[])))?;
+            Some(add_account(token, refresh_token, "oauth").await.map_err(CliError::Auth)?)
+        }
+        "2" => {
+            let refresh_token = prompt(&t("init_token_prompt", &[]), "");
+            if refresh_token.is_empty() {
+                return Err(CliError::Usage(t("missing_value", &[("flag", &"refresh_token")])));
+            }
+            let token = modules::oauth::refresh_access_token(&refresh_token)
+                .await
+                .map_err(CliError::Auth)?;
+            Some(add_account(token, refresh_token, "refresh_token").await.map_err(CliError::Auth)?)
+        }
+        "3" => None,
+        other => return Err(CliError::Usage(t("init_invalid_choice", &[("value", &other)]))),
+    };
+    match &added {
+        Some(email) => println!("{}", t("init_account_added", &[("email", email)])),
+        None if accounts.is_empty() => println!("{}", t("init_no_accounts", &[])),
+        None => {}
+    }
+
+    // 2. 端口与局域网访问
+    let port = prompt(&t("init_port_prompt", &[]), &config.proxy.port.to_string());
+    config.proxy.port = port
+        .parse()
+        .map_err(|_| CliError::Usage(t("invalid_port", &[("value", &port)])))?;
+    config.proxy.allow_lan_access = confirm(&t("init_lan_prompt", &[]), config.proxy.allow_lan_access);
+    if config.proxy.allow_lan_access && matches!(config.proxy.auth_mode, crate::proxy::ProxyAuthMode::Off) {
+        // 对局域网开放时必须鉴权
+        config.proxy.auth_mode = crate::proxy::ProxyAuthMode::AllExceptHealth;
+    }
+
+    // 3. API Key
+    let regenerate = config.proxy.api_key.is_empty()
+        || confirm(&t("init_regenerate_key_prompt", &[]), config.proxy.allow_lan_access);
+    let new_key = regenerate.then(crate::commands::proxy::generate_api_key);
+    if let Some(key) = &new_key {
+        config.proxy.api_key = key.clone();
+        config.proxy.hash_api_keys = confirm(&t("init_hash_prompt", &[]), config.proxy.hash_api_keys);
+    }
+
+    // 4. TLS 监听器 (与明文端口并存)
+    if confirm(&t("init_tls_prompt", &[]), false) {
+        let default_bind = if config.proxy.allow_lan_access { "0.0.0.0:8443" } else { "127.0.0.1:8443" };
+        let bind = prompt(&t("init_tls_bind_prompt", &[]), default_bind);
+        let mut paths = Vec::new();
+        for key in ["init_tls_cert_prompt", "init_tls_key_prompt"] {
+            let path = prompt(&t(key, &[]), "");
+            if !std::path::Path::new(&path).is_file() {
+                return Err(CliError::NotFound(t("init_file_missing", &[("path", &path)])));
+            }
+            paths.push(path);
+        }
+        let tls = crate::proxy::config::ListenerTlsConfig {
+            cert_path: paths.remove(0),
+            key_path: paths.remove(0),
+        };
+        config.proxy.listeners.retain(|l| l.bind != bind);
+        config.proxy.listeners.push(crate::proxy::config::ListenerConfig {
+            bind,
+            tls: Some(tls),
+            auth_mode: None,
+            api_key: None,
+            socket_mode: None,
+            acme: None,
+        });
+    }
+
+    // 5. 写入配置
+    // 反代可能已在该端口运行，跳过端口占用检测
+    let report = modules::config_validation::validate_config(&config, false).await;
+    if !report.issues.is_empty() {
+        print!("{}", modules::config_validation::format_report(&report));
+    }
+    if report.has_errors() {
+        return Err(CliError::ConfigInvalid(t("validation_failed", &[])));
+    }
+    modules::config::save_app_config(&config).map_err(CliError::Storage)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::ConfigChange,
+        Some("init"),
+        Some(serde_json::json!({ "port": config.proxy.port, "allow_lan_access": config.proxy.allow_lan_access })),
+    );
+    if new_key.is_some() {
+        modules::audit::record(
+            modules::audit::AuditActor::Cli,
+            modules::audit::AuditAction::KeyRotate,
+            Some("proxy.api_key"),
+            None,
+        );
+    }
+
+    let path = modules::config::get_config_path().map_err(CliError::Storage)?;
+    println!("\n{}", t("init_saved", &[("path", &path.display())]));
+    if let Some(key) = &new_key {
+        println!("{}\n{}", t("new_api_key", &[]), key);
+    }
+    println!("{}", t("init_next", &[]));
+    Ok(())
+}
+
+/// 通过 Google 登录添加账号；远程授权时在其他设备上完成登录，再粘贴回调地址 (或由转发的回调端口自动完成)
+async fn account_login(remote_auth: bool) -> CliResult<()> {
+    let token = if remote_auth {
+        modules::oauth_server::remote_oauth_flow(
+            |url, port| {
+                println!("{}", t("login_remote_open", &[("url", &url), ("port", &port)]));
+            },
+            || {
+                use std::io::Write;
+                print!("{}: ", t("login_paste_prompt", &[]));
+                let _ = std::io::stdout().flush();
+                let mut line = String::new();
+                match std::io::stdin().read_line(&mut line) {
+                    Ok(n) if n > 0 => Some(line),
+                    _ => None,
+                }
+            },
+        )
+        .await
+    } else {
+        modules::oauth_server::loopback_oauth_flow(|url| {
+            println!("{}", t("init_oauth_open", &[("url", &url)]));
+        })
+        .await
+    }
+    .map_err(CliError::Auth)?;
+    let refresh_token = token
+        .refresh_token
+        .clone()
+        .ok_or_else(|| CliError::Auth(t("init_no_refresh_token", &[])))?;
+    let email = add_account(token, refresh_token, "oauth").await.map_err(CliError::Auth)?;
+    println!("{}", t("init_account_added", &[("email", &email)]));
+    Ok(())
+}
+
+/// 保存 OAuth / refresh_token 换取的账号并刷新其配额，返回 email
+async fn add_account(
+    token: modules::oauth::TokenResponse,
+    refresh_token: String,
+    source: &str,
+) -> Result<String, String> {
+    let user_info = modules::oauth::get_user_info(&token.access_token).await?;
+    let project_id = crate::proxy::project_resolver::fetch_project_id(&token.access_token)
+        .await
+        .ok();
+    let token_data = crate::models::TokenData::new(
+        token.access_token,
+        refresh_token,
+        token.expires_in,
+        Some(user_info.email.clone()),
+        project_id,
+        None,
+    );
+    let account = modules::upsert_account(user_info.email.clone(), user_info.get_display_name(), token_data)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::AccountAdd,
+        Some(&account.id),
+        Some(serde_json::json!({ "email": account.email, "source": source })),
+    );
+
+    // 配额拉取失败不影响账号添加 (启动反代后会再次刷新)
+    let filter = modules::account::RefreshFilter {
+        accounts: vec![account.email.clone()],
+        ..Default::default()
+    };
+    let _ = crate::commands::refresh_all_quotas(Some(filter)).await;
+    Ok(account.email)
+}
+
+/// 切换当前账号；`apply_ide` 时同时写入 IDE 数据库，IDE 原本在运行则重新启动
+async fn account_switch(target: &str, apply_ide: bool, assume_yes: bool) -> CliResult<()> {
+    let mut account = find_account(target)?;
+    if !apply_ide {
+        modules::account::set_current_account_id(&account.id).map_err(CliError::Storage)?;
+        modules::audit::record(
+            modules::audit::AuditActor::Cli,
+            modules::audit::AuditAction::AccountSwitch,
+            Some(&account.id),
+            Some(serde_json::json!({ "apply_ide": false })),
+        );
+        println!("{}", t("switched", &[("email", &account.email)]));
+        println!("{}", t("switch_ide_hint", &[]));
+        return Ok(());
+    }
+
+    if !assume_yes && !confirm(&t("switch_ide_confirm", &[("email", &account.email)]), false) {
+        return Err(CliError::Failed(t("cancelled", &[])));
+    }
+    let result = modules::account::apply_account_to_ide(&mut account)
+        .await
+        .map_err(CliError::Failed)?;
+    modules::account::set_current_account_id(&account.id).map_err(CliError::Storage)?;
+    account.update_last_used();
+    modules::account::save_account(&account).map_err(CliError::Storage)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::AccountSwitch,
+        Some(&account.id),
+        Some(serde_json::json!({ "apply_ide": true })),
+    );
+
+    println!("{}", t("switched_ide", &[("email", &account.email), ("path", &result.db_path.display())]));
+    if let Some(backup) = &result.backup_path {
+        println!("{}", t("ide_backup", &[("path", &backup.display())]));
+    }
+    if result.was_running {
+        if let Err(e) = modules::process::start_antigravity() {
+            println!("{}", t("ide_restart_failed", &[("error", &e)]));
+        }
+    }
+    Ok(())
+}
+
+/// 从 IDE 本地数据库 (state.vscdb) 导入当前登录的账号，每个数据库导入前需确认
+async fn account_import_from_ide(paths: Vec<PathBuf>, assume_yes: bool) -> CliResult<()> {
+    let paths = if paths.is_empty() {
+        modules::db::candidate_db_paths()
+    } else {
+        paths
+    };
+    if paths.is_empty() {
+        return Err(CliError::NotFound(t("ide_db_not_found", &[])));
+    }
+
+    let mut seen = Vec::new();
+    let mut imported = 0;
+    for path in &paths {
+        let path_text = path.display().to_string();
+        let refresh_token = match modules::migration::extract_refresh_token_from_file(path) {
+            Ok(token) => token,
+            Err(e) => {
+                println!("{}", t("ide_no_token", &[("path", &path_text), ("error", &e)]));
+                continue;
+            }
+        };
+        if seen.contains(&refresh_token) {
+            continue;
+        }
+        seen.push(refresh_token.clone());
+
+        println!("{}", t("ide_token_found", &[("path", &path_text), ("token", &mask_secret(&refresh_token))]));
+        if !assume_yes && !confirm(&t("ide_import_confirm", &[]), true) {
+            continue;
+        }
+        let token = modules::oauth::refresh_access_token(&refresh_token)
+            .await
+            .map_err(CliError::Auth)?;
+        let email = add_account(token, refresh_token, "ide").await.map_err(CliError::Auth)?;
+        println!("{}", t("init_account_added", &[("email", &email)]));
+        imported += 1;
+    }
+
+    if seen.is_empty() {
+        return Err(CliError::NotFound(t("ide_no_accounts", &[])));
+    }
+    println!("{}", t("ide_import_done", &[("count", &imported)]));
+    Ok(())
+}
+
+/// 仅显示密钥首尾几位，用于确认提示
+fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 12 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..6].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+/// 生成新的 API Key，开启哈希存储并保存配置，返回明文
+fn create_hashed_api_key() -> Result<String, String> {
+    let mut config = modules::config::load_app_config()?;
+    let key = crate::commands::proxy::generate_api_key();
+    config.proxy.api_key = key.clone();
+    config.proxy.hash_api_keys = true;
+    modules::config::save_app_config(&config)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::KeyRotate,
+        Some("proxy.api_key"),
+        None,
+    );
+    Ok(key)
+}
+
+/// 启用鉴权但未配置 API Key 时生成并保存新密钥，明文仅输出一次
+fn create_initial_api_key() -> Result<String, String> {
+    // 重新读取配置文件，避免把命令行覆盖的端口等参数写入配置
+    let mut config = modules::config::load_app_config()?;
+    let key = crate::commands::proxy::generate_api_key();
+    config.proxy.api_key = key.clone();
+    modules::config::save_app_config(&config)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::KeyCreate,
+        Some("proxy.api_key"),
+        None,
+    );
+    eprintln!("{}\n{}", t("generated_api_key", &[]), key);
+    Ok(key)
+}
+
+/// 创建带权限范围的附加 API Key 并保存配置，返回明文 (开启哈希存储时只保存哈希)
+fn create_scoped_api_key(args: &CreateKeyArgs) -> CliResult<String> {
+    let CreateKeyArgs { name, scopes, .. } = args;
+    let mut config = modules::config::load_app_config().map_err(CliError::ConfigInvalid)?;
+    if config.proxy.api_keys.iter().any(|k| &k.name == name) {
+        return Err(CliError::Conflict(t("api_key_exists", &[("name", name)])));
+    }
+    let key = match &args.key {
+        Some(key) => {
+            if let Some(reason) = crate::proxy::secrets::weak_key_reason(key) {
+                if !args.force {
+                    return Err(CliError::Usage(t("weak_api_key", &[("reason", &reason)])));
+                }
+                eprintln!("{}", t("weak_api_key_forced", &[("reason", &reason)]));
+            }
+            key.clone()
+        }
+        None => crate::commands::proxy::generate_api_key(),
+    };
+    let stored = if config.proxy.hash_api_keys {
+        crate::proxy::secrets::hash_api_key(&key).map_err(CliError::Failed)?
+    } else {
+        key.clone()
+    };
+    config.proxy.api_keys.push(crate::proxy::config::ScopedApiKey {
+        name: name.to_string(),
+        key: stored,
+        scopes: scopes.to_vec(),
+        created_at: chrono::Utc::now().timestamp(),
+    });
+    modules::config::save_app_config(&config).map_err(CliError::Storage)?;
+    let scope_names: Vec<&str> = scopes.iter().map(|s| s.as_str()).collect();
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::KeyCreate,
+        Some(name.as_str()),
+        Some(serde_json::json!({ "scopes": scope_names })),
+    );
+    Ok(key)
+}
+
+/// 轮换 API Key 并输出新密钥
+async fn rotate_api_key(grace: Option<u64>) -> CliResult<()> {
+    let (rotation, _) = crate::proxy::key_rotation::rotate_and_save(grace, modules::audit::AuditActor::Cli)
+        .await
+        .map_err(CliError::ConfigInvalid)?;
+    println!("{}\n{}", t("new_api_key", &[]), rotation.api_key);
+    match rotation.previous_expires_at {
+        Some(expires_at) => {
+            let time = chrono::DateTime::from_timestamp(expires_at, 0)
+                .map(|t| t.with_timezone(&chrono::Local).to_rfc3339())
+                .unwrap_or_else(|| expires_at.to_string());
+            println!("{}", t("old_key_expires", &[("time", &time)]));
+        }
+        None => println!("{}", t("old_key_revoked", &[])),
+    }
+    println!("{}", t("restart_required", &[]));
+    Ok(())
+}
+
+/// 按条件刷新账号配额并输出结果，存在失败时返回一般失败
+async fn account_refresh(filter: modules::account::RefreshFilter) -> CliResult<()> {
+    let stats = crate::commands::refresh_all_quotas(Some(filter))
+        .await
+        .map_err(CliError::Storage)?;
+    println!(
+        "{}",
+        t(
+            "refresh_summary",
+            &[
+                ("total", &stats.total),
+                ("success", &stats.success),
+                ("failed", &stats.failed),
+                ("skipped", &stats.skipped),
+            ]
+        )
+    );
+    for detail in &stats.details {
+        println!("    {}", detail);
+    }
+    if stats.failed > 0 {
+        return Err(CliError::Failed(t("refresh_failed", &[("count", &stats.failed)])));
+    }
+    Ok(())
+}
+
+/// 回收站操作: 删除 / 查看 / 恢复 / 永久删除
+/// 按 ID 或邮箱查找账号
+fn find_account(target: &str) -> CliResult<crate::models::Account> {
+    modules::account::list_accounts()
+        .map_err(CliError::Storage)?
+        .into_iter()
+        .find(|a| a.id == target || a.email.eq_ignore_ascii_case(target))
+        .ok_or_else(|| CliError::NotFound(t("account_not_found", &[("target", &target)])))
+}
+
+/// 输出账号详情；冷却状态取自本机运行中的反代 (未运行时显示未知)
+async fn account_show(options: HeadlessOptions) -> CliResult<()> {
+    let Some(target) = options.account_show.as_ref() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--account-show")])));
+    };
+    let account = find_account(target)?;
+    let config = load_config(&options).map_err(CliError::ConfigInvalid)?.proxy;
+    let now = chrono::Utc::now().timestamp();
+
+    // 尚无请求日志数据库时按无记录处理
+    let usage: Vec<_> = modules::proxy_db::get_usage_rows((now - ACCOUNT_USAGE_WINDOW_SECS) * 1000)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|row| row.account.as_deref() == Some(account.email.as_str()))
+        .collect();
+    let errors = modules::proxy_db::get_account_errors(&account.email, ACCOUNT_RECENT_ERRORS).unwrap_or_default();
+    let health = fetch_account_health(&config, &account.email).await;
+
+    print!("{}", format_account_detail(&account, &usage, &errors, health, &config, now));
+    Ok(())
+}
+
+/// 从本机运行中的反代读取账号健康状况: 外层 None 表示反代不可达，内层 None 表示该账号未加载
+async fn fetch_account_health(
+    config: &crate::proxy::ProxyConfig,
+    email: &str,
+) -> Option<Option<crate::proxy::token_manager::AccountHealth>> {
+    let api_key = admin_api_key(config).ok()?;
+    let response = reqwest::Client::new()
+        .get(format!("{}/admin/status", config.admin_base_url()))
+        .bearer_auth(api_key)
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let runtime: crate::proxy::status::RuntimeStatus = response.json().await.ok()?;
+    Some(runtime.accounts.into_iter().find(|a| a.email.eq_ignore_ascii_case(email)))
+}
+
+/// 账号记录的上游错误: 时间、状态码与截断后的错误信息 (单行)
+fn format_account_error(error: &crate::models::AccountError, max_chars: usize) -> String {
+    let time = chrono::DateTime::from_timestamp(error.timestamp, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let status = error.status.map_or_else(|| "-".to_string(), |s| s.to_string());
+    let message: String = error.message.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(max_chars).collect();
+    format!("{}  {}  {}", time, status, message)
+}
+
+fn format_account_detail(
+    account: &crate::models::Account,
+    usage: &[crate::proxy::usage_report::UsageRow],
+    errors: &[crate::proxy::monitor::ProxyRequestLog],
+    health: Option<Option<crate::proxy::token_manager::AccountHealth>>,
+    config: &crate::proxy::ProxyConfig,
+    now: i64,
+) -> String {
+    use crate::models::quota::format_countdown;
+
+    let time = |ts: i64| {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let mut out = String::new();
+    let mut line = |text: String| {
+        out.push_str(&text);
+        out.push('\n');
+    };
+
+    line(account.email.clone());
+    line(format!("  {}", t("account_id", &[("id", &account.id)])));
+    if let Some(name) = &account.name {
+        line(format!("  {}", t("show_name", &[("name", name)])));
+    }
+    let status = if account.disabled {
+        let reason = account.disabled_reason.as_deref().unwrap_or("-");
+        format!("{} ({})", t("status_disabled", &[]), reason)
+    } else if account.proxy_disabled {
+        let reason = account.proxy_disabled_reason.as_deref().unwrap_or("-");
+        format!("{} ({})", t("status_proxy_disabled", &[]), reason)
+    } else {
+        t("status_active", &[])
+    };
+    line(format!("  {}", t("show_status", &[("status", &status)])));
+    if !account.last_errors.is_empty() {
+        line(format!("  {}", t("show_account_errors", &[])));
+        for error in &account.last_errors {
+            line(format!("    {}", format_account_error(error, 120)));
+        }
+    }
+    line(format!("  {}", t("show_created", &[("time", &time(account.created_at)), ("last_used", &time(account.last_used))])));
+
+    let expiry = account.token.expiry_timestamp;
+    if expiry > now {
+        line(format!(
+            "  {}",
+            t("show_token_expiry", &[("time", &time(expiry)), ("countdown", &format_countdown(expiry - now))])
+        ));
+    } else {
+        line(format!("  {}", t("show_token_expired", &[("time", &time(expiry))])));
+    }
+    if let Some(project) = &account.token.project_id {
+        line(format!("  {}", t("show_project", &[("project", project)])));
+    }
+
+    // 上游代理 (全局配置，所有账号共用)
+    let egress = if config.upstream_proxy.enabled && !config.upstream_proxy.url.is_empty() {
+        config.upstream_proxy.url.clone()
+    } else {
+        t("show_egress_direct", &[])
+    };
+    line(format!("  {}", t("show_egress", &[("proxy", &egress)])));
+
+    let cooldown = match &health {
+        None => t("show_cooldown_unknown", &[]),
+        Some(None) => t("show_cooldown_not_loaded", &[]),
+        Some(Some(h)) => match h.rate_limited_secs {
+            Some(secs) => t("show_cooldown_active", &[("countdown", &format_countdown(secs as i64))]),
+            None => t("show_cooldown_none", &[]),
+        },
+    };
+    line(format!("  {}", t("show_cooldown", &[("state", &cooldown)])));
+
+    if let Some(note) = &account.note {
+        line(format!("  {}", t("account_note", &[("note", note)])));
+    }
+    if !account.metadata.is_empty() {
+        line(format!("  {}", t("show_metadata", &[])));
+        let mut metadata: Vec<_> = account.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            line(format!("    {} = {}", key, value));
+        }
+    }
+
+    match &account.quota {
+        None => line(format!("  {}", t("quota_not_fetched", &[]))),
+        Some(quota) => {
+            let tier = quota.subscription_tier.as_ref().map_or("-", |t| t.as_str());
+            line(format!("  {}", t("show_quota", &[("tier", &tier), ("time", &time(quota.last_updated))])));
+            if quota.is_forbidden {
+                line(format!("    {}", t("show_quota_forbidden", &[])));
+            }
+            for model in &quota.models {
+                let reset = match model.resets_in(now) {
+                    Some(secs) => format!(
+                        "{} ({})",
+                        t("resets_in", &[("countdown", &format_countdown(secs))]),
+                        time(now + secs)
+                    ),
+                    None if model.reset_timestamp().is_some() => t("reset_pending", &[]),
+                    None => "-".to_string(),
+                };
+                line(format!("    {:<32} {:>3}%  {}", model.name, model.percentage, reset));
+            }
+        }
+    }
+
+    let (requests, input, output) = usage.iter().fold((0, 0, 0), |(r, i, o), row| {
+        (r + row.requests, i + row.input_tokens, o + row.output_tokens)
+    });
+    line(format!(
+        "  {}",
+        t("show_usage", &[("days", &(ACCOUNT_USAGE_WINDOW_SECS / 86400)), ("requests", &requests), ("input", &input), ("output", &output)])
+    ));
+    let mut by_model: Vec<_> = usage.iter().collect();
+    by_model.sort_by(|a, b| b.requests.cmp(&a.requests));
+    for row in by_model {
+        line(format!(
+            "    {:<32} {:>6}  {} / {}",
+            row.model.as_deref().unwrap_or("-"),
+            row.requests,
+            row.input_tokens,
+            row.output_tokens
+        ));
+    }
+
+    if errors.is_empty() {
+        line(format!("  {}", t("show_no_errors", &[])));
+    } else {
+        line(format!("  {}", t("show_recent_errors", &[])));
+        for log in errors {
+            let message: String = log.error.as_deref().unwrap_or("-").chars().take(120).collect();
+            line(format!(
+                "    {}  {}  {}  {}",
+                time(log.timestamp / 1000),
+                log.status,
+                log.model.as_deref().unwrap_or("-"),
+                message
+            ));
+        }
+    }
+    out
+}
+
+/// 设置账号备注与元数据
+fn account_note(target: &str, note: Option<&str>, metadata: &[(String, Option<String>)]) -> CliResult<()> {
+    if note.is_none() && metadata.is_empty() {
+        return Err(CliError::Usage(t("note_missing", &[])));
+    }
+    let account = find_account(target)?;
+    let account = modules::account::update_account_note(&account.id, note, metadata).map_err(CliError::Storage)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::AccountUpdate,
+        Some(&account.id),
+        Some(serde_json::json!({ "note": account.note, "metadata": account.metadata })),
+    );
+    println!("{}", t("note_updated", &[("email", &account.email)]));
+    Ok(())
+}
+
+/// 设置账号的客户端标识档案
+fn account_profile(
+    target: &str,
+    profile: Option<&str>,
+    config: &crate::proxy::config::ClientProfileConfig,
+) -> CliResult<()> {
+    let available = crate::proxy::upstream::profiles::available(config);
+    if let Some(name) = profile {
+        if !available.iter().any(|p| p.name == name) {
+            let names: Vec<&str> = available.iter().map(|p| p.name.as_str()).collect();
+            return Err(CliError::NotFound(t(
+                "profile_not_found",
+                &[("name", &name), ("available", &names.join(", "))],
+            )));
+        }
+    }
+    let account = find_account(target)?;
+    let account = modules::account::set_client_profile(&account.id, profile).map_err(CliError::Storage)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::AccountUpdate,
+        Some(&account.id),
+        Some(serde_json::json!({ "client_profile": account.client_profile })),
+    );
+    match &account.client_profile {
+        Some(name) => println!("{}", t("profile_set", &[("email", &account.email), ("name", name)])),
+        None => println!("{}", t("profile_reset", &[("email", &account.email), ("name", &config.active)])),
+    }
+    Ok(())
+}
+
+/// 输出账号指定模型的配额；模型名不区分大小写，读取本地保存的配额 (不请求上游)
+fn account_quota(target: &str, model: &str, json: bool) -> CliResult<()> {
+    use crate::models::quota::format_countdown;
+
+    let account = find_account(target)?;
+    let quota = account
+        .quota
+        .as_ref()
+        .ok_or_else(|| CliError::NotFound(t("quota_not_fetched", &[])))?;
+    let entry = quota
+        .models
+        .iter()
+        .find(|m| m.name.eq_ignore_ascii_case(model))
+        .ok_or_else(|| CliError::NotFound(t("quota_model_not_found", &[("email", &account.email), ("model", &model)])))?;
+    let now = chrono::Utc::now().timestamp();
+    let reset_at = entry.reset_timestamp();
+    let limits = entry.limits.clone().unwrap_or_default();
+
+    if json {
+        let output = serde_json::json!({
+            "account_id": account.id,
+            "email": account.email,
+            "model": entry.name,
+            "remaining_percent": entry.percentage,
+            "exhausted": entry.is_exhausted(),
+            "reset_at": reset_at,
+            "resets_in_secs": entry.resets_in(now),
+            "max_tokens": limits.max_tokens,
+            "max_output_tokens": limits.max_output_tokens,
+            "forbidden": quota.is_forbidden,
+            "updated_at": quota.last_updated,
+        });
+        println!("{}", output);
+        return Ok(());
+    }
+
+    let time = |ts: i64| {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let reset = match entry.resets_in(now) {
+        Some(secs) => format!("{} ({})", t("resets_in", &[("countdown", &format_countdown(secs))]), time(now + secs)),
+        None if reset_at.is_some() => t("reset_pending", &[]),
+        None => "-".to_string(),
+    };
+    let number = |n: Option<u64>| n.map_or_else(|| "-".to_string(), |n| n.to_string());
+
+    println!("{} {}", account.email, entry.name);
+    println!("  {}", t("quota_remaining", &[("percent", &entry.percentage)]));
+    if entry.is_exhausted() {
+        println!("  {}", t("quota_exhausted", &[]));
+    }
+    if quota.is_forbidden {
+        println!("  {}", t("show_quota_forbidden", &[]));
+    }
+    println!("  {}", t("quota_reset", &[("reset", &reset)]));
+    println!(
+        "  {}",
+        t("quota_limits", &[("input", &number(limits.max_tokens)), ("output", &number(limits.max_output_tokens))])
+    );
+    println!("  {}", t("quota_updated", &[("time", &time(quota.last_updated))]));
+    Ok(())
+}
+
+/// 重新生成账号的设备标识
+fn account_rotate_device(target: &str) -> CliResult<()> {
+    let account = find_account(target)?;
+    let account = modules::account::rotate_device(&account.id).map_err(CliError::Storage)?;
+    let machine_id = account.device.as_ref().map(|d| d.machine_id.as_str()).unwrap_or_default();
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::AccountUpdate,
+        Some(&account.id),
+        Some(serde_json::json!({ "device": "rotated" })),
+    );
+    println!("{}", t("device_rotated", &[("email", &account.email), ("machine_id", &machine_id)]));
+    Ok(())
+}
+
+/// Token 健康报告: 存在严重问题的账号时返回一般失败，便于定时任务告警
+fn account_health(json: bool) -> CliResult<()> {
+    use modules::token_health::HealthLevel;
+
+    let accounts = modules::account::list_accounts().map_err(CliError::Storage)?;
+    let now = chrono::Utc::now().timestamp();
+    let report = modules::token_health::report(&accounts, now);
+    if json {
+        let output = serde_json::to_string_pretty(&report).map_err(|e| CliError::Failed(e.to_string()))?;
+        println!("{}", output);
+    } else {
+        print!("{}", format_token_health(&report, now));
+    }
+    let critical = report.iter().filter(|h| h.level == HealthLevel::Critical).count();
+    if critical > 0 {
+        return Err(CliError::Failed(t("account_health_critical", &[("count", &critical)])));
+    }
+    Ok(())
+}
+
+fn format_token_health(report: &[modules::token_health::TokenHealth], now: i64) -> String {
+    use crate::models::quota::format_countdown;
+    use modules::token_health::{HealthIssue, HealthLevel};
+
+    if report.is_empty() {
+        return format!("{}\n", t("no_accounts", &[]));
+    }
+    let ago = |ts: Option<i64>| {
+        ts.map_or("-".to_string(), |ts| t("health_ago", &[("ago", &format_countdown(now - ts))]))
+    };
+    let mut out = format!(
+        "{:<32} {:<10} {:<14} {:>5} {:<10} {}\n",
+        "EMAIL", "ACCESS", "LAST REFRESH", "FAILS", "TOKEN AGE", "STATUS"
+    );
+    for health in report {
+        let access = if health.access_expires_at > now {
+            format_countdown(health.access_expires_at - now)
+        } else {
+            t("health_access_expired", &[])
+        };
+        let age_days = (now - health.token_obtained_at).max(0) / 86400;
+        let age = format!("{}{}d", if health.token_age_estimated { "~" } else { "" }, age_days);
+        let status = match health.level {
+            HealthLevel::Ok => "OK",
+            HealthLevel::Warning => "WARN",
+            HealthLevel::Critical => "CRITICAL",
+        };
+        out.push_str(&format!(
+            "{:<32} {:<10} {:<14} {:>5} {:<10} {}\n",
+            health.email,
+            access,
+            ago(health.last_refresh_at),
+            health.failure_streak,
+            age,
+            status
+        ));
+        for issue in &health.issues {
+            let line = match issue {
+                HealthIssue::Revoked => t("token_issue_revoked", &[]),
+                HealthIssue::RefreshFailing => t(
+                    "token_issue_refresh_failing",
+                    &[("count", &health.failure_streak), ("ago", &ago(health.last_failure_at))],
+                ),
+                HealthIssue::Idle if health.idle_revoke_at <= now => t("token_issue_idle_expired", &[]),
+                HealthIssue::Idle => t(
+                    "token_issue_idle",
+                    &[("countdown", &format_countdown(health.idle_revoke_at - now))],
+                ),
+            };
+            out.push_str(&format!("    - {}\n", line));
+        }
+    }
+    out
+}
+
+fn account_trash(command: &TrashCommand) -> CliResult<()> {
+    match command {
+        TrashCommand::Delete(target) => {
+            let account = modules::account::list_accounts()
+                .map_err(CliError::Storage)?
+                .into_iter()
+                .find(|a| &a.id == target || a.email.eq_ignore_ascii_case(target))
+                .ok_or_else(|| CliError::NotFound(t("account_not_found", &[("target", target)])))?;
+            modules::account::delete_account(&account.id).map_err(CliError::Storage)?;
+            modules::audit::record(
+                modules::audit::AuditActor::Cli,
+                modules::audit::AuditAction::AccountDelete,
+                Some(&account.id),
+                None,
+            );
+            println!("{}", t("moved_to_trash", &[("email", &account.email), ("id", &account.id)]));
+            println!("{}", t("restore_hint", &[("id", &account.id)]));
+        }
+        TrashCommand::List => {
+            let trashed = modules::account::list_trash().map_err(CliError::Storage)?;
+            let now = chrono::Utc::now().timestamp();
+            for item in &trashed {
+                let ago = crate::models::quota::format_countdown(now - item.deleted_at);
+                println!(
+                    "{}",
+                    t("trash_item", &[("id", &item.account.id), ("email", &item.account.email), ("ago", &ago)])
+                );
+            }
+            if trashed.is_empty() {
+                println!("{}", t("trash_empty", &[]));
+            }
+        }
+        TrashCommand::Restore(id) => {
+            let item = modules::account::list_trash()
+                .map_err(CliError::Storage)?
+                .into_iter()
+                .find(|t| &t.account.id == id)
+                .ok_or_else(|| CliError::NotFound(t("trash_not_found", &[("id", id)])))?;
+            let exists = modules::account::list_accounts()
+                .map_err(CliError::Storage)?
+                .iter()
+                .any(|a| a.email == item.account.email);
+            if exists {
+                return Err(CliError::Conflict(t("account_exists", &[("email", &item.account.email)])));
+            }
+            let account = modules::account::restore_account(id).map_err(CliError::Storage)?;
+            modules::audit::record(
+                modules::audit::AuditActor::Cli,
+                modules::audit::AuditAction::AccountRestore,
+                Some(&account.id),
+                Some(serde_json::json!({ "email": account.email })),
+            );
+            println!("{}", t("restored", &[("email", &account.email), ("id", &account.id)]));
+        }
+        TrashCommand::Purge(older_than) => {
+            let purged = modules::account::purge_trash(*older_than, chrono::Utc::now().timestamp())
+                .map_err(CliError::Storage)?;
+            if !purged.is_empty() {
+                modules::audit::record(
+                    modules::audit::AuditActor::Cli,
+                    modules::audit::AuditAction::AccountPurge,
+                    None,
+                    Some(serde_json::json!({
+                        "account_ids": purged.iter().map(|t| t.account.id.clone()).collect::<Vec<_>>()
+                    })),
+                );
+            }
+            for item in &purged {
+                println!("{}", t("purged", &[("email", &item.account.email), ("id", &item.account.id)]));
+            }
+            println!("{}", t("purged_total", &[("count", &purged.len())]));
+        }
+    }
+    Ok(())
+}
+
+/// 配置档管理: 列出 / 创建 / 设置默认配置档
+fn profile_command(command: &ProfileCommand) -> CliResult<()> {
+    match command {
+        ProfileCommand::List => {
+            for profile in modules::profile::list_profiles().map_err(CliError::Storage)? {
+                let mut line = t(
+                    "profile_item",
+                    &[
+                        ("marker", &if profile.active { "*" } else { " " }),
+                        ("name", &profile.name),
+                        ("dir", &profile.data_dir),
+                    ],
+                );
+                if profile.is_default {
+                    line.push_str(&t("profile_default_marker", &[]));
+                }
+                println!("{}", line);
+            }
+        }
+        ProfileCommand::Create(name) => {
+            let profile = modules::profile::create_profile(name.trim()).map_err(CliError::Usage)?;
+            println!("{}", t("profile_created", &[("name", &profile.name), ("dir", &profile.data_dir)]));
+        }
+        ProfileCommand::Switch(name) => {
+            modules::profile::switch_profile(name.trim()).map_err(CliError::Usage)?;
+            println!("{}", t("profile_switched", &[("name", &name.trim())]));
+        }
+    }
+    Ok(())
+}
+
+fn account_snapshot(command: &SnapshotCommand) -> CliResult<()> {
+    let time = |ts: i64| {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    match command {
+        SnapshotCommand::Save(name) => {
+            let snapshot = modules::snapshot::save(name).map_err(CliError::Storage)?;
+            println!(
+                "{}",
+                t("snapshot_saved", &[("name", &snapshot.name), ("count", &snapshot.accounts.len())])
+            );
+            // 快照记录的是最近一次刷新的配额
+            if let Some(oldest) = snapshot.accounts.iter().filter_map(|a| a.quota_updated_at).min() {
+                let age = crate::models::quota::format_countdown(snapshot.created_at - oldest);
+                println!("{}", t("snapshot_quota_age", &[("age", &age)]));
+            }
+        }
+        SnapshotCommand::List => {
+            let snapshots = modules::snapshot::list().map_err(CliError::Storage)?;
+            for snapshot in &snapshots {
+                println!(
+                    "{}",
+                    t(
+                        "snapshot_item",
+                        &[
+                            ("name", &snapshot.name),
+                            ("time", &time(snapshot.created_at)),
+                            ("count", &snapshot.accounts.len()),
+                        ]
+                    )
+                );
+            }
+            if snapshots.is_empty() {
+                println!("{}", t("snapshot_empty", &[]));
+            }
+        }
+        SnapshotCommand::Diff(from, to) => {
+            let load = |name: &str| {
+                modules::snapshot::load(name)
+                    .map_err(CliError::Storage)?
+                    .ok_or_else(|| CliError::NotFound(t("snapshot_not_found", &[("name", &name)])))
+            };
+            let diff = modules::snapshot::diff(&load(from)?, &load(to)?);
+            print!("{}", format_snapshot_diff(&diff));
+        }
+    }
+    Ok(())
+}
+
+fn format_snapshot_diff(diff: &modules::snapshot::SnapshotDiff) -> String {
+    use crate::models::quota::format_countdown;
+
+    let mut out = format!(
+        "{}\n",
+        t(
+            "snapshot_diff_header",
+            &[("from", &diff.from), ("to", &diff.to), ("elapsed", &format_countdown(diff.elapsed_secs.abs()))]
+        )
+    );
+    for account in &diff.accounts {
+        match account.presence {
+            Some("added") => out.push_str(&format!("+ {}\n", account.email)),
+            Some(_) => out.push_str(&format!("- {}\n", account.email)),
+            None => out.push_str(&format!("  {}\n", account.email)),
+        }
+        if let Some((before, after)) = &account.status {
+            out.push_str(&format!(
+                "    {}\n",
+                t("snapshot_status_changed", &[("before", before), ("after", after)])
+            ));
+        }
+        for change in &account.models {
+            let percent = |v: Option<i32>| v.map_or("-".to_string(), |v| format!("{}%", v));
+            let consumed = change.consumed().map_or(String::new(), |c| format!("  ({:+})", -c));
+            out.push_str(&format!(
+                "    {:<32} {:>4} -> {:>4}{}\n",
+                change.model,
+                percent(change.before),
+                percent(change.after),
+                consumed
+            ));
+        }
+    }
+    if diff.accounts.is_empty() {
+        out.push_str(&format!("{}\n", t("snapshot_no_changes", &[])));
+    }
+    if !diff.consumed_by_model.is_empty() {
+        out.push_str(&format!("{}\n", t("snapshot_consumed", &[])));
+        for (model, consumed) in &diff.consumed_by_model {
+            out.push_str(&format!("    {:<32} {:>5}\n", model, consumed));
+        }
+    }
+    out
+}
+
+/// 压测运行中的反代并输出报告；存在失败请求时返回一般失败
+async fn bench(options: HeadlessOptions) -> CliResult<()> {
+    let Some(args) = options.bench.as_ref() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--bench")])));
+    };
+    let config = load_config(&options).map_err(CliError::ConfigInvalid)?.proxy;
+    let api_key = admin_api_key(&config).map_err(CliError::Auth)?;
+    let base_url = args
+        .url
+        .clone()
+        .unwrap_or_else(|| format!("http://127.0.0.1:{}", config.port))
+        .trim_end_matches('/')
+        .to_string();
+
+    println!(
+        "{}",
+        t(
+            "bench_start",
+            &[
+                ("requests", &args.requests),
+                ("concurrency", &args.concurrency),
+                ("model", &args.model),
+                ("url", &base_url),
+            ]
+        )
+    );
+    // 指定 --url 时管理接口与压测目标为同一实例
+    let admin_url = match &args.url {
+        Some(_) => base_url.clone(),
+        None => config.admin_base_url(),
+    };
+    let bench_options = crate::proxy::bench::BenchOptions {
+        base_url,
+        admin_url,
+        api_key,
+        requests: args.requests,
+        concurrency: args.concurrency,
+        model: args.model.clone(),
+        prompt: "Reply with the single word: pong".to_string(),
+        max_tokens: 16,
+    };
+    let report = crate::proxy::bench::run(&bench_options).await?;
+    print!("{}", crate::proxy::bench::format_report(&report));
+    if report.failed > 0 {
+        return Err(CliError::Failed(t("bench_failed", &[("count", &report.failed)])));
+    }
+    Ok(())
+}
+
+/// 查询运行中实例的实时状态 (`GET /admin/status`)
+async fn proxy_status(options: HeadlessOptions) -> CliResult<()> {
+    let Some(remote) = options.status.as_ref() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--status")])));
+    };
+    let (base, api_key) = admin_target(&options, remote.as_deref())?;
+    let url = format!("{}/admin/status", base.trim_end_matches('/'));
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .bearer_auth(&api_key)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| CliError::Network(t("proxy_unreachable", &[("url", &url), ("error", &e)])))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CliError::from_status(status, t("status_failed", &[("status", &status)])));
+    }
+    let runtime: crate::proxy::status::RuntimeStatus = response
+        .json()
+        .await
+        .map_err(|e| CliError::Failed(t("status_parse_failed", &[("error", &e)])))?;
+    println!("{}", t("status_target", &[("url", &base)]));
+    print!("{}", crate::proxy::status::format_status(&runtime));
+    Ok(())
+}
+
+/// 输出运行中实例各上游模型的延迟与成功率统计
+async fn models_stats(options: HeadlessOptions) -> CliResult<()> {
+    let Some((remote, json)) = options.models_stats.clone() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--models-stats")])));
+    };
+    let (base, api_key) = admin_target(&options, remote.as_deref())?;
+    let url = format!("{}/stats/models", base.trim_end_matches('/'));
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .bearer_auth(&api_key)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| CliError::Network(t("proxy_unreachable", &[("url", &url), ("error", &e)])))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CliError::from_status(status, t("status_failed", &[("status", &status)])));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| CliError::Failed(t("status_parse_failed", &[("error", &e)])))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&body).unwrap_or_default());
+        return Ok(());
+    }
+    let stats: Vec<crate::proxy::model_stats::ModelStats> = serde_json::from_value(body["models"].clone())
+        .map_err(|e| CliError::Failed(t("status_parse_failed", &[("error", &e)])))?;
+    if stats.is_empty() {
+        println!("{}", t("models_stats_empty", &[]));
+    } else {
+        print!("{}", crate::proxy::model_stats::format_table(&stats));
+    }
+    Ok(())
+}
+
+/// 列出运行中实例的在途请求，或取消指定请求
+async fn proxy_active(options: HeadlessOptions) -> CliResult<()> {
+    let Some((remote, cancel)) = options.proxy_active.clone() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--proxy-active")])));
+    };
+    let (base, api_key) = admin_target(&options, remote.as_deref())?;
+    let base = base.trim_end_matches('/');
+    let client = reqwest::Client::new();
+
+    if let Some(id) = cancel {
+        let url = format!("{}/admin/requests/{}/cancel", base, id);
+        let response = client
+            .post(&url)
+            .bearer_auth(&api_key)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| CliError::Network(t("proxy_unreachable", &[("url", &url), ("error", &e)])))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(CliError::NotFound(t("request_not_active", &[("id", &id)])));
+        }
+        if !status.is_success() {
+            return Err(CliError::from_status(status, t("request_cancel_failed", &[("status", &status)])));
+        }
+        println!("{}", t("request_cancelled", &[("id", &id)]));
+        return Ok(());
+    }
+
+    let url = format!("{}/admin/requests", base);
+    let response = client
+        .get(&url)
+        .bearer_auth(&api_key)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| CliError::Network(t("proxy_unreachable", &[("url", &url), ("error", &e)])))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CliError::from_status(status, t("status_failed", &[("status", &status)])));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| CliError::Failed(t("status_parse_failed", &[("error", &e)])))?;
+    let requests: Vec<crate::proxy::inflight::InflightRequest> =
+        serde_json::from_value(body["requests"].clone())
+            .map_err(|e| CliError::Failed(t("status_parse_failed", &[("error", &e)])))?;
+    println!("{}", t("status_target", &[("url", &base)]));
+    print!("{}", crate::proxy::inflight::format_inflight(&requests));
+    Ok(())
+}
+
+/// 查看或调整运行中实例的日志过滤规则
+async fn log_filter(options: HeadlessOptions) -> CliResult<()> {
+    let Some((command, remote)) = options.log_filter.clone() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--log-filter")])));
+    };
+    let (base, api_key) = admin_target(&options, remote.as_deref())?;
+    let url = format!("{}/admin/log-filter", base.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+    let request = match &command {
+        LogFilterCommand::Show => client.get(&url),
+        LogFilterCommand::Set(rules, duration) => client
+            .put(&url)
+            .json(&serde_json::json!({ "filter": rules, "duration_secs": duration })),
+        LogFilterCommand::Reset => client.delete(&url),
+    };
+    let response = request
+        .bearer_auth(&api_key)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| CliError::Network(t("proxy_unreachable", &[("url", &url), ("error", &e)])))?;
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    if status == reqwest::StatusCode::BAD_REQUEST {
+        let message = body["error"]["message"].as_str().unwrap_or_default();
+        return Err(CliError::Usage(message.to_string()));
+    }
+    if !status.is_success() {
+        return Err(CliError::from_status(status, t("log_filter_failed", &[("status", &status)])));
+    }
+
+    let filter = body["filter"].as_str().unwrap_or("-");
+    println!("{}", t("log_filter_current", &[("filter", &filter)]));
+    if let Some(default) = body["default"].as_str().filter(|d| *d != filter) {
+        println!("{}", t("log_filter_default", &[("filter", &default)]));
+    }
+    if let Some(expires_at) = body["expires_at"].as_i64() {
+        let countdown = crate::models::quota::format_countdown(expires_at - chrono::Utc::now().timestamp());
+        println!("{}", t("log_filter_expires", &[("countdown", &countdown)]));
+    }
+    Ok(())
+}
+
+/// 管理接口地址与密钥: 未指定远程实例时使用本机的管理地址 (`admin_listen`，未设置时为反代端口)
+fn admin_target(options: &HeadlessOptions, remote: Option<&str>) -> CliResult<(String, String)> {
+    let config = load_config(options).map_err(CliError::ConfigInvalid)?.proxy;
+    // 远程实例的密钥通常与本机不同，优先使用环境变量
+    let api_key = match (remote, std::env::var("ANTIGRAVITY_API_KEY")) {
+        (Some(_), Ok(key)) => key,
+        _ => admin_api_key(&config).map_err(CliError::Auth)?,
+    };
+    let base = remote
+        .map(str::to_string)
+        .unwrap_or_else(|| config.admin_base_url());
+    Ok((base, api_key))
+}
+
+/// 调用管理接口使用的密钥: 哈希存储时需由环境变量提供明文
+fn admin_api_key(config: &crate::proxy::ProxyConfig) -> Result<String, String> {
+    if !crate::proxy::secrets::is_hashed(&config.api_key) {
+        return Ok(config.api_key.clone());
+    }
+    std::env::var("ANTIGRAVITY_API_KEY")
+        .map_err(|_| t("api_key_hashed_env", &[]))
+}
+
+/// 模型能力: 按配置的映射解析上游模型，能力取自内置目录、本地账号最近一次报告的配额数据与配置覆盖
+fn models_info(model: &str, config: &crate::proxy::ProxyConfig) -> Result<String, String> {
+    let target = crate::proxy::common::model_mapping::resolve_model_route(
+        model,
+        &config.custom_mapping,
+        &crate::proxy::common::mapping_rules::MappingRules::from_config(&config.mapping_rules),
+        &config.openai_mapping,
+        &config.anthropic_mapping,
+        false,
+    );
+    let accounts = modules::account::list_accounts()?;
+    let upstream = accounts
+        .iter()
+        .filter_map(|a| a.quota.as_ref())
+        .flat_map(|q| q.models.iter())
+        .find(|m| m.name == target)
+        .and_then(|m| m.limits.clone());
+    let caps = crate::proxy::model_capabilities::resolve(
+        &target,
+        upstream.as_ref(),
+        &config.model_capabilities.overrides,
+    );
+    Ok(crate::proxy::model_capabilities::format_capabilities(model, &target, caps.as_ref()))
+}
+
+/// 账号列表: 每个账号一行，随后每个模型一行 (剩余配额与重置倒计时)
+fn format_account_list(accounts: &[crate::models::Account], now: i64, wide: bool) -> String {
+    use crate::models::quota::format_countdown;
+
+    let mut out = String::new();
+    for account in accounts {
+        let status = if account.disabled {
+            format!(" [{}]", t("status_disabled", &[]))
+        } else if account.proxy_disabled {
+            format!(" [{}]", t("status_proxy_disabled", &[]))
+        } else {
+            String::new()
+        };
+        let tier = account
+            .quota
+            .as_ref()
+            .and_then(|q| q.subscription_tier.as_ref())
+            .map_or("-", |t| t.as_str());
+        out.push_str(&format!("{} ({}){}\n", account.email, tier, status));
+        if wide {
+            out.push_str(&format!("    {}\n", t("account_id", &[("id", &account.id)])));
+            if let Some(note) = &account.note {
+                out.push_str(&format!("    {}\n", t("account_note", &[("note", note)])));
+            }
+            let mut metadata: Vec<_> = account.metadata.iter().collect();
+            metadata.sort();
+            for (key, value) in metadata {
+                out.push_str(&format!("    {} = {}\n", key, value));
+            }
+            if let Some(error) = account.last_errors.first() {
+                out.push_str(&format!("    {}\n", t("account_last_error", &[("error", &format_account_error(error, 80))])));
+            }
+        }
+
+        let Some(quota) = &account.quota else {
+            out.push_str(&format!("    {}\n", t("quota_not_fetched", &[])));
+            continue;
+        };
+        for model in &quota.models {
+            let reset = match model.resets_in(now) {
+                Some(secs) => t("resets_in", &[("countdown", &format_countdown(secs))]),
+                None if model.reset_timestamp().is_some() => t("reset_pending", &[]),
+                None => "-".to_string(),
+            };
+            out.push_str(&format!("    {:<32} {:>3}%  {}\n", model.name, model.percentage, reset));
+        }
+    }
+    if accounts.is_empty() {
+        out.push_str(&format!("{}\n", t("no_accounts", &[])));
+    }
+    out
+}
+
+/// 加载配置并应用命令行覆盖
+fn load_config(options: &HeadlessOptions) -> Result<crate::models::AppConfig, String> {
+    let mut config = modules::config::load_app_config()?;
+    crate::proxy::upstream::profiles::configure(&config.proxy.client_profiles);
+    if let Some(port) = options.port {
+        config.proxy.port = port;
+    }
+    if options.allow_lan {
+        config.proxy.allow_lan_access = true;
+    }
+    if options.warmup {
+        config.proxy.warmup_on_start = true;
+    }
+    if options.strict_mappings {
+        config.proxy.strict_mappings = true;
+    }
+    if let Some(path) = &options.uds {
+        config.proxy.listen_tcp = false;
+        config.proxy.listeners = vec![crate::proxy::config::ListenerConfig {
+            bind: format!("unix:{}", path),
+            tls: None,
+            auth_mode: None,
+            api_key: None,
+            socket_mode: None,
+            acme: None,
+        }];
+    }
+    Ok(config)
+}
+
+/// 校验配置并输出报告，存在错误时返回配置无效
+async fn validate(options: HeadlessOptions) -> CliResult<()> {
+    let config = match load_config(&options) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("[ERROR] config: {}", e);
+            return Err(CliError::ConfigInvalid(e));
+        }
+    };
+    let report = modules::config_validation::validate_config(&config, true).await;
+    print!("{}", modules::config_validation::format_report(&report));
+    if report.has_errors() {
+        return Err(CliError::ConfigInvalid(t("validation_failed", &[])));
+    }
+    Ok(())
+}
+
+/// 加密导出 / 导入的口令: 环境变量 ANTIGRAVITY_CONFIG_PASSPHRASE，未设置时交互式输入
+fn config_passphrase() -> String {
+    std::env::var("ANTIGRAVITY_CONFIG_PASSPHRASE")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| prompt(&t("config_passphrase_prompt", &[]), ""))
+}
+
+/// 转换配置文件格式；未指定输出路径时写入数据目录并将原文件重命名为 .bak，使新文件生效
+fn config_convert(format: modules::config::ConfigFormat, output: Option<&std::path::Path>) -> CliResult<()> {
+    let config = modules::config::load_app_config().map_err(CliError::ConfigInvalid)?;
+    let source = modules::config::get_config_path().map_err(CliError::Storage)?;
+    let target = match output {
+        Some(path) => path.to_path_buf(),
+        None => source.with_file_name(format.default_file_name()),
+    };
+    if target == source {
+        println!("{}", t("config_convert_unchanged", &[("path", &source.display())]));
+        return Ok(());
+    }
+
+    let content = format.serialize(&config).map_err(CliError::Failed)?;
+    std::fs::write(&target, content)
+        .map_err(|e| CliError::Failed(t("export_write_failed", &[("path", &target.display()), ("error", &e)])))?;
+    println!("{}", t("config_converted", &[("path", &target.display())]));
+
+    if output.is_none() && source.is_file() {
+        let mut backup = source.clone().into_os_string();
+        backup.push(".bak");
+        std::fs::rename(&source, &backup).map_err(|e| CliError::Storage(e.to_string()))?;
+        println!("{}", t("config_convert_backup", &[("path", &std::path::Path::new(&backup).display())]));
+    }
+    Ok(())
+}
+
+/// 导出当前配置到文件 (读取已保存的配置，不含命令行覆盖项)
+fn config_export(path: &std::path::Path, mode: modules::config_export::SecretMode) -> CliResult<()> {
+    use modules::config_export::SecretMode;
+
+    let config = modules::config::load_app_config().map_err(CliError::ConfigInvalid)?;
+    let passphrase = (mode == SecretMode::Encrypted).then(config_passphrase);
+    let content =
+        modules::config_export::export_config(&config, mode, passphrase.as_deref()).map_err(CliError::Failed)?;
+    std::fs::write(path, content)
+        .map_err(|e| CliError::Failed(t("export_write_failed", &[("path", &path.display()), ("error", &e)])))?;
+    println!("{}", t("config_exported", &[("path", &path.display())]));
+    if mode == SecretMode::Plain {
+        println!("{}", t("config_export_plain_warning", &[]));
+    }
+    Ok(())
+}
+
+/// 查看或修改单个配置项；修改前校验新配置，写入后记录变更的键名到审计日志
+async fn config_value(command: &ConfigValueCommand) -> CliResult<()> {
+    let print_value = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => println!("{}", s),
+        other => println!("{}", other),
+    };
+    let updated = match command {
+        ConfigValueCommand::Keys => {
+            let keys = modules::config::list_config_keys().map_err(CliError::ConfigInvalid)?;
+            for info in &keys {
+                println!("{:<48} {:<8} {}", info.key, info.value_type, info.value);
+            }
+            return Ok(());
+        }
+        ConfigValueCommand::Get(key) => {
+            print_value(&modules::config::get_config_value(key).map_err(CliError::Usage)?);
+            return Ok(());
+        }
+        ConfigValueCommand::Set(key, value) => modules::config::set_config_value(key, value),
+        ConfigValueCommand::Unset(key) => modules::config::unset_config_value(key),
+    }
+    .map_err(CliError::Usage)?;
+
+    let report = modules::config_validation::validate_config(&updated, false).await;
+    if report.has_errors() {
+        print!("{}", modules::config_validation::format_report(&report));
+        return Err(CliError::ConfigInvalid(t("validation_failed", &[])));
+    }
+    let previous = modules::config::load_app_config().map_err(CliError::ConfigInvalid)?;
+    modules::config::save_app_config(&updated).map_err(CliError::Storage)?;
+    let keys = modules::audit::record_config_change(modules::audit::AuditActor::Cli, &previous, &updated);
+    if keys.is_empty() {
+        println!("{}", t("config_value_unchanged", &[]));
+    } else {
+        println!("{}", t("config_value_updated", &[("keys", &keys.join(", "))]));
+    }
+    Ok(())
+}
+
+/// 输出客户端工具配置: 内容写到标准输出便于重定向到文件，保存位置提示写到 stderr
+async fn export_client_config(options: &HeadlessOptions, tool: modules::client_config::ClientTool) -> CliResult<()> {
+    let config = load_config(options).map_err(CliError::ConfigInvalid)?.proxy;
+    let export = modules::client_config::export_client_config(tool, &config)
+        .await
+        .map_err(CliError::ConfigInvalid)?;
+    eprintln!("{}", t("client_config_target", &[("target", &export.target)]));
+    println!("{}", export.content.trim_end());
+    Ok(())
+}
+
+/// 导入配置: 还原密钥字段、校验并显示差异，确认后写入
+async fn config_import(path: &std::path::Path, dry_run: bool, assume_yes: bool) -> CliResult<()> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| CliError::NotFound(t("config_import_read_failed", &[("path", &path.display()), ("error", &e)])))?;
+    let current = modules::config::load_app_config().map_err(CliError::ConfigInvalid)?;
+    let passphrase = modules::config_export::requires_passphrase(&content).then(config_passphrase);
+    let plan = modules::config_export::prepare_import(&content, &current, passphrase.as_deref())
+        .map_err(CliError::ConfigInvalid)?;
+
+    let report = modules::config_validation::validate_config(&plan.config, false).await;
+    print!("{}", modules::config_validation::format_report(&report));
+    for path in &plan.missing_secrets {
+        println!("{}", t("config_import_missing_secret", &[("path", path)]));
+    }
+    if plan.changes.is_empty() {
+        println!("{}", t("config_import_no_changes", &[]));
+        return Ok(());
+    }
+    println!("{}", t("config_import_changes", &[("count", &plan.changes.len())]));
+    print!("{}", modules::config_export::format_changes(&plan.changes));
+    if report.has_errors() {
+        return Err(CliError::ConfigInvalid(t("validation_failed", &[])));
+    }
+    if dry_run || !(assume_yes || confirm(&t("config_import_confirm", &[]), false)) {
+        return Ok(());
+    }
+
+    modules::config::save_app_config(&plan.config).map_err(CliError::Storage)?;
+    modules::audit::record(
+        modules::audit::AuditActor::Cli,
+        modules::audit::AuditAction::ConfigChange,
+        Some("import"),
+        Some(serde_json::json!({
+            "source": path.display().to_string(),
+            "changed": plan.changes.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(),
+        })),
+    );
+    println!("{}", t("config_imported", &[("count", &plan.changes.len())]));
+    Ok(())
+}
+
+/// 安装后台服务: 服务以无头模式启动，并带上本次指定的端口、配置路径与反代参数
+fn install_service(options: &HeadlessOptions) -> CliResult<()> {
+    // 服务的工作目录不确定，路径参数一律转为绝对路径
+    let absolute = |path: &PathBuf| {
+        std::path::absolute(path)
+            .unwrap_or_else(|_| path.clone())
+            .to_string_lossy()
+            .to_string()
+    };
+    let mut extra_args = Vec::new();
+    if let Some(path) = &options.config_path {
+        extra_args.extend(["--config".to_string(), absolute(path)]);
+    }
+    if let Some(dir) = &options.data_dir {
+        extra_args.extend(["--data-dir".to_string(), absolute(dir)]);
+    }
+    if options.allow_lan {
+        extra_args.push("--allow-lan".to_string());
+    }
+    if let Some(uds) = &options.uds {
+        extra_args.extend(["--uds".to_string(), absolute(&PathBuf::from(uds))]);
+    }
+    if options.warmup {
+        extra_args.push("--warmup".to_string());
+    }
+    if options.strict_mappings {
+        extra_args.push("--strict-mappings".to_string());
+    }
+    if options.drain_timeout != Duration::from_secs(DEFAULT_DRAIN_TIMEOUT_SECS) {
+        extra_args.extend(["--drain-timeout".to_string(), options.drain_timeout.as_secs().to_string()]);
+    }
+
+    let result = modules::service::install_service(&modules::service::ServiceOptions {
+        port: options.port,
+        extra_args,
+    })
+    .map_err(CliError::Failed)?;
+    if result.platform == "windows-task" {
+        // 计划任务只在用户登录后运行，不能称为服务
+        println!("{}", t("service_installed_logon_task", &[]));
+    } else {
+        println!("{}", t("service_installed", &[("platform", &result.platform)]));
+    }
+    if let Some(path) = &result.path {
+        println!("  {}", path);
+    }
+    println!("  {}", result.command.join(" "));
+    if result.linger == Some(false) {
+        let user = std::env::var("USER").unwrap_or_else(|_| "$USER".to_string());
+        println!("{}", t("service_linger_hint", &[("user", &user)]));
+    }
+    Ok(())
+}
+
+/// 部署诊断: 逐项检查并输出报告，存在失败项时返回错误
+async fn doctor(options: &HeadlessOptions, json: bool) -> CliResult<()> {
+    let config = load_config(options).map_err(CliError::ConfigInvalid)?;
+    let report = crate::proxy::doctor::run(&config).await;
+    if json {
+        let output = serde_json::to_string_pretty(&report).map_err(|e| CliError::Failed(e.to_string()))?;
+        println!("{}", output);
+    } else {
+        print!("{}", crate::proxy::doctor::format_report(&report));
+    }
+    if report.has_failures() {
+        return Err(CliError::Failed(t("doctor_failed", &[])));
+    }
+    Ok(())
+}
+
+/// 输出最近的请求日志，`--follow` 时连接运行中的反代实时跟踪
+async fn logs_tail(options: HeadlessOptions) -> CliResult<()> {
+    use crate::proxy::monitor::{format_log_line, LogFilter, ProxyRequestLog};
+    use futures::StreamExt;
+
+    let Some(tail) = options.logs_tail.as_ref() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--logs-tail")])));
+    };
+    let filter = LogFilter::parse(&tail.filters).map_err(CliError::Usage)?;
+
+    // 1. 历史日志 (直接读取数据库，GUI 未运行时也可用)
+    if tail.lines > 0 {
+        match modules::proxy_db::get_logs(tail.lines) {
+            Ok(logs) => {
+                for log in logs.iter().rev().filter(|log| filter.matches(log)) {
+                    println!("{}", format_log_line(log));
+                }
+            }
+            Err(e) => eprintln!("{}", t("history_read_failed", &[("error", &e)])),
+        }
+    }
+    if !tail.follow {
+        return Ok(());
+    }
+
+    // 2. 实时跟踪 (订阅反代的 SSE 日志流)
+    let config = load_config(&options).map_err(CliError::ConfigInvalid)?.proxy;
+    let api_key = admin_api_key(&config).map_err(CliError::Auth)?;
+    let base = tail
+        .url
+        .clone()
+        .unwrap_or_else(|| config.admin_base_url());
+    let url = format!("{}/admin/logs/stream", base.trim_end_matches('/'));
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .bearer_auth(&api_key)
+        .query(&[("filter", tail.filters.join(","))])
+        .send()
+        .await
+        .map_err(|e| CliError::Network(t("proxy_unreachable", &[("url", &url), ("error", &e)])))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(CliError::from_status(status, t("log_stream_failed", &[("status", &status)])));
+    }
+
+    let mut stream = response.bytes_stream();
+    let mut buffer = Vec::new();
+    loop {
+        tokio::select! {
+            chunk = stream.next() => match chunk {
+                Some(Ok(bytes)) => {
+                    buffer.extend_from_slice(&bytes);
+                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=pos).collect();
+                        let line = String::from_utf8_lossy(&line);
+                        if let Some(data) = line.trim().strip_prefix("data:") {
+                            if let Ok(log) = serde_json::from_str::<ProxyRequestLog>(data.trim()) {
+                                println!("{}", format_log_line(&log));
+                            }
+                        } else if let Some(comment) = line.trim().strip_prefix(':') {
+                            if !comment.trim().is_empty() {
+                                eprintln!("{}", comment.trim());
+                            }
+                        }
+                    }
+                }
+                Some(Err(e)) => return Err(CliError::Network(t("log_stream_interrupted", &[("error", &e)]))),
+                None => return Ok(()),
+            },
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// 通过运行中的反代重放一条请求，输出状态码与响应差异；与原始响应不一致时返回一般失败
+async fn logs_replay(options: HeadlessOptions) -> CliResult<()> {
+    let Some((id, account, url)) = options.logs_replay.as_ref() else {
+        return Err(CliError::Usage(t("missing_flag", &[("flag", &"--logs-replay")])));
+    };
+    let config = load_config(&options).map_err(CliError::ConfigInvalid)?.proxy;
+    let api_key = admin_api_key(&config).map_err(CliError::Auth)?;
+    let base = url
+        .clone()
+        .unwrap_or_else(|| config.admin_base_url());
+    let endpoint = format!("{}/admin/logs/{}/replay", base.trim_end_matches('/'), id);
+
+    let mut request = reqwest::Client::new()
+        .post(&endpoint)
+        .bearer_auth(&api_key);
+    if let Some(account) = account {
+        request = request.query(&[("account", account)]);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| CliError::Network(t("proxy_unreachable", &[("url", &endpoint), ("error", &e)])))?;
+    let status = response.status();
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| CliError::from_status(status, t("replay_parse_failed", &[("error", &e)])))?;
+    if !status.is_success() {
+        let message = body["error"]["message"].as_str().unwrap_or("unknown error");
+        return Err(CliError::from_status(
+            status,
+            t("replay_failed", &[("status", &status), ("message", &message)]),
+        ));
+    }
+
+    let summary = t(
+        "replay_summary",
+        &[
+            ("method", &body["method"].as_str().unwrap_or("-")),
+            ("url", &body["url"].as_str().unwrap_or("-")),
+            ("status", &body["status"]),
+            ("original", &body["original_status"]),
+            ("duration", &body["duration"]),
+        ],
+    );
+    let account = body["account"]
+        .as_str()
+        .map(|a| t("replay_account", &[("account", &a)]))
+        .unwrap_or_default();
+    println!("{}{}", summary, account);
+    if body["identical"].as_bool().unwrap_or(false) {
+        println!("{}", t("replay_identical", &[]));
+        return Ok(());
+    }
+    for line in body["diff"].as_array().into_iter().flatten() {
+        println!("{}", line.as_str().unwrap_or_default());
+    }
+    Err(CliError::Failed(t("replay_differs", &[])))
+}
+
+/// 反代配置 (含命令行覆盖)；鉴权开启但未配置密钥时生成并保存初始密钥
+fn serve_config(options: &HeadlessOptions) -> Result<crate::proxy::ProxyConfig, String> {
+    let mut config = load_config(options)?.proxy;
+    if config.api_key.is_empty()
+        && !matches!(
+            crate::proxy::ProxySecurityConfig::from_proxy_config(&config).effective_auth_mode(),
+            crate::proxy::ProxyAuthMode::Off
+        )
+    {
+        config.api_key = create_initial_api_key()?;
+    }
+    Ok(config)
+}
+
+async fn serve(options: HeadlessOptions) -> Result<(), String> {
+    info!(
+        "以无头模式启动，配置档: {}，配置文件: {:?}",
+        modules::profile::get_active_profile(),
+        modules::config::get_config_path()?
+    );
+
+    let options = Arc::new(options);
+    let config = serve_config(&options)?;
+    let state = crate::commands::proxy::ProxyServiceState::new();
+    let active_accounts = crate::commands::proxy::start_instance(
+        &state,
+        &config,
+        None,
+        modules::audit::AuditActor::Cli,
+    )
+    .await?;
+    let listen = match &options.uds {
+        Some(path) => format!("unix:{}", path),
+        None => format!("{}:{}", config.get_bind_address(), config.port),
+    };
+    info!("反代服务已启动: {}，可用账号 {} 个", listen, active_accounts);
+    let loader_options = options.clone();
+    crate::commands::proxy::ensure_control_plane(
+        &state,
+        &config.grpc,
+        None,
+        Arc::new(move || serve_config(&loader_options)),
+    )
+    .await?;
+
+    wait_for_shutdown_signal().await;
+
+    // 反代可能已通过 gRPC 管理接口停止
+    if state.instance.read().await.is_some() {
+        info!(
+            "停止接收新连接，等待在途请求完成 (最长 {} 秒)",
+            options.drain_timeout.as_secs()
+        );
+        crate::commands::proxy::stop_instance(
+            &state,
+            Some(options.drain_timeout),
+            modules::audit::AuditActor::Cli,
+        )
+        .await?;
+    }
+    info!("反代服务已停止");
+    Ok(())
+}
+
+/// 等待 SIGTERM (容器停止) 或 SIGINT (Ctrl+C)
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => info!("收到 SIGINT"),
+                _ = sigterm.recv() => info!("收到 SIGTERM"),
+            }
+        }
+        Err(e) => {
+            error!("注册 SIGTERM 处理失败: {}，仅响应 Ctrl+C", e);
+            let _ = tokio::signal::ctrl_c().await;
+            info!("收到 SIGINT");
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+    info!("收到 Ctrl+C");
+}
@@ -3,7 +3,13 @@ mod modules;
 mod commands;
 mod utils;
 mod proxy;  // 反代服务模块
+mod headless;  // 无头模式 (容器部署)
 pub mod error;
+// 账号与配置的存储后端，嵌入方可通过 `storage::set_backend` 替换
+pub use modules::storage;
+// 嵌入式反代 API
+pub use proxy::builder::{EmbeddedProxy, ProxyBuilder};
+pub use proxy::ProxyConfig;
 
 use tauri::Manager;
 use modules::logger;
@@ -17,8 +23,33 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // 解析 --profile 参数 (需在首次访问数据目录之前)
+    let profile_result = modules::profile::parse_profile_arg(args.iter().cloned())
+        .map(|name| modules::profile::set_process_profile(&name));
+
+    // 无头模式: 不启动 Tauri，仅运行反代服务
+    if headless::is_headless(&args) {
+        if let Some(Err(e)) = profile_result {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+        std::process::exit(headless::run(&args));
+    }
+
     // 初始化日志
     logger::init_logger();
+    if let Some(Err(e)) = profile_result {
+        error!("配置档参数无效，已回退到默认配置档: {}", e);
+    }
+    let profile = modules::profile::get_active_profile();
+    info!("当前配置档: {}", profile);
+
+    // 单实例插件以应用标识区分实例，按配置档区分后 `--profile work` 不会被其他配置档的窗口拦截
+    let mut context = tauri::generate_context!();
+    let identifier = modules::profile::instance_identifier(&context.config().identifier, &profile);
+    context.config_mut().identifier = identifier;
     
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
@@ -42,14 +73,26 @@ pub fn run() {
             info!("Setup starting...");
             modules::tray::create_tray(app.handle())?;
             info!("Tray created");
+            // 反代事件 (账号状态、配置热更新) 转发给前端
+            crate::proxy::events::bridge_to_tauri(app.handle().clone());
             
             // 自动启动反代服务
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 // 加载配置
                 if let Ok(config) = modules::config::load_app_config() {
+                    crate::proxy::upstream::profiles::configure(&config.proxy.client_profiles);
+                    let state = handle.state::<commands::proxy::ProxyServiceState>();
+                    // gRPC 管理接口随应用启动，反代可通过它远程启停
+                    if let Err(e) = commands::proxy::ensure_control_plane(
+                        &state,
+                        &config.proxy.grpc,
+                        Some(handle.clone()),
+                        commands::proxy::gui_config_loader(),
+                    ).await {
+                        error!("启动 gRPC 管理接口失败: {}", e);
+                    }
                     if config.proxy.auto_start {
-                        let state = handle.state::<commands::proxy::ProxyServiceState>();
                         // 尝试启动服务
                         if let Err(e) = commands::proxy::start_proxy_service(
                             config.proxy,
@@ -84,6 +127,9 @@ pub fn run() {
             commands::add_account,
             commands::delete_account,
             commands::delete_accounts,
+            commands::list_trashed_accounts,
+            commands::restore_account,
+            commands::purge_trashed_accounts,
             commands::reorder_accounts,
             commands::switch_account,
             commands::get_current_account,
@@ -93,6 +139,12 @@ pub fn run() {
             // 配置命令
             commands::load_config,
             commands::save_config,
+            commands::list_config_keys,
+            commands::get_config_value,
+            commands::set_config_value,
+            commands::unset_config_value,
+            commands::validate_config,
+            commands::get_audit_log,
             // 新增命令
             commands::prepare_oauth_url,
             commands::start_oauth_login,
@@ -117,9 +169,15 @@ pub fn run() {
             commands::proxy::get_proxy_status,
             commands::proxy::get_proxy_stats,
             commands::proxy::get_proxy_logs,
+            commands::proxy::get_proxy_sessions,
+            commands::proxy::get_proxy_session_logs,
+            commands::proxy::get_usage_report,
+            commands::proxy::get_usage_trend,
             commands::proxy::set_proxy_monitor_enabled,
             commands::proxy::clear_proxy_logs,
             commands::proxy::generate_api_key,
+            commands::proxy::rotate_api_key,
+            commands::proxy::export_client_config,
             commands::proxy::reload_proxy_accounts,
             commands::proxy::update_model_mapping,
             commands::proxy::fetch_zai_models,
@@ -129,7 +187,15 @@ pub fn run() {
             // Autostart 命令
             commands::autostart::toggle_auto_launch,
             commands::autostart::is_auto_launch_enabled,
+            // 后台服务命令
+            commands::service::install_proxy_service,
+            commands::service::uninstall_proxy_service,
+            // 配置档命令
+            commands::profile::list_profiles,
+            commands::profile::create_profile,
+            commands::profile::switch_profile,
+            commands::profile::get_active_profile,
         ])
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
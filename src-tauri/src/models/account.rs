@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use super::{token::TokenData, quota::QuotaData};
 
 /// 账号数据结构
@@ -27,10 +28,61 @@ pub struct Account {
     /// Unix timestamp when the proxy was disabled.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_disabled_at: Option<i64>,
+    /// Free-form note, e.g. where the token came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Arbitrary key/value metadata, e.g. source or expected expiry.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    /// Per-account device identity sent upstream (generated lazily for older accounts).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceIdentity>,
+    /// Upstream client header profile; `None` uses the global default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_profile: Option<String>,
+    /// Most recent upstream errors for this account, newest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub last_errors: Vec<AccountError>,
+    /// Access-token refresh history, used by the token health report.
+    #[serde(default, skip_serializing_if = "RefreshStats::is_empty")]
+    pub refresh: RefreshStats,
     pub created_at: i64,
     pub last_used: i64,
 }
 
+/// Access-token refresh bookkeeping for an account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshStats {
+    /// Unix timestamp of the last successful access-token refresh.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<i64>,
+    /// Unix timestamp of the last failed refresh.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failure_at: Option<i64>,
+    /// Consecutive failed refreshes since the last success.
+    #[serde(default)]
+    pub failure_streak: u32,
+    /// When the current refresh_token was obtained (login or credential update); unknown for older accounts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_obtained_at: Option<i64>,
+}
+
+impl RefreshStats {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn record_success(&mut self, now: i64) {
+        self.last_success_at = Some(now);
+        self.failure_streak = 0;
+    }
+
+    pub fn record_failure(&mut self, now: i64) {
+        self.last_failure_at = Some(now);
+        self.failure_streak += 1;
+    }
+}
+
 impl Account {
     pub fn new(id: String, email: String, token: TokenData) -> Self {
         let now = chrono::Utc::now().timestamp();
@@ -46,6 +98,15 @@ impl Account {
             proxy_disabled: false,
             proxy_disabled_reason: None,
             proxy_disabled_at: None,
+            note: None,
+            metadata: HashMap::new(),
+            device: Some(DeviceIdentity::generate()),
+            client_profile: None,
+            last_errors: Vec::new(),
+            refresh: RefreshStats {
+                token_obtained_at: Some(now),
+                ..Default::default()
+            },
             created_at: now,
             last_used: now,
         }
@@ -60,6 +121,45 @@ impl Account {
     }
 }
 
+/// 账号最近的一次上游错误
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountError {
+    /// Unix 秒
+    pub timestamp: i64,
+    /// 上游 HTTP 状态码 (token 刷新失败等非 HTTP 错误为空)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+/// 账号独立的设备标识，避免多个账号在上游共用同一设备指纹
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    /// 机器 ID (64 位十六进制，与 IDE 的 machineId 格式一致)
+    pub machine_id: String,
+    /// 上游会话 ID
+    pub session_id: String,
+    /// 生成 (轮换) 时间
+    pub created_at: i64,
+}
+
+impl DeviceIdentity {
+    pub fn generate() -> Self {
+        use sha2::{Digest, Sha256};
+
+        let seed = format!("{}{}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        let machine_id = Sha256::digest(seed.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Self {
+            machine_id,
+            session_id: uuid::Uuid::new_v4().to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
 /// 账号索引数据（accounts.json）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountIndex {
@@ -3,7 +3,7 @@ pub mod token;
 pub mod quota;
 pub mod config;
 
-pub use account::{Account, AccountIndex, AccountSummary};
+pub use account::{Account, AccountError, AccountIndex, AccountSummary, DeviceIdentity, RefreshStats};
 pub use token::TokenData;
-pub use quota::QuotaData;
+pub use quota::{QuotaData, SubscriptionTier};
 pub use config::AppConfig;
@@ -6,6 +6,138 @@ pub struct ModelQuota {
     pub name: String,
     pub percentage: i32,  // 剩余百分比 0-100
     pub reset_time: String,
+    /// 配额重置时间 (Unix 秒)，由 `reset_time` 解析而来
+    #[serde(default)]
+    pub reset_at: Option<i64>,
+    /// 上游报告的模型能力，旧数据为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<ModelLimits>,
+}
+
+/// 上游 (fetchAvailableModels) 报告的模型能力，未报告的字段为 None
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelLimits {
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    #[serde(default)]
+    pub max_output_tokens: Option<u64>,
+    #[serde(default)]
+    pub supports_images: Option<bool>,
+    #[serde(default)]
+    pub supports_thinking: Option<bool>,
+}
+
+impl ModelLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl ModelQuota {
+    /// 配额重置时间戳 (兼容旧数据：缺少 `reset_at` 时现场解析 `reset_time`)
+    pub fn reset_timestamp(&self) -> Option<i64> {
+        self.reset_at.or_else(|| parse_reset_time(&self.reset_time))
+    }
+
+    /// 距离配额重置的秒数，已重置或未知时返回 `None`
+    pub fn resets_in(&self, now: i64) -> Option<i64> {
+        self.reset_timestamp()
+            .map(|ts| ts - now)
+            .filter(|secs| *secs > 0)
+    }
+
+    /// 配额是否已耗尽
+    pub fn is_exhausted(&self) -> bool {
+        self.percentage <= 0
+    }
+}
+
+/// 解析上游返回的配额重置时间 (RFC3339) 为 Unix 时间戳
+pub fn parse_reset_time(reset_time: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(reset_time.trim())
+        .ok()
+        .map(|dt| dt.timestamp())
+}
+
+/// 格式化倒计时，如 `2h05m`、`4m30s`
+pub fn format_countdown(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (days, hours, minutes, seconds) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60, secs % 60);
+    if days > 0 {
+        format!("{}d{:02}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h{:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m{:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// 订阅等级
+///
+/// 上游返回的等级 ID 形如 `free-tier`、`g1-pro-tier`，统一归一为 FREE / PRO / ULTRA；
+/// 无法识别的 ID 原样保留
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum SubscriptionTier {
+    Free,
+    Pro,
+    Ultra,
+    Other(String),
+}
+
+impl SubscriptionTier {
+    /// 解析等级名称或上游等级 ID (不区分大小写)
+    pub fn parse(value: &str) -> Self {
+        let lower = value.trim().to_ascii_lowercase();
+        if lower.contains("ultra") {
+            Self::Ultra
+        } else if lower.contains("pro") {
+            Self::Pro
+        } else if lower.contains("free") || lower.contains("standard") || lower.contains("legacy") {
+            Self::Free
+        } else {
+            Self::Other(value.trim().to_string())
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Free => "FREE",
+            Self::Pro => "PRO",
+            Self::Ultra => "ULTRA",
+            Self::Other(raw) => raw,
+        }
+    }
+
+    /// 调度优先级，数值越小越优先 (ULTRA/PRO 重置快，优先消耗；FREE 用于兜底)
+    pub fn priority(&self) -> u8 {
+        match self {
+            Self::Ultra => 0,
+            Self::Pro => 1,
+            Self::Free => 2,
+            Self::Other(_) => 3,
+        }
+    }
+}
+
+impl From<String> for SubscriptionTier {
+    fn from(value: String) -> Self {
+        Self::parse(&value)
+    }
+}
+
+impl From<SubscriptionTier> for String {
+    fn from(tier: SubscriptionTier) -> Self {
+        tier.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for SubscriptionTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// 配额数据结构
@@ -17,7 +149,7 @@ pub struct QuotaData {
     pub is_forbidden: bool,
     /// 订阅等级 (FREE/PRO/ULTRA)
     #[serde(default)]
-    pub subscription_tier: Option<String>,
+    pub subscription_tier: Option<SubscriptionTier>,
 }
 
 impl QuotaData {
@@ -30,13 +162,25 @@ impl QuotaData {
         }
     }
 
-    pub fn add_model(&mut self, name: String, percentage: i32, reset_time: String) {
+    pub fn add_model(&mut self, name: String, percentage: i32, reset_time: String, limits: Option<ModelLimits>) {
+        let reset_at = parse_reset_time(&reset_time);
         self.models.push(ModelQuota {
             name,
             percentage,
             reset_time,
+            reset_at,
+            limits: limits.filter(|l| !l.is_empty()),
         });
     }
+
+    /// 最近一次配额重置时间 (仅统计尚未到达的重置时间)
+    pub fn next_reset(&self, now: i64) -> Option<i64> {
+        self.models
+            .iter()
+            .filter_map(|m| m.reset_timestamp())
+            .filter(|ts| *ts > now)
+            .min()
+    }
 }
 
 impl Default for QuotaData {
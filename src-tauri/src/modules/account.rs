@@ -5,6 +5,7 @@ use uuid::Uuid;
 
 use crate::models::{Account, AccountIndex, AccountSummary, TokenData, QuotaData};
 use crate::modules;
+use crate::modules::storage::{self, Collection, INDEX_KEY};
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 
@@ -13,14 +14,32 @@ static ACCOUNT_INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
 // ... existing constants ...
 const DATA_DIR: &str = ".antigravity_tools";
-const ACCOUNTS_INDEX: &str = "accounts.json";
 const ACCOUNTS_DIR: &str = "accounts";
 
 // ... existing functions get_data_dir, get_accounts_dir, load_account_index, save_account_index ...
-/// 获取数据目录路径
-pub fn get_data_dir() -> Result<PathBuf, String> {
+/// 数据根目录覆盖 (无头模式 `--data-dir` / `ANTIGRAVITY_DATA_DIR`，例如容器挂载卷)
+static DATA_DIR_OVERRIDE: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
+
+/// 指定数据根目录 (需在首次访问数据目录之前调用)
+pub fn set_data_dir_override(path: PathBuf) -> Result<(), String> {
+    DATA_DIR_OVERRIDE
+        .set(path)
+        .map_err(|_| "数据目录已初始化，无法覆盖".to_string())
+}
+
+/// 获取数据根目录路径 (所有配置档共享)
+pub fn get_root_data_dir() -> Result<PathBuf, String> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Ok(dir.clone());
+    }
     let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
-    let data_dir = home.join(DATA_DIR);
+    Ok(home.join(DATA_DIR))
+}
+
+/// 获取数据目录路径 (当前配置档)
+pub fn get_data_dir() -> Result<PathBuf, String> {
+    let profile = crate::modules::profile::get_active_profile();
+    let data_dir = crate::modules::profile::get_profile_dir(&profile)?;
     
     // 确保目录存在
     if !data_dir.exists() {
@@ -46,17 +65,13 @@ pub fn get_accounts_dir() -> Result<PathBuf, String> {
 
 /// 加载账号索引
 pub fn load_account_index() -> Result<AccountIndex, String> {
-    let data_dir = get_data_dir()?;
-    let index_path = data_dir.join(ACCOUNTS_INDEX);
-    // modules::logger::log_info(&format!("正在加载账号索引: {:?}", index_path)); // Optional: reduce noise
-    
-    if !index_path.exists() {
+    let Some(content) = storage::backend()
+        .read(Collection::Meta, INDEX_KEY)
+        .map_err(|e| format!("读取账号索引失败: {}", e))?
+    else {
         crate::modules::logger::log_warn("账号索引文件不存在");
         return Ok(AccountIndex::new());
-    }
-    
-    let content = fs::read_to_string(&index_path)
-        .map_err(|e| format!("读取账号索引失败: {}", e))?;
+    };
     
     let index: AccountIndex = serde_json::from_str(&content)
         .map_err(|e| format!("解析账号索引失败: {}", e))?;
@@ -65,50 +80,62 @@ pub fn load_account_index() -> Result<AccountIndex, String> {
     Ok(index)
 }
 
-/// 保存账号索引 (原子化写入)
+/// 保存账号索引 (文件存储为原子化写入)
 pub fn save_account_index(index: &AccountIndex) -> Result<(), String> {
-    let data_dir = get_data_dir()?;
-    let index_path = data_dir.join(ACCOUNTS_INDEX);
-    let temp_path = data_dir.join(format!("{}.tmp", ACCOUNTS_INDEX));
-    
     let content = serde_json::to_string_pretty(index)
         .map_err(|e| format!("序列化账号索引失败: {}", e))?;
-    
-    // 写入临时文件
-    fs::write(&temp_path, content)
-        .map_err(|e| format!("写入临时索引文件失败: {}", e))?;
-        
-    // 原子重命名
-    fs::rename(temp_path, index_path)
-        .map_err(|e| format!("替换索引文件失败: {}", e))
+
+    storage::backend()
+        .write(Collection::Meta, INDEX_KEY, &content)
+        .map_err(|e| format!("保存账号索引失败: {}", e))
 }
 
 /// 加载账号数据
 pub fn load_account(account_id: &str) -> Result<Account, String> {
-    let accounts_dir = get_accounts_dir()?;
-    let account_path = accounts_dir.join(format!("{}.json", account_id));
+    let content = storage::backend()
+        .read(Collection::Accounts, account_id)
+        .map_err(|e| format!("读取账号数据失败: {}", e))?
+        .ok_or_else(|| format!("账号不存在: {}", account_id))?;
     
-    if !account_path.exists() {
-        return Err(format!("账号不存在: {}", account_id));
+    let mut account: Account = serde_json::from_str(&content)
+        .map_err(|e| format!("解析账号数据失败: {}", e))?;
+
+    // 旧账号没有设备标识，首次加载时生成并保存
+    if account.device.is_none() {
+        account.device = Some(crate::models::DeviceIdentity::generate());
+        save_account(&account)?;
+    } else {
+        modules::device::register(&account);
     }
-    
-    let content = fs::read_to_string(&account_path)
-        .map_err(|e| format!("读取账号数据失败: {}", e))?;
-    
-    serde_json::from_str(&content)
-        .map_err(|e| format!("解析账号数据失败: {}", e))
+    Ok(account)
 }
 
 /// 保存账号数据
 pub fn save_account(account: &Account) -> Result<(), String> {
-    let accounts_dir = get_accounts_dir()?;
-    let account_path = accounts_dir.join(format!("{}.json", account.id));
-    
     let content = serde_json::to_string_pretty(account)
         .map_err(|e| format!("序列化账号数据失败: {}", e))?;
-    
-    fs::write(&account_path, content)
-        .map_err(|e| format!("保存账号数据失败: {}", e))
+
+    storage::backend()
+        .write(Collection::Accounts, &account.id, &content)
+        .map_err(|e| format!("保存账号数据失败: {}", e))?;
+    modules::device::register(account);
+    Ok(())
+}
+
+/// 设置账号使用的客户端标识档案 (None 表示使用全局默认档案)
+pub fn set_client_profile(account_id: &str, profile: Option<&str>) -> Result<Account, String> {
+    let mut account = load_account(account_id)?;
+    account.client_profile = profile.map(str::to_string);
+    save_account(&account)?;
+    Ok(account)
+}
+
+/// 重新生成账号的设备标识 (machine_id 与会话 ID)
+pub fn rotate_device(account_id: &str) -> Result<Account, String> {
+    let mut account = load_account(account_id)?;
+    account.device = Some(crate::models::DeviceIdentity::generate());
+    save_account(&account)?;
+    Ok(account)
 }
 
 /// 列出所有账号
@@ -212,6 +239,13 @@ pub fn upsert_account(email: String, name: Option<String>, token: TokenData) ->
                 let old_refresh_token = account.token.refresh_token.clone();
                 account.token = token;
                 account.name = name.clone();
+                // 换了新的 refresh_token: 重新开始统计刷新记录
+                if account.token.refresh_token != old_refresh_token {
+                    account.refresh = crate::models::RefreshStats {
+                        token_obtained_at: Some(chrono::Utc::now().timestamp()),
+                        ..Default::default()
+                    };
+                }
                 // If an account was previously disabled (e.g. invalid_grant), any explicit token upsert
                 // should re-enable it (user manually updated credentials in the UI).
                 if account.disabled
@@ -280,16 +314,8 @@ pub fn delete_account(account_id: &str) -> Result<(), String> {
     
     save_account_index(&index)?;
     
-    // 删除账号文件
-    let accounts_dir = get_accounts_dir()?;
-    let account_path = accounts_dir.join(format!("{}.json", account_id));
-    
-    if account_path.exists() {
-        fs::remove_file(&account_path)
-            .map_err(|e| format!("删除账号文件失败: {}", e))?;
-    }
-    
-    Ok(())
+    // 账号文件移入回收站 (可恢复)
+    move_to_trash(account_id, chrono::Utc::now().timestamp())
 }
 
 /// 批量删除账号 (原子性操作索引)
@@ -297,7 +323,7 @@ pub fn delete_accounts(account_ids: &[String]) -> Result<(), String> {
     let _lock = ACCOUNT_INDEX_LOCK.lock().map_err(|e| format!("获取锁失败: {}", e))?;
     let mut index = load_account_index()?;
     
-    let accounts_dir = get_accounts_dir()?;
+    let now = chrono::Utc::now().timestamp();
     
     for account_id in account_ids {
         // 从索引中移除
@@ -308,10 +334,9 @@ pub fn delete_accounts(account_ids: &[String]) -> Result<(), String> {
             index.current_account_id = None;
         }
         
-        // 删除账号文件
-        let account_path = accounts_dir.join(format!("{}.json", account_id));
-        if account_path.exists() {
-            let _ = fs::remove_file(&account_path);
+        // 账号文件移入回收站
+        if let Err(e) = move_to_trash(account_id, now) {
+            crate::modules::logger::log_warn(&format!("账号 {} 移入回收站失败: {}", account_id, e));
         }
     }
     
@@ -323,6 +348,117 @@ pub fn delete_accounts(account_ids: &[String]) -> Result<(), String> {
     save_account_index(&index)
 }
 
+/// 回收站中的账号 (删除时间 + 完整账号数据，含 refresh_token)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TrashedAccount {
+    /// 删除时间 (Unix 秒)
+    pub deleted_at: i64,
+    pub account: Account,
+}
+
+/// 将账号文件移入回收站；账号文件损坏无法解析时直接删除
+fn move_to_trash(account_id: &str, now: i64) -> Result<(), String> {
+    let backend = storage::backend();
+    if !backend.exists(Collection::Accounts, account_id)? {
+        return Ok(());
+    }
+
+    match load_account(account_id) {
+        Ok(account) => {
+            let entry = TrashedAccount { deleted_at: now, account };
+            let content = serde_json::to_string_pretty(&entry)
+                .map_err(|e| format!("序列化回收站数据失败: {}", e))?;
+            backend
+                .write(Collection::Trash, account_id, &content)
+                .map_err(|e| format!("写入回收站失败: {}", e))?;
+        }
+        Err(e) => {
+            crate::modules::logger::log_warn(&format!("账号文件无法解析，直接删除: {}", e));
+        }
+    }
+
+    backend
+        .remove(Collection::Accounts, account_id)
+        .map(|_| ())
+        .map_err(|e| format!("删除账号文件失败: {}", e))
+}
+
+/// 列出回收站中的账号，最近删除的在前
+pub fn list_trash() -> Result<Vec<TrashedAccount>, String> {
+    let backend = storage::backend();
+    let keys = backend
+        .keys(Collection::Trash)
+        .map_err(|e| format!("读取回收站失败: {}", e))?;
+
+    let mut trashed = Vec::new();
+    for key in keys {
+        match backend
+            .read(Collection::Trash, &key)
+            .and_then(|c| c.ok_or_else(|| "文件已被删除".to_string()))
+            .and_then(|c| serde_json::from_str::<TrashedAccount>(&c).map_err(|e| e.to_string()))
+        {
+            Ok(item) => trashed.push(item),
+            Err(e) => crate::modules::logger::log_warn(&format!("跳过无法解析的回收站文件 {}: {}", key, e)),
+        }
+    }
+    trashed.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(trashed)
+}
+
+/// 从回收站恢复账号 (邮箱已重新添加时拒绝恢复，避免重复)
+pub fn restore_account(account_id: &str) -> Result<Account, String> {
+    let _lock = ACCOUNT_INDEX_LOCK.lock().map_err(|e| format!("获取锁失败: {}", e))?;
+    let backend = storage::backend();
+    let content = backend
+        .read(Collection::Trash, account_id)
+        .map_err(|e| format!("读取回收站失败: {}", e))?
+        .ok_or_else(|| format!("回收站中找不到账号 ID: {}", account_id))?;
+    let entry: TrashedAccount = serde_json::from_str(&content)
+        .map_err(|e| format!("解析回收站数据失败: {}", e))?;
+    let account = entry.account;
+
+    let mut index = load_account_index()?;
+    if index.accounts.iter().any(|s| s.id == account.id || s.email == account.email) {
+        return Err(format!("账号已存在: {}", account.email));
+    }
+
+    save_account(&account)?;
+    index.accounts.push(AccountSummary {
+        id: account.id.clone(),
+        email: account.email.clone(),
+        name: account.name.clone(),
+        created_at: account.created_at,
+        last_used: account.last_used,
+    });
+    if index.current_account_id.is_none() {
+        index.current_account_id = Some(account.id.clone());
+    }
+    save_account_index(&index)?;
+
+    backend
+        .remove(Collection::Trash, account_id)
+        .map_err(|e| format!("清理回收站文件失败: {}", e))?;
+    Ok(account)
+}
+
+/// 永久删除回收站中的账号；`older_than_secs` 为空时清空回收站。返回被删除的账号
+pub fn purge_trash(older_than_secs: Option<i64>, now: i64) -> Result<Vec<TrashedAccount>, String> {
+    let backend = storage::backend();
+    let cutoff = older_than_secs.map(|secs| now - secs);
+
+    let mut purged = Vec::new();
+    for item in list_trash()? {
+        if cutoff.map_or(false, |cutoff| item.deleted_at > cutoff) {
+            continue;
+        }
+        backend
+            .remove(Collection::Trash, &item.account.id)
+            .map_err(|e| format!("删除回收站文件失败: {}", e))?;
+        purged.push(item);
+    }
+    Ok(purged)
+}
+
 /// 重新排序账号列表
 /// 根据传入的账号ID顺序更新索引文件中的账号排列顺序
 pub fn reorder_accounts(account_ids: &[String]) -> Result<(), String> {
@@ -359,8 +495,6 @@ pub fn reorder_accounts(account_ids: &[String]) -> Result<(), String> {
 
 /// 切换当前账号
 pub async fn switch_account(account_id: &str) -> Result<(), String> {
-    use crate::modules::{oauth, process, db};
-    
     let index = {
         let _lock = ACCOUNT_INDEX_LOCK.lock().map_err(|e| format!("获取锁失败: {}", e))?;
         load_account_index()?
@@ -374,32 +508,65 @@ pub async fn switch_account(account_id: &str) -> Result<(), String> {
     let mut account = load_account(account_id)?;
     crate::modules::logger::log_info(&format!("正在切换到账号: {} (ID: {})", account.email, account.id));
     
-    // 2. 确保 Token 有效（自动刷新）
+    // 2. 写入 IDE 数据库 (刷新 Token、关闭 IDE、备份、注入)
+    apply_account_to_ide(&mut account).await?;
+    
+    // 3. 更新工具内部状态
+    set_current_account_id(account_id)?;
+    account.update_last_used();
+    save_account(&account)?;
+    
+    // 4. 重启 Antigravity
+    crate::modules::process::start_antigravity()?;
+    crate::modules::logger::log_info(&format!("账号切换完成: {}", account.email));
+    
+    Ok(())
+}
+
+/// IDE 数据库保留的备份数量
+const IDE_DB_BACKUPS_KEPT: usize = 5;
+
+/// 账号凭据写入 IDE 的结果
+#[derive(Debug, Clone)]
+pub struct IdeApplyResult {
+    pub db_path: PathBuf,
+    /// 写入前的数据库备份 (数据库原本不存在时为 None)
+    pub backup_path: Option<PathBuf>,
+    /// 写入前 IDE 是否正在运行 (已被关闭)
+    pub was_running: bool,
+}
+
+/// 将账号凭据写入 IDE 数据库: 确保 Token 有效，关闭正在运行的 IDE，备份数据库后注入 (不重启 IDE)
+pub async fn apply_account_to_ide(account: &mut Account) -> Result<IdeApplyResult, String> {
+    use crate::modules::{db, oauth, process};
+
+    // 1. 确保 Token 有效（自动刷新）
     let fresh_token = oauth::ensure_fresh_token(&account.token).await
         .map_err(|e| format!("Token 刷新失败: {}", e))?;
         
     // 如果 Token 更新了，保存回账号文件
     if fresh_token.access_token != account.token.access_token {
         account.token = fresh_token.clone();
-        save_account(&account)?;
+        account.refresh.record_success(chrono::Utc::now().timestamp());
+        save_account(account)?;
     }
     
-    // 3. 关闭 Antigravity (增加超时时间到 20 秒)
-    if process::is_antigravity_running() {
+    // 2. 关闭 Antigravity (增加超时时间到 20 秒)
+    let was_running = process::is_antigravity_running();
+    if was_running {
         process::close_antigravity(20)?;
     }
     
-    // 4. 获取数据库路径并备份
+    // 3. 获取数据库路径并备份
     let db_path = db::get_db_path()?;
-    if db_path.exists() {
-        let backup_path = db_path.with_extension("vscdb.backup");
-        fs::copy(&db_path, &backup_path)
-            .map_err(|e| format!("备份数据库失败: {}", e))?;
+    let backup_path = if db_path.exists() {
+        Some(backup_ide_db(&db_path)?)
     } else {
         crate::modules::logger::log_info("数据库不存在，跳过备份");
-    }
+        None
+    };
     
-    // 5. 注入 Token
+    // 4. 注入 Token
     crate::modules::logger::log_info("正在注入 Token 到数据库...");
     db::inject_token(
         &db_path,
@@ -408,22 +575,43 @@ pub async fn switch_account(account_id: &str) -> Result<(), String> {
         account.token.expiry_timestamp
     )?;
     
-    // 6. 更新工具内部状态
-    {
-        let _lock = ACCOUNT_INDEX_LOCK.lock().map_err(|e| format!("获取锁失败: {}", e))?;
-        let mut index = load_account_index()?;
-        index.current_account_id = Some(account_id.to_string());
-        save_account_index(&index)?;
+    Ok(IdeApplyResult { db_path, backup_path, was_running })
+}
+
+/// 备份 IDE 数据库为 `state.vscdb.<时间>.backup`，仅保留最近几份
+fn backup_ide_db(db_path: &PathBuf) -> Result<PathBuf, String> {
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "state.vscdb".to_string());
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let backup_path = db_path.with_file_name(format!("{}.{}.backup", file_name, stamp));
+    fs::copy(db_path, &backup_path)
+        .map_err(|e| format!("备份数据库失败: {}", e))?;
+
+    if let Some(dir) = db_path.parent() {
+        let prefix = format!("{}.", file_name);
+        let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| {
+                        p.file_name()
+                            .map(|n| n.to_string_lossy())
+                            .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".backup"))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // 文件名中的时间戳可直接按字典序排序
+        backups.sort();
+        let excess = backups.len().saturating_sub(IDE_DB_BACKUPS_KEPT);
+        for old in &backups[..excess] {
+            let _ = fs::remove_file(old);
+        }
     }
-    
-    account.update_last_used();
-    save_account(&account)?;
-    
-    // 7. 重启 Antigravity
-    process::start_antigravity()?;
-    crate::modules::logger::log_info(&format!("账号切换完成: {}", account.email));
-    
-    Ok(())
+    Ok(backup_path)
 }
 
 /// 获取当前账号 ID
@@ -456,6 +644,114 @@ pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), St
     save_account(&account)
 }
 
+/// 更新账号备注与元数据: `note` 为空字符串时清除备注，元数据值为 `None` 时删除该键
+pub fn update_account_note(
+    account_id: &str,
+    note: Option<&str>,
+    metadata: &[(String, Option<String>)],
+) -> Result<Account, String> {
+    let mut account = load_account(account_id)?;
+    if let Some(note) = note {
+        let note = note.trim();
+        account.note = (!note.is_empty()).then(|| note.to_string());
+    }
+    for (key, value) in metadata {
+        match value {
+            Some(value) => account.metadata.insert(key.clone(), value.clone()),
+            None => account.metadata.remove(key),
+        };
+    }
+    save_account(&account)?;
+    Ok(account)
+}
+
+/// 每个账号保留的最近上游错误数
+const ACCOUNT_ERRORS_KEPT: usize = 5;
+/// 记录的错误信息最大长度 (字符)
+const ACCOUNT_ERROR_MAX_CHARS: usize = 500;
+
+/// 记录账号的上游错误 (最新的在前，仅保留最近几条)，使账号列表与详情能直接说明失败原因
+pub fn record_account_error(account_id: &str, status: Option<u16>, message: &str) -> Result<(), String> {
+    let mut account = load_account(account_id)?;
+    account.last_errors.insert(
+        0,
+        crate::models::AccountError {
+            timestamp: chrono::Utc::now().timestamp(),
+            status,
+            message: message.trim().chars().take(ACCOUNT_ERROR_MAX_CHARS).collect(),
+        },
+    );
+    account.last_errors.truncate(ACCOUNT_ERRORS_KEPT);
+    save_account(&account)
+}
+
+/// 记录一次 access_token 刷新结果；失败时同时写入账号的最近错误
+pub fn record_token_refresh(account_id: &str, error: Option<&str>) -> Result<(), String> {
+    let mut account = load_account(account_id)?;
+    let now = chrono::Utc::now().timestamp();
+    match error {
+        None => account.refresh.record_success(now),
+        Some(message) => {
+            account.refresh.record_failure(now);
+            account.last_errors.insert(
+                0,
+                crate::models::AccountError {
+                    timestamp: now,
+                    status: None,
+                    message: format!("token 刷新失败: {}", message.trim())
+                        .chars()
+                        .take(ACCOUNT_ERROR_MAX_CHARS)
+                        .collect(),
+                },
+            );
+            account.last_errors.truncate(ACCOUNT_ERRORS_KEPT);
+        }
+    }
+    save_account(&account)
+}
+
+/// 批量刷新配额时的账号筛选条件 (各条件同时生效)
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct RefreshFilter {
+    /// 仅刷新配额快照早于该秒数的账号 (从未获取过配额的视为过期)
+    #[serde(default)]
+    pub stale_after_secs: Option<i64>,
+    /// 仅刷新被标记为 403 Forbidden 的账号，用于检查是否已恢复
+    #[serde(default)]
+    pub only_forbidden: bool,
+    /// 仅刷新指定账号 (ID 或邮箱)
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+impl RefreshFilter {
+    /// 返回跳过原因，`None` 表示需要刷新
+    pub fn skip_reason(&self, account: &Account, now: i64) -> Option<String> {
+        if account.disabled {
+            return Some("Disabled".to_string());
+        }
+        if !self.accounts.is_empty()
+            && !self
+                .accounts
+                .iter()
+                .any(|a| a == &account.id || a.eq_ignore_ascii_case(&account.email))
+        {
+            return Some("Not selected".to_string());
+        }
+        let forbidden = account.quota.as_ref().map_or(false, |q| q.is_forbidden);
+        if self.only_forbidden != forbidden {
+            return Some(if forbidden { "Forbidden" } else { "Not forbidden" }.to_string());
+        }
+        if let (Some(threshold), Some(quota)) = (self.stale_after_secs, account.quota.as_ref()) {
+            let age = now - quota.last_updated;
+            if age < threshold {
+                return Some(format!("Fresh, updated {}s ago", age));
+            }
+        }
+        None
+    }
+}
+
 /// 导出所有账号的 refresh_token
 #[allow(dead_code)]
 pub fn export_accounts() -> Result<Vec<(String, String)>, String> {
@@ -487,8 +783,9 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
                 account.disabled = true;
                 account.disabled_at = Some(chrono::Utc::now().timestamp());
                 account.disabled_reason = Some(format!("invalid_grant: {}", e));
-                let _ = save_account(account);
             }
+            account.refresh.record_failure(chrono::Utc::now().timestamp());
+            let _ = save_account(account);
             return Err(AppError::OAuth(e));
         }
     };
@@ -496,6 +793,7 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
     if token.access_token != account.token.access_token {
         modules::logger::log_info(&format!("基于时间的 Token 刷新: {}", account.email));
         account.token = token.clone();
+        account.refresh.record_success(chrono::Utc::now().timestamp());
         
         // 重新获取用户名 (Token 刷新后顺便获取)
         let name = if account.name.is_none() || account.name.as_ref().map_or(false, |n| n.trim().is_empty()) {
@@ -509,6 +807,7 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
         
         account.name = name.clone();
         upsert_account(account.email.clone(), name, token.clone()).map_err(AppError::Account)?;
+        let _ = record_token_refresh(&account.id, None);
     }
 
     // 0. 补充用户名 (如果 Token 没过期但也没用户名，或者上面没获取到)
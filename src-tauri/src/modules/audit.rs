@@ -0,0 +1,232 @@
+// 管理操作审计日志 (仅追加的 JSONL)，用于多人共同管理同一实例时追溯变更
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// 串行化写入，避免并发追加时行交错
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 操作发起方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditActor {
+    /// 桌面界面 (Tauri 命令)
+    Gui,
+    /// 命令行 / 无头模式
+    Cli,
+    /// 管理 API
+    AdminApi,
+}
+
+/// 审计动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    AccountAdd,
+    AccountDelete,
+    AccountRestore,
+    AccountPurge,
+    AccountSwitch,
+    AccountUpdate,
+    ConfigChange,
+    KeyRotate,
+    KeyCreate,
+    ProxyStart,
+    ProxyStop,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AccountAdd => "account_add",
+            Self::AccountDelete => "account_delete",
+            Self::AccountRestore => "account_restore",
+            Self::AccountPurge => "account_purge",
+            Self::AccountSwitch => "account_switch",
+            Self::AccountUpdate => "account_update",
+            Self::ConfigChange => "config_change",
+            Self::KeyRotate => "key_rotate",
+            Self::KeyCreate => "key_create",
+            Self::ProxyStart => "proxy_start",
+            Self::ProxyStop => "proxy_stop",
+        }
+    }
+}
+
+/// 单条审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix 时间戳(秒)
+    pub timestamp: i64,
+    pub actor: AuditActor,
+    pub action: AuditAction,
+    /// 操作对象 (账号 ID、配置项、监听地址等)
+    #[serde(default)]
+    pub target: Option<String>,
+    /// 附加信息 (不包含密钥等敏感值)
+    #[serde(default)]
+    pub details: Option<serde_json::Value>,
+}
+
+fn get_audit_path() -> Result<PathBuf, String> {
+    Ok(super::account::get_data_dir()?.join(AUDIT_FILE))
+}
+
+/// 追加一条审计记录；写入失败只记录日志，不影响业务操作
+pub fn record(
+    actor: AuditActor,
+    action: AuditAction,
+    target: Option<&str>,
+    details: Option<serde_json::Value>,
+) {
+    let entry = AuditEntry {
+        timestamp: chrono::Utc::now().timestamp(),
+        actor,
+        action,
+        target: target.map(|s| s.to_string()),
+        details,
+    };
+    if let Err(e) = append(&entry) {
+        tracing::warn!("写入审计日志失败: {}", e);
+    }
+}
+
+fn append(entry: &AuditEntry) -> Result<(), String> {
+    let line = serde_json::to_string(entry).map_err(|e| format!("序列化审计记录失败: {}", e))?;
+    let path = get_audit_path()?;
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("打开审计日志失败: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("写入审计日志失败: {}", e))
+}
+
+/// 读取最近的审计记录 (按时间正序)，可按动作过滤
+pub fn read_entries(limit: usize, action: Option<&str>) -> Result<Vec<AuditEntry>, String> {
+    let path = get_audit_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = std::fs::File::open(&path).map_err(|e| format!("打开审计日志失败: {}", e))?;
+
+    let mut entries: Vec<AuditEntry> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(&line).ok())
+        .filter(|entry| match action {
+            Some(a) => entry.action.as_str() == a,
+            None => true,
+        })
+        .collect();
+
+    if entries.len() > limit {
+        entries.drain(..entries.len() - limit);
+    }
+    Ok(entries)
+}
+
+/// 格式化为适合终端输出的文本
+pub fn format_entries(entries: &[AuditEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let time = chrono::DateTime::from_timestamp(entry.timestamp, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| entry.timestamp.to_string());
+        let actor = serde_json::to_value(entry.actor)
+            .ok()
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "{} [{}] {} {}",
+            time,
+            actor,
+            entry.action.as_str(),
+            entry.target.as_deref().unwrap_or("-")
+        ));
+        if let Some(details) = &entry.details {
+            out.push(' ');
+            out.push_str(&details.to_string());
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// 记录配置变更审计 (仅记录变更的键名；API Key 变化额外记录为密钥轮换)，返回变更的键名
+pub fn record_config_change(
+    actor: AuditActor,
+    previous: &crate::models::AppConfig,
+    config: &crate::models::AppConfig,
+) -> Vec<String> {
+    let keys = changed_config_keys(previous, config);
+    if keys.is_empty() {
+        return keys;
+    }
+    if keys.iter().any(|k| k == "proxy.api_key") {
+        record(actor, AuditAction::KeyRotate, Some("proxy.api_key"), None);
+    }
+    record(actor, AuditAction::ConfigChange, None, Some(serde_json::json!({ "keys": keys })));
+    keys
+}
+
+/// 对比两份配置，返回发生变化的配置项 (点分路径，仅记录键名不记录值)
+pub fn changed_config_keys(old: &crate::models::AppConfig, new: &crate::models::AppConfig) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut keys = Vec::new();
+    diff_values("", &old, &new, &mut keys);
+    keys
+}
+
+fn diff_values(prefix: &str, old: &serde_json::Value, new: &serde_json::Value, out: &mut Vec<String>) {
+    use serde_json::Value;
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_values(&path, x, y, out),
+                    _ => out.push(path),
+                }
+            }
+        }
+        _ if old != new => out.push(prefix.to_string()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::config::{apply_config_value, apply_default_value};
+
+    #[test]
+    fn config_set_and_unset_record_the_changed_key() {
+        let previous = crate::models::AppConfig::new();
+        let port = previous.proxy.port.wrapping_add(1).to_string();
+        let updated = apply_config_value(&previous, "proxy.port", &port).unwrap();
+        assert_eq!(changed_config_keys(&previous, &updated), vec!["proxy.port".to_string()]);
+
+        let restored = apply_default_value(&updated, "proxy.port").unwrap();
+        assert_eq!(changed_config_keys(&updated, &restored), vec!["proxy.port".to_string()]);
+        assert!(changed_config_keys(&previous, &restored).is_empty());
+
+        let updated = apply_config_value(&previous, "proxy.api_key", "sk-rotated-0123456789").unwrap();
+        assert_eq!(changed_config_keys(&previous, &updated), vec!["proxy.api_key".to_string()]);
+    }
+}
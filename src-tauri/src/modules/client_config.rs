@@ -0,0 +1,322 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+use crate::proxy::ProxyConfig;
+
+/// OpenAI 协议客户端的默认模型
+const DEFAULT_OPENAI_MODEL: &str = "gemini-3-flash";
+/// Claude Code 的默认模型
+const DEFAULT_CLAUDE_MODEL: &str = "claude-sonnet-4-5";
+
+/// 支持导出配置的客户端工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientTool {
+    Cline,
+    Continue,
+    Aider,
+    ClaudeCode,
+    LibreChat,
+}
+
+impl FromStr for ClientTool {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cline" => Ok(Self::Cline),
+            "continue" => Ok(Self::Continue),
+            "aider" => Ok(Self::Aider),
+            "claude-code" | "claude_code" | "claude" => Ok(Self::ClaudeCode),
+            "librechat" => Ok(Self::LibreChat),
+            other => Err(format!(
+                "不支持的客户端: {} (可选: cline, continue, aider, claude-code, librechat)",
+                other
+            )),
+        }
+    }
+}
+
+/// 导出的客户端配置
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientConfigExport {
+    pub tool: String,
+    /// 建议保存的文件名 (或配置位置说明)
+    pub target: String,
+    /// 内容格式: json / yaml
+    pub format: String,
+    pub content: String,
+}
+
+/// YAML 字符串使用 JSON 双引号转义 (JSON 字符串是合法的 YAML 标量)
+fn yaml_str(s: &str) -> String {
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+/// 客户端连接反代的地址，以及该监听器覆盖的 API Key
+///
+/// 优先使用主 TCP 端口；`listen_tcp` 关闭时取第一个 TCP 附加监听器 (配置 TLS / ACME 时使用 https)，
+/// 监听全部地址时改用本机回环地址。仅有 Unix 套接字时客户端无法通过 HTTP 连接，返回错误
+fn client_endpoint(config: &ProxyConfig) -> Result<(String, Option<&str>), String> {
+    if config.listen_tcp {
+        return Ok((format!("http://127.0.0.1:{}", config.port), None));
+    }
+    let listener = config
+        .listeners
+        .iter()
+        .find(|l| l.unix_path().is_none())
+        .ok_or("没有可供客户端连接的 TCP 监听地址 (listen_tcp 已关闭且 listeners 中仅有 Unix 套接字)")?;
+    let scheme = if listener.tls.is_some() || listener.acme.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let authority = match listener.bind.parse::<SocketAddr>() {
+        Ok(addr) => match listener.acme.as_ref().and_then(|acme| acme.domains.first()) {
+            // ACME 证书只对申请的域名有效
+            Some(domain) => format!("{}:{}", domain, addr.port()),
+            None => {
+                let ip = match addr.ip() {
+                    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    ip => ip,
+                };
+                SocketAddr::new(ip, addr.port()).to_string()
+            }
+        },
+        Err(_) => listener.bind.clone(),
+    };
+    Ok((format!("{}://{}", scheme, authority), listener.api_key.as_deref()))
+}
+
+/// 收集反代对外暴露的模型列表
+async fn collect_models(config: &ProxyConfig) -> Vec<String> {
+    fn lock(map: &HashMap<String, String>) -> tokio::sync::RwLock<HashMap<String, String>> {
+        tokio::sync::RwLock::new(map.clone())
+    }
+    crate::proxy::common::model_mapping::get_all_dynamic_models(
+        &lock(&config.openai_mapping),
+        &lock(&config.custom_mapping),
+        &lock(&config.anthropic_mapping),
+    )
+    .await
+}
+
+/// 根据当前反代配置生成指定客户端的配置
+pub async fn export_client_config(
+    tool: ClientTool,
+    config: &ProxyConfig,
+) -> Result<ClientConfigExport, String> {
+    let (base_url, listener_key) = client_endpoint(config)?;
+    let openai_base = format!("{}/v1", base_url);
+    let api_key = listener_key.unwrap_or(&config.api_key);
+    // 哈希存储时无法还原明文，导出占位符由用户自行填写
+    let api_key = if crate::proxy::secrets::is_hashed(api_key) {
+        "<your-api-key>"
+    } else {
+        api_key
+    };
+    let models = collect_models(config).await;
+
+    let export = match tool {
+        ClientTool::Cline => ClientConfigExport {
+            tool: "cline".to_string(),
+            target: "Cline 设置 → API Provider: OpenAI Compatible".to_string(),
+            format: "json".to_string(),
+            content: serde_json::to_string_pretty(&serde_json::json!({
+                "apiProvider": "openai",
+                "openAiBaseUrl": openai_base,
+                "openAiApiKey": api_key,
+                "openAiModelId": DEFAULT_OPENAI_MODEL,
+            }))
+            .map_err(|e| format!("序列化配置失败: {}", e))?,
+        },
+        ClientTool::Continue => {
+            let mut content = String::from("models:\n");
+            for model in &models {
+                content.push_str(&format!(
+                    "  - name: {}\n    provider: openai\n    model: {}\n    apiBase: {}\n    apiKey: {}\n    roles:\n      - chat\n      - edit\n",
+                    yaml_str(&format!("Antigravity {}", model)),
+                    yaml_str(model),
+                    yaml_str(&openai_base),
+                    yaml_str(api_key),
+                ));
+            }
+            ClientConfigExport {
+                tool: "continue".to_string(),
+                target: "~/.continue/config.yaml".to_string(),
+                format: "yaml".to_string(),
+                content,
+            }
+        }
+        ClientTool::Aider => ClientConfigExport {
+            tool: "aider".to_string(),
+            target: ".aider.conf.yml".to_string(),
+            format: "yaml".to_string(),
+            content: format!(
+                "openai-api-base: {}\nopenai-api-key: {}\nmodel: {}\n",
+                yaml_str(&openai_base),
+                yaml_str(api_key),
+                yaml_str(&format!("openai/{}", DEFAULT_OPENAI_MODEL)),
+            ),
+        },
+        ClientTool::ClaudeCode => ClientConfigExport {
+            tool: "claude-code".to_string(),
+            target: "~/.claude/settings.json".to_string(),
+            format: "json".to_string(),
+            content: serde_json::to_string_pretty(&serde_json::json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": base_url,
+                    "ANTHROPIC_API_KEY": api_key,
+                    "ANTHROPIC_MODEL": DEFAULT_CLAUDE_MODEL,
+                }
+            }))
+            .map_err(|e| format!("序列化配置失败: {}", e))?,
+        },
+        ClientTool::LibreChat => {
+            let model_list = models
+                .iter()
+                .map(|m| yaml_str(m))
+                .collect::<Vec<_>>()
+                .join(", ");
+            ClientConfigExport {
+                tool: "librechat".to_string(),
+                target: "librechat.yaml".to_string(),
+                format: "yaml".to_string(),
+                content: format!(
+                    "endpoints:\n  custom:\n    - name: \"Antigravity\"\n      apiKey: {}\n      baseURL: {}\n      models:\n        default: [{}]\n        fetch: false\n      titleConvo: true\n      titleModel: {}\n",
+                    yaml_str(api_key),
+                    yaml_str(&openai_base),
+                    model_list,
+                    yaml_str(DEFAULT_OPENAI_MODEL),
+                ),
+            }
+        }
+    };
+
+    Ok(export)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::{ListenerConfig, ListenerTlsConfig};
+
+    const TOOLS: [ClientTool; 5] = [
+        ClientTool::Cline,
+        ClientTool::Continue,
+        ClientTool::Aider,
+        ClientTool::ClaudeCode,
+        ClientTool::LibreChat,
+    ];
+
+    fn config() -> ProxyConfig {
+        let mut config = ProxyConfig::default();
+        config.port = 8045;
+        config.api_key = "sk-\"quoted\": key".to_string();
+        config
+    }
+
+    fn listener(bind: &str) -> ListenerConfig {
+        ListenerConfig {
+            bind: bind.to_string(),
+            tls: None,
+            auth_mode: None,
+            api_key: None,
+            socket_mode: None,
+            acme: None,
+        }
+    }
+
+    /// 按导出格式解析内容
+    fn parse(export: &ClientConfigExport) -> serde_json::Value {
+        match export.format.as_str() {
+            "json" => serde_json::from_str(&export.content).unwrap(),
+            "yaml" => serde_yaml::from_str(&export.content).unwrap(),
+            other => panic!("unexpected format {}", other),
+        }
+    }
+
+    /// 各客户端配置中的 (API 地址, API Key)
+    fn endpoint(tool: ClientTool, value: &serde_json::Value) -> (String, String) {
+        let (base, key) = match tool {
+            ClientTool::Cline => (&value["openAiBaseUrl"], &value["openAiApiKey"]),
+            ClientTool::Continue => (&value["models"][0]["apiBase"], &value["models"][0]["apiKey"]),
+            ClientTool::Aider => (&value["openai-api-base"], &value["openai-api-key"]),
+            ClientTool::ClaudeCode => (&value["env"]["ANTHROPIC_BASE_URL"], &value["env"]["ANTHROPIC_API_KEY"]),
+            ClientTool::LibreChat => (
+                &value["endpoints"]["custom"][0]["baseURL"],
+                &value["endpoints"]["custom"][0]["apiKey"],
+            ),
+        };
+        (base.as_str().unwrap().to_string(), key.as_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn every_tool_exports_parseable_config() {
+        let config = config();
+        let models = collect_models(&config).await;
+        for tool in TOOLS {
+            let export = export_client_config(tool, &config).await.unwrap();
+            let value = parse(&export);
+            let expected_base = match tool {
+                ClientTool::ClaudeCode => "http://127.0.0.1:8045",
+                _ => "http://127.0.0.1:8045/v1",
+            };
+            assert_eq!(
+                endpoint(tool, &value),
+                (expected_base.to_string(), config.api_key.clone()),
+                "{:?}",
+                tool
+            );
+            match tool {
+                ClientTool::Continue => {
+                    let entries = value["models"].as_array().unwrap();
+                    assert_eq!(entries.len(), models.len());
+                    assert_eq!(entries[0]["model"], models[0].as_str());
+                    assert_eq!(entries[0]["roles"], serde_json::json!(["chat", "edit"]));
+                }
+                ClientTool::LibreChat => {
+                    let defaults = &value["endpoints"]["custom"][0]["models"]["default"];
+                    assert_eq!(defaults.as_array().unwrap().len(), models.len());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn hashed_api_key_exports_placeholder() {
+        let mut config = config();
+        config.api_key = crate::proxy::secrets::hash_api_key("sk-plain").unwrap();
+        let export = export_client_config(ClientTool::Aider, &config).await.unwrap();
+        assert_eq!(endpoint(ClientTool::Aider, &parse(&export)).1, "<your-api-key>");
+    }
+
+    #[test]
+    fn endpoint_follows_configured_listeners() {
+        let mut config = config();
+        config.listen_tcp = false;
+        config.listeners = vec![listener("unix:/run/antigravity.sock")];
+        assert!(client_endpoint(&config).is_err());
+
+        let mut tls = listener("0.0.0.0:8443");
+        tls.tls = Some(ListenerTlsConfig {
+            cert_path: "cert.pem".to_string(),
+            key_path: "key.pem".to_string(),
+        });
+        tls.api_key = Some("sk-listener".to_string());
+        config.listeners.push(tls);
+        assert_eq!(
+            client_endpoint(&config).unwrap(),
+            ("https://127.0.0.1:8443".to_string(), Some("sk-listener"))
+        );
+
+        config.listeners = vec![listener("[::]:9000")];
+        assert_eq!(client_endpoint(&config).unwrap().0, "http://[::1]:9000");
+
+        config.listen_tcp = true;
+        assert_eq!(client_endpoint(&config).unwrap(), ("http://127.0.0.1:8045".to_string(), None));
+    }
+}
@@ -1,35 +1,356 @@
-use std::fs;
+use std::path::{Path, PathBuf};
 use serde_json;
 
 use crate::models::AppConfig;
 use super::account::get_data_dir;
+use super::storage::{self, Collection, CONFIG_KEY};
 
 const CONFIG_FILE: &str = "gui_config.json";
+/// 数据目录中存在时优先于 gui_config.json 的手写配置文件 (按顺序查找)
+const ALT_CONFIG_FILES: &[&str] = &["config.toml", "config.yaml", "config.yml"];
+
+/// 配置文件路径覆盖 (无头模式 `--config`)
+static CONFIG_PATH_OVERRIDE: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
+
+/// 指定配置文件路径 (需在首次加载配置之前调用)
+pub fn set_config_path_override(path: PathBuf) -> Result<(), String> {
+    CONFIG_PATH_OVERRIDE
+        .set(path)
+        .map_err(|_| "配置文件路径已初始化，无法覆盖".to_string())
+}
+
+/// 获取配置文件路径 (文件存储后端)；数据目录下存在 config.toml / config.yaml 时优先使用
+pub fn get_config_path() -> Result<PathBuf, String> {
+    if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+        return Ok(path.clone());
+    }
+    let dir = get_data_dir()?;
+    Ok(ALT_CONFIG_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| dir.join(CONFIG_FILE)))
+}
+
+/// 配置文件格式 (按扩展名识别，其余均视为 JSON)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::parse)
+            .unwrap_or(Self::Json)
+    }
+
+    /// 该格式在数据目录中的默认文件名
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            Self::Json => CONFIG_FILE,
+            Self::Toml => ALT_CONFIG_FILES[0],
+            Self::Yaml => ALT_CONFIG_FILES[1],
+        }
+    }
+
+    pub fn deserialize(&self, content: &str) -> Result<AppConfig, String> {
+        match self {
+            Self::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            Self::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            Self::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("解析配置文件失败: {}", e))
+    }
+
+    pub fn serialize(&self, config: &AppConfig) -> Result<String, String> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(config).map_err(|e| e.to_string()),
+            Self::Toml => toml::to_string_pretty(config).map_err(|e| e.to_string()),
+            Self::Yaml => serde_yaml::to_string(config).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("序列化配置失败: {}", e))
+    }
+}
+
+/// 当前配置文档的格式: 仅默认的文件存储后端支持 TOML / YAML，其余后端始终保存 JSON
+fn stored_format() -> Result<ConfigFormat, String> {
+    if storage::backend().name() != "file" {
+        return Ok(ConfigFormat::Json);
+    }
+    Ok(ConfigFormat::from_path(&get_config_path()?))
+}
 
 /// 加载应用配置
 pub fn load_app_config() -> Result<AppConfig, String> {
-    let data_dir = get_data_dir()?;
-    let config_path = data_dir.join(CONFIG_FILE);
-    
-    if !config_path.exists() {
+    let Some(content) = storage::backend()
+        .read(Collection::Meta, CONFIG_KEY)
+        .map_err(|e| format!("读取配置文件失败: {}", e))?
+    else {
         return Ok(AppConfig::new());
-    }
-    
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("读取配置文件失败: {}", e))?;
-    
-    serde_json::from_str(&content)
-        .map_err(|e| format!("解析配置文件失败: {}", e))
+    };
+
+    // 以 `{` 开头的内容总是按 JSON 解析 (例如 FileStorage::at 固定目录下的 gui_config.json)
+    let format = if content.trim_start().starts_with('{') {
+        ConfigFormat::Json
+    } else {
+        stored_format()?
+    };
+    format.deserialize(&content)
 }
 
 /// 保存应用配置
 pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
-    let data_dir = get_data_dir()?;
-    let config_path = data_dir.join(CONFIG_FILE);
-    
-    let content = serde_json::to_string_pretty(config)
-        .map_err(|e| format!("序列化配置失败: {}", e))?;
+    // 开启哈希存储时，明文 API Key 不落盘
+    let hashed;
+    let config = if config.proxy.hash_api_keys {
+        let mut copy = config.clone();
+        crate::proxy::secrets::hash_config_keys(&mut copy.proxy)?;
+        hashed = copy;
+        &hashed
+    } else {
+        config
+    };
+
+    let content = match stored_format()? {
+        // TOML 配置通常是手写的: 在原文档上原位更新，保留注释与键的顺序
+        ConfigFormat::Toml => {
+            let existing = storage::backend()
+                .read(Collection::Meta, CONFIG_KEY)
+                .map_err(|e| format!("读取配置文件失败: {}", e))?;
+            update_toml(existing.as_deref().unwrap_or_default(), config)?
+        }
+        format => format.serialize(config)?,
+    };
     
-    fs::write(&config_path, content)
+    storage::backend()
+        .write(Collection::Meta, CONFIG_KEY, &content)
         .map_err(|e| format!("保存配置失败: {}", e))
 }
+
+fn update_toml(existing: &str, config: &AppConfig) -> Result<String, String> {
+    let fresh: toml_edit::DocumentMut = ConfigFormat::Toml
+        .serialize(config)?
+        .parse()
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+    let Ok(mut doc) = existing.parse::<toml_edit::DocumentMut>() else {
+        return Ok(fresh.to_string());
+    };
+    merge_toml_table(doc.as_table_mut(), fresh.as_table());
+    Ok(doc.to_string())
+}
+
+/// 以 `fresh` 的内容覆盖 `doc`: 删除多余的键，值替换时保留原有的行尾注释等修饰
+fn merge_toml_table(doc: &mut toml_edit::Table, fresh: &toml_edit::Table) {
+    let stale: Vec<String> = doc
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !fresh.contains_key(key))
+        .collect();
+    for key in stale {
+        doc.remove(&key);
+    }
+    for (key, item) in fresh.iter() {
+        match (doc.get_mut(key), item) {
+            (Some(toml_edit::Item::Table(current)), toml_edit::Item::Table(table)) => merge_toml_table(current, table),
+            (Some(toml_edit::Item::Value(current)), toml_edit::Item::Value(value)) => {
+                let decor = current.decor().clone();
+                *current = value.clone();
+                *current.decor_mut() = decor;
+            }
+            _ => {
+                doc.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+/// 可设置的配置项信息 (点分路径，例如 `proxy.port`)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConfigKeyInfo {
+    pub key: String,
+    /// 值类型: bool / number / string / array / map / nullable
+    pub value_type: String,
+    pub value: serde_json::Value,
+    pub default: serde_json::Value,
+}
+
+pub(crate) fn config_to_value(config: &AppConfig) -> Result<serde_json::Value, String> {
+    serde_json::to_value(config).map_err(|e| format!("序列化配置失败: {}", e))
+}
+
+pub(crate) fn value_to_config(value: serde_json::Value) -> Result<AppConfig, String> {
+    serde_json::from_value(value).map_err(|e| format!("配置值类型不匹配: {}", e))
+}
+
+/// 默认配置中的非空对象视为结构体 (可继续展开)，其余对象视为映射表 (作为整体设置)
+fn is_struct(default: Option<&serde_json::Value>) -> bool {
+    matches!(default, Some(serde_json::Value::Object(map)) if !map.is_empty())
+}
+
+fn value_type_of(value: &serde_json::Value, default: &serde_json::Value) -> &'static str {
+    let sample = if default.is_null() { value } else { default };
+    match sample {
+        serde_json::Value::Bool(_) => "bool",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "map",
+        serde_json::Value::Null => "nullable",
+    }
+}
+
+fn collect_keys(
+    prefix: &str,
+    current: &serde_json::Value,
+    default: &serde_json::Value,
+    out: &mut Vec<ConfigKeyInfo>,
+) {
+    let Some(default_map) = default.as_object() else {
+        return;
+    };
+    for (name, default_child) in default_map {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        let current_child = current.get(name).cloned().unwrap_or(serde_json::Value::Null);
+        if is_struct(Some(default_child)) {
+            collect_keys(&key, &current_child, default_child, out);
+        } else {
+            out.push(ConfigKeyInfo {
+                value_type: value_type_of(&current_child, default_child).to_string(),
+                key,
+                value: current_child,
+                default: default_child.clone(),
+            });
+        }
+    }
+}
+
+/// 脱敏输出中的密钥字段 (任意层级的 `api_key`，以及轮换宽限期内旧密钥的 `key`)，仅保留前缀
+fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (name, child) in map.iter_mut() {
+                match child {
+                    serde_json::Value::String(secret) if name == "api_key" || name == "key" => {
+                        *secret = crate::proxy::secrets::redact_secret(secret);
+                    }
+                    _ => redact_secrets(child),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// 完整配置 (密钥已脱敏)，供管理接口输出
+pub fn redacted_config() -> Result<serde_json::Value, String> {
+    let mut current = config_to_value(&load_app_config()?)?;
+    redact_secrets(&mut current);
+    Ok(current)
+}
+
+/// 列出所有可设置的配置项 (含类型、当前值与默认值，密钥已脱敏)
+pub fn list_config_keys() -> Result<Vec<ConfigKeyInfo>, String> {
+    let mut current = config_to_value(&load_app_config()?)?;
+    redact_secrets(&mut current);
+    let mut default = config_to_value(&AppConfig::new())?;
+    redact_secrets(&mut default);
+    let mut keys = Vec::new();
+    collect_keys("", &current, &default, &mut keys);
+    keys.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(keys)
+}
+
+/// 按点分路径定位配置项 (仅允许叶子节点)
+fn lookup_key<'a>(root: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    key.split('.').try_fold(root, |node, part| node.get(part))
+}
+
+fn ensure_settable_key(key: &str) -> Result<serde_json::Value, String> {
+    let default = config_to_value(&AppConfig::new())?;
+    match lookup_key(&default, key) {
+        Some(value) if !is_struct(Some(value)) => Ok(value.clone()),
+        Some(_) => Err(format!("{} 是配置分组，请指定具体的子项", key)),
+        None => Err(format!("未知的配置项: {}", key)),
+    }
+}
+
+fn replace_key(root: &mut serde_json::Value, key: &str, value: serde_json::Value) -> Result<(), String> {
+    let mut node = root;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let map = node
+            .as_object_mut()
+            .ok_or_else(|| format!("未知的配置项: {}", key))?;
+        if parts.peek().is_none() {
+            map.insert(part.to_string(), value);
+            return Ok(());
+        }
+        node = map
+            .entry(part.to_string())
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+    }
+    Err(format!("未知的配置项: {}", key))
+}
+
+/// 读取单个配置项 (密钥已脱敏)
+pub fn get_config_value(key: &str) -> Result<serde_json::Value, String> {
+    ensure_settable_key(key)?;
+    let mut current = config_to_value(&load_app_config()?)?;
+    redact_secrets(&mut current);
+    Ok(lookup_key(&current, key).cloned().unwrap_or(serde_json::Value::Null))
+}
+
+/// 设置单个配置项，字符串输入按目标类型解析 (数组/映射/可空项使用 JSON)；
+/// 返回修改后的配置 (尚未保存)，由调用方保存，以便对比保存前的配置记录审计
+pub fn set_config_value(key: &str, raw: &str) -> Result<AppConfig, String> {
+    apply_config_value(&load_app_config()?, key, raw)
+}
+
+/// 在给定配置上设置单个配置项，返回修改后的副本
+pub fn apply_config_value(config: &AppConfig, key: &str, raw: &str) -> Result<AppConfig, String> {
+    let default = ensure_settable_key(key)?;
+    let value = match &default {
+        serde_json::Value::String(_) => serde_json::Value::String(raw.to_string()),
+        serde_json::Value::Bool(_) => serde_json::Value::Bool(
+            raw.parse()
+                .map_err(|_| format!("{} 需要 true 或 false", key))?,
+        ),
+        _ => serde_json::from_str(raw)
+            .unwrap_or_else(|_| serde_json::Value::String(raw.to_string())),
+    };
+
+    let mut current = config_to_value(config)?;
+    replace_key(&mut current, key, value)?;
+    value_to_config(current)
+}
+
+/// 将单个配置项恢复为默认值；与 `set_config_value` 相同，返回的配置由调用方保存
+pub fn unset_config_value(key: &str) -> Result<AppConfig, String> {
+    apply_default_value(&load_app_config()?, key)
+}
+
+/// 在给定配置上将单个配置项恢复为默认值，返回修改后的副本
+pub fn apply_default_value(config: &AppConfig, key: &str) -> Result<AppConfig, String> {
+    let default = ensure_settable_key(key)?;
+    let mut current = config_to_value(config)?;
+    replace_key(&mut current, key, default)?;
+    value_to_config(current)
+}
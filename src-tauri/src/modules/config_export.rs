@@ -0,0 +1,310 @@
+// 配置导出 / 导入: 在多台机器间同步设置，或将配置纳入 dotfiles 管理
+//
+// 导出文件格式:
+// {"format": "antigravity-config", "version": 1, "exported_at": "...", "secrets": "plain|stripped|encrypted",
+//  "salt": "<base64>" (仅加密时), "config": { ...完整 AppConfig... }}
+//
+// 密钥字段 (任意层级的 `api_key`、`key` 与 `webhook_url`) 按导出模式处理:
+// - plain: 原样导出
+// - stripped: 替换为占位符，导入时保留本机的现有值
+// - encrypted: 以口令派生的密钥 (argon2) 逐字段 AES-256-GCM 加密，字段路径作为附加认证数据，防止密文被挪用到其他字段
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::AppConfig;
+
+const FORMAT: &str = "antigravity-config";
+const VERSION: u32 = 1;
+const SECRET_FIELDS: &[&str] = &["api_key", "key", "webhook_url"];
+const STRIPPED: &str = "[stripped]";
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const SALT_LEN: usize = 16;
+
+/// 导出时密钥字段的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretMode {
+    Plain,
+    Stripped,
+    Encrypted,
+}
+
+impl SecretMode {
+    /// 解析命令行参数: `keep` / `strip` / `encrypt`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "keep" | "plain" => Some(Self::Plain),
+            "strip" | "stripped" => Some(Self::Stripped),
+            "encrypt" | "encrypted" => Some(Self::Encrypted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportFile {
+    format: String,
+    version: u32,
+    #[serde(default)]
+    exported_at: String,
+    secrets: SecretMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    config: Value,
+}
+
+/// 单项配置差异 (点分路径；数组整体比较)，密钥字段已脱敏
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChange {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// 导入预览: 合并后的配置、与当前配置的差异，以及无法还原的密钥字段
+#[derive(Debug)]
+pub struct ImportPlan {
+    pub config: AppConfig,
+    pub changes: Vec<ConfigChange>,
+    /// 导出时被移除、且本机也没有对应值的密钥字段
+    pub missing_secrets: Vec<String>,
+}
+
+/// 导出配置为 JSON 文本；加密模式需提供口令
+pub fn export_config(config: &AppConfig, mode: SecretMode, passphrase: Option<&str>) -> Result<String, String> {
+    let mut value = super::config::config_to_value(config)?;
+    let mut salt = None;
+    match mode {
+        SecretMode::Plain => {}
+        SecretMode::Stripped => visit_secrets(&mut value, "", &mut |_, secret| {
+            *secret = STRIPPED.to_string();
+            Ok(())
+        })?,
+        SecretMode::Encrypted => {
+            let passphrase = passphrase.filter(|p| !p.is_empty()).ok_or("加密导出需要提供口令")?;
+            let mut bytes = [0u8; SALT_LEN];
+            SystemRandom::new()
+                .fill(&mut bytes)
+                .map_err(|_| "生成随机盐失败".to_string())?;
+            let key = derive_key(passphrase, &bytes)?;
+            visit_secrets(&mut value, "", &mut |path, secret| {
+                *secret = encrypt_field(&key, path, secret)?;
+                Ok(())
+            })?;
+            salt = Some(base64::engine::general_purpose::STANDARD.encode(bytes));
+        }
+    }
+
+    let file = ExportFile {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        secrets: mode,
+        salt,
+        config: value,
+    };
+    serde_json::to_string_pretty(&file).map_err(|e| format!("序列化配置失败: {}", e))
+}
+
+/// 导出文件是否包含加密字段 (调用方据此决定是否询问口令)
+pub fn requires_passphrase(content: &str) -> bool {
+    serde_json::from_str::<ExportFile>(content).is_ok_and(|file| file.secrets == SecretMode::Encrypted)
+}
+
+/// 解析导出文件 (也接受直接的 gui_config.json)，还原密钥字段并计算与当前配置的差异
+pub fn prepare_import(content: &str, current: &AppConfig, passphrase: Option<&str>) -> Result<ImportPlan, String> {
+    let raw: Value = serde_json::from_str(content).map_err(|e| format!("解析导入文件失败: {}", e))?;
+    let (mode, salt, mut value) = if raw.get("format").and_then(Value::as_str) == Some(FORMAT) {
+        let file: ExportFile = serde_json::from_value(raw).map_err(|e| format!("解析导入文件失败: {}", e))?;
+        if file.version > VERSION {
+            return Err(format!("导入文件版本 {} 高于当前支持的版本 {}", file.version, VERSION));
+        }
+        (file.secrets, file.salt, file.config)
+    } else {
+        (SecretMode::Plain, None, raw)
+    };
+
+    let current_value = super::config::config_to_value(current)?;
+    let mut missing_secrets = Vec::new();
+    match mode {
+        SecretMode::Plain => {}
+        SecretMode::Stripped => visit_secrets(&mut value, "", &mut |path, secret| {
+            if secret.as_str() == STRIPPED {
+                match lookup(&current_value, path).and_then(Value::as_str) {
+                    Some(local) => *secret = local.to_string(),
+                    None => {
+                        secret.clear();
+                        missing_secrets.push(path.to_string());
+                    }
+                }
+            }
+            Ok(())
+        })?,
+        SecretMode::Encrypted => {
+            let passphrase = passphrase.filter(|p| !p.is_empty()).ok_or("导入文件已加密，需要提供口令")?;
+            let salt = salt
+                .and_then(|s| base64::engine::general_purpose::STANDARD.decode(s).ok())
+                .ok_or("导入文件缺少有效的 salt")?;
+            let key = derive_key(passphrase, &salt)?;
+            visit_secrets(&mut value, "", &mut |path, secret| {
+                if secret.starts_with(ENCRYPTED_PREFIX) {
+                    *secret = decrypt_field(&key, path, secret)?;
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    let config = super::config::value_to_config(value)?;
+    let mut changes = Vec::new();
+    diff_values("", &current_value, &super::config::config_to_value(&config)?, &mut changes);
+    Ok(ImportPlan { config, changes, missing_secrets })
+}
+
+/// 差异输出，每行一项: `~ 路径: 旧值 -> 新值`、`+ 路径: 新值`、`- 路径: 旧值`
+pub fn format_changes(changes: &[ConfigChange]) -> String {
+    let show = |value: &Value| serde_json::to_string(value).unwrap_or_default();
+    changes
+        .iter()
+        .map(|change| match (&change.before, &change.after) {
+            (Some(before), Some(after)) => format!("~ {}: {} -> {}\n", change.path, show(before), show(after)),
+            (None, Some(after)) => format!("+ {}: {}\n", change.path, show(after)),
+            (Some(before), None) => format!("- {}: {}\n", change.path, show(before)),
+            (None, None) => String::new(),
+        })
+        .collect()
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey, String> {
+    let mut bytes = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut bytes)
+        .map_err(|e| format!("派生加密密钥失败: {}", e))?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes).map_err(|_| "派生加密密钥失败".to_string())?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn encrypt_field(key: &LessSafeKey, path: &str, plaintext: &str) -> Result<String, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| "生成随机数失败".to_string())?;
+    let mut sealed = plaintext.as_bytes().to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(path.as_bytes()), &mut sealed)
+        .map_err(|_| format!("加密字段 {} 失败", path))?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&sealed);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, base64::engine::general_purpose::STANDARD.encode(out)))
+}
+
+fn decrypt_field(key: &LessSafeKey, path: &str, encoded: &str) -> Result<String, String> {
+    let invalid = || format!("解密字段 {} 失败 (口令错误或文件已损坏)", path);
+    let mut data = base64::engine::general_purpose::STANDARD
+        .decode(&encoded[ENCRYPTED_PREFIX.len()..])
+        .map_err(|_| invalid())?;
+    if data.len() < NONCE_LEN {
+        return Err(invalid());
+    }
+    let mut sealed = data.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&data).map_err(|_| invalid())?;
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(path.as_bytes()), &mut sealed)
+        .map_err(|_| invalid())?;
+    String::from_utf8(plaintext.to_vec()).map_err(|_| invalid())
+}
+
+fn child_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+/// 遍历所有非空的密钥字段，回调参数为 (点分路径, 字段值)
+fn visit_secrets(
+    value: &mut Value,
+    prefix: &str,
+    f: &mut dyn FnMut(&str, &mut String) -> Result<(), String>,
+) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            for (name, child) in map.iter_mut() {
+                let path = child_path(prefix, name);
+                match child {
+                    Value::String(secret) if SECRET_FIELDS.contains(&name.as_str()) => {
+                        if !secret.is_empty() {
+                            f(&path, secret)?;
+                        }
+                    }
+                    _ => visit_secrets(child, &path, f)?,
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                visit_secrets(item, &child_path(prefix, &index.to_string()), f)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn lookup<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(root, |node, part| match node {
+        Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => node.get(part),
+    })
+}
+
+fn redacted(value: &Value) -> Value {
+    let mut copy = value.clone();
+    let _ = visit_secrets(&mut copy, "", &mut |_, secret| {
+        *secret = crate::proxy::secrets::redact_secret(secret);
+        Ok(())
+    });
+    copy
+}
+
+fn diff_values(prefix: &str, before: &Value, after: &Value, out: &mut Vec<ConfigChange>) {
+    match (before, after) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (name, old_child) in old {
+                let path = child_path(prefix, name);
+                match new.get(name) {
+                    Some(new_child) => diff_values(&path, old_child, new_child, out),
+                    None => out.push(ConfigChange { path, before: Some(redacted_leaf(name, old_child)), after: None }),
+                }
+            }
+            for (name, new_child) in new.iter().filter(|(name, _)| !old.contains_key(*name)) {
+                out.push(ConfigChange {
+                    path: child_path(prefix, name),
+                    before: None,
+                    after: Some(redacted_leaf(name, new_child)),
+                });
+            }
+        }
+        _ if before != after => {
+            let name = prefix.rsplit('.').next().unwrap_or_default();
+            out.push(ConfigChange {
+                path: prefix.to_string(),
+                before: Some(redacted_leaf(name, before)),
+                after: Some(redacted_leaf(name, after)),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn redacted_leaf(name: &str, value: &Value) -> Value {
+    match value {
+        Value::String(secret) if SECRET_FIELDS.contains(&name) => {
+            Value::String(crate::proxy::secrets::redact_secret(secret))
+        }
+        _ => redacted(value),
+    }
+}
@@ -0,0 +1,542 @@
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::models::AppConfig;
+use crate::proxy::{ProxyAuthMode, ProxySecurityConfig, ZaiDispatchMode};
+
+/// 上游代理连通性检测超时
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueLevel {
+    Error,
+    Warning,
+}
+
+/// 单条校验问题
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub level: IssueLevel,
+    /// 对应的配置项 (点分路径)
+    pub key: String,
+    pub message: String,
+    /// 修复建议
+    pub hint: Option<String>,
+}
+
+/// 配置校验报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+    pub error_count: usize,
+    pub warning_count: usize,
+}
+
+impl ValidationReport {
+    fn push(&mut self, level: IssueLevel, key: &str, message: String, hint: Option<&str>) {
+        match level {
+            IssueLevel::Error => self.error_count += 1,
+            IssueLevel::Warning => self.warning_count += 1,
+        }
+        self.issues.push(ValidationIssue {
+            level,
+            key: key.to_string(),
+            message,
+            hint: hint.map(|h| h.to_string()),
+        });
+    }
+
+    fn error(&mut self, key: &str, message: String, hint: Option<&str>) {
+        self.push(IssueLevel::Error, key, message, hint);
+    }
+
+    fn warning(&mut self, key: &str, message: String, hint: Option<&str>) {
+        self.push(IssueLevel::Warning, key, message, hint);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count > 0
+    }
+}
+
+/// 校验配置
+///
+/// `check_port` 为 false 时跳过端口占用检测 (例如反代服务已在该端口运行)
+pub async fn validate_config(config: &AppConfig, check_port: bool) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let proxy = &config.proxy;
+
+    // 1. 端口
+    if proxy.port == 0 {
+        report.error("proxy.port", "端口不能为 0".to_string(), None);
+    } else if check_port && proxy.listen_tcp {
+        let addr = format!("{}:{}", proxy.get_bind_address(), proxy.port);
+        if let Err(e) = std::net::TcpListener::bind(&addr) {
+            report.error(
+                "proxy.port",
+                format!("端口 {} 不可用: {}", addr, e),
+                Some("停止占用该端口的进程，或修改 proxy.port"),
+            );
+        }
+    }
+
+    if proxy.request_timeout == 0 {
+        report.error("proxy.request_timeout", "请求超时不能为 0".to_string(), None);
+    } else if proxy.request_timeout > 3600 {
+        report.warning(
+            "proxy.request_timeout",
+            format!("请求超时过长: {} 秒", proxy.request_timeout),
+            Some("建议不超过 3600 秒"),
+        );
+    }
+
+    // 2. 鉴权与 API Key 强度
+    let auth_mode = ProxySecurityConfig::from_proxy_config(proxy).effective_auth_mode();
+    if matches!(auth_mode, ProxyAuthMode::Off) {
+        if proxy.allow_lan_access {
+            report.warning(
+                "proxy.auth_mode",
+                "已开启局域网访问但未启用鉴权，任何人都可以使用反代".to_string(),
+                Some("将 proxy.auth_mode 设置为 strict 或 auto"),
+            );
+        }
+    } else {
+        check_api_key(&mut report, &proxy.api_key);
+    }
+
+    // 3. 模型映射目标 (账号池的可用性在反代启动时检查)
+    for issue in crate::proxy::mapping_check::check(proxy, &std::collections::HashSet::new()) {
+        if issue.problem == crate::proxy::mapping_check::MappingProblem::EmptyTarget {
+            report.error(&issue.key, format!("{} 的映射目标为空", issue.from), None);
+        } else {
+            report.warning(
+                &issue.key,
+                format!("{} -> {}: 目标不是已知模型", issue.from, issue.target),
+                Some("请确认上游支持该模型名称"),
+            );
+        }
+    }
+
+    for (i, rule) in proxy.mapping_rules.iter().enumerate() {
+        if let Err(e) = crate::proxy::common::mapping_rules::MappingRules::compile(
+            std::slice::from_ref(rule),
+        ) {
+            report.error(
+                &format!("proxy.mapping_rules[{}]", i),
+                e,
+                Some("通配符使用 * / ?，正则需以 re: 开头"),
+            );
+        }
+    }
+
+    // 3b. 额外监听器
+    let global_security = ProxySecurityConfig::from_proxy_config(proxy);
+    if !proxy.listen_tcp && proxy.listeners.is_empty() {
+        report.error(
+            "proxy.listen_tcp",
+            "已关闭主 TCP 端口但未配置其他监听地址".to_string(),
+            Some("在 proxy.listeners 中添加地址，或开启 proxy.listen_tcp"),
+        );
+    }
+    for (i, listener) in proxy.listeners.iter().enumerate() {
+        let key = format!("proxy.listeners[{}]", i);
+        if let Some(path) = listener.unix_path() {
+            if cfg!(not(unix)) {
+                report.error(&key, format!("当前平台不支持 Unix 套接字: {}", path), None);
+            }
+            if listener.tls.is_some() || listener.acme.is_some() {
+                report.warning(&key, "Unix 套接字不需要 TLS，已忽略 tls / acme 配置".to_string(), None);
+            }
+            continue;
+        }
+        if listener.bind.parse::<std::net::SocketAddr>().is_err() {
+            report.error(
+                &key,
+                format!("无效的监听地址: {}", listener.bind),
+                Some("格式应为 IP:端口，如 0.0.0.0:8443"),
+            );
+            continue;
+        }
+        if let Some(acme) = &listener.acme {
+            check_acme(&mut report, &key, listener, acme);
+        } else if let Some(tls) = &listener.tls {
+            for path in [&tls.cert_path, &tls.key_path] {
+                if !std::path::Path::new(path).is_file() {
+                    report.error(&key, format!("TLS 文件不存在: {}", path), None);
+                }
+            }
+        }
+        let security = global_security.for_listener(listener);
+        if listener.is_lan() && matches!(security.effective_auth_mode(), ProxyAuthMode::Off) {
+            report.warning(
+                &key,
+                format!("监听器 {} 对局域网开放但未启用鉴权", listener.bind),
+                Some("为该监听器设置 auth_mode"),
+            );
+        }
+        if check_port {
+            if let Err(e) = std::net::TcpListener::bind(&listener.bind) {
+                report.error(&key, format!("地址 {} 不可用: {}", listener.bind, e), None);
+            }
+        }
+    }
+
+    // 3c. 管理监听器
+    if let Some(admin) = &proxy.admin_listen {
+        match admin.parse::<std::net::SocketAddr>() {
+            Err(_) => report.error(
+                "proxy.admin_listen",
+                format!("无效的管理监听地址: {}", admin),
+                Some("格式应为 IP:端口，如 127.0.0.1:8046"),
+            ),
+            Ok(addr) => {
+                if !addr.ip().is_loopback() {
+                    report.warning(
+                        "proxy.admin_listen",
+                        format!("管理接口监听在非本机地址 {}，局域网内均可访问", admin),
+                        Some("除非需要远程管理，建议使用 127.0.0.1"),
+                    );
+                }
+                if check_port {
+                    if let Err(e) = std::net::TcpListener::bind(addr) {
+                        report.error("proxy.admin_listen", format!("地址 {} 不可用: {}", admin, e), None);
+                    }
+                }
+            }
+        }
+    }
+
+    // 4. 上游代理
+    if proxy.upstream_proxy.enabled {
+        check_upstream_proxy(&mut report, &proxy.upstream_proxy.url).await;
+    }
+
+    // 5. z.ai
+    let zai = &proxy.zai;
+    if zai.enabled {
+        if zai.api_key.trim().is_empty() {
+            report.error("proxy.zai.api_key", "已启用 z.ai 但未配置 API Key".to_string(), None);
+        }
+        match url::Url::parse(&zai.base_url) {
+            Ok(u) if u.scheme() == "http" || u.scheme() == "https" => {}
+            _ => report.error(
+                "proxy.zai.base_url",
+                format!("无效的 z.ai 地址: {}", zai.base_url),
+                Some("应为 http(s):// 开头的完整 URL"),
+            ),
+        }
+        if matches!(zai.dispatch_mode, ZaiDispatchMode::Off) {
+            report.warning(
+                "proxy.zai.dispatch_mode",
+                "已启用 z.ai 但分发模式为 off，请求不会被转发到 z.ai".to_string(),
+                Some("设置为 exclusive / pooled / fallback"),
+            );
+        }
+        for (from, to) in &zai.model_mapping {
+            if to.trim().is_empty() {
+                report.error("proxy.zai.model_mapping", format!("{} 的映射目标为空", from), None);
+            }
+        }
+    }
+
+    // 6. anthropic-version
+    for version in crate::proxy::middleware::anthropic_version::invalid_versions(&proxy.anthropic_versions) {
+        report.error(
+            "proxy.anthropic_versions",
+            format!("无效的 anthropic-version: {:?}", version),
+            Some("如 2023-06-01；留空列表表示不校验"),
+        );
+    }
+
+    // 7. 客户端标识档案
+    let profiles = &proxy.client_profiles;
+    let available = crate::proxy::upstream::profiles::available(profiles);
+    if !available.iter().any(|p| p.name == profiles.active) {
+        report.error(
+            "proxy.client_profiles.active",
+            format!("默认客户端档案不存在: {}", profiles.active),
+            Some("使用内置档案 (如 antigravity-windows) 或在 profiles 中定义"),
+        );
+    }
+    for profile in &profiles.profiles {
+        if profile.name.trim().is_empty() || profile.user_agent.trim().is_empty() {
+            report.error(
+                "proxy.client_profiles.profiles",
+                "客户端档案的 name 与 user_agent 不能为空".to_string(),
+                None,
+            );
+        }
+        for (name, value) in &profile.headers {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err()
+                || reqwest::header::HeaderValue::from_str(value).is_err()
+            {
+                report.error(
+                    "proxy.client_profiles.profiles",
+                    format!("客户端档案 {} 中的请求头无效: {}", profile.name, name),
+                    None,
+                );
+            }
+        }
+    }
+
+    // 8. 流量镜像
+    let mirror = &proxy.mirror;
+    if mirror.enabled {
+        if !(0.0..=100.0).contains(&mirror.percent) {
+            report.error(
+                "proxy.mirror.percent",
+                format!("镜像比例超出范围: {}", mirror.percent),
+                Some("取值 0-100"),
+            );
+        }
+        if mirror.model_mapping.is_empty() && mirror.accounts.is_empty() {
+            report.warning(
+                "proxy.mirror",
+                "已启用流量镜像但未配置镜像模型映射或账号组，不会产生镜像请求".to_string(),
+                Some("配置 model_mapping 或 accounts"),
+            );
+        }
+        for (from, to) in &mirror.model_mapping {
+            if to.trim().is_empty() {
+                report.error("proxy.mirror.model_mapping", format!("{} 的映射目标为空", from), None);
+            }
+        }
+    }
+
+    // 9. 多实例协同
+    let cluster = &proxy.cluster;
+    if cluster.enabled {
+        if cluster.peers.is_empty() {
+            report.warning(
+                "proxy.cluster.peers",
+                "已启用多实例协同但未配置对等实例".to_string(),
+                Some("添加其他实例的地址，如 http://10.0.0.2:8045"),
+            );
+        }
+        for peer in &cluster.peers {
+            match url::Url::parse(peer.trim()) {
+                Ok(u) if u.scheme() == "http" || u.scheme() == "https" => {}
+                _ => report.error(
+                    "proxy.cluster.peers",
+                    format!("无效的对等实例地址: {}", peer),
+                    Some("应为 http(s):// 开头的完整 URL"),
+                ),
+            }
+        }
+        if cluster.api_key.is_empty() {
+            report.error(
+                "proxy.cluster.api_key",
+                "已启用多实例协同但未配置集群共享密钥".to_string(),
+                Some("在各实例的 cluster.api_key 中配置相同的随机密钥"),
+            );
+        }
+        let security = crate::proxy::ProxySecurityConfig::from_proxy_config(proxy);
+        if matches!(security.effective_auth_mode(), crate::proxy::ProxyAuthMode::Off) {
+            report.error(
+                "proxy.auth_mode",
+                "反代鉴权关闭时不接收对等实例的集群事件".to_string(),
+                Some("开启鉴权 (auth_mode 设为 strict 或 all_except_health)"),
+            );
+        }
+    }
+
+    // 10. 请求/响应体采样
+    let capture = &proxy.body_capture;
+    if !(0.0..=100.0).contains(&capture.percent) {
+        report.error(
+            "proxy.body_capture.percent",
+            format!("采样比例超出范围: {}", capture.percent),
+            Some("取值 0-100"),
+        );
+    }
+    for rule in &capture.always {
+        let exprs: Vec<&str> = rule.split_whitespace().collect();
+        if let Err(e) = crate::proxy::monitor::LogFilter::parse(&exprs) {
+            report.error("proxy.body_capture.always", format!("{}: {}", rule, e), None);
+        }
+    }
+
+    // 11. 按时段路由
+    let schedule = &proxy.schedule_routing;
+    if schedule.enabled {
+        if let Err(e) = crate::proxy::schedule_routing::parse_timezone(&schedule.timezone) {
+            report.error("proxy.schedule_routing.timezone", e, Some("使用 IANA 时区名，如 Asia/Shanghai；留空使用本地时区"));
+        }
+        for (i, rule) in schedule.rules.iter().enumerate() {
+            let key = format!("proxy.schedule_routing.rules[{}]", i);
+            if let Err(e) = crate::proxy::schedule_routing::check_rule(rule) {
+                report.error(&key, e, None);
+            }
+            if rule.tiers.is_empty() && rule.accounts.is_empty() {
+                report.warning(
+                    &key,
+                    "规则未指定 tiers 或 accounts，生效期间所有请求都会因无可用账号而失败".to_string(),
+                    Some("至少指定一个订阅等级或账号"),
+                );
+            }
+        }
+    }
+
+    // 12. 采样参数策略
+    let key_names: Vec<&str> = proxy.api_keys.iter().map(|k| k.name.as_str()).collect();
+    for (i, rule) in proxy.param_policy.rules.iter().enumerate() {
+        let key = format!("proxy.param_policy.rules[{}]", i);
+        for (name, limit) in [("temperature", &rule.temperature), ("top_p", &rule.top_p)] {
+            let Some(limit) = limit else { continue };
+            if let (Some(min), Some(max)) = (limit.min, limit.max) {
+                if min > max {
+                    report.error(&key, format!("{} 的下限 {} 大于上限 {}", name, min, max), None);
+                }
+            }
+            let values = [limit.min, limit.max, limit.default, limit.force];
+            if values.iter().flatten().any(|v| !v.is_finite() || *v < 0.0) {
+                report.error(&key, format!("{} 的取值不能为负数", name), None);
+            }
+        }
+        if rule.max_tokens == Some(0) {
+            report.error(&key, "max_tokens 上限不能为 0".to_string(), None);
+        }
+        for name in &rule.keys {
+            if name != "default" && !key_names.contains(&name.as_str()) {
+                report.warning(
+                    &key,
+                    format!("未找到名为 {} 的 API Key，该规则不会按此密钥匹配", name),
+                    Some("主密钥使用 default，附加密钥使用其 name"),
+                );
+            }
+        }
+    }
+
+    report
+}
+
+fn check_api_key(report: &mut ValidationReport, api_key: &str) {
+    // 哈希存储的密钥无法检查强度 (生成时已为随机值)
+    if crate::proxy::secrets::is_hashed(api_key) {
+        return;
+    }
+    if api_key.trim().is_empty() {
+        report.error(
+            "proxy.api_key",
+            "已启用鉴权但 API Key 为空".to_string(),
+            Some("生成新的 API Key"),
+        );
+        return;
+    }
+    if let Some(reason) = crate::proxy::secrets::weak_key_reason(api_key) {
+        report.warning(
+            "proxy.api_key",
+            format!("API Key 强度不足: {}", reason),
+            Some("使用随机生成的 API Key (--rotate-api-key)"),
+        );
+    }
+}
+
+fn check_acme(
+    report: &mut ValidationReport,
+    key: &str,
+    listener: &crate::proxy::config::ListenerConfig,
+    acme: &crate::proxy::config::AcmeConfig,
+) {
+    use crate::proxy::config::AcmeChallenge;
+
+    if cfg!(not(feature = "acme")) {
+        report.error(
+            key,
+            "当前版本未以 acme 特性编译，无法自动申请证书".to_string(),
+            Some("使用 --features acme 重新编译，或改用 tls 配置证书文件"),
+        );
+    }
+    if acme.domains.iter().all(|d| d.trim().is_empty()) {
+        report.error(key, "ACME 配置缺少域名".to_string(), Some("在 acme.domains 中填写证书域名"));
+    }
+    if listener.tls.is_some() {
+        report.warning(key, "已配置 ACME，tls 证书文件将被忽略".to_string(), None);
+    }
+    if !acme.directory_url.starts_with("https://") {
+        report.error(key, format!("无效的 ACME 目录地址: {}", acme.directory_url), None);
+    }
+    match acme.challenge {
+        AcmeChallenge::Http01 => {
+            if acme.http_bind.parse::<std::net::SocketAddr>().is_err() {
+                report.error(key, format!("无效的 HTTP-01 验证地址: {}", acme.http_bind), None);
+            } else if !acme.http_bind.ends_with(":80") {
+                report.warning(
+                    key,
+                    format!("HTTP-01 验证地址 {} 不是 80 端口", acme.http_bind),
+                    Some("CA 仅访问 80 端口，需由端口转发将其指向该地址"),
+                );
+            }
+        }
+        AcmeChallenge::TlsAlpn01 => {
+            if !listener.bind.ends_with(":443") {
+                report.warning(
+                    key,
+                    format!("TLS-ALPN-01 验证要求监听 443 端口，当前为 {}", listener.bind),
+                    Some("确保外部 443 端口转发到该监听器"),
+                );
+            }
+        }
+    }
+}
+
+async fn check_upstream_proxy(report: &mut ValidationReport, proxy_url: &str) {
+    const KEY: &str = "proxy.upstream_proxy.url";
+    if proxy_url.trim().is_empty() {
+        report.error(KEY, "已启用上游代理但地址为空".to_string(), None);
+        return;
+    }
+
+    let parsed = match url::Url::parse(proxy_url) {
+        Ok(u) => u,
+        Err(e) => {
+            report.error(
+                KEY,
+                format!("无效的上游代理地址: {}", e),
+                Some("例如 http://127.0.0.1:7890 或 socks5://127.0.0.1:1080"),
+            );
+            return;
+        }
+    };
+    if !matches!(parsed.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        report.error(
+            KEY,
+            format!("不支持的代理协议: {}", parsed.scheme()),
+            Some("支持 http / https / socks5 / socks5h"),
+        );
+        return;
+    }
+
+    let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+        report.error(KEY, "上游代理地址缺少主机或端口".to_string(), None);
+        return;
+    };
+    let target = format!("{}:{}", host, port);
+    match tokio::time::timeout(REACHABILITY_TIMEOUT, tokio::net::TcpStream::connect(&target)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => report.error(KEY, format!("无法连接上游代理 {}: {}", target, e), None),
+        Err(_) => report.error(KEY, format!("连接上游代理 {} 超时", target), None),
+    }
+}
+
+/// 将报告格式化为适合终端输出的文本
+pub fn format_report(report: &ValidationReport) -> String {
+    let mut out = String::new();
+    for issue in &report.issues {
+        let level = match issue.level {
+            IssueLevel::Error => "ERROR",
+            IssueLevel::Warning => "WARN ",
+        };
+        out.push_str(&format!("[{}] {}: {}\n", level, issue.key, issue.message));
+        if let Some(hint) = &issue.hint {
+            out.push_str(&format!("        -> {}\n", hint));
+        }
+    }
+    out.push_str(&crate::modules::i18n::cli_text(
+        "validation_summary",
+        &[("errors", &report.error_count), ("warnings", &report.warning_count)],
+    ));
+    out.push('\n');
+    out
+}
@@ -15,33 +15,28 @@ fn get_antigravity_path() -> Option<PathBuf> {
     crate::modules::process::get_antigravity_executable_path()
 }
 
-/// 获取 Antigravity 数据库路径（跨平台）
-pub fn get_db_path() -> Result<PathBuf, String> {
-    // 优先检查 --user-data-dir 参数指定的路径
-    if let Some(user_data_dir) = crate::modules::process::get_user_data_dir_from_process() {
-        let custom_db_path = user_data_dir.join("User").join("globalStorage").join("state.vscdb");
-        if custom_db_path.exists() {
-            return Ok(custom_db_path);
-        }
-    }
+/// `--user-data-dir` 启动参数指定的数据库路径
+fn user_data_dir_db_path() -> Option<PathBuf> {
+    crate::modules::process::get_user_data_dir_from_process()
+        .map(|dir| dir.join("User").join("globalStorage").join("state.vscdb"))
+}
 
-    // 检查是否为便携模式
-    if let Some(antigravity_path) = get_antigravity_path() {
-        if let Some(parent_dir) = antigravity_path.parent() {
-            let portable_db_path = PathBuf::from(parent_dir)
-                .join("data")
-                .join("user-data")
-                .join("User")
-                .join("globalStorage")
-                .join("state.vscdb");
-
-            if portable_db_path.exists() {
-                return Ok(portable_db_path);
-            }
-        }
-    }
+/// 便携模式 (可执行文件同级的 data 目录) 的数据库路径
+fn portable_db_path() -> Option<PathBuf> {
+    let antigravity_path = get_antigravity_path()?;
+    let parent_dir = antigravity_path.parent()?;
+    Some(
+        PathBuf::from(parent_dir)
+            .join("data")
+            .join("user-data")
+            .join("User")
+            .join("globalStorage")
+            .join("state.vscdb"),
+    )
+}
 
-    // 标准模式：使用系统默认路径
+/// 标准模式：系统默认路径
+fn standard_db_path() -> Result<PathBuf, String> {
     #[cfg(target_os = "macos")]
     {
         let home = dirs::home_dir().ok_or("无法获取 Home 目录")?;
@@ -62,6 +57,33 @@ pub fn get_db_path() -> Result<PathBuf, String> {
     }
 }
 
+/// 获取 Antigravity 数据库路径（跨平台）
+pub fn get_db_path() -> Result<PathBuf, String> {
+    // 优先检查 --user-data-dir 参数指定的路径，其次为便携模式
+    if let Some(path) = user_data_dir_db_path().filter(|p| p.exists()) {
+        return Ok(path);
+    }
+    if let Some(path) = portable_db_path().filter(|p| p.exists()) {
+        return Ok(path);
+    }
+    standard_db_path()
+}
+
+/// 本机所有存在的 Antigravity 数据库 (启动参数指定、便携模式、系统默认路径，去重)
+pub fn candidate_db_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for path in [user_data_dir_db_path(), portable_db_path(), standard_db_path().ok()]
+        .into_iter()
+        .flatten()
+    {
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+        if path.exists() && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
 /// 注入 Token 到数据库
 pub fn inject_token(
     db_path: &PathBuf,
@@ -0,0 +1,82 @@
+// 账号设备标识注册表：按 access_token 查找账号的 machine_id / 会话 ID 与客户端标识档案，
+// 供 OAuth、配额查询与反代请求统一携带，避免多账号共用同一设备指纹
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderValue};
+use sha2::{Digest, Sha256};
+
+use crate::models::{Account, DeviceIdentity};
+
+/// 上游请求携带 machine_id 的请求头
+pub const MACHINE_ID_HEADER: &str = "x-machine-id";
+
+struct Registration {
+    device: DeviceIdentity,
+    client_profile: Option<String>,
+}
+
+static BY_TOKEN: Lazy<DashMap<String, Registration>> = Lazy::new(DashMap::new);
+
+/// 登记账号当前 access_token 对应的设备标识
+pub fn register(account: &Account) {
+    if let Some(device) = &account.device {
+        register_token(&account.token.access_token, device, account.client_profile.as_deref());
+    }
+}
+
+/// 登记 access_token 对应的设备标识与客户端档案；同一 machine_id 的旧 token (刷新前) 会被移除
+pub fn register_token(access_token: &str, device: &DeviceIdentity, client_profile: Option<&str>) {
+    if access_token.is_empty() {
+        return;
+    }
+    BY_TOKEN.retain(|token, existing| {
+        token == access_token || existing.device.machine_id != device.machine_id
+    });
+    BY_TOKEN.insert(
+        access_token.to_string(),
+        Registration {
+            device: device.clone(),
+            client_profile: client_profile.map(str::to_string),
+        },
+    );
+}
+
+pub fn lookup(access_token: &str) -> Option<DeviceIdentity> {
+    BY_TOKEN.get(access_token).map(|r| r.device.clone())
+}
+
+/// 为 access_token 对应账号附加客户端档案请求头与 machine_id
+/// (未登记的 token 使用全局默认档案，且不带 machine_id)
+pub fn apply_headers(headers: &mut HeaderMap, access_token: &str) {
+    let registration = BY_TOKEN.get(access_token);
+    let profile_name = registration.as_ref().and_then(|r| r.client_profile.as_deref());
+    crate::proxy::upstream::profiles::apply(
+        headers,
+        &crate::proxy::upstream::profiles::resolve(profile_name),
+    );
+    if let Some(registration) = &registration {
+        if let Ok(value) = HeaderValue::from_str(&registration.device.machine_id) {
+            headers.insert(MACHINE_ID_HEADER, value);
+        }
+    }
+}
+
+/// access_token 对应账号的设备请求头，供 `RequestBuilder::headers` 使用
+pub fn headers_for(access_token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    apply_headers(&mut headers, access_token);
+    headers
+}
+
+/// 上游请求使用的会话 ID：客户端自带的会话 ID 会被映射到账号自己的会话下，
+/// 保证不同账号之间不会出现相同的 sessionId
+pub fn scoped_session_id(device: &DeviceIdentity, client_session: Option<&str>) -> String {
+    match client_session.filter(|s| !s.is_empty()) {
+        Some(client) => {
+            let digest = Sha256::digest(client.as_bytes());
+            let suffix: String = digest.iter().take(4).map(|b| format!("{:02x}", b)).collect();
+            format!("{}-{}", device.session_id, suffix)
+        }
+        None => device.session_id.clone(),
+    }
+}
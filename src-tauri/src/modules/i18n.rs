@@ -1,5 +1,7 @@
+use once_cell::sync::Lazy;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::RwLock;
 
 /// 托盘文本结构
 #[derive(Debug, Clone)]
@@ -15,8 +17,8 @@ pub struct TrayTexts {
     pub forbidden: String,
 }
 
-/// 从 JSON 加载翻译
-fn load_translations(lang: &str) -> HashMap<String, String> {
+/// 从 JSON 加载指定分组 (如 `tray`、`cli`) 的翻译
+fn load_translations(lang: &str, section: &str) -> HashMap<String, String> {
     let json_content = match lang {
         "en" | "en-US" => include_str!("../../../src/locales/en.json"),
         _ => include_str!("../../../src/locales/zh.json"),
@@ -27,8 +29,8 @@ fn load_translations(lang: &str) -> HashMap<String, String> {
     
     let mut map = HashMap::new();
     
-    if let Some(tray) = v.get("tray").and_then(|t| t.as_object()) {
-        for (key, value) in tray {
+    if let Some(group) = v.get(section).and_then(|t| t.as_object()) {
+        for (key, value) in group {
             if let Some(s) = value.as_str() {
                 map.insert(key.clone(), s.to_string());
             }
@@ -40,7 +42,7 @@ fn load_translations(lang: &str) -> HashMap<String, String> {
 
 /// 获取托盘文本（根据语言）
 pub fn get_tray_texts(lang: &str) -> TrayTexts {
-    let t = load_translations(lang);
+    let t = load_translations(lang, "tray");
     
     TrayTexts {
         current: t.get("current").cloned().unwrap_or_else(|| "Current".to_string()),
@@ -54,3 +56,29 @@ pub fn get_tray_texts(lang: &str) -> TrayTexts {
         forbidden: t.get("forbidden").cloned().unwrap_or_else(|| "Account Forbidden".to_string()),
     }
 }
+
+/// 命令行 (无头模式) 输出语言及其文本缓存
+static CLI_TEXTS: Lazy<RwLock<(String, HashMap<String, String>)>> =
+    Lazy::new(|| RwLock::new(("zh".to_string(), load_translations("zh", "cli"))));
+
+/// 设置命令行输出语言 (`zh`/`zh-CN`、`en`/`en-US`，其他值按中文处理)
+pub fn set_cli_language(lang: &str) {
+    if let Ok(mut texts) = CLI_TEXTS.write() {
+        if texts.0 != lang {
+            *texts = (lang.to_string(), load_translations(lang, "cli"));
+        }
+    }
+}
+
+/// 获取命令行文本，`{{name}}` 占位符按 `args` 替换；缺少翻译时返回 key 本身
+pub fn cli_text(key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let mut text = CLI_TEXTS
+        .read()
+        .ok()
+        .and_then(|texts| texts.1.get(key).cloned())
+        .unwrap_or_else(|| key.to_string());
+    for (name, value) in args {
+        text = text.replace(&format!("{{{{{}}}}}", name), &value.to_string());
+    }
+    text
+}
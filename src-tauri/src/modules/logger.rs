@@ -1,9 +1,166 @@
 use tracing::{info, warn, error};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
 use crate::modules::account::get_data_dir;
 
+/// 运行时可调整的日志过滤器，由 init_logger / init_json_logger 安装
+static LOG_FILTER: OnceCell<FilterControl> = OnceCell::new();
+
+struct FilterControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// 启动时的过滤规则 (RUST_LOG 或 info)
+    default: String,
+    /// (附加规则, 到期时间 (Unix 秒), 代次)；代次用于让过期的自动恢复任务失效
+    state: Mutex<(Option<String>, Option<i64>, u64)>,
+}
+
+/// 当前日志过滤器状态
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFilterStatus {
+    /// 实际生效的过滤规则
+    pub filter: String,
+    pub default: String,
+    /// 在默认规则之上临时附加的规则
+    pub overrides: Option<String>,
+    /// 附加规则自动失效的时间 (Unix 秒)
+    pub expires_at: Option<i64>,
+}
+
+/// 启动时的过滤规则: RUST_LOG 有效时使用，否则为 info
+fn default_filter() -> (EnvFilter, String) {
+    match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(value) => match EnvFilter::try_new(&value) {
+            Ok(filter) => (filter, value),
+            Err(_) => (EnvFilter::new("info"), "info".to_string()),
+        },
+        Err(_) => (EnvFilter::new("info"), "info".to_string()),
+    }
+}
+
+/// 安装可重载的过滤层，返回给订阅器使用
+fn reloadable_filter() -> reload::Layer<EnvFilter, Registry> {
+    let (filter, default) = default_filter();
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = LOG_FILTER.set(FilterControl {
+        handle,
+        default,
+        state: Mutex::new((None, None, 0)),
+    });
+    layer
+}
+
+/// 本 crate 的顶层模块名，规则中以它们开头的目标自动补全 crate 名 (如 `proxy::mappers=debug`)
+const CRATE_MODULES: &[&str] = &["proxy", "modules", "commands", "models", "utils", "headless", "error"];
+
+fn qualify_directive(directive: &str) -> String {
+    let directive = directive.trim();
+    let target_end = directive.find(['[', '=']).unwrap_or(directive.len());
+    let first = directive[..target_end].split("::").next().unwrap_or_default();
+    if CRATE_MODULES.contains(&first) {
+        let crate_name = module_path!().split("::").next().unwrap_or_default();
+        format!("{}::{}", crate_name, directive)
+    } else {
+        directive.to_string()
+    }
+}
+
+fn status_of(control: &FilterControl, state: &(Option<String>, Option<i64>, u64)) -> LogFilterStatus {
+    let filter = match &state.0 {
+        Some(overrides) => format!("{},{}", control.default, overrides),
+        None => control.default.clone(),
+    };
+    LogFilterStatus {
+        filter,
+        default: control.default.clone(),
+        overrides: state.0.clone(),
+        expires_at: state.1,
+    }
+}
+
+fn apply(control: &FilterControl, state: &(Option<String>, Option<i64>, u64)) -> Result<LogFilterStatus, String> {
+    let status = status_of(control, state);
+    let filter = EnvFilter::try_new(&status.filter).map_err(|e| format!("日志过滤规则无效: {}", e))?;
+    control
+        .handle
+        .reload(filter)
+        .map_err(|e| format!("更新日志过滤规则失败: {}", e))?;
+    Ok(status)
+}
+
+fn control() -> Result<&'static FilterControl, String> {
+    LOG_FILTER.get().ok_or_else(|| "日志系统尚未初始化".to_string())
+}
+
+/// 当前日志过滤器状态，日志系统未初始化时为 None
+pub fn log_filter_status() -> Option<LogFilterStatus> {
+    let control = LOG_FILTER.get()?;
+    let state = control.state.lock().unwrap_or_else(|e| e.into_inner());
+    Some(status_of(control, &state))
+}
+
+/// 在默认规则之上附加过滤规则 (如 `proxy::mappers=debug`)，替换此前附加的规则；
+/// 指定 `duration` 时到期后自动恢复默认规则
+pub fn set_log_filter(directives: &str, duration: Option<Duration>) -> Result<LogFilterStatus, String> {
+    let control = control()?;
+    let overrides: Vec<String> = directives
+        .split(',')
+        .filter(|d| !d.trim().is_empty())
+        .map(qualify_directive)
+        .collect();
+    if overrides.is_empty() {
+        return Err("日志过滤规则为空".to_string());
+    }
+    let overrides = overrides.join(",");
+    // 逐条校验，便于指出具体哪条无效
+    for directive in overrides.split(',') {
+        directive
+            .parse::<tracing_subscriber::filter::Directive>()
+            .map_err(|e| format!("日志过滤规则无效 ({}): {}", directive, e))?;
+    }
+
+    let mut state = control.state.lock().unwrap_or_else(|e| e.into_inner());
+    let expires_at = duration.map(|d| chrono::Utc::now().timestamp() + d.as_secs() as i64);
+    let next = (Some(overrides), expires_at, state.2 + 1);
+    let status = apply(control, &next)?;
+    *state = next;
+    let generation = state.2;
+    drop(state);
+
+    if let Some(duration) = duration {
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let mut state = control.state.lock().unwrap_or_else(|e| e.into_inner());
+            // 期间已被再次修改或恢复时不处理
+            if state.2 != generation {
+                return;
+            }
+            let next = (None, None, generation + 1);
+            if apply(control, &next).is_ok() {
+                *state = next;
+                info!("临时日志过滤规则已到期，恢复为: {}", control.default);
+            }
+        });
+    }
+    info!("日志过滤规则已更新为: {}", status.filter);
+    Ok(status)
+}
+
+/// 移除附加的过滤规则，恢复启动时的默认规则
+pub fn reset_log_filter() -> Result<LogFilterStatus, String> {
+    let control = control()?;
+    let mut state = control.state.lock().unwrap_or_else(|e| e.into_inner());
+    let next = (None, None, state.2 + 1);
+    let status = apply(control, &next)?;
+    *state = next;
+    info!("日志过滤规则已恢复为: {}", status.filter);
+    Ok(status)
+}
+
 // 自定义本地时区时间格式化器
 struct LocalTimer;
 
@@ -14,6 +171,32 @@ impl tracing_subscriber::fmt::time::FormatTime for LocalTimer {
     }
 }
 
+/// 写出前脱敏日志中的密钥/令牌 (fmt 层每条事件整体写入一次)
+struct RedactingWriter<W>(W);
+
+impl<W: std::io::Write> std::io::Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.0
+            .write_all(crate::proxy::secrets::redact_text(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct Redacted<M>(M);
+
+impl<'a, M: fmt::MakeWriter<'a>> fmt::MakeWriter<'a> for Redacted<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
 pub fn get_log_dir() -> Result<PathBuf, String> {
     let data_dir = get_data_dir()?;
     let log_dir = data_dir.join("logs");
@@ -45,6 +228,7 @@ pub fn init_logger() {
     
     // 2. 终端输出层（使用本地时区）
     let console_layer = fmt::Layer::new()
+        .with_writer(Redacted(std::io::stdout))
         .with_target(false)
         .with_thread_ids(false)
         .with_level(true)
@@ -52,15 +236,14 @@ pub fn init_logger() {
         
     // 3. 文件输出层 (关闭 ANSI 格式化，使用本地时区)
     let file_layer = fmt::Layer::new()
-        .with_writer(non_blocking)
+        .with_writer(Redacted(non_blocking))
         .with_ansi(false)
         .with_target(true)
         .with_level(true)
         .with_timer(LocalTimer);
 
-    // 4. 设置过滤层 (默认使用 INFO 级别以减少日志体积)
-    let filter_layer = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info"));
+    // 4. 设置过滤层 (默认使用 INFO 级别以减少日志体积，运行时可通过管理端点调整)
+    let filter_layer = reloadable_filter();
 
     // 5. 初始化全局订阅器 (使用 try_init 避免重复初始化崩溃)
     let _ = tracing_subscriber::registry()
@@ -76,6 +259,30 @@ pub fn init_logger() {
     info!("日志系统已完成初始化 (终端控制台 + 文件持久化)");
 }
 
+/// 初始化无头模式日志 (JSON 输出到 stdout，由容器运行时收集，不写入文件)
+pub fn init_json_logger() {
+    let _ = tracing_log::LogTracer::init();
+
+    let filter_layer = reloadable_filter();
+
+    // 每条日志一行 JSON: timestamp / level / target / message 与事件字段平铺在顶层
+    let json_layer = fmt::Layer::new()
+        .json()
+        .flatten_event(true)
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_writer(Redacted(std::io::stdout))
+        .with_target(true)
+        .with_timer(LocalTimer);
+
+    let _ = tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(json_layer)
+        .try_init();
+
+    info!("日志系统已完成初始化 (JSON 标准输出)");
+}
+
 /// 清理日志缓存 (采用截断模式以保持文件句柄有效)
 pub fn clear_logs() -> Result<(), String> {
     let log_dir = get_log_dir()?;
@@ -10,6 +10,16 @@ pub mod migration;
 pub mod tray;
 pub mod i18n;
 pub mod proxy_db;
+pub mod service;
+pub mod profile;
+pub mod config_validation;
+pub mod client_config;
+pub mod audit;
+pub mod device;
+pub mod storage;
+pub mod snapshot;
+pub mod token_health;
+pub mod config_export;
 
 use crate::models;
 
@@ -1,4 +1,7 @@
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 // Google OAuth 配置
 const CLIENT_ID: &str = "1071006060591-tmhssin2h21lcre235vtolojh4g403ep.apps.googleusercontent.com";
@@ -161,6 +164,7 @@ pub async fn get_user_info(access_token: &str) -> Result<UserInfo, String> {
     let response = client
         .get(USERINFO_URL)
         .bearer_auth(access_token)
+        .headers(crate::modules::device::headers_for(access_token))
         .send()
         .await
         .map_err(|e| format!("用户信息请求失败: {}", e))?;
@@ -175,6 +179,10 @@ pub async fn get_user_info(access_token: &str) -> Result<UserInfo, String> {
     }
 }
 
+/// 同一 refresh_token 的刷新单飞：并发调用排队等待，复用第一个任务刷新到的 token
+static REFRESH_FLIGHTS: Lazy<DashMap<String, Arc<tokio::sync::Mutex<Option<crate::models::TokenData>>>>> =
+    Lazy::new(DashMap::new);
+
 /// 检查并在需要时刷新 Token
 /// 返回最新的 access_token
 pub async fn ensure_fresh_token(
@@ -186,18 +194,30 @@ pub async fn ensure_fresh_token(
     if current_token.expiry_timestamp > now + 300 {
         return Ok(current_token.clone());
     }
+
+    let flight = REFRESH_FLIGHTS
+        .entry(current_token.refresh_token.clone())
+        .or_default()
+        .clone();
+    let mut latest = flight.lock().await;
+    // 排队期间已被其他任务刷新
+    if let Some(token) = latest.as_ref().filter(|t| t.expiry_timestamp > now + 300) {
+        return Ok(token.clone());
+    }
     
     // 需要刷新
     crate::modules::logger::log_info("Token 即将过期，正在刷新...");
     let response = refresh_access_token(&current_token.refresh_token).await?;
     
     // 构造新 TokenData
-    Ok(crate::models::TokenData::new(
+    let token = crate::models::TokenData::new(
         response.access_token,
         current_token.refresh_token.clone(), // 刷新时不一定会返回新的 refresh_token
         response.expires_in,
         current_token.email.clone(),
         current_token.project_id.clone(), // 保留原有 project_id
         None,  // session_id 会在 token_manager 中生成
-    ))
+    );
+    *latest = Some(token.clone());
+    Ok(token)
 }
@@ -308,3 +308,139 @@ pub async fn complete_oauth_flow(app_handle: tauri::AppHandle) -> Result<oauth::
 
     oauth::exchange_code(&code, &redirect_uri).await
 }
+
+/// 无头模式 OAuth (不依赖 Tauri): 监听 IPv4 回环地址，通过 `on_url` 输出授权链接后等待浏览器回调，
+/// 再交换 token。浏览器需与本进程在同一台机器上
+pub async fn loopback_oauth_flow(on_url: impl FnOnce(&str)) -> Result<oauth::TokenResponse, String> {
+    let (listener, port) = bind_loopback().await?;
+    let redirect_uri = format!("http://127.0.0.1:{}/oauth-callback", port);
+    on_url(&oauth::get_auth_url(&redirect_uri));
+
+    let code = wait_for_callback(&listener, port).await?;
+    oauth::exchange_code(&code, &redirect_uri).await
+}
+
+/// 无浏览器服务器的远程授权: 通过 `on_url` 输出授权链接与回调端口，在任意设备上完成登录后，
+/// 由 `read_pasted` 读取用户粘贴的回调地址 (或授权码)；若回调端口已通过 `ssh -L` 转发到本机，
+/// 浏览器的回调会直接完成授权，两者以先到者为准
+///
+/// Google 的设备码流程不支持 cloud-platform 权限，因此沿用回环地址的授权链接，仅改为手动回传授权码
+pub async fn remote_oauth_flow(
+    on_url: impl FnOnce(&str, u16),
+    read_pasted: impl FnOnce() -> Option<String> + Send + 'static,
+) -> Result<oauth::TokenResponse, String> {
+    let (listener, port) = bind_loopback().await?;
+    let redirect_uri = format!("http://127.0.0.1:{}/oauth-callback", port);
+    on_url(&oauth::get_auth_url(&redirect_uri), port);
+
+    let pasted = tokio::task::spawn_blocking(read_pasted);
+    let code = tokio::select! {
+        code = wait_for_callback(&listener, port) => code?,
+        pasted = pasted => {
+            let input = pasted
+                .map_err(|e| format!("读取输入失败: {}", e))?
+                .ok_or_else(|| "已取消授权".to_string())?;
+            parse_pasted_code(&input)?
+        }
+    };
+    oauth::exchange_code(&code, &redirect_uri).await
+}
+
+async fn bind_loopback() -> Result<(TcpListener, u16), String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("无法绑定本地端口: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("无法获取本地端口: {}", e))?
+        .port();
+    Ok((listener, port))
+}
+
+/// 等待浏览器访问回调地址，返回其中的授权码
+async fn wait_for_callback(listener: &TcpListener, port: u16) -> Result<String, String> {
+    loop {
+        let (mut stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("接受连接失败: {}", e))?;
+        let mut buffer = [0u8; 4096];
+        let _ = stream.read(&mut buffer).await;
+        let request = String::from_utf8_lossy(&buffer);
+        let Some(path) = request
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .filter(|path| path.starts_with("/oauth-callback"))
+        else {
+            // 浏览器的 favicon 等请求，忽略后继续等待回调
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await;
+            continue;
+        };
+        let code = Url::parse(&format!("http://127.0.0.1:{}{}", port, path))
+            .ok()
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(k, _)| k == "code")
+                    .map(|(_, v)| v.into_owned())
+            });
+
+        let response_html = if code.is_some() { oauth_success_html() } else { oauth_fail_html() };
+        let _ = stream.write_all(response_html.as_bytes()).await;
+        let _ = stream.flush().await;
+
+        return code.ok_or_else(|| "未能在回调中获取 Authorization Code".to_string());
+    }
+}
+
+/// 从粘贴的内容中取出授权码: 完整的回调地址、仅查询参数 (`code=...&scope=...`) 或授权码本身
+fn parse_pasted_code(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("未输入回调地址或授权码".to_string());
+    }
+    if !input.contains("code=") && !input.contains("error=") {
+        return Ok(input.to_string());
+    }
+    let query = input.split_once('?').map_or(input, |(_, q)| q);
+    let params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if let Some((_, error)) = params.iter().find(|(k, _)| k == "error") {
+        return Err(format!("授权被拒绝: {}", error));
+    }
+    params
+        .into_iter()
+        .find(|(k, v)| k == "code" && !v.is_empty())
+        .map(|(_, v)| v)
+        .ok_or_else(|| "未能在粘贴的地址中找到 Authorization Code".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pasted_callback_url_yields_code() {
+        let url = "http://127.0.0.1:51121/oauth-callback?state=abc&code=4%2F0AbC-xyz&scope=email%20profile";
+        assert_eq!(parse_pasted_code(url).unwrap(), "4/0AbC-xyz");
+    }
+
+    #[test]
+    fn pasted_query_string_yields_code() {
+        assert_eq!(parse_pasted_code("  code=4%2F0AbC&scope=email\n").unwrap(), "4/0AbC");
+    }
+
+    #[test]
+    fn raw_code_is_used_as_is() {
+        assert_eq!(parse_pasted_code(" 4/0AbC-xyz \n").unwrap(), "4/0AbC-xyz");
+        assert!(parse_pasted_code("   ").is_err());
+    }
+
+    #[test]
+    fn denied_authorization_is_reported() {
+        let err = parse_pasted_code("http://127.0.0.1:51121/oauth-callback?error=access_denied").unwrap_err();
+        assert!(err.contains("access_denied"), "{}", err);
+        assert!(parse_pasted_code("http://127.0.0.1:51121/oauth-callback?code=&state=x").is_err());
+    }
+}
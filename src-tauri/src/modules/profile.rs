@@ -0,0 +1,167 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// 默认配置档 (直接使用数据根目录，兼容旧版本)
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+const ACTIVE_PROFILE_FILE: &str = "active_profile";
+
+/// 当前进程使用的配置档，启动时确定后不再变化
+static PROCESS_PROFILE: OnceCell<String> = OnceCell::new();
+
+/// 配置档信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub data_dir: String,
+    /// 当前进程正在使用
+    pub active: bool,
+    /// 下次启动默认使用
+    pub is_default: bool,
+}
+
+/// 校验配置档名称 (仅允许字母、数字、`-`、`_`)
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("配置档名称长度必须在 1-64 之间".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("配置档名称只能包含字母、数字、- 和 _: {}", name));
+    }
+    Ok(())
+}
+
+/// 从启动参数中解析 `--profile <name>` / `--profile=<name>`
+pub fn parse_profile_arg<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--profile" {
+            return iter.next();
+        }
+        if let Some(value) = arg.strip_prefix("--profile=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// 单实例标识: 默认配置档沿用应用标识，其他配置档各自独立，不同配置档的窗口可以同时运行
+pub fn instance_identifier(app_identifier: &str, profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        app_identifier.to_string()
+    } else {
+        format!("{}.profile-{}", app_identifier, profile)
+    }
+}
+
+/// 为当前进程指定配置档 (需在首次访问数据目录之前调用)
+pub fn set_process_profile(name: &str) -> Result<(), String> {
+    validate_profile_name(name)?;
+    PROCESS_PROFILE
+        .set(name.to_string())
+        .map_err(|_| "配置档已初始化，无法在运行中切换".to_string())
+}
+
+/// 读取持久化的默认配置档
+fn load_default_profile() -> String {
+    crate::modules::account::get_root_data_dir()
+        .ok()
+        .map(|root| root.join(ACTIVE_PROFILE_FILE))
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| validate_profile_name(s).is_ok())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// 获取当前进程使用的配置档名称
+pub fn get_active_profile() -> String {
+    PROCESS_PROFILE.get_or_init(load_default_profile).clone()
+}
+
+/// 计算指定配置档的数据目录
+pub fn get_profile_dir(name: &str) -> Result<PathBuf, String> {
+    let root = crate::modules::account::get_root_data_dir()?;
+    if name == DEFAULT_PROFILE {
+        Ok(root)
+    } else {
+        Ok(root.join(PROFILES_DIR).join(name))
+    }
+}
+
+/// 列出所有配置档
+pub fn list_profiles() -> Result<Vec<ProfileInfo>, String> {
+    let root = crate::modules::account::get_root_data_dir()?;
+    let active = get_active_profile();
+    let default = load_default_profile();
+
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    let profiles_dir = root.join(PROFILES_DIR);
+    if profiles_dir.exists() {
+        let entries =
+            fs::read_dir(&profiles_dir).map_err(|e| format!("读取配置档目录失败: {}", e))?;
+        for entry in entries.flatten() {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if validate_profile_name(&name).is_ok() && name != DEFAULT_PROFILE {
+                names.push(name);
+            }
+        }
+    }
+    names[1..].sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            Ok(ProfileInfo {
+                data_dir: get_profile_dir(&name)?.to_string_lossy().to_string(),
+                active: name == active,
+                is_default: name == default,
+                name,
+            })
+        })
+        .collect()
+}
+
+/// 创建配置档 (拥有独立的数据目录、账号与反代配置)
+pub fn create_profile(name: &str) -> Result<ProfileInfo, String> {
+    validate_profile_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err("默认配置档已存在".to_string());
+    }
+
+    let dir = get_profile_dir(name)?;
+    if dir.exists() {
+        return Err(format!("配置档已存在: {}", name));
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("创建配置档目录失败: {}", e))?;
+    crate::modules::logger::log_info(&format!("已创建配置档: {} ({:?})", name, dir));
+
+    Ok(ProfileInfo {
+        name: name.to_string(),
+        data_dir: dir.to_string_lossy().to_string(),
+        active: false,
+        is_default: false,
+    })
+}
+
+/// 设置默认配置档 (下次启动生效；`--profile` 参数优先)
+pub fn switch_profile(name: &str) -> Result<(), String> {
+    validate_profile_name(name)?;
+    let dir = get_profile_dir(name)?;
+    if !dir.exists() {
+        return Err(format!("配置档不存在: {}", name));
+    }
+
+    let root = crate::modules::account::get_root_data_dir()?;
+    fs::write(root.join(ACTIVE_PROFILE_FILE), name)
+        .map_err(|e| format!("保存默认配置档失败: {}", e))?;
+    crate::modules::logger::log_info(&format!("默认配置档已切换为: {}，重启后生效", name));
+    Ok(())
+}
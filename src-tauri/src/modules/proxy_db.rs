@@ -30,22 +30,72 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN response_body TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN input_tokens INTEGER", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN output_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN session_id TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN account TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN key_id TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_ip TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN canary_target TEXT", []);
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC)",
         [],
     ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_session ON request_logs (session_id, timestamp DESC)",
+        [],
+    ).map_err(|e| e.to_string())?;
+
+    // 小时 / 天级统计汇总 (维度缺失时存为空字符串，保证主键唯一)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stats_rollups (
+            period TEXT NOT NULL,
+            bucket INTEGER NOT NULL,
+            model TEXT NOT NULL DEFAULT '',
+            account TEXT NOT NULL DEFAULT '',
+            key_id TEXT NOT NULL DEFAULT '',
+            requests INTEGER NOT NULL DEFAULT 0,
+            errors INTEGER NOT NULL DEFAULT 0,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            duration_ms INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (period, bucket, model, account, key_id)
+        )",
+        [],
+    ).map_err(|e| e.to_string())?;
+
+    // 首次创建汇总表时，从已有的请求日志回填
+    let rollups: i64 = conn
+        .query_row("SELECT COUNT(*) FROM stats_rollups", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if rollups == 0 {
+        for period in [crate::proxy::stats::Period::Hour, crate::proxy::stats::Period::Day] {
+            conn.execute(
+                "INSERT INTO stats_rollups (period, bucket, model, account, key_id, requests, errors, input_tokens, output_tokens, duration_ms)
+                 SELECT ?1, (timestamp / ?2) * ?2, COALESCE(model, ''), COALESCE(account, ''), COALESCE(key_id, ''),
+                        COUNT(*),
+                        SUM(CASE WHEN status < 200 OR status >= 400 THEN 1 ELSE 0 END),
+                        COALESCE(SUM(input_tokens), 0),
+                        COALESCE(SUM(output_tokens), 0),
+                        COALESCE(SUM(duration), 0)
+                 FROM request_logs
+                 GROUP BY 2, 3, 4, 5",
+                params![period.as_str(), period.millis()],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    prune_rollups(&conn, chrono::Utc::now().timestamp_millis())?;
 
     Ok(())
 }
 
+/// 保存请求日志 (ID 即请求 ID；客户端重复使用同一 X-Request-Id 时保留最新一次)
 pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
     let db_path = get_proxy_db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        "INSERT OR REPLACE INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
         params![
             log.id,
             log.timestamp,
@@ -59,6 +109,11 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.response_body,
             log.input_tokens,
             log.output_tokens,
+            log.session_id,
+            log.account,
+            log.key_id,
+            log.client_ip,
+            log.canary_target,
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -70,28 +125,13 @@ pub fn get_logs(limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens
+        "SELECT id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1"
     ).map_err(|e| e.to_string())?;
 
-    let logs_iter = stmt.query_map([limit], |row| {
-        Ok(ProxyRequestLog {
-            id: row.get(0)?,
-            timestamp: row.get(1)?,
-            method: row.get(2)?,
-            url: row.get(3)?,
-            status: row.get(4)?,
-            duration: row.get(5)?,
-            model: row.get(6)?,
-            error: row.get(7)?,
-            request_body: row.get(8).unwrap_or(None),
-            response_body: row.get(9).unwrap_or(None),
-            input_tokens: row.get(10).unwrap_or(None),
-            output_tokens: row.get(11).unwrap_or(None),
-        })
-    }).map_err(|e| e.to_string())?;
+    let logs_iter = stmt.query_map([limit], row_to_log).map_err(|e| e.to_string())?;
 
     let mut logs = Vec::new();
     for log in logs_iter {
@@ -100,6 +140,292 @@ pub fn get_logs(limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
     Ok(logs)
 }
 
+/// 按 ID 读取单条日志
+pub fn get_log(id: &str) -> Result<Option<ProxyRequestLog>, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target
+         FROM request_logs
+         WHERE id = ?1"
+    ).map_err(|e| e.to_string())?;
+
+    let mut rows = stmt.query_map([id], row_to_log).map_err(|e| e.to_string())?;
+    rows.next().transpose().map_err(|e| e.to_string())
+}
+
+fn row_to_log(row: &rusqlite::Row) -> rusqlite::Result<ProxyRequestLog> {
+    Ok(ProxyRequestLog {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        method: row.get(2)?,
+        url: row.get(3)?,
+        status: row.get(4)?,
+        duration: row.get(5)?,
+        model: row.get(6)?,
+        error: row.get(7)?,
+        request_body: row.get(8).unwrap_or(None),
+        response_body: row.get(9).unwrap_or(None),
+        input_tokens: row.get(10).unwrap_or(None),
+        output_tokens: row.get(11).unwrap_or(None),
+        session_id: row.get(12).unwrap_or(None),
+        account: row.get(13).unwrap_or(None),
+        key_id: row.get(14).unwrap_or(None),
+        client_ip: row.get(15).unwrap_or(None),
+        canary_target: row.get(16).unwrap_or(None),
+    })
+}
+
+/// 按会话汇总请求数与 Token 用量，最近活跃的会话在前
+pub fn get_sessions(limit: usize) -> Result<Vec<crate::proxy::monitor::SessionSummary>, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT session_id,
+                COUNT(*),
+                SUM(CASE WHEN status < 200 OR status >= 400 THEN 1 ELSE 0 END),
+                COALESCE(SUM(input_tokens), 0),
+                COALESCE(SUM(output_tokens), 0),
+                MIN(timestamp),
+                MAX(timestamp),
+                GROUP_CONCAT(DISTINCT model)
+         FROM request_logs
+         WHERE session_id IS NOT NULL
+         GROUP BY session_id
+         ORDER BY MAX(timestamp) DESC
+         LIMIT ?1"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([limit], |row| {
+        let models: Option<String> = row.get(7)?;
+        Ok(crate::proxy::monitor::SessionSummary {
+            session_id: row.get(0)?,
+            requests: row.get(1)?,
+            error_count: row.get(2)?,
+            input_tokens: row.get(3)?,
+            output_tokens: row.get(4)?,
+            first_seen: row.get(5)?,
+            last_seen: row.get(6)?,
+            models: models
+                .map(|m| m.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+        })
+    }).map_err(|e| e.to_string())?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// 读取单个会话内的请求，按时间顺序排列
+pub fn get_session_logs(session_id: &str, limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target
+         FROM request_logs
+         WHERE session_id = ?1
+         ORDER BY timestamp ASC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![session_id, limit], row_to_log).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// 读取某账号最近的失败请求 (不含请求/响应体)，最新的在前
+pub fn get_account_errors(account: &str, limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error, NULL, NULL, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target
+         FROM request_logs
+         WHERE account = ?1 AND (status < 200 OR status >= 400)
+         ORDER BY timestamp DESC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![account, limit], row_to_log).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// 将一条请求日志计入小时与天级汇总
+pub fn record_rollups(log: &ProxyRequestLog) -> Result<(), String> {
+    use crate::proxy::stats::{Period, Rollup};
+
+    let db_path = get_proxy_db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for period in [Period::Hour, Period::Day] {
+        let r = Rollup::from_log(period, log);
+        tx.execute(
+            "INSERT INTO stats_rollups (period, bucket, model, account, key_id, requests, errors, input_tokens, output_tokens, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT (period, bucket, model, account, key_id) DO UPDATE SET
+                requests = requests + excluded.requests,
+                errors = errors + excluded.errors,
+                input_tokens = input_tokens + excluded.input_tokens,
+                output_tokens = output_tokens + excluded.output_tokens,
+                duration_ms = duration_ms + excluded.duration_ms",
+            params![
+                period.as_str(),
+                r.bucket,
+                r.model.unwrap_or_default(),
+                r.account.unwrap_or_default(),
+                r.key_id.unwrap_or_default(),
+                r.requests,
+                r.errors,
+                r.input_tokens,
+                r.output_tokens,
+                r.duration_ms,
+            ],
+        ).map_err(|e| e.to_string())?;
+    }
+    prune_rollups(&tx, log.timestamp)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 删除超出保留期的小时级汇总 (天级汇总长期保留)
+fn prune_rollups(conn: &Connection, now_ms: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM stats_rollups WHERE period = 'hour' AND bucket < ?1",
+        [crate::proxy::stats::hourly_cutoff(now_ms)],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn empty_to_none(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+/// 读取 `since` (Unix 毫秒) 所在区间及之后的汇总行
+pub fn get_rollups(period: crate::proxy::stats::Period, since: i64) -> Result<Vec<crate::proxy::stats::Rollup>, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT bucket, model, account, key_id, requests, errors, input_tokens, output_tokens, duration_ms
+         FROM stats_rollups
+         WHERE period = ?1 AND bucket >= ?2
+         ORDER BY bucket ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![period.as_str(), period.bucket(since)], |row| {
+        Ok(crate::proxy::stats::Rollup {
+            bucket: row.get(0)?,
+            model: empty_to_none(row.get(1)?),
+            account: empty_to_none(row.get(2)?),
+            key_id: empty_to_none(row.get(3)?),
+            requests: row.get(4)?,
+            errors: row.get(5)?,
+            input_tokens: row.get(6)?,
+            output_tokens: row.get(7)?,
+            duration_ms: row.get(8)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// 读取最近的失败请求 (不含请求/响应体)，最新的在前
+pub fn get_recent_errors(limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error, NULL, NULL, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target
+         FROM request_logs
+         WHERE status < 200 OR status >= 400
+         ORDER BY timestamp DESC
+         LIMIT ?1"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([limit], row_to_log).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// 读取 `since` (Unix 毫秒) 之后的请求 (不含请求/响应体)，按时间顺序排列
+pub fn get_logs_since(since: i64, limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error, NULL, NULL, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target
+         FROM request_logs
+         WHERE timestamp >= ?1
+         ORDER BY timestamp ASC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![since, limit], row_to_log).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// 逐条读取 `[since, until)` (Unix 毫秒) 范围内的日志，按时间升序交给 `f`，返回读取条数。
+/// 单次查询、逐行读取，导出大量历史日志时不会一次性载入内存。
+pub fn for_each_log(
+    since: i64,
+    until: i64,
+    with_bodies: bool,
+    mut f: impl FnMut(ProxyRequestLog) -> Result<(), String>,
+) -> Result<u64, String> {
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let bodies = if with_bodies { "request_body, response_body" } else { "NULL, NULL" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, timestamp, method, url, status, duration, model, error, {}, input_tokens, output_tokens, session_id, account, key_id, client_ip, canary_target
+         FROM request_logs
+         WHERE timestamp >= ?1 AND timestamp < ?2
+         ORDER BY timestamp ASC",
+        bodies
+    )).map_err(|e| e.to_string())?;
+
+    let mut rows = stmt.query(params![since, until]).map_err(|e| e.to_string())?;
+    let mut count = 0;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        f(row_to_log(row).map_err(|e| e.to_string())?)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// 按 (模型, 账号, 密钥) 聚合 `since` (Unix 毫秒) 之后的请求数与 Token 用量。
+/// 读取统计汇总而非请求日志: 小时级汇总覆盖的范围按小时取整，更早的部分按天取整。
+pub fn get_usage_rows(since: i64) -> Result<Vec<crate::proxy::usage_report::UsageRow>, String> {
+    use crate::proxy::stats::Period;
+
+    let db_path = get_proxy_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let cutoff = crate::proxy::stats::hourly_cutoff(chrono::Utc::now().timestamp_millis());
+
+    let mut stmt = conn.prepare(
+        "SELECT model, account, key_id, SUM(requests), SUM(input_tokens), SUM(output_tokens)
+         FROM stats_rollups
+         WHERE (period = 'day' AND bucket >= ?1 AND bucket < ?3)
+            OR (period = 'hour' AND bucket >= MAX(?2, ?3))
+         GROUP BY model, account, key_id"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(
+        params![Period::Day.bucket(since), Period::Hour.bucket(since), cutoff],
+        |row| {
+            Ok(crate::proxy::usage_report::UsageRow {
+                model: empty_to_none(row.get(0)?),
+                account: empty_to_none(row.get(1)?),
+                key_id: empty_to_none(row.get(2)?),
+                requests: row.get(3)?,
+                input_tokens: row.get(4)?,
+                output_tokens: row.get(5)?,
+            })
+        },
+    ).map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
 pub fn get_stats() -> Result<crate::proxy::monitor::ProxyStats, String> {
     let db_path = get_proxy_db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
@@ -2,9 +2,9 @@ use reqwest;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use crate::models::QuotaData;
+use crate::models::SubscriptionTier;
 
 const QUOTA_API_URL: &str = "https://cloudcode-pa.googleapis.com/v1internal:fetchAvailableModels";
-const USER_AGENT: &str = "antigravity/1.11.3 Darwin/arm64";
 
 #[derive(Debug, Serialize, Deserialize)]
 struct QuotaResponse {
@@ -15,6 +15,14 @@ struct QuotaResponse {
 struct ModelInfo {
     #[serde(rename = "quotaInfo")]
     quota_info: Option<QuotaInfo>,
+    #[serde(rename = "maxTokens", default)]
+    max_tokens: Option<u64>,
+    #[serde(rename = "maxOutputTokens", default)]
+    max_output_tokens: Option<u64>,
+    #[serde(rename = "supportsImages", default)]
+    supports_images: Option<bool>,
+    #[serde(rename = "supportsThinking", default)]
+    supports_thinking: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,7 +63,7 @@ fn create_client() -> reqwest::Client {
 const CLOUD_CODE_BASE_URL: &str = "https://cloudcode-pa.googleapis.com";
 
 /// 获取项目 ID 和订阅类型
-async fn fetch_project_id(access_token: &str, email: &str) -> (Option<String>, Option<String>) {
+async fn fetch_project_id(access_token: &str, email: &str) -> (Option<String>, Option<SubscriptionTier>) {
     let client = create_client();
     let meta = json!({"metadata": {"ideType": "ANTIGRAVITY"}});
 
@@ -63,7 +71,7 @@ async fn fetch_project_id(access_token: &str, email: &str) -> (Option<String>, O
         .post(format!("{}/v1internal:loadCodeAssist", CLOUD_CODE_BASE_URL))
         .header(reqwest::header::AUTHORIZATION, format!("Bearer {}", access_token))
         .header(reqwest::header::CONTENT_TYPE, "application/json")
-        .header(reqwest::header::USER_AGENT, "antigravity/windows/amd64")
+        .headers(crate::modules::device::headers_for(access_token))
         .json(&meta)
         .send()
         .await;
@@ -77,7 +85,8 @@ async fn fetch_project_id(access_token: &str, email: &str) -> (Option<String>, O
                     // 核心逻辑：优先从 paid_tier 获取订阅 ID，这比 current_tier 更能反映真实账户权益
                     let subscription_tier = data.paid_tier
                         .and_then(|t| t.id)
-                        .or_else(|| data.current_tier.and_then(|t| t.id));
+                        .or_else(|| data.current_tier.and_then(|t| t.id))
+                        .map(|id| SubscriptionTier::parse(&id));
                     
                     if let Some(ref tier) = subscription_tier {
                         crate::modules::logger::log_info(&format!(
@@ -129,7 +138,7 @@ pub async fn fetch_quota_inner(access_token: &str, email: &str) -> crate::error:
         match client
             .post(url)
             .bearer_auth(access_token)
-            .header("User-Agent", USER_AGENT)
+            .headers(crate::modules::device::headers_for(access_token))
             .json(&json!(payload))
             .send()
             .await
@@ -183,7 +192,13 @@ pub async fn fetch_quota_inner(access_token: &str, email: &str) -> crate::error:
                         
                         // 只保存我们关心的模型
                         if name.contains("gemini") || name.contains("claude") {
-                            quota_data.add_model(name, percentage, reset_time);
+                            let limits = crate::models::quota::ModelLimits {
+                                max_tokens: info.max_tokens,
+                                max_output_tokens: info.max_output_tokens,
+                                supports_images: info.supports_images,
+                                supports_thinking: info.supports_thinking,
+                            };
+                            quota_data.add_model(name, percentage, reset_time, Some(limits));
                         }
                     }
                 }
@@ -0,0 +1,376 @@
+use serde::Serialize;
+use std::process::Command;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::{fs, path::PathBuf};
+#[cfg(target_os = "linux")]
+use std::path::Path;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "linux")]
+const SYSTEMD_UNIT_NAME: &str = "antigravity-tools.service";
+/// logind 为开启 lingering 的用户在此目录下创建同名文件
+#[cfg(target_os = "linux")]
+const SYSTEMD_LINGER_DIR: &str = "/var/lib/systemd/linger";
+#[cfg(target_os = "macos")]
+const LAUNCHD_LABEL: &str = "com.lbjlaq.antigravity-tools.proxy";
+#[cfg(target_os = "windows")]
+const WINDOWS_TASK_NAME: &str = "AntigravityToolsProxy";
+
+/// 服务安装结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceInstallResult {
+    /// 平台 (systemd / launchd / windows-task)
+    pub platform: String,
+    /// 生成的服务文件路径 (Windows 计划任务为空)
+    pub path: Option<String>,
+    /// 服务启动时执行的完整命令行
+    pub command: Vec<String>,
+    /// systemd: 当前用户是否已开启 lingering (未开启时用户注销后服务随之停止)；其它平台或无法判断时为空
+    pub linger: Option<bool>,
+}
+
+/// 服务启动参数
+#[derive(Debug, Clone, Default)]
+pub struct ServiceOptions {
+    /// 反代监听端口，写入配置并作为 `--port` 传给服务
+    pub port: Option<u16>,
+    /// 附加到无头模式启动参数后的额外参数 (如 `--allow-lan`)
+    pub extra_args: Vec<String>,
+}
+
+/// 计算服务启动时执行的命令行
+fn build_command(options: &ServiceOptions) -> Result<Vec<String>, String> {
+    let exe = std::env::current_exe()
+        .and_then(|p| p.canonicalize())
+        .map_err(|e| format!("无法获取当前可执行文件路径: {}", e))?;
+    let profile = crate::modules::profile::get_active_profile();
+    Ok(service_command(&exe.to_string_lossy(), &profile, options))
+}
+
+/// 服务以无头模式运行 (服务器上通常没有图形界面)，并带上指定的端口与额外参数
+fn service_command(exe: &str, profile: &str, options: &ServiceOptions) -> Vec<String> {
+    let mut command = vec![exe.to_string(), "--headless".to_string()];
+    if let Some(port) = options.port {
+        command.push("--port".to_string());
+        command.push(port.to_string());
+    }
+    // 非默认配置档需显式指定，避免服务启动时读取其它配置档
+    if profile != crate::modules::profile::DEFAULT_PROFILE {
+        command.push("--profile".to_string());
+        command.push(profile.to_string());
+    }
+    command.extend(options.extra_args.iter().cloned());
+    command
+}
+
+/// 将端口写入配置并开启反代自启动，使服务拉起应用后自动开始监听
+fn persist_proxy_options(options: &ServiceOptions) -> Result<(), String> {
+    let mut config = crate::modules::config::load_app_config()?;
+    if let Some(port) = options.port {
+        config.proxy.port = port;
+    }
+    config.proxy.auto_start = true;
+    crate::modules::config::save_app_config(&config)
+}
+
+/// 生成 systemd user unit 内容
+#[cfg(target_os = "linux")]
+fn render_systemd_unit(command: &[String]) -> String {
+    let exec_start = command
+        .iter()
+        .map(|arg| quote_systemd_arg(arg.as_str()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "[Unit]\n\
+         Description=Antigravity Tools API Proxy\n\
+         After=network-online.target\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec_start
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn quote_systemd_arg(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(char::is_whitespace) || arg.contains('"') {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// 生成 launchd plist 内容
+#[cfg(target_os = "macos")]
+fn render_launchd_plist(label: &str, command: &[String], log_dir: &str) -> String {
+    let args = command
+        .iter()
+        .map(|arg| format!("        <string>{}</string>", escape_xml(arg)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{args}
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log_dir}/service.out.log</string>
+    <key>StandardErrorPath</key>
+    <string>{log_dir}/service.err.log</string>
+</dict>
+</plist>
+"#,
+        label = escape_xml(label),
+        args = args,
+        log_dir = escape_xml(log_dir),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[allow(dead_code)]
+fn run_command(program: &str, args: &[&str]) -> Result<(), String> {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("执行 {} 失败: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} 执行失败: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn systemd_unit_path() -> Result<PathBuf, String> {
+    let config_dir = dirs::config_dir().ok_or("无法获取用户配置目录")?;
+    Ok(config_dir
+        .join("systemd")
+        .join("user")
+        .join(SYSTEMD_UNIT_NAME))
+}
+
+/// 当前用户是否开启了 lingering；未开启时 systemd user 实例在最后一个会话结束后退出
+#[cfg(target_os = "linux")]
+fn linger_enabled() -> Option<bool> {
+    let user = std::env::var("USER").ok().filter(|u| !u.is_empty())?;
+    Some(Path::new(SYSTEMD_LINGER_DIR).join(user).exists())
+}
+
+#[cfg(target_os = "macos")]
+fn launchd_plist_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
+    Ok(home
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+/// 安装后台服务 (Linux: systemd user unit, macOS: launchd agent, Windows: 登录计划任务)
+pub fn install_service(options: &ServiceOptions) -> Result<ServiceInstallResult, String> {
+    let command = build_command(options)?;
+    persist_proxy_options(options)?;
+    install_platform_service(command)
+}
+
+/// 卸载后台服务
+pub fn uninstall_service() -> Result<(), String> {
+    uninstall_platform_service()
+}
+
+#[cfg(target_os = "linux")]
+fn install_platform_service(command: Vec<String>) -> Result<ServiceInstallResult, String> {
+    let path = systemd_unit_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建 systemd 目录失败: {}", e))?;
+    }
+    fs::write(&path, render_systemd_unit(&command))
+        .map_err(|e| format!("写入 systemd unit 失败: {}", e))?;
+
+    run_command("systemctl", &["--user", "daemon-reload"])?;
+    run_command("systemctl", &["--user", "enable", "--now", SYSTEMD_UNIT_NAME])?;
+
+    crate::modules::logger::log_info(&format!("已安装 systemd 服务: {:?}", path));
+    let linger = linger_enabled();
+    if linger == Some(false) {
+        crate::modules::logger::log_warn("当前用户未开启 lingering，注销后 systemd 用户服务将停止 (loginctl enable-linger)");
+    }
+    Ok(ServiceInstallResult {
+        platform: "systemd".to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        command,
+        linger,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn uninstall_platform_service() -> Result<(), String> {
+    let path = systemd_unit_path()?;
+    let _ = run_command("systemctl", &["--user", "disable", "--now", SYSTEMD_UNIT_NAME]);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("删除 systemd unit 失败: {}", e))?;
+    }
+    let _ = run_command("systemctl", &["--user", "daemon-reload"]);
+    crate::modules::logger::log_info("已卸载 systemd 服务");
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn install_platform_service(command: Vec<String>) -> Result<ServiceInstallResult, String> {
+    let path = launchd_plist_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建 LaunchAgents 目录失败: {}", e))?;
+    }
+    let log_dir = crate::modules::logger::get_log_dir()?;
+    fs::write(
+        &path,
+        render_launchd_plist(LAUNCHD_LABEL, &command, &log_dir.to_string_lossy()),
+    )
+    .map_err(|e| format!("写入 launchd plist 失败: {}", e))?;
+
+    let path_str = path.to_string_lossy().to_string();
+    // 重复安装时先卸载旧定义，忽略失败
+    let _ = run_command("launchctl", &["unload", &path_str]);
+    run_command("launchctl", &["load", "-w", &path_str])?;
+
+    crate::modules::logger::log_info(&format!("已安装 launchd 服务: {}", path_str));
+    Ok(ServiceInstallResult {
+        platform: "launchd".to_string(),
+        path: Some(path_str),
+        command,
+        linger: None,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn uninstall_platform_service() -> Result<(), String> {
+    let path = launchd_plist_path()?;
+    if path.exists() {
+        let path_str = path.to_string_lossy().to_string();
+        let _ = run_command("launchctl", &["unload", "-w", &path_str]);
+        fs::remove_file(&path).map_err(|e| format!("删除 launchd plist 失败: {}", e))?;
+    }
+    crate::modules::logger::log_info("已卸载 launchd 服务");
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn install_platform_service(command: Vec<String>) -> Result<ServiceInstallResult, String> {
+    let task_command = command
+        .iter()
+        .map(|arg| {
+            if arg.contains(' ') {
+                format!("\"{}\"", arg)
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    run_command(
+        "schtasks",
+        &[
+            "/Create", "/F", "/SC", "ONLOGON", "/RL", "LIMITED", "/TN", WINDOWS_TASK_NAME, "/TR",
+            &task_command,
+        ],
+    )?;
+
+    crate::modules::logger::log_info(&format!("已注册 Windows 计划任务: {}", WINDOWS_TASK_NAME));
+    Ok(ServiceInstallResult {
+        platform: "windows-task".to_string(),
+        path: None,
+        command,
+        linger: None,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn uninstall_platform_service() -> Result<(), String> {
+    run_command("schtasks", &["/Delete", "/F", "/TN", WINDOWS_TASK_NAME])?;
+    crate::modules::logger::log_info("已删除 Windows 计划任务");
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn install_platform_service(_command: Vec<String>) -> Result<ServiceInstallResult, String> {
+    Err("当前平台不支持安装后台服务".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn uninstall_platform_service() -> Result<(), String> {
+    Err("当前平台不支持卸载后台服务".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_runs_headless_with_port_and_flags() {
+        let options = ServiceOptions {
+            port: Some(9045),
+            extra_args: vec!["--allow-lan".to_string()],
+        };
+        let command = service_command("/opt/antigravity/antigravity_tools", "work", &options);
+        assert_eq!(
+            command,
+            ["/opt/antigravity/antigravity_tools", "--headless", "--port", "9045", "--profile", "work", "--allow-lan"]
+        );
+
+        let default = service_command("/usr/bin/app", crate::modules::profile::DEFAULT_PROFILE, &Default::default());
+        assert_eq!(default, ["/usr/bin/app", "--headless"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn systemd_unit_exec_start_is_headless() {
+        let options = ServiceOptions {
+            port: Some(9045),
+            extra_args: vec!["--allow-lan".to_string()],
+        };
+        let exe = "/opt/Antigravity Tools/app";
+        let unit = render_systemd_unit(&service_command(exe, crate::modules::profile::DEFAULT_PROFILE, &options));
+        let exec_start = unit.lines().find(|l| l.starts_with("ExecStart=")).unwrap();
+        assert_eq!(exec_start, "ExecStart=\"/opt/Antigravity Tools/app\" --headless --port 9045 --allow-lan");
+        assert!(!unit.contains("--minimized"));
+    }
+}
@@ -0,0 +1,208 @@
+// 账号池快照: 记录某一时刻各账号的配额与状态，之后与另一快照对比，量化一段时间内的实际消耗
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::storage::{self, Collection};
+use crate::models::Account;
+
+/// 单个账号在快照时刻的状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub id: String,
+    pub email: String,
+    /// active / disabled / proxy_disabled / forbidden
+    pub status: String,
+    pub tier: Option<String>,
+    /// 配额数据的刷新时间 (Unix 秒)，未获取过配额为 None
+    pub quota_updated_at: Option<i64>,
+    /// 各模型剩余配额百分比
+    pub models: BTreeMap<String, i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolSnapshot {
+    pub name: String,
+    pub created_at: i64,
+    pub accounts: Vec<AccountSnapshot>,
+}
+
+fn account_status(account: &Account) -> &'static str {
+    if account.disabled {
+        "disabled"
+    } else if account.proxy_disabled {
+        "proxy_disabled"
+    } else if account.quota.as_ref().is_some_and(|q| q.is_forbidden) {
+        "forbidden"
+    } else {
+        "active"
+    }
+}
+
+/// 由账号列表生成快照
+pub fn capture(name: &str, accounts: &[Account], now: i64) -> PoolSnapshot {
+    let accounts = accounts
+        .iter()
+        .map(|account| AccountSnapshot {
+            id: account.id.clone(),
+            email: account.email.clone(),
+            status: account_status(account).to_string(),
+            tier: account
+                .quota
+                .as_ref()
+                .and_then(|q| q.subscription_tier.as_ref())
+                .map(|t| t.as_str().to_string()),
+            quota_updated_at: account.quota.as_ref().map(|q| q.last_updated),
+            models: account
+                .quota
+                .as_ref()
+                .map(|q| q.models.iter().map(|m| (m.name.clone(), m.percentage)).collect())
+                .unwrap_or_default(),
+        })
+        .collect();
+    PoolSnapshot {
+        name: name.to_string(),
+        created_at: now,
+        accounts,
+    }
+}
+
+/// 保存当前账号池的快照 (同名快照覆盖)
+pub fn save(name: &str) -> Result<PoolSnapshot, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("快照名称不能为空".to_string());
+    }
+    let accounts = super::account::list_accounts()?;
+    let snapshot = capture(name, &accounts, chrono::Utc::now().timestamp());
+    let content = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| format!("序列化快照失败: {}", e))?;
+    storage::backend().write(Collection::Snapshots, name, &content)?;
+    Ok(snapshot)
+}
+
+/// 读取快照，不存在时返回 None
+pub fn load(name: &str) -> Result<Option<PoolSnapshot>, String> {
+    let Some(content) = storage::backend().read(Collection::Snapshots, name.trim())? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("解析快照 {} 失败: {}", name, e))
+}
+
+/// 所有快照，按创建时间排序
+pub fn list() -> Result<Vec<PoolSnapshot>, String> {
+    let mut snapshots = Vec::new();
+    for key in storage::backend().keys(Collection::Snapshots)? {
+        match load(&key) {
+            Ok(Some(snapshot)) => snapshots.push(snapshot),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    snapshots.sort_by_key(|s| s.created_at);
+    Ok(snapshots)
+}
+
+/// 单个模型的配额变化
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelChange {
+    pub model: String,
+    pub before: Option<i32>,
+    pub after: Option<i32>,
+}
+
+impl ModelChange {
+    /// 消耗的百分点 (负数表示期间配额已重置回升)
+    pub fn consumed(&self) -> Option<i32> {
+        Some(self.before? - self.after?)
+    }
+}
+
+/// 单个账号在两次快照之间的变化
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountChange {
+    pub email: String,
+    /// 仅在其中一次快照中存在时为 `added` / `removed`
+    pub presence: Option<&'static str>,
+    /// 状态变化 (之前, 之后)
+    pub status: Option<(String, String)>,
+    pub models: Vec<ModelChange>,
+}
+
+/// 两次快照的对比
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiff {
+    pub from: String,
+    pub to: String,
+    /// 两次快照的时间间隔 (秒)
+    pub elapsed_secs: i64,
+    pub accounts: Vec<AccountChange>,
+    /// 按模型汇总的消耗百分点 (所有账号之和，仅统计两次都有数据的账号)
+    pub consumed_by_model: BTreeMap<String, i32>,
+}
+
+/// 对比两次快照；账号按 ID 匹配，没有任何变化的账号不列出
+pub fn diff(from: &PoolSnapshot, to: &PoolSnapshot) -> SnapshotDiff {
+    let mut accounts = Vec::new();
+    let mut consumed_by_model: BTreeMap<String, i32> = BTreeMap::new();
+
+    for before in &from.accounts {
+        let Some(after) = to.accounts.iter().find(|a| a.id == before.id) else {
+            accounts.push(AccountChange {
+                email: before.email.clone(),
+                presence: Some("removed"),
+                status: None,
+                models: Vec::new(),
+            });
+            continue;
+        };
+
+        let mut names: Vec<&String> = before.models.keys().chain(after.models.keys()).collect();
+        names.sort();
+        names.dedup();
+        let models: Vec<ModelChange> = names
+            .into_iter()
+            .map(|name| ModelChange {
+                model: name.clone(),
+                before: before.models.get(name).copied(),
+                after: after.models.get(name).copied(),
+            })
+            .filter(|change| change.before != change.after)
+            .collect();
+        for change in &models {
+            if let Some(consumed) = change.consumed() {
+                *consumed_by_model.entry(change.model.clone()).or_default() += consumed;
+            }
+        }
+
+        let status = (before.status != after.status).then(|| (before.status.clone(), after.status.clone()));
+        if status.is_some() || !models.is_empty() {
+            accounts.push(AccountChange {
+                email: after.email.clone(),
+                presence: None,
+                status,
+                models,
+            });
+        }
+    }
+
+    for after in &to.accounts {
+        if !from.accounts.iter().any(|a| a.id == after.id) {
+            accounts.push(AccountChange {
+                email: after.email.clone(),
+                presence: Some("added"),
+                status: None,
+                models: Vec::new(),
+            });
+        }
+    }
+
+    SnapshotDiff {
+        from: from.name.clone(),
+        to: to.name.clone(),
+        elapsed_secs: to.created_at - from.created_at,
+        accounts,
+        consumed_by_model,
+    }
+}
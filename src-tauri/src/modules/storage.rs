@@ -0,0 +1,311 @@
+// 账号与配置的存储后端: 默认按原有目录结构保存为 JSON 文件，也可切换为 SQLite 或内存存储。
+// 嵌入 `antigravity_tools_lib` 的程序可通过 `set_backend` 提供自己的实现
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// 账号索引在 `Collection::Meta` 中的键
+pub const INDEX_KEY: &str = "accounts";
+/// 应用配置在 `Collection::Meta` 中的键
+pub const CONFIG_KEY: &str = "gui_config";
+
+/// 文档集合
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Collection {
+    /// 单例文档: 账号索引、应用配置
+    Meta,
+    /// 账号数据，键为账号 ID
+    Accounts,
+    /// 回收站，键为账号 ID
+    Trash,
+    /// 账号池快照，键为快照名称
+    Snapshots,
+}
+
+impl Collection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Collection::Meta => "meta",
+            Collection::Accounts => "accounts",
+            Collection::Trash => "trash",
+            Collection::Snapshots => "snapshots",
+        }
+    }
+}
+
+/// 存储后端: 按 (集合, 键) 读写 JSON 文档，序列化与业务逻辑由调用方负责
+pub trait StorageBackend: Send + Sync {
+    /// 后端名称 (日志 / 诊断用)
+    fn name(&self) -> &'static str;
+
+    /// 读取文档，不存在时返回 `None`
+    fn read(&self, collection: Collection, key: &str) -> Result<Option<String>, String>;
+
+    /// 写入 (覆盖) 文档
+    fn write(&self, collection: Collection, key: &str, content: &str) -> Result<(), String>;
+
+    /// 删除文档，返回文档此前是否存在
+    fn remove(&self, collection: Collection, key: &str) -> Result<bool, String>;
+
+    /// 列出集合中的所有键
+    fn keys(&self, collection: Collection) -> Result<Vec<String>, String>;
+
+    fn exists(&self, collection: Collection, key: &str) -> Result<bool, String> {
+        Ok(self.read(collection, key)?.is_some())
+    }
+}
+
+static BACKEND: Lazy<RwLock<Arc<dyn StorageBackend>>> =
+    Lazy::new(|| RwLock::new(Arc::new(FileStorage::default())));
+
+/// 替换全局存储后端 (需在首次读写账号或配置之前调用)
+pub fn set_backend(backend: Arc<dyn StorageBackend>) {
+    let mut current = BACKEND.write().unwrap_or_else(|e| e.into_inner());
+    *current = backend;
+}
+
+/// 当前的存储后端
+pub fn backend() -> Arc<dyn StorageBackend> {
+    BACKEND.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// JSON 文件存储 (默认): `accounts.json`、`gui_config.json`、`accounts/{id}.json`、`trash/{id}.json`、`snapshots/{name}.json`
+#[derive(Debug, Clone, Default)]
+pub struct FileStorage {
+    /// 数据目录；为空时使用当前配置档的数据目录，配置文件遵循 `--config` 覆盖
+    root: Option<PathBuf>,
+}
+
+impl FileStorage {
+    /// 固定在指定目录下存储
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self { root: Some(root.into()) }
+    }
+
+    fn root(&self) -> Result<PathBuf, String> {
+        match &self.root {
+            Some(root) => {
+                ensure_dir(root)?;
+                Ok(root.clone())
+            }
+            None => super::account::get_data_dir(),
+        }
+    }
+
+    fn dir(&self, collection: Collection) -> Result<PathBuf, String> {
+        let root = self.root()?;
+        let dir = match collection {
+            Collection::Meta => return Ok(root),
+            Collection::Accounts => root.join("accounts"),
+            Collection::Trash => root.join("trash"),
+            Collection::Snapshots => root.join("snapshots"),
+        };
+        ensure_dir(&dir)?;
+        Ok(dir)
+    }
+
+    fn path(&self, collection: Collection, key: &str) -> Result<PathBuf, String> {
+        if key.is_empty() || key.contains(['/', '\\']) || key.starts_with('.') {
+            return Err(format!("无效的存储键: {}", key));
+        }
+        if collection == Collection::Meta && key == CONFIG_KEY && self.root.is_none() {
+            return super::config::get_config_path();
+        }
+        Ok(self.dir(collection)?.join(format!("{}.json", key)))
+    }
+}
+
+fn ensure_dir(dir: &Path) -> Result<(), String> {
+    if !dir.exists() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建目录 {:?} 失败: {}", dir, e))?;
+    }
+    Ok(())
+}
+
+impl StorageBackend for FileStorage {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn read(&self, collection: Collection, key: &str) -> Result<Option<String>, String> {
+        let path = self.path(collection, key)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| format!("读取 {:?} 失败: {}", path, e))
+    }
+
+    fn write(&self, collection: Collection, key: &str, content: &str) -> Result<(), String> {
+        // 先写临时文件再原子重命名，避免中途退出留下半个文件
+        let path = self.path(collection, key)?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, content).map_err(|e| format!("写入 {:?} 失败: {}", temp_path, e))?;
+        fs::rename(&temp_path, &path).map_err(|e| format!("替换 {:?} 失败: {}", path, e))
+    }
+
+    fn remove(&self, collection: Collection, key: &str) -> Result<bool, String> {
+        let path = self.path(collection, key)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(&path)
+            .map(|_| true)
+            .map_err(|e| format!("删除 {:?} 失败: {}", path, e))
+    }
+
+    fn keys(&self, collection: Collection) -> Result<Vec<String>, String> {
+        let dir = self.dir(collection)?;
+        let entries = fs::read_dir(&dir).map_err(|e| format!("读取目录 {:?} 失败: {}", dir, e))?;
+        let mut keys: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    return None;
+                }
+                path.file_stem().and_then(|s| s.to_str()).map(str::to_string)
+            })
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// SQLite 存储: 所有文档保存在单表 `documents (collection, key, content)` 中
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let conn = Connection::open(path.as_ref()).map_err(|e| format!("打开存储数据库失败: {}", e))?;
+        Self::with_connection(conn)
+    }
+
+    /// 内存数据库 (进程退出即丢失)
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = Connection::open_in_memory().map_err(|e| format!("打开存储数据库失败: {}", e))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, String> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS documents (
+                collection TEXT NOT NULL,
+                key TEXT NOT NULL,
+                content TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (collection, key)
+            )",
+            [],
+        )
+        .map_err(|e| format!("初始化存储数据库失败: {}", e))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>, String> {
+        self.conn.lock().map_err(|e| format!("获取锁失败: {}", e))
+    }
+}
+
+impl StorageBackend for SqliteStorage {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn read(&self, collection: Collection, key: &str) -> Result<Option<String>, String> {
+        self.conn()?
+            .query_row(
+                "SELECT content FROM documents WHERE collection = ?1 AND key = ?2",
+                params![collection.as_str(), key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("读取存储失败: {}", e))
+    }
+
+    fn write(&self, collection: Collection, key: &str, content: &str) -> Result<(), String> {
+        self.conn()?
+            .execute(
+                "INSERT INTO documents (collection, key, content, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(collection, key) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at",
+                params![collection.as_str(), key, content, chrono::Utc::now().timestamp()],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("写入存储失败: {}", e))
+    }
+
+    fn remove(&self, collection: Collection, key: &str) -> Result<bool, String> {
+        self.conn()?
+            .execute(
+                "DELETE FROM documents WHERE collection = ?1 AND key = ?2",
+                params![collection.as_str(), key],
+            )
+            .map(|n| n > 0)
+            .map_err(|e| format!("删除存储失败: {}", e))
+    }
+
+    fn keys(&self, collection: Collection) -> Result<Vec<String>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare("SELECT key FROM documents WHERE collection = ?1 ORDER BY key")
+            .map_err(|e| format!("读取存储失败: {}", e))?;
+        let keys = stmt
+            .query_map(params![collection.as_str()], |row| row.get(0))
+            .map_err(|e| format!("读取存储失败: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("读取存储失败: {}", e))?;
+        Ok(keys)
+    }
+}
+
+/// 内存存储 (测试 / 临时实例)
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    documents: Mutex<BTreeMap<(Collection, String), String>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn documents(&self) -> Result<std::sync::MutexGuard<'_, BTreeMap<(Collection, String), String>>, String> {
+        self.documents.lock().map_err(|e| format!("获取锁失败: {}", e))
+    }
+}
+
+impl StorageBackend for MemoryStorage {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn read(&self, collection: Collection, key: &str) -> Result<Option<String>, String> {
+        Ok(self.documents()?.get(&(collection, key.to_string())).cloned())
+    }
+
+    fn write(&self, collection: Collection, key: &str, content: &str) -> Result<(), String> {
+        self.documents()?
+            .insert((collection, key.to_string()), content.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, collection: Collection, key: &str) -> Result<bool, String> {
+        Ok(self.documents()?.remove(&(collection, key.to_string())).is_some())
+    }
+
+    fn keys(&self, collection: Collection) -> Result<Vec<String>, String> {
+        Ok(self
+            .documents()?
+            .keys()
+            .filter(|(c, _)| *c == collection)
+            .map(|(_, key)| key.clone())
+            .collect())
+    }
+}
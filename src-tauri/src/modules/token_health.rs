@@ -0,0 +1,104 @@
+// Token 健康报告: 汇总各账号 access_token 的到期时间、最近一次成功刷新、连续刷新失败次数与 refresh_token
+// 的估计年龄，标记可能即将失效的账号，便于提前准备替换
+use serde::Serialize;
+
+use crate::models::Account;
+
+/// Google 会回收连续 6 个月未使用的 refresh_token
+const IDLE_REVOKE_SECS: i64 = 180 * 86400;
+/// 距闲置回收不足该时长时提醒
+const IDLE_WARN_SECS: i64 = 30 * 86400;
+/// 连续刷新失败达到该次数视为即将失效
+const FAILURE_STREAK_CRITICAL: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthIssue {
+    /// 已因 invalid_grant 被禁用: refresh_token 已被撤销或过期
+    Revoked,
+    /// 最近的刷新连续失败
+    RefreshFailing,
+    /// 长期没有成功刷新，接近 (或已超过) 闲置回收期限
+    Idle,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenHealth {
+    pub id: String,
+    pub email: String,
+    pub disabled: bool,
+    /// access_token 到期时间 (Unix 秒)
+    pub access_expires_at: i64,
+    pub last_refresh_at: Option<i64>,
+    pub last_failure_at: Option<i64>,
+    pub failure_streak: u32,
+    /// refresh_token 的获取时间；未记录时 (旧账号) 以账号创建时间估算
+    pub token_obtained_at: i64,
+    pub token_age_estimated: bool,
+    /// 按最近一次成功刷新 (或获取时间) 推算的闲置回收时间
+    pub idle_revoke_at: i64,
+    pub level: HealthLevel,
+    pub issues: Vec<HealthIssue>,
+}
+
+pub fn assess(account: &Account, now: i64) -> TokenHealth {
+    let refresh = &account.refresh;
+    let token_obtained_at = refresh.token_obtained_at.unwrap_or(account.created_at);
+    let last_active = refresh.last_success_at.unwrap_or(token_obtained_at).max(token_obtained_at);
+    let idle_revoke_at = last_active + IDLE_REVOKE_SECS;
+
+    let mut issues = Vec::new();
+    let mut level = HealthLevel::Ok;
+    let mut flag = |issue: HealthIssue, severity: HealthLevel| {
+        issues.push(issue);
+        level = level.max(severity);
+    };
+    if account.disabled
+        && account
+            .disabled_reason
+            .as_deref()
+            .is_some_and(|r| r.contains("invalid_grant"))
+    {
+        flag(HealthIssue::Revoked, HealthLevel::Critical);
+    }
+    if refresh.failure_streak >= FAILURE_STREAK_CRITICAL {
+        flag(HealthIssue::RefreshFailing, HealthLevel::Critical);
+    } else if refresh.failure_streak > 0 {
+        flag(HealthIssue::RefreshFailing, HealthLevel::Warning);
+    }
+    if idle_revoke_at <= now {
+        flag(HealthIssue::Idle, HealthLevel::Critical);
+    } else if idle_revoke_at - now <= IDLE_WARN_SECS {
+        flag(HealthIssue::Idle, HealthLevel::Warning);
+    }
+
+    TokenHealth {
+        id: account.id.clone(),
+        email: account.email.clone(),
+        disabled: account.disabled,
+        access_expires_at: account.token.expiry_timestamp,
+        last_refresh_at: refresh.last_success_at,
+        last_failure_at: refresh.last_failure_at,
+        failure_streak: refresh.failure_streak,
+        token_obtained_at,
+        token_age_estimated: refresh.token_obtained_at.is_none(),
+        idle_revoke_at,
+        level,
+        issues,
+    }
+}
+
+/// 所有账号的健康状况: 问题最严重的在前，同级按闲置回收时间先后排列
+pub fn report(accounts: &[Account], now: i64) -> Vec<TokenHealth> {
+    let mut report: Vec<TokenHealth> = accounts.iter().map(|a| assess(a, now)).collect();
+    report.sort_by(|a, b| b.level.cmp(&a.level).then(a.idle_revoke_at.cmp(&b.idle_revoke_at)));
+    report
+}
@@ -0,0 +1,91 @@
+// Forbidden 账号自动恢复: 定期重新查询被标记为 403 的账号，上游恢复后自动回到轮换池
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::models::Account;
+use crate::modules;
+use crate::proxy::monitor::ProxyMonitor;
+use crate::proxy::token_manager::TokenManager;
+
+/// 最短检查间隔，避免配置过小时频繁请求配额接口
+const MIN_INTERVAL_SECS: u64 = 300;
+
+/// 账号恢复通知 (前端事件 `proxy://account-recovered`)
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveredAccount {
+    pub id: String,
+    pub email: String,
+}
+
+/// 重新查询所有 Forbidden 账号的配额，返回已恢复的账号
+pub async fn probe_forbidden_accounts() -> Result<Vec<RecoveredAccount>, String> {
+    let forbidden: Vec<Account> = modules::list_accounts()?
+        .into_iter()
+        .filter(|a| !a.disabled && a.quota.as_ref().map_or(false, |q| q.is_forbidden))
+        .collect();
+
+    let mut recovered = Vec::new();
+    for mut account in forbidden {
+        match modules::account::fetch_quota_with_retry(&mut account).await {
+            Ok(quota) if !quota.is_forbidden => {
+                modules::update_account_quota(&account.id, quota)?;
+                tracing::info!("账号已从 Forbidden 状态恢复: {}", account.email);
+                recovered.push(RecoveredAccount {
+                    id: account.id,
+                    email: account.email,
+                });
+            }
+            Ok(_) => tracing::debug!("账号仍为 Forbidden: {}", account.email),
+            Err(e) => tracing::debug!("Forbidden 账号检查失败 ({}): {}", account.email, e),
+        }
+    }
+    Ok(recovered)
+}
+
+/// 启动后台恢复探测任务；任务只持有 TokenManager 的弱引用，服务停止后自动退出
+pub fn start(
+    token_manager: &Arc<TokenManager>,
+    monitor: Arc<ProxyMonitor>,
+    interval_secs: u64,
+) -> tokio::task::JoinHandle<()> {
+    let weak = Arc::downgrade(token_manager);
+    let period = std::time::Duration::from_secs(interval_secs.max(MIN_INTERVAL_SECS));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if weak.strong_count() == 0 {
+                break;
+            }
+            let recovered = match probe_forbidden_accounts().await {
+                Ok(recovered) => recovered,
+                Err(e) => {
+                    tracing::warn!("Forbidden 账号恢复检查失败: {}", e);
+                    continue;
+                }
+            };
+            if recovered.is_empty() {
+                continue;
+            }
+            let Some(manager) = weak.upgrade() else {
+                break;
+            };
+            match manager.load_accounts().await {
+                Ok(count) => tracing::info!(
+                    "{} 个账号已恢复并重新加入轮换池 (当前可用 {} 个)",
+                    recovered.len(),
+                    count
+                ),
+                Err(e) => tracing::warn!("恢复后重新加载账号失败: {}", e),
+            }
+            for account in &recovered {
+                monitor.notify("proxy://account-recovered", account);
+                crate::proxy::events::publish(crate::proxy::events::ProxyEvent::AccountRecovered {
+                    account: account.email.clone(),
+                });
+            }
+        }
+        tracing::debug!("Forbidden 账号恢复任务已退出");
+    })
+}
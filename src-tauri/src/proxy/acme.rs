@@ -0,0 +1,528 @@
+// ACME 自动证书：为 TLS 监听器申请并续期证书 (HTTP-01 / TLS-ALPN-01)，证书保存在数据目录 acme/<域名>/
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use dashmap::DashMap;
+use instant_acme::{
+    Account, AccountCredentials, AuthorizationStatus, ChallengeType, Identifier, NewAccount,
+    NewOrder, Order, OrderStatus,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::{crypto::ring, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::proxy::config::{AcmeChallenge, AcmeConfig};
+
+/// TLS-ALPN-01 验证使用的 ALPN 协议名
+const ACME_TLS_ALPN: &[u8] = b"acme-tls/1";
+/// 无法从证书读取有效期时假定的有效期 (Let's Encrypt 默认 90 天)
+const FALLBACK_LIFETIME_SECS: i64 = 90 * 24 * 3600;
+/// 剩余有效期不足总有效期的 1/3 时续期 (90 天证书提前 30 天，6 天短期证书提前 2 天)
+const RENEW_REMAINING_DIVISOR: i64 = 3;
+/// 证书检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 3600);
+/// 申请失败后的重试间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(3600);
+/// 订单状态轮询次数上限
+const POLL_ATTEMPTS: usize = 30;
+const MAX_POLL_DELAY: Duration = Duration::from_secs(10);
+
+/// 证书元数据 (meta.json)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CertMeta {
+    /// 签发时间 (Unix 秒)
+    issued_at: i64,
+    domains: Vec<String>,
+}
+
+/// 证书解析器：常规握手返回当前证书，`acme-tls/1` 握手返回对应域名的验证证书
+#[derive(Default)]
+pub struct AcmeResolver {
+    current: RwLock<Option<Arc<CertifiedKey>>>,
+    challenges: DashMap<String, Arc<CertifiedKey>>,
+}
+
+impl std::fmt::Debug for AcmeResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcmeResolver")
+            .field("has_certificate", &self.has_certificate())
+            .field("pending_challenges", &self.challenges.len())
+            .finish()
+    }
+}
+
+impl AcmeResolver {
+    fn has_certificate(&self) -> bool {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    fn install(&self, key: Arc<CertifiedKey>) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Some(key);
+    }
+}
+
+impl ResolvesServerCert for AcmeResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let is_challenge = client_hello
+            .alpn()
+            .is_some_and(|mut protocols| protocols.any(|p| p == ACME_TLS_ALPN));
+        if is_challenge {
+            let domain = client_hello.server_name()?;
+            return self.challenges.get(domain).map(|c| c.clone());
+        }
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// 构建使用 ACME 证书的 TLS 接收器，并启动后台任务申请 / 续期证书
+/// (首次申请完成前普通握手会失败)
+pub fn start(config: &AcmeConfig) -> Result<(TlsAcceptor, JoinHandle<()>), String> {
+    if config.domains.is_empty() {
+        return Err("ACME 配置缺少域名".to_string());
+    }
+    let resolver = Arc::new(AcmeResolver::default());
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS 配置失败: {}", e))?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver.clone());
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec(), ACME_TLS_ALPN.to_vec()];
+
+    let task = tokio::spawn(renew_loop(config.clone(), resolver));
+    Ok((TlsAcceptor::from(Arc::new(server_config)), task))
+}
+
+async fn renew_loop(config: AcmeConfig, resolver: Arc<AcmeResolver>) {
+    loop {
+        let wait = match ensure_certificate(&config, &resolver).await {
+            Ok(()) => CHECK_INTERVAL,
+            Err(e) => {
+                tracing::error!("ACME 证书申请失败 ({}): {}", config.domains.join(", "), e);
+                RETRY_INTERVAL
+            }
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// 加载缓存证书；缺失、域名变化或临近到期时重新申请
+async fn ensure_certificate(config: &AcmeConfig, resolver: &AcmeResolver) -> Result<(), String> {
+    let dir = cert_dir(&config.domains[0])?;
+    if let Some((key, meta)) = load_cached(&dir)? {
+        let validity = key
+            .cert
+            .first()
+            .and_then(|cert| crate::proxy::doctor::certificate_validity(cert.as_ref()));
+        if !resolver.has_certificate() {
+            resolver.install(key);
+            tracing::info!("已加载 ACME 证书: {}", dir.display());
+        }
+        if !needs_renewal(&meta, validity, &config.domains, chrono::Utc::now().timestamp()) {
+            return Ok(());
+        }
+        tracing::info!("ACME 证书即将到期或域名已变更，开始续期: {}", config.domains.join(", "));
+    } else {
+        tracing::info!("开始申请 ACME 证书: {}", config.domains.join(", "));
+    }
+
+    let (cert_pem, key_pem) = issue(config, resolver).await?;
+    let key = certified_key(cert_pem.as_bytes(), key_pem.as_bytes())?;
+    save(&dir, &cert_pem, &key_pem, &config.domains)?;
+    resolver.install(key);
+    tracing::info!("ACME 证书已签发: {}", config.domains.join(", "));
+    Ok(())
+}
+
+/// `validity` 为证书中的 (notBefore, notAfter)；无法解析时按签发时间与默认有效期估算
+fn needs_renewal(meta: &CertMeta, validity: Option<(i64, i64)>, domains: &[String], now: i64) -> bool {
+    let mut cached = meta.domains.clone();
+    let mut wanted = domains.to_vec();
+    cached.sort();
+    wanted.sort();
+    let (not_before, not_after) =
+        validity.unwrap_or((meta.issued_at, meta.issued_at + FALLBACK_LIFETIME_SECS));
+    let renew_before = (not_after - not_before).max(0) / RENEW_REMAINING_DIVISOR;
+    cached != wanted || now >= not_after - renew_before
+}
+
+fn acme_root() -> Result<PathBuf, String> {
+    Ok(crate::modules::account::get_data_dir()?.join("acme"))
+}
+
+/// 证书目录名 (通配符域名 `*.example.com` 保存为 `_.example.com`)
+fn dir_name(domain: &str) -> String {
+    domain
+        .trim()
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+fn cert_dir(domain: &str) -> Result<PathBuf, String> {
+    Ok(acme_root()?.join(dir_name(domain)))
+}
+
+fn load_cached(dir: &std::path::Path) -> Result<Option<(Arc<CertifiedKey>, CertMeta)>, String> {
+    let (Ok(cert), Ok(key), Ok(meta)) = (
+        std::fs::read(dir.join("cert.pem")),
+        std::fs::read(dir.join("key.pem")),
+        std::fs::read_to_string(dir.join("meta.json")),
+    ) else {
+        return Ok(None);
+    };
+    let meta: CertMeta = match serde_json::from_str(&meta) {
+        Ok(meta) => meta,
+        Err(e) => {
+            tracing::warn!("ACME 证书元数据损坏，将重新申请: {}", e);
+            return Ok(None);
+        }
+    };
+    Ok(Some((certified_key(&cert, &key)?, meta)))
+}
+
+fn save(dir: &std::path::Path, cert_pem: &str, key_pem: &str, domains: &[String]) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建证书目录失败: {}", e))?;
+    let meta = CertMeta {
+        issued_at: chrono::Utc::now().timestamp(),
+        domains: domains.to_vec(),
+    };
+    let meta = serde_json::to_string_pretty(&meta).map_err(|e| e.to_string())?;
+    write_private_key(&dir.join("key.pem"), key_pem)?;
+    for (name, content) in [("cert.pem", cert_pem), ("meta.json", meta.as_str())] {
+        std::fs::write(dir.join(name), content)
+            .map_err(|e| format!("保存证书文件 {} 失败: {}", name, e))?;
+    }
+    Ok(())
+}
+
+/// 写入私钥: 新建时即为 0600，已存在的文件先收紧权限再写入内容
+fn write_private_key(path: &std::path::Path, key_pem: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    let failed = |e: std::io::Error| format!("保存证书文件 key.pem 失败: {}", e);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path).map_err(failed)?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600)).map_err(failed)?;
+        (&file).write_all(key_pem.as_bytes()).map_err(failed)
+    }
+    #[cfg(not(unix))]
+    {
+        let mut file = options.open(path).map_err(failed)?;
+        file.write_all(key_pem.as_bytes()).map_err(failed)
+    }
+}
+
+fn certified_key(cert_pem: &[u8], key_pem: &[u8]) -> Result<Arc<CertifiedKey>, String> {
+    let certs = CertificateDer::pem_slice_iter(cert_pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("解析 ACME 证书失败: {}", e))?;
+    if certs.is_empty() {
+        return Err("ACME 证书为空".to_string());
+    }
+    let key = PrivateKeyDer::from_pem_slice(key_pem).map_err(|e| format!("解析 ACME 私钥失败: {}", e))?;
+    let signing_key = ring::sign::any_supported_type(&key).map_err(|e| format!("不支持的私钥类型: {}", e))?;
+    Ok(Arc::new(CertifiedKey::new(certs, signing_key)))
+}
+
+/// 读取或注册 ACME 账户 (按目录地址分别保存凭据)
+async fn load_account(config: &AcmeConfig) -> Result<Account, String> {
+    use sha2::{Digest, Sha256};
+
+    let digest = Sha256::digest(config.directory_url.as_bytes());
+    let suffix: String = digest[..6].iter().map(|b| format!("{:02x}", b)).collect();
+    let path = acme_root()?.join(format!("account_{}.json", suffix));
+
+    if let Ok(raw) = std::fs::read_to_string(&path) {
+        match serde_json::from_str::<AccountCredentials>(&raw) {
+            Ok(credentials) => {
+                return Account::from_credentials(credentials)
+                    .await
+                    .map_err(|e| format!("加载 ACME 账户失败: {}", e));
+            }
+            Err(e) => tracing::warn!("ACME 账户凭据损坏，重新注册: {}", e),
+        }
+    }
+
+    let contact: Vec<String> = config
+        .email
+        .iter()
+        .filter(|e| !e.trim().is_empty())
+        .map(|e| format!("mailto:{}", e.trim()))
+        .collect();
+    let contact: Vec<&str> = contact.iter().map(String::as_str).collect();
+    let (account, credentials) = Account::create(
+        &NewAccount {
+            contact: &contact,
+            terms_of_service_agreed: true,
+            only_return_existing: false,
+        },
+        &config.directory_url,
+        None,
+    )
+    .await
+    .map_err(|e| format!("注册 ACME 账户失败: {}", e))?;
+
+    std::fs::create_dir_all(acme_root()?).map_err(|e| format!("创建 ACME 目录失败: {}", e))?;
+    let raw = serde_json::to_string_pretty(&credentials).map_err(|e| e.to_string())?;
+    std::fs::write(&path, raw).map_err(|e| format!("保存 ACME 账户凭据失败: {}", e))?;
+    tracing::info!("已注册 ACME 账户: {}", config.directory_url);
+    Ok(account)
+}
+
+/// 完成一次证书申请，返回 (证书链 PEM, 私钥 PEM)
+async fn issue(config: &AcmeConfig, resolver: &AcmeResolver) -> Result<(String, String), String> {
+    let account = load_account(config).await?;
+    let identifiers: Vec<Identifier> = config
+        .domains
+        .iter()
+        .map(|d| Identifier::Dns(d.trim().to_string()))
+        .collect();
+    let mut order = account
+        .new_order(&NewOrder {
+            identifiers: &identifiers,
+        })
+        .await
+        .map_err(|e| format!("创建 ACME 订单失败: {}", e))?;
+
+    let challenge_type = match config.challenge {
+        AcmeChallenge::Http01 => ChallengeType::Http01,
+        AcmeChallenge::TlsAlpn01 => ChallengeType::TlsAlpn01,
+    };
+    let authorizations = order
+        .authorizations()
+        .await
+        .map_err(|e| format!("获取 ACME 授权失败: {}", e))?;
+
+    let http_tokens = Arc::new(DashMap::new());
+    let mut ready = Vec::new();
+    for authz in &authorizations {
+        match authz.status {
+            AuthorizationStatus::Valid => continue,
+            AuthorizationStatus::Pending => {}
+            status => return Err(format!("域名授权状态异常: {:?}", status)),
+        }
+        let domain = match &authz.identifier {
+            Identifier::Dns(domain) => domain.clone(),
+        };
+        let challenge = authz
+            .challenges
+            .iter()
+            .find(|c| c.r#type == challenge_type)
+            .ok_or_else(|| format!("ACME 服务器未提供 {:?} 验证方式: {}", challenge_type, domain))?;
+        let key_authorization = order.key_authorization(challenge);
+        match config.challenge {
+            AcmeChallenge::Http01 => {
+                http_tokens.insert(challenge.token.clone(), key_authorization.as_str().to_string());
+            }
+            AcmeChallenge::TlsAlpn01 => {
+                let cert = challenge_cert(&domain, key_authorization.digest().as_ref())?;
+                resolver.challenges.insert(domain, cert);
+            }
+        }
+        ready.push(challenge.url.clone());
+    }
+
+    let http_server = match config.challenge {
+        AcmeChallenge::Http01 if !ready.is_empty() => {
+            Some(serve_http_challenges(&config.http_bind, http_tokens).await?)
+        }
+        _ => None,
+    };
+    let validated = async {
+        for url in &ready {
+            order
+                .set_challenge_ready(url)
+                .await
+                .map_err(|e| format!("提交 ACME 验证失败: {}", e))?;
+        }
+        wait_for_status(&mut order, OrderStatus::Ready).await
+    }
+    .await;
+    if let Some(server) = http_server {
+        server.abort();
+    }
+    resolver.challenges.clear();
+    validated?;
+
+    let key_pair = rcgen::KeyPair::generate().map_err(|e| format!("生成证书私钥失败: {}", e))?;
+    let mut params = rcgen::CertificateParams::new(config.domains.clone())
+        .map_err(|e| format!("无效的证书域名: {}", e))?;
+    params.distinguished_name = rcgen::DistinguishedName::new();
+    let csr = params
+        .serialize_request(&key_pair)
+        .map_err(|e| format!("生成 CSR 失败: {}", e))?;
+    order
+        .finalize(csr.der())
+        .await
+        .map_err(|e| format!("提交 CSR 失败: {}", e))?;
+
+    let mut delay = Duration::from_millis(500);
+    for _ in 0..POLL_ATTEMPTS {
+        if let Some(cert_pem) = order
+            .certificate()
+            .await
+            .map_err(|e| format!("下载 ACME 证书失败: {}", e))?
+        {
+            return Ok((cert_pem, key_pair.serialize_pem()));
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_POLL_DELAY);
+    }
+    Err("等待 ACME 证书签发超时".to_string())
+}
+
+async fn wait_for_status(order: &mut Order, wanted: OrderStatus) -> Result<(), String> {
+    let mut delay = Duration::from_millis(500);
+    for _ in 0..POLL_ATTEMPTS {
+        let state = order
+            .refresh()
+            .await
+            .map_err(|e| format!("查询 ACME 订单失败: {}", e))?;
+        if state.status == wanted {
+            return Ok(());
+        }
+        if state.status == OrderStatus::Invalid {
+            let detail = state
+                .error
+                .as_ref()
+                .and_then(|e| e.detail.clone())
+                .unwrap_or_default();
+            return Err(format!("ACME 域名验证失败 {}", detail).trim_end().to_string());
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_POLL_DELAY);
+    }
+    Err("等待 ACME 域名验证超时".to_string())
+}
+
+/// TLS-ALPN-01 验证证书：包含 acmeIdentifier 扩展的自签名证书
+fn challenge_cert(domain: &str, digest: &[u8]) -> Result<Arc<CertifiedKey>, String> {
+    let mut params = rcgen::CertificateParams::new(vec![domain.to_string()])
+        .map_err(|e| format!("无效的证书域名: {}", e))?;
+    params.custom_extensions = vec![rcgen::CustomExtension::new_acme_identifier(digest)];
+    let key_pair = rcgen::KeyPair::generate().map_err(|e| format!("生成验证证书失败: {}", e))?;
+    let cert = params
+        .self_signed(&key_pair)
+        .map_err(|e| format!("生成验证证书失败: {}", e))?;
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+    let signing_key = ring::sign::any_supported_type(&key).map_err(|e| format!("生成验证证书失败: {}", e))?;
+    Ok(Arc::new(CertifiedKey::new(vec![cert.der().clone()], signing_key)))
+}
+
+/// HTTP-01 验证期间临时监听 `bind`，仅响应 /.well-known/acme-challenge/<token>
+async fn serve_http_challenges(
+    bind: &str,
+    tokens: Arc<DashMap<String, String>>,
+) -> Result<JoinHandle<()>, String> {
+    async fn challenge(
+        State(tokens): State<Arc<DashMap<String, String>>>,
+        Path(token): Path<String>,
+    ) -> impl IntoResponse {
+        match tokens.get(&token) {
+            Some(value) => (StatusCode::OK, value.clone()).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    let app = Router::new()
+        .route("/.well-known/acme-challenge/:token", get(challenge))
+        .with_state(tokens);
+    let listener = tokio::net::TcpListener::bind(bind)
+        .await
+        .map_err(|e| format!("ACME HTTP-01 验证地址 {} 绑定失败: {}", bind, e))?;
+    Ok(tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            tracing::warn!("ACME HTTP-01 验证服务异常退出: {}", e);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(issued_at: i64, domains: &[&str]) -> CertMeta {
+        CertMeta {
+            issued_at,
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    const DAY: i64 = 24 * 3600;
+
+    #[test]
+    fn renews_after_two_thirds_of_the_certificate_lifetime() {
+        let domains = vec!["proxy.example.com".to_string()];
+        let cached = meta(0, &["proxy.example.com"]);
+        // 90 天证书: 到期前 30 天
+        assert!(!needs_renewal(&cached, Some((0, 90 * DAY)), &domains, 59 * DAY));
+        assert!(needs_renewal(&cached, Some((0, 90 * DAY)), &domains, 60 * DAY));
+        // 6 天短期证书: 到期前 2 天，而不是按 90 天估算
+        assert!(!needs_renewal(&cached, Some((0, 6 * DAY)), &domains, 3 * DAY));
+        assert!(needs_renewal(&cached, Some((0, 6 * DAY)), &domains, 4 * DAY));
+        // 无法读取有效期时按签发时间与默认有效期估算
+        assert!(!needs_renewal(&cached, None, &domains, 59 * DAY));
+        assert!(needs_renewal(&cached, None, &domains, 60 * DAY));
+    }
+
+    #[test]
+    fn reads_validity_from_issued_certificate() {
+        let key_pair = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(vec!["proxy.example.com".to_string()]).unwrap();
+        params.not_before = rcgen::date_time_ymd(2030, 1, 1);
+        params.not_after = rcgen::date_time_ymd(2030, 1, 7);
+        let cert = params.self_signed(&key_pair).unwrap();
+        let key = certified_key(cert.pem().as_bytes(), key_pair.serialize_pem().as_bytes()).unwrap();
+        let validity = crate::proxy::doctor::certificate_validity(key.cert[0].as_ref()).unwrap();
+        assert_eq!(validity, (1893456000, 1893456000 + 6 * DAY));
+    }
+
+    #[test]
+    fn renews_when_domains_change() {
+        let domains = vec!["b.example.com".to_string(), "a.example.com".to_string()];
+        let validity = Some((0, 90 * DAY));
+        assert!(!needs_renewal(&meta(0, &["a.example.com", "b.example.com"]), validity, &domains, 0));
+        assert!(needs_renewal(&meta(0, &["a.example.com"]), validity, &domains, 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_key_is_written_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("antigravity-acme-test-{}", uuid::Uuid::new_v4()));
+        save(&dir, "CERT", "KEY", &["proxy.example.com".to_string()]).unwrap();
+        let mode = std::fs::metadata(dir.join("key.pem")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(dir.join("key.pem")).unwrap(), "KEY");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dir_name_is_filesystem_safe() {
+        assert_eq!(dir_name("Proxy.Example.com"), "proxy.example.com");
+        assert_eq!(dir_name("*.example.com"), "_.example.com");
+        assert_eq!(dir_name("../etc"), ".._etc");
+    }
+}
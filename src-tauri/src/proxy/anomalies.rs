@@ -0,0 +1,384 @@
+// 请求日志异常分析 (`--logs-summary`): 错误突增、失败率异常的账号、按模型的延迟退化与发送格式错误请求的客户端
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::proxy::config::LogAnomalyConfig;
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 视为请求格式错误的状态码
+const MALFORMED_STATUSES: &[u16] = &[400, 413, 415, 422];
+
+fn is_error(status: u16) -> bool {
+    !(200..400).contains(&status)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorSpike {
+    /// 时间片起点 (Unix 毫秒)
+    pub start: i64,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountFailures {
+    pub account: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub failure_rate: f64,
+    /// 最常见的失败状态码
+    pub top_status: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyRegression {
+    pub model: String,
+    /// 前半段成功请求的延迟中位数 (毫秒)
+    pub baseline_ms: u64,
+    /// 后半段成功请求的延迟中位数 (毫秒)
+    pub recent_ms: u64,
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MalformedClient {
+    /// API Key 指纹，未记录时为客户端 IP
+    pub client: String,
+    pub count: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AnomalyReport {
+    pub requests: u64,
+    pub errors: u64,
+    pub error_spikes: Vec<ErrorSpike>,
+    pub failing_accounts: Vec<AccountFailures>,
+    pub latency_regressions: Vec<LatencyRegression>,
+    pub malformed_clients: Vec<MalformedClient>,
+}
+
+impl AnomalyReport {
+    pub fn is_clean(&self) -> bool {
+        self.error_spikes.is_empty()
+            && self.failing_accounts.is_empty()
+            && self.latency_regressions.is_empty()
+            && self.malformed_clients.is_empty()
+    }
+}
+
+fn median(values: &mut [u64]) -> u64 {
+    values.sort_unstable();
+    values[values.len() / 2]
+}
+
+/// 分析按时间顺序排列的请求日志
+pub fn analyze(logs: &[ProxyRequestLog], config: &LogAnomalyConfig) -> AnomalyReport {
+    let mut report = AnomalyReport {
+        requests: logs.len() as u64,
+        errors: logs.iter().filter(|l| is_error(l.status)).count() as u64,
+        ..Default::default()
+    };
+    if logs.is_empty() {
+        return report;
+    }
+
+    // 错误突增: 时间片错误率明显高于整体错误率
+    let overall_rate = report.errors as f64 / report.requests as f64;
+    let bucket_ms = (config.bucket_secs.max(1) * 1000) as i64;
+    let mut buckets: BTreeMap<i64, (u64, u64)> = BTreeMap::new();
+    for log in logs {
+        let entry = buckets.entry(log.timestamp.div_euclid(bucket_ms) * bucket_ms).or_default();
+        entry.0 += 1;
+        if is_error(log.status) {
+            entry.1 += 1;
+        }
+    }
+    for (start, (requests, errors)) in buckets {
+        let rate = errors as f64 / requests as f64;
+        if errors >= config.error_spike_min_errors && rate >= overall_rate * config.error_spike_factor {
+            report.error_spikes.push(ErrorSpike {
+                start,
+                requests,
+                errors,
+                error_rate: rate,
+            });
+        }
+    }
+
+    // 失败率异常的账号
+    let mut accounts: HashMap<&str, (u64, u64, HashMap<u16, u64>)> = HashMap::new();
+    for log in logs {
+        let Some(account) = log.account.as_deref() else {
+            continue;
+        };
+        let entry = accounts.entry(account).or_default();
+        entry.0 += 1;
+        if is_error(log.status) {
+            entry.1 += 1;
+            *entry.2.entry(log.status).or_default() += 1;
+        }
+    }
+    for (account, (requests, failures, statuses)) in accounts {
+        let rate = failures as f64 / requests as f64;
+        if requests >= config.account_min_requests && failures > 0 && rate >= config.account_failure_rate {
+            let top_status = statuses
+                .into_iter()
+                .max_by_key(|(status, count)| (*count, std::cmp::Reverse(*status)))
+                .map(|(status, _)| status)
+                .unwrap_or(0);
+            report.failing_accounts.push(AccountFailures {
+                account: account.to_string(),
+                requests,
+                failures,
+                failure_rate: rate,
+                top_status,
+            });
+        }
+    }
+    report
+        .failing_accounts
+        .sort_by(|a, b| b.failure_rate.total_cmp(&a.failure_rate).then_with(|| a.account.cmp(&b.account)));
+
+    // 延迟退化: 以时间窗口中点划分前后两段，对比成功请求的延迟中位数
+    let midpoint = logs[0].timestamp + (logs[logs.len() - 1].timestamp - logs[0].timestamp) / 2;
+    let mut latencies: BTreeMap<&str, (Vec<u64>, Vec<u64>)> = BTreeMap::new();
+    for log in logs.iter().filter(|l| (200..300).contains(&l.status)) {
+        let Some(model) = log.model.as_deref() else {
+            continue;
+        };
+        let entry = latencies.entry(model).or_default();
+        if log.timestamp < midpoint {
+            entry.0.push(log.duration);
+        } else {
+            entry.1.push(log.duration);
+        }
+    }
+    for (model, (mut baseline, mut recent)) in latencies {
+        if baseline.len() < config.latency_min_samples || recent.len() < config.latency_min_samples {
+            continue;
+        }
+        let samples = baseline.len() + recent.len();
+        let baseline_ms = median(&mut baseline);
+        let recent_ms = median(&mut recent);
+        if recent_ms as f64 >= baseline_ms.max(1) as f64 * config.latency_regression_factor {
+            report.latency_regressions.push(LatencyRegression {
+                model: model.to_string(),
+                baseline_ms,
+                recent_ms,
+                samples,
+            });
+        }
+    }
+
+    // 发送格式错误请求的客户端
+    let mut clients: HashMap<String, (u64, Option<String>)> = HashMap::new();
+    for log in logs.iter().filter(|l| MALFORMED_STATUSES.contains(&l.status)) {
+        let Some(client) = log.key_id.clone().or_else(|| log.client_ip.clone()) else {
+            continue;
+        };
+        let entry = clients.entry(client).or_default();
+        entry.0 += 1;
+        if log.error.is_some() {
+            entry.1 = log.error.clone();
+        }
+    }
+    report.malformed_clients = clients
+        .into_iter()
+        .filter(|(_, (count, _))| *count >= config.malformed_min_requests)
+        .map(|(client, (count, last_error))| MalformedClient {
+            client,
+            count,
+            last_error,
+        })
+        .collect();
+    report
+        .malformed_clients
+        .sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.client.cmp(&b.client)));
+
+    report
+}
+
+pub fn format_report(report: &AnomalyReport) -> String {
+    let time = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let mut out = format!("{} requests, {} errors\n", report.requests, report.errors);
+    if report.is_clean() {
+        out.push_str("no anomalies found\n");
+        return out;
+    }
+    if !report.error_spikes.is_empty() {
+        out.push_str("error spikes:\n");
+        for spike in &report.error_spikes {
+            out.push_str(&format!(
+                "    {}  {:>5}/{:<5} errors  ({:.0}%)\n",
+                time(spike.start),
+                spike.errors,
+                spike.requests,
+                spike.error_rate * 100.0
+            ));
+        }
+    }
+    if !report.failing_accounts.is_empty() {
+        out.push_str("accounts with high failure rates:\n");
+        for account in &report.failing_accounts {
+            out.push_str(&format!(
+                "    {:<40} {:>5}/{:<5} failed  ({:.0}%, mostly {})\n",
+                account.account,
+                account.failures,
+                account.requests,
+                account.failure_rate * 100.0,
+                account.top_status
+            ));
+        }
+    }
+    if !report.latency_regressions.is_empty() {
+        out.push_str("latency regressions (median):\n");
+        for regression in &report.latency_regressions {
+            out.push_str(&format!(
+                "    {:<40} {:>7}ms -> {:>7}ms  ({} samples)\n",
+                regression.model, regression.baseline_ms, regression.recent_ms, regression.samples
+            ));
+        }
+    }
+    if !report.malformed_clients.is_empty() {
+        out.push_str("clients sending malformed requests:\n");
+        for client in &report.malformed_clients {
+            let error = client
+                .last_error
+                .as_deref()
+                .map(|e| format!("  last: {}", e.chars().take(80).collect::<String>()))
+                .unwrap_or_default();
+            out.push_str(&format!("    {:<40} {:>5}{}\n", client.client, client.count, error));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(timestamp: i64, status: u16) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: timestamp.to_string(),
+            timestamp,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status,
+            duration: 100,
+            model: Some("claude-sonnet-4-5".to_string()),
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            session_id: None,
+            account: None,
+            key_id: None,
+            client_ip: None,
+            canary_target: None,
+        }
+    }
+
+    fn config() -> LogAnomalyConfig {
+        LogAnomalyConfig {
+            bucket_secs: 60,
+            error_spike_min_errors: 3,
+            account_min_requests: 5,
+            latency_min_samples: 3,
+            malformed_min_requests: 2,
+            ..LogAnomalyConfig::default()
+        }
+    }
+
+    #[test]
+    fn detects_error_spike_bucket() {
+        let mut logs: Vec<_> = (0..50).map(|i| log(i * 10_000, 200)).collect();
+        // 第 10 分钟内集中出现错误
+        logs.extend((0..5).map(|i| log(600_000 + i * 1000, 500)));
+        logs.sort_by_key(|l| l.timestamp);
+
+        let report = analyze(&logs, &config());
+        assert_eq!(report.errors, 5);
+        assert_eq!(report.error_spikes.len(), 1);
+        assert_eq!(report.error_spikes[0].start, 600_000);
+        assert_eq!(report.error_spikes[0].errors, 5);
+    }
+
+    #[test]
+    fn flags_failing_accounts_above_threshold() {
+        let with_account = |i: i64, account: &str, status: u16| ProxyRequestLog {
+            account: Some(account.to_string()),
+            ..log(i, status)
+        };
+        let mut logs = Vec::new();
+        for i in 0..10 {
+            logs.push(with_account(i, "good@example.com", 200));
+            logs.push(with_account(i, "bad@example.com", if i < 6 { 429 } else { 200 }));
+            // 请求数不足，不参与判定
+            if i < 3 {
+                logs.push(with_account(i, "rare@example.com", 500));
+            }
+        }
+        let report = analyze(&logs, &config());
+        assert_eq!(report.failing_accounts.len(), 1);
+        let account = &report.failing_accounts[0];
+        assert_eq!(account.account, "bad@example.com");
+        assert_eq!((account.failures, account.requests, account.top_status), (6, 10, 429));
+    }
+
+    #[test]
+    fn detects_latency_regression_by_model() {
+        let mut logs = Vec::new();
+        for i in 0..10 {
+            let fast = ProxyRequestLog { duration: 100, ..log(i * 1000, 200) };
+            let slow = ProxyRequestLog {
+                duration: if i < 5 { 100 } else { 400 },
+                model: Some("gemini-2.5-pro".into()),
+                ..log(i * 1000, 200)
+            };
+            logs.push(fast);
+            logs.push(slow);
+        }
+        let report = analyze(&logs, &config());
+        assert_eq!(report.latency_regressions.len(), 1);
+        let regression = &report.latency_regressions[0];
+        assert_eq!(regression.model, "gemini-2.5-pro");
+        assert_eq!((regression.baseline_ms, regression.recent_ms), (100, 400));
+    }
+
+    #[test]
+    fn groups_malformed_requests_by_client() {
+        let bad = |i: i64, key: Option<&str>, ip: &str| ProxyRequestLog {
+            key_id: key.map(str::to_string),
+            client_ip: Some(ip.to_string()),
+            error: Some(format!("missing field {}", i)),
+            ..log(i, 400)
+        };
+        let logs = vec![
+            bad(1, Some("key-a"), "10.0.0.1"),
+            bad(2, Some("key-a"), "10.0.0.2"),
+            bad(3, Some("key-a"), "10.0.0.1"),
+            bad(4, None, "10.0.0.9"),
+            bad(5, None, "10.0.0.9"),
+            bad(6, None, "10.0.0.7"),
+        ];
+        let report = analyze(&logs, &config());
+        let clients: Vec<_> = report.malformed_clients.iter().map(|c| (c.client.as_str(), c.count)).collect();
+        assert_eq!(clients, vec![("key-a", 3), ("10.0.0.9", 2)]);
+        assert_eq!(report.malformed_clients[0].last_error.as_deref(), Some("missing field 3"));
+    }
+
+    #[test]
+    fn clean_logs_report_nothing() {
+        let logs: Vec<_> = (0..100).map(|i| log(i * 1000, 200)).collect();
+        let report = analyze(&logs, &config());
+        assert!(report.is_clean());
+        assert!(format_report(&report).contains("no anomalies"));
+    }
+}
@@ -0,0 +1,549 @@
+// Anthropic Message Batches 模拟 (`/v1/messages/batches`)
+// 接收批次后使用账号池按配置的并发执行，元数据与结果持久化到数据目录的 batches/ 下
+//   <id>.json           批次元数据
+//   <id>.requests.jsonl 原始请求 (用于重启后续跑)
+//   <id>.results.jsonl  逐条结果
+
+use axum::{extract::State, http::HeaderMap, Json};
+use dashmap::DashMap;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::proxy::config::{BatchConfig, RequestPriority};
+use crate::proxy::server::AppState;
+
+/// 单个批次最多包含的请求数
+pub const MAX_BATCH_REQUESTS: usize = 10_000;
+/// 批次有效期 (与 Anthropic 一致，24 小时后未执行的请求标记为 expired)
+const BATCH_EXPIRY_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequest {
+    pub custom_id: String,
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    InProgress,
+    Canceling,
+    Ended,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestCounts {
+    pub processing: u64,
+    pub succeeded: u64,
+    pub errored: u64,
+    pub canceled: u64,
+    pub expired: u64,
+}
+
+/// 批次对象 (字段与 Anthropic `message_batch` 保持一致)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageBatch {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub processing_status: BatchStatus,
+    pub request_counts: RequestCounts,
+    pub ended_at: Option<String>,
+    pub created_at: String,
+    pub expires_at: String,
+    pub cancel_initiated_at: Option<String>,
+    pub archived_at: Option<String>,
+    pub results_url: Option<String>,
+}
+
+fn now_rfc3339() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn is_expired(batch: &MessageBatch) -> bool {
+    chrono::DateTime::parse_from_rfc3339(&batch.expires_at)
+        .map(|t| t < chrono::Utc::now())
+        .unwrap_or(false)
+}
+
+pub struct BatchManager {
+    dir: PathBuf,
+    concurrency: AtomicUsize,
+    batches: DashMap<String, MessageBatch>,
+    cancel_flags: DashMap<String, Arc<AtomicBool>>,
+    /// 串行化结果追加与元数据写入
+    write_lock: Mutex<()>,
+}
+
+impl BatchManager {
+    /// 使用当前数据目录下的 batches/ 目录
+    pub fn new(config: &BatchConfig) -> Self {
+        let dir = crate::modules::account::get_data_dir()
+            .map(|d| d.join("batches"))
+            .unwrap_or_else(|_| std::env::temp_dir().join("antigravity_batches"));
+        Self::with_dir(dir, config)
+    }
+
+    pub fn with_dir(dir: PathBuf, config: &BatchConfig) -> Self {
+        let manager = Self {
+            dir,
+            concurrency: AtomicUsize::new(config.max_concurrency.max(1)),
+            batches: DashMap::new(),
+            cancel_flags: DashMap::new(),
+            write_lock: Mutex::new(()),
+        };
+        manager.load_existing();
+        manager
+    }
+
+    pub fn configure(&self, config: &BatchConfig) {
+        self.concurrency
+            .store(config.max_concurrency.max(1), Ordering::Relaxed);
+    }
+
+    fn meta_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn requests_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.requests.jsonl", id))
+    }
+
+    pub fn results_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.results.jsonl", id))
+    }
+
+    fn load_existing(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_meta = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(".json"));
+            if !is_meta {
+                continue;
+            }
+            match fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<MessageBatch>(&s).ok())
+            {
+                Some(batch) => {
+                    self.batches.insert(batch.id.clone(), batch);
+                }
+                None => tracing::warn!("[Batches] 无法解析批次文件: {:?}", path),
+            }
+        }
+    }
+
+    fn persist(&self, batch: &MessageBatch) {
+        let content = match serde_json::to_string_pretty(batch) {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::error!("[Batches] 序列化批次 {} 失败: {}", batch.id, e);
+                return;
+            }
+        };
+        if let Err(e) = fs::write(self.meta_path(&batch.id), content) {
+            tracing::error!("[Batches] 保存批次 {} 失败: {}", batch.id, e);
+        }
+    }
+
+    /// 校验并创建批次 (不启动执行)
+    pub fn create(&self, requests: Vec<BatchRequest>) -> Result<MessageBatch, String> {
+        if requests.is_empty() {
+            return Err("requests: at least one request is required".to_string());
+        }
+        if requests.len() > MAX_BATCH_REQUESTS {
+            return Err(format!(
+                "requests: at most {} requests are allowed per batch",
+                MAX_BATCH_REQUESTS
+            ));
+        }
+        let mut seen = HashSet::new();
+        for request in &requests {
+            if request.custom_id.is_empty() || request.custom_id.len() > 64 {
+                return Err("custom_id must be 1-64 characters".to_string());
+            }
+            if !seen.insert(request.custom_id.as_str()) {
+                return Err(format!("Duplicate custom_id: {}", request.custom_id));
+            }
+            if !request.params.is_object() {
+                return Err(format!("params for {} must be an object", request.custom_id));
+            }
+        }
+
+        fs::create_dir_all(&self.dir).map_err(|e| format!("创建批次目录失败: {}", e))?;
+
+        let id = format!("msgbatch_{}", uuid::Uuid::new_v4().simple());
+        let mut lines = String::new();
+        for request in &requests {
+            lines.push_str(&serde_json::to_string(request).map_err(|e| e.to_string())?);
+            lines.push('\n');
+        }
+        fs::write(self.requests_path(&id), lines).map_err(|e| format!("保存批次请求失败: {}", e))?;
+
+        let created = chrono::Utc::now();
+        let batch = MessageBatch {
+            id: id.clone(),
+            object_type: "message_batch".to_string(),
+            processing_status: BatchStatus::InProgress,
+            request_counts: RequestCounts {
+                processing: requests.len() as u64,
+                ..Default::default()
+            },
+            ended_at: None,
+            created_at: created.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            expires_at: (created + chrono::Duration::hours(BATCH_EXPIRY_HOURS))
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            cancel_initiated_at: None,
+            archived_at: None,
+            results_url: None,
+        };
+        self.persist(&batch);
+        self.batches.insert(id, batch.clone());
+        Ok(batch)
+    }
+
+    pub fn get(&self, id: &str) -> Option<MessageBatch> {
+        self.batches.get(id).map(|b| b.clone())
+    }
+
+    /// 按创建时间倒序分页 (`after_id` / `before_id` 语义与 Anthropic 一致)
+    pub fn list(
+        &self,
+        limit: usize,
+        before_id: Option<&str>,
+        after_id: Option<&str>,
+    ) -> (Vec<MessageBatch>, bool) {
+        let mut all: Vec<MessageBatch> = self.batches.iter().map(|b| b.clone()).collect();
+        all.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+
+        let position = |id: &str| all.iter().position(|b| b.id == id);
+        let (start, end) = match (after_id.and_then(position), before_id.and_then(position)) {
+            (Some(after), _) => (after + 1, all.len()),
+            (None, Some(before)) => (before.saturating_sub(limit), before),
+            _ => (0, all.len()),
+        };
+        let page: Vec<MessageBatch> = all[start..end].iter().take(limit).cloned().collect();
+        let has_more = end - start > page.len() || (before_id.is_some() && start > 0);
+        (page, has_more)
+    }
+
+    /// 请求取消：未执行的请求将被标记为 canceled
+    pub fn cancel(&self, id: &str) -> Option<MessageBatch> {
+        let mut batch = self.batches.get_mut(id)?;
+        if batch.processing_status == BatchStatus::InProgress {
+            batch.processing_status = BatchStatus::Canceling;
+            batch.cancel_initiated_at = Some(now_rfc3339());
+            if let Some(flag) = self.cancel_flags.get(id) {
+                flag.store(true, Ordering::SeqCst);
+            }
+            self.persist(&batch);
+        }
+        Some(batch.clone())
+    }
+
+    /// 删除已结束的批次及其结果文件
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        match self.get(id) {
+            None => Err(format!("Batch not found: {}", id)),
+            Some(b) if b.processing_status != BatchStatus::Ended => {
+                Err("Batch must be ended before it can be deleted; cancel it first".to_string())
+            }
+            Some(_) => {
+                self.batches.remove(id);
+                for path in [self.meta_path(id), self.requests_path(id), self.results_path(id)] {
+                    let _ = fs::remove_file(path);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn load_requests(&self, id: &str) -> Vec<BatchRequest> {
+        read_jsonl(&self.requests_path(id))
+    }
+
+    fn completed_ids(&self, id: &str) -> HashSet<String> {
+        read_jsonl::<Value>(&self.results_path(id))
+            .into_iter()
+            .filter_map(|v| v.get("custom_id").and_then(|c| c.as_str()).map(|s| s.to_string()))
+            .collect()
+    }
+
+    /// 追加一条结果并更新计数
+    fn record_result(&self, id: &str, custom_id: &str, result: Value) {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let line = json!({ "custom_id": custom_id, "result": result }).to_string();
+        let appended = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.results_path(id))
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = appended {
+            tracing::error!("[Batches] 写入批次 {} 结果失败: {}", id, e);
+        }
+
+        if let Some(mut batch) = self.batches.get_mut(id) {
+            let counts = &mut batch.request_counts;
+            counts.processing = counts.processing.saturating_sub(1);
+            match result.get("type").and_then(|t| t.as_str()) {
+                Some("succeeded") => counts.succeeded += 1,
+                Some("canceled") => counts.canceled += 1,
+                Some("expired") => counts.expired += 1,
+                _ => counts.errored += 1,
+            }
+            self.persist(&batch);
+        }
+    }
+
+    fn finish(&self, id: &str) {
+        if let Some(mut batch) = self.batches.get_mut(id) {
+            batch.processing_status = BatchStatus::Ended;
+            batch.ended_at = Some(now_rfc3339());
+            batch.results_url = Some(format!("/v1/messages/batches/{}/results", id));
+            self.persist(&batch);
+        }
+        self.cancel_flags.remove(id);
+        tracing::info!("[Batches] 批次 {} 已结束", id);
+    }
+
+    /// 在后台执行批次
+    pub fn spawn(self: &Arc<Self>, state: AppState, id: String) {
+        let cancel = self
+            .cancel_flags
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(AtomicBool::new(false)))
+            .clone();
+        if self
+            .get(&id)
+            .is_some_and(|b| b.processing_status == BatchStatus::Canceling)
+        {
+            cancel.store(true, Ordering::SeqCst);
+        }
+
+        let manager = self.clone();
+        tokio::spawn(async move {
+            manager.run(state, id, cancel).await;
+        });
+    }
+
+    /// 续跑上次进程退出时尚未结束的批次
+    pub fn resume_pending(self: &Arc<Self>, state: &AppState) {
+        let pending: Vec<String> = self
+            .batches
+            .iter()
+            .filter(|b| b.processing_status != BatchStatus::Ended)
+            .map(|b| b.id.clone())
+            .collect();
+        for id in pending {
+            tracing::info!("[Batches] 续跑未完成的批次 {}", id);
+            self.spawn(state.clone(), id);
+        }
+    }
+
+    async fn run(self: Arc<Self>, state: AppState, id: String, cancel: Arc<AtomicBool>) {
+        let done = self.completed_ids(&id);
+        let pending: Vec<BatchRequest> = self
+            .load_requests(&id)
+            .into_iter()
+            .filter(|r| !done.contains(&r.custom_id))
+            .collect();
+        let concurrency = self.concurrency.load(Ordering::Relaxed).max(1);
+        tracing::info!(
+            "[Batches] 开始执行批次 {}: {} 个待处理请求，并发 {}",
+            id,
+            pending.len(),
+            concurrency
+        );
+
+        futures::stream::iter(pending)
+            .for_each_concurrent(concurrency, |request| {
+                let state = state.clone();
+                let cancel = cancel.clone();
+                let manager = self.clone();
+                let id = id.clone();
+                async move {
+                    let expired = manager.get(&id).is_some_and(|b| is_expired(&b));
+                    let result = if cancel.load(Ordering::SeqCst) {
+                        json!({ "type": "canceled" })
+                    } else if expired {
+                        json!({ "type": "expired" })
+                    } else {
+                        // 批量请求以低优先级参与准入，过载时等待而不是失败
+                        let permit = loop {
+                            match state.admission.acquire(RequestPriority::Low).await {
+                                Ok(permit) => break Some(permit),
+                                Err(shed) if !cancel.load(Ordering::SeqCst) => {
+                                    tokio::time::sleep(shed.retry_after).await;
+                                }
+                                Err(_) => break None,
+                            }
+                        };
+                        if permit.is_some() {
+                            execute_request(&state, request.params).await
+                        } else {
+                            json!({ "type": "canceled" })
+                        }
+                    };
+                    manager.record_result(&id, &request.custom_id, result);
+                }
+            })
+            .await;
+
+        self.finish(&id);
+    }
+}
+
+fn read_jsonl<T: serde::de::DeserializeOwned>(path: &Path) -> Vec<T> {
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+/// 通过 Claude 消息处理器执行单个请求 (强制非流式)，返回批次结果对象
+async fn execute_request(state: &AppState, mut params: Value) -> Value {
+    if let Some(obj) = params.as_object_mut() {
+        obj.insert("stream".to_string(), Value::Bool(false));
+    }
+
+    let response = crate::proxy::handlers::claude::handle_messages(
+        State(state.clone()),
+        HeaderMap::new(),
+        Json(params),
+    )
+    .await;
+    let status = response.status();
+    let bytes = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return errored("api_error", &format!("Failed to read response: {}", e));
+        }
+    };
+
+    match serde_json::from_slice::<Value>(&bytes) {
+        Ok(message) if status.is_success() => json!({ "type": "succeeded", "message": message }),
+        Ok(error) if error.get("type").and_then(|t| t.as_str()) == Some("error") => {
+            json!({ "type": "errored", "error": error })
+        }
+        _ => errored(
+            if status.is_client_error() { "invalid_request_error" } else { "api_error" },
+            &format!("HTTP {}: {}", status.as_u16(), String::from_utf8_lossy(&bytes)),
+        ),
+    }
+}
+
+fn errored(error_type: &str, message: &str) -> Value {
+    json!({
+        "type": "errored",
+        "error": {
+            "type": "error",
+            "error": { "type": error_type, "message": message }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> (BatchManager, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("ag_batches_{}", uuid::Uuid::new_v4().simple()));
+        (BatchManager::with_dir(dir.clone(), &BatchConfig::default()), dir)
+    }
+
+    fn request(custom_id: &str) -> BatchRequest {
+        BatchRequest {
+            custom_id: custom_id.to_string(),
+            params: json!({ "model": "claude-sonnet-4-5", "max_tokens": 16, "messages": [] }),
+        }
+    }
+
+    #[test]
+    fn create_validates_requests() {
+        let (manager, dir) = manager();
+        assert!(manager.create(vec![]).is_err());
+        assert!(manager.create(vec![request("a"), request("a")]).is_err());
+
+        let batch = manager.create(vec![request("a"), request("b")]).unwrap();
+        assert_eq!(batch.processing_status, BatchStatus::InProgress);
+        assert_eq!(batch.request_counts.processing, 2);
+        assert_eq!(manager.load_requests(&batch.id).len(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn results_update_counts_and_survive_reload() {
+        let (manager, dir) = manager();
+        let batch = manager.create(vec![request("a"), request("b")]).unwrap();
+
+        manager.record_result(&batch.id, "a", json!({ "type": "succeeded", "message": {} }));
+        manager.record_result(&batch.id, "b", errored("api_error", "boom"));
+        manager.finish(&batch.id);
+
+        let reloaded = BatchManager::with_dir(dir.clone(), &BatchConfig::default());
+        let batch = reloaded.get(&batch.id).unwrap();
+        assert_eq!(batch.processing_status, BatchStatus::Ended);
+        assert_eq!(batch.request_counts.succeeded, 1);
+        assert_eq!(batch.request_counts.errored, 1);
+        assert_eq!(batch.request_counts.processing, 0);
+        assert_eq!(reloaded.completed_ids(&batch.id).len(), 2);
+        assert!(batch.results_url.is_some());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn cancel_and_delete_lifecycle() {
+        let (manager, dir) = manager();
+        let batch = manager.create(vec![request("a")]).unwrap();
+
+        assert!(manager.delete(&batch.id).is_err());
+        let canceled = manager.cancel(&batch.id).unwrap();
+        assert_eq!(canceled.processing_status, BatchStatus::Canceling);
+        assert!(canceled.cancel_initiated_at.is_some());
+
+        manager.finish(&batch.id);
+        manager.delete(&batch.id).unwrap();
+        assert!(manager.get(&batch.id).is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn list_is_newest_first_with_pagination() {
+        let (manager, dir) = manager();
+        let ids: Vec<String> = (0..3)
+            .map(|i| {
+                let mut batch = manager.create(vec![request("a")]).unwrap();
+                batch.created_at = format!("2026-01-0{}T00:00:00Z", i + 1);
+                manager.batches.insert(batch.id.clone(), batch.clone());
+                batch.id
+            })
+            .collect();
+
+        let (page, has_more) = manager.list(2, None, None);
+        assert_eq!(page.iter().map(|b| b.id.clone()).collect::<Vec<_>>(), vec![ids[2].clone(), ids[1].clone()]);
+        assert!(has_more);
+
+        let (page, has_more) = manager.list(2, None, Some(&ids[1]));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, ids[0]);
+        assert!(!has_more);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
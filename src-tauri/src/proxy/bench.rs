@@ -0,0 +1,224 @@
+// 压测: 向运行中的反代并发发送合成对话请求，统计吞吐、延迟分位数与各账号的请求分布
+use futures::StreamExt;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// 反代地址，如 `http://127.0.0.1:8045`
+    pub base_url: String,
+    /// 管理接口地址 (配置了 `admin_listen` 时与反代地址不同)
+    pub admin_url: String,
+    pub api_key: String,
+    pub requests: usize,
+    pub concurrency: usize,
+    pub model: String,
+    pub prompt: String,
+    pub max_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub requests: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub elapsed_ms: u64,
+    pub requests_per_sec: f64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+    /// 状态码 -> 次数 (连接失败记为 0)
+    pub statuses: BTreeMap<u16, usize>,
+    /// 账号 -> 压测期间分配到的请求数
+    pub accounts: BTreeMap<String, u64>,
+}
+
+/// 单个请求的结果: (状态码，连接失败为 0；耗时)
+type Sample = (u16, Duration);
+
+async fn send_one(client: &reqwest::Client, options: &BenchOptions, index: usize) -> Sample {
+    let body = serde_json::json!({
+        "model": options.model,
+        "messages": [{ "role": "user", "content": format!("{} (#{})", options.prompt, index) }],
+        "max_tokens": options.max_tokens,
+        "stream": false,
+    });
+    let start = Instant::now();
+    let status = match client
+        .post(format!("{}/v1/chat/completions", options.base_url))
+        .bearer_auth(&options.api_key)
+        .json(&body)
+        .send()
+        .await
+    {
+        // 读完响应体，计入完整耗时
+        Ok(resp) => {
+            let status = resp.status().as_u16();
+            let _ = resp.bytes().await;
+            status
+        }
+        Err(_) => 0,
+    };
+    (status, start.elapsed())
+}
+
+/// 当前窗口内各账号的请求数 (来自 `/admin/usage`)
+async fn usage_counts(client: &reqwest::Client, options: &BenchOptions) -> Result<HashMap<String, u64>, reqwest::Error> {
+    let body: serde_json::Value = client
+        .get(format!("{}/admin/usage", options.admin_url))
+        .bearer_auth(&options.api_key)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(body["accounts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|a| Some((a["email"].as_str()?.to_string(), a["requests"].as_u64()?)))
+        .collect())
+}
+
+/// 执行压测；管理接口不可用时 (如认证失败) 直接返回错误，不发送压测请求
+pub async fn run(options: &BenchOptions) -> Result<BenchReport, reqwest::Error> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()?;
+    let before = usage_counts(&client, options).await?;
+
+    let start = Instant::now();
+    let samples: Vec<Sample> = futures::stream::iter(0..options.requests)
+        .map(|i| send_one(&client, options, i))
+        .buffer_unordered(options.concurrency.max(1))
+        .collect()
+        .await;
+    let elapsed = start.elapsed();
+
+    // 统计窗口在压测期间切换时，切换后的计数即为增量
+    let after = usage_counts(&client, options).await.unwrap_or_default();
+    let accounts = after
+        .into_iter()
+        .filter_map(|(email, count)| {
+            let prev = before.get(&email).copied().unwrap_or(0);
+            let delta = if count >= prev { count - prev } else { count };
+            (delta > 0).then_some((email, delta))
+        })
+        .collect();
+
+    Ok(summarize(&samples, elapsed, accounts))
+}
+
+fn summarize(samples: &[Sample], elapsed: Duration, accounts: BTreeMap<String, u64>) -> BenchReport {
+    let mut latencies: Vec<u64> = samples.iter().map(|(_, d)| d.as_millis() as u64).collect();
+    latencies.sort_unstable();
+    let pick = |q: f64| {
+        if latencies.is_empty() {
+            0
+        } else {
+            latencies[((latencies.len() - 1) as f64 * q).round() as usize]
+        }
+    };
+
+    let mut statuses = BTreeMap::new();
+    for (status, _) in samples {
+        *statuses.entry(*status).or_insert(0) += 1;
+    }
+    let succeeded = samples.iter().filter(|(s, _)| (200..300).contains(s)).count();
+    let secs = elapsed.as_secs_f64();
+
+    BenchReport {
+        requests: samples.len(),
+        succeeded,
+        failed: samples.len() - succeeded,
+        elapsed_ms: elapsed.as_millis() as u64,
+        requests_per_sec: if secs > 0.0 { samples.len() as f64 / secs } else { 0.0 },
+        p50_ms: pick(0.5),
+        p90_ms: pick(0.9),
+        p99_ms: pick(0.99),
+        max_ms: latencies.last().copied().unwrap_or(0),
+        statuses,
+        accounts,
+    }
+}
+
+/// 文本报告
+pub fn format_report(report: &BenchReport) -> String {
+    let mut out = format!(
+        "requests: {} ({} ok, {} failed) in {:.1}s, {:.2} req/s\n",
+        report.requests,
+        report.succeeded,
+        report.failed,
+        report.elapsed_ms as f64 / 1000.0,
+        report.requests_per_sec
+    );
+    out.push_str(&format!(
+        "latency:  p50 {} ms, p90 {} ms, p99 {} ms, max {} ms\n",
+        report.p50_ms, report.p90_ms, report.p99_ms, report.max_ms
+    ));
+    let statuses: Vec<String> = report
+        .statuses
+        .iter()
+        .map(|(status, count)| match status {
+            0 => format!("conn-error x{}", count),
+            s => format!("{} x{}", s, count),
+        })
+        .collect();
+    out.push_str(&format!("status:   {}\n", statuses.join(", ")));
+
+    let total: u64 = report.accounts.values().sum();
+    if total > 0 {
+        out.push_str("accounts:\n");
+        let mut accounts: Vec<_> = report.accounts.iter().collect();
+        accounts.sort_by(|a, b| b.1.cmp(a.1));
+        for (email, count) in accounts {
+            out.push_str(&format!(
+                "    {:<40} {:>6}  {:>5.1}%\n",
+                email,
+                count,
+                *count as f64 * 100.0 / total as f64
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_latency_and_statuses() {
+        let samples: Vec<Sample> = (1..=100)
+            .map(|i| (if i % 10 == 0 { 429 } else { 200 }, Duration::from_millis(i * 10)))
+            .chain(std::iter::once((0, Duration::from_millis(5))))
+            .collect();
+        let mut accounts = BTreeMap::new();
+        accounts.insert("a@example.com".to_string(), 60);
+        accounts.insert("b@example.com".to_string(), 30);
+
+        let report = summarize(&samples, Duration::from_secs(2), accounts);
+        assert_eq!(report.requests, 101);
+        assert_eq!(report.succeeded, 90);
+        assert_eq!(report.failed, 11);
+        assert_eq!(report.statuses[&429], 10);
+        assert_eq!(report.statuses[&0], 1);
+        assert_eq!(report.p50_ms, 500);
+        assert_eq!(report.max_ms, 1000);
+        assert!((report.requests_per_sec - 50.5).abs() < 1e-9);
+
+        let text = format_report(&report);
+        assert!(text.contains("conn-error x1"));
+        assert!(text.contains("a@example.com"));
+        assert!(text.contains("66.7%"));
+    }
+
+    #[test]
+    fn empty_run_has_zero_latency() {
+        let report = summarize(&[], Duration::ZERO, BTreeMap::new());
+        assert_eq!(report.p99_ms, 0);
+        assert_eq!(report.requests_per_sec, 0.0);
+    }
+}
@@ -0,0 +1,191 @@
+//! 嵌入式 API: 其他 Rust 程序可直接在自己的 tokio 运行时中启动带账号池的反代服务，
+//! 无需经过 CLI 或 Tauri 界面。
+//!
+//! ```no_run
+//! use antigravity_tools_lib::ProxyBuilder;
+//!
+//! # async fn run() -> Result<(), String> {
+//! let proxy = ProxyBuilder::new()
+//!     .accounts("/var/lib/antigravity")
+//!     .api_key("sk-embedded")
+//!     .mapping("gpt-4o", "gemini-2.5-pro")
+//!     .listen("127.0.0.1:8045")
+//!     .await?;
+//! println!("{} 个账号可用", proxy.active_accounts());
+//! proxy.shutdown(std::time::Duration::from_secs(10)).await;
+//! # Ok(())
+//! # }
+//! ```
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::commands::proxy::ProxyServiceInstance;
+use crate::modules::storage::{FileStorage, StorageBackend};
+use crate::proxy::config::{ListenerConfig, ProxyConfig};
+use crate::proxy::monitor::ProxyMonitor;
+use crate::proxy::TokenManager;
+
+/// 未启用请求日志时监控保留的最大日志条数
+const MONITOR_MAX_LOGS: usize = 1000;
+
+/// 反代服务构建器
+pub struct ProxyBuilder {
+    config: ProxyConfig,
+    data_dir: Option<PathBuf>,
+    storage: Option<Arc<dyn StorageBackend>>,
+}
+
+impl Default for ProxyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProxyBuilder {
+    /// 使用默认配置 (仅本机访问、随机 API Key)
+    pub fn new() -> Self {
+        Self::from_config(ProxyConfig::default())
+    }
+
+    /// 基于完整的反代配置 (与 `gui_config.json` 中的 `proxy` 段相同)
+    pub fn from_config(config: ProxyConfig) -> Self {
+        Self {
+            config,
+            data_dir: None,
+            storage: None,
+        }
+    }
+
+    /// 账号数据目录，账号从其中的 `accounts/*.json` 加载；未指定时使用全局存储后端 (`storage::backend()`)
+    pub fn accounts(mut self, data_dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(data_dir.into());
+        self
+    }
+
+    /// 从指定存储后端加载账号，刷新后的 token 也写回该后端 (优先于 `accounts`)
+    pub fn storage(mut self, storage: Arc<dyn StorageBackend>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// 添加一条自定义模型映射 (`from` 支持通配符，与配置中的 `custom_mapping` 相同)
+    pub fn mapping(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.config.custom_mapping.insert(from.into(), to.into());
+        self
+    }
+
+    /// 客户端访问反代使用的 API Key
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.config.api_key = key.into();
+        self
+    }
+
+    /// 修改其余配置项
+    pub fn configure(mut self, f: impl FnOnce(&mut ProxyConfig)) -> Self {
+        f(&mut self.config);
+        self
+    }
+
+    /// 加载账号并开始监听 `bind` (如 `127.0.0.1:8045`，或 Unix 套接字 `unix:/run/antigravity.sock`)
+    pub async fn listen(mut self, bind: impl Into<String>) -> Result<EmbeddedProxy, String> {
+        let bind = bind.into();
+        self.config.listen_tcp = false;
+        self.config.listeners = vec![ListenerConfig {
+            bind: bind.clone(),
+            tls: None,
+            auth_mode: None,
+            api_key: None,
+            socket_mode: None,
+            acme: None,
+        }];
+
+        let monitor = Arc::new(ProxyMonitor::new(MONITOR_MAX_LOGS, None));
+        monitor.set_enabled(self.config.enable_logging);
+        monitor.set_body_capture(&self.config.body_capture);
+
+        let storage = match (self.storage, self.data_dir) {
+            (Some(storage), _) => storage,
+            (None, Some(dir)) => Arc::new(FileStorage::at(dir)),
+            (None, None) => crate::modules::storage::backend(),
+        };
+        let (instance, active_accounts) =
+            crate::commands::proxy::create_proxy_instance_in(&self.config, monitor.clone(), storage)
+                .await?;
+        tracing::info!("嵌入式反代服务已启动: {}，可用账号 {} 个", bind, active_accounts);
+
+        Ok(EmbeddedProxy {
+            instance,
+            monitor,
+            bind,
+            active_accounts,
+        })
+    }
+}
+
+/// 运行中的嵌入式反代服务
+pub struct EmbeddedProxy {
+    instance: ProxyServiceInstance,
+    monitor: Arc<ProxyMonitor>,
+    bind: String,
+    active_accounts: usize,
+}
+
+impl EmbeddedProxy {
+    /// 监听地址
+    pub fn bind(&self) -> &str {
+        &self.bind
+    }
+
+    /// 启动时加载的可用账号数
+    pub fn active_accounts(&self) -> usize {
+        self.active_accounts
+    }
+
+    /// 生效的反代配置
+    pub fn config(&self) -> &ProxyConfig {
+        &self.instance.config
+    }
+
+    /// 账号池 (查询账号状态、手动限流 / 移除账号等)
+    pub fn token_manager(&self) -> Arc<TokenManager> {
+        self.instance.token_manager.clone()
+    }
+
+    /// 请求监控 (统计与请求日志)
+    pub fn monitor(&self) -> Arc<ProxyMonitor> {
+        self.monitor.clone()
+    }
+
+    /// 停止监听，在 `drain_timeout` 内等待在途请求完成后返回
+    pub async fn shutdown(self, drain_timeout: Duration) {
+        let ProxyServiceInstance {
+            axum_server,
+            server_handle,
+            ..
+        } = self.instance;
+        axum_server.stop_with_drain(drain_timeout);
+        let _ = server_handle.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_collects_settings() {
+        let builder = ProxyBuilder::new()
+            .accounts("/tmp/antigravity-embed")
+            .api_key("sk-embedded")
+            .mapping("gpt-4o", "gemini-2.5-pro")
+            .configure(|c| c.enable_logging = true);
+        assert_eq!(builder.data_dir, Some(PathBuf::from("/tmp/antigravity-embed")));
+        assert_eq!(builder.config.api_key, "sk-embedded");
+        assert_eq!(
+            builder.config.custom_mapping.get("gpt-4o").map(String::as_str),
+            Some("gemini-2.5-pro")
+        );
+        assert!(builder.config.enable_logging);
+    }
+}
@@ -0,0 +1,194 @@
+// 客户端真实地址：部署在 nginx / caddy 等反向代理之后时，仅当对端是受信任代理才采用 X-Forwarded-For
+use axum::http::HeaderMap;
+use std::net::IpAddr;
+
+/// 解析后的客户端地址，由 `client_ip_middleware` 写入请求扩展
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// 单个地址或 CIDR 网段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Network {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr: IpAddr = addr.trim().parse().ok()?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.trim().parse::<u8>().ok().filter(|p| *p <= max)?,
+            None => max,
+        };
+        Some(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 映射的 IPv6 地址 (::ffff:a.b.c.d) 按 IPv4 比较
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix)).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix)).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 受信任的反向代理地址 (`proxy.trusted_proxies`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    networks: Vec<Network>,
+}
+
+impl TrustedProxies {
+    /// 解析配置，返回无效条目 (调用方负责提示)
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> (Self, Vec<String>) {
+        let mut networks = Vec::new();
+        let mut invalid = Vec::new();
+        for entry in entries {
+            let entry = entry.as_ref();
+            match Network::parse(entry) {
+                Some(network) => networks.push(network),
+                None => invalid.push(entry.to_string()),
+            }
+        }
+        (Self { networks }, invalid)
+    }
+
+    pub fn from_config(entries: &[String]) -> Self {
+        let (trusted, invalid) = Self::parse(entries);
+        for entry in invalid {
+            tracing::warn!("忽略无效的受信任代理地址: {}", entry);
+        }
+        trusted
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|n| n.contains(ip))
+    }
+
+    /// 计算客户端地址：对端不受信任时直接使用对端地址；
+    /// 否则从 X-Forwarded-For 右侧向左跳过受信任代理，取第一个不受信任的地址 (没有 XFF 时参考 X-Real-IP)
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        let forwarded: Vec<IpAddr> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(parse_forwarded_addr)
+            .collect();
+        if forwarded.is_empty() {
+            return headers
+                .get("x-real-ip")
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_forwarded_addr)
+                .unwrap_or(peer);
+        }
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !self.is_trusted(**ip))
+            .or_else(|| forwarded.first())
+            .copied()
+            .unwrap_or(peer)
+    }
+}
+
+/// 解析转发头中的单个地址，兼容 `1.2.3.4:5678` 与 `[::1]:443` 形式
+fn parse_forwarded_addr(value: &str) -> Option<IpAddr> {
+    let value = value.trim();
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = value.parse::<std::net::SocketAddr>() {
+        return Some(addr.ip());
+    }
+    value
+        .strip_prefix('[')
+        .and_then(|v| v.split_once(']'))
+        .and_then(|(ip, _)| ip.parse().ok())
+}
+
+/// 请求的客户端地址：优先使用解析后的 `ClientIp`，否则为连接对端地址
+pub fn client_ip<B>(request: &axum::http::Request<B>) -> Option<IpAddr> {
+    request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| *ip)
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+                .map(|axum::extract::ConnectInfo(addr)| addr.ip())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_addresses_and_cidrs() {
+        let (trusted, invalid) = TrustedProxies::parse(&["127.0.0.1", "10.0.0.0/8", "fd00::/8", "bogus", "1.2.3.4/40"]);
+        assert_eq!(invalid, vec!["bogus", "1.2.3.4/40"]);
+        assert!(trusted.is_trusted(ip("127.0.0.1")));
+        assert!(trusted.is_trusted(ip("10.20.30.40")));
+        assert!(trusted.is_trusted(ip("::ffff:10.1.1.1")));
+        assert!(trusted.is_trusted(ip("fd12::1")));
+        assert!(!trusted.is_trusted(ip("11.0.0.1")));
+        assert!(!trusted.is_trusted(ip("127.0.0.2")));
+    }
+
+    #[test]
+    fn untrusted_peer_ignores_forwarded_headers() {
+        let (trusted, _) = TrustedProxies::parse(&["127.0.0.1"]);
+        let h = headers(&[("x-forwarded-for", "1.1.1.1")]);
+        assert_eq!(trusted.resolve(ip("192.168.1.5"), &h), ip("192.168.1.5"));
+        assert_eq!(TrustedProxies::default().resolve(ip("127.0.0.1"), &h), ip("127.0.0.1"));
+    }
+
+    #[test]
+    fn trusted_peer_uses_rightmost_untrusted_hop() {
+        let (trusted, _) = TrustedProxies::parse(&["127.0.0.1", "10.0.0.0/8"]);
+        // 客户端伪造的最左侧地址不被采用
+        let h = headers(&[("x-forwarded-for", "6.6.6.6, 203.0.113.7, 10.0.0.2")]);
+        assert_eq!(trusted.resolve(ip("127.0.0.1"), &h), ip("203.0.113.7"));
+
+        let split = headers(&[("x-forwarded-for", "203.0.113.9:5555"), ("x-forwarded-for", "10.0.0.3")]);
+        assert_eq!(trusted.resolve(ip("127.0.0.1"), &split), ip("203.0.113.9"));
+
+        let real_ip = headers(&[("x-real-ip", "198.51.100.1")]);
+        assert_eq!(trusted.resolve(ip("127.0.0.1"), &real_ip), ip("198.51.100.1"));
+        assert_eq!(trusted.resolve(ip("127.0.0.1"), &HeaderMap::new()), ip("127.0.0.1"));
+    }
+}
@@ -0,0 +1,253 @@
+// 多实例协同: 多个反代实例共用同一账号池时，通过共享后端同步账号冷却与用量计数，
+// 避免同一账号被多个实例同时打满
+//
+// 后端通过 `SharedStateBackend` 接入；内置实现为 HTTP 对等广播 (调用各对等实例的
+// `POST /admin/cluster/events`，使用 `cluster.api_key` 共享密钥鉴权，不接受客户端 API Key)。
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::proxy::config::ClusterConfig;
+use crate::proxy::TokenManager;
+
+/// 单次广播最多携带的事件数
+const MAX_EVENTS_PER_MESSAGE: usize = 256;
+/// 对等实例之间的事件接口
+pub const EVENTS_PATH: &str = "/admin/cluster/events";
+/// 携带集群共享密钥的请求头
+pub const SECRET_HEADER: &str = "x-antigravity-cluster-secret";
+/// 对等实例同步的冷却最长生效时间 (秒)，防止单条消息长期锁定账号
+pub const MAX_REMOTE_COOLDOWN_SECS: i64 = 3600;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClusterEvent {
+    /// 账号进入冷却 (限流)，`until` 为 Unix 秒
+    Cooldown { account: String, until: i64 },
+    /// 发送方当前窗口内各账号的用量 (覆盖该实例此前上报的值)
+    Usage { window_start: i64, accounts: Vec<AccountUsage> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountUsage {
+    pub email: String,
+    pub requests: u64,
+    pub tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterMessage {
+    /// 发送方实例 ID
+    pub instance: String,
+    pub events: Vec<ClusterEvent>,
+}
+
+/// 共享状态后端
+pub trait SharedStateBackend: Send + Sync {
+    fn publish(&self, message: ClusterMessage) -> BoxFuture<'static, Result<(), String>>;
+}
+
+/// HTTP 对等广播后端
+pub struct HttpGossipBackend {
+    client: reqwest::Client,
+    peers: Vec<String>,
+    secret: String,
+}
+
+impl HttpGossipBackend {
+    pub fn new(peers: Vec<String>, secret: String) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+            peers: peers
+                .into_iter()
+                .map(|p| p.trim().trim_end_matches('/').to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            secret,
+        }
+    }
+}
+
+impl SharedStateBackend for HttpGossipBackend {
+    fn publish(&self, message: ClusterMessage) -> BoxFuture<'static, Result<(), String>> {
+        let client = self.client.clone();
+        let peers = self.peers.clone();
+        let secret = self.secret.clone();
+        Box::pin(async move {
+            let sends = peers.iter().map(|peer| {
+                client
+                    .post(format!("{}{}", peer, EVENTS_PATH))
+                    .header(SECRET_HEADER, &secret)
+                    .json(&message)
+                    .send()
+            });
+            let mut errors = Vec::new();
+            for (peer, result) in peers.iter().zip(futures::future::join_all(sends).await) {
+                match result.and_then(|r| r.error_for_status()) {
+                    Ok(_) => {}
+                    Err(e) => errors.push(format!("{}: {}", peer, e)),
+                }
+            }
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors.join("; "))
+            }
+        })
+    }
+}
+
+/// 运行中的集群同步: 收集本实例的事件，按间隔批量广播
+pub struct Cluster {
+    config: ClusterConfig,
+    instance_id: String,
+    events: mpsc::UnboundedSender<ClusterEvent>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Cluster {
+    pub fn start(
+        config: &ClusterConfig,
+        backend: Arc<dyn SharedStateBackend>,
+        token_manager: Weak<TokenManager>,
+    ) -> Arc<Self> {
+        let instance_id = if config.instance_id.trim().is_empty() {
+            uuid::Uuid::new_v4().to_string()
+        } else {
+            config.instance_id.trim().to_string()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let interval = Duration::from_secs(config.sync_interval_secs.max(1));
+        let task = tokio::spawn(run(instance_id.clone(), backend, token_manager, rx, interval));
+        tracing::info!("集群同步已启动: instance={}, peers={}", instance_id, config.peers.len());
+        Arc::new(Self {
+            config: config.clone(),
+            instance_id,
+            events: tx,
+            task,
+        })
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// 排队广播一条本实例产生的事件 (不阻塞调用方)
+    pub fn emit(&self, event: ClusterEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(
+    instance_id: String,
+    backend: Arc<dyn SharedStateBackend>,
+    token_manager: Weak<TokenManager>,
+    mut rx: mpsc::UnboundedReceiver<ClusterEvent>,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        let mut events = Vec::new();
+        tokio::select! {
+            _ = ticker.tick() => {
+                let Some(manager) = token_manager.upgrade() else { break };
+                if let Some(usage) = manager.local_usage_event().await {
+                    events.push(usage);
+                }
+            }
+            event = rx.recv() => match event {
+                Some(event) => events.push(event),
+                None => break,
+            },
+        }
+        // 冷却事件尽快广播，同时合并已排队的其他事件
+        while events.len() < MAX_EVENTS_PER_MESSAGE {
+            match rx.try_recv() {
+                Ok(event) => events.push(event),
+                Err(_) => break,
+            }
+        }
+        if events.is_empty() {
+            continue;
+        }
+        let message = ClusterMessage {
+            instance: instance_id.clone(),
+            events,
+        };
+        if let Err(e) = backend.publish(message).await {
+            tracing::warn!("集群同步广播失败: {}", e);
+        }
+    }
+    tracing::debug!("集群同步任务已退出");
+}
+
+/// 按配置启用 / 停用集群同步 (启动与热更新共用，配置未变化时保持运行)
+pub fn configure(token_manager: &Arc<TokenManager>, config: &ClusterConfig) {
+    if token_manager.cluster().map_or(false, |c| c.config == *config) {
+        return;
+    }
+    if !config.enabled || config.peers.is_empty() {
+        token_manager.set_cluster(None);
+        return;
+    }
+    if config.api_key.is_empty() {
+        tracing::warn!("集群同步未启动: 未配置 cluster.api_key 共享密钥");
+        token_manager.set_cluster(None);
+        return;
+    }
+    let backend = Arc::new(HttpGossipBackend::new(config.peers.clone(), config.api_key.clone()));
+    let cluster = Cluster::start(config, backend, Arc::downgrade(token_manager));
+    token_manager.set_cluster(Some(cluster));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip_as_tagged_json() {
+        let message = ClusterMessage {
+            instance: "a".to_string(),
+            events: vec![
+                ClusterEvent::Cooldown {
+                    account: "x@example.com".to_string(),
+                    until: 1_700_000_000,
+                },
+                ClusterEvent::Usage {
+                    window_start: 1_699_977_600,
+                    accounts: vec![AccountUsage {
+                        email: "x@example.com".to_string(),
+                        requests: 3,
+                        tokens: 1200,
+                    }],
+                },
+            ],
+        };
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["events"][0]["type"], "cooldown");
+        assert_eq!(json["events"][1]["type"], "usage");
+        let back: ClusterMessage = serde_json::from_value(json).unwrap();
+        assert_eq!(back.events, message.events);
+    }
+
+    #[test]
+    fn gossip_backend_normalizes_peers() {
+        let backend = HttpGossipBackend::new(
+            vec!["http://10.0.0.2:8045/".to_string(), " ".to_string()],
+            "sk-test".to_string(),
+        );
+        assert_eq!(backend.peers, vec!["http://10.0.0.2:8045".to_string()]);
+    }
+}
@@ -0,0 +1,310 @@
+// 模型映射规则: 按顺序匹配的通配符/正则规则，供三种协议的路由共用
+use rand::Rng;
+use regex::Regex;
+use std::cell::RefCell;
+
+use crate::proxy::config::ModelMappingRule;
+
+/// 正则规则前缀，如 `re:^claude-3-5-(\w+)`
+const REGEX_PREFIX: &str = "re:";
+
+tokio::task_local! {
+    /// 当前请求中加权规则已选定的目标 (key: 原始模型)，保证同一请求多次路由 (重试、换号) 结果一致
+    static CANARY_CHOICES: RefCell<Vec<(String, String)>>;
+}
+
+/// 执行 `future` 并返回期间加权规则最后一次选定的目标；嵌套调用时共用外层的记录
+pub async fn track_canary<F: std::future::Future>(future: F) -> (F::Output, Option<String>) {
+    let last = || CANARY_CHOICES.with(|c| c.borrow().last().map(|(_, target)| target.clone()));
+    if CANARY_CHOICES.try_with(|_| ()).is_ok() {
+        let output = future.await;
+        return (output, last());
+    }
+    CANARY_CHOICES
+        .scope(RefCell::new(Vec::new()), async move {
+            let output = future.await;
+            (output, last())
+        })
+        .await
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    pattern: String,
+    regex: Regex,
+    /// (目标模型, 权重)；单一目标时只有一项
+    targets: Vec<(String, u32)>,
+    /// 正则规则的目标支持 `$1` 等捕获组引用
+    expand: bool,
+}
+
+impl CompiledRule {
+    fn total_weight(&self) -> u32 {
+        self.targets.iter().map(|(_, w)| *w).sum()
+    }
+
+    /// 按 `roll` (0..总权重) 选取目标
+    fn pick(&self, roll: u32) -> &str {
+        let mut acc = 0;
+        for (target, weight) in &self.targets {
+            acc += weight;
+            if roll < acc {
+                return target;
+            }
+        }
+        &self.targets[self.targets.len() - 1].0
+    }
+}
+
+/// 已编译的映射规则 (保持配置中的顺序，先匹配者优先)
+#[derive(Debug, Clone, Default)]
+pub struct MappingRules {
+    rules: Vec<CompiledRule>,
+}
+
+/// 将通配符转换为锚定的正则: `*` 匹配任意字符，`?` 匹配单个字符
+fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::from("(?i)^");
+    for ch in glob.chars() {
+        match ch {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out.push('$');
+    out
+}
+
+fn compile_rule(rule: &ModelMappingRule) -> Result<CompiledRule, String> {
+    let pattern = rule.pattern.trim();
+    if pattern.is_empty() {
+        return Err("映射规则的匹配模式为空".to_string());
+    }
+    let targets: Vec<(String, u32)> = if rule.targets.is_empty() {
+        if rule.target.trim().is_empty() {
+            return Err(format!("{} 的映射目标为空", pattern));
+        }
+        vec![(rule.target.trim().to_string(), 1)]
+    } else {
+        if rule.targets.iter().any(|t| t.model.trim().is_empty()) {
+            return Err(format!("{} 的加权目标中存在空模型", pattern));
+        }
+        if rule.targets.iter().all(|t| t.weight == 0) {
+            return Err(format!("{} 的加权目标权重之和为 0", pattern));
+        }
+        rule.targets
+            .iter()
+            .filter(|t| t.weight > 0)
+            .map(|t| (t.model.trim().to_string(), t.weight))
+            .collect()
+    };
+
+    let (source, expand) = match pattern.strip_prefix(REGEX_PREFIX) {
+        Some(re) => (re.to_string(), true),
+        None => (glob_to_regex(pattern), false),
+    };
+    let regex = Regex::new(&source).map_err(|e| format!("无效的映射规则 {}: {}", pattern, e))?;
+
+    Ok(CompiledRule {
+        pattern: pattern.to_string(),
+        regex,
+        targets,
+        expand,
+    })
+}
+
+impl MappingRules {
+    /// 编译全部规则，任一规则无效即返回错误 (用于配置校验)
+    pub fn compile(rules: &[ModelMappingRule]) -> Result<Self, String> {
+        Ok(Self {
+            rules: rules.iter().map(compile_rule).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// 编译规则并跳过无效项 (运行时加载，避免单条错误规则导致整体失效)
+    pub fn from_config(rules: &[ModelMappingRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match compile_rule(rule) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    tracing::warn!("[Router] 忽略映射规则: {}", e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// 返回第一条命中规则的 (模式, 目标模型)；加权规则在同一请求内只抽取一次
+    pub fn resolve(&self, model: &str) -> Option<(&str, String)> {
+        self.resolve_with(model, |total| rand::thread_rng().gen_range(0..total))
+    }
+
+    fn resolve_with(&self, model: &str, roll: impl FnOnce(u32) -> u32) -> Option<(&str, String)> {
+        self.rules.iter().find_map(|rule| {
+            let caps = rule.regex.captures(model)?;
+            let weighted = rule.targets.len() > 1;
+            if weighted {
+                let chosen = CANARY_CHOICES
+                    .try_with(|c| {
+                        c.borrow()
+                            .iter()
+                            .find(|(m, _)| m == model)
+                            .map(|(_, target)| target.clone())
+                    })
+                    .ok()
+                    .flatten();
+                if let Some(target) = chosen {
+                    return Some((rule.pattern.as_str(), target));
+                }
+            }
+
+            let target = rule.pick(roll(rule.total_weight()));
+            let target = if rule.expand {
+                let mut expanded = String::new();
+                caps.expand(target, &mut expanded);
+                expanded
+            } else {
+                target.to_string()
+            };
+            if weighted {
+                let _ = CANARY_CHOICES
+                    .try_with(|c| c.borrow_mut().push((model.to_string(), target.clone())));
+            }
+            Some((rule.pattern.as_str(), target))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::WeightedTarget;
+
+    fn rule(pattern: &str, target: &str) -> ModelMappingRule {
+        ModelMappingRule {
+            pattern: pattern.to_string(),
+            target: target.to_string(),
+            targets: Vec::new(),
+        }
+    }
+
+    fn weighted(pattern: &str, targets: &[(&str, u32)]) -> ModelMappingRule {
+        ModelMappingRule {
+            pattern: pattern.to_string(),
+            target: String::new(),
+            targets: targets
+                .iter()
+                .map(|(model, weight)| WeightedTarget {
+                    model: model.to_string(),
+                    weight: *weight,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn glob_rules_match_in_order() {
+        let rules = MappingRules::compile(&[
+            rule("claude-3-5-haiku*", "gemini-2.5-flash"),
+            rule("claude-*", "gemini-3-pro-high"),
+            rule("gpt-4?", "gemini-2.5-pro"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            rules.resolve("claude-3-5-haiku-20241022").map(|(_, t)| t).as_deref(),
+            Some("gemini-2.5-flash")
+        );
+        assert_eq!(
+            rules.resolve("Claude-Sonnet-4-5").map(|(p, t)| (p.to_string(), t)),
+            Some(("claude-*".to_string(), "gemini-3-pro-high".to_string()))
+        );
+        assert_eq!(
+            rules.resolve("gpt-4o").map(|(_, t)| t).as_deref(),
+            Some("gemini-2.5-pro")
+        );
+        assert!(rules.resolve("gpt-4o-mini").is_none());
+        // `.` 按字面匹配
+        assert!(MappingRules::compile(&[rule("gpt-4.1", "x")])
+            .unwrap()
+            .resolve("gpt-441")
+            .is_none());
+    }
+
+    #[test]
+    fn regex_rules_expand_captures() {
+        let rules =
+            MappingRules::compile(&[rule(r"re:^gemini-(\d)-pro-preview$", "gemini-$1-pro-high")])
+                .unwrap();
+        assert_eq!(
+            rules.resolve("gemini-3-pro-preview").map(|(_, t)| t).as_deref(),
+            Some("gemini-3-pro-high")
+        );
+        assert!(rules.resolve("gemini-3-pro-preview-x").is_none());
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(MappingRules::compile(&[rule("re:(", "x")]).is_err());
+        assert!(MappingRules::compile(&[rule("gpt-*", " ")]).is_err());
+
+        let lenient = MappingRules::from_config(&[rule("re:(", "x"), rule("gpt-*", "y")]);
+        assert_eq!(lenient.resolve("gpt-5").map(|(_, t)| t).as_deref(), Some("y"));
+    }
+
+    #[test]
+    fn weighted_targets_split_by_roll() {
+        let rules = MappingRules::compile(&[weighted(
+            "claude-sonnet*",
+            &[("gemini-3-pro-high", 90), ("claude-sonnet-4-5", 10)],
+        )])
+        .unwrap();
+        let pick = |roll: u32| {
+            rules
+                .resolve_with("claude-sonnet-4", |total| {
+                    assert_eq!(total, 100);
+                    roll
+                })
+                .map(|(_, t)| t)
+        };
+        assert_eq!(pick(0).as_deref(), Some("gemini-3-pro-high"));
+        assert_eq!(pick(89).as_deref(), Some("gemini-3-pro-high"));
+        assert_eq!(pick(90).as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(pick(99).as_deref(), Some("claude-sonnet-4-5"));
+    }
+
+    #[test]
+    fn weighted_targets_validation() {
+        assert!(MappingRules::compile(&[weighted("a*", &[("x", 0), ("y", 0)])]).is_err());
+        assert!(MappingRules::compile(&[weighted("a*", &[(" ", 1), ("y", 1)])]).is_err());
+        // 权重为 0 的目标不参与抽取
+        let rules = MappingRules::compile(&[weighted("a*", &[("x", 0), ("y", 1)])]).unwrap();
+        assert_eq!(rules.resolve("abc").map(|(_, t)| t).as_deref(), Some("y"));
+    }
+
+    #[tokio::test]
+    async fn weighted_choice_is_sticky_within_request() {
+        let rules = MappingRules::compile(&[weighted(
+            r"re:^claude-(\w+)$",
+            &[("gemini-$1-a", 1), ("gemini-$1-b", 1)],
+        )])
+        .unwrap();
+        let ((first, second), recorded) = track_canary(async {
+            let first = rules.resolve_with("claude-x", |_| 0).map(|(_, t)| t);
+            let second = rules.resolve_with("claude-x", |_| 1).map(|(_, t)| t);
+            (first, second)
+        })
+        .await;
+        assert_eq!(first.as_deref(), Some("gemini-x-a"));
+        assert_eq!(second, first);
+        assert_eq!(recorded.as_deref(), Some("gemini-x-a"));
+
+        // 单一目标的规则不记录
+        let plain = MappingRules::compile(&[rule("gpt-*", "y")]).unwrap();
+        let (_, recorded) = track_canary(async { plain.resolve("gpt-5") }).await;
+        assert!(recorded.is_none());
+    }
+}
@@ -68,12 +68,24 @@ pub fn map_claude_model_to_gemini(input: &str) -> String {
     "claude-sonnet-4-5".to_string()
 }
 
+/// 内置映射表中的目标模型 (不含前缀透传与兜底)
+pub fn builtin_target(input: &str) -> Option<&'static str> {
+    CLAUDE_TO_GEMINI.get(input).copied()
+}
+
 /// 获取所有内置支持的模型列表关键字
 pub fn get_supported_models() -> Vec<String> {
     CLAUDE_TO_GEMINI.keys().map(|s| s.to_string()).collect()
 }
 
 /// 动态获取所有可用模型列表 (包含内置与用户自定义)
+/// 判断映射目标是否为已知的上游模型 (内置映射表的键/值，或图片生成模型变体)
+pub fn is_known_upstream_model(model: &str) -> bool {
+    model.starts_with("gemini-3-pro-image")
+        || CLAUDE_TO_GEMINI.contains_key(model)
+        || CLAUDE_TO_GEMINI.values().any(|v| *v == model)
+}
+
 pub async fn get_all_dynamic_models(
     openai_mapping: &tokio::sync::RwLock<std::collections::HashMap<String, String>>,
     custom_mapping: &tokio::sync::RwLock<std::collections::HashMap<String, String>>,
@@ -146,7 +158,7 @@ pub async fn get_all_dynamic_models(
 }
 
 /// 核心模型路由解析引擎
-/// 优先级：Custom Mapping (精确) > Group Mapping (家族) > System Mapping (内置插件)
+/// 优先级：Custom Mapping (精确) > Mapping Rules (通配/正则) > Group Mapping (家族) > System Mapping (内置插件)
 /// 
 /// # 参数
 /// - `apply_claude_family_mapping`: 是否对 Claude 模型应用家族映射
@@ -155,6 +167,7 @@ pub async fn get_all_dynamic_models(
 pub fn resolve_model_route(
     original_model: &str,
     custom_mapping: &std::collections::HashMap<String, String>,
+    mapping_rules: &super::mapping_rules::MappingRules,
     openai_mapping: &std::collections::HashMap<String, String>,
     anthropic_mapping: &std::collections::HashMap<String, String>,
     apply_claude_family_mapping: bool,
@@ -165,6 +178,12 @@ pub fn resolve_model_route(
         return target.clone();
     }
 
+    // 1b. 按顺序检查通配/正则映射规则
+    if let Some((pattern, target)) = mapping_rules.resolve(original_model) {
+        crate::modules::logger::log_info(&format!("[Router] 使用映射规则 {}: {} -> {}", pattern, original_model, target));
+        return target;
+    }
+
     let lower_model = original_model.to_lowercase();
 
     // 2. 检查家族分组映射 (OpenAI 系)
@@ -267,4 +286,30 @@ mod tests {
             "claude-sonnet-4-5"
         );
     }
+
+    #[test]
+    fn test_mapping_rules_precedence() {
+        use crate::proxy::config::ModelMappingRule;
+        let rules = super::super::mapping_rules::MappingRules::compile(&[ModelMappingRule {
+            pattern: "gpt-4*".to_string(),
+            target: "gemini-3-pro-high".to_string(),
+            targets: Vec::new(),
+        }])
+        .unwrap();
+        let mut custom = HashMap::new();
+        custom.insert("gpt-4o".to_string(), "gemini-2.5-flash".to_string());
+        let mut openai = HashMap::new();
+        openai.insert("gpt-4o-series".to_string(), "gemini-2.5-pro".to_string());
+        let anthropic = HashMap::new();
+
+        // 精确映射优先于规则，规则优先于家族映射
+        assert_eq!(
+            resolve_model_route("gpt-4o", &custom, &rules, &openai, &anthropic, false),
+            "gemini-2.5-flash"
+        );
+        assert_eq!(
+            resolve_model_route("gpt-4o-mini", &custom, &rules, &openai, &anthropic, false),
+            "gemini-3-pro-high"
+        );
+    }
 }
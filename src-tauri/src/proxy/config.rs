@@ -136,56 +136,1387 @@ pub struct ProxyConfig {
     #[serde(default)]
     pub auth_mode: ProxyAuthMode,
     
+    /// 额外监听地址 (主监听地址仍由 `port` / `allow_lan_access` 决定)
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+
+    /// 独立的管理监听地址，如 `127.0.0.1:8046`；设置后管理 / 统计 / 调试接口与仪表盘仅在该地址提供，
+    /// 未设置时这些接口仍随对话接口监听，但只接受本机 (回环地址) 客户端访问
+    #[serde(default)]
+    pub admin_listen: Option<String>,
+
+    /// 是否监听主 TCP 端口；关闭后仅使用 `listeners` (如仅 Unix 套接字)
+    #[serde(default = "default_listen_tcp")]
+    pub listen_tcp: bool,
+
     /// 监听端口
     pub port: u16,
     
-    /// API 密钥
+    /// API 密钥 (明文，或开启 `hash_api_keys` 后保存的 argon2 哈希)
     pub api_key: String,
+
+    /// 保存配置时将 API Key 哈希存储 (明文仅在生成时显示一次)
+    #[serde(default)]
+    pub hash_api_keys: bool,
+
+    /// 轮换后仍在宽限期内有效的旧 API Key
+    #[serde(default)]
+    pub previous_api_keys: Vec<RetiredApiKey>,
+
+    /// 附加的 API Key，各自限定可访问的接口范围 (主密钥始终拥有全部权限)
+    #[serde(default)]
+    pub api_keys: Vec<ScopedApiKey>,
+
+    /// Claude 接口接受的 `anthropic-version` 取值 (为空时不校验)
+    #[serde(default = "default_anthropic_versions")]
+    pub anthropic_versions: Vec<String>,
+
+    /// API Key 轮换配置 (宽限期、通知 webhook)
+    #[serde(default)]
+    pub key_rotation: KeyRotationConfig,
     
 
-    /// 是否自动启动
-    pub auto_start: bool,
+    /// 是否自动启动
+    pub auto_start: bool,
+
+    /// Anthropic 模型映射表 (key: Claude模型名, value: Gemini模型名)
+    #[serde(default)]
+    pub anthropic_mapping: std::collections::HashMap<String, String>,
+
+    /// OpenAI 模型映射表 (key: OpenAI模型组, value: Gemini模型名)
+    #[serde(default)]
+    pub openai_mapping: std::collections::HashMap<String, String>,
+
+    /// 自定义精确模型映射表 (key: 原始模型名, value: 目标模型名)
+    #[serde(default)]
+    pub custom_mapping: std::collections::HashMap<String, String>,
+
+    /// 模型映射规则 (通配符 / `re:` 正则)，按顺序匹配，优先级低于自定义精确映射
+    #[serde(default)]
+    pub mapping_rules: Vec<ModelMappingRule>,
+
+    /// API 请求超时时间(秒)
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: u64,
+
+    /// 连接超时、流式空闲超时及按路由的超时覆盖
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+
+    /// 是否开启请求日志记录 (监控)
+    #[serde(default)]
+    pub enable_logging: bool,
+
+    /// 是否开启调试端点 (`/debug/*`)；开启后即使鉴权模式为 off 也要求 API Key
+    #[serde(default)]
+    pub enable_debug_endpoints: bool,
+
+    /// 允许客户端通过 `X-Antigravity-Account` 请求头固定使用某个账号 (ID / 邮箱 / 标签)；
+    /// 带权限范围的附加密钥还需具有 `pin-account` 权限
+    #[serde(default)]
+    pub allow_account_pinning: bool,
+
+    /// 上游代理配置
+    #[serde(default)]
+    pub upstream_proxy: UpstreamProxyConfig,
+
+    /// 上游连接池配置
+    #[serde(default)]
+    pub upstream_pool: UpstreamPoolConfig,
+
+    /// 上游请求速率平滑 (全局与按主机的令牌桶，突发请求排队而非拒绝)
+    #[serde(default)]
+    pub upstream_rate_limit: UpstreamRateLimitConfig,
+
+    /// 按客户端 IP 的限流配置
+    #[serde(default)]
+    pub client_rate_limit: ClientRateLimitConfig,
+
+    /// 受信任的反向代理 (地址或 CIDR)：仅当连接对端在此列表中时才采用 X-Forwarded-For / X-Real-IP 中的客户端地址
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+
+    /// 鉴权失败的暴力破解防护 (按客户端 IP 延迟与临时封禁)
+    #[serde(default)]
+    pub auth_lockout: AuthLockoutConfig,
+
+    /// Anthropic 批量请求 (`/v1/messages/batches`) 配置
+    #[serde(default)]
+    pub batches: BatchConfig,
+
+    /// 请求优先级与过载卸载配置
+    #[serde(default)]
+    pub load_shedding: LoadSheddingConfig,
+
+    /// 账号配额保留阈值 (低于阈值的账号退出轮换，为 IDE 保留余量)
+    #[serde(default)]
+    pub quota_thresholds: QuotaThresholdConfig,
+
+    /// 单账号用量上限 (与上游配额无关，用于均摊负载)
+    #[serde(default)]
+    pub usage_caps: UsageCapConfig,
+
+    /// 按 OpenAI-Organization / OpenAI-Project 请求头将团队路由到指定账号组
+    #[serde(default)]
+    pub team_routing: TeamRoutingConfig,
+
+    /// 按订阅等级预留账号: 指定等级的账号仅服务于匹配的模型或密钥
+    #[serde(default)]
+    pub tier_policy: TierPolicyConfig,
+
+    /// 按时段路由: 按星期与时段限定可用的账号 (如工作日白天只用免费账号，夜间开放 Pro 账号)
+    #[serde(default)]
+    pub schedule_routing: ScheduleRoutingConfig,
+
+    /// 上游错误重试策略
+    #[serde(default)]
+    pub retry_policy: RetryPolicyConfig,
+
+    /// 响应头策略: 透传的上游响应头与反代附加的信息头
+    #[serde(default)]
+    pub response_headers: ResponseHeaderConfig,
+
+    /// 配额缓存: 调度只使用缓存的配额，过期后在后台重新拉取
+    #[serde(default)]
+    pub quota_cache: QuotaCacheConfig,
+
+    /// 多实例协同: 与对等实例同步账号冷却与用量计数
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+
+    /// 模型能力 (上下文长度、视觉、工具、思考)：超出限制的请求直接拒绝
+    #[serde(default)]
+    pub model_capabilities: ModelCapabilityConfig,
+
+    /// 转换后的请求超出目标模型上下文窗口时的处理策略
+    #[serde(default)]
+    pub context_overflow: ContextOverflowConfig,
+
+    /// 模型列表 (/v1/models 等) 的展示策略
+    #[serde(default)]
+    pub model_list: ModelListConfig,
+
+    /// 发往上游的客户端标识 (User-Agent、客户端版本等请求头) 档案
+    #[serde(default)]
+    pub client_profiles: ClientProfileConfig,
+
+    /// 流量镜像: 按比例将请求复制到备用映射 / 账号组并记录响应差异 (不返回给客户端)
+    #[serde(default)]
+    pub mirror: MirrorConfig,
+
+    /// 响应压缩: 按客户端的 Accept-Encoding 对 JSON (及可选的 SSE) 响应进行 gzip / br 压缩
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// 请求校验: 转发上游之前检查请求格式，返回指明字段的 400 错误
+    #[serde(default)]
+    pub request_validation: RequestValidationConfig,
+
+    /// 采样参数策略: 转发前按密钥 / 模型限制或覆盖 temperature、top_p 与最大输出 token 数
+    #[serde(default)]
+    pub param_policy: ParamPolicyConfig,
+
+    /// 流式响应中断续传: 上游流中途断开时换号续写，客户端看到的是一条连续的流
+    #[serde(default)]
+    pub stream_resumption: StreamResumptionConfig,
+
+    /// 模型单价表 (key: 模型名，结尾 `*` 表示前缀匹配)，用于在用量报告中估算等值费用
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPrice>,
+
+    /// 请求日志异常分析 (`--logs-summary`) 的判定阈值
+    #[serde(default)]
+    pub log_anomalies: LogAnomalyConfig,
+
+    /// 请求日志中完整请求/响应体的采样规则 (元数据、状态码与用量始终记录)
+    #[serde(default)]
+    pub body_capture: BodyCaptureConfig,
+
+    /// 启动时预热账号 (刷新 token 并验证可用性)，失效账号不参与轮换
+    #[serde(default)]
+    pub warmup_on_start: bool,
+
+    /// 启动时的模型映射检查发现问题 (目标为空、未知模型或没有账号可用) 时拒绝启动，而不仅是告警
+    #[serde(default)]
+    pub strict_mappings: bool,
+
+    /// gRPC 管理接口 (需以 `grpc` 特性编译)
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+
+    /// 403 Forbidden 账号的自动恢复探测
+    #[serde(default)]
+    pub account_recovery: AccountRecoveryConfig,
+
+    /// z.ai provider configuration (Anthropic-compatible).
+    #[serde(default)]
+    pub zai: ZaiConfig,
+    
+    /// 账号调度配置 (粘性会话/限流重试)
+    #[serde(default)]
+    pub scheduling: crate::proxy::sticky_config::StickySessionConfig,
+}
+
+/// 模型映射规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelMappingRule {
+    /// 匹配模式: 通配符 (`*` / `?`，不区分大小写)，或以 `re:` 开头的正则
+    pub pattern: String,
+    /// 目标模型；正则规则可使用 `$1` 等捕获组
+    #[serde(default)]
+    pub target: String,
+    /// 按权重分流的多个目标 (金丝雀对比)，非空时替代 `target`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<WeightedTarget>,
+}
+
+/// 映射规则的加权目标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedTarget {
+    /// 目标模型；正则规则可使用 `$1` 等捕获组
+    pub model: String,
+    /// 相对权重，如 90 / 10
+    pub weight: u32,
+}
+
+/// 已轮换下线、宽限期内仍可使用的 API Key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetiredApiKey {
+    /// 明文或 argon2 哈希
+    pub key: String,
+    /// 失效时间 (Unix 秒)
+    pub expires_at: i64,
+}
+
+/// API Key 权限范围；同时设置多个时，`chat-only` / `read-only-stats` 允许的接口取并集，`no-*` 一律排除
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyScope {
+    /// 仅对话 / 补全接口 (含模型列表与 Token 计数)
+    ChatOnly,
+    /// 禁止 Embedding 接口
+    NoEmbeddings,
+    /// 禁止 `/admin/*` 与 `/debug/*`
+    NoAdmin,
+    /// 仅 GET 统计类管理接口 (状态、延迟、用量、配额缓存) 与健康检查
+    ReadOnlyStats,
+    /// 允许通过 `X-Antigravity-Account` 请求头固定账号 (需开启 `allow_account_pinning`)
+    PinAccount,
+}
+
+impl KeyScope {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "chat-only" => Some(Self::ChatOnly),
+            "no-embeddings" => Some(Self::NoEmbeddings),
+            "no-admin" => Some(Self::NoAdmin),
+            "read-only-stats" => Some(Self::ReadOnlyStats),
+            "pin-account" => Some(Self::PinAccount),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ChatOnly => "chat-only",
+            Self::NoEmbeddings => "no-embeddings",
+            Self::NoAdmin => "no-admin",
+            Self::ReadOnlyStats => "read-only-stats",
+            Self::PinAccount => "pin-account",
+        }
+    }
+}
+
+/// 带权限范围的附加 API Key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopedApiKey {
+    /// 便于识别的名称 (如 `dashboard`)
+    pub name: String,
+    /// 明文或 argon2 哈希
+    pub key: String,
+    /// 为空表示不限制
+    #[serde(default)]
+    pub scopes: Vec<KeyScope>,
+    /// 创建时间 (Unix 秒)
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// API Key 轮换配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotationConfig {
+    /// 旧密钥的宽限期 (秒)
+    #[serde(default = "default_key_grace_period")]
+    pub grace_period_secs: u64,
+    /// 轮换后通知的 webhook 地址 (为空则不通知)
+    #[serde(default)]
+    pub webhook_url: String,
+}
+
+impl Default for KeyRotationConfig {
+    fn default() -> Self {
+        Self {
+            grace_period_secs: default_key_grace_period(),
+            webhook_url: String::new(),
+        }
+    }
+}
+
+fn default_key_grace_period() -> u64 {
+    24 * 3600
+}
+
+/// gRPC 管理接口配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 监听地址，鉴权使用反代 API Key (`authorization: Bearer <key>`)
+    #[serde(default = "default_grpc_bind")]
+    pub bind: String,
+    /// 启用 TLS (监听非回环地址时建议配置，否则 API Key 以明文传输)
+    #[serde(default)]
+    pub tls: Option<ListenerTlsConfig>,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: default_grpc_bind(),
+            tls: None,
+        }
+    }
+}
+
+fn default_grpc_bind() -> String {
+    "127.0.0.1:50051".to_string()
+}
+
+/// Forbidden 账号自动恢复配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRecoveryConfig {
+    /// 是否定期重新检查被标记为 Forbidden 的账号
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 检查间隔 (秒)
+    #[serde(default = "default_recovery_interval")]
+    pub interval_secs: u64,
+}
+
+impl Default for AccountRecoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: default_recovery_interval(),
+        }
+    }
+}
+
+fn default_recovery_interval() -> u64 {
+    6 * 3600
+}
+
+/// 额外监听地址配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerConfig {
+    /// 监听地址，如 `0.0.0.0:8443`，或 Unix 套接字 `unix:/run/antigravity.sock`
+    pub bind: String,
+    /// 启用 TLS (PEM 证书与私钥)
+    #[serde(default)]
+    pub tls: Option<ListenerTlsConfig>,
+    /// 覆盖全局鉴权模式；`auto` 按监听地址是否为回环地址判断
+    #[serde(default)]
+    pub auth_mode: Option<ProxyAuthMode>,
+    /// 覆盖全局 API Key
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Unix 套接字文件权限 (八进制字符串，默认 `600`)
+    #[serde(default)]
+    pub socket_mode: Option<String>,
+    /// 通过 ACME 自动申请并续期证书 (设置后忽略 `tls`，需以 acme 特性编译)
+    #[serde(default)]
+    pub acme: Option<AcmeConfig>,
+}
+
+impl ListenerConfig {
+    /// Unix 套接字路径 (`unix:` 前缀)
+    pub fn unix_path(&self) -> Option<&str> {
+        self.bind.strip_prefix("unix:")
+    }
+
+    /// 是否对局域网开放 (非回环地址；Unix 套接字仅本机可用)
+    pub fn is_lan(&self) -> bool {
+        if self.unix_path().is_some() {
+            return false;
+        }
+        match self.bind.parse::<std::net::SocketAddr>() {
+            Ok(addr) => !addr.ip().is_loopback(),
+            Err(_) => !self.bind.starts_with("localhost:"),
+        }
+    }
+}
+
+/// 监听器 TLS 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerTlsConfig {
+    /// PEM 证书链路径
+    pub cert_path: String,
+    /// PEM 私钥路径 (PKCS#8 / PKCS#1 / SEC1)
+    pub key_path: String,
+}
+
+/// ACME 验证方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AcmeChallenge {
+    /// 在 `http_bind` (通常为 80 端口) 临时提供 /.well-known/acme-challenge/
+    #[default]
+    #[serde(rename = "http-01")]
+    Http01,
+    /// 在监听器自身的 TLS 端口 (须为 443) 上完成验证
+    #[serde(rename = "tls-alpn-01")]
+    TlsAlpn01,
+}
+
+/// 监听器 ACME 证书配置 (证书保存在数据目录 `acme/` 下)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcmeConfig {
+    /// 证书包含的域名 (第一个作为存储目录名)
+    pub domains: Vec<String>,
+    /// 账户联系邮箱 (可选)
+    #[serde(default)]
+    pub email: Option<String>,
+    /// ACME 目录地址，默认 Let's Encrypt 正式环境
+    #[serde(default = "default_acme_directory")]
+    pub directory_url: String,
+    #[serde(default)]
+    pub challenge: AcmeChallenge,
+    /// HTTP-01 验证服务监听地址
+    #[serde(default = "default_acme_http_bind")]
+    pub http_bind: String,
+}
+
+fn default_acme_directory() -> String {
+    "https://acme-v02.api.letsencrypt.org/directory".to_string()
+}
+
+fn default_acme_http_bind() -> String {
+    "0.0.0.0:80".to_string()
+}
+
+/// 上游代理配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UpstreamProxyConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 代理地址 (http://, https://, socks5://)
+    pub url: String,
+}
+
+/// 单个路由的超时覆盖 (未设置的项沿用全局值)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteTimeoutOverride {
+    /// 连接超时(秒)
+    #[serde(default)]
+    pub connect_timeout: Option<u64>,
+    /// 非流式请求总超时(秒)
+    #[serde(default)]
+    pub request_timeout: Option<u64>,
+    /// 流式响应空闲超时(秒)，0 表示关闭
+    #[serde(default)]
+    pub stream_idle_timeout: Option<u64>,
+    /// 流式响应心跳间隔(秒)，0 表示关闭
+    #[serde(default)]
+    pub stream_heartbeat_interval: Option<u64>,
+}
+
+/// 上游超时配置 (非流式总超时沿用 `ProxyConfig::request_timeout`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeoutConfig {
+    /// 连接超时(秒)
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u64,
+    /// 流式响应空闲超时(秒)：超过该时间未收到任何数据块则中断，0 表示关闭
+    #[serde(default = "default_stream_idle_timeout")]
+    pub stream_idle_timeout: u64,
+    /// 流式响应心跳间隔(秒)：上游长时间无输出 (如长时间思考) 时向客户端发送保活事件，
+    /// 避免中间设备或客户端因空闲断开连接；0 表示关闭
+    #[serde(default = "default_stream_heartbeat_interval")]
+    pub stream_heartbeat_interval: u64,
+    /// 按路由前缀覆盖 (key 例如 `/v1/messages`、`/v1beta/models`)，按最长前缀匹配
+    #[serde(default)]
+    pub routes: HashMap<String, RouteTimeoutOverride>,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: default_connect_timeout(),
+            stream_idle_timeout: default_stream_idle_timeout(),
+            stream_heartbeat_interval: default_stream_heartbeat_interval(),
+            routes: HashMap::new(),
+        }
+    }
+}
+
+/// 按客户端 IP 的令牌桶限流配置 (防止局域网内失控脚本压垮反代)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientRateLimitConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每秒补充的令牌数 (持续请求速率)
+    #[serde(default = "default_rate_limit_rps")]
+    pub requests_per_second: f64,
+    /// 令牌桶容量 (允许的突发请求数)
+    #[serde(default = "default_rate_limit_burst")]
+    pub burst: u32,
+}
+
+impl Default for ClientRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_second: default_rate_limit_rps(),
+            burst: default_rate_limit_burst(),
+        }
+    }
+}
+
+/// 流式响应中断续传配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamResumptionConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 单个请求最多续传次数
+    #[serde(default = "default_resume_attempts")]
+    pub max_attempts: u32,
+    /// 续写指令 (作为用户消息附在已生成内容之后)
+    #[serde(default = "default_resume_prompt")]
+    pub continue_prompt: String,
+}
+
+impl Default for StreamResumptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: default_resume_attempts(),
+            continue_prompt: default_resume_prompt(),
+        }
+    }
+}
+
+/// 上游请求令牌桶参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpstreamBucketConfig {
+    /// 每秒补充的令牌数 (持续 QPS)
+    pub qps: f64,
+    /// 令牌桶容量 (允许的突发请求数)
+    pub burst: u32,
+}
+
+/// 上游速率平滑配置：多个客户端同时涌入时按令牌桶排队发往上游，避免触发上游的异常流量检测
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpstreamRateLimitConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 所有上游请求共享的全局桶
+    #[serde(default = "default_upstream_global_bucket")]
+    pub global: UpstreamBucketConfig,
+    /// 按主机的桶 (key: 上游主机名，如 `cloudcode-pa.googleapis.com`)，未列出的主机只受全局桶限制
+    #[serde(default)]
+    pub per_host: std::collections::HashMap<String, UpstreamBucketConfig>,
+    /// 单个请求最长排队时间(秒)，超过后直接返回错误，0 表示不限
+    #[serde(default = "default_upstream_max_wait")]
+    pub max_wait_secs: u64,
+}
+
+impl Default for UpstreamRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            global: default_upstream_global_bucket(),
+            per_host: std::collections::HashMap::new(),
+            max_wait_secs: default_upstream_max_wait(),
+        }
+    }
+}
+
+/// 鉴权失败锁定配置：连续失败时逐次加长响应延迟，窗口内失败达到上限后临时封禁该 IP (本机地址不计入)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthLockoutConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 窗口内允许的失败次数，达到后封禁
+    #[serde(default = "default_lockout_max_failures")]
+    pub max_failures: u32,
+    /// 失败计数窗口 (秒)
+    #[serde(default = "default_lockout_window_secs")]
+    pub window_secs: u64,
+    /// 首次封禁时长 (秒)，同一 IP 每次再被封禁时翻倍 (最长 24 小时)
+    #[serde(default = "default_lockout_ban_secs")]
+    pub ban_secs: u64,
+    /// 首次失败的响应延迟 (毫秒)，之后每次失败翻倍
+    #[serde(default = "default_lockout_base_delay_ms")]
+    pub base_delay_ms: u64,
+    /// 响应延迟上限 (毫秒)
+    #[serde(default = "default_lockout_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// 封禁时通知的 webhook 地址 (为空则仅记录日志)
+    #[serde(default)]
+    pub webhook_url: String,
+}
+
+fn default_lockout_max_failures() -> u32 {
+    10
+}
+
+fn default_lockout_window_secs() -> u64 {
+    600
+}
+
+fn default_lockout_ban_secs() -> u64 {
+    900
+}
+
+fn default_lockout_base_delay_ms() -> u64 {
+    200
+}
+
+fn default_lockout_max_delay_ms() -> u64 {
+    5000
+}
+
+impl Default for AuthLockoutConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_failures: default_lockout_max_failures(),
+            window_secs: default_lockout_window_secs(),
+            ban_secs: default_lockout_ban_secs(),
+            base_delay_ms: default_lockout_base_delay_ms(),
+            max_delay_ms: default_lockout_max_delay_ms(),
+            webhook_url: String::new(),
+        }
+    }
+}
+
+/// 请求优先级 (过载时高优先级请求先获得执行机会，低优先级请求先被卸载)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestPriority {
+    /// 批处理 / 后台任务
+    Low,
+    #[default]
+    Normal,
+    /// 交互式请求
+    High,
+}
+
+/// 过载卸载配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadSheddingConfig {
+    /// 是否启用 (关闭时不限制并发)
+    #[serde(default)]
+    pub enabled: bool,
+    /// 同时执行的最大请求数，超出后进入优先级队列
+    #[serde(default = "default_shedding_max_concurrent")]
+    pub max_concurrent: usize,
+    /// 队列最大长度，队列满时卸载优先级最低的请求
+    #[serde(default = "default_shedding_max_queue")]
+    pub max_queue: usize,
+    /// 排队最长等待时间(秒)，超时返回 503
+    #[serde(default = "default_shedding_queue_timeout")]
+    pub queue_timeout: u64,
+    /// 503 响应中的 Retry-After(秒)
+    #[serde(default = "default_shedding_retry_after")]
+    pub retry_after: u64,
+    /// 未指定优先级时的默认值
+    #[serde(default)]
+    pub default_priority: RequestPriority,
+    /// 按 API Key 指定优先级，作为该 Key 的上限 (请求头 `X-Request-Priority` 只能调低)
+    #[serde(default)]
+    pub key_priorities: HashMap<String, RequestPriority>,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: default_shedding_max_concurrent(),
+            max_queue: default_shedding_max_queue(),
+            queue_timeout: default_shedding_queue_timeout(),
+            retry_after: default_shedding_retry_after(),
+            default_priority: RequestPriority::default(),
+            key_priorities: HashMap::new(),
+        }
+    }
+}
+
+/// 单条配额保留规则
+///
+/// 上游配额接口只返回剩余比例，因此阈值以百分比表示。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaThresholdRule {
+    /// 模型名，支持 `*` 通配 (如 `claude-*`)
+    pub model: String,
+    /// 剩余配额低于该百分比时，账号在配额重置前不再参与该模型的轮换
+    pub min_percentage: i32,
+}
+
+/// 账号配额保留阈值配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaThresholdConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 按顺序匹配，第一条命中的规则生效
+    #[serde(default)]
+    pub rules: Vec<QuotaThresholdRule>,
+}
+
+/// 单账号用量上限配置 (按固定时间窗口计数，窗口按 UTC 对齐)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageCapConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 统计窗口 (秒)，默认 1 天
+    #[serde(default = "default_usage_window")]
+    pub window_secs: u64,
+    /// 每个账号在窗口内的最大请求数
+    #[serde(default)]
+    pub max_requests: Option<u64>,
+    /// 每个账号在窗口内的最大 Token 数 (输入 + 输出)
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// 按账号 (邮箱或 ID) 覆盖上限
+    #[serde(default)]
+    pub accounts: HashMap<String, AccountUsageCap>,
+}
+
+impl Default for UsageCapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: default_usage_window(),
+            max_requests: None,
+            max_tokens: None,
+            accounts: HashMap::new(),
+        }
+    }
+}
+
+/// 团队路由配置: 按请求头中的组织 / 项目限定可用账号
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamRoutingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 按顺序匹配，命中第一条规则
+    #[serde(default)]
+    pub rules: Vec<TeamRoute>,
+    /// 携带组织 / 项目头但未命中任何规则时拒绝请求 (否则使用整个账号池)
+    #[serde(default)]
+    pub reject_unmatched: bool,
+}
+
+/// 单条团队路由规则: 指定的字段都匹配时生效 (未指定的字段不参与匹配)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamRoute {
+    /// `OpenAI-Organization` 取值
+    #[serde(default)]
+    pub organization: Option<String>,
+    /// `OpenAI-Project` 取值
+    #[serde(default)]
+    pub project: Option<String>,
+    /// 该团队可用的账号 (ID 或邮箱)
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+/// 订阅等级策略配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierPolicyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub reservations: Vec<TierReservation>,
+}
+
+/// 等级预留: 该等级的账号只处理匹配 `models` 或 `keys` 的请求
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierReservation {
+    pub tier: crate::models::SubscriptionTier,
+    /// 模型名，支持 `前缀*` 通配
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// API 密钥指纹 (与用量报告中的 key 一致)
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+/// 按时段路由配置 (按顺序匹配，第一条命中当前时段与模型的规则生效；没有命中的规则时不限制)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRoutingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// IANA 时区名，如 `Asia/Shanghai`；为空使用系统本地时区
+    #[serde(default)]
+    pub timezone: String,
+    #[serde(default)]
+    pub rules: Vec<ScheduleRule>,
+}
+
+/// 时段规则: 生效期间只使用匹配 `tiers` 或 `accounts` 的账号
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    /// 便于识别的名称 (用于日志与错误信息)
+    #[serde(default)]
+    pub name: String,
+    /// 生效的星期: `mon` ... `sun`，或 `weekdays` / `weekends`；为空表示每天
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// 生效时段 `HH:MM-HH:MM` (不含结束时刻，可跨午夜，跨午夜部分仍按开始当天的星期判断)；为空表示全天
+    #[serde(default)]
+    pub hours: Option<String>,
+    /// 仅对这些模型生效 (`*` 通配)；为空表示全部
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 允许使用的订阅等级
+    #[serde(default)]
+    pub tiers: Vec<crate::models::SubscriptionTier>,
+    /// 允许使用的账号 (ID / 邮箱 / 标签)
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+/// 多实例协同配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 本实例 ID (留空则每次启动随机生成)
+    #[serde(default)]
+    pub instance_id: String,
+    /// 对等实例地址，如 `http://10.0.0.2:8045`
+    #[serde(default)]
+    pub peers: Vec<String>,
+    /// 集群共享密钥: 广播时携带，接收对等实例的事件时校验 (未配置则不启动集群同步)
+    #[serde(default)]
+    pub api_key: String,
+    /// 用量计数的广播间隔 (秒)；冷却事件立即广播
+    #[serde(default = "default_cluster_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+fn default_cluster_sync_interval_secs() -> u64 {
+    5
+}
 
-    /// Anthropic 模型映射表 (key: Claude模型名, value: Gemini模型名)
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            instance_id: String::new(),
+            peers: Vec::new(),
+            api_key: String::new(),
+            sync_interval_secs: default_cluster_sync_interval_secs(),
+        }
+    }
+}
+
+/// 配额缓存配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaCacheConfig {
+    /// 配额快照的最长可用时间 (秒)，超过后视为过期
+    #[serde(default = "default_quota_max_staleness_secs")]
+    pub max_staleness_secs: u64,
+    /// 是否在后台重新拉取过期的配额 (不阻塞请求)
+    #[serde(default = "default_true")]
+    pub revalidate: bool,
+}
+
+fn default_quota_max_staleness_secs() -> u64 {
+    1800
+}
+
+impl Default for QuotaCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_staleness_secs: default_quota_max_staleness_secs(),
+            revalidate: true,
+        }
+    }
+}
+
+/// 模型能力配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilityConfig {
+    /// 拒绝超出模型限制的请求 (上下文长度、输出长度、图片输入、工具调用)
+    #[serde(default = "default_true")]
+    pub enforce: bool,
+    /// 覆盖内置目录与上游报告的能力 (key: 上游模型名，结尾 `*` 表示前缀匹配)
     #[serde(default)]
-    pub anthropic_mapping: std::collections::HashMap<String, String>,
+    pub overrides: HashMap<String, ModelCapabilityOverride>,
+}
 
-    /// OpenAI 模型映射表 (key: OpenAI模型组, value: Gemini模型名)
+impl Default for ModelCapabilityConfig {
+    fn default() -> Self {
+        Self {
+            enforce: true,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+/// 单个模型的能力覆盖，未设置的字段沿用内置目录 / 上游报告
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilityOverride {
     #[serde(default)]
-    pub openai_mapping: std::collections::HashMap<String, String>,
+    pub max_context_tokens: Option<u64>,
+    #[serde(default)]
+    pub max_output_tokens: Option<u64>,
+    #[serde(default)]
+    pub vision: Option<bool>,
+    #[serde(default)]
+    pub tools: Option<bool>,
+    #[serde(default)]
+    pub thinking: Option<bool>,
+}
 
-    /// 自定义精确模型映射表 (key: 原始模型名, value: 目标模型名)
+/// 上下文超限处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextOverflowStrategy {
+    /// 直接返回 400 (并说明超出多少)
+    #[default]
+    Reject,
+    /// 丢弃最早的消息直到放得下
+    Truncate,
+    /// 将被丢弃的消息摘要后作为前情提要，摘要失败时退化为截断
+    Summarize,
+}
+
+/// 上下文超限处理配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextOverflowConfig {
     #[serde(default)]
-    pub custom_mapping: std::collections::HashMap<String, String>,
+    pub strategy: ContextOverflowStrategy,
+    /// 截断 / 摘要时至少保留的最近消息数
+    #[serde(default = "default_keep_recent_messages")]
+    pub keep_recent_messages: usize,
+    /// 生成摘要使用的模型
+    #[serde(default = "default_summary_model")]
+    pub summary_model: String,
+}
 
-    /// API 请求超时时间(秒)
-    #[serde(default = "default_request_timeout")]
-    pub request_timeout: u64,
+fn default_keep_recent_messages() -> usize {
+    4
+}
 
-    /// 是否开启请求日志记录 (监控)
+fn default_summary_model() -> String {
+    "gemini-2.5-flash".to_string()
+}
+
+impl Default for ContextOverflowConfig {
+    fn default() -> Self {
+        Self {
+            strategy: ContextOverflowStrategy::Reject,
+            keep_recent_messages: default_keep_recent_messages(),
+            summary_model: default_summary_model(),
+        }
+    }
+}
+
+/// 模型列表展示策略
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelListConfig {
+    /// 仅列出账号池中仍有剩余配额的模型 (无配额数据的模型照常列出)
     #[serde(default)]
-    pub enable_logging: bool,
+    pub quota_aware: bool,
+}
 
-    /// 上游代理配置
+/// 上游客户端标识档案配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientProfileConfig {
+    /// 全局默认档案名称 (账号可单独指定)
+    #[serde(default = "default_client_profile")]
+    pub active: String,
+    /// 自定义档案；与内置档案同名时覆盖内置档案
     #[serde(default)]
-    pub upstream_proxy: UpstreamProxyConfig,
+    pub profiles: Vec<ClientProfile>,
+}
 
-    /// z.ai provider configuration (Anthropic-compatible).
+/// 单个客户端标识档案
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientProfile {
+    pub name: String,
+    pub user_agent: String,
+    /// 额外请求头 (如客户端版本)
     #[serde(default)]
-    pub zai: ZaiConfig,
-    
-    /// 账号调度配置 (粘性会话/限流重试)
+    pub headers: HashMap<String, String>,
+}
+
+fn default_client_profile() -> String {
+    "antigravity-windows".to_string()
+}
+
+impl Default for ClientProfileConfig {
+    fn default() -> Self {
+        Self {
+            active: default_client_profile(),
+            profiles: Vec::new(),
+        }
+    }
+}
+
+/// 流量镜像 (影子测试) 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorConfig {
     #[serde(default)]
-    pub scheduling: crate::proxy::sticky_config::StickySessionConfig,
+    pub enabled: bool,
+    /// 镜像的请求比例 (0-100)
+    #[serde(default = "default_mirror_percent")]
+    pub percent: f64,
+    /// 镜像请求使用的模型映射 (key: 客户端请求的模型，结尾 `*` 表示前缀匹配)；
+    /// 为空时保持原模型，仅切换账号组
+    #[serde(default)]
+    pub model_mapping: HashMap<String, String>,
+    /// 镜像请求可用的账号 (ID 或邮箱)，为空时使用整个账号池
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// 内存中保留的最近对比结果数
+    #[serde(default = "default_mirror_history")]
+    pub history: usize,
 }
 
-/// 上游代理配置
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct UpstreamProxyConfig {
-    /// 是否启用
+/// 请求日志的请求/响应体采样
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BodyCaptureConfig {
+    /// 记录请求/响应体的请求比例 (0-100)，默认全部记录
+    #[serde(default = "default_body_capture_percent")]
+    pub percent: f64,
+    /// 满足任一规则的请求始终记录请求/响应体；每条规则为空格分隔的日志过滤条件 (同时满足)，
+    /// 如 `status>=400`、`model=gemini-3-pro key=3f2a`
+    #[serde(default)]
+    pub always: Vec<String>,
+}
+
+fn default_body_capture_percent() -> f64 {
+    100.0
+}
+
+impl Default for BodyCaptureConfig {
+    fn default() -> Self {
+        Self {
+            percent: default_body_capture_percent(),
+            always: Vec::new(),
+        }
+    }
+}
+
+fn default_mirror_percent() -> f64 {
+    5.0
+}
+
+fn default_mirror_history() -> usize {
+    200
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            percent: default_mirror_percent(),
+            model_mapping: HashMap::new(),
+            accounts: Vec::new(),
+            history: default_mirror_history(),
+        }
+    }
+}
+
+/// 响应压缩配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    #[serde(default = "default_true")]
     pub enabled: bool,
-    /// 代理地址 (http://, https://, socks5://)
-    pub url: String,
+    /// 小于该字节数的非流式响应不压缩
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: usize,
+    /// 是否压缩 SSE 流式响应 (逐块压缩并立即刷新，不增加首字延迟)
+    #[serde(default = "default_true")]
+    pub streaming: bool,
+}
+
+fn default_compression_min_size() -> usize {
+    1024
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_size: default_compression_min_size(),
+            streaming: true,
+        }
+    }
+}
+
+/// 请求校验模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationMode {
+    /// 不校验，请求原样交给处理器
+    Off,
+    /// 检查必填字段、角色与数值范围，忽略未知字段
+    #[default]
+    Lenient,
+    /// 另外拒绝未知的顶层字段，并要求 Claude 请求携带 `max_tokens`
+    Strict,
+}
+
+/// 请求校验配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestValidationConfig {
+    #[serde(default)]
+    pub mode: ValidationMode,
+}
+
+/// 采样参数策略 (按顺序匹配，第一条命中的规则生效)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParamPolicyConfig {
+    #[serde(default)]
+    pub rules: Vec<ParamPolicyRule>,
+}
+
+/// 单条参数策略规则
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParamPolicyRule {
+    /// 匹配的客户端模型名 (`*` 通配，不区分大小写)；为空匹配全部
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 匹配的 API Key 名称 (附加密钥的 `name`，主密钥与监听器单独指定的密钥为 `default`)；为空匹配全部。
+    /// 鉴权关闭时请求没有已校验的密钥，只有该项为空的规则生效
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub temperature: Option<ParamLimit>,
+    #[serde(default)]
+    pub top_p: Option<ParamLimit>,
+    /// 最大输出 token 数上限；客户端未指定时按该值填充
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+}
+
+/// 数值参数的限制: `override` 优先，其次按 `min` / `max` 截断，客户端未指定时使用 `default`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParamLimit {
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub default: Option<f64>,
+    #[serde(default, rename = "override")]
+    pub force: Option<f64>,
+}
+
+/// 请求日志异常判定阈值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogAnomalyConfig {
+    /// 错误突增的统计时间片 (秒)
+    #[serde(default = "default_anomaly_bucket_secs")]
+    pub bucket_secs: u64,
+    /// 时间片错误率达到整体错误率的该倍数时视为突增
+    #[serde(default = "default_anomaly_spike_factor")]
+    pub error_spike_factor: f64,
+    /// 时间片内至少出现该数量的错误才判定突增
+    #[serde(default = "default_anomaly_spike_min_errors")]
+    pub error_spike_min_errors: u64,
+    /// 账号失败率超过该值视为异常
+    #[serde(default = "default_anomaly_account_failure_rate")]
+    pub account_failure_rate: f64,
+    /// 账号至少有该数量的请求才参与失败率判定
+    #[serde(default = "default_anomaly_min_requests")]
+    pub account_min_requests: u64,
+    /// 后半段时间的延迟中位数达到前半段的该倍数时视为延迟退化
+    #[serde(default = "default_anomaly_latency_factor")]
+    pub latency_regression_factor: f64,
+    /// 前后两段各至少有该数量的成功请求才参与延迟判定
+    #[serde(default = "default_anomaly_latency_min_samples")]
+    pub latency_min_samples: usize,
+    /// 客户端 (API Key 或 IP) 发出至少该数量的格式错误请求 (400/413/415/422) 时列出
+    #[serde(default = "default_anomaly_malformed_min")]
+    pub malformed_min_requests: u64,
+}
+
+impl Default for LogAnomalyConfig {
+    fn default() -> Self {
+        Self {
+            bucket_secs: default_anomaly_bucket_secs(),
+            error_spike_factor: default_anomaly_spike_factor(),
+            error_spike_min_errors: default_anomaly_spike_min_errors(),
+            account_failure_rate: default_anomaly_account_failure_rate(),
+            account_min_requests: default_anomaly_min_requests(),
+            latency_regression_factor: default_anomaly_latency_factor(),
+            latency_min_samples: default_anomaly_latency_min_samples(),
+            malformed_min_requests: default_anomaly_malformed_min(),
+        }
+    }
+}
+
+/// 响应头策略
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseHeaderConfig {
+    /// 透传给客户端的上游响应头 (不区分大小写)
+    #[serde(default)]
+    pub forward: Vec<String>,
+    /// 隐私模式: 不附加任何 `X-Antigravity-*` 信息头
+    #[serde(default)]
+    pub privacy: bool,
+}
+
+/// 上游错误重试策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicyConfig {
+    /// 单个请求的最大尝试次数 (含首次)；轮换账号时不超过账号池大小
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: usize,
+    /// 触发重试的上游状态码
+    #[serde(default = "default_retry_statuses")]
+    pub retry_statuses: Vec<u16>,
+    /// 退避基数 (毫秒)，第 n 次重试等待 base * 2^(n-1)
+    #[serde(default = "default_retry_backoff_base_ms")]
+    pub backoff_base_ms: u64,
+    /// 单次退避上限 (毫秒)
+    #[serde(default = "default_retry_backoff_max_ms")]
+    pub backoff_max_ms: u64,
+    /// 重试时轮换账号；关闭后在同一账号上退避重试
+    #[serde(default = "default_true")]
+    pub rotate_account: bool,
+    /// 流式请求在返回首字节前是否允许重试
+    #[serde(default = "default_true")]
+    pub retry_streaming: bool,
+}
+
+fn default_retry_max_attempts() -> usize {
+    3
+}
+
+fn default_retry_statuses() -> Vec<u16> {
+    vec![401, 403, 429, 500, 503, 529]
+}
+
+fn default_retry_backoff_base_ms() -> u64 {
+    1000
+}
+
+fn default_retry_backoff_max_ms() -> u64 {
+    8000
+}
+
+impl Default for RetryPolicyConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            retry_statuses: default_retry_statuses(),
+            backoff_base_ms: default_retry_backoff_base_ms(),
+            backoff_max_ms: default_retry_backoff_max_ms(),
+            rotate_account: true,
+            retry_streaming: true,
+        }
+    }
+}
+
+/// 模型单价 (美元 / 百万 Token)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// 单个账号的用量上限覆盖 (未设置的项沿用全局值)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountUsageCap {
+    #[serde(default)]
+    pub max_requests: Option<u64>,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+}
+
+impl UsageCapConfig {
+    /// 账号生效的 (最大请求数, 最大 Token 数)
+    pub fn caps_for(&self, account_id: &str, email: &str) -> (Option<u64>, Option<u64>) {
+        let account = self
+            .accounts
+            .iter()
+            .find(|(key, _)| key.as_str() == account_id || key.eq_ignore_ascii_case(email))
+            .map(|(_, cap)| cap);
+        (
+            account.and_then(|c| c.max_requests).or(self.max_requests),
+            account.and_then(|c| c.max_tokens).or(self.max_tokens),
+        )
+    }
+}
+
+fn default_anthropic_versions() -> Vec<String> {
+    vec!["2023-06-01".to_string(), "2023-01-01".to_string()]
+}
+
+fn default_usage_window() -> u64 {
+    24 * 3600
+}
+
+/// 批量请求执行配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchConfig {
+    /// 单个批次内同时执行的请求数
+    #[serde(default = "default_batch_concurrency")]
+    pub max_concurrency: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: default_batch_concurrency(),
+        }
+    }
+}
+
+/// 上游 HTTP 连接池配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpstreamPoolConfig {
+    /// 每个主机最多保留的空闲连接数
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub max_idle_per_host: usize,
+    /// 空闲连接保持时间(秒)
+    #[serde(default = "default_pool_idle_timeout")]
+    pub idle_timeout: u64,
+    /// TCP keepalive 探测间隔(秒)，0 表示关闭
+    #[serde(default = "default_tcp_keepalive")]
+    pub tcp_keepalive: u64,
+    /// 强制使用 HTTP/2 (prior knowledge)，仅在上游与代理均支持时开启
+    #[serde(default)]
+    pub http2_only: bool,
+    /// HTTP/2 PING 保活间隔(秒)，0 表示关闭
+    #[serde(default)]
+    pub http2_keep_alive_interval: u64,
+    /// 启用 HTTP/2 自适应流控窗口
+    #[serde(default)]
+    pub http2_adaptive_window: bool,
+}
+
+impl Default for UpstreamPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: default_pool_max_idle_per_host(),
+            idle_timeout: default_pool_idle_timeout(),
+            tcp_keepalive: default_tcp_keepalive(),
+            http2_only: false,
+            http2_keep_alive_interval: 0,
+            http2_adaptive_window: false,
+        }
+    }
 }
 
 impl Default for ProxyConfig {
@@ -195,24 +1526,179 @@ impl Default for ProxyConfig {
             allow_lan_access: false, // 默认仅本机访问，隐私优先
             auth_mode: ProxyAuthMode::default(),
             port: 8045,
-            api_key: format!("sk-{}", uuid::Uuid::new_v4().simple()),
+            listeners: Vec::new(),
+            listen_tcp: true,
+            admin_listen: None,
+            api_key: crate::proxy::secrets::generate_api_key(),
+            hash_api_keys: false,
+            previous_api_keys: Vec::new(),
+            api_keys: Vec::new(),
+            anthropic_versions: default_anthropic_versions(),
+            key_rotation: KeyRotationConfig::default(),
             auto_start: false,
             anthropic_mapping: std::collections::HashMap::new(),
             openai_mapping: std::collections::HashMap::new(),
             custom_mapping: std::collections::HashMap::new(),
+            mapping_rules: Vec::new(),
             request_timeout: default_request_timeout(),
+            timeouts: TimeoutConfig::default(),
             enable_logging: false, // 默认关闭，节省性能
+            enable_debug_endpoints: false,
+            allow_account_pinning: false,
             upstream_proxy: UpstreamProxyConfig::default(),
+            upstream_pool: UpstreamPoolConfig::default(),
+            upstream_rate_limit: UpstreamRateLimitConfig::default(),
+            client_rate_limit: ClientRateLimitConfig::default(),
+            auth_lockout: AuthLockoutConfig::default(),
+            trusted_proxies: Vec::new(),
+            batches: BatchConfig::default(),
+            load_shedding: LoadSheddingConfig::default(),
+            quota_thresholds: QuotaThresholdConfig::default(),
+            usage_caps: UsageCapConfig::default(),
+            pricing: HashMap::new(),
+            team_routing: TeamRoutingConfig::default(),
+            tier_policy: TierPolicyConfig::default(),
+            schedule_routing: ScheduleRoutingConfig::default(),
+            retry_policy: RetryPolicyConfig::default(),
+            response_headers: ResponseHeaderConfig::default(),
+            quota_cache: QuotaCacheConfig::default(),
+            cluster: ClusterConfig::default(),
+            model_capabilities: ModelCapabilityConfig::default(),
+            context_overflow: ContextOverflowConfig::default(),
+            model_list: ModelListConfig::default(),
+            client_profiles: ClientProfileConfig::default(),
+            mirror: MirrorConfig::default(),
+            compression: CompressionConfig::default(),
+            request_validation: RequestValidationConfig::default(),
+            param_policy: ParamPolicyConfig::default(),
+            stream_resumption: StreamResumptionConfig::default(),
+            log_anomalies: LogAnomalyConfig::default(),
+            body_capture: BodyCaptureConfig::default(),
+            warmup_on_start: false,
+            strict_mappings: false,
+            grpc: GrpcConfig::default(),
+            account_recovery: AccountRecoveryConfig::default(),
             zai: ZaiConfig::default(),
             scheduling: crate::proxy::sticky_config::StickySessionConfig::default(),
         }
     }
 }
 
+fn default_listen_tcp() -> bool {
+    true
+}
+
+fn default_true() -> bool {
+    true
+}
+
 fn default_request_timeout() -> u64 {
     120  // 默认 120 秒,原来 60 秒太短
 }
 
+fn default_connect_timeout() -> u64 {
+    20
+}
+
+fn default_stream_idle_timeout() -> u64 {
+    120
+}
+
+fn default_stream_heartbeat_interval() -> u64 {
+    15
+}
+
+fn default_shedding_max_concurrent() -> usize {
+    32
+}
+
+fn default_shedding_max_queue() -> usize {
+    64
+}
+
+fn default_shedding_queue_timeout() -> u64 {
+    30
+}
+
+fn default_shedding_retry_after() -> u64 {
+    5
+}
+
+fn default_batch_concurrency() -> usize {
+    4
+}
+
+fn default_rate_limit_rps() -> f64 {
+    5.0
+}
+
+fn default_rate_limit_burst() -> u32 {
+    20
+}
+
+fn default_upstream_global_bucket() -> UpstreamBucketConfig {
+    UpstreamBucketConfig {
+        qps: 10.0,
+        burst: 20,
+    }
+}
+
+fn default_upstream_max_wait() -> u64 {
+    30
+}
+
+fn default_anomaly_bucket_secs() -> u64 {
+    300
+}
+
+fn default_anomaly_spike_factor() -> f64 {
+    3.0
+}
+
+fn default_anomaly_spike_min_errors() -> u64 {
+    10
+}
+
+fn default_anomaly_account_failure_rate() -> f64 {
+    0.3
+}
+
+fn default_anomaly_min_requests() -> u64 {
+    20
+}
+
+fn default_anomaly_latency_factor() -> f64 {
+    1.5
+}
+
+fn default_anomaly_latency_min_samples() -> usize {
+    20
+}
+
+fn default_anomaly_malformed_min() -> u64 {
+    5
+}
+
+fn default_resume_attempts() -> u32 {
+    1
+}
+
+fn default_resume_prompt() -> String {
+    "Your previous response was interrupted. Continue exactly where it stopped, without repeating or summarizing anything already written.".to_string()
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    16
+}
+
+fn default_pool_idle_timeout() -> u64 {
+    90
+}
+
+fn default_tcp_keepalive() -> u64 {
+    60
+}
+
 fn default_zai_base_url() -> String {
     "https://api.z.ai/api/anthropic".to_string()
 }
@@ -240,4 +1726,13 @@ impl ProxyConfig {
             "127.0.0.1"
         }
     }
+
+    /// 本机访问管理接口的基础地址: 优先使用独立的管理监听地址 (通配地址改为回环地址)
+    pub fn admin_base_url(&self) -> String {
+        match self.admin_listen.as_deref().and_then(|a| a.parse::<std::net::SocketAddr>().ok()) {
+            Some(addr) if addr.ip().is_unspecified() => format!("http://127.0.0.1:{}", addr.port()),
+            Some(addr) => format!("http://{}", addr),
+            None => format!("http://127.0.0.1:{}", self.port),
+        }
+    }
 }
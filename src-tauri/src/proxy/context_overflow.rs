@@ -0,0 +1,326 @@
+// 上下文超限处理：转换后的 v1internal 请求超出目标模型上下文窗口时，按配置截断或摘要最早的消息
+use serde_json::{json, Value};
+
+use crate::proxy::config::{ContextOverflowConfig, ContextOverflowStrategy};
+use crate::proxy::model_capabilities::estimate_tokens;
+use crate::proxy::timeouts::EffectiveTimeouts;
+use crate::proxy::token_manager::TokenManager;
+use crate::proxy::upstream::client::UpstreamClient;
+
+/// 交给摘要模型的历史对话最多保留的字符数 (超出时保留较新的部分)
+const MAX_TRANSCRIPT_CHARS: usize = 200_000;
+const SUMMARY_MAX_OUTPUT_TOKENS: u64 = 2048;
+const TRUNCATION_NOTICE: &str = "[Earlier messages in this conversation were omitted to fit the model's context window.]";
+
+/// 请求 (`request.contents` + 系统指令 + 工具声明) 的 Token 估算
+pub fn estimate_request_tokens(body: &Value) -> u64 {
+    let Some(request) = body.get("request") else {
+        return 0;
+    };
+    ["contents", "systemInstruction", "tools"]
+        .iter()
+        .filter_map(|key| request.get(*key))
+        .map(estimate_tokens)
+        .sum()
+}
+
+/// 可以作为新对话起点的消息：用户消息且不是工具结果 (否则会留下没有对应调用的 functionResponse)
+fn is_boundary(content: &Value) -> bool {
+    content.get("role").and_then(|r| r.as_str()) == Some("user")
+        && !content
+            .get("parts")
+            .and_then(|p| p.as_array())
+            .is_some_and(|parts| parts.iter().any(|p| p.get("functionResponse").is_some()))
+}
+
+/// 计算需要丢弃的最早消息数，放得下时返回 `Ok(None)`；丢弃到只剩 `keep_recent` 条仍超限时返回错误
+pub fn plan_truncation(
+    body: &Value,
+    model: &str,
+    max_context_tokens: u64,
+    keep_recent: usize,
+) -> Result<Option<usize>, String> {
+    let total = estimate_request_tokens(body);
+    if total <= max_context_tokens {
+        return Ok(None);
+    }
+    let contents = body
+        .get("request")
+        .and_then(|r| r.get("contents"))
+        .and_then(|c| c.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let max_drop = contents.len().saturating_sub(keep_recent.max(1));
+    let mut remaining = total;
+    for cut in 1..=max_drop {
+        remaining = remaining.saturating_sub(estimate_tokens(&contents[cut - 1]));
+        if remaining <= max_context_tokens && is_boundary(&contents[cut]) {
+            return Ok(Some(cut));
+        }
+    }
+    Err(format!(
+        "Input is about {} tokens, which exceeds the {} token context window of model {} even after dropping older messages",
+        total, max_context_tokens, model
+    ))
+}
+
+/// 丢弃最早的 `cut` 条消息并在新的第一条用户消息前插入说明，返回被丢弃的消息
+pub fn apply_truncation(body: &mut Value, cut: usize, preface: &str) -> Vec<Value> {
+    let Some(contents) = body
+        .get_mut("request")
+        .and_then(|r| r.get_mut("contents"))
+        .and_then(|c| c.as_array_mut())
+    else {
+        return Vec::new();
+    };
+    let dropped: Vec<Value> = contents.drain(..cut.min(contents.len())).collect();
+    if let Some(parts) = contents
+        .first_mut()
+        .and_then(|c| c.get_mut("parts"))
+        .and_then(|p| p.as_array_mut())
+    {
+        parts.insert(0, json!({ "text": preface }));
+    }
+    dropped
+}
+
+/// 将消息渲染为供摘要模型阅读的纯文本记录 (超长时保留较新的部分)
+pub fn render_transcript(contents: &[Value], max_chars: usize) -> String {
+    let mut lines = Vec::new();
+    for content in contents {
+        let role = content.get("role").and_then(|r| r.as_str()).unwrap_or("user");
+        let Some(parts) = content.get("parts").and_then(|p| p.as_array()) else {
+            continue;
+        };
+        for part in parts {
+            if part.get("thought").and_then(|t| t.as_bool()) == Some(true) {
+                continue;
+            }
+            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                lines.push(format!("{}: {}", role, text));
+            } else if let Some(call) = part.get("functionCall") {
+                lines.push(format!(
+                    "{} called tool {}: {}",
+                    role,
+                    call.get("name").and_then(|n| n.as_str()).unwrap_or("?"),
+                    call.get("args").map(|a| a.to_string()).unwrap_or_default()
+                ));
+            } else if let Some(result) = part.get("functionResponse") {
+                lines.push(format!(
+                    "tool {} returned: {}",
+                    result.get("name").and_then(|n| n.as_str()).unwrap_or("?"),
+                    result.get("response").map(|r| r.to_string()).unwrap_or_default()
+                ));
+            }
+        }
+    }
+    let transcript = lines.join("\n");
+    let len = transcript.chars().count();
+    if len <= max_chars {
+        return transcript;
+    }
+    transcript.chars().skip(len - max_chars).collect()
+}
+
+/// 调用摘要模型总结被丢弃的历史对话
+async fn summarize(
+    upstream: &UpstreamClient,
+    access_token: &str,
+    project: &Value,
+    summary_model: &str,
+    dropped: &[Value],
+    timeouts: &EffectiveTimeouts,
+) -> Result<String, String> {
+    let prompt = format!(
+        "Summarize the following earlier part of a conversation so it can continue without it. \
+         Keep facts, decisions, open tasks, file names and tool results that later messages may rely on. \
+         Reply with the summary only.\n\n{}",
+        render_transcript(dropped, MAX_TRANSCRIPT_CHARS)
+    );
+    let body = json!({
+        "project": project,
+        "requestId": format!("summary-{}", uuid::Uuid::new_v4()),
+        "model": summary_model,
+        "userAgent": "antigravity",
+        "requestType": "agent",
+        "request": {
+            "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+            "generationConfig": { "maxOutputTokens": SUMMARY_MAX_OUTPUT_TOKENS }
+        }
+    });
+    let response = upstream
+        .call_v1_internal("generateContent", access_token, body, None, timeouts)
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("摘要请求失败: HTTP {}", status));
+    }
+    let value: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("解析摘要响应失败: {}", e))?;
+    let summary = value
+        .pointer("/response/candidates/0/content/parts")
+        .and_then(|p| p.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter(|p| p.get("thought").and_then(|t| t.as_bool()) != Some(true))
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    if summary.trim().is_empty() {
+        return Err("摘要响应为空".to_string());
+    }
+    Ok(summary)
+}
+
+/// 让转换后的请求放进目标模型的上下文窗口
+///
+/// 拒绝策略已由 `TokenManager::check_model_request` 在转换前处理，这里只处理截断与摘要。
+/// 模型不在能力注册表中时放行。
+pub async fn fit_request(
+    token_manager: &TokenManager,
+    upstream: &UpstreamClient,
+    access_token: &str,
+    model: &str,
+    body: &mut Value,
+    timeouts: &EffectiveTimeouts,
+) -> Result<(), String> {
+    let ContextOverflowConfig {
+        strategy,
+        keep_recent_messages,
+        summary_model,
+    } = token_manager.context_overflow().await;
+    if strategy == ContextOverflowStrategy::Reject {
+        return Ok(());
+    }
+    let Some(caps) = token_manager.model_capabilities(model).await else {
+        return Ok(());
+    };
+    let Some(cut) = plan_truncation(body, model, caps.max_context_tokens, keep_recent_messages)? else {
+        return Ok(());
+    };
+
+    if strategy == ContextOverflowStrategy::Summarize {
+        let dropped: Vec<Value> = body
+            .pointer("/request/contents")
+            .and_then(|c| c.as_array())
+            .map(|c| c[..cut].to_vec())
+            .unwrap_or_default();
+        let project = body.get("project").cloned().unwrap_or(Value::Null);
+        match summarize(upstream, access_token, &project, &summary_model, &dropped, timeouts).await {
+            Ok(summary) => {
+                let preface = format!("[Summary of the earlier conversation]\n{}", summary);
+                apply_truncation(body, cut, &preface);
+                // 摘要本身也占上下文，仍超限时按截断处理剩余部分
+                if estimate_request_tokens(body) <= caps.max_context_tokens {
+                    tracing::info!("上下文超出 {} 的窗口，已将最早的 {} 条消息替换为摘要", model, cut);
+                    return Ok(());
+                }
+                tracing::warn!("加入摘要后请求仍超出 {} 的上下文窗口，改为截断", model);
+                return truncate_remaining(body, model, caps.max_context_tokens, keep_recent_messages);
+            }
+            Err(e) => {
+                tracing::warn!("生成上下文摘要失败，改为截断: {}", e);
+            }
+        }
+    }
+
+    apply_truncation(body, cut, TRUNCATION_NOTICE);
+    tracing::info!("上下文超出 {} 的窗口，已丢弃最早的 {} 条消息", model, cut);
+    Ok(())
+}
+
+/// 摘要后仍超限时继续截断 (摘要随第一条消息一起被丢弃)
+fn truncate_remaining(
+    body: &mut Value,
+    model: &str,
+    max_context_tokens: u64,
+    keep_recent: usize,
+) -> Result<(), String> {
+    if let Some(cut) = plan_truncation(body, model, max_context_tokens, keep_recent)? {
+        apply_truncation(body, cut, TRUNCATION_NOTICE);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(role: &str, text: &str) -> Value {
+        json!({ "role": role, "parts": [{ "text": text }] })
+    }
+
+    fn body(contents: Vec<Value>) -> Value {
+        json!({ "project": "p", "model": "m", "request": { "contents": contents } })
+    }
+
+    #[test]
+    fn fits_without_truncation() {
+        let body = body(vec![text("user", "hello")]);
+        assert_eq!(plan_truncation(&body, "m", 100, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn drops_oldest_messages_up_to_a_user_turn() {
+        let long = "x".repeat(400); // 100 tokens
+        let body = body(vec![
+            text("user", &long),
+            text("model", &long),
+            text("user", &long),
+            text("model", "ok"),
+            text("user", "next"),
+        ]);
+        // 总计约 302，窗口 150：需丢弃前两条，且第 3 条是用户消息
+        assert_eq!(plan_truncation(&body, "m", 150, 2).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn never_starts_on_a_tool_result() {
+        let long = "x".repeat(400);
+        let body = body(vec![
+            text("user", &long),
+            json!({ "role": "model", "parts": [{ "functionCall": { "name": "f", "args": {} } }] }),
+            json!({ "role": "user", "parts": [{ "functionResponse": { "name": "f", "response": {} } }] }),
+            text("model", "done"),
+            text("user", "next"),
+        ]);
+        assert_eq!(plan_truncation(&body, "m", 20, 1).unwrap(), Some(4));
+    }
+
+    #[test]
+    fn errors_when_recent_messages_alone_overflow() {
+        let long = "x".repeat(400);
+        let body = body(vec![text("user", "a"), text("model", "b"), text("user", &long)]);
+        let err = plan_truncation(&body, "m", 50, 1).unwrap_err();
+        assert!(err.contains("50 token context window of model m"));
+    }
+
+    #[test]
+    fn truncation_prefixes_the_new_first_message() {
+        let mut body = body(vec![text("user", "old"), text("model", "reply"), text("user", "new")]);
+        let dropped = apply_truncation(&mut body, 2, "notice");
+        assert_eq!(dropped.len(), 2);
+        let contents = body["request"]["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents[0]["parts"][0]["text"], "notice");
+        assert_eq!(contents[0]["parts"][1]["text"], "new");
+    }
+
+    #[test]
+    fn transcript_keeps_most_recent_text() {
+        let contents = vec![
+            text("user", "first"),
+            json!({ "role": "model", "parts": [
+                { "text": "thinking", "thought": true },
+                { "functionCall": { "name": "search", "args": { "q": "x" } } }
+            ] }),
+        ];
+        let transcript = render_transcript(&contents, 1000);
+        assert_eq!(transcript, "user: first\nmodel called tool search: {\"q\":\"x\"}");
+        assert_eq!(render_transcript(&contents, 9), "{\"q\":\"x\"}");
+    }
+}
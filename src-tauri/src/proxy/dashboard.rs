@@ -0,0 +1,98 @@
+// 内置状态面板 (`/dashboard`): 供没有桌面界面的服务器部署查看流量、账号池、剩余配额与最近的错误。
+// 页面为单个静态 HTML，每隔数秒从 `/dashboard/data` 拉取数据并在浏览器端绘图
+use serde::Serialize;
+
+use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::pool_quota::PoolQuota;
+use crate::proxy::stats::TrendPoint;
+use crate::proxy::status::RuntimeStatus;
+
+pub const DASHBOARD_HTML: &str = include_str!("dashboard.html");
+
+/// 面板展示的最近错误条数
+pub const RECENT_ERRORS: usize = 20;
+/// 错误信息的最大展示长度 (字符)
+const MAX_ERROR_CHARS: usize = 300;
+
+/// 面板数据 (`GET /dashboard/data`)
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardData {
+    pub status: RuntimeStatus,
+    /// 最近 24 小时的小时级流量
+    pub traffic: Vec<TrendPoint>,
+    pub models: Vec<ModelQuota>,
+    pub recent_errors: Vec<RecentError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelQuota {
+    pub model: String,
+    pub quota: Option<PoolQuota>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecentError {
+    pub timestamp: i64,
+    pub status: u16,
+    pub model: Option<String>,
+    pub account: Option<String>,
+    pub url: String,
+    pub error: Option<String>,
+}
+
+impl From<ProxyRequestLog> for RecentError {
+    fn from(log: ProxyRequestLog) -> Self {
+        Self {
+            timestamp: log.timestamp,
+            status: log.status,
+            model: log.model,
+            account: log.account,
+            url: log.url,
+            error: log.error.map(|e| {
+                if e.chars().count() > MAX_ERROR_CHARS {
+                    format!("{}…", e.chars().take(MAX_ERROR_CHARS).collect::<String>())
+                } else {
+                    e
+                }
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_polls_data_endpoint() {
+        assert!(DASHBOARD_HTML.contains("/dashboard/data"));
+        // 页面不依赖外部资源，内网 / 离线部署可直接使用
+        assert!(!DASHBOARD_HTML.contains("https://"));
+    }
+
+    #[test]
+    fn recent_error_truncates_long_messages() {
+        let log = ProxyRequestLog {
+            id: "r1".to_string(),
+            timestamp: 1,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status: 429,
+            duration: 10,
+            model: Some("claude-sonnet-4-5".to_string()),
+            error: Some("配额".repeat(400)),
+            request_body: Some("{}".to_string()),
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            session_id: None,
+            account: Some("a@example.com".to_string()),
+            key_id: None,
+            client_ip: None,
+            canary_target: None,
+        };
+        let error = RecentError::from(log);
+        assert_eq!(error.status, 429);
+        assert_eq!(error.error.unwrap().chars().count(), MAX_ERROR_CHARS + 1);
+    }
+}
@@ -0,0 +1,455 @@
+// 部署诊断 (`--doctor`): 逐项检查端口、数据目录、账号与 refresh_token、上游连通性 (直连与经上游代理)、
+// 时钟偏差与 TLS 证书，输出通过 / 警告 / 失败报告及修复建议
+use futures::StreamExt;
+use serde::Serialize;
+
+use crate::models::{Account, AppConfig};
+
+/// 同时校验 refresh_token 的账号数
+const REFRESH_CONCURRENCY: usize = 4;
+/// 连通性检测超时 (秒)
+const PROBE_TIMEOUT_SECS: u64 = 5;
+/// 上游连通性检测地址 (任意 HTTP 响应即视为可达)
+const UPSTREAM_ENDPOINTS: &[&str] = &["https://oauth2.googleapis.com", "https://cloudcode-pa.googleapis.com"];
+/// 时钟偏差告警 / 失败阈值 (秒)；偏差过大时 OAuth token 会被上游判定为未生效或已过期
+const CLOCK_SKEW_WARN_SECS: i64 = 30;
+const CLOCK_SKEW_FAIL_SECS: i64 = 300;
+/// TLS 证书剩余有效期低于该天数时告警
+const CERT_EXPIRY_WARN_DAYS: i64 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+        }
+    }
+}
+
+/// 单项检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// 修复建议
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(name: &str, message: String) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Pass, message, fix: None }
+    }
+
+    fn warn(name: &str, message: String, fix: Option<&str>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Warn, message, fix: fix.map(str::to_string) }
+    }
+
+    fn fail(name: &str, message: String, fix: Option<&str>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Fail, message, fix: fix.map(str::to_string) }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(CheckStatus::Fail) > 0
+    }
+}
+
+/// 依次执行全部检查 (只读: 不修改账号状态，也不保存刷新得到的 access_token)
+pub async fn run(config: &AppConfig) -> DoctorReport {
+    let mut report = DoctorReport::default();
+    report.checks.push(check_port(config).await);
+    report.checks.push(check_data_dir());
+
+    let (accounts_check, usable) = check_accounts();
+    report.checks.push(accounts_check);
+    if !usable.is_empty() {
+        report.checks.push(check_refresh_tokens(&usable).await);
+    }
+
+    let (upstream_checks, server_time) = check_upstream(config).await;
+    report.checks.extend(upstream_checks);
+    report.checks.push(check_clock(server_time, chrono::Utc::now().timestamp()));
+    report.checks.extend(check_tls(config));
+    report
+}
+
+async fn check_port(config: &AppConfig) -> DoctorCheck {
+    const NAME: &str = "port";
+    let proxy = &config.proxy;
+    if !proxy.listen_tcp {
+        return DoctorCheck::pass(NAME, "未监听 TCP 端口 (proxy.listen_tcp = false)".to_string());
+    }
+    let addr = format!("{}:{}", proxy.get_bind_address(), proxy.port);
+    let Err(e) = std::net::TcpListener::bind(&addr) else {
+        return DoctorCheck::pass(NAME, format!("{} 可用", addr));
+    };
+
+    // 端口被本程序的反代占用时不算失败
+    let health = format!("http://127.0.0.1:{}/healthz", proxy.port);
+    let running = crate::utils::http::create_client_with_proxy(2, None)
+        .get(&health)
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status().is_success());
+    if running {
+        DoctorCheck::warn(NAME, format!("{} 已被运行中的反代占用", addr), Some("如需重新启动，请先停止正在运行的实例"))
+    } else {
+        DoctorCheck::fail(NAME, format!("{} 不可用: {}", addr, e), Some("停止占用该端口的进程，或使用 --port 指定其他端口"))
+    }
+}
+
+fn check_data_dir() -> DoctorCheck {
+    const NAME: &str = "data_dir";
+    const FIX: Option<&str> = Some("检查目录权限与磁盘空间，或使用 --data-dir 指定可写目录");
+    let dir = match crate::modules::account::get_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return DoctorCheck::fail(NAME, e, FIX),
+    };
+    let probe = dir.join(".doctor_probe");
+    match std::fs::write(&probe, b"ok") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            DoctorCheck::pass(NAME, format!("{} 可写", dir.display()))
+        }
+        Err(e) => DoctorCheck::fail(NAME, format!("{} 不可写: {}", dir.display(), e), FIX),
+    }
+}
+
+/// 账号检查，同时返回参与反代轮换的账号 (未禁用且未被 403)
+fn check_accounts() -> (DoctorCheck, Vec<Account>) {
+    const NAME: &str = "accounts";
+    const FIX: Option<&str> = Some("使用 --init 或 --account-import-from-ide 添加账号，或在 GUI 中重新登录");
+    let accounts = match crate::modules::account::list_accounts() {
+        Ok(accounts) => accounts,
+        Err(e) => return (DoctorCheck::fail(NAME, format!("读取账号失败: {}", e), None), Vec::new()),
+    };
+    let total = accounts.len();
+    let usable: Vec<Account> = accounts
+        .into_iter()
+        .filter(|a| !a.disabled && !a.proxy_disabled && !a.quota.as_ref().is_some_and(|q| q.is_forbidden))
+        .collect();
+    let check = match (total, usable.len()) {
+        (0, _) => DoctorCheck::fail(NAME, "没有账号".to_string(), FIX),
+        (total, 0) => DoctorCheck::fail(NAME, format!("{} 个账号均已禁用或无权限 (403)", total), FIX),
+        (total, n) => DoctorCheck::pass(NAME, format!("{}/{} 个账号可用于反代", n, total)),
+    };
+    (check, usable)
+}
+
+async fn check_refresh_tokens(accounts: &[Account]) -> DoctorCheck {
+    const NAME: &str = "refresh_token";
+    let failures: Vec<(String, String)> = futures::stream::iter(accounts)
+        .map(|account| async move {
+            crate::modules::oauth::refresh_access_token(&account.token.refresh_token)
+                .await
+                .err()
+                .map(|e| (account.email.clone(), e))
+        })
+        .buffer_unordered(REFRESH_CONCURRENCY)
+        .filter_map(|failure| async move { failure })
+        .collect()
+        .await;
+
+    if failures.is_empty() {
+        return DoctorCheck::pass(NAME, format!("{} 个账号的 refresh_token 均有效", accounts.len()));
+    }
+    let revoked = failures.iter().any(|(_, e)| e.contains("invalid_grant"));
+    let fix = if revoked {
+        Some("invalid_grant 表示授权已撤销或过期，请重新登录这些账号")
+    } else {
+        Some("检查网络或上游代理设置后重试")
+    };
+    let detail = failures
+        .iter()
+        .map(|(email, e)| format!("{} ({})", email, e.chars().take(80).collect::<String>()))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let message = format!("{}/{} 个账号刷新失败: {}", failures.len(), accounts.len(), detail);
+    if failures.len() == accounts.len() {
+        DoctorCheck::fail(NAME, message, fix)
+    } else {
+        DoctorCheck::warn(NAME, message, fix)
+    }
+}
+
+/// 探测上游连通性，返回检查结果与上游响应的 `Date` (Unix 秒，用于时钟偏差检查)
+async fn check_upstream(config: &AppConfig) -> (Vec<DoctorCheck>, Option<i64>) {
+    let upstream_proxy = &config.proxy.upstream_proxy;
+    let via_proxy = upstream_proxy.enabled && !upstream_proxy.url.is_empty();
+    let direct_client = crate::utils::http::create_client_with_proxy(PROBE_TIMEOUT_SECS, None);
+    let (direct_errors, mut server_time) = probe_endpoints(&direct_client).await;
+
+    let mut checks = Vec::new();
+    let direct = if direct_errors.is_empty() {
+        DoctorCheck::pass("upstream_direct", "直连上游可达".to_string())
+    } else if via_proxy {
+        // 需要上游代理的网络环境中直连失败属于预期
+        DoctorCheck::warn("upstream_direct", format!("直连上游失败: {}", direct_errors.join("; ")), None)
+    } else {
+        DoctorCheck::fail(
+            "upstream_direct",
+            format!("直连上游失败: {}", direct_errors.join("; ")),
+            Some("检查网络与 DNS；如需经代理访问，请配置 proxy.upstream_proxy"),
+        )
+    };
+    checks.push(direct);
+
+    if via_proxy {
+        let proxy_client =
+            crate::utils::http::create_client_with_proxy(PROBE_TIMEOUT_SECS, Some(upstream_proxy.clone()));
+        let (proxy_errors, time) = probe_endpoints(&proxy_client).await;
+        server_time = server_time.or(time);
+        checks.push(if proxy_errors.is_empty() {
+            DoctorCheck::pass("upstream_proxy", format!("经上游代理 {} 可达", upstream_proxy.url))
+        } else {
+            DoctorCheck::fail(
+                "upstream_proxy",
+                format!("经上游代理 {} 访问失败: {}", upstream_proxy.url, proxy_errors.join("; ")),
+                Some("确认代理地址、协议与认证信息正确，且代理允许访问 googleapis.com"),
+            )
+        });
+    }
+    (checks, server_time)
+}
+
+async fn probe_endpoints(client: &reqwest::Client) -> (Vec<String>, Option<i64>) {
+    let mut errors = Vec::new();
+    let mut server_time = None;
+    for endpoint in UPSTREAM_ENDPOINTS {
+        match client.get(*endpoint).send().await {
+            Ok(resp) => {
+                server_time = server_time.or_else(|| {
+                    resp.headers()
+                        .get(reqwest::header::DATE)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(parse_http_date)
+                });
+            }
+            Err(e) => errors.push(format!("{}: {}", endpoint, e)),
+        }
+    }
+    (errors, server_time)
+}
+
+fn parse_http_date(value: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc2822(value).ok().map(|t| t.timestamp())
+}
+
+fn check_clock(server_time: Option<i64>, now: i64) -> DoctorCheck {
+    const NAME: &str = "clock";
+    const FIX: Option<&str> = Some("启用 NTP 时间同步 (如 timedatectl set-ntp true)");
+    let Some(server_time) = server_time else {
+        return DoctorCheck::warn(NAME, "无法从上游获取服务器时间，已跳过时钟偏差检查".to_string(), None);
+    };
+    let skew = now - server_time;
+    let message = format!("本机时钟与上游相差 {} 秒", skew);
+    match skew.abs() {
+        s if s > CLOCK_SKEW_FAIL_SECS => DoctorCheck::fail(NAME, message, FIX),
+        s if s > CLOCK_SKEW_WARN_SECS => DoctorCheck::warn(NAME, message, FIX),
+        _ => DoctorCheck::pass(NAME, message),
+    }
+}
+
+/// 检查手动配置的 TLS 证书 (ACME 证书自动续期，不在此检查)
+fn check_tls(config: &AppConfig) -> Vec<DoctorCheck> {
+    let now = chrono::Utc::now().timestamp();
+    config
+        .proxy
+        .listeners
+        .iter()
+        .filter(|l| l.acme.is_none())
+        .filter_map(|l| l.tls.as_ref().map(|tls| (l, tls)))
+        .map(|(listener, tls)| {
+            let name = format!("tls {}", listener.bind);
+            const FIX: Option<&str> = Some("更换证书，或为该监听器配置 acme 自动申请");
+            if let Err(e) = crate::proxy::listener::load_tls_acceptor(tls) {
+                return DoctorCheck::fail(&name, e, FIX);
+            }
+            let Some(not_after) = read_cert_not_after(&tls.cert_path) else {
+                return DoctorCheck::warn(&name, format!("无法解析证书有效期: {}", tls.cert_path), None);
+            };
+            let expires = chrono::DateTime::from_timestamp(not_after, 0)
+                .map(|t| t.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            let days = (not_after - now) / 86400;
+            if not_after <= now {
+                DoctorCheck::fail(&name, format!("证书已于 {} 过期", expires), FIX)
+            } else if days < CERT_EXPIRY_WARN_DAYS {
+                DoctorCheck::warn(&name, format!("证书将在 {} 天后 ({}) 过期", days, expires), FIX)
+            } else {
+                DoctorCheck::pass(&name, format!("证书有效至 {}", expires))
+            }
+        })
+        .collect()
+}
+
+fn read_cert_not_after(path: &str) -> Option<i64> {
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::CertificateDer;
+
+    let cert = CertificateDer::pem_file_iter(path).ok()?.next()?.ok()?;
+    certificate_not_after(cert.as_ref())
+}
+
+/// 读取一个 DER TLV，返回 (标签, 内容, 剩余字节)
+fn der_next(buf: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *buf.first()?;
+    let first = *buf.get(1)?;
+    let (len, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let bytes = buf.get(2..2 + n)?;
+        (bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize), 2 + n)
+    };
+    let end = header.checked_add(len)?;
+    Some((tag, buf.get(header..end)?, &buf[end..]))
+}
+
+/// 从 X.509 证书 (DER) 中读取 notAfter (Unix 秒)
+fn certificate_not_after(der: &[u8]) -> Option<i64> {
+    certificate_validity(der).map(|(_, not_after)| not_after)
+}
+
+/// 从 X.509 证书 (DER) 中读取有效期 (notBefore, notAfter)，Unix 秒
+pub(crate) fn certificate_validity(der: &[u8]) -> Option<(i64, i64)> {
+    let (_, cert, _) = der_next(der)?;
+    let (_, tbs, _) = der_next(cert)?;
+    // tbsCertificate: [0] version (可选), serialNumber, signature, issuer, validity, ...
+    let (tag, _, rest) = der_next(tbs)?;
+    let rest = if tag == 0xa0 { der_next(rest)?.2 } else { rest };
+    let (_, _, rest) = der_next(rest)?;
+    let (_, _, rest) = der_next(rest)?;
+    let (_, validity, _) = der_next(rest)?;
+    let (tag, time, rest) = der_next(validity)?;
+    let not_before = der_time(tag, time)?;
+    let (tag, time, _) = der_next(rest)?;
+    Some((not_before, der_time(tag, time)?))
+}
+
+fn der_time(tag: u8, time: &[u8]) -> Option<i64> {
+    let time = std::str::from_utf8(time).ok()?;
+    let full = match tag {
+        // UTCTime: YYMMDDHHMMSSZ，年份 50-99 表示 19xx
+        0x17 => {
+            let year: u32 = time.get(..2)?.parse().ok()?;
+            format!("{}{}", if year >= 50 { "19" } else { "20" }, time)
+        }
+        // GeneralizedTime: YYYYMMDDHHMMSSZ
+        0x18 => time.to_string(),
+        _ => return None,
+    };
+    chrono::NaiveDateTime::parse_from_str(&full, "%Y%m%d%H%M%SZ")
+        .ok()
+        .map(|t| t.and_utc().timestamp())
+}
+
+/// 将报告格式化为适合终端输出的文本
+pub fn format_report(report: &DoctorReport) -> String {
+    let width = report.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for check in &report.checks {
+        out.push_str(&format!("[{}] {:<width$}  {}\n", check.status.as_str(), check.name, check.message));
+        if let Some(fix) = &check.fix {
+            out.push_str(&format!("       -> {}\n", fix));
+        }
+    }
+    out.push_str(&crate::modules::i18n::cli_text(
+        "doctor_summary",
+        &[
+            ("passed", &report.count(CheckStatus::Pass)),
+            ("warnings", &report.count(CheckStatus::Warn)),
+            ("failed", &report.count(CheckStatus::Fail)),
+        ],
+    ));
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            out.extend([0x82, (content.len() >> 8) as u8, content.len() as u8]);
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn certificate(with_version: bool, not_after: (u8, &str)) -> Vec<u8> {
+        let mut tbs = Vec::new();
+        if with_version {
+            tbs.extend(tlv(0xa0, &tlv(0x02, &[2])));
+        }
+        tbs.extend(tlv(0x02, &[0x01, 0x23]));
+        tbs.extend(tlv(0x30, &tlv(0x06, &[0x2a, 0x86, 0x48])));
+        tbs.extend(tlv(0x30, &[0u8; 200]));
+        let validity = [tlv(0x17, b"250101000000Z"), tlv(not_after.0, not_after.1.as_bytes())].concat();
+        tbs.extend(tlv(0x30, &validity));
+        tlv(0x30, &[tlv(0x30, &tbs), tlv(0x30, &[]), tlv(0x03, &[0])].concat())
+    }
+
+    #[test]
+    fn reads_not_after_from_der() {
+        assert_eq!(certificate_not_after(&certificate(true, (0x18, "20350101000000Z"))), Some(2051222400));
+        assert_eq!(certificate_not_after(&certificate(false, (0x17, "491231235959Z"))), Some(2524607999));
+        assert_eq!(certificate_not_after(&[0x30, 0x05, 0x30]), None);
+        assert_eq!(
+            certificate_validity(&certificate(true, (0x18, "20350101000000Z"))),
+            Some((1735689600, 2051222400))
+        );
+    }
+
+    #[test]
+    fn clock_skew_thresholds() {
+        assert_eq!(check_clock(Some(1_000), 1_010).status, CheckStatus::Pass);
+        assert_eq!(check_clock(Some(1_000), 940).status, CheckStatus::Warn);
+        assert_eq!(check_clock(Some(1_000), 1_400).status, CheckStatus::Fail);
+        assert_eq!(check_clock(None, 1_000).status, CheckStatus::Warn);
+        assert_eq!(parse_http_date("Mon, 01 Jan 2035 00:00:00 GMT"), Some(2051222400));
+    }
+
+    #[test]
+    fn report_lists_checks_with_fixes() {
+        let report = DoctorReport {
+            checks: vec![
+                DoctorCheck::pass("port", "127.0.0.1:8045 可用".to_string()),
+                DoctorCheck::fail("accounts", "没有账号".to_string(), Some("添加账号")),
+            ],
+        };
+        assert!(report.has_failures());
+        let text = format_report(&report);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("[PASS] port"));
+        assert!(lines[1].starts_with("[FAIL] accounts"));
+        assert_eq!(lines[2].trim(), "-> 添加账号");
+    }
+}
@@ -0,0 +1,319 @@
+// 统一错误模型: 把上游失败、账号池错误与中间件拒绝统一改写为各协议原生的错误格式
+// (OpenAI `error.type/code`、Anthropic `error.type`、Gemini `error.status`)，
+// 避免把上游原始文本或通用 500 直接返回给客户端
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+/// 客户端所用的协议 (按请求路径判断)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    OpenAI,
+    Anthropic,
+    Gemini,
+}
+
+impl Protocol {
+    /// 非模型接口 (管理接口、面板、健康检查等) 返回 None，不做改写
+    pub fn from_path(path: &str) -> Option<Self> {
+        if path.starts_with("/v1beta/") {
+            return Some(Protocol::Gemini);
+        }
+        if path == "/v1/messages" || path.starts_with("/v1/messages/") || path == "/v1/models/claude" {
+            return Some(Protocol::Anthropic);
+        }
+        if path.starts_with("/v1/") {
+            return Some(Protocol::OpenAI);
+        }
+        None
+    }
+}
+
+/// 与协议无关的错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidRequest,
+    Authentication,
+    PermissionDenied,
+    NotFound,
+    RequestTooLarge,
+    RateLimited,
+    Timeout,
+    Overloaded,
+    NotImplemented,
+    Api,
+}
+
+impl ErrorKind {
+    pub fn from_status(status: StatusCode) -> Self {
+        match status.as_u16() {
+            401 => ErrorKind::Authentication,
+            403 => ErrorKind::PermissionDenied,
+            404 => ErrorKind::NotFound,
+            413 => ErrorKind::RequestTooLarge,
+            429 => ErrorKind::RateLimited,
+            408 | 504 => ErrorKind::Timeout,
+            503 | 529 => ErrorKind::Overloaded,
+            501 => ErrorKind::NotImplemented,
+            400..=499 => ErrorKind::InvalidRequest,
+            _ => ErrorKind::Api,
+        }
+    }
+
+    /// OpenAI 的 (`error.type`, `error.code`)
+    fn openai(&self) -> (&'static str, &'static str) {
+        match self {
+            ErrorKind::InvalidRequest => ("invalid_request_error", "invalid_request"),
+            ErrorKind::Authentication => ("invalid_request_error", "invalid_api_key"),
+            ErrorKind::PermissionDenied => ("invalid_request_error", "permission_denied"),
+            ErrorKind::NotFound => ("invalid_request_error", "not_found"),
+            ErrorKind::RequestTooLarge => ("invalid_request_error", "request_too_large"),
+            ErrorKind::RateLimited => ("rate_limit_error", "rate_limit_exceeded"),
+            ErrorKind::Timeout => ("timeout_error", "timeout"),
+            ErrorKind::Overloaded => ("server_error", "overloaded"),
+            ErrorKind::NotImplemented => ("invalid_request_error", "not_implemented"),
+            ErrorKind::Api => ("server_error", "upstream_error"),
+        }
+    }
+
+    /// Anthropic 的 `error.type`
+    fn anthropic(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidRequest | ErrorKind::NotImplemented => "invalid_request_error",
+            ErrorKind::Authentication => "authentication_error",
+            ErrorKind::PermissionDenied => "permission_error",
+            ErrorKind::NotFound => "not_found_error",
+            ErrorKind::RequestTooLarge => "request_too_large",
+            ErrorKind::RateLimited => "rate_limit_error",
+            ErrorKind::Overloaded => "overloaded_error",
+            ErrorKind::Timeout | ErrorKind::Api => "api_error",
+        }
+    }
+
+    /// Gemini (Google RPC) 的 `error.status`
+    fn gemini(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidRequest | ErrorKind::RequestTooLarge => "INVALID_ARGUMENT",
+            ErrorKind::Authentication => "UNAUTHENTICATED",
+            ErrorKind::PermissionDenied => "PERMISSION_DENIED",
+            ErrorKind::NotFound => "NOT_FOUND",
+            ErrorKind::RateLimited => "RESOURCE_EXHAUSTED",
+            ErrorKind::Timeout => "DEADLINE_EXCEEDED",
+            ErrorKind::Overloaded => "UNAVAILABLE",
+            ErrorKind::NotImplemented => "UNIMPLEMENTED",
+            ErrorKind::Api => "INTERNAL",
+        }
+    }
+}
+
+/// Google RPC 状态对应的 HTTP 状态码
+fn google_status_code(status: &str) -> Option<StatusCode> {
+    let code = match status {
+        "INVALID_ARGUMENT" | "FAILED_PRECONDITION" | "OUT_OF_RANGE" => 400,
+        "UNAUTHENTICATED" => 401,
+        "PERMISSION_DENIED" => 403,
+        "NOT_FOUND" => 404,
+        "ALREADY_EXISTS" | "ABORTED" => 409,
+        "RESOURCE_EXHAUSTED" => 429,
+        "UNIMPLEMENTED" => 501,
+        "UNAVAILABLE" => 503,
+        "DEADLINE_EXCEEDED" => 504,
+        "INTERNAL" | "UNKNOWN" | "DATA_LOSS" => 500,
+        _ => return None,
+    };
+    StatusCode::from_u16(code).ok()
+}
+
+/// 从错误 JSON 中取出的信息
+struct Extracted {
+    message: Option<String>,
+    google_status: Option<String>,
+}
+
+fn extract(value: &Value) -> Extracted {
+    // Google 偶尔以数组形式返回错误
+    let value = match value {
+        Value::Array(items) => items.first().unwrap_or(value),
+        _ => value,
+    };
+    let error = value.get("error");
+    let message = match error {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(e) => e.get("message").and_then(|m| m.as_str()).map(str::to_string),
+        None => None,
+    }
+    .or_else(|| value.get("message").and_then(|m| m.as_str()).map(str::to_string));
+    let google_status = error
+        .and_then(|e| e.get("status"))
+        .and_then(|s| s.as_str())
+        .map(str::to_string);
+    Extracted { message, google_status }
+}
+
+/// 响应体是否已经是该协议的原生错误格式
+fn is_native(protocol: Protocol, value: &Value) -> bool {
+    let error = value.get("error");
+    let str_field = |name: &str| error.and_then(|e| e.get(name)).is_some_and(Value::is_string);
+    let has_message = str_field("message");
+    match protocol {
+        Protocol::OpenAI => has_message && str_field("type") && value.get("type").is_none(),
+        Protocol::Anthropic => {
+            value.get("type").and_then(|t| t.as_str()) == Some("error") && has_message && str_field("type")
+        }
+        Protocol::Gemini => {
+            has_message && str_field("status") && error.and_then(|e| e.get("code")).is_some_and(Value::is_number)
+        }
+    }
+}
+
+/// 解析错误响应体: 整体为 JSON，或形如 `HTTP 429: {...}` 的文本前缀加上游 JSON
+fn parse_body(text: &str) -> (Option<Value>, String) {
+    let text = text.trim();
+    if let Ok(value) = serde_json::from_str::<Value>(text) {
+        return (Some(value), String::new());
+    }
+    if let Some(start) = text.find(['{', '[']) {
+        if let Ok(value) = serde_json::from_str::<Value>(&text[start..]) {
+            return (Some(value), text[..start].trim_end().to_string());
+        }
+    }
+    (None, text.to_string())
+}
+
+/// 按协议渲染错误响应体
+pub fn render(protocol: Protocol, status: StatusCode, kind: ErrorKind, message: &str) -> Value {
+    match protocol {
+        Protocol::OpenAI => {
+            let (error_type, code) = kind.openai();
+            json!({
+                "error": {
+                    "message": message,
+                    "type": error_type,
+                    "code": code,
+                    "param": null,
+                }
+            })
+        }
+        Protocol::Anthropic => json!({
+            "type": "error",
+            "error": {
+                "type": kind.anthropic(),
+                "message": message,
+            }
+        }),
+        Protocol::Gemini => json!({
+            "error": {
+                "code": status.as_u16(),
+                "message": message,
+                "status": kind.gemini(),
+            }
+        }),
+    }
+}
+
+/// 把错误响应改写为协议原生格式；已是原生格式且状态码无需修正时返回 None
+pub fn normalize(protocol: Protocol, status: StatusCode, body: &[u8]) -> Option<(StatusCode, Value)> {
+    let text = String::from_utf8_lossy(body);
+    let (value, prefix) = parse_body(&text);
+    let extracted = value.as_ref().map(extract);
+
+    let mut corrected = status;
+    // 处理器兜底返回的 500 以上游 RPC 状态为准 (如 RESOURCE_EXHAUSTED -> 429)
+    if status == StatusCode::INTERNAL_SERVER_ERROR {
+        if let Some(code) = extracted
+            .as_ref()
+            .and_then(|e| e.google_status.as_deref())
+            .and_then(google_status_code)
+        {
+            corrected = code;
+        }
+    }
+    // 529 (过载) 是 Anthropic 专有状态码，其他协议的客户端不认识
+    if corrected.as_u16() == 529 && protocol != Protocol::Anthropic {
+        corrected = StatusCode::SERVICE_UNAVAILABLE;
+    }
+
+    if prefix.is_empty() && corrected == status && value.as_ref().is_some_and(|v| is_native(protocol, v)) {
+        return None;
+    }
+
+    let inner = extracted.and_then(|e| e.message).filter(|m| !m.trim().is_empty());
+    let message = match (prefix.is_empty(), inner) {
+        (true, Some(inner)) => inner,
+        (false, Some(inner)) => format!("{} {}", prefix, inner),
+        (false, None) => prefix,
+        (true, None) => corrected.canonical_reason().unwrap_or("Error").to_string(),
+    };
+    let kind = ErrorKind::from_status(corrected);
+    Some((corrected, render(protocol, corrected, kind, &message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOOGLE_429: &str = r#"{"error": {"code": 429, "message": "Resource has been exhausted (e.g. check quota).", "status": "RESOURCE_EXHAUSTED"}}"#;
+
+    #[test]
+    fn protocol_follows_path() {
+        assert_eq!(Protocol::from_path("/v1/messages"), Some(Protocol::Anthropic));
+        assert_eq!(Protocol::from_path("/v1/messages/count_tokens"), Some(Protocol::Anthropic));
+        assert_eq!(Protocol::from_path("/v1/chat/completions"), Some(Protocol::OpenAI));
+        assert_eq!(
+            Protocol::from_path("/v1beta/models/gemini-2.5-pro:generateContent"),
+            Some(Protocol::Gemini)
+        );
+        assert_eq!(Protocol::from_path("/admin/status"), None);
+    }
+
+    #[test]
+    fn upstream_text_becomes_openai_error() {
+        let body = format!("All accounts exhausted. Last error: HTTP 429: {}", GOOGLE_429);
+        let (status, value) =
+            normalize(Protocol::OpenAI, StatusCode::TOO_MANY_REQUESTS, body.as_bytes()).unwrap();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(value["error"]["type"], "rate_limit_error");
+        assert_eq!(value["error"]["code"], "rate_limit_exceeded");
+        assert_eq!(
+            value["error"]["message"],
+            "All accounts exhausted. Last error: HTTP 429: Resource has been exhausted (e.g. check quota)."
+        );
+    }
+
+    #[test]
+    fn generic_500_uses_upstream_status() {
+        let (status, value) =
+            normalize(Protocol::Anthropic, StatusCode::INTERNAL_SERVER_ERROR, GOOGLE_429.as_bytes()).unwrap();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(value["type"], "error");
+        assert_eq!(value["error"]["type"], "rate_limit_error");
+    }
+
+    #[test]
+    fn native_errors_pass_through() {
+        assert!(normalize(Protocol::Gemini, StatusCode::TOO_MANY_REQUESTS, GOOGLE_429.as_bytes()).is_none());
+        let anthropic = r#"{"type":"error","error":{"type":"overloaded_error","message":"busy"}}"#;
+        assert!(normalize(Protocol::Anthropic, StatusCode::SERVICE_UNAVAILABLE, anthropic.as_bytes()).is_none());
+
+        // 其他协议格式的错误仍需转换
+        let (_, value) =
+            normalize(Protocol::Gemini, StatusCode::SERVICE_UNAVAILABLE, anthropic.as_bytes()).unwrap();
+        assert_eq!(value["error"]["status"], "UNAVAILABLE");
+        assert_eq!(value["error"]["code"], 503);
+        assert_eq!(value["error"]["message"], "busy");
+    }
+
+    #[test]
+    fn empty_and_overloaded_bodies() {
+        let (status, value) = normalize(Protocol::OpenAI, StatusCode::UNAUTHORIZED, b"").unwrap();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(value["error"]["message"], "Unauthorized");
+        assert_eq!(value["error"]["code"], "invalid_api_key");
+
+        let overloaded = StatusCode::from_u16(529).unwrap();
+        let (status, value) = normalize(Protocol::OpenAI, overloaded, b"overloaded").unwrap();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(value["error"]["message"], "overloaded");
+        let (status, _) = normalize(Protocol::Anthropic, overloaded, b"overloaded").unwrap();
+        assert_eq!(status.as_u16(), 529);
+    }
+}
@@ -0,0 +1,204 @@
+// 反代事件总线: 账号状态变化、请求生命周期与配置热更新，供管理端点 (`/admin/events`，SSE / WebSocket)
+// 与 GUI (Tauri 事件 `proxy://event`) 实时订阅，无需轮询
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::broadcast;
+
+/// 订阅者落后超过该数量时丢弃旧事件
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Tauri 前端事件名
+pub const TAURI_EVENT: &str = "proxy://event";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProxyEvent {
+    RequestStarted {
+        id: String,
+        method: String,
+        path: String,
+        model: Option<String>,
+    },
+    RequestFinished {
+        id: String,
+        status: u16,
+        duration_ms: u64,
+        model: Option<String>,
+        account: Option<String>,
+    },
+    /// 账号被限流或临时锁定，`until` 为预计恢复时间 (Unix 秒)
+    AccountRateLimited {
+        account: String,
+        status: u16,
+        until: i64,
+    },
+    AccountDisabled {
+        account: String,
+        reason: String,
+    },
+    AccountRecovered {
+        account: String,
+    },
+    /// 账号池重新加载完成
+    AccountsReloaded {
+        count: usize,
+    },
+    ConfigReloaded,
+}
+
+impl ProxyEvent {
+    /// 事件类别，用于订阅过滤: `request`、`account`、`config`
+    pub fn topic(&self) -> &'static str {
+        match self {
+            Self::RequestStarted { .. } | Self::RequestFinished { .. } => "request",
+            Self::AccountRateLimited { .. }
+            | Self::AccountDisabled { .. }
+            | Self::AccountRecovered { .. }
+            | Self::AccountsReloaded { .. } => "account",
+            Self::ConfigReloaded => "config",
+        }
+    }
+}
+
+/// 带序号与时间戳的事件 (序号在进程内递增，订阅者可据此发现丢失)
+#[derive(Debug, Clone, Serialize)]
+pub struct EventEnvelope {
+    pub seq: u64,
+    /// Unix 毫秒
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub event: ProxyEvent,
+}
+
+pub struct EventBus {
+    tx: broadcast::Sender<EventEnvelope>,
+    seq: AtomicU64,
+}
+
+impl EventBus {
+    fn new() -> Self {
+        Self {
+            tx: broadcast::channel(CHANNEL_CAPACITY).0,
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    /// 发布事件 (无订阅者时忽略)
+    pub fn publish(&self, event: ProxyEvent) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        let _ = self.tx.send(EventEnvelope {
+            seq: self.seq.fetch_add(1, Ordering::Relaxed) + 1,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            event,
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.tx.subscribe()
+    }
+}
+
+static GLOBAL_BUS: Lazy<EventBus> = Lazy::new(EventBus::new);
+
+/// 全局事件总线
+pub fn global() -> &'static EventBus {
+    &GLOBAL_BUS
+}
+
+pub fn publish(event: ProxyEvent) {
+    global().publish(event);
+}
+
+/// 解析逗号分隔的订阅类别，为空时订阅全部
+pub fn parse_topics(value: Option<&str>) -> Result<Vec<String>, String> {
+    let topics: Vec<String> = value
+        .unwrap_or_default()
+        .split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if let Some(unknown) = topics.iter().find(|t| !matches!(t.as_str(), "request" | "account" | "config")) {
+        return Err(format!("Unknown event topic: {} (expected request, account or config)", unknown));
+    }
+    Ok(topics)
+}
+
+pub fn matches_topics(topics: &[String], event: &ProxyEvent) -> bool {
+    topics.is_empty() || topics.iter().any(|t| t == event.topic())
+}
+
+/// 将总线事件转发给 GUI 前端 (请求生命周期事件频繁，仅转发账号与配置事件；请求日志已由监控推送)
+pub fn bridge_to_tauri(app: tauri::AppHandle) {
+    use tauri::Emitter;
+
+    let mut rx = global().subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(envelope) if envelope.event.topic() != "request" => {
+                    let _ = app.emit(TAURI_EVENT, &envelope);
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::debug!("事件桥接落后，丢弃 {} 条事件", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_flattens_event_with_type_tag() {
+        let envelope = EventEnvelope {
+            seq: 7,
+            timestamp: 1_700_000_000_000,
+            event: ProxyEvent::AccountDisabled {
+                account: "a@example.com".to_string(),
+                reason: "invalid_grant".to_string(),
+            },
+        };
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["type"], "account_disabled");
+        assert_eq!(json["seq"], 7);
+        assert_eq!(json["account"], "a@example.com");
+        assert_eq!(serde_json::to_value(ProxyEvent::ConfigReloaded).unwrap()["type"], "config_reloaded");
+    }
+
+    #[test]
+    fn filters_by_topic() {
+        let topics = parse_topics(Some("account, config")).unwrap();
+        assert!(matches_topics(&topics, &ProxyEvent::AccountsReloaded { count: 3 }));
+        assert!(!matches_topics(
+            &topics,
+            &ProxyEvent::RequestStarted {
+                id: "1".to_string(),
+                method: "POST".to_string(),
+                path: "/v1/messages".to_string(),
+                model: None,
+            }
+        ));
+        assert!(matches_topics(&parse_topics(None).unwrap(), &ProxyEvent::ConfigReloaded));
+        assert!(parse_topics(Some("requests")).is_err());
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_events_in_order() {
+        let bus = EventBus::new();
+        bus.publish(ProxyEvent::ConfigReloaded);
+        let mut rx = bus.subscribe();
+        bus.publish(ProxyEvent::AccountsReloaded { count: 1 });
+        bus.publish(ProxyEvent::ConfigReloaded);
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!(first.event, ProxyEvent::AccountsReloaded { count: 1 });
+        assert_eq!(second.seq, first.seq + 1);
+    }
+}
@@ -0,0 +1,468 @@
+// gRPC 管理接口: 对应 /admin REST 接口 (账号、统计、配置、生命周期)，便于接入以 gRPC 为控制面的基础设施
+//
+// 管理接口随进程运行，不随反代实例启停，因此可以通过 StopProxy / StartProxy 控制反代本身；
+// 鉴权由拦截器统一处理，要求不受范围限制的反代 API Key。
+use futures::future::BoxFuture;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+use tonic::service::Interceptor;
+use tonic::{Request, Response, Status};
+
+use crate::modules;
+use crate::proxy::config::GrpcConfig;
+use crate::proxy::monitor::ProxyMonitor;
+use crate::proxy::security::{ProxySecurityConfig, SecurityStates};
+use crate::proxy::token_manager::TokenManager;
+
+pub mod pb {
+    tonic::include_proto!("antigravity.admin.v1");
+}
+
+use pb::admin_service_server::{AdminService, AdminServiceServer};
+
+/// 运行中的反代实例 (管理接口查询账号池、统计与更新鉴权配置时使用)
+pub struct ProxyRuntime {
+    pub port: u16,
+    pub token_manager: Arc<TokenManager>,
+    pub monitor: Arc<ProxyMonitor>,
+    pub security: Arc<SecurityStates>,
+}
+
+/// 反代生命周期控制，由 GUI / 无头模式各自提供 (与其启停命令共用同一实例状态)
+pub trait ProxyControl: Send + Sync + 'static {
+    /// 当前生效的鉴权配置；无法确定时 (如配置读取失败) 为 None，拦截器拒绝所有调用
+    fn security(&self) -> Option<ProxySecurityConfig>;
+    /// 运行中的反代，未运行为 None
+    fn runtime(&self) -> BoxFuture<'_, Option<ProxyRuntime>>;
+    fn start(&self) -> BoxFuture<'_, Result<(), String>>;
+    fn stop(&self) -> BoxFuture<'_, Result<(), String>>;
+}
+
+/// 鉴权拦截器: 要求 `authorization: Bearer <key>` 为不受范围限制的反代 API Key (与鉴权模式无关)
+#[derive(Clone)]
+pub struct AdminAuth {
+    control: Arc<dyn ProxyControl>,
+}
+
+impl Interceptor for AdminAuth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        authorize(self.control.security(), request.metadata())?;
+        Ok(request)
+    }
+}
+
+fn authorize(security: Option<ProxySecurityConfig>, metadata: &MetadataMap) -> Result<(), Status> {
+    let provided = metadata
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.strip_prefix("Bearer ").unwrap_or(v).trim())
+        .unwrap_or("");
+    let Some(security) = security.filter(|s| !s.api_key.is_empty()) else {
+        return Err(Status::unavailable("admin authentication is not configured"));
+    };
+    if provided.is_empty() {
+        return Err(Status::unauthenticated("missing API key"));
+    }
+    // 拦截器为同步调用: 需要执行 argon2 时在多线程运行时中让出工作线程
+    let multi_thread = tokio::runtime::Handle::try_current()
+        .is_ok_and(|h| h.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread);
+    let resolved = if multi_thread {
+        tokio::task::block_in_place(|| security.resolve(provided))
+    } else {
+        security.resolve(provided)
+    };
+    // 附加密钥带有权限范围，不开放管理接口
+    match resolved {
+        Some(key) if key.scopes.is_empty() => Ok(()),
+        Some(_) => Err(Status::permission_denied("API key scope does not allow admin access")),
+        None => Err(Status::unauthenticated("invalid API key")),
+    }
+}
+
+pub struct AdminGrpc {
+    control: Arc<dyn ProxyControl>,
+}
+
+impl AdminGrpc {
+    async fn runtime(&self) -> Result<ProxyRuntime, Status> {
+        self.control
+            .runtime()
+            .await
+            .ok_or_else(|| Status::failed_precondition("proxy is not running"))
+    }
+
+    async fn status(&self) -> pb::ProxyStatus {
+        match self.control.runtime().await {
+            Some(runtime) => pb::ProxyStatus {
+                running: true,
+                port: runtime.port as u32,
+                active_accounts: runtime.token_manager.len() as u64,
+            },
+            None => pb::ProxyStatus {
+                running: false,
+                port: 0,
+                active_accounts: 0,
+            },
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl AdminService for AdminGrpc {
+    async fn list_accounts(
+        &self,
+        _request: Request<pb::ListAccountsRequest>,
+    ) -> Result<Response<pb::ListAccountsResponse>, Status> {
+        let accounts = tokio::task::spawn_blocking(modules::list_accounts)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(Status::internal)?;
+        // 反代未运行时所有账号都不在轮换池中
+        let pooled: HashSet<String> = match self.control.runtime().await {
+            Some(runtime) => runtime
+                .token_manager
+                .account_ids()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect(),
+            None => HashSet::new(),
+        };
+
+        let accounts = accounts
+            .into_iter()
+            .map(|a| pb::Account {
+                in_pool: pooled.contains(&a.id),
+                forbidden: a.quota.as_ref().map_or(false, |q| q.is_forbidden),
+                subscription_tier: a
+                    .quota
+                    .as_ref()
+                    .and_then(|q| q.subscription_tier.as_ref().map(|t| t.to_string()))
+                    .unwrap_or_default(),
+                quota_updated_at: a.quota.as_ref().map_or(0, |q| q.last_updated),
+                id: a.id,
+                email: a.email,
+                disabled: a.disabled,
+                proxy_disabled: a.proxy_disabled,
+            })
+            .collect();
+        Ok(Response::new(pb::ListAccountsResponse { accounts }))
+    }
+
+    async fn get_stats(
+        &self,
+        _request: Request<pb::GetStatsRequest>,
+    ) -> Result<Response<pb::Stats>, Status> {
+        let runtime = self.runtime().await?;
+        let stats = runtime.monitor.get_stats().await;
+        Ok(Response::new(pb::Stats {
+            total_requests: stats.total_requests,
+            success_count: stats.success_count,
+            error_count: stats.error_count,
+            active_accounts: runtime.token_manager.len() as u64,
+        }))
+    }
+
+    async fn get_config(
+        &self,
+        _request: Request<pb::GetConfigRequest>,
+    ) -> Result<Response<pb::GetConfigResponse>, Status> {
+        let config = modules::config::redacted_config().map_err(Status::internal)?;
+        let json = serde_json::to_string_pretty(&config).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(pb::GetConfigResponse { json }))
+    }
+
+    async fn reload_accounts(
+        &self,
+        _request: Request<pb::ReloadAccountsRequest>,
+    ) -> Result<Response<pb::ReloadAccountsResponse>, Status> {
+        let count = self
+            .runtime()
+            .await?
+            .token_manager
+            .load_accounts()
+            .await
+            .map_err(Status::internal)?;
+        Ok(Response::new(pb::ReloadAccountsResponse {
+            active_accounts: count as u64,
+        }))
+    }
+
+    async fn rotate_api_key(
+        &self,
+        request: Request<pb::RotateApiKeyRequest>,
+    ) -> Result<Response<pb::RotateApiKeyResponse>, Status> {
+        let grace = request.into_inner().grace_secs;
+        let (rotation, config) =
+            crate::proxy::key_rotation::rotate_and_save(grace, modules::audit::AuditActor::AdminApi)
+                .await
+                .map_err(Status::internal)?;
+        if let Some(runtime) = self.control.runtime().await {
+            runtime.security.update(&config).await;
+        }
+        Ok(Response::new(pb::RotateApiKeyResponse {
+            api_key: rotation.api_key,
+            previous_expires_at: rotation.previous_expires_at,
+        }))
+    }
+
+    async fn get_proxy_status(
+        &self,
+        _request: Request<pb::GetProxyStatusRequest>,
+    ) -> Result<Response<pb::ProxyStatus>, Status> {
+        Ok(Response::new(self.status().await))
+    }
+
+    async fn start_proxy(
+        &self,
+        _request: Request<pb::StartProxyRequest>,
+    ) -> Result<Response<pb::ProxyStatus>, Status> {
+        if self.control.runtime().await.is_some() {
+            return Err(Status::failed_precondition("proxy is already running"));
+        }
+        self.control.start().await.map_err(Status::internal)?;
+        Ok(Response::new(self.status().await))
+    }
+
+    async fn stop_proxy(
+        &self,
+        _request: Request<pb::StopProxyRequest>,
+    ) -> Result<Response<pb::ProxyStatus>, Status> {
+        if self.control.runtime().await.is_none() {
+            return Err(Status::failed_precondition("proxy is not running"));
+        }
+        self.control.stop().await.map_err(Status::internal)?;
+        Ok(Response::new(self.status().await))
+    }
+
+    async fn restart_proxy(
+        &self,
+        _request: Request<pb::RestartProxyRequest>,
+    ) -> Result<Response<pb::ProxyStatus>, Status> {
+        if self.control.runtime().await.is_some() {
+            self.control.stop().await.map_err(Status::internal)?;
+        }
+        self.control.start().await.map_err(Status::internal)?;
+        Ok(Response::new(self.status().await))
+    }
+}
+
+/// 在已绑定的监听器上运行管理接口，直到 `shutdown` 完成
+async fn serve(
+    listener: tokio::net::TcpListener,
+    tls: Option<tonic::transport::ServerTlsConfig>,
+    control: Arc<dyn ProxyControl>,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<(), String> {
+    let service = AdminServiceServer::with_interceptor(
+        AdminGrpc {
+            control: control.clone(),
+        },
+        AdminAuth { control },
+    );
+    let incoming = futures::stream::unfold(listener, |listener| async move {
+        let conn = listener.accept().await.map(|(stream, _)| stream);
+        Some((conn, listener))
+    });
+    let mut builder = tonic::transport::Server::builder();
+    if let Some(tls) = tls {
+        builder = builder
+            .tls_config(tls)
+            .map_err(|e| format!("gRPC TLS 配置无效: {}", e))?;
+    }
+    builder
+        .add_service(service)
+        .serve_with_incoming_shutdown(incoming, shutdown)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn load_tls(config: &GrpcConfig) -> Result<Option<tonic::transport::ServerTlsConfig>, String> {
+    let Some(tls) = &config.tls else {
+        return Ok(None);
+    };
+    let cert = std::fs::read(&tls.cert_path).map_err(|e| format!("读取 gRPC 证书失败 {}: {}", tls.cert_path, e))?;
+    let key = std::fs::read(&tls.key_path).map_err(|e| format!("读取 gRPC 私钥失败 {}: {}", tls.key_path, e))?;
+    Ok(Some(
+        tonic::transport::ServerTlsConfig::new().identity(tonic::transport::Identity::from_pem(cert, key)),
+    ))
+}
+
+/// 启动 gRPC 管理接口；返回的发送端被释放 (或发送) 时服务停止
+pub async fn start(
+    config: &GrpcConfig,
+    control: Arc<dyn ProxyControl>,
+) -> Result<tokio::sync::oneshot::Sender<()>, String> {
+    let addr: SocketAddr = config
+        .bind
+        .parse()
+        .map_err(|e| format!("无效的 gRPC 监听地址 {}: {}", config.bind, e))?;
+    let tls = load_tls(config)?;
+    if tls.is_none() && !addr.ip().is_loopback() {
+        tracing::warn!("gRPC 管理接口监听非回环地址 {} 且未启用 TLS，API Key 将以明文传输", addr);
+    }
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("gRPC 管理接口监听 {} 失败: {}", addr, e))?;
+    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+    tokio::spawn(async move {
+        tracing::info!("gRPC 管理接口已启动: {}", addr);
+        let shutdown = async {
+            let _ = rx.await;
+        };
+        match serve(listener, tls, control, shutdown).await {
+            Ok(()) => tracing::info!("gRPC 管理接口已停止"),
+            Err(e) => tracing::error!("gRPC 管理接口运行失败: {}", e),
+        }
+    });
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::storage::MemoryStorage;
+    use crate::proxy::config::{KeyScope, ProxyAuthMode, ScopedApiKey};
+    use std::sync::Mutex;
+
+    /// 不启动真实反代的生命周期控制
+    struct FakeControl {
+        security: ProxySecurityConfig,
+        runtime: Mutex<Option<(Arc<TokenManager>, Arc<ProxyMonitor>, Arc<SecurityStates>)>>,
+    }
+
+    impl FakeControl {
+        fn new() -> Self {
+            Self {
+                security: ProxySecurityConfig {
+                    auth_mode: ProxyAuthMode::Off,
+                    api_key: "sk-admin-0123456789".to_string(),
+                    previous_keys: Vec::new(),
+                    scoped_keys: vec![ScopedApiKey {
+                        name: "chat".to_string(),
+                        key: "sk-chat-0123456789".to_string(),
+                        scopes: vec![KeyScope::ChatOnly],
+                        created_at: 0,
+                    }],
+                    allow_lan_access: false,
+                    anthropic_versions: Vec::new(),
+                    allow_account_pinning: false,
+                    cluster_secret: String::new(),
+                },
+                runtime: Mutex::new(None),
+            }
+        }
+    }
+
+    impl ProxyControl for FakeControl {
+        fn security(&self) -> Option<ProxySecurityConfig> {
+            Some(self.security.clone())
+        }
+
+        fn runtime(&self) -> BoxFuture<'_, Option<ProxyRuntime>> {
+            let runtime = self.runtime.lock().unwrap().clone();
+            Box::pin(async move {
+                runtime.map(|(token_manager, monitor, security)| ProxyRuntime {
+                    port: 8045,
+                    token_manager,
+                    monitor,
+                    security,
+                })
+            })
+        }
+
+        fn start(&self) -> BoxFuture<'_, Result<(), String>> {
+            *self.runtime.lock().unwrap() = Some((
+                Arc::new(TokenManager::new(Arc::new(MemoryStorage::new()))),
+                Arc::new(ProxyMonitor::new(10, None)),
+                Arc::new(SecurityStates::new(self.security.clone(), &[])),
+            ));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn stop(&self) -> BoxFuture<'_, Result<(), String>> {
+            *self.runtime.lock().unwrap() = None;
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn with_key<T>(message: T, key: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .metadata_mut()
+            .insert("authorization", format!("Bearer {}", key).parse().unwrap());
+        request
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn client_controls_lifecycle_with_admin_key() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, None, Arc::new(FakeControl::new()), async {
+            let _ = rx.await;
+        }));
+
+        let mut client = pb::admin_service_client::AdminServiceClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap();
+
+        // 缺少密钥 / 无效密钥 / 带权限范围的附加密钥均被拦截器拒绝
+        let missing = client.get_proxy_status(pb::GetProxyStatusRequest {}).await.unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::Unauthenticated);
+        let invalid = client
+            .get_proxy_status(with_key(pb::GetProxyStatusRequest {}, "sk-wrong"))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.code(), tonic::Code::Unauthenticated);
+        let scoped = client
+            .stop_proxy(with_key(pb::StopProxyRequest {}, "sk-chat-0123456789"))
+            .await
+            .unwrap_err();
+        assert_eq!(scoped.code(), tonic::Code::PermissionDenied);
+
+        let key = "sk-admin-0123456789";
+        let status = client
+            .get_proxy_status(with_key(pb::GetProxyStatusRequest {}, key))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!status.running);
+        let stopped = client.stop_proxy(with_key(pb::StopProxyRequest {}, key)).await.unwrap_err();
+        assert_eq!(stopped.code(), tonic::Code::FailedPrecondition);
+
+        let started = client
+            .start_proxy(with_key(pb::StartProxyRequest {}, key))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(started.running);
+        assert_eq!(started.port, 8045);
+        let again = client.start_proxy(with_key(pb::StartProxyRequest {}, key)).await.unwrap_err();
+        assert_eq!(again.code(), tonic::Code::FailedPrecondition);
+
+        let restarted = client
+            .restart_proxy(with_key(pb::RestartProxyRequest {}, key))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(restarted.running);
+        let stats = client
+            .get_stats(with_key(pb::GetStatsRequest {}, key))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(stats.total_requests, 0);
+
+        let stopped = client
+            .stop_proxy(with_key(pb::StopProxyRequest {}, key))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!stopped.running);
+
+        let _ = tx.send(());
+        server.await.unwrap().unwrap();
+    }
+}
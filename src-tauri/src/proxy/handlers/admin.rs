@@ -0,0 +1,508 @@
+// 管理端点 (实时日志等)，与 API 端点共用鉴权中间件
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+
+use crate::proxy::monitor::{LogFilter, ProxyRequestLog};
+use crate::proxy::replay::{self, ReplayResult};
+use crate::proxy::server::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct LogStreamQuery {
+    /// 逗号分隔的过滤条件，如 `model=gemini-2.0-pro,status=5xx`
+    pub filter: Option<String>,
+    /// 连接后先推送最近的 N 条历史日志
+    pub backlog: Option<usize>,
+}
+
+/// GET /admin/logs/stream — 以 SSE 实时推送请求日志 (event: log)
+pub async fn handle_logs_stream(
+    State(state): State<AppState>,
+    Query(query): Query<LogStreamQuery>,
+) -> Response {
+    let exprs: Vec<&str> = query
+        .filter
+        .as_deref()
+        .map(|f| f.split(',').collect())
+        .unwrap_or_default();
+    let filter = match LogFilter::parse(&exprs) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    // 先订阅再读取历史，避免两者之间的日志丢失
+    let live = BroadcastStream::new(state.monitor.subscribe());
+
+    let mut backlog = match query.backlog {
+        Some(n) if n > 0 => state.monitor.get_logs(n).await,
+        _ => Vec::new(),
+    };
+    backlog.reverse();
+
+    let mut initial = Vec::new();
+    if !state.monitor.is_enabled() {
+        initial.push(Event::default().comment("request logging is disabled; enable it to receive live entries"));
+    }
+    let backlog_filter = filter.clone();
+    initial.extend(
+        backlog
+            .into_iter()
+            .filter(|log| backlog_filter.matches(log))
+            .filter_map(|log| Event::default().event("log").json_data(&log).ok()),
+    );
+
+    let live = live.filter_map(move |item| {
+        let event = match item {
+            Ok(log) if filter.matches(&log) => Event::default().event("log").json_data(&log).ok(),
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(n)) => {
+                Some(Event::default().event("lagged").data(n.to_string()))
+            }
+        };
+        futures::future::ready(event)
+    });
+
+    let stream = futures::stream::iter(initial)
+        .chain(live)
+        .map(Ok::<Event, std::convert::Infallible>);
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayQuery {
+    /// 指定执行的账号 (account_id 或 email)
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// 逗号分隔的事件类别 (`request`、`account`、`config`)，缺省为全部
+    pub topics: Option<String>,
+}
+
+/// GET /admin/events — 订阅反代事件总线；WebSocket 升级请求逐条推送 JSON 文本帧，否则以 SSE 推送 (event: 事件类型)
+pub async fn handle_events(Query(query): Query<EventsQuery>, ws: Option<WebSocketUpgrade>) -> Response {
+    use crate::proxy::events;
+
+    let topics = match events::parse_topics(query.topics.as_deref()) {
+        Ok(topics) => topics,
+        Err(e) => return admin_error(StatusCode::BAD_REQUEST, e),
+    };
+    let rx = events::global().subscribe();
+    if let Some(ws) = ws {
+        return ws.on_upgrade(move |socket| forward_events(socket, rx, topics));
+    }
+
+    let live = BroadcastStream::new(rx).filter_map(move |item| {
+        let event = match item {
+            Ok(envelope) if events::matches_topics(&topics, &envelope.event) => {
+                let name = serde_json::to_value(&envelope.event)
+                    .ok()
+                    .and_then(|v| v["type"].as_str().map(str::to_string))
+                    .unwrap_or_default();
+                Event::default().event(name).json_data(&envelope).ok()
+            }
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(n)) => {
+                Some(Event::default().event("lagged").data(n.to_string()))
+            }
+        };
+        futures::future::ready(event)
+    });
+    Sse::new(live.map(Ok::<Event, std::convert::Infallible>))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn forward_events(
+    mut socket: WebSocket,
+    mut rx: tokio::sync::broadcast::Receiver<crate::proxy::events::EventEnvelope>,
+    topics: Vec<String>,
+) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    loop {
+        tokio::select! {
+            received = rx.recv() => {
+                let text = match received {
+                    Ok(envelope) if crate::proxy::events::matches_topics(&topics, &envelope.event) => {
+                        match serde_json::to_string(&envelope) {
+                            Ok(text) => text,
+                            Err(_) => continue,
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(n)) => json!({ "type": "lagged", "missed": n }).to_string(),
+                    Err(RecvError::Closed) => break,
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            // 客户端只需接收；收到关闭帧或连接断开时结束
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+fn admin_error(status: StatusCode, message: String) -> Response {
+    (status, axum::Json(json!({ "error": { "message": message } }))).into_response()
+}
+
+/// 先查内存中的近期日志，再查持久化存储
+async fn find_log(state: &AppState, id: &str) -> Result<Option<ProxyRequestLog>, String> {
+    if let Some(log) = state.monitor.logs.read().await.iter().find(|l| l.id == id) {
+        return Ok(Some(log.clone()));
+    }
+    let id = id.to_string();
+    tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_log(&id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// POST /admin/logs/:id/replay — 用当前管线重放已捕获的请求，并与原始响应对比
+pub async fn handle_replay(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> Response {
+    use tower::Service;
+
+    let log = match find_log(&state, &id).await {
+        Ok(Some(log)) => log,
+        Ok(None) => return admin_error(StatusCode::NOT_FOUND, format!("Log not found: {}", id)),
+        Err(e) => return admin_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let request = match replay::build_request(&log) {
+        Ok(request) => request,
+        Err(e) => return admin_error(StatusCode::BAD_REQUEST, e),
+    };
+    let Some(router) = state.replay_router.get().and_then(|r| r.upgrade()) else {
+        return admin_error(StatusCode::SERVICE_UNAVAILABLE, "Proxy is shutting down".to_string());
+    };
+
+    tracing::info!("[Replay] 重放请求 {} {} (account: {:?})", log.method, log.url, query.account);
+    let start = std::time::Instant::now();
+    let mut router = (*router).clone();
+    let response = match query.account.clone() {
+        Some(account) => {
+            crate::proxy::token_manager::with_pinned_account(account, router.call(request)).await
+        }
+        None => router.call(request).await,
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+
+    let status = response.status().as_u16();
+    let body = match axum::body::to_bytes(response.into_body(), 100 * 1024 * 1024).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        Err(e) => return admin_error(StatusCode::BAD_GATEWAY, format!("Failed to read replay response: {}", e)),
+    };
+    let duration = start.elapsed().as_millis() as u64;
+
+    let original = replay::normalize_body(log.response_body.as_deref().unwrap_or(""));
+    let current = replay::normalize_body(&body);
+    let diff = replay::diff_lines(&original, &current);
+
+    axum::Json(ReplayResult {
+        id: log.id,
+        method: log.method,
+        url: log.url,
+        account: query.account,
+        original_status: log.status,
+        status,
+        duration,
+        identical: diff.is_empty() && status == log.status,
+        diff,
+        response_body: body,
+    })
+    .into_response()
+}
+
+/// GET /admin/status — 运行时长、在途请求、请求计数与账号池健康状况
+pub async fn handle_status(State(state): State<AppState>) -> Response {
+    axum::Json(runtime_status(&state).await).into_response()
+}
+
+async fn runtime_status(state: &AppState) -> crate::proxy::status::RuntimeStatus {
+    let now = chrono::Utc::now().timestamp();
+    crate::proxy::status::RuntimeStatus {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at: state.started_at,
+        uptime_secs: now - state.started_at,
+        active_requests: state.admission.active(),
+        queued_requests: state.admission.queued(),
+        requests: state.monitor.get_stats().await,
+        accounts: state.token_manager.pool_health(),
+        cluster_instance: state.token_manager.cluster().map(|c| c.instance_id().to_string()),
+        streams: crate::proxy::streaming::stats(),
+    }
+}
+
+/// GET /dashboard — 内置状态面板页面
+pub async fn handle_dashboard() -> Response {
+    axum::response::Html(crate::proxy::dashboard::DASHBOARD_HTML).into_response()
+}
+
+/// GET /dashboard/data — 状态面板数据: 运行状态、24 小时流量、各模型剩余配额与最近的错误
+pub async fn handle_dashboard_data(State(state): State<AppState>) -> Response {
+    use crate::proxy::dashboard::{DashboardData, ModelQuota, RecentError, RECENT_ERRORS};
+
+    let since = chrono::Utc::now().timestamp_millis() - 23 * 3600 * 1000;
+    let traffic = crate::modules::proxy_db::get_rollups(crate::proxy::stats::Period::Hour, since)
+        .map(|rollups| crate::proxy::stats::trend(&rollups))
+        .unwrap_or_else(|e| {
+            tracing::warn!("读取统计汇总失败: {}", e);
+            Vec::new()
+        });
+    let recent_errors = crate::modules::proxy_db::get_recent_errors(RECENT_ERRORS)
+        .unwrap_or_default()
+        .into_iter()
+        .map(RecentError::from)
+        .collect();
+    let models = crate::proxy::handlers::common::listed_models(&state)
+        .await
+        .into_iter()
+        .map(|(model, quota)| ModelQuota { model, quota })
+        .collect();
+
+    axum::Json(DashboardData {
+        status: runtime_status(&state).await,
+        traffic,
+        models,
+        recent_errors,
+    })
+    .into_response()
+}
+
+/// GET /admin/latency — 各账号上游延迟 (滚动 p50/p95)，按 p50 升序
+pub async fn handle_latency(State(state): State<AppState>) -> Response {
+    let accounts: Vec<_> = state
+        .token_manager
+        .latency_stats()
+        .into_iter()
+        .map(|(email, stats)| json!({ "email": email, "p50_ms": stats.p50_ms, "p95_ms": stats.p95_ms, "samples": stats.samples }))
+        .collect();
+    axum::Json(json!({ "accounts": accounts })).into_response()
+}
+
+/// GET /stats/models — 各上游模型滚动窗口内的延迟分位数、成功率与吞吐
+pub async fn handle_model_stats(State(state): State<AppState>) -> Response {
+    axum::Json(json!({
+        "window_secs": crate::proxy::model_stats::WINDOW_SECS,
+        "models": state.model_stats.snapshot(),
+    }))
+    .into_response()
+}
+
+/// GET /admin/usage — 当前窗口内各账号的请求数与 Token 用量 (用量上限)
+pub async fn handle_usage(State(state): State<AppState>) -> Response {
+    let accounts: Vec<_> = state
+        .token_manager
+        .usage_snapshot()
+        .await
+        .into_iter()
+        .map(|(email, w)| json!({ "email": email, "window_start": w.started_at, "requests": w.requests, "tokens": w.tokens }))
+        .collect();
+    axum::Json(json!({ "accounts": accounts })).into_response()
+}
+
+/// GET /admin/quota-cache — 各账号配额快照的时间与是否正在后台拉取
+pub async fn handle_quota_cache(State(state): State<AppState>) -> Response {
+    let now = chrono::Utc::now().timestamp();
+    let accounts: Vec<_> = state
+        .token_manager
+        .quota_staleness()
+        .into_iter()
+        .map(|(email, updated_at)| {
+            json!({ "email": email, "updated_at": updated_at, "age_secs": updated_at.map(|ts| now - ts) })
+        })
+        .collect();
+    axum::Json(json!({ "accounts": accounts, "revalidating": state.token_manager.quota_revalidating() })).into_response()
+}
+
+/// POST /admin/cluster/events — 接收对等实例广播的冷却与用量事件 (集群共享密钥已由鉴权中间件校验)
+pub async fn handle_cluster_events(
+    State(state): State<AppState>,
+    axum::Json(message): axum::Json<crate::proxy::cluster::ClusterMessage>,
+) -> Response {
+    if state.token_manager.apply_cluster_message(message) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        (StatusCode::CONFLICT, "cluster sync is not enabled on this instance").into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionQuery {
+    pub limit: Option<usize>,
+}
+
+/// GET /admin/sessions — 按会话汇总请求数与 Token 用量
+pub async fn handle_list_sessions(
+    State(state): State<AppState>,
+    Query(query): Query<SessionQuery>,
+) -> Response {
+    axum::Json(state.monitor.get_sessions(query.limit.unwrap_or(50)).await).into_response()
+}
+
+/// GET /admin/requests — 在途请求 (按持续时间从长到短)
+pub async fn handle_list_requests(State(state): State<AppState>) -> Response {
+    axum::Json(json!({ "requests": state.inflight.list() })).into_response()
+}
+
+/// POST /admin/requests/:id/cancel — 取消在途请求 (如卡住的流式请求)
+pub async fn handle_cancel_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    if state.inflight.cancel(&id) {
+        axum::Json(json!({ "id": id, "cancelled": true })).into_response()
+    } else {
+        admin_error(StatusCode::NOT_FOUND, format!("No active request with id {}", id))
+    }
+}
+
+/// GET /admin/mirror — 最近的流量镜像对比结果 (新的在前)
+pub async fn handle_mirror_results(
+    State(state): State<AppState>,
+    Query(query): Query<SessionQuery>,
+) -> Response {
+    let results = state.mirror.results(query.limit.unwrap_or(50));
+    let differing = results.iter().filter(|r| !r.identical).count();
+    axum::Json(json!({
+        "enabled": state.mirror.is_enabled(),
+        "differing": differing,
+        "results": results,
+    }))
+    .into_response()
+}
+
+/// GET /admin/sessions/:id — 会话内的请求 (按时间顺序) 及汇总
+pub async fn handle_session_logs(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<SessionQuery>,
+) -> Response {
+    let logs = state.monitor.get_session_logs(&id, query.limit.unwrap_or(500)).await;
+    let Some(summary) = crate::proxy::monitor::summarize_sessions(&logs).into_iter().next() else {
+        return admin_error(StatusCode::NOT_FOUND, format!("Session not found: {}", id));
+    };
+    axum::Json(json!({ "session": summary, "requests": logs })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RotateQuery {
+    /// 旧密钥宽限期 (秒)，缺省使用配置值
+    pub grace: Option<u64>,
+}
+
+/// GET /admin/keys — 当前密钥、宽限期内旧密钥与附加密钥的状态 (仅显示前缀)
+pub async fn handle_list_keys(State(state): State<AppState>) -> Response {
+    use crate::proxy::secrets::redact_secret;
+
+    let security = state.security.global.read().await.clone();
+    let now = chrono::Utc::now().timestamp();
+    let previous: Vec<_> = security
+        .previous_keys
+        .iter()
+        .filter(|k| k.expires_at > now)
+        .map(|k| {
+            json!({
+                "key": redact_secret(&k.key),
+                "expires_at": k.expires_at,
+                "expires_in": crate::models::quota::format_countdown(k.expires_at - now),
+            })
+        })
+        .collect();
+    let scoped: Vec<_> = security
+        .scoped_keys
+        .iter()
+        .map(|k| {
+            json!({
+                "name": k.name,
+                "key": redact_secret(&k.key),
+                "scopes": k.scopes,
+                "created_at": k.created_at,
+            })
+        })
+        .collect();
+    axum::Json(json!({
+        "current": redact_secret(&security.api_key),
+        "previous": previous,
+        "scoped": scoped,
+    }))
+    .into_response()
+}
+
+/// POST /admin/keys/rotate — 生成新密钥，旧密钥在宽限期内继续有效
+pub async fn handle_rotate_key(
+    State(state): State<AppState>,
+    Query(query): Query<RotateQuery>,
+) -> Response {
+    match crate::proxy::key_rotation::rotate_and_save(
+        query.grace,
+        crate::modules::audit::AuditActor::AdminApi,
+    )
+    .await
+    {
+        Ok((rotation, config)) => {
+            state.security.update(&config).await;
+            axum::Json(rotation).into_response()
+        }
+        Err(e) => admin_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogFilterRequest {
+    /// 附加在默认规则之上的过滤规则，如 `proxy::mappers=debug`
+    pub filter: String,
+    /// 自动恢复默认规则前的秒数，缺省为一直生效
+    pub duration_secs: Option<u64>,
+}
+
+/// GET /admin/log-filter — 当前生效的日志过滤规则
+pub async fn handle_get_log_filter() -> Response {
+    match crate::modules::logger::log_filter_status() {
+        Some(status) => axum::Json(status).into_response(),
+        None => admin_error(StatusCode::SERVICE_UNAVAILABLE, "Logger is not initialized".to_string()),
+    }
+}
+
+/// PUT /admin/log-filter — 临时调整日志详细程度，无需重启
+pub async fn handle_set_log_filter(axum::Json(request): axum::Json<LogFilterRequest>) -> Response {
+    let duration = request.duration_secs.map(std::time::Duration::from_secs);
+    match crate::modules::logger::set_log_filter(&request.filter, duration) {
+        Ok(status) => axum::Json(status).into_response(),
+        Err(e) => admin_error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+/// DELETE /admin/log-filter — 恢复启动时的日志过滤规则
+pub async fn handle_reset_log_filter() -> Response {
+    match crate::modules::logger::reset_log_filter() {
+        Ok(status) => axum::Json(status).into_response(),
+        Err(e) => admin_error(StatusCode::SERVICE_UNAVAILABLE, e),
+    }
+}
@@ -0,0 +1,140 @@
+// Anthropic Message Batches 端点
+use axum::{
+    body::Body,
+    extract::{Json, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::proxy::batches::{BatchRequest, BatchStatus};
+use crate::proxy::server::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBatchBody {
+    pub requests: Vec<BatchRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListBatchesQuery {
+    pub limit: Option<usize>,
+    pub before_id: Option<String>,
+    pub after_id: Option<String>,
+}
+
+fn error_response(status: StatusCode, error_type: &str, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "type": "error",
+            "error": { "type": error_type, "message": message }
+        })),
+    )
+        .into_response()
+}
+
+fn not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        "not_found_error",
+        &format!("Batch not found: {}", id),
+    )
+}
+
+/// POST /v1/messages/batches
+pub async fn handle_create_batch(
+    State(state): State<AppState>,
+    Json(body): Json<CreateBatchBody>,
+) -> Response {
+    match state.batches.create(body.requests) {
+        Ok(batch) => {
+            tracing::info!(
+                "[Batches] 创建批次 {}，共 {} 个请求",
+                batch.id,
+                batch.request_counts.processing
+            );
+            state.batches.spawn(state.clone(), batch.id.clone());
+            Json(batch).into_response()
+        }
+        Err(e) => error_response(StatusCode::BAD_REQUEST, "invalid_request_error", &e),
+    }
+}
+
+/// GET /v1/messages/batches
+pub async fn handle_list_batches(
+    State(state): State<AppState>,
+    Query(query): Query<ListBatchesQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(20).clamp(1, 1000);
+    let (data, has_more) = state.batches.list(
+        limit,
+        query.before_id.as_deref(),
+        query.after_id.as_deref(),
+    );
+    Json(json!({
+        "data": data,
+        "has_more": has_more,
+        "first_id": data.first().map(|b| b.id.clone()),
+        "last_id": data.last().map(|b| b.id.clone()),
+    }))
+    .into_response()
+}
+
+/// GET /v1/messages/batches/:id
+pub async fn handle_get_batch(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    match state.batches.get(&id) {
+        Some(batch) => Json(batch).into_response(),
+        None => not_found(&id),
+    }
+}
+
+/// POST /v1/messages/batches/:id/cancel
+pub async fn handle_cancel_batch(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.batches.cancel(&id) {
+        Some(batch) => Json(batch).into_response(),
+        None => not_found(&id),
+    }
+}
+
+/// DELETE /v1/messages/batches/:id
+pub async fn handle_delete_batch(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    if state.batches.get(&id).is_none() {
+        return not_found(&id);
+    }
+    match state.batches.delete(&id) {
+        Ok(()) => Json(json!({ "id": id, "type": "message_batch_deleted" })).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, "invalid_request_error", &e),
+    }
+}
+
+/// GET /v1/messages/batches/:id/results (JSONL)
+pub async fn handle_batch_results(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    let Some(batch) = state.batches.get(&id) else {
+        return not_found(&id);
+    };
+    if batch.processing_status != BatchStatus::Ended {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            &format!("Batch {} is still processing; results are available once it has ended", id),
+        );
+    }
+
+    let content = tokio::fs::read(state.batches.results_path(&id))
+        .await
+        .unwrap_or_default();
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-jsonl")
+        .body(Body::from(content))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
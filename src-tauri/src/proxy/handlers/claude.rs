@@ -19,7 +19,6 @@ use crate::proxy::server::AppState;
 use axum::http::HeaderMap;
 use std::sync::atomic::Ordering;
 
-const MAX_RETRY_ATTEMPTS: usize = 3;
 const MIN_SIGNATURE_LENGTH: usize = 10;  // 最小有效签名长度
 
 // ===== Model Constants for Background Tasks =====
@@ -183,11 +182,12 @@ enum RetryStrategy {
     ExponentialBackoff { base_ms: u64, max_ms: u64 },
 }
 
-/// 根据错误状态码和错误信息确定重试策略
+/// 根据错误状态码、错误信息与配置的重试策略确定退避方式
 fn determine_retry_strategy(
     status_code: u16,
     error_text: &str,
     retried_without_thinking: bool,
+    policy: &crate::proxy::config::RetryPolicyConfig,
 ) -> RetryStrategy {
     match status_code {
         // 400 错误：Thinking 签名失败
@@ -200,6 +200,9 @@ fn determine_retry_strategy(
             RetryStrategy::FixedDelay(Duration::from_millis(200))
         }
 
+        // 未配置为可重试的状态码
+        _ if !policy.should_retry(status_code) => RetryStrategy::NoRetry,
+
         // 429 限流错误
         429 => {
             // 优先使用服务端返回的 Retry-After
@@ -207,31 +210,34 @@ fn determine_retry_strategy(
                 let actual_delay = delay_ms.saturating_add(200).min(10_000);
                 RetryStrategy::FixedDelay(Duration::from_millis(actual_delay))
             } else {
-                // 否则使用线性退避：1s, 2s, 3s
-                RetryStrategy::LinearBackoff { base_ms: 1000 }
+                // 否则使用线性退避 (默认 1s, 2s, 3s)
+                RetryStrategy::LinearBackoff { base_ms: policy.backoff_base_ms }
             }
         }
 
         // 503 服务不可用 / 529 服务器过载
         503 | 529 => {
-            // 指数退避：1s, 2s, 4s, 8s
+            // 指数退避 (默认 1s, 2s, 4s, 8s)
             RetryStrategy::ExponentialBackoff {
-                base_ms: 1000,
-                max_ms: 8000,
+                base_ms: policy.backoff_base_ms,
+                max_ms: policy.backoff_max_ms,
             }
         }
 
         // 500 服务器内部错误
         500 => {
-            // 线性退避：500ms, 1s, 1.5s
-            RetryStrategy::LinearBackoff { base_ms: 500 }
+            // 线性退避 (默认 500ms, 1s, 1.5s)
+            RetryStrategy::LinearBackoff { base_ms: policy.backoff_base_ms / 2 }
         }
 
         // 401/403 认证/权限错误：可重试（轮换账号）
         401 | 403 => RetryStrategy::FixedDelay(Duration::from_millis(100)),
 
-        // 其他错误：不重试
-        _ => RetryStrategy::NoRetry,
+        // 其他配置为可重试的错误：按指数退避重试
+        _ => RetryStrategy::ExponentialBackoff {
+            base_ms: policy.backoff_base_ms,
+            max_ms: policy.backoff_max_ms,
+        },
     }
 }
 
@@ -239,6 +245,7 @@ fn determine_retry_strategy(
 async fn apply_retry_strategy(
     strategy: RetryStrategy,
     attempt: usize,
+    max_attempts: usize,
     status_code: u16,
     trace_id: &str,
 ) -> bool {
@@ -257,7 +264,7 @@ async fn apply_retry_strategy(
                 trace_id,
                 status_code,
                 attempt + 1,
-                MAX_RETRY_ATTEMPTS,
+                max_attempts,
                 base_ms,
                 jittered_ms
             );
@@ -266,14 +273,14 @@ async fn apply_retry_strategy(
         }
 
         RetryStrategy::LinearBackoff { base_ms } => {
-            let calculated_ms = base_ms * (attempt as u64 + 1);
+            let calculated_ms = base_ms.saturating_mul(attempt as u64 + 1);
             let jittered_ms = apply_jitter(calculated_ms);
             info!(
                 "[{}] ⏱️  Retry with linear backoff: status={}, attempt={}/{}, base={}ms, actual={}ms (jitter applied)",
                 trace_id,
                 status_code,
                 attempt + 1,
-                MAX_RETRY_ATTEMPTS,
+                max_attempts,
                 calculated_ms,
                 jittered_ms
             );
@@ -282,14 +289,16 @@ async fn apply_retry_strategy(
         }
 
         RetryStrategy::ExponentialBackoff { base_ms, max_ms } => {
-            let calculated_ms = (base_ms * 2_u64.pow(attempt as u32)).min(max_ms);
+            let calculated_ms = base_ms
+                .saturating_mul(2_u64.saturating_pow(attempt as u32))
+                .min(max_ms);
             let jittered_ms = apply_jitter(calculated_ms);
             info!(
                 "[{}] ⏱️  Retry with exponential backoff: status={}, attempt={}/{}, base={}ms, actual={}ms (jitter applied)",
                 trace_id,
                 status_code,
                 attempt + 1,
-                MAX_RETRY_ATTEMPTS,
+                max_attempts,
                 calculated_ms,
                 jittered_ms
             );
@@ -352,6 +361,7 @@ pub async fn handle_messages(
     };
 
     // [CRITICAL REFACTOR] 优先解析并过滤 Thinking 块，确保 z.ai 也是用修复后的 Body
+    let features = crate::proxy::model_capabilities::RequestFeatures::from_body(&body);
     let mut request: crate::proxy::mappers::claude::models::ClaudeRequest = match serde_json::from_value(body) {
         Ok(r) => r,
         Err(e) => {
@@ -488,16 +498,19 @@ pub async fn handle_messages(
 
     // 2. 获取 UpstreamClient
     let upstream = state.upstream.clone();
+    let timeouts = state.timeouts.read().await.resolve("/v1/messages");
     
     // 3. 准备闭包
     let mut request_for_body = request.clone();
     let token_manager = state.token_manager;
     
     let pool_size = token_manager.len();
-    let max_attempts = MAX_RETRY_ATTEMPTS.min(pool_size).max(1);
+    let retry_policy = token_manager.retry_policy().await;
+    let max_attempts = retry_policy.attempts(pool_size, request.stream);
 
     let mut last_error = String::new();
     let mut retried_without_thinking = false;
+    let mut previous_account: Option<String> = None;
     
     for attempt in 0..max_attempts {
         // 2. 模型路由与配置解析 (提前解析以确定请求类型)
@@ -505,6 +518,7 @@ pub async fn handle_messages(
         let initial_mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
             &request_for_body.model,
             &*state.custom_mapping.read().await,
+            &*state.mapping_rules.read().await,
             &*state.openai_mapping.read().await,
             &*state.anthropic_mapping.read().await,
             false,  // 先不应用家族映射
@@ -527,6 +541,7 @@ pub async fn handle_messages(
             crate::proxy::common::model_mapping::resolve_model_route(
                 &request_for_body.model,
                 &*state.custom_mapping.read().await,
+                &*state.mapping_rules.read().await,
                 &*state.openai_mapping.read().await,
                 &*state.anthropic_mapping.read().await,
                 true,  // CLI 请求应用家族映射
@@ -536,13 +551,35 @@ pub async fn handle_messages(
             initial_mapped_model
         };
 
+        // 超出上游模型能力的请求直接拒绝，不占用账号
+        if let Err(message) = token_manager.check_model_request(&mapped_model, &features).await {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "type": "error",
+                    "error": {
+                        "type": "invalid_request_error",
+                        "message": message
+                    }
+                }))
+            ).into_response();
+        }
+
         // 0. 尝试提取 session_id 用于粘性调度 (Phase 2/3)
         // 使用 SessionManager 生成稳定的会话指纹
         let session_id_str = crate::proxy::session_manager::SessionManager::extract_session_id(&request_for_body);
         let session_id = Some(session_id_str.as_str());
 
-        let force_rotate_token = attempt > 0;
-        let (access_token, project_id, email) = match token_manager.get_token(&config.request_type, force_rotate_token, session_id).await {
+        let (access_token, project_id, email) = match crate::proxy::upstream::retry::token_for_attempt(
+            &token_manager,
+            &retry_policy,
+            previous_account.as_deref(),
+            &config.request_type,
+            session_id,
+            Some(&config.final_model),
+        )
+        .await
+        {
             Ok(t) => t,
             Err(e) => {
                 let safe_message = if e.contains("invalid_grant") {
@@ -564,7 +601,7 @@ pub async fn handle_messages(
         };
 
         info!("✓ Using account: {} (type: {})", email, config.request_type);
-        
+        previous_account = Some(email.clone());
         
         // ===== 【优化】后台任务智能检测与降级 =====
         // 使用新的检测系统，支持 5 大类关键词和多 Flash 模型策略
@@ -629,7 +666,7 @@ pub async fn handle_messages(
         // 生成 Trace ID (简单用时间戳后缀)
         // let _trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
 
-        let gemini_body = match transform_claude_request_in(&request_with_mapped, &project_id) {
+        let mut gemini_body = match transform_claude_request_in(&request_with_mapped, &project_id) {
             Ok(b) => {
                 debug!("[{}] Transformed Gemini Body: {}", trace_id, serde_json::to_string_pretty(&b).unwrap_or_default());
                 b
@@ -648,18 +685,59 @@ pub async fn handle_messages(
             }
         };
         
+    if let Err(e) = crate::proxy::context_overflow::fit_request(
+        &token_manager,
+        &upstream,
+        &access_token,
+        &request_with_mapped.model,
+        &mut gemini_body,
+        &timeouts,
+    )
+    .await
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "type": "error",
+                "error": {
+                    "type": "invalid_request_error",
+                    "message": e
+                }
+            }))
+        ).into_response();
+    }
+
     // 4. 上游调用
     let is_stream = request.stream;
     let method = if is_stream { "streamGenerateContent" } else { "generateContent" };
     let query = if is_stream { Some("alt=sse") } else { None };
 
+    let resume = if is_stream {
+        crate::proxy::stream_resume::ResumeContext::new(
+            &token_manager,
+            &upstream,
+            &gemini_body,
+            &config.request_type,
+            &config.final_model,
+            &email,
+            &timeouts,
+        )
+    } else {
+        None
+    };
+
+    let upstream_start = std::time::Instant::now();
     let response = match upstream.call_v1_internal(
         method,
         &access_token,
         gemini_body,
-        query
+        query,
+        &timeouts,
     ).await {
-            Ok(r) => r,
+            Ok(r) => {
+                token_manager.record_latency(&email, upstream_start.elapsed());
+                r
+            }
             Err(e) => {
                 last_error = e.clone();
                 debug!("Request failed on attempt {}/{}: {}", attempt + 1, max_attempts, e);
@@ -673,8 +751,9 @@ pub async fn handle_messages(
         if status.is_success() {
             // 处理流式响应
             if request.stream {
-                let stream = response.bytes_stream();
-                let gemini_stream = Box::pin(stream);
+                let armed = resume.is_some();
+                let gemini_stream =
+                    crate::proxy::stream_resume::upstream_stream(response, &timeouts, resume);
                 let claude_stream = create_claude_sse_stream(gemini_stream, trace_id, email);
 
                 // 转换为 Bytes stream
@@ -684,8 +763,13 @@ pub async fn handle_messages(
                         Err(e) => Ok(Bytes::from(format!("data: {{\"error\":\"{}\"}}\n\n", e))),
                     }
                 });
+                let sse_stream = crate::proxy::timeouts::with_heartbeat(
+                    sse_stream,
+                    timeouts.heartbeat,
+                    crate::proxy::timeouts::CLAUDE_PING_HEARTBEAT,
+                );
 
-                return Response::builder()
+                return crate::proxy::stream_resume::annotate(Response::builder(), armed)
                     .status(StatusCode::OK)
                     .header(header::CONTENT_TYPE, "text/event-stream")
                     .header(header::CACHE_CONTROL, "no-cache")
@@ -751,6 +835,7 @@ pub async fn handle_messages(
         // 2. 获取错误文本并转移 Response 所有权
         let error_text = response.text().await.unwrap_or_else(|_| format!("HTTP {}", status));
         last_error = format!("HTTP {}: {}", status_code, error_text);
+        token_manager.record_upstream_error(&email, Some(status_code), &error_text);
         debug!("[{}] Upstream Error Response: {}", trace_id, error_text);
         
         // 3. 标记限流状态（用于 UI 显示）
@@ -803,8 +888,8 @@ pub async fn handle_messages(
             }
             
             // 使用统一退避策略
-            let strategy = determine_retry_strategy(status_code, &error_text, retried_without_thinking);
-            if apply_retry_strategy(strategy, attempt, status_code, &trace_id).await {
+            let strategy = determine_retry_strategy(status_code, &error_text, retried_without_thinking, &retry_policy);
+            if apply_retry_strategy(strategy, attempt, max_attempts, status_code, &trace_id).await {
                 continue;
             }
         }
@@ -815,10 +900,10 @@ pub async fn handle_messages(
         
         
         // 确定重试策略
-        let strategy = determine_retry_strategy(status_code, &error_text, retried_without_thinking);
+        let strategy = determine_retry_strategy(status_code, &error_text, retried_without_thinking, &retry_policy);
         
         // 执行退避
-        if apply_retry_strategy(strategy, attempt, status_code, &trace_id).await {
+        if apply_retry_strategy(strategy, attempt, max_attempts, status_code, &trace_id).await {
             // 判断是否需要轮换账号
             if !should_rotate_account(status_code) {
                 debug!("[{}] Keeping same account for status {} (server-side issue)", trace_id, status_code);
@@ -842,27 +927,28 @@ pub async fn handle_messages(
 
 /// 列出可用模型
 pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoResponse {
-    use crate::proxy::common::model_mapping::get_all_dynamic_models;
+    let models = crate::proxy::handlers::common::listed_models(&state).await;
+    let quotas: Vec<_> = models.iter().filter_map(|(_, quota)| *quota).collect();
 
-    let model_ids = get_all_dynamic_models(
-        &state.openai_mapping,
-        &state.custom_mapping,
-        &state.anthropic_mapping,
-    ).await;
-
-    let data: Vec<_> = model_ids.into_iter().map(|id| {
-        json!({
+    let data: Vec<_> = models.into_iter().map(|(id, quota)| {
+        let mut entry = json!({
             "id": id,
             "object": "model",
             "created": 1706745600,
             "owned_by": "antigravity"
-        })
+        });
+        if let Some(quota) = quota {
+            entry["quota"] = json!(quota);
+        }
+        entry
     }).collect();
 
-    Json(json!({
+    let response = Json(json!({
         "object": "list",
         "data": data
     }))
+    .into_response();
+    crate::proxy::handlers::common::with_pool_quota_header(&state, response, &quotas).await
 }
 
 /// 计算 tokens (占位符)
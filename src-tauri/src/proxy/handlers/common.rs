@@ -18,6 +18,7 @@ pub async fn handle_detect_model(
     let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
         model_name,
         &*state.custom_mapping.read().await,
+        &*state.mapping_rules.read().await,
         &*state.openai_mapping.read().await,
         &*state.anthropic_mapping.read().await,
         false,  // Common 请求不应用 Claude 家族映射
@@ -31,6 +32,7 @@ pub async fn handle_detect_model(
     );
 
     // 3. Construct response
+    let capabilities = state.token_manager.model_capabilities(&mapped_model).await;
     let mut response = json!({
         "model": model_name,
         "mapped_model": mapped_model,
@@ -38,7 +40,8 @@ pub async fn handle_detect_model(
         "features": {
             "has_web_search": config.inject_google_search,
             "is_image_gen": config.request_type == "image_gen"
-        }
+        },
+        "capabilities": capabilities
     });
 
     if let Some(img_conf) = config.image_config {
@@ -49,3 +52,65 @@ pub async fn handle_detect_model(
 
     Json(response).into_response()
 }
+
+/// 模型列表中 ID 对应的上游模型 (自定义精确映射 > 内置映射表 > 原名)，不经过完整路由以免逐条打印路由日志
+async fn listed_model_target(state: &AppState, id: &str) -> String {
+    state
+        .custom_mapping
+        .read()
+        .await
+        .get(id)
+        .cloned()
+        .or_else(|| crate::proxy::common::model_mapping::builtin_target(id).map(str::to_string))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// 模型列表中各 ID 的能力
+pub async fn listed_model_capabilities(
+    state: &AppState,
+    id: &str,
+) -> Option<crate::proxy::model_capabilities::ModelCapabilities> {
+    let target = listed_model_target(state, id).await;
+    state.token_manager.model_capabilities(&target).await
+}
+
+/// 模型列表 (与 /v1/models 一致) 及各模型在账号池中的剩余配额；
+/// 开启 `model_list.quota_aware` 时省略池中所有账号均已耗尽的模型
+pub async fn listed_models(state: &AppState) -> Vec<(String, Option<crate::proxy::pool_quota::PoolQuota>)> {
+    let model_ids = crate::proxy::common::model_mapping::get_all_dynamic_models(
+        &state.openai_mapping,
+        &state.custom_mapping,
+        &state.anthropic_mapping,
+    )
+    .await;
+    let quota_aware = state.token_manager.model_list().await.quota_aware;
+
+    let mut models = Vec::with_capacity(model_ids.len());
+    for id in model_ids {
+        let target = listed_model_target(state, &id).await;
+        let quota = state.token_manager.pool_quota(&target);
+        if quota_aware && quota.is_some_and(|q| !q.is_available()) {
+            continue;
+        }
+        models.push((id, quota));
+    }
+    models
+}
+
+/// 模型列表响应附加池内平均剩余配额头 (隐私模式下省略)
+pub async fn with_pool_quota_header(
+    state: &AppState,
+    mut response: axum::response::Response,
+    quotas: &[crate::proxy::pool_quota::PoolQuota],
+) -> axum::response::Response {
+    if state.response_headers.read().await.privacy {
+        return response;
+    }
+    if let Some(percent) = crate::proxy::pool_quota::overall_percent(quotas) {
+        response.headers_mut().insert(
+            crate::proxy::middleware::response_headers::QUOTA_REMAINING_HEADER,
+            axum::http::HeaderValue::from(percent),
+        );
+    }
+    response
+}
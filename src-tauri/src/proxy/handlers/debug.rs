@@ -0,0 +1,260 @@
+// 调试端点: 查看请求转换结果 (不调用上游)
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+use crate::proxy::common::mapping_rules::MappingRules;
+use crate::proxy::mappers::claude::{transform_claude_request_in, ClaudeRequest};
+use crate::proxy::mappers::gemini::wrap_request;
+use crate::proxy::mappers::openai::{transform_openai_request, OpenAIRequest};
+use crate::proxy::server::AppState;
+
+/// 转换结果中使用的占位 project_id (真实值取决于调度到的账号)
+const PLACEHOLDER_PROJECT: &str = "<project-id>";
+
+#[derive(Debug, Deserialize)]
+pub struct TranslateBody {
+    /// `openai` / `claude` / `gemini`，缺省时按请求结构推断
+    pub protocol: Option<String>,
+    /// Gemini 原生请求的模型名，可带方法后缀 (如 `gemini-2.5-flash:streamGenerateContent`)；
+    /// 其余协议取自请求体
+    pub model: Option<String>,
+    /// 客户端原始请求体
+    pub request: Value,
+}
+
+/// 模型映射快照
+pub struct Mappings<'a> {
+    pub custom: &'a HashMap<String, String>,
+    pub rules: &'a MappingRules,
+    pub openai: &'a HashMap<String, String>,
+    pub anthropic: &'a HashMap<String, String>,
+}
+
+fn infer_protocol(request: &Value) -> &'static str {
+    if request.get("contents").is_some() {
+        "gemini"
+    } else if request.get("system").is_some()
+        || (request.get("max_tokens").is_some()
+            && request.get("n").is_none()
+            && request.get("max_completion_tokens").is_none()
+            && request.get("response_format").is_none())
+    {
+        "claude"
+    } else {
+        "openai"
+    }
+}
+
+fn tool_values<T: serde::Serialize>(tools: &Option<Vec<T>>) -> Option<Vec<Value>> {
+    tools.as_ref().map(|list| {
+        list.iter()
+            .map(|t| serde_json::to_value(t).unwrap_or(json!({})))
+            .collect()
+    })
+}
+
+/// 按各协议处理器的路由规则转换请求，返回上游方法与请求体
+pub fn translate(
+    protocol: &str,
+    model: Option<&str>,
+    request: &Value,
+    mappings: &Mappings,
+) -> Result<Value, String> {
+    let route = |model: &str, family: bool| {
+        crate::proxy::common::model_mapping::resolve_model_route(
+            model,
+            mappings.custom,
+            mappings.rules,
+            mappings.openai,
+            mappings.anthropic,
+            family,
+        )
+    };
+
+    let (original_model, mapped_model, config, stream, body) = match protocol {
+        "openai" => {
+            let req: OpenAIRequest = serde_json::from_value(request.clone())
+                .map_err(|e| format!("Invalid OpenAI request: {}", e))?;
+            let mapped = route(&req.model, false);
+            let config = crate::proxy::mappers::common_utils::resolve_request_config(
+                &req.model,
+                &mapped,
+                &req.tools.clone(),
+            );
+            let body = transform_openai_request(&req, PLACEHOLDER_PROJECT, &mapped);
+            (req.model.clone(), mapped, config, req.stream, body)
+        }
+        "claude" => {
+            let mut req: ClaudeRequest = serde_json::from_value(request.clone())
+                .map_err(|e| format!("Invalid Claude request: {}", e))?;
+            let tools = tool_values(&req.tools);
+            let initial = route(&req.model, false);
+            let config = crate::proxy::mappers::common_utils::resolve_request_config(
+                &req.model, &initial, &tools,
+            );
+            // 与 Claude 处理器一致: 仅 agent 类请求应用家族映射
+            let mapped = if config.request_type == "agent" {
+                route(&req.model, true)
+            } else {
+                initial
+            };
+            let original = std::mem::replace(&mut req.model, mapped.clone());
+            let body = transform_claude_request_in(&req, PLACEHOLDER_PROJECT)?;
+            (original, mapped, config, req.stream, body)
+        }
+        "gemini" => {
+            let model = model
+                .ok_or_else(|| "Gemini requests require the `model` field".to_string())?;
+            let (model, stream) = match model.split_once(':') {
+                Some((name, method)) => (name, method == "streamGenerateContent"),
+                None => (model, false),
+            };
+            let mapped = route(model, false);
+            let tools = request.get("tools").and_then(|t| t.as_array()).map(|arr| {
+                arr.iter()
+                    .flat_map(|entry| {
+                        entry
+                            .get("functionDeclarations")
+                            .and_then(|d| d.as_array())
+                            .cloned()
+                            .unwrap_or_else(|| vec![entry.clone()])
+                    })
+                    .collect::<Vec<Value>>()
+            });
+            let config =
+                crate::proxy::mappers::common_utils::resolve_request_config(model, &mapped, &tools);
+            let body = wrap_request(request, PLACEHOLDER_PROJECT, &mapped);
+            (model.to_string(), mapped, config, stream, body)
+        }
+        other => return Err(format!("Unsupported protocol: {}", other)),
+    };
+
+    let method = if stream { "streamGenerateContent" } else { "generateContent" };
+    Ok(json!({
+        "protocol": protocol,
+        "model": original_model,
+        "mapped_model": mapped_model,
+        "request_type": config.request_type,
+        "inject_google_search": config.inject_google_search,
+        "upstream": {
+            "method": method,
+            "query": if stream { Some("alt=sse") } else { None },
+            "body": body,
+        }
+    }))
+}
+
+/// POST /debug/translate — 返回将发送给上游的完整请求 (需在配置中开启调试端点)
+pub async fn handle_translate(
+    State(state): State<AppState>,
+    Json(body): Json<TranslateBody>,
+) -> Response {
+    if !state.debug_endpoints.load(Ordering::Relaxed) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let protocol = body
+        .protocol
+        .as_deref()
+        .map(|p| p.to_ascii_lowercase())
+        .unwrap_or_else(|| infer_protocol(&body.request).to_string());
+    let custom = state.custom_mapping.read().await;
+    let rules = state.mapping_rules.read().await;
+    let openai = state.openai_mapping.read().await;
+    let anthropic = state.anthropic_mapping.read().await;
+    let mappings = Mappings {
+        custom: &custom,
+        rules: &rules,
+        openai: &openai,
+        anthropic: &anthropic,
+    };
+
+    match translate(&protocol, body.model.as_deref(), &body.request, &mappings) {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": { "type": "invalid_request_error", "message": e } })),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty() -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    #[test]
+    fn infers_protocol_from_shape() {
+        assert_eq!(infer_protocol(&json!({"contents": []})), "gemini");
+        assert_eq!(
+            infer_protocol(&json!({"model": "m", "system": "s", "messages": []})),
+            "claude"
+        );
+        assert_eq!(
+            infer_protocol(&json!({"model": "m", "messages": [], "temperature": 0.2})),
+            "openai"
+        );
+    }
+
+    #[test]
+    fn translates_openai_with_custom_mapping() {
+        let mut custom = empty();
+        custom.insert("my-model".to_string(), "gemini-2.5-flash".to_string());
+        let (openai, anthropic) = (empty(), empty());
+        let rules = MappingRules::default();
+        let mappings = Mappings {
+            custom: &custom,
+            rules: &rules,
+            openai: &openai,
+            anthropic: &anthropic,
+        };
+        let request = json!({
+            "model": "my-model",
+            "stream": true,
+            "messages": [{"role": "user", "content": "hi"}]
+        });
+
+        let result = translate("openai", None, &request, &mappings).unwrap();
+        assert_eq!(result["mapped_model"], "gemini-2.5-flash");
+        assert_eq!(result["upstream"]["method"], "streamGenerateContent");
+        assert_eq!(result["upstream"]["query"], "alt=sse");
+        assert_eq!(result["upstream"]["body"]["project"], PLACEHOLDER_PROJECT);
+    }
+
+    #[test]
+    fn gemini_requires_model() {
+        let (custom, openai, anthropic) = (empty(), empty(), empty());
+        let rules = MappingRules::default();
+        let mappings = Mappings {
+            custom: &custom,
+            rules: &rules,
+            openai: &openai,
+            anthropic: &anthropic,
+        };
+        let request = json!({"contents": [{"role": "user", "parts": [{"text": "hi"}]}]});
+        assert!(translate("gemini", None, &request, &mappings).is_err());
+        let result = translate("gemini", Some("gemini-2.5-flash"), &request, &mappings).unwrap();
+        assert_eq!(result["upstream"]["method"], "generateContent");
+        let result = translate(
+            "gemini",
+            Some("gemini-2.5-flash:streamGenerateContent"),
+            &request,
+            &mappings,
+        )
+        .unwrap();
+        assert_eq!(result["model"], "gemini-2.5-flash");
+        assert_eq!(result["upstream"]["method"], "streamGenerateContent");
+        assert!(translate("cohere", None, &request, &mappings).is_err());
+    }
+}
@@ -1,14 +1,13 @@
 // Gemini Handler
-use axum::{extract::State, extract::{Json, Path}, http::StatusCode, response::IntoResponse};
+use axum::{extract::State, extract::{Json, Path}, http::StatusCode, response::{IntoResponse, Response}};
 use serde_json::{json, Value};
 use tracing::{debug, error, info};
 
 use crate::proxy::mappers::gemini::{wrap_request, unwrap_response};
 use crate::proxy::server::AppState;
 use crate::proxy::session_manager::SessionManager;
- 
-const MAX_RETRY_ATTEMPTS: usize = 3;
- 
+use crate::proxy::upstream::retry::token_for_attempt;
+
 /// 处理 generateContent 和 streamGenerateContent
 /// 路径参数: model_name, method (e.g. "gemini-pro", "generateContent")
 pub async fn handle_generate(
@@ -25,29 +24,44 @@ pub async fn handle_generate(
 
     crate::modules::logger::log_info(&format!("Received Gemini request: {}/{}", model_name, method));
 
-    // 1. 验证方法
+    // 1. 验证方法 (countTokens / embedContent 与 generateContent 共用 `model:method` 路由)
+    match method.as_str() {
+        "countTokens" => return Ok(count_tokens(&state, &model_name, &body).await),
+        "embedContent" | "batchEmbedContents" => return Ok(embed_unsupported(&method)),
+        _ => {}
+    }
     if method != "generateContent" && method != "streamGenerateContent" {
         return Err((StatusCode::BAD_REQUEST, format!("Unsupported method: {}", method)));
     }
     let is_stream = method == "streamGenerateContent";
+    let features = crate::proxy::model_capabilities::RequestFeatures::from_body(&body);
 
     // 2. 获取 UpstreamClient 和 TokenManager
     let upstream = state.upstream.clone();
+    let timeouts = state.timeouts.read().await.resolve("/v1beta/models");
     let token_manager = state.token_manager;
     let pool_size = token_manager.len();
-    let max_attempts = MAX_RETRY_ATTEMPTS.min(pool_size).max(1);
+    let retry_policy = token_manager.retry_policy().await;
+    let max_attempts = retry_policy.attempts(pool_size, is_stream);
     
     let mut last_error = String::new();
+    let mut previous_account: Option<String> = None;
 
     for attempt in 0..max_attempts {
         // 3. 模型路由与配置解析
         let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
             &model_name,
             &*state.custom_mapping.read().await,
+            &*state.mapping_rules.read().await,
             &*state.openai_mapping.read().await,
             &*state.anthropic_mapping.read().await,
             false,  // Gemini 请求不应用 Claude 家族映射
         );
+        // 超出上游模型能力的请求直接拒绝，不占用账号
+        token_manager
+            .check_model_request(&mapped_model, &features)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
         // 提取 tools 列表以进行联网探测 (Gemini 风格可能是嵌套的)
         let tools_val: Option<Vec<Value>> = body.get("tools").and_then(|t| t.as_array()).map(|arr| {
             let mut flattened = Vec::new();
@@ -67,8 +81,17 @@ pub async fn handle_generate(
         // 提取 SessionId (粘性指纹)
         let session_id = SessionManager::extract_gemini_session_id(&body, &model_name);
 
-        // 关键：在重试尝试 (attempt > 0) 时强制轮换账号
-        let (access_token, project_id, email) = match token_manager.get_token(&config.request_type, attempt > 0, Some(&session_id)).await {
+        // 关键：重试时按重试策略轮换账号或固定在原账号
+        let (access_token, project_id, email) = match token_for_attempt(
+            &token_manager,
+            &retry_policy,
+            previous_account.as_deref(),
+            &config.request_type,
+            Some(&session_id),
+            Some(&config.final_model),
+        )
+        .await
+        {
             Ok(t) => t,
             Err(e) => {
                 return Err((StatusCode::SERVICE_UNAVAILABLE, format!("Token error: {}", e)));
@@ -76,18 +99,49 @@ pub async fn handle_generate(
         };
 
         info!("✓ Using account: {} (type: {})", email, config.request_type);
+        previous_account = Some(email.clone());
 
         // 5. 包装请求 (project injection)
-        let wrapped_body = wrap_request(&body, &project_id, &mapped_model);
+        let mut wrapped_body = wrap_request(&body, &project_id, &mapped_model);
+        if let Err(e) = crate::proxy::context_overflow::fit_request(
+            &token_manager,
+            &upstream,
+            &access_token,
+            &mapped_model,
+            &mut wrapped_body,
+            &timeouts,
+        )
+        .await
+        {
+            return Err((StatusCode::BAD_REQUEST, e));
+        }
 
         // 5. 上游调用
         let query_string = if is_stream { Some("alt=sse") } else { None };
         let upstream_method = if is_stream { "streamGenerateContent" } else { "generateContent" };
 
+        let resume = if is_stream {
+            crate::proxy::stream_resume::ResumeContext::new(
+                &token_manager,
+                &upstream,
+                &wrapped_body,
+                &config.request_type,
+                &config.final_model,
+                &email,
+                &timeouts,
+            )
+        } else {
+            None
+        };
+
+        let upstream_start = std::time::Instant::now();
         let response = match upstream
-            .call_v1_internal(upstream_method, &access_token, wrapped_body, query_string)
+            .call_v1_internal(upstream_method, &access_token, wrapped_body, query_string, &timeouts)
             .await {
-                Ok(r) => r,
+                Ok(r) => {
+                    token_manager.record_latency(&email, upstream_start.elapsed());
+                    r
+                }
                 Err(e) => {
                     last_error = e.clone();
                     debug!("Gemini Request failed on attempt {}/{}: {}", attempt + 1, max_attempts, e);
@@ -100,21 +154,24 @@ pub async fn handle_generate(
             // 6. 响应处理
             if is_stream {
                 use axum::body::Body;
-                use axum::response::Response;
-                use bytes::{Bytes, BytesMut};
+                use bytes::Bytes;
                 use futures::StreamExt;
                 
-                let mut response_stream = response.bytes_stream();
-                let mut buffer = BytesMut::new();
+                let armed = resume.is_some();
+                let mut response_stream =
+                    crate::proxy::stream_resume::upstream_stream(response, &timeouts, resume);
+                let mut buffer = crate::proxy::streaming::SseLineBuffer::new();
 
                 let stream = async_stream::stream! {
                     while let Some(item) = response_stream.next().await {
                         match item {
                             Ok(bytes) => {
                                 debug!("[Gemini-SSE] Received chunk: {} bytes", bytes.len());
-                                buffer.extend_from_slice(&bytes);
-                                while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
-                                    let line_raw = buffer.split_to(pos + 1);
+                                if let Err(e) = buffer.push(&bytes) {
+                                    yield Err(format!("Stream error: {}", e));
+                                    break;
+                                }
+                                while let Some(line_raw) = buffer.next_line() {
                                     if let Ok(line_str) = std::str::from_utf8(&line_raw) {
                                         let line = line_str.trim();
                                         if line.is_empty() { continue; }
@@ -160,8 +217,13 @@ pub async fn handle_generate(
                     }
                 };
                 
+                let stream = crate::proxy::timeouts::with_heartbeat(
+                    stream,
+                    timeouts.heartbeat,
+                    crate::proxy::timeouts::SSE_COMMENT_HEARTBEAT,
+                );
                 let body = Body::from_stream(stream);
-                return Ok(Response::builder()
+                return Ok(crate::proxy::stream_resume::annotate(Response::builder(), armed)
                     .header("Content-Type", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .header("Connection", "keep-alive")
@@ -184,9 +246,10 @@ pub async fn handle_generate(
         let retry_after = response.headers().get("Retry-After").and_then(|h| h.to_str().ok()).map(|s| s.to_string());
         let error_text = response.text().await.unwrap_or_else(|_| format!("HTTP {}", status_code));
         last_error = format!("HTTP {}: {}", status_code, error_text);
+        token_manager.record_upstream_error(&email, Some(status_code), &error_text);
  
-        // 只有 429 (限流), 529 (过载), 503, 403 (权限) 和 401 (认证失效) 触发账号轮换
-        if status_code == 429 || status_code == 529 || status_code == 503 || status_code == 500 || status_code == 403 || status_code == 401 {
+        // 按重试策略处理 (默认: 429 限流, 529 过载, 503, 500, 403 权限, 401 认证失效)
+        if retry_policy.should_retry(status_code) {
             // 记录限流信息 (全局同步)
             token_manager.mark_rate_limited(&email, status_code, retry_after.as_deref(), &error_text);
 
@@ -196,7 +259,13 @@ pub async fn handle_generate(
                 return Err((status, error_text));
             }
 
-            tracing::warn!("Gemini Upstream {} on account {} attempt {}/{}, rotating account", status_code, email, attempt + 1, max_attempts);
+            if retry_policy.rotate_account {
+                tracing::warn!("Gemini Upstream {} on account {} attempt {}/{}, rotating account", status_code, email, attempt + 1, max_attempts);
+            } else {
+                let delay = retry_policy.backoff(attempt);
+                tracing::warn!("Gemini Upstream {} on account {} attempt {}/{}, retrying same account in {}ms", status_code, email, attempt + 1, max_attempts, delay.as_millis());
+                tokio::time::sleep(delay).await;
+            }
             continue;
         }
  
@@ -208,46 +277,186 @@ pub async fn handle_generate(
     Ok((StatusCode::TOO_MANY_REQUESTS, format!("All accounts exhausted. Last error: {}", last_error)).into_response())
 }
 
-pub async fn handle_list_models(State(state): State<AppState>) -> Result<impl IntoResponse, (StatusCode, String)> {
-    use crate::proxy::common::model_mapping::get_all_dynamic_models;
+/// Gemini 模型描述 (models 列表与单个模型查询共用)
+fn model_info(id: &str, caps: Option<&crate::proxy::model_capabilities::ModelCapabilities>) -> Value {
+    json!({
+        "name": format!("models/{}", id),
+        "version": "001",
+        "displayName": id,
+        "description": "",
+        "inputTokenLimit": caps.map_or(128000, |c| c.max_context_tokens),
+        "outputTokenLimit": caps.map_or(8192, |c| c.max_output_tokens),
+        "thinking": caps.is_some_and(|c| c.thinking),
+        "supportedGenerationMethods": ["generateContent", "streamGenerateContent", "countTokens"],
+        "temperature": 1.0,
+        "topP": 0.95,
+        "topK": 64
+    })
+}
 
-    // 获取所有动态模型列表（与 /v1/models 一致）
-    let model_ids = get_all_dynamic_models(
-        &state.openai_mapping,
-        &state.custom_mapping,
-        &state.anthropic_mapping,
-    ).await;
+pub async fn handle_list_models(State(state): State<AppState>) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // 获取所有动态模型列表（与 /v1/models 一致，按配置隐藏已无配额的模型）
+    let listed = crate::proxy::handlers::common::listed_models(&state).await;
 
     // 转换为 Gemini API 格式
-    let models: Vec<_> = model_ids.into_iter().map(|id| {
-        json!({
-            "name": format!("models/{}", id),
-            "version": "001",
-            "displayName": id.clone(),
-            "description": "",
-            "inputTokenLimit": 128000,
-            "outputTokenLimit": 8192,
-            "supportedGenerationMethods": ["generateContent", "countTokens"],
-            "temperature": 1.0,
-            "topP": 0.95,
-            "topK": 64
-        })
-    }).collect();
-
-    Ok(Json(json!({ "models": models })))
+    let mut models = Vec::with_capacity(listed.len());
+    let mut quotas = Vec::new();
+    for (id, quota) in &listed {
+        let caps = crate::proxy::handlers::common::listed_model_capabilities(&state, id).await;
+        let mut info = model_info(id, caps.as_ref());
+        if let Some(quota) = quota {
+            info["quota"] = json!(quota);
+            quotas.push(*quota);
+        }
+        models.push(info);
+    }
+
+    let response = Json(json!({ "models": models })).into_response();
+    Ok(crate::proxy::handlers::common::with_pool_quota_header(&state, response, &quotas).await)
 }
 
-pub async fn handle_get_model(Path(model_name): Path<String>) -> impl IntoResponse {
-    Json(json!({
-        "name": format!("models/{}", model_name),
-        "displayName": model_name
-    }))
+pub async fn handle_get_model(
+    State(state): State<AppState>,
+    Path(model_name): Path<String>,
+) -> impl IntoResponse {
+    let id = model_name.trim_start_matches("models/");
+    let caps = crate::proxy::handlers::common::listed_model_capabilities(&state, id).await;
+    Json(model_info(id, caps.as_ref()))
 }
 
-pub async fn handle_count_tokens(State(state): State<AppState>, Path(_model_name): Path<String>, Json(_body): Json<Value>) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let model_group = "gemini";
-    let (_access_token, _project_id, _) = state.token_manager.get_token(model_group, false, None).await
-        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, format!("Token error: {}", e)))?;
-    
-    Ok(Json(json!({"totalTokens": 0})))
+/// `/v1beta/models/{model}/countTokens` (兼容旧路径，SDK 实际使用 `{model}:countTokens`)
+pub async fn handle_count_tokens(
+    State(state): State<AppState>,
+    Path(model_name): Path<String>,
+    Json(body): Json<Value>,
+) -> Response {
+    count_tokens(&state, &model_name, &body).await
+}
+
+/// 提取 countTokens 请求中的 contents (支持 `contents` 与 `generateContentRequest.contents` 两种写法)
+fn count_tokens_contents(body: &Value) -> Value {
+    body.get("contents")
+        .or_else(|| body.pointer("/generateContentRequest/contents"))
+        .cloned()
+        .unwrap_or_else(|| json!([]))
+}
+
+/// 构造 v1internal:countTokens 请求体
+fn build_count_tokens_request(model: &str, body: &Value) -> Value {
+    json!({
+        "request": {
+            "model": format!("models/{}", model),
+            "contents": count_tokens_contents(body),
+        }
+    })
+}
+
+/// 本地粗略估算 (约 4 字符 / token)，上游不可用时兜底
+fn estimate_tokens(contents: &Value) -> u64 {
+    fn collect_chars(value: &Value) -> usize {
+        match value {
+            Value::String(s) => s.chars().count(),
+            Value::Array(items) => items.iter().map(collect_chars).sum(),
+            Value::Object(map) => map
+                .iter()
+                .filter(|(k, _)| k.as_str() != "inlineData" && k.as_str() != "role")
+                .map(|(_, v)| collect_chars(v))
+                .sum(),
+            _ => 0,
+        }
+    }
+    (collect_chars(contents) as u64).div_ceil(4)
+}
+
+/// countTokens: 优先转发上游，失败时返回本地估算值
+async fn count_tokens(state: &AppState, model_name: &str, body: &Value) -> Response {
+    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        model_name,
+        &*state.custom_mapping.read().await,
+        &*state.mapping_rules.read().await,
+        &*state.openai_mapping.read().await,
+        &*state.anthropic_mapping.read().await,
+        false,
+    );
+    let timeouts = state.timeouts.read().await.resolve("/v1beta/models");
+
+    let upstream_total = match state.token_manager.get_token("gemini", false, None).await {
+        Ok((access_token, _project_id, _email)) => {
+            let request = build_count_tokens_request(&mapped_model, body);
+            match state
+                .upstream
+                .call_v1_internal("countTokens", &access_token, request, None, &timeouts)
+                .await
+            {
+                Ok(resp) if resp.status().is_success() => resp
+                    .json::<Value>()
+                    .await
+                    .ok()
+                    .and_then(|v| v.get("totalTokens").and_then(|t| t.as_u64())),
+                Ok(resp) => {
+                    debug!("[Gemini] countTokens upstream returned {}", resp.status());
+                    None
+                }
+                Err(e) => {
+                    debug!("[Gemini] countTokens upstream failed: {}", e);
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            debug!("[Gemini] countTokens without token: {}", e);
+            None
+        }
+    };
+
+    let total_tokens =
+        upstream_total.unwrap_or_else(|| estimate_tokens(&count_tokens_contents(body)));
+    Json(json!({ "totalTokens": total_tokens })).into_response()
+}
+
+/// embedContent / batchEmbedContents: v1internal 不提供向量接口，返回 Gemini 格式的 501 错误
+fn embed_unsupported(method: &str) -> Response {
+    (
+        StatusCode::NOT_IMPLEMENTED,
+        Json(json!({
+            "error": {
+                "code": 501,
+                "message": format!("{} is not supported by the Antigravity upstream; use a Gemini API key for embeddings", method),
+                "status": "UNIMPLEMENTED"
+            }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_tokens_request_accepts_both_shapes() {
+        let contents = json!([{ "role": "user", "parts": [{ "text": "hello" }] }]);
+
+        let direct = build_count_tokens_request("gemini-2.5-flash", &json!({ "contents": contents }));
+        assert_eq!(direct["request"]["model"], "models/gemini-2.5-flash");
+        assert_eq!(direct["request"]["contents"], contents);
+
+        let nested = build_count_tokens_request(
+            "gemini-2.5-flash",
+            &json!({ "generateContentRequest": { "contents": contents } }),
+        );
+        assert_eq!(nested["request"]["contents"], contents);
+    }
+
+    #[test]
+    fn estimate_ignores_roles_and_inline_data() {
+        let contents = json!([{
+            "role": "user",
+            "parts": [
+                { "text": "12345678" },
+                { "inlineData": { "mimeType": "image/png", "data": "AAAAAAAAAAAAAAAA" } }
+            ]
+        }]);
+        assert_eq!(estimate_tokens(&contents), 2);
+    }
 }
@@ -16,16 +16,7 @@ fn build_client(
     upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
     timeout_secs: u64,
 ) -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs.max(5)));
-
-    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
-        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
-            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-        builder = builder.proxy(proxy);
-    }
-
-    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+    crate::proxy::upstream::pool::global().get("zai-mcp", Some(&upstream_proxy), timeout_secs)
 }
 
 fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
@@ -2,8 +2,12 @@
 // 核心端点处理器模块
 
 pub mod claude;
+pub mod batches;
 pub mod openai;
 pub mod gemini;
 pub mod mcp;
 pub mod common;
+pub mod admin;
+pub mod debug;
+pub mod realtime;
 
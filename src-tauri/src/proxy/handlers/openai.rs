@@ -10,13 +10,15 @@ use crate::proxy::mappers::openai::{
 // use crate::proxy::upstream::client::UpstreamClient; // 通过 state 获取
 use crate::proxy::server::AppState;
 
-const MAX_RETRY_ATTEMPTS: usize = 3;
+use crate::proxy::model_capabilities::RequestFeatures;
 use crate::proxy::session_manager::SessionManager;
+use crate::proxy::upstream::retry::token_for_attempt;
 
 pub async fn handle_chat_completions(
     State(state): State<AppState>,
     Json(body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let features = RequestFeatures::from_body(&body);
     let mut openai_req: OpenAIRequest = serde_json::from_value(body)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
 
@@ -40,21 +42,30 @@ pub async fn handle_chat_completions(
 
     // 1. 获取 UpstreamClient (Clone handle)
     let upstream = state.upstream.clone();
+    let timeouts = state.timeouts.read().await.resolve("/v1/chat/completions");
     let token_manager = state.token_manager;
     let pool_size = token_manager.len();
-    let max_attempts = MAX_RETRY_ATTEMPTS.min(pool_size).max(1);
+    let retry_policy = token_manager.retry_policy().await;
+    let max_attempts = retry_policy.attempts(pool_size, openai_req.stream);
 
     let mut last_error = String::new();
+    let mut previous_account: Option<String> = None;
 
     for attempt in 0..max_attempts {
         // 2. 预解析模型路由与配置
         let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
             &openai_req.model,
             &*state.custom_mapping.read().await,
+            &*state.mapping_rules.read().await,
             &*state.openai_mapping.read().await,
             &*state.anthropic_mapping.read().await,
             false,  // OpenAI 请求不应用 Claude 家族映射
         );
+        // 超出上游模型能力的请求直接拒绝，不占用账号
+        token_manager
+            .check_model_request(&mapped_model, &features)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
         // 将 OpenAI 工具转为 Value 数组以便探测联网
         let tools_val: Option<Vec<Value>> = openai_req
             .tools
@@ -70,10 +81,16 @@ pub async fn handle_chat_completions(
         let session_id = SessionManager::extract_openai_session_id(&openai_req);
 
         // 4. 获取 Token (使用准确的 request_type)
-        // 关键：在重试尝试 (attempt > 0) 时强制轮换账号
-        let (access_token, project_id, email) = match token_manager
-            .get_token(&config.request_type, attempt > 0, Some(&session_id))
-            .await
+        // 关键：重试时按重试策略轮换账号或固定在原账号
+        let (access_token, project_id, email) = match token_for_attempt(
+            &token_manager,
+            &retry_policy,
+            previous_account.as_deref(),
+            &config.request_type,
+            Some(&session_id),
+            Some(&config.final_model),
+        )
+        .await
         {
             Ok(t) => t,
             Err(e) => {
@@ -85,15 +102,29 @@ pub async fn handle_chat_completions(
         };
 
         info!("✓ Using account: {} (type: {})", email, config.request_type);
+        previous_account = Some(email.clone());
 
         // 4. 转换请求
-        let gemini_body = transform_openai_request(&openai_req, &project_id, &mapped_model);
+        let mut gemini_body = transform_openai_request(&openai_req, &project_id, &mapped_model);
 
         // [New] 打印转换后的报文 (Gemini Body) 供调试
         if let Ok(body_json) = serde_json::to_string_pretty(&gemini_body) {
             debug!("[OpenAI-Request] Transformed Gemini Body:\n{}", body_json);
         }
 
+        if let Err(e) = crate::proxy::context_overflow::fit_request(
+            &token_manager,
+            &upstream,
+            &access_token,
+            &mapped_model,
+            &mut gemini_body,
+            &timeouts,
+        )
+        .await
+        {
+            return Err((StatusCode::BAD_REQUEST, e));
+        }
+
         // 5. 发送请求
         let list_response = openai_req.stream;
         let method = if list_response {
@@ -103,11 +134,29 @@ pub async fn handle_chat_completions(
         };
         let query_string = if list_response { Some("alt=sse") } else { None };
 
+        let resume = if list_response {
+            crate::proxy::stream_resume::ResumeContext::new(
+                &token_manager,
+                &upstream,
+                &gemini_body,
+                &config.request_type,
+                &config.final_model,
+                &email,
+                &timeouts,
+            )
+        } else {
+            None
+        };
+
+        let upstream_start = std::time::Instant::now();
         let response = match upstream
-            .call_v1_internal(method, &access_token, gemini_body, query_string)
+            .call_v1_internal(method, &access_token, gemini_body, query_string, &timeouts)
             .await
         {
-            Ok(r) => r,
+            Ok(r) => {
+                token_manager.record_latency(&email, upstream_start.elapsed());
+                r
+            }
             Err(e) => {
                 last_error = e.clone();
                 debug!(
@@ -129,12 +178,19 @@ pub async fn handle_chat_completions(
                 use axum::response::Response;
                 // Removed redundant StreamExt
 
-                let gemini_stream = response.bytes_stream();
+                let armed = resume.is_some();
+                let gemini_stream =
+                    crate::proxy::stream_resume::upstream_stream(response, &timeouts, resume);
                 let openai_stream =
-                    create_openai_sse_stream(Box::pin(gemini_stream), openai_req.model.clone());
+                    create_openai_sse_stream(gemini_stream, openai_req.model.clone());
+                let openai_stream = crate::proxy::timeouts::with_heartbeat(
+                    openai_stream,
+                    timeouts.heartbeat,
+                    crate::proxy::timeouts::SSE_COMMENT_HEARTBEAT,
+                );
                 let body = Body::from_stream(openai_stream);
 
-                return Ok(Response::builder()
+                return Ok(crate::proxy::stream_resume::annotate(Response::builder(), armed)
                     .header("Content-Type", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .header("Connection", "keep-alive")
@@ -157,6 +213,7 @@ pub async fn handle_chat_completions(
         let retry_after = response.headers().get("Retry-After").and_then(|h| h.to_str().ok()).map(|s| s.to_string());
         let error_text = response.text().await.unwrap_or_else(|_| format!("HTTP {}", status_code));
         last_error = format!("HTTP {}: {}", status_code, error_text);
+        token_manager.record_upstream_error(&email, Some(status_code), &error_text);
 
         // [New] 打印错误报文日志
         tracing::error!(
@@ -169,7 +226,9 @@ pub async fn handle_chat_completions(
         if status_code == 429 || status_code == 529 || status_code == 503 || status_code == 500 {
             // 记录限流信息 (全局同步)
             token_manager.mark_rate_limited(&email, status_code, retry_after.as_deref(), &error_text);
+        }
 
+        if retry_policy.should_retry(status_code) {
             // 1. 优先尝试解析 RetryInfo (由 Google Cloud 直接下发)
             if let Some(delay_ms) = crate::proxy::upstream::retry::parse_retry_delay(&error_text) {
                 let actual_delay = delay_ms.saturating_add(200).min(10_000);
@@ -196,26 +255,27 @@ pub async fn handle_chat_completions(
                 return Err((status, error_text));
             }
 
-            // 3. 其他限流或服务器过载情况，轮换账号
-            tracing::warn!(
-                "OpenAI Upstream {} on {} attempt {}/{}, rotating account",
-                status_code,
-                email,
-                attempt + 1,
-                max_attempts
-            );
-            continue;
-        }
-
-        // 只有 403 (权限/地区限制) 和 401 (认证失效) 触发账号轮换
-        if status_code == 403 || status_code == 401 {
-            tracing::warn!(
-                "OpenAI Upstream {} on account {} attempt {}/{}, rotating account",
-                status_code,
-                email,
-                attempt + 1,
-                max_attempts
-            );
+            // 3. 其他限流、服务器过载或认证错误: 轮换账号，或在原账号上退避后重试
+            if retry_policy.rotate_account {
+                tracing::warn!(
+                    "OpenAI Upstream {} on {} attempt {}/{}, rotating account",
+                    status_code,
+                    email,
+                    attempt + 1,
+                    max_attempts
+                );
+            } else {
+                let delay = retry_policy.backoff(attempt);
+                tracing::warn!(
+                    "OpenAI Upstream {} on {} attempt {}/{}, retrying same account in {}ms",
+                    status_code,
+                    email,
+                    attempt + 1,
+                    max_attempts,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+            }
             continue;
         }
 
@@ -246,6 +306,7 @@ pub async fn handle_completions(
     );
 
     let is_codex_style = body.get("input").is_some() && body.get("instructions").is_some();
+    let mut legacy_options = crate::proxy::mappers::openai::legacy::LegacyCompletionOptions::default();
 
     // 1. Convert Payload to Messages (Shared Chat Format)
     if is_codex_style {
@@ -472,28 +533,17 @@ pub async fn handle_completions(
         if let Some(obj) = body.as_object_mut() {
             obj.insert("messages".to_string(), json!(messages));
         }
-    } else if let Some(prompt_val) = body.get("prompt") {
+    } else if body.get("prompt").is_some() {
         // Legacy OpenAI Style: prompt -> Chat
-        let prompt_str = match prompt_val {
-            Value::String(s) => s.clone(),
-            Value::Array(arr) => arr
-                .iter()
-                .filter_map(|v| v.as_str())
-                .collect::<Vec<_>>()
-                .join("\n"),
-            _ => prompt_val.to_string(),
-        };
-        let messages = json!([ { "role": "user", "content": prompt_str } ]);
-        if let Some(obj) = body.as_object_mut() {
-            obj.remove("prompt");
-            obj.insert("messages".to_string(), messages);
-        }
+        legacy_options = crate::proxy::mappers::openai::legacy::legacy_to_chat(&mut body)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
     }
 
     // 2. Reuse handle_chat_completions logic (wrapping with custom handler or direct call)
     // Actually, due to SSE handling differences (Codex uses different event format), we replicate the loop here or abstract it.
     // For now, let's replicate the core loop but with Codex specific SSE mapping.
 
+    let features = RequestFeatures::from_body(&body);
     let mut openai_req: OpenAIRequest = serde_json::from_value(body.clone())
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
 
@@ -513,20 +563,29 @@ pub async fn handle_completions(
     }
 
     let upstream = state.upstream.clone();
+    let timeouts = state.timeouts.read().await.resolve(if is_codex_style { "/v1/responses" } else { "/v1/completions" });
     let token_manager = state.token_manager;
     let pool_size = token_manager.len();
-    let max_attempts = MAX_RETRY_ATTEMPTS.min(pool_size).max(1);
+    let retry_policy = token_manager.retry_policy().await;
+    let max_attempts = retry_policy.attempts(pool_size, openai_req.stream);
 
     let mut last_error = String::new();
+    let mut previous_account: Option<String> = None;
 
-    for _attempt in 0..max_attempts {
+    for attempt in 0..max_attempts {
         let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
             &openai_req.model,
             &*state.custom_mapping.read().await,
+            &*state.mapping_rules.read().await,
             &*state.openai_mapping.read().await,
             &*state.anthropic_mapping.read().await,
             false,  // OpenAI 请求不应用 Claude 家族映射
         );
+        // 超出上游模型能力的请求直接拒绝，不占用账号
+        token_manager
+            .check_model_request(&mapped_model, &features)
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
         // 将 OpenAI 工具转为 Value 数组以便探测联网
         let tools_val: Option<Vec<Value>> = openai_req
             .tools
@@ -539,7 +598,16 @@ pub async fn handle_completions(
         );
 
         let (access_token, project_id, email) =
-            match token_manager.get_token(&config.request_type, false, None).await {
+            match token_for_attempt(
+                &token_manager,
+                &retry_policy,
+                previous_account.as_deref(),
+                &config.request_type,
+                None,
+                Some(&config.final_model),
+            )
+            .await
+            {
                 Ok(t) => t,
                 Err(e) => {
                     return Err((
@@ -550,14 +618,28 @@ pub async fn handle_completions(
             };
 
         info!("✓ Using account: {} (type: {})", email, config.request_type);
+        previous_account = Some(email.clone());
 
-        let gemini_body = transform_openai_request(&openai_req, &project_id, &mapped_model);
+        let mut gemini_body = transform_openai_request(&openai_req, &project_id, &mapped_model);
 
         // [New] 打印转换后的报文 (Gemini Body) 供调试 (Codex 路径)
         if let Ok(body_json) = serde_json::to_string_pretty(&gemini_body) {
             debug!("[Codex-Request] Transformed Gemini Body:\n{}", body_json);
         }
 
+        if let Err(e) = crate::proxy::context_overflow::fit_request(
+            &token_manager,
+            &upstream,
+            &access_token,
+            &mapped_model,
+            &mut gemini_body,
+            &timeouts,
+        )
+        .await
+        {
+            return Err((StatusCode::BAD_REQUEST, e));
+        }
+
         let list_response = openai_req.stream;
         let method = if list_response {
             "streamGenerateContent"
@@ -566,11 +648,29 @@ pub async fn handle_completions(
         };
         let query_string = if list_response { Some("alt=sse") } else { None };
 
+        let resume = if list_response {
+            crate::proxy::stream_resume::ResumeContext::new(
+                &token_manager,
+                &upstream,
+                &gemini_body,
+                &config.request_type,
+                &config.final_model,
+                &email,
+                &timeouts,
+            )
+        } else {
+            None
+        };
+
+        let upstream_start = std::time::Instant::now();
         let response = match upstream
-            .call_v1_internal(method, &access_token, gemini_body, query_string)
+            .call_v1_internal(method, &access_token, gemini_body, query_string, &timeouts)
             .await
         {
-            Ok(r) => r,
+            Ok(r) => {
+                token_manager.record_latency(&email, upstream_start.elapsed());
+                r
+            }
             Err(e) => {
                 last_error = e.clone();
                 continue;
@@ -583,20 +683,33 @@ pub async fn handle_completions(
                 use axum::body::Body;
                 use axum::response::Response;
 
-                let gemini_stream = response.bytes_stream();
+                let armed = resume.is_some();
+                let gemini_stream =
+                    crate::proxy::stream_resume::upstream_stream(response, &timeouts, resume);
                 let body = if is_codex_style {
                     use crate::proxy::mappers::openai::streaming::create_codex_sse_stream;
                     let s =
-                        create_codex_sse_stream(Box::pin(gemini_stream), openai_req.model.clone());
-                    Body::from_stream(s)
+                        create_codex_sse_stream(gemini_stream, openai_req.model.clone());
+                    Body::from_stream(crate::proxy::timeouts::with_heartbeat(
+                        s,
+                        timeouts.heartbeat,
+                        crate::proxy::timeouts::SSE_COMMENT_HEARTBEAT,
+                    ))
                 } else {
                     use crate::proxy::mappers::openai::streaming::create_legacy_sse_stream;
-                    let s =
-                        create_legacy_sse_stream(Box::pin(gemini_stream), openai_req.model.clone());
-                    Body::from_stream(s)
+                    let s = create_legacy_sse_stream(
+                        gemini_stream,
+                        openai_req.model.clone(),
+                        legacy_options.echo.clone(),
+                    );
+                    Body::from_stream(crate::proxy::timeouts::with_heartbeat(
+                        s,
+                        timeouts.heartbeat,
+                        crate::proxy::timeouts::SSE_COMMENT_HEARTBEAT,
+                    ))
                 };
 
-                return Ok(Response::builder()
+                return Ok(crate::proxy::stream_resume::annotate(Response::builder(), armed)
                     .header("Content-Type", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .header("Connection", "keep-alive")
@@ -613,25 +726,10 @@ pub async fn handle_completions(
             let chat_resp = transform_openai_response(&gemini_resp);
 
             // Map Chat Response -> Legacy Completions Response
-            let choices = chat_resp.choices.iter().map(|c| {
-                json!({
-                    "text": match &c.message.content {
-                        Some(crate::proxy::mappers::openai::OpenAIContent::String(s)) => s.clone(),
-                        _ => "".to_string()
-                    },
-                    "index": c.index,
-                    "logprobs": null,
-                    "finish_reason": c.finish_reason
-                })
-            }).collect::<Vec<_>>();
-
-            let legacy_resp = json!({
-                "id": chat_resp.id,
-                "object": "text_completion",
-                "created": chat_resp.created,
-                "model": chat_resp.model,
-                "choices": choices
-            });
+            let legacy_resp = crate::proxy::mappers::openai::legacy::chat_to_legacy_response(
+                &chat_resp,
+                &legacy_options,
+            );
 
             return Ok(axum::Json(legacy_resp).into_response());
         }
@@ -640,8 +738,12 @@ pub async fn handle_completions(
         let status_code = status.as_u16();
         let error_text = response.text().await.unwrap_or_default();
         last_error = format!("HTTP {}: {}", status_code, error_text);
+        token_manager.record_upstream_error(&email, Some(status_code), &error_text);
 
-        if status_code == 429 || status_code == 403 || status_code == 401 {
+        if retry_policy.should_retry(status_code) {
+            if !retry_policy.rotate_account {
+                tokio::time::sleep(retry_policy.backoff(attempt)).await;
+            }
             continue;
         }
         return Err((status, error_text));
@@ -654,27 +756,38 @@ pub async fn handle_completions(
 }
 
 pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoResponse {
-    use crate::proxy::common::model_mapping::get_all_dynamic_models;
-
-    let model_ids = get_all_dynamic_models(
-        &state.openai_mapping,
-        &state.custom_mapping,
-        &state.anthropic_mapping,
-    ).await;
-
-    let data: Vec<_> = model_ids.into_iter().map(|id| {
-        json!({
+    let models = crate::proxy::handlers::common::listed_models(&state).await;
+
+    let mut data = Vec::with_capacity(models.len());
+    let mut quotas = Vec::new();
+    for (id, quota) in models {
+        let capabilities =
+            crate::proxy::handlers::common::listed_model_capabilities(&state, &id).await;
+        let mut entry = json!({
             "id": id,
             "object": "model",
             "created": 1706745600,
             "owned_by": "antigravity"
-        })
-    }).collect();
+        });
+        // 扩展字段: 上下文长度与视觉/工具/思考支持 (未知模型省略)
+        if let Some(caps) = capabilities {
+            entry["context_window"] = json!(caps.max_context_tokens);
+            entry["capabilities"] = json!(caps);
+        }
+        // 扩展字段: 账号池中该模型的剩余配额
+        if let Some(quota) = quota {
+            entry["quota"] = json!(quota);
+            quotas.push(quota);
+        }
+        data.push(entry);
+    }
 
-    Json(json!({
+    let response = Json(json!({
         "object": "list",
         "data": data
     }))
+    .into_response();
+    crate::proxy::handlers::common::with_pool_quota_header(&state, response, &quotas).await
 }
 
 /// OpenAI Images API: POST /v1/images/generations
@@ -748,6 +861,7 @@ pub async fn handle_images_generations(
 
     // 3. 获取 Token
     let upstream = state.upstream.clone();
+    let timeouts = state.timeouts.read().await.resolve("/v1/images/generations");
     let token_manager = state.token_manager;
 
     let (access_token, project_id, email) = match token_manager.get_token("image_gen", false, None).await
@@ -803,7 +917,7 @@ pub async fn handle_images_generations(
             });
 
             match upstream
-                .call_v1_internal("generateContent", &access_token, gemini_body, None)
+                .call_v1_internal("generateContent", &access_token, gemini_body, None, &timeouts)
                 .await
             {
                 Ok(response) => {
@@ -998,6 +1112,7 @@ pub async fn handle_images_edits(
 
     // 1. 获取 Upstream
     let upstream = state.upstream.clone();
+    let timeouts = state.timeouts.read().await.resolve("/v1/images/edits");
     let token_manager = state.token_manager;
     // Fix: Proper get_token call with correct signature and unwrap (using image_gen quota)
     let (access_token, project_id, _email) = match token_manager.get_token("image_gen", false, None).await
@@ -1074,7 +1189,7 @@ pub async fn handle_images_edits(
 
         tasks.push(tokio::spawn(async move {
             match upstream
-                .call_v1_internal("generateContent", &access_token, body, None)
+                .call_v1_internal("generateContent", &access_token, body, None, &timeouts)
                 .await
             {
                 Ok(response) => {
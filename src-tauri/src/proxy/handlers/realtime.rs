@@ -0,0 +1,248 @@
+// WebSocket 实时桥接 (`/v1/realtime`): 客户端通过 WebSocket 发起请求，经完整反代管线调用上游流式接口并逐帧推送
+//
+// 客户端事件:
+//   {"type": "response.create", "id": "r1", "endpoint": "/v1/chat/completions", "body": {...}}
+//   {"type": "response.cancel", "id": "r1"}
+// 服务端事件: response.chunk / response.done / response.cancelled / error
+use axum::{
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{header, Method, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Router,
+};
+use eventsource_stream::Eventsource;
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Weak;
+use tokio::sync::mpsc;
+
+use crate::proxy::server::AppState;
+
+/// 未指定 endpoint 时使用 OpenAI Chat Completions
+const DEFAULT_ENDPOINT: &str = "/v1/chat/completions";
+/// 非流式响应的最大读取长度
+const MAX_RESPONSE_BYTES: usize = 100 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientEvent {
+    #[serde(rename = "response.create")]
+    Create {
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default)]
+        endpoint: Option<String>,
+        body: Value,
+    },
+    #[serde(rename = "response.cancel")]
+    Cancel { id: String },
+}
+
+/// 构建内部流式请求；仅允许对话类端点
+pub fn build_stream_request(endpoint: &str, mut body: Value) -> Result<Request<Body>, String> {
+    let path = endpoint.split('?').next().unwrap_or(endpoint);
+    let uri = match path {
+        "/v1/chat/completions" | "/v1/completions" | "/v1/responses" | "/v1/messages" => {
+            let Some(object) = body.as_object_mut() else {
+                return Err("Request body must be a JSON object".to_string());
+            };
+            object.entry("stream").or_insert(Value::Bool(true));
+            path.to_string()
+        }
+        p if p.starts_with("/v1beta/models/") && p.ends_with(":streamGenerateContent") => {
+            format!("{}?alt=sse", p)
+        }
+        _ => return Err(format!("Unsupported realtime endpoint: {}", endpoint)),
+    };
+
+    Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .map_err(|e| format!("构建桥接请求失败: {}", e))
+}
+
+fn parse_data(data: &str) -> Value {
+    serde_json::from_str(data).unwrap_or_else(|_| Value::String(data.to_string()))
+}
+
+fn error_event(id: Option<&str>, status: u16, error: Value) -> Value {
+    json!({ "type": "error", "id": id, "status": status, "error": error })
+}
+
+/// GET /v1/realtime — 升级为 WebSocket (鉴权在升级请求上完成)
+pub async fn handle_realtime(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    let Some(router) = state.replay_router.get().cloned() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Proxy is starting").into_response();
+    };
+    ws.on_upgrade(move |socket| bridge(socket, router))
+}
+
+async fn bridge(socket: WebSocket, router: Weak<Router>) {
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::channel::<Value>(64);
+    let writer = tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            if sink.send(Message::Text(event.to_string())).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let mut inflight: HashMap<String, tokio::task::AbortHandle> = HashMap::new();
+    let mut counter = 0u64;
+    while let Some(Ok(message)) = stream.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        inflight.retain(|_, handle| !handle.is_finished());
+
+        match serde_json::from_str::<ClientEvent>(&text) {
+            Ok(ClientEvent::Create { id, endpoint, body }) => {
+                counter += 1;
+                let id = id.unwrap_or_else(|| format!("resp_{}", counter));
+                let request = match build_stream_request(endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT), body) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        let _ = tx.send(error_event(Some(&id), 400, Value::String(e))).await;
+                        continue;
+                    }
+                };
+                // 服务停止后路由失效，结束连接
+                let Some(router) = router.upgrade() else {
+                    let _ = tx
+                        .send(error_event(Some(&id), 503, Value::String("Proxy is shutting down".to_string())))
+                        .await;
+                    break;
+                };
+                let handle = tokio::spawn(forward((*router).clone(), request, id.clone(), tx.clone()));
+                inflight.insert(id, handle.abort_handle());
+            }
+            Ok(ClientEvent::Cancel { id }) => {
+                if let Some(handle) = inflight.remove(&id) {
+                    handle.abort();
+                    let _ = tx.send(json!({ "type": "response.cancelled", "id": id })).await;
+                }
+            }
+            Err(e) => {
+                let _ = tx
+                    .send(error_event(None, 400, Value::String(format!("Invalid event: {}", e))))
+                    .await;
+            }
+        }
+    }
+
+    for handle in inflight.values() {
+        handle.abort();
+    }
+    drop(tx);
+    let _ = writer.await;
+}
+
+/// 执行单个请求并将 SSE 事件逐条转发
+async fn forward(mut router: Router, request: Request<Body>, id: String, tx: mpsc::Sender<Value>) {
+    use tower::Service;
+
+    let response = match router.call(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let status = response.status();
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| v.contains("text/event-stream"));
+
+    if !status.is_success() || !is_sse {
+        let body = match axum::body::to_bytes(response.into_body(), MAX_RESPONSE_BYTES).await {
+            Ok(bytes) => parse_data(&String::from_utf8_lossy(&bytes)),
+            Err(e) => Value::String(format!("Failed to read response: {}", e)),
+        };
+        let event = if status.is_success() {
+            json!({ "type": "response.chunk", "id": id, "data": body })
+        } else {
+            error_event(Some(&id), status.as_u16(), body)
+        };
+        let _ = tx.send(event).await;
+        if status.is_success() {
+            let _ = tx.send(json!({ "type": "response.done", "id": id })).await;
+        }
+        return;
+    }
+
+    let mut events = response.into_body().into_data_stream().eventsource();
+    while let Some(event) = events.next().await {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                let _ = tx
+                    .send(error_event(Some(&id), 502, Value::String(format!("Upstream stream error: {}", e))))
+                    .await;
+                return;
+            }
+        };
+        if event.data.trim() == "[DONE]" {
+            break;
+        }
+        let name = (!event.event.is_empty() && event.event != "message").then_some(event.event);
+        let chunk = json!({ "type": "response.chunk", "id": id, "event": name, "data": parse_data(&event.data) });
+        if tx.send(chunk).await.is_err() {
+            return;
+        }
+    }
+    let _ = tx.send(json!({ "type": "response.done", "id": id })).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_client_events() {
+        let event: ClientEvent = serde_json::from_str(
+            r#"{"type":"response.create","body":{"model":"gemini-2.5-flash","messages":[]}}"#,
+        )
+        .unwrap();
+        assert!(matches!(event, ClientEvent::Create { id: None, endpoint: None, .. }));
+
+        let event: ClientEvent = serde_json::from_str(r#"{"type":"response.cancel","id":"r1"}"#).unwrap();
+        assert!(matches!(event, ClientEvent::Cancel { id } if id == "r1"));
+
+        assert!(serde_json::from_str::<ClientEvent>(r#"{"type":"session.update"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn builds_streaming_requests_for_chat_endpoints() {
+        let request = build_stream_request("/v1/messages", json!({"model": "claude-sonnet-4-5"})).unwrap();
+        assert_eq!(request.uri(), "/v1/messages");
+        let bytes = axum::body::to_bytes(request.into_body(), 1024).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["stream"], true);
+
+        // 显式关闭流式时保持不变
+        let request = build_stream_request("/v1/chat/completions", json!({"stream": false})).unwrap();
+        let bytes = axum::body::to_bytes(request.into_body(), 1024).await.unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&bytes).unwrap()["stream"], false);
+
+        let request = build_stream_request(
+            "/v1beta/models/gemini-2.5-flash:streamGenerateContent",
+            json!({"contents": []}),
+        )
+        .unwrap();
+        assert_eq!(request.uri(), "/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse");
+
+        assert!(build_stream_request("/admin/keys/rotate", json!({})).is_err());
+        assert!(build_stream_request("/v1/messages", json!([1, 2])).is_err());
+    }
+}
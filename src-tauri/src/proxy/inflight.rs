@@ -0,0 +1,209 @@
+// 在途请求跟踪: 记录正在处理的请求 (模型、账号、耗时、客户端密钥)，支持按 ID 取消卡住的请求
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Notify;
+
+/// 单个在途请求 (`GET /admin/requests`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InflightRequest {
+    /// 请求 ID (与请求日志一致)
+    pub id: String,
+    pub method: String,
+    pub path: String,
+    pub model: Option<String>,
+    /// 服务该请求的账号 (收到上游响应头后才确定)
+    pub account: Option<String>,
+    /// 客户端 API 密钥指纹
+    pub key_id: Option<String>,
+    pub client_ip: Option<String>,
+    /// 开始时间 (Unix 毫秒)
+    pub started_at: i64,
+    /// 已持续时间 (毫秒)
+    #[serde(default)]
+    pub age_ms: u64,
+    /// 是否已开始返回流式响应
+    #[serde(default)]
+    pub streaming: bool,
+}
+
+struct Entry {
+    info: InflightRequest,
+    started: Instant,
+    cancel: Arc<Notify>,
+}
+
+#[derive(Default)]
+pub struct InflightRegistry {
+    entries: DashMap<String, Entry>,
+}
+
+/// 请求结束 (含客户端断开) 时自动移除登记
+pub struct InflightGuard {
+    registry: Arc<InflightRegistry>,
+    id: String,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.registry.entries.remove(&self.id);
+    }
+}
+
+impl InflightRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记请求，返回移除守卫与取消信号
+    pub fn register(self: &Arc<Self>, info: InflightRequest) -> (InflightGuard, Arc<Notify>) {
+        let cancel = Arc::new(Notify::new());
+        let id = info.id.clone();
+        self.entries.insert(
+            id.clone(),
+            Entry {
+                info,
+                started: Instant::now(),
+                cancel: cancel.clone(),
+            },
+        );
+        (
+            InflightGuard {
+                registry: self.clone(),
+                id,
+            },
+            cancel,
+        )
+    }
+
+    /// 收到上游响应头后补充账号与是否流式
+    pub fn update(&self, id: &str, account: Option<String>, streaming: bool) {
+        if let Some(mut entry) = self.entries.get_mut(id) {
+            if account.is_some() {
+                entry.info.account = account;
+            }
+            entry.info.streaming = streaming;
+        }
+    }
+
+    /// 当前在途请求，按持续时间从长到短排列
+    pub fn list(&self) -> Vec<InflightRequest> {
+        let mut list: Vec<InflightRequest> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut info = entry.info.clone();
+                info.age_ms = entry.started.elapsed().as_millis() as u64;
+                info
+            })
+            .collect();
+        list.sort_by(|a, b| b.age_ms.cmp(&a.age_ms));
+        list
+    }
+
+    /// 取消指定请求；请求不存在 (已结束) 时返回 false
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.entries.get(id) {
+            Some(entry) => {
+                entry.cancel.notify_one();
+                tracing::warn!(
+                    "[Inflight] 已取消请求 {} ({} {}, account: {:?})",
+                    id,
+                    entry.info.method,
+                    entry.info.path,
+                    entry.info.account
+                );
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 在途请求表格 (CLI `--proxy-active`)
+pub fn format_inflight(requests: &[InflightRequest]) -> String {
+    if requests.is_empty() {
+        return "no active requests\n".to_string();
+    }
+    let mut out = format!(
+        "{:<36} {:>8} {:<6} {:<28} {:<30} {:<30} {}\n",
+        "ID", "AGE", "KIND", "MODEL", "ACCOUNT", "PATH", "KEY"
+    );
+    for r in requests {
+        out.push_str(&format!(
+            "{:<36} {:>8} {:<6} {:<28} {:<30} {:<30} {}\n",
+            r.id,
+            crate::models::quota::format_countdown((r.age_ms / 1000) as i64),
+            if r.streaming { "stream" } else { "-" },
+            r.model.as_deref().unwrap_or("-"),
+            r.account.as_deref().unwrap_or("-"),
+            r.path,
+            r.key_id.as_deref().unwrap_or("-"),
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: &str) -> InflightRequest {
+        InflightRequest {
+            id: id.to_string(),
+            method: "POST".to_string(),
+            path: "/v1/messages".to_string(),
+            model: Some("claude-sonnet-4-5".to_string()),
+            account: None,
+            key_id: Some("k1".to_string()),
+            client_ip: None,
+            started_at: 0,
+            age_ms: 0,
+            streaming: false,
+        }
+    }
+
+    #[test]
+    fn guard_removes_entry() {
+        let registry = Arc::new(InflightRegistry::new());
+        let (guard, _) = registry.register(request("a"));
+        let (_other, _) = registry.register(request("b"));
+        assert_eq!(registry.list().len(), 2);
+        drop(guard);
+        let list = registry.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].id, "b");
+    }
+
+    #[test]
+    fn update_fills_account_and_streaming() {
+        let registry = Arc::new(InflightRegistry::new());
+        let (_guard, _) = registry.register(request("a"));
+        registry.update("a", Some("user@example.com".to_string()), true);
+        registry.update("a", None, true);
+        let info = &registry.list()[0];
+        assert_eq!(info.account.as_deref(), Some("user@example.com"));
+        assert!(info.streaming);
+    }
+
+    #[tokio::test]
+    async fn cancel_signals_request() {
+        let registry = Arc::new(InflightRegistry::new());
+        let (_guard, cancel) = registry.register(request("a"));
+        assert!(registry.cancel("a"));
+        assert!(!registry.cancel("missing"));
+        // 取消信号在等待开始前发出也不会丢失
+        tokio::time::timeout(std::time::Duration::from_secs(1), cancel.notified())
+            .await
+            .expect("cancel signal");
+    }
+
+    #[test]
+    fn format_lists_requests() {
+        assert_eq!(format_inflight(&[]), "no active requests\n");
+        let out = format_inflight(&[request("a")]);
+        assert!(out.contains("claude-sonnet-4-5"));
+        assert_eq!(out.lines().count(), 2);
+    }
+}
@@ -0,0 +1,118 @@
+// API Key 轮换: 生成新密钥，旧密钥在宽限期内继续有效，便于客户端无停机迁移
+use serde::Serialize;
+
+use crate::modules::audit::{AuditAction, AuditActor};
+use crate::proxy::config::{ProxyConfig, RetiredApiKey};
+
+/// webhook 通知超时 (通知失败不影响轮换结果)
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// 轮换结果 (新密钥明文仅在此返回一次)
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRotation {
+    pub api_key: String,
+    /// 旧密钥失效时间 (Unix 秒)；原先没有密钥时为空
+    pub previous_expires_at: Option<i64>,
+    pub rotated_at: i64,
+}
+
+/// 在配置上执行轮换: 旧密钥移入宽限列表，并清理已过期的旧密钥
+pub fn rotate(config: &mut ProxyConfig, grace_secs: u64, now: i64) -> KeyRotation {
+    let api_key = crate::commands::proxy::generate_api_key();
+    let previous = std::mem::replace(&mut config.api_key, api_key.clone());
+
+    config.previous_api_keys.retain(|k| k.expires_at > now);
+    let previous_expires_at = if previous.is_empty() || grace_secs == 0 {
+        None
+    } else {
+        let expires_at = now + grace_secs as i64;
+        config.previous_api_keys.push(RetiredApiKey {
+            key: previous,
+            expires_at,
+        });
+        Some(expires_at)
+    };
+
+    KeyRotation {
+        api_key,
+        previous_expires_at,
+        rotated_at: now,
+    }
+}
+
+/// 轮换并持久化全局配置，记录审计并发送 webhook 通知；返回轮换结果与新的反代配置
+pub async fn rotate_and_save(
+    grace_secs: Option<u64>,
+    actor: AuditActor,
+) -> Result<(KeyRotation, ProxyConfig), String> {
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let grace = grace_secs.unwrap_or(app_config.proxy.key_rotation.grace_period_secs);
+    let rotation = rotate(&mut app_config.proxy, grace, chrono::Utc::now().timestamp());
+    crate::modules::config::save_app_config(&app_config)?;
+
+    crate::modules::audit::record(
+        actor,
+        AuditAction::KeyRotate,
+        Some("proxy.api_key"),
+        Some(serde_json::json!({ "previous_expires_at": rotation.previous_expires_at })),
+    );
+    tracing::info!(
+        "API Key 已轮换，旧密钥宽限期至 {:?}",
+        rotation.previous_expires_at
+    );
+
+    let webhook = app_config.proxy.key_rotation.webhook_url.trim().to_string();
+    if !webhook.is_empty() {
+        let payload = serde_json::json!({
+            "event": "api_key_rotated",
+            "key_prefix": crate::proxy::secrets::redact_secret(&rotation.api_key),
+            "previous_expires_at": rotation.previous_expires_at,
+            "rotated_at": rotation.rotated_at,
+        });
+        let result = reqwest::Client::new()
+            .post(&webhook)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => tracing::warn!("密钥轮换通知失败: HTTP {}", resp.status()),
+            Err(e) => tracing::warn!("密钥轮换通知失败: {}", e),
+        }
+    }
+
+    // 保存时可能已哈希，返回落盘后的配置供热更新
+    let saved = crate::modules::config::load_app_config()?.proxy;
+    Ok((rotation, saved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_keeps_old_key_for_grace_period() {
+        let mut config = ProxyConfig {
+            api_key: "sk-old".to_string(),
+            previous_api_keys: vec![RetiredApiKey {
+                key: "sk-ancient".to_string(),
+                expires_at: 50,
+            }],
+            ..Default::default()
+        };
+
+        let rotation = rotate(&mut config, 3600, 100);
+        assert_eq!(config.api_key, rotation.api_key);
+        assert_ne!(rotation.api_key, "sk-old");
+        assert_eq!(rotation.previous_expires_at, Some(3700));
+        // 已过期的旧密钥被清理
+        assert_eq!(config.previous_api_keys.len(), 1);
+        assert_eq!(config.previous_api_keys[0].key, "sk-old");
+
+        // 宽限期为 0 时旧密钥立即失效
+        let rotation = rotate(&mut config, 0, 200);
+        assert_eq!(rotation.previous_expires_at, None);
+        assert_eq!(config.previous_api_keys.len(), 1);
+    }
+}
@@ -0,0 +1,133 @@
+// API Key 权限范围：按请求方法与路径判断附加密钥能否访问某个接口
+use axum::http::Method;
+
+use crate::proxy::config::KeyScope;
+
+/// 对话 / 补全类接口 (含模型列表、Token 计数与客户端遥测)
+fn is_chat(method: &Method, path: &str) -> bool {
+    if matches!(
+        path,
+        "/v1/chat/completions"
+            | "/v1/completions"
+            | "/v1/responses"
+            | "/v1/realtime"
+            | "/v1/messages"
+            | "/v1/messages/count_tokens"
+            | "/v1/models"
+            | "/v1/models/claude"
+            | "/v1/models/detect"
+            | "/v1/api/event_logging"
+            | "/v1/api/event_logging/batch"
+            | "/v1beta/models"
+    ) {
+        return true;
+    }
+    let Some(rest) = path.strip_prefix("/v1beta/models/") else {
+        return false;
+    };
+    if *method == Method::GET {
+        return !rest.contains('/');
+    }
+    rest.ends_with("/countTokens")
+        || [":generateContent", ":streamGenerateContent", ":countTokens"]
+            .iter()
+            .any(|action| rest.ends_with(action))
+}
+
+fn is_embeddings(path: &str) -> bool {
+    path == "/v1/embeddings" || path.ends_with(":embedContent") || path.ends_with(":batchEmbedContents")
+}
+
+/// 管理 / 统计 / 调试接口与内置仪表盘
+pub(crate) fn is_admin(path: &str) -> bool {
+    path.starts_with("/admin/") || path.starts_with("/stats/") || path.starts_with("/debug/") || is_dashboard(path)
+}
+
+fn is_dashboard(path: &str) -> bool {
+    path == "/dashboard" || path == "/dashboard/data"
+}
+
+/// 只读统计接口
+fn is_stats(method: &Method, path: &str) -> bool {
+    *method == Method::GET
+        && matches!(
+            path,
+            "/healthz"
+                | "/admin/status"
+                | "/admin/latency"
+                | "/admin/usage"
+                | "/admin/quota-cache"
+                | "/stats/models"
+        )
+        || (*method == Method::GET && is_dashboard(path))
+}
+
+/// 具有 `scopes` 的密钥能否访问该接口 (无范围表示不限制)
+pub fn is_allowed(scopes: &[KeyScope], method: &Method, path: &str) -> bool {
+    if scopes.contains(&KeyScope::NoEmbeddings) && is_embeddings(path) {
+        return false;
+    }
+    if scopes.contains(&KeyScope::NoAdmin) && is_admin(path) {
+        return false;
+    }
+    let mut allowlists = scopes
+        .iter()
+        .filter(|s| matches!(s, KeyScope::ChatOnly | KeyScope::ReadOnlyStats))
+        .peekable();
+    if allowlists.peek().is_none() {
+        return true;
+    }
+    allowlists.any(|scope| match scope {
+        KeyScope::ChatOnly => is_chat(method, path),
+        KeyScope::ReadOnlyStats => is_stats(method, path),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unscoped_keys_are_unrestricted() {
+        assert!(is_allowed(&[], &Method::POST, "/admin/keys/rotate"));
+    }
+
+    #[test]
+    fn read_only_stats_cannot_chat() {
+        let scopes = [KeyScope::ReadOnlyStats];
+        assert!(is_allowed(&scopes, &Method::GET, "/admin/status"));
+        assert!(is_allowed(&scopes, &Method::GET, "/admin/usage"));
+        assert!(is_allowed(&scopes, &Method::GET, "/dashboard/data"));
+        assert!(is_allowed(&scopes, &Method::GET, "/stats/models"));
+        assert!(!is_allowed(&scopes, &Method::POST, "/v1/chat/completions"));
+        assert!(!is_allowed(&scopes, &Method::GET, "/admin/sessions"));
+        assert!(!is_allowed(&scopes, &Method::POST, "/admin/keys/rotate"));
+    }
+
+    #[test]
+    fn chat_only_covers_all_protocols() {
+        let scopes = [KeyScope::ChatOnly];
+        assert!(is_allowed(&scopes, &Method::POST, "/v1/messages"));
+        assert!(is_allowed(&scopes, &Method::POST, "/v1beta/models/gemini-2.5-pro:streamGenerateContent"));
+        assert!(is_allowed(&scopes, &Method::GET, "/v1beta/models/gemini-2.5-pro"));
+        assert!(!is_allowed(&scopes, &Method::POST, "/v1/images/generations"));
+        assert!(!is_allowed(&scopes, &Method::POST, "/v1/messages/batches"));
+        assert!(!is_allowed(&scopes, &Method::GET, "/admin/status"));
+    }
+
+    #[test]
+    fn deny_scopes_combine_with_allowlists() {
+        let scopes = [KeyScope::NoAdmin, KeyScope::NoEmbeddings];
+        assert!(is_allowed(&scopes, &Method::POST, "/v1/images/generations"));
+        assert!(!is_allowed(&scopes, &Method::GET, "/admin/status"));
+        assert!(!is_allowed(&scopes, &Method::POST, "/debug/translate"));
+        assert!(!is_allowed(&scopes, &Method::GET, "/dashboard"));
+        assert!(!is_allowed(&scopes, &Method::GET, "/stats/models"));
+        assert!(!is_allowed(&scopes, &Method::POST, "/v1beta/models/text-embedding-004:embedContent"));
+
+        let both = [KeyScope::ChatOnly, KeyScope::ReadOnlyStats];
+        assert!(is_allowed(&both, &Method::POST, "/v1/chat/completions"));
+        assert!(is_allowed(&both, &Method::GET, "/admin/latency"));
+    }
+}
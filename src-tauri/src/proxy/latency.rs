@@ -0,0 +1,123 @@
+// 按账号统计上游延迟 (滚动窗口 p50/p95)，供延迟优先调度使用
+use dashmap::DashMap;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// 每个账号保留的最近样本数
+const WINDOW_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub samples: usize,
+}
+
+/// 上游首字节延迟跟踪器 (key: 账号邮箱)
+#[derive(Default)]
+pub struct LatencyTracker {
+    samples: DashMap<String, VecDeque<u64>>,
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, key: &str, latency_ms: u64) {
+        let mut window = self.samples.entry(key.to_string()).or_default();
+        if window.len() >= WINDOW_SIZE {
+            window.pop_front();
+        }
+        window.push_back(latency_ms);
+    }
+
+    pub fn stats(&self, key: &str) -> Option<LatencyStats> {
+        let window = self.samples.get(key)?;
+        compute_stats(window.iter().copied())
+    }
+
+    /// 所有账号的延迟统计，按 p50 升序
+    pub fn snapshot(&self) -> Vec<(String, LatencyStats)> {
+        let mut all: Vec<_> = self
+            .samples
+            .iter()
+            .filter_map(|e| compute_stats(e.value().iter().copied()).map(|s| (e.key().clone(), s)))
+            .collect();
+        all.sort_by_key(|(_, s)| s.p50_ms);
+        all
+    }
+}
+
+fn compute_stats(samples: impl Iterator<Item = u64>) -> Option<LatencyStats> {
+    let mut sorted: Vec<u64> = samples.collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_unstable();
+    let pick = |q: f64| sorted[((sorted.len() - 1) as f64 * q).round() as usize];
+    Some(LatencyStats {
+        p50_ms: pick(0.5),
+        p95_ms: pick(0.95),
+        samples: sorted.len(),
+    })
+}
+
+/// 按延迟加权随机排序 (权重 ∝ 1/p50²)，越快的账号越可能排在前面
+///
+/// 尚无样本的账号使用已知最快的延迟，保证新账号也能被探测到。
+pub fn weighted_order<R: Rng>(p50s: &[Option<u64>], rng: &mut R) -> Vec<usize> {
+    let fastest = p50s.iter().flatten().copied().min().unwrap_or(1).max(1);
+    let mut keyed: Vec<(f64, usize)> = p50s
+        .iter()
+        .enumerate()
+        .map(|(i, p50)| {
+            let ratio = fastest as f64 / p50.unwrap_or(fastest).max(1) as f64;
+            let weight = ratio * ratio;
+            // Efraimidis–Spirakis 加权随机抽样: key = u^(1/w)
+            let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+            (u.powf(1.0 / weight), i)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().map(|(_, i)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn rolling_percentiles() {
+        let tracker = LatencyTracker::new();
+        for ms in 1..=100 {
+            tracker.record("a@example.com", ms);
+        }
+        let stats = tracker.stats("a@example.com").unwrap();
+        assert_eq!((stats.p50_ms, stats.p95_ms, stats.samples), (51, 95, 100));
+
+        // 窗口满后淘汰最旧样本
+        for _ in 0..100 {
+            tracker.record("a@example.com", 1000);
+        }
+        assert_eq!(tracker.stats("a@example.com").unwrap().p50_ms, 1000);
+        assert!(tracker.stats("b@example.com").is_none());
+    }
+
+    #[test]
+    fn faster_accounts_are_preferred() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let p50s = [Some(2000), Some(400), None];
+        let mut first = [0usize; 3];
+        for _ in 0..1000 {
+            let order = weighted_order(&p50s, &mut rng);
+            assert_eq!(order.len(), 3);
+            first[order[0]] += 1;
+        }
+        // 慢账号很少排第一，未采样账号与最快账号机会相当
+        assert!(first[0] < 50, "{:?}", first);
+        assert!(first[1] > 350 && first[2] > 350, "{:?}", first);
+    }
+}
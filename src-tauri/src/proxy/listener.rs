@@ -0,0 +1,371 @@
+// 反代监听器: 每个监听地址 (TCP / Unix 套接字) 独立接收连接 (可选 TLS)，停止时各自排空在途请求
+use axum::Router;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::watch;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, error};
+
+use crate::proxy::config::ListenerTlsConfig;
+
+/// TLS 握手超时
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 连接 IO (TCP / TLS 等统一装箱)
+pub trait ConnIo: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> ConnIo for T {}
+
+/// Unix 套接字默认权限: 仅属主可读写
+#[cfg(unix)]
+const DEFAULT_SOCKET_MODE: u32 = 0o600;
+
+/// 监听套接字的副本，持有期间套接字不会关闭，停止监听后需及时释放
+pub struct PortShare(socket2::Socket);
+
+impl PortShare {
+    /// 开启 / 关闭 SO_REUSEPORT: 开启后同一用户的新套接字可以绑定相同端口，仅在切换期间短暂开启
+    pub fn set(&self, enabled: bool) -> Result<(), String> {
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        return self
+            .0
+            .set_reuse_port(enabled)
+            .map_err(|e| format!("设置 SO_REUSEPORT 失败: {}", e));
+        #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+        {
+            let _ = enabled;
+            Err("当前平台不支持 SO_REUSEPORT".to_string())
+        }
+    }
+}
+
+/// 已绑定的监听套接字
+pub enum BoundListener {
+    Tcp(tokio::net::TcpListener),
+    /// Unix 套接字 (停止监听时删除套接字文件)
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, std::path::PathBuf),
+}
+
+impl BoundListener {
+    pub async fn bind_tcp(addr: &str) -> Result<Self, String> {
+        tokio::net::TcpListener::bind(addr)
+            .await
+            .map(Self::Tcp)
+            .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))
+    }
+
+    /// 绑定 TCP 地址并开启 SO_REUSEPORT，仅用于热切换主监听地址时与旧监听器短暂共用同一端口；
+    /// 切换完成后需通过 `PortShare::set(false)` 关闭，之后其他进程无法再绑定该端口
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    pub async fn bind_tcp_shared(addr: &str) -> Result<Self, String> {
+        use socket2::{Domain, Protocol, Socket, Type};
+
+        let failed = |e: std::io::Error| format!("地址 {} 绑定失败: {}", addr, e);
+        let resolved: SocketAddr = tokio::net::lookup_host(addr)
+            .await
+            .map_err(failed)?
+            .next()
+            .ok_or_else(|| format!("地址 {} 无法解析", addr))?;
+        let socket = Socket::new(Domain::for_address(resolved), Type::STREAM, Some(Protocol::TCP)).map_err(failed)?;
+        // 与 tokio::net::TcpListener::bind 一致: 允许复用 TIME_WAIT 状态的地址
+        socket.set_reuse_address(true).map_err(failed)?;
+        socket.set_reuse_port(true).map_err(failed)?;
+        socket.set_nonblocking(true).map_err(failed)?;
+        socket.bind(&resolved.into()).map_err(failed)?;
+        socket.listen(1024).map_err(failed)?;
+        tokio::net::TcpListener::from_std(socket.into())
+            .map(Self::Tcp)
+            .map_err(failed)
+    }
+
+    /// 不支持 SO_REUSEPORT 的平台: 无法与旧监听器共用端口
+    #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+    pub async fn bind_tcp_shared(addr: &str) -> Result<Self, String> {
+        Err(format!("地址 {} 绑定失败: 当前平台不支持 SO_REUSEPORT", addr))
+    }
+
+    /// 同一 TCP 监听套接字的句柄 (复制的文件描述符)，用于热切换期间开关 SO_REUSEPORT
+    pub fn port_share(&self) -> Option<PortShare> {
+        match self {
+            Self::Tcp(listener) => socket2::SockRef::from(listener).try_clone().ok().map(PortShare),
+            #[cfg(unix)]
+            Self::Unix(..) => None,
+        }
+    }
+
+    /// 绑定 Unix 套接字并设置文件权限；`mode` 为八进制字符串 (如 `660`)
+    #[cfg(unix)]
+    pub fn bind_unix(path: &str, mode: Option<&str>) -> Result<Self, String> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        let mode = match mode {
+            Some(m) => u32::from_str_radix(m.trim_start_matches("0o"), 8)
+                .ok()
+                .filter(|m| *m <= 0o777)
+                .ok_or_else(|| format!("无效的套接字权限: {}", m))?,
+            None => DEFAULT_SOCKET_MODE,
+        };
+
+        let path = std::path::PathBuf::from(path);
+        // 清理上次异常退出残留的套接字文件 (仅删除套接字，避免误删普通文件)
+        if let Ok(meta) = std::fs::symlink_metadata(&path) {
+            if !meta.file_type().is_socket() {
+                return Err(format!("{} 已存在且不是套接字文件", path.display()));
+            }
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Err(format!("套接字 {} 正被其他进程使用", path.display()));
+            }
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("删除残留套接字 {} 失败: {}", path.display(), e))?;
+        }
+
+        let listener = tokio::net::UnixListener::bind(&path)
+            .map_err(|e| format!("套接字 {} 绑定失败: {}", path.display(), e))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+            .map_err(|e| format!("设置套接字 {} 权限失败: {}", path.display(), e))?;
+        Ok(Self::Unix(listener, path))
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind_unix(path: &str, _mode: Option<&str>) -> Result<Self, String> {
+        Err(format!("当前平台不支持 Unix 套接字: {}", path))
+    }
+
+    async fn accept(&self) -> std::io::Result<(Box<dyn ConnIo>, SocketAddr)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, peer_addr) = listener.accept().await?;
+                Ok((Box::new(stream), peer_addr))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener, _) => {
+                let (stream, _) = listener.accept().await?;
+                // Unix 套接字只能来自本机，按回环地址处理
+                Ok((Box::new(stream), SocketAddr::from(([127, 0, 0, 1], 0))))
+            }
+        }
+    }
+}
+
+impl Drop for BoundListener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Self::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// 一个监听器及其路由 (各监听器可挂载不同的安全策略)
+pub struct Listener {
+    /// 用于日志的地址描述，如 `https://0.0.0.0:8443`
+    pub name: String,
+    pub bound: BoundListener,
+    pub app: Router,
+    pub tls: Option<TlsAcceptor>,
+}
+
+/// 读取 PEM 证书与私钥，构建 TLS 接收器
+pub fn load_tls_acceptor(config: &ListenerTlsConfig) -> Result<TlsAcceptor, String> {
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use tokio_rustls::rustls::{crypto::ring, ServerConfig};
+
+    let certs = CertificateDer::pem_file_iter(&config.cert_path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("读取 TLS 证书失败 ({}): {}", config.cert_path, e))?;
+    if certs.is_empty() {
+        return Err(format!("TLS 证书文件中没有证书: {}", config.cert_path));
+    }
+    let key = PrivateKeyDer::from_pem_file(&config.key_path)
+        .map_err(|e| format!("读取 TLS 私钥失败 ({}): {}", config.key_path, e))?;
+
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS 配置失败: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("TLS 证书与私钥不匹配: {}", e))?;
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// 运行单个监听器，直到 `shutdown` 收到排空时长 (`Some`) 或发送端被丢弃
+pub async fn serve(listener: Listener, mut shutdown: watch::Receiver<Option<Duration>>) {
+    use hyper::server::conn::http1;
+    use hyper_util::rt::TokioIo;
+    use tower::Service;
+
+    let Listener {
+        name,
+        bound,
+        app,
+        tls,
+    } = listener;
+
+    // 连接任务集合与优雅关闭信号，用于停止时排空在途请求
+    let mut connections = tokio::task::JoinSet::new();
+    let (drain_tx, drain_rx) = watch::channel(false);
+
+    let drain_timeout = loop {
+        tokio::select! {
+            res = bound.accept() => {
+                match res {
+                    Ok((io, peer_addr)) => {
+                        // 注入客户端地址，供按 IP 限流等中间件使用
+                        let router = app.clone();
+                        let service = hyper::service::service_fn(
+                            move |mut req: hyper::Request<hyper::body::Incoming>| {
+                                req.extensions_mut()
+                                    .insert(axum::extract::ConnectInfo(peer_addr));
+                                router.clone().call(req)
+                            },
+                        );
+                        let tls = tls.clone();
+                        let mut drain_rx = drain_rx.clone();
+
+                        connections.spawn(async move {
+                            let io: Box<dyn ConnIo> = match tls {
+                                Some(acceptor) => {
+                                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(io)).await {
+                                        Ok(Ok(stream)) => Box::new(stream),
+                                        Ok(Err(e)) => {
+                                            debug!("TLS 握手失败 ({}): {}", peer_addr, e);
+                                            return;
+                                        }
+                                        Err(_) => {
+                                            debug!("TLS 握手超时 ({})", peer_addr);
+                                            return;
+                                        }
+                                    }
+                                }
+                                None => io,
+                            };
+
+                            let conn = http1::Builder::new()
+                                .serve_connection(TokioIo::new(io), service)
+                                .with_upgrades(); // 支持 WebSocket (如果以后需要)
+                            tokio::pin!(conn);
+
+                            tokio::select! {
+                                res = conn.as_mut() => {
+                                    if let Err(err) = res {
+                                        debug!("连接处理结束或出错: {:?}", err);
+                                    }
+                                    return;
+                                }
+                                _ = drain_rx.changed() => {
+                                    // 处理完当前请求后关闭 keep-alive 连接
+                                    conn.as_mut().graceful_shutdown();
+                                }
+                            }
+                            if let Err(err) = conn.await {
+                                debug!("连接处理结束或出错: {:?}", err);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        error!("接收连接失败 ({}): {:?}", name, e);
+                    }
+                }
+            }
+            // 回收已结束的连接任务
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            res = shutdown.changed() => {
+                tracing::info!("反代服务器停止监听 {}", name);
+                break if res.is_ok() {
+                    shutdown.borrow().unwrap_or(Duration::ZERO)
+                } else {
+                    Duration::ZERO
+                };
+            }
+        }
+    };
+
+    drop(bound);
+    let _ = drain_tx.send(true);
+
+    if drain_timeout.is_zero() || connections.is_empty() {
+        // 不等待排空: 在途连接继续在后台完成
+        connections.detach_all();
+        return;
+    }
+
+    tracing::info!(
+        "等待 {} 上的 {} 个在途连接完成 (最长 {} 秒)",
+        name,
+        connections.len(),
+        drain_timeout.as_secs()
+    );
+    let drained = tokio::time::timeout(drain_timeout, async {
+        while connections.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!("排空超时，强制关闭 {} 上剩余 {} 个连接", name, connections.len());
+        connections.abort_all();
+    } else {
+        tracing::info!("{} 上的在途连接已全部完成", name);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[tokio::test]
+    async fn port_is_shared_only_while_both_listeners_allow_it() {
+        // 启动时的主监听器为独占绑定，同一用户的其他进程也无法绑定该端口
+        let first = BoundListener::bind_tcp("127.0.0.1:0").await.unwrap();
+        let BoundListener::Tcp(listener) = &first else {
+            panic!("expected a TCP listener");
+        };
+        let port = listener.local_addr().unwrap().port();
+        let other = format!("0.0.0.0:{}", port);
+        assert!(BoundListener::bind_tcp_shared(&other).await.is_err());
+
+        // 热切换: 旧监听器临时开启 SO_REUSEPORT 后，新地址可以在旧监听器关闭前绑定
+        let old_share = first.port_share().unwrap();
+        old_share.set(true).unwrap();
+        let second = BoundListener::bind_tcp_shared(&other).await.unwrap();
+        let new_share = second.port_share().unwrap();
+        drop(first);
+        drop(old_share);
+        new_share.set(false).unwrap();
+
+        // 切换完成后恢复独占: 同端口启动的其他实例 (独占绑定) 仍然绑定失败
+        assert!(BoundListener::bind_tcp(&format!("127.0.0.1:{}", port)).await.is_err());
+        assert!(BoundListener::bind_tcp(&other).await.is_err());
+        drop(second);
+    }
+
+    #[tokio::test]
+    async fn unix_socket_permissions_and_cleanup() {
+        let dir = std::env::temp_dir().join(format!("ag-uds-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("proxy.sock");
+        let path_str = path.to_str().unwrap();
+
+        let bound = BoundListener::bind_unix(path_str, None).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+
+        // 正在使用中的套接字不能被覆盖
+        assert!(BoundListener::bind_unix(path_str, None).is_err());
+
+        drop(bound);
+        assert!(!path.exists());
+
+        let bound = BoundListener::bind_unix(path_str, Some("660")).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o660);
+        drop(bound);
+
+        assert!(BoundListener::bind_unix(path_str, Some("999")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,338 @@
+// 请求准入控制: 并发已满时按优先级排队，队列满或等待超时时卸载低优先级请求
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::proxy::config::{LoadSheddingConfig, RequestPriority};
+
+const PRIORITIES: [RequestPriority; 3] = [
+    RequestPriority::Low,
+    RequestPriority::Normal,
+    RequestPriority::High,
+];
+
+fn slot(priority: RequestPriority) -> usize {
+    match priority {
+        RequestPriority::Low => 0,
+        RequestPriority::Normal => 1,
+        RequestPriority::High => 2,
+    }
+}
+
+/// 请求被卸载，携带建议的重试等待时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shed {
+    pub retry_after: Duration,
+}
+
+struct Waiter {
+    id: u64,
+    tx: oneshot::Sender<bool>,
+}
+
+#[derive(Default)]
+struct Inner {
+    in_flight: usize,
+    next_id: u64,
+    /// 按优先级分队列 (下标见 `slot`)
+    queues: [VecDeque<Waiter>; 3],
+}
+
+impl Inner {
+    fn queued(&self) -> usize {
+        self.queues.iter().map(|q| q.len()).sum()
+    }
+}
+
+pub struct AdmissionController {
+    config: std::sync::RwLock<LoadSheddingConfig>,
+    inner: Mutex<Inner>,
+    /// 正在处理的请求数 (未启用卸载时同样统计)
+    active: AtomicUsize,
+}
+
+/// 执行许可，释放时把名额交给队列中优先级最高的请求
+pub struct AdmissionPermit {
+    controller: Option<Arc<AdmissionController>>,
+    owner: Arc<AdmissionController>,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.owner.active.fetch_sub(1, Ordering::Relaxed);
+        if let Some(controller) = self.controller.take() {
+            controller.release();
+        }
+    }
+}
+
+impl AdmissionController {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config),
+            inner: Mutex::new(Inner::default()),
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn configure(&self, config: &LoadSheddingConfig) {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        if *current != *config {
+            *current = config.clone();
+            tracing::info!(
+                "过载卸载配置已更新: enabled={}, max_concurrent={}, max_queue={}",
+                config.enabled,
+                config.max_concurrent,
+                config.max_queue
+            );
+        }
+    }
+
+    pub fn config(&self) -> LoadSheddingConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 解析请求优先级: 请求头 > 按 Key 配置 > 默认值
+    ///
+    /// 按 Key 配置的优先级是上限，请求头只能调低，避免批处理 Key 自称 `high` 抢占交互式请求
+    pub fn resolve_priority(&self, header: Option<&str>, api_key: Option<&str>) -> RequestPriority {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+        let requested = header.and_then(parse_priority);
+        match api_key.and_then(|k| config.key_priorities.get(k).copied()) {
+            Some(ceiling) => requested.map_or(ceiling, |p| p.min(ceiling)),
+            None => requested.unwrap_or(config.default_priority),
+        }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).in_flight
+    }
+
+    /// 排队等待名额的请求数
+    pub fn queued(&self) -> usize {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).queued()
+    }
+
+    /// 正在处理的请求数 (含流式响应)，不受是否启用卸载影响
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    fn permit(self: &Arc<Self>, holds_slot: bool) -> AdmissionPermit {
+        self.active.fetch_add(1, Ordering::Relaxed);
+        AdmissionPermit {
+            controller: holds_slot.then(|| self.clone()),
+            owner: self.clone(),
+        }
+    }
+
+    /// 申请执行许可；过载时返回 `Shed`
+    pub async fn acquire(
+        self: &Arc<Self>,
+        priority: RequestPriority,
+    ) -> Result<AdmissionPermit, Shed> {
+        let config = self.config();
+        if !config.enabled {
+            return Ok(self.permit(false));
+        }
+        let shed = Shed {
+            retry_after: Duration::from_secs(config.retry_after.max(1)),
+        };
+
+        let (id, rx) = {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            if inner.in_flight < config.max_concurrent.max(1) && inner.queued() == 0 {
+                inner.in_flight += 1;
+                return Ok(self.permit(true));
+            }
+
+            if inner.queued() >= config.max_queue {
+                // 队列已满: 挤掉队列中优先级更低的最新请求，否则卸载自己
+                let victim = PRIORITIES
+                    .iter()
+                    .take_while(|p| **p < priority)
+                    .find_map(|p| inner.queues[slot(*p)].pop_back());
+                match victim {
+                    Some(waiter) => {
+                        let _ = waiter.tx.send(false);
+                    }
+                    None => return Err(shed),
+                }
+            }
+
+            let (tx, rx) = oneshot::channel();
+            inner.next_id += 1;
+            let id = inner.next_id;
+            inner.queues[slot(priority)].push_back(Waiter { id, tx });
+            (id, rx)
+        };
+
+        let mut rx = rx;
+        let admitted = match tokio::time::timeout(Duration::from_secs(config.queue_timeout), &mut rx).await {
+            Ok(result) => result.unwrap_or(false),
+            Err(_) => {
+                // 超时: 从队列中移除；若已不在队列中，说明超时瞬间已被放行或挤出
+                let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+                let queue = &mut inner.queues[slot(priority)];
+                if let Some(pos) = queue.iter().position(|w| w.id == id) {
+                    queue.remove(pos);
+                    false
+                } else {
+                    drop(inner);
+                    rx.try_recv().unwrap_or(false)
+                }
+            }
+        };
+
+        if admitted {
+            Ok(self.permit(true))
+        } else {
+            Err(shed)
+        }
+    }
+
+    /// 释放名额: 优先交给高优先级等待者，没有等待者时减少在途数
+    fn release(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        for priority in PRIORITIES.iter().rev() {
+            while let Some(waiter) = inner.queues[slot(*priority)].pop_front() {
+                if waiter.tx.send(true).is_ok() {
+                    return;
+                }
+            }
+        }
+        inner.in_flight = inner.in_flight.saturating_sub(1);
+    }
+}
+
+pub fn parse_priority(value: &str) -> Option<RequestPriority> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "high" | "interactive" => Some(RequestPriority::High),
+        "normal" | "default" => Some(RequestPriority::Normal),
+        "low" | "batch" | "background" => Some(RequestPriority::Low),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller(max_concurrent: usize, max_queue: usize) -> Arc<AdmissionController> {
+        Arc::new(AdmissionController::new(LoadSheddingConfig {
+            enabled: true,
+            max_concurrent,
+            max_queue,
+            queue_timeout: 5,
+            ..LoadSheddingConfig::default()
+        }))
+    }
+
+    #[tokio::test]
+    async fn disabled_controller_never_sheds() {
+        let controller = Arc::new(AdmissionController::new(LoadSheddingConfig::default()));
+        let _a = controller.acquire(RequestPriority::Low).await.unwrap();
+        let b = controller.acquire(RequestPriority::Low).await.unwrap();
+        assert_eq!(controller.in_flight(), 0);
+        assert_eq!(controller.active(), 2);
+        drop(b);
+        assert_eq!(controller.active(), 1);
+    }
+
+    #[tokio::test]
+    async fn high_priority_preempts_queued_low() {
+        let controller = controller(1, 4);
+        let running = controller.acquire(RequestPriority::Normal).await.unwrap();
+
+        let low = tokio::spawn({
+            let c = controller.clone();
+            async move { c.acquire(RequestPriority::Low).await.map(|_| "low") }
+        });
+        tokio::task::yield_now().await;
+        let high = tokio::spawn({
+            let c = controller.clone();
+            async move { c.acquire(RequestPriority::High).await.map(|p| (p, "high")) }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        drop(running);
+        let (permit, name) = high.await.unwrap().unwrap();
+        assert_eq!(name, "high");
+        assert!(!low.is_finished());
+
+        drop(permit);
+        assert_eq!(low.await.unwrap().unwrap(), "low");
+        assert_eq!(controller.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn full_queue_sheds_lowest_priority() {
+        let controller = controller(1, 1);
+        let running = controller.acquire(RequestPriority::Normal).await.unwrap();
+
+        let low = tokio::spawn({
+            let c = controller.clone();
+            async move { c.acquire(RequestPriority::Low).await.is_ok() }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // 同优先级无法挤占，直接卸载
+        assert!(controller.acquire(RequestPriority::Low).await.is_err());
+
+        // 更高优先级挤掉排队中的低优先级请求
+        let normal = tokio::spawn({
+            let c = controller.clone();
+            async move { c.acquire(RequestPriority::Normal).await.is_ok() }
+        });
+        assert!(!low.await.unwrap());
+        drop(running);
+        assert!(normal.await.unwrap());
+    }
+
+    #[test]
+    fn priority_resolution_order() {
+        let mut config = LoadSheddingConfig::default();
+        config
+            .key_priorities
+            .insert("sk-batch".to_string(), RequestPriority::Low);
+        let controller = AdmissionController::new(config);
+
+        assert_eq!(controller.resolve_priority(None, None), RequestPriority::Normal);
+        assert_eq!(controller.resolve_priority(None, Some("sk-batch")), RequestPriority::Low);
+        assert_eq!(
+            controller.resolve_priority(Some("interactive"), Some("sk-other")),
+            RequestPriority::High
+        );
+        assert_eq!(controller.resolve_priority(Some("bogus"), None), RequestPriority::Normal);
+    }
+
+    #[test]
+    fn key_priority_caps_header() {
+        let mut config = LoadSheddingConfig::default();
+        config
+            .key_priorities
+            .insert("sk-batch".to_string(), RequestPriority::Low);
+        config
+            .key_priorities
+            .insert("sk-app".to_string(), RequestPriority::Normal);
+        let controller = AdmissionController::new(config);
+
+        assert_eq!(
+            controller.resolve_priority(Some("high"), Some("sk-batch")),
+            RequestPriority::Low
+        );
+        assert_eq!(
+            controller.resolve_priority(Some("high"), Some("sk-app")),
+            RequestPriority::Normal
+        );
+        assert_eq!(
+            controller.resolve_priority(Some("low"), Some("sk-app")),
+            RequestPriority::Low
+        );
+    }
+}
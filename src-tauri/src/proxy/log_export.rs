@@ -0,0 +1,625 @@
+// 请求日志批量导出 (`--logs-export`): JSON Lines 或 Parquet，供 DuckDB / pandas 等分析工具直接读取
+//
+// 日志从 SQLite 中一次查询逐行读出，边读边写，不在内存中保留完整结果集。
+// Parquet 为最小实现: 每个行组每列一个 PLAIN 编码、不压缩的数据页，统计信息与字典编码均省略。
+use std::io::Write;
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 单个行组最多缓存的行数
+const ROW_GROUP_ROWS: usize = 50_000;
+/// 单个行组最多缓存的数据量 (含请求/响应体时行组会很大)
+const ROW_GROUP_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "parquet" => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+}
+
+/// 按格式逐条写出日志
+pub enum LogWriter<W: Write> {
+    Jsonl(W),
+    Parquet(ParquetWriter<W>),
+}
+
+impl<W: Write> LogWriter<W> {
+    pub fn new(format: ExportFormat, out: W) -> Result<Self, String> {
+        Ok(match format {
+            ExportFormat::Jsonl => Self::Jsonl(out),
+            ExportFormat::Parquet => Self::Parquet(ParquetWriter::new(out)?),
+        })
+    }
+
+    pub fn write(&mut self, log: &ProxyRequestLog) -> Result<(), String> {
+        match self {
+            Self::Jsonl(out) => {
+                serde_json::to_writer(&mut *out, log).map_err(|e| format!("写入日志失败: {}", e))?;
+                out.write_all(b"\n").map_err(|e| format!("写入日志失败: {}", e))
+            }
+            Self::Parquet(writer) => writer.write(log),
+        }
+    }
+
+    /// 写出剩余数据 (Parquet 写入文件尾)，返回底层输出
+    pub fn finish(self) -> Result<W, String> {
+        let mut out = match self {
+            Self::Jsonl(out) => out,
+            Self::Parquet(writer) => writer.finish()?,
+        };
+        out.flush().map_err(|e| format!("写入日志失败: {}", e))?;
+        Ok(out)
+    }
+}
+
+// ===== Parquet =====
+
+const MAGIC: &[u8] = b"PAR1";
+
+// parquet.thrift 中的枚举值
+const TYPE_INT32: i32 = 1;
+const TYPE_INT64: i32 = 2;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const REPETITION_REQUIRED: i32 = 0;
+const REPETITION_OPTIONAL: i32 = 1;
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_TIMESTAMP_MILLIS: i32 = 9;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_DATA: i32 = 0;
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Utf8,
+    Int32,
+    Int64,
+    TimestampMillis,
+}
+
+impl Kind {
+    fn physical(self) -> i32 {
+        match self {
+            Kind::Utf8 => TYPE_BYTE_ARRAY,
+            Kind::Int32 => TYPE_INT32,
+            Kind::Int64 | Kind::TimestampMillis => TYPE_INT64,
+        }
+    }
+
+    fn converted(self) -> Option<i32> {
+        match self {
+            Kind::Utf8 => Some(CONVERTED_UTF8),
+            Kind::TimestampMillis => Some(CONVERTED_TIMESTAMP_MILLIS),
+            Kind::Int32 | Kind::Int64 => None,
+        }
+    }
+}
+
+enum Value<'a> {
+    Str(Option<&'a str>),
+    I32(Option<i32>),
+    I64(Option<i64>),
+}
+
+struct ColumnDef {
+    name: &'static str,
+    kind: Kind,
+    required: bool,
+    get: fn(&ProxyRequestLog) -> Value<'_>,
+}
+
+/// 导出的列，与 `ProxyRequestLog` 字段一一对应
+const COLUMNS: &[ColumnDef] = &[
+    ColumnDef { name: "id", kind: Kind::Utf8, required: true, get: |l| Value::Str(Some(&l.id)) },
+    ColumnDef { name: "timestamp", kind: Kind::TimestampMillis, required: true, get: |l| Value::I64(Some(l.timestamp)) },
+    ColumnDef { name: "method", kind: Kind::Utf8, required: true, get: |l| Value::Str(Some(&l.method)) },
+    ColumnDef { name: "url", kind: Kind::Utf8, required: true, get: |l| Value::Str(Some(&l.url)) },
+    ColumnDef { name: "status", kind: Kind::Int32, required: true, get: |l| Value::I32(Some(l.status as i32)) },
+    ColumnDef { name: "duration", kind: Kind::Int64, required: true, get: |l| Value::I64(Some(l.duration as i64)) },
+    ColumnDef { name: "model", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.model.as_deref()) },
+    ColumnDef { name: "error", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.error.as_deref()) },
+    ColumnDef { name: "request_body", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.request_body.as_deref()) },
+    ColumnDef { name: "response_body", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.response_body.as_deref()) },
+    ColumnDef { name: "input_tokens", kind: Kind::Int64, required: false, get: |l| Value::I64(l.input_tokens.map(i64::from)) },
+    ColumnDef { name: "output_tokens", kind: Kind::Int64, required: false, get: |l| Value::I64(l.output_tokens.map(i64::from)) },
+    ColumnDef { name: "session_id", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.session_id.as_deref()) },
+    ColumnDef { name: "account", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.account.as_deref()) },
+    ColumnDef { name: "key_id", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.key_id.as_deref()) },
+    ColumnDef { name: "client_ip", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.client_ip.as_deref()) },
+    ColumnDef { name: "canary_target", kind: Kind::Utf8, required: false, get: |l| Value::Str(l.canary_target.as_deref()) },
+];
+
+/// 单列在当前行组中缓存的数据
+#[derive(Default)]
+struct ColumnBuffer {
+    /// 定义级别 (仅可空列): true 表示有值
+    defined: Vec<bool>,
+    /// PLAIN 编码的非空值
+    values: Vec<u8>,
+}
+
+impl ColumnBuffer {
+    fn push(&mut self, required: bool, value: Value<'_>) {
+        let present = match value {
+            Value::Str(Some(s)) => {
+                self.values.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                self.values.extend_from_slice(s.as_bytes());
+                true
+            }
+            Value::I32(Some(v)) => {
+                self.values.extend_from_slice(&v.to_le_bytes());
+                true
+            }
+            Value::I64(Some(v)) => {
+                self.values.extend_from_slice(&v.to_le_bytes());
+                true
+            }
+            Value::Str(None) | Value::I32(None) | Value::I64(None) => false,
+        };
+        if !required {
+            self.defined.push(present);
+        }
+    }
+
+    /// 数据页内容: [定义级别 (4 字节长度 + RLE)] + 值
+    fn page(&self, required: bool) -> Vec<u8> {
+        let mut page = Vec::with_capacity(self.values.len() + 16);
+        if !required {
+            let levels = rle_levels(&self.defined);
+            page.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+            page.extend_from_slice(&levels);
+        }
+        page.extend_from_slice(&self.values);
+        page
+    }
+}
+
+/// 位宽为 1 的定义级别按 RLE 游程编码 (RLE / bit-packing 混合编码中只使用 RLE 游程)
+fn rle_levels(defined: &[bool]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < defined.len() {
+        let value = defined[i];
+        let run = defined[i..].iter().take_while(|d| **d == value).count();
+        write_varint(&mut out, (run as u64) << 1);
+        out.push(value as u8);
+        i += run;
+    }
+    out
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// Thrift compact protocol 类型标记
+const CT_I32: u8 = 5;
+const CT_I64: u8 = 6;
+const CT_BINARY: u8 = 8;
+const CT_LIST: u8 = 9;
+const CT_STRUCT: u8 = 12;
+
+/// Parquet 元数据所用的 Thrift compact protocol 编码 (仅实现用到的类型)
+struct Thrift {
+    buf: Vec<u8>,
+    /// 各层结构体中上一个字段的 ID (字段头按差值编码)
+    last: Vec<i16>,
+}
+
+impl Thrift {
+    fn new() -> Self {
+        Self { buf: Vec::new(), last: vec![0] }
+    }
+
+    fn zigzag(&mut self, value: i64) {
+        write_varint(&mut self.buf, ((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last.last_mut().expect("thrift struct stack");
+        let delta = id - std::mem::replace(last, id);
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            self.zigzag(id as i64);
+        }
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, CT_I32);
+        self.zigzag(value as i64);
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, CT_I64);
+        self.zigzag(value);
+    }
+
+    fn string(&mut self, id: i16, value: &str) {
+        self.field(id, CT_BINARY);
+        write_varint(&mut self.buf, value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn list(&mut self, id: i16, element: u8, len: usize) {
+        self.field(id, CT_LIST);
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | element);
+        } else {
+            self.buf.push(0xf0 | element);
+            write_varint(&mut self.buf, len as u64);
+        }
+    }
+
+    fn list_i32(&mut self, value: i32) {
+        self.zigzag(value as i64);
+    }
+
+    fn list_string(&mut self, value: &str) {
+        write_varint(&mut self.buf, value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    /// 结构体字段；列表中的结构体元素用 `begin_element`
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, CT_STRUCT);
+        self.last.push(0);
+    }
+
+    fn begin_element(&mut self) {
+        self.last.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.buf.push(0);
+        self.last.pop();
+    }
+
+    /// 结束最外层结构体
+    fn finish(mut self) -> Vec<u8> {
+        self.buf.push(0);
+        self.buf
+    }
+}
+
+/// 已写出的列块位置
+struct ChunkMeta {
+    offset: u64,
+    size: u64,
+    num_values: usize,
+}
+
+struct RowGroupMeta {
+    rows: usize,
+    chunks: Vec<ChunkMeta>,
+}
+
+pub struct ParquetWriter<W: Write> {
+    out: W,
+    offset: u64,
+    columns: Vec<ColumnBuffer>,
+    rows: usize,
+    buffered_bytes: usize,
+    row_groups: Vec<RowGroupMeta>,
+}
+
+impl<W: Write> ParquetWriter<W> {
+    pub fn new(mut out: W) -> Result<Self, String> {
+        out.write_all(MAGIC).map_err(|e| format!("写入 Parquet 失败: {}", e))?;
+        Ok(Self {
+            out,
+            offset: MAGIC.len() as u64,
+            columns: COLUMNS.iter().map(|_| ColumnBuffer::default()).collect(),
+            rows: 0,
+            buffered_bytes: 0,
+            row_groups: Vec::new(),
+        })
+    }
+
+    pub fn write(&mut self, log: &ProxyRequestLog) -> Result<(), String> {
+        for (def, column) in COLUMNS.iter().zip(self.columns.iter_mut()) {
+            let before = column.values.len();
+            column.push(def.required, (def.get)(log));
+            self.buffered_bytes += column.values.len() - before;
+        }
+        self.rows += 1;
+        if self.rows >= ROW_GROUP_ROWS || self.buffered_bytes >= ROW_GROUP_BYTES {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.out.write_all(bytes).map_err(|e| format!("写入 Parquet 失败: {}", e))?;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn flush_row_group(&mut self) -> Result<(), String> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let rows = self.rows;
+        let columns = std::mem::take(&mut self.columns);
+        let mut chunks = Vec::with_capacity(columns.len());
+        for (def, column) in COLUMNS.iter().zip(&columns) {
+            let page = column.page(def.required);
+            let header = page_header(rows, page.len());
+            let offset = self.offset;
+            self.emit(&header)?;
+            self.emit(&page)?;
+            chunks.push(ChunkMeta {
+                offset,
+                size: (header.len() + page.len()) as u64,
+                num_values: rows,
+            });
+        }
+        self.row_groups.push(RowGroupMeta { rows, chunks });
+        self.columns = COLUMNS.iter().map(|_| ColumnBuffer::default()).collect();
+        self.rows = 0;
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// 写出最后一个行组与文件尾，返回底层输出
+    pub fn finish(mut self) -> Result<W, String> {
+        self.flush_row_group()?;
+        let footer = file_metadata(&self.row_groups);
+        self.emit(&footer)?;
+        self.emit(&(footer.len() as u32).to_le_bytes())?;
+        self.emit(MAGIC)?;
+        Ok(self.out)
+    }
+}
+
+/// PageHeader (DATA_PAGE, 不压缩)
+fn page_header(num_values: usize, page_size: usize) -> Vec<u8> {
+    let mut t = Thrift::new();
+    t.i32(1, PAGE_DATA);
+    t.i32(2, page_size as i32);
+    t.i32(3, page_size as i32);
+    t.begin_struct(5);
+    t.i32(1, num_values as i32);
+    t.i32(2, ENCODING_PLAIN);
+    t.i32(3, ENCODING_RLE);
+    t.i32(4, ENCODING_RLE);
+    t.end_struct();
+    t.finish()
+}
+
+/// FileMetaData
+fn file_metadata(row_groups: &[RowGroupMeta]) -> Vec<u8> {
+    let mut t = Thrift::new();
+    t.i32(1, 1);
+
+    t.list(2, CT_STRUCT, COLUMNS.len() + 1);
+    t.begin_element();
+    t.string(4, "schema");
+    t.i32(5, COLUMNS.len() as i32);
+    t.end_struct();
+    for def in COLUMNS {
+        t.begin_element();
+        t.i32(1, def.kind.physical());
+        t.i32(3, if def.required { REPETITION_REQUIRED } else { REPETITION_OPTIONAL });
+        t.string(4, def.name);
+        if let Some(converted) = def.kind.converted() {
+            t.i32(6, converted);
+        }
+        t.end_struct();
+    }
+
+    let total_rows: usize = row_groups.iter().map(|g| g.rows).sum();
+    t.i64(3, total_rows as i64);
+
+    t.list(4, CT_STRUCT, row_groups.len());
+    for group in row_groups {
+        t.begin_element();
+        t.list(1, CT_STRUCT, group.chunks.len());
+        for (def, chunk) in COLUMNS.iter().zip(&group.chunks) {
+            t.begin_element();
+            t.i64(2, chunk.offset as i64);
+            t.begin_struct(3);
+            t.i32(1, def.kind.physical());
+            t.list(2, CT_I32, 2);
+            t.list_i32(ENCODING_PLAIN);
+            t.list_i32(ENCODING_RLE);
+            t.list(3, CT_BINARY, 1);
+            t.list_string(def.name);
+            t.i32(4, CODEC_UNCOMPRESSED);
+            t.i64(5, chunk.num_values as i64);
+            t.i64(6, chunk.size as i64);
+            t.i64(7, chunk.size as i64);
+            t.i64(9, chunk.offset as i64);
+            t.end_struct();
+            t.end_struct();
+        }
+        let group_bytes: u64 = group.chunks.iter().map(|c| c.size).sum();
+        t.i64(2, group_bytes as i64);
+        t.i64(3, group.rows as i64);
+        t.end_struct();
+    }
+
+    t.string(6, concat!("antigravity_tools version ", env!("CARGO_PKG_VERSION")));
+    t.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::{Field, Row};
+
+    fn log(id: &str, model: Option<&str>) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: id.to_string(),
+            timestamp: 1_700_000_000_000,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status: 200,
+            duration: 1234,
+            model: model.map(str::to_string),
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: Some(10),
+            output_tokens: None,
+            session_id: None,
+            account: Some("a@example.com".to_string()),
+            key_id: None,
+            client_ip: None,
+            canary_target: None,
+        }
+    }
+
+    #[test]
+    fn parses_formats() {
+        assert_eq!(ExportFormat::parse("jsonl"), Some(ExportFormat::Jsonl));
+        assert_eq!(ExportFormat::parse("Parquet"), Some(ExportFormat::Parquet));
+        assert_eq!(ExportFormat::parse("csv"), None);
+    }
+
+    #[test]
+    fn jsonl_writes_one_object_per_line() {
+        let mut writer = LogWriter::new(ExportFormat::Jsonl, Vec::new()).unwrap();
+        writer.write(&log("a", Some("gemini-2.5-pro"))).unwrap();
+        writer.write(&log("b", None)).unwrap();
+        let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: ProxyRequestLog = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first.id, "a");
+        assert_eq!(first.model.as_deref(), Some("gemini-2.5-pro"));
+    }
+
+    #[test]
+    fn thrift_compact_encoding() {
+        let mut t = Thrift::new();
+        t.i32(1, -1);
+        t.i64(20, 300);
+        t.begin_struct(21);
+        t.string(1, "ab");
+        t.end_struct();
+        // 字段 1 差值编码; 字段 20 差值 19 超过 15，单独写 zigzag 字段 ID; 嵌套结构体字段 ID 重新计数
+        assert_eq!(
+            t.finish(),
+            vec![0x15, 0x01, 0x06, 0x28, 0xd8, 0x04, 0x1c, 0x18, 0x02, b'a', b'b', 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn definition_levels_use_rle_runs() {
+        assert_eq!(rle_levels(&[true, true, true, false, true]), vec![0x06, 1, 0x02, 0, 0x02, 1]);
+        assert!(rle_levels(&[]).is_empty());
+    }
+
+    #[test]
+    fn parquet_file_layout() {
+        let mut writer = LogWriter::new(ExportFormat::Parquet, Vec::new()).unwrap();
+        writer.write(&log("a", Some("m"))).unwrap();
+        writer.write(&log("b", None)).unwrap();
+        let out = writer.finish().unwrap();
+
+        assert_eq!(&out[..4], MAGIC);
+        assert_eq!(&out[out.len() - 4..], MAGIC);
+        let footer_len = u32::from_le_bytes(out[out.len() - 8..out.len() - 4].try_into().unwrap()) as usize;
+        let footer_start = out.len() - 8 - footer_len;
+        let footer = &out[footer_start..out.len() - 8];
+        assert_eq!(footer.last(), Some(&0));
+        assert!(footer.windows(b"canary_target".len()).any(|w| w == b"canary_target"));
+
+        // 第一个列块 (id) 紧随文件头，页内容为两个 PLAIN 编码的字符串
+        let page = [1u32.to_le_bytes().as_slice(), b"a", 1u32.to_le_bytes().as_slice(), b"b"].concat();
+        let header = page_header(2, page.len());
+        assert_eq!(&out[4..4 + header.len()], header.as_slice());
+        assert_eq!(&out[4 + header.len()..4 + header.len() + page.len()], page.as_slice());
+        assert!(footer_start > 4 + header.len() + page.len());
+    }
+
+    /// 使用 parquet crate (Arrow 官方 Rust 实现) 读取导出文件，验证其他分析工具同样可以打开
+    fn read_rows(out: Vec<u8>) -> (SerializedFileReader<bytes::Bytes>, Vec<Row>) {
+        let reader = SerializedFileReader::new(bytes::Bytes::from(out)).unwrap();
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        (reader, rows)
+    }
+
+    fn field(row: &Row, name: &str) -> Field {
+        row.get_column_iter()
+            .find(|(column, _)| column.as_str() == name)
+            .map(|(_, value)| value.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn parquet_round_trips_through_independent_reader() {
+        let mut writer = LogWriter::new(ExportFormat::Parquet, Vec::new()).unwrap();
+        writer.write(&log("a", Some("gemini-2.5-pro"))).unwrap();
+        writer.write(&log("b", None)).unwrap();
+        let (reader, rows) = read_rows(writer.finish().unwrap());
+
+        let schema = reader.metadata().file_metadata().schema_descr_ptr();
+        let names: Vec<&str> = schema.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(names, COLUMNS.iter().map(|c| c.name).collect::<Vec<_>>());
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(field(&rows[0], "id"), Field::Str("a".to_string()));
+        assert_eq!(field(&rows[0], "timestamp"), Field::TimestampMillis(1_700_000_000_000));
+        assert_eq!(field(&rows[0], "status"), Field::Int(200));
+        assert_eq!(field(&rows[0], "duration"), Field::Long(1234));
+        assert_eq!(field(&rows[0], "model"), Field::Str("gemini-2.5-pro".to_string()));
+        assert_eq!(field(&rows[0], "input_tokens"), Field::Long(10));
+        assert_eq!(field(&rows[0], "output_tokens"), Field::Null);
+        assert_eq!(field(&rows[1], "id"), Field::Str("b".to_string()));
+        assert_eq!(field(&rows[1], "model"), Field::Null);
+        assert_eq!(field(&rows[1], "account"), Field::Str("a@example.com".to_string()));
+    }
+
+    #[test]
+    fn optional_column_page_has_levels_then_values() {
+        let mut column = ColumnBuffer::default();
+        column.push(false, Value::Str(Some("m")));
+        column.push(false, Value::Str(None));
+        let page = column.page(false);
+        let levels = rle_levels(&[true, false]);
+        assert_eq!(&page[..4], (levels.len() as u32).to_le_bytes().as_slice());
+        assert_eq!(&page[4..4 + levels.len()], levels.as_slice());
+        assert_eq!(&page[4 + levels.len()..], [1u32.to_le_bytes().as_slice(), b"m"].concat().as_slice());
+    }
+
+    #[test]
+    fn flushes_row_groups_by_row_count() {
+        let mut writer = ParquetWriter::new(Vec::new()).unwrap();
+        for i in 0..ROW_GROUP_ROWS + 1 {
+            writer.write(&log(&i.to_string(), None)).unwrap();
+        }
+        assert_eq!(writer.row_groups.len(), 1);
+        assert_eq!(writer.rows, 1);
+        let (reader, rows) = read_rows(writer.finish().unwrap());
+        assert_eq!(reader.num_row_groups(), 2);
+        assert_eq!(rows.len(), ROW_GROUP_ROWS + 1);
+        assert_eq!(field(&rows[ROW_GROUP_ROWS], "id"), Field::Str(ROW_GROUP_ROWS.to_string()));
+    }
+}
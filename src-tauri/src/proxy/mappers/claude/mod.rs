@@ -23,21 +23,22 @@ pub fn create_claude_sse_stream(
     email: String,
 ) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
     use async_stream::stream;
-    use bytes::BytesMut;
     use futures::StreamExt;
 
     Box::pin(stream! {
         let mut state = StreamingState::new();
-        let mut buffer = BytesMut::new();
+        let mut buffer = crate::proxy::streaming::SseLineBuffer::new();
 
         while let Some(chunk_result) = gemini_stream.next().await {
             match chunk_result {
                 Ok(chunk) => {
-                    buffer.extend_from_slice(&chunk);
+                    if let Err(e) = buffer.push(&chunk) {
+                        yield Err(format!("Stream error: {}", e));
+                        break;
+                    }
 
                     // Process complete lines
-                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
-                        let line_raw = buffer.split_to(pos + 1);
+                    while let Some(line_raw) = buffer.next_line() {
                         if let Ok(line_str) = std::str::from_utf8(&line_raw) {
                             let line = line_str.trim();
                             if line.is_empty() { continue; }
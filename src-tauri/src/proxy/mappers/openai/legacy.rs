@@ -0,0 +1,177 @@
+// OpenAI 旧版 /v1/completions (text_completion) ↔ Chat 请求转换
+use serde_json::{json, Value};
+
+use super::models::{OpenAIContent, OpenAIResponse};
+
+/// Chat 请求无法直接表达、需要在响应阶段处理的旧版参数
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LegacyCompletionOptions {
+    /// `echo: true` 时需要在输出前回显的 prompt
+    pub echo: Option<String>,
+}
+
+/// 将旧版 completions 请求体原地转换为 Chat 请求体
+/// - `prompt` (字符串 / 字符串数组) 包装为单条 user 消息
+/// - `stop` 去除空字符串，统一为数组
+/// - `logprobs` / `best_of` / `suffix` 等上游不支持的参数被忽略，响应中 `logprobs` 固定为 null
+pub fn legacy_to_chat(body: &mut Value) -> Result<LegacyCompletionOptions, String> {
+    let Some(obj) = body.as_object_mut() else {
+        return Err("Request body must be a JSON object".to_string());
+    };
+
+    let prompt = match obj.remove("prompt") {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s,
+        Some(Value::Array(items)) => {
+            if items.iter().any(|v| !v.is_string()) {
+                return Err("Token-array prompts are not supported; send prompt as text".to_string());
+            }
+            if items.len() > 1 {
+                tracing::warn!(
+                    "[Legacy-Completions] {} prompts in one request, joining into a single prompt",
+                    items.len()
+                );
+            }
+            items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        Some(other) => other.to_string(),
+    };
+
+    match obj.get("stop").cloned() {
+        Some(Value::String(s)) if s.is_empty() => {
+            obj.remove("stop");
+        }
+        Some(Value::Array(items)) => {
+            let stops: Vec<Value> = items
+                .into_iter()
+                .filter(|v| v.as_str().is_some_and(|s| !s.is_empty()))
+                .collect();
+            if stops.is_empty() {
+                obj.remove("stop");
+            } else {
+                obj.insert("stop".to_string(), Value::Array(stops));
+            }
+        }
+        _ => {}
+    }
+
+    for ignored in ["logprobs", "best_of", "suffix", "logit_bias"] {
+        if let Some(value) = obj.remove(ignored) {
+            if !value.is_null() {
+                tracing::debug!("[Legacy-Completions] Ignoring unsupported parameter {}", ignored);
+            }
+        }
+    }
+
+    let echo = obj
+        .remove("echo")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+        .then(|| prompt.clone());
+
+    obj.insert(
+        "messages".to_string(),
+        json!([{ "role": "user", "content": prompt }]),
+    );
+
+    Ok(LegacyCompletionOptions { echo })
+}
+
+/// 将 Chat 响应转换为旧版 text_completion 响应
+pub fn chat_to_legacy_response(chat_resp: &OpenAIResponse, options: &LegacyCompletionOptions) -> Value {
+    let choices = chat_resp
+        .choices
+        .iter()
+        .map(|c| {
+            let text = match &c.message.content {
+                Some(OpenAIContent::String(s)) => s.clone(),
+                _ => String::new(),
+            };
+            json!({
+                "text": format!("{}{}", options.echo.as_deref().unwrap_or(""), text),
+                "index": c.index,
+                "logprobs": null,
+                "finish_reason": c.finish_reason
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "id": chat_resp.id,
+        "object": "text_completion",
+        "created": chat_resp.created,
+        "model": chat_resp.model,
+        "choices": choices
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::mappers::openai::{Choice, OpenAIMessage};
+
+    #[test]
+    fn wraps_prompt_and_strips_unsupported_params() {
+        let mut body = json!({
+            "model": "gpt-3.5-turbo-instruct",
+            "prompt": ["Say", "hello"],
+            "logprobs": 5,
+            "suffix": "!",
+            "stop": ["", "\n\n"],
+        });
+        let options = legacy_to_chat(&mut body).unwrap();
+
+        assert_eq!(options, LegacyCompletionOptions::default());
+        assert_eq!(body["messages"][0]["content"], "Say\nhello");
+        assert_eq!(body["stop"], json!(["\n\n"]));
+        assert!(body.get("prompt").is_none());
+        assert!(body.get("logprobs").is_none());
+        assert!(body.get("suffix").is_none());
+    }
+
+    #[test]
+    fn empty_stop_is_removed() {
+        let mut body = json!({ "model": "m", "prompt": "hi", "stop": "" });
+        legacy_to_chat(&mut body).unwrap();
+        assert!(body.get("stop").is_none());
+    }
+
+    #[test]
+    fn token_prompts_are_rejected() {
+        let mut body = json!({ "model": "m", "prompt": [1, 2, 3] });
+        assert!(legacy_to_chat(&mut body).is_err());
+    }
+
+    #[test]
+    fn echo_prepends_prompt() {
+        let mut body = json!({ "model": "m", "prompt": "1, 2, ", "echo": true });
+        let options = legacy_to_chat(&mut body).unwrap();
+        assert_eq!(options.echo.as_deref(), Some("1, 2, "));
+
+        let chat = OpenAIResponse {
+            id: "chatcmpl-1".to_string(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "m".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: OpenAIMessage {
+                    role: "assistant".to_string(),
+                    content: Some(OpenAIContent::String("3".to_string())),
+                    tool_calls: None,
+                    tool_call_id: None,
+                    name: None,
+                },
+                finish_reason: Some("stop".to_string()),
+            }],
+        };
+        let legacy = chat_to_legacy_response(&chat, &options);
+        assert_eq!(legacy["object"], "text_completion");
+        assert_eq!(legacy["choices"][0]["text"], "1, 2, 3");
+        assert!(legacy["choices"][0]["logprobs"].is_null());
+    }
+}
@@ -1,5 +1,5 @@
 // OpenAI 流式转换
-use bytes::{Bytes, BytesMut};
+use bytes::Bytes;
 use futures::{Stream, StreamExt};
 use serde_json::{json, Value};
 use std::pin::Pin;
@@ -54,7 +54,7 @@ pub fn create_openai_sse_stream(
     mut gemini_stream: Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
     model: String,
 ) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
-    let mut buffer = BytesMut::new();
+    let mut buffer = crate::proxy::streaming::SseLineBuffer::new();
     
     let stream = async_stream::stream! {
         while let Some(item) = gemini_stream.next().await {
@@ -62,11 +62,13 @@ pub fn create_openai_sse_stream(
                 Ok(bytes) => {
                     // Verbose logging for debugging image fragmentation
                     debug!("[OpenAI-SSE] Received chunk: {} bytes", bytes.len());
-                    buffer.extend_from_slice(&bytes);
+                    if let Err(e) = buffer.push(&bytes) {
+                        yield Err(format!("Stream error: {}", e));
+                        break;
+                    }
                     
                     // Process complete lines from buffer
-                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
-                        let line_raw = buffer.split_to(pos + 1);
+                    while let Some(line_raw) = buffer.next_line() {
                         if let Ok(line_str) = std::str::from_utf8(&line_raw) {
                             let line = line_str.trim();
                             if line.is_empty() { continue; }
@@ -205,8 +207,9 @@ pub fn create_openai_sse_stream(
 pub fn create_legacy_sse_stream(
     mut gemini_stream: Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
     model: String,
+    echo: Option<String>,
 ) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
-    let mut buffer = BytesMut::new();
+    let mut buffer = crate::proxy::streaming::SseLineBuffer::new();
     
     // Generate constant alphanumeric ID (mimics OpenAI base62 format)
     let charset = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
@@ -221,12 +224,26 @@ pub fn create_legacy_sse_stream(
     let created_ts = Utc::now().timestamp(); 
     
     let stream = async_stream::stream! {
+        // echo: 先回显 prompt
+        if let Some(prompt) = echo.filter(|p| !p.is_empty()) {
+            let echo_chunk = json!({
+                "id": &stream_id,
+                "object": "text_completion",
+                "created": created_ts,
+                "model": &model,
+                "choices": [{ "text": prompt, "index": 0, "logprobs": null, "finish_reason": null }]
+            });
+            yield Ok::<Bytes, String>(Bytes::from(format!("data: {}\n\n", echo_chunk)));
+        }
+
         while let Some(item) = gemini_stream.next().await {
             match item {
                 Ok(bytes) => {
-                    buffer.extend_from_slice(&bytes);
-                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
-                        let line_raw = buffer.split_to(pos + 1);
+                    if let Err(e) = buffer.push(&bytes) {
+                        yield Err(format!("Stream error: {}", e));
+                        break;
+                    }
+                    while let Some(line_raw) = buffer.next_line() {
                         if let Ok(line_str) = std::str::from_utf8(&line_raw) {
                             let line = line_str.trim();
                             if line.is_empty() { continue; }
@@ -312,7 +329,7 @@ pub fn create_codex_sse_stream(
     mut gemini_stream: Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
     _model: String,
 ) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
-    let mut buffer = BytesMut::new();
+    let mut buffer = crate::proxy::streaming::SseLineBuffer::new();
     
     // Generate alphanumeric ID
     let charset = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
@@ -343,9 +360,11 @@ pub fn create_codex_sse_stream(
         while let Some(item) = gemini_stream.next().await {
             match item {
                 Ok(bytes) => {
-                    buffer.extend_from_slice(&bytes);
-                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
-                        let line_raw = buffer.split_to(pos + 1);
+                    if let Err(e) = buffer.push(&bytes) {
+                        yield Err(format!("Stream error: {}", e));
+                        break;
+                    }
+                    while let Some(line_raw) = buffer.next_line() {
                         if let Ok(line_str) = std::str::from_utf8(&line_raw) {
                             let line = line_str.trim();
                             if line.is_empty() || !line.starts_with("data: ") { continue; }
@@ -0,0 +1,179 @@
+// 启动时的模型映射检查: 逐条解析映射表与映射规则的目标，对照内置模型表与账号池实际可用的模型，
+// 提前发现目标为空、拼写错误或没有任何账号能提供的映射 (否则只会在运行时表现为上游 404)
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::proxy::common::model_mapping::is_known_upstream_model;
+use crate::proxy::config::ProxyConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingProblem {
+    EmptyTarget,
+    /// 不在内置模型表中，账号池中也没有账号报告该模型
+    UnknownModel,
+    /// 已知模型，但账号池中没有账号报告该模型的配额
+    NoAccount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingIssue {
+    /// 配置项，如 `proxy.openai_mapping`、`proxy.mapping_rules[2]`
+    pub key: String,
+    pub from: String,
+    pub target: String,
+    pub problem: MappingProblem,
+}
+
+impl fmt::Display for MappingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self.problem {
+            MappingProblem::EmptyTarget => "目标为空",
+            MappingProblem::UnknownModel => "目标不是已知模型",
+            MappingProblem::NoAccount => "没有账号可以提供该模型",
+        };
+        write!(f, "{}: {} -> {}: {}", self.key, self.from, self.target, problem)
+    }
+}
+
+/// 账号池是否提供 `target`: 与账号报告的模型同名，或为其变体 (如 `gemini-3-pro-image-4k`)
+fn is_served(target: &str, served: &HashSet<String>) -> bool {
+    served.contains(target)
+        || served
+            .iter()
+            .any(|m| target.strip_prefix(m.as_str()).is_some_and(|rest| rest.starts_with('-')))
+}
+
+fn problem_for(target: &str, served: &HashSet<String>) -> Option<MappingProblem> {
+    let target = target.trim();
+    if target.is_empty() {
+        return Some(MappingProblem::EmptyTarget);
+    }
+    // 正则捕获组在请求时才能确定目标
+    if target.contains('$') {
+        return None;
+    }
+    // 账号尚未获取过配额时无法判断可用性，只检查是否为已知模型
+    if served.is_empty() {
+        return (!is_known_upstream_model(target)).then_some(MappingProblem::UnknownModel);
+    }
+    if is_served(target, served) {
+        return None;
+    }
+    Some(if is_known_upstream_model(target) {
+        MappingProblem::NoAccount
+    } else {
+        MappingProblem::UnknownModel
+    })
+}
+
+/// 检查所有映射目标；`served` 为账号池中报告了配额的模型
+pub fn check(config: &ProxyConfig, served: &HashSet<String>) -> Vec<MappingIssue> {
+    let mut issues = Vec::new();
+
+    for (key, mapping) in [
+        ("proxy.anthropic_mapping", &config.anthropic_mapping),
+        ("proxy.openai_mapping", &config.openai_mapping),
+        ("proxy.custom_mapping", &config.custom_mapping),
+    ] {
+        let mut entries: Vec<_> = mapping.iter().collect();
+        entries.sort();
+        for (from, target) in entries {
+            if let Some(problem) = problem_for(target, served) {
+                issues.push(MappingIssue {
+                    key: key.to_string(),
+                    from: from.clone(),
+                    target: target.clone(),
+                    problem,
+                });
+            }
+        }
+    }
+
+    for (i, rule) in config.mapping_rules.iter().enumerate() {
+        let targets: Vec<&str> = if rule.targets.is_empty() {
+            vec![rule.target.as_str()]
+        } else {
+            rule.targets.iter().map(|t| t.model.as_str()).collect()
+        };
+        for target in targets {
+            if let Some(problem) = problem_for(target, served) {
+                issues.push(MappingIssue {
+                    key: format!("proxy.mapping_rules[{}]", i),
+                    from: rule.pattern.clone(),
+                    target: target.to_string(),
+                    problem,
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::{ModelMappingRule, WeightedTarget};
+
+    fn served(models: &[&str]) -> HashSet<String> {
+        models.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn flags_empty_unknown_and_unserved_targets() {
+        let mut config = ProxyConfig::default();
+        config.custom_mapping.insert("a".to_string(), "gemini-2.5-flash".to_string());
+        config.custom_mapping.insert("b".to_string(), " ".to_string());
+        config.custom_mapping.insert("c".to_string(), "gemini-2.5-flsh".to_string());
+        config.custom_mapping.insert("d".to_string(), "claude-sonnet-4-5".to_string());
+
+        let issues = check(&config, &served(&["gemini-2.5-flash"]));
+        let problems: Vec<(&str, MappingProblem)> =
+            issues.iter().map(|i| (i.from.as_str(), i.problem)).collect();
+        assert_eq!(
+            problems,
+            vec![
+                ("b", MappingProblem::EmptyTarget),
+                ("c", MappingProblem::UnknownModel),
+                ("d", MappingProblem::NoAccount),
+            ]
+        );
+        assert!(issues[2].to_string().contains("proxy.custom_mapping: d -> claude-sonnet-4-5"));
+    }
+
+    #[test]
+    fn without_quota_data_only_checks_registry() {
+        let mut config = ProxyConfig::default();
+        config.custom_mapping.insert("a".to_string(), "claude-sonnet-4-5".to_string());
+        config.custom_mapping.insert("b".to_string(), "not-a-model".to_string());
+        let issues = check(&config, &HashSet::new());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].problem, MappingProblem::UnknownModel);
+    }
+
+    #[test]
+    fn served_variants_and_capture_targets_pass() {
+        let mut config = ProxyConfig::default();
+        config.custom_mapping.insert("img".to_string(), "gemini-3-pro-image-4k".to_string());
+        config.mapping_rules = vec![
+            ModelMappingRule {
+                pattern: "re:^my-(.*)$".to_string(),
+                target: "$1".to_string(),
+                targets: Vec::new(),
+            },
+            ModelMappingRule {
+                pattern: "gpt-*".to_string(),
+                target: String::new(),
+                targets: vec![
+                    WeightedTarget { model: "gemini-2.5-pro".to_string(), weight: 90 },
+                    WeightedTarget { model: "gemini-9-ultra".to_string(), weight: 10 },
+                ],
+            },
+        ];
+        let issues = check(&config, &served(&["gemini-3-pro-image", "gemini-2.5-pro"]));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "proxy.mapping_rules[1]");
+        assert_eq!(issues[0].target, "gemini-9-ultra");
+        assert_eq!(issues[0].problem, MappingProblem::UnknownModel);
+    }
+}
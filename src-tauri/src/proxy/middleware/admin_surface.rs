@@ -0,0 +1,103 @@
+// 两级接口面: 对外监听器只提供对话接口，管理 / 调试接口仅限本机客户端或独立的管理监听器 (`admin_listen`)
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::net::IpAddr;
+
+use crate::proxy::cluster::EVENTS_PATH as CLUSTER_EVENTS_PATH;
+use crate::proxy::key_scopes::is_admin;
+
+/// 监听器提供的接口范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiSurface {
+    /// 全部接口，管理接口仅允许回环地址的客户端访问 (未配置 `admin_listen`)
+    Shared,
+    /// 仅对话接口 (管理接口由独立的管理监听器提供)
+    Public,
+    /// 独立管理监听器: 仅管理接口与健康检查
+    Admin,
+}
+
+impl ApiSurface {
+    pub fn allows(&self, path: &str, client: Option<IpAddr>) -> bool {
+        // 多实例协同的事件通道由对等实例通过对外端口调用，在鉴权中间件中校验集群共享密钥
+        if path == "/healthz" || path == CLUSTER_EVENTS_PATH {
+            return true;
+        }
+        match self {
+            Self::Shared => !is_admin(path) || client.is_some_and(|ip| ip.is_loopback()),
+            Self::Public => !is_admin(path),
+            Self::Admin => is_admin(path),
+        }
+    }
+}
+
+/// 不在当前接口面内的请求一律按不存在处理 (不暴露管理接口的存在)
+pub async fn admin_surface_middleware(
+    State(surface): State<ApiSurface>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = crate::proxy::client_ip::client_ip(&request);
+    if surface.allows(request.uri().path(), client) {
+        return next.run(request).await;
+    }
+    tracing::debug!(
+        "拒绝接口面之外的请求: {} {} (客户端 {})",
+        request.method(),
+        request.uri().path(),
+        client.map(|ip| ip.to_string()).unwrap_or_else(|| "-".to_string())
+    );
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": {
+                "message": format!("Not found: {}", request.uri().path()),
+                "type": "not_found_error"
+            }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAN: Option<IpAddr> = Some(IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 20)));
+    const LOCAL: Option<IpAddr> = Some(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+
+    #[test]
+    fn shared_surface_limits_admin_to_loopback() {
+        let surface = ApiSurface::Shared;
+        assert!(surface.allows("/v1/messages", LAN));
+        assert!(!surface.allows("/admin/status", LAN));
+        assert!(!surface.allows("/debug/translate", LAN));
+        assert!(!surface.allows("/dashboard", LAN));
+        assert!(!surface.allows("/stats/models", None));
+        assert!(surface.allows("/admin/status", LOCAL));
+        assert!(surface.allows("/admin/status", Some(IpAddr::V6(std::net::Ipv6Addr::LOCALHOST))));
+    }
+
+    #[test]
+    fn public_and_admin_surfaces_are_disjoint() {
+        assert!(ApiSurface::Public.allows("/v1/chat/completions", LAN));
+        assert!(!ApiSurface::Public.allows("/admin/status", LOCAL));
+        assert!(ApiSurface::Admin.allows("/admin/status", LAN));
+        assert!(!ApiSurface::Admin.allows("/v1/chat/completions", LOCAL));
+        for surface in [ApiSurface::Public, ApiSurface::Admin] {
+            assert!(surface.allows("/healthz", LAN));
+        }
+    }
+
+    #[test]
+    fn cluster_events_stay_reachable_from_peers() {
+        assert!(ApiSurface::Shared.allows(CLUSTER_EVENTS_PATH, LAN));
+        assert!(ApiSurface::Public.allows(CLUSTER_EVENTS_PATH, LAN));
+    }
+}
@@ -0,0 +1,92 @@
+// anthropic-version 中间件: 校验 Claude 客户端声明的 API 版本，并在响应中回传
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::proxy::ProxySecurityConfig;
+
+pub const ANTHROPIC_VERSION_HEADER: &str = "anthropic-version";
+
+/// 版本是否可接受: 未配置版本列表时不校验，未携带版本头时放行
+fn is_supported(supported: &[String], version: Option<&str>) -> bool {
+    match version {
+        Some(version) => supported.is_empty() || supported.iter().any(|v| v == version),
+        None => true,
+    }
+}
+
+pub async fn anthropic_version_middleware(
+    State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !request.uri().path().starts_with("/v1/messages") {
+        return next.run(request).await;
+    }
+
+    let version = request.headers().get(ANTHROPIC_VERSION_HEADER).cloned();
+    let version_str = version.as_ref().and_then(|v| v.to_str().ok());
+    let supported = security.read().await.anthropic_versions.clone();
+    if !is_supported(&supported, version_str) {
+        // 与 Anthropic API 一致的错误格式
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "type": "error",
+                "error": {
+                    "type": "invalid_request_error",
+                    "message": format!(
+                        "anthropic-version: \"{}\" is not a supported version (supported: {})",
+                        version_str.unwrap_or_default(),
+                        supported.join(", ")
+                    ),
+                }
+            })),
+        )
+            .into_response();
+    }
+
+    let mut response = next.run(request).await;
+    if let Some(version) = version.filter(|v| !v.is_empty()) {
+        response
+            .headers_mut()
+            .insert(ANTHROPIC_VERSION_HEADER, version);
+    }
+    response
+}
+
+/// 配置中无法作为请求头取值的版本 (配置校验用)
+pub fn invalid_versions(supported: &[String]) -> Vec<String> {
+    supported
+        .iter()
+        .filter(|v| v.trim().is_empty() || HeaderValue::from_str(v).is_err())
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_declared_version_against_config() {
+        let supported = vec!["2023-06-01".to_string(), "2023-01-01".to_string()];
+        assert!(is_supported(&supported, Some("2023-06-01")));
+        assert!(!is_supported(&supported, Some("2022-01-01")));
+        assert!(is_supported(&supported, None));
+        assert!(is_supported(&[], Some("anything")));
+    }
+
+    #[test]
+    fn rejects_unrepresentable_versions() {
+        let versions = vec!["2023-06-01".to_string(), " ".to_string(), "bad\nvalue".to_string()];
+        assert_eq!(invalid_versions(&versions), vec![" ".to_string(), "bad\nvalue".to_string()]);
+    }
+}
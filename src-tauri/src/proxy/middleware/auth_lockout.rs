@@ -0,0 +1,333 @@
+// 鉴权失败锁定中间件：按客户端 IP 统计鉴权失败，逐次加长响应延迟，超过上限后临时封禁
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use dashmap::DashMap;
+use serde_json::json;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::AuthLockoutConfig;
+
+/// 超过该数量的客户端记录时清理已过期的记录
+const PRUNE_THRESHOLD: usize = 1024;
+/// 封禁时长上限
+const MAX_BAN: Duration = Duration::from_secs(24 * 3600);
+/// 同一 IP 被封禁达到该次数时视为持续攻击
+const SUSTAINED_BANS: u32 = 3;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 鉴权中间件拒绝请求时在响应上附加的标记 (区别于上游返回的 401)
+#[derive(Debug, Clone, Copy)]
+pub struct AuthRejected;
+
+#[derive(Debug, Clone, Copy)]
+struct ClientState {
+    failures: u32,
+    window_start: Instant,
+    banned_until: Option<Instant>,
+    /// 累计被封禁次数 (决定下次封禁时长)
+    bans: u32,
+}
+
+/// 一次鉴权失败的处理结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FailureOutcome {
+    /// 返回 401 前的延迟
+    pub delay: Duration,
+    /// 本次失败触发的封禁时长
+    pub banned_for: Option<Duration>,
+    /// 该 IP 累计被封禁次数
+    pub bans: u32,
+}
+
+pub struct AuthLockout {
+    config: RwLock<AuthLockoutConfig>,
+    clients: DashMap<IpAddr, ClientState>,
+}
+
+impl AuthLockout {
+    pub fn new(config: AuthLockoutConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            clients: DashMap::new(),
+        }
+    }
+
+    /// 热更新配置；已有的封禁保持不变
+    pub fn configure(&self, config: &AuthLockoutConfig) {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        if *current != *config {
+            *current = config.clone();
+            tracing::info!("鉴权失败锁定配置已更新: {:?}", config);
+        }
+    }
+
+    fn current_config(&self) -> AuthLockoutConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 本机地址不计入 (本地客户端配置错误不应把自己锁在外面)
+    fn tracked(&self, ip: IpAddr) -> Option<AuthLockoutConfig> {
+        let config = self.current_config();
+        (config.enabled && !ip.is_loopback()).then_some(config)
+    }
+
+    /// 该 IP 剩余的封禁时长
+    pub fn banned_for(&self, ip: IpAddr) -> Option<Duration> {
+        self.banned_for_at(ip, Instant::now())
+    }
+
+    fn banned_for_at(&self, ip: IpAddr, now: Instant) -> Option<Duration> {
+        self.tracked(ip)?;
+        let until = self.clients.get(&ip)?.banned_until?;
+        (until > now).then(|| until - now)
+    }
+
+    pub fn record_failure(&self, ip: IpAddr) -> Option<FailureOutcome> {
+        self.record_failure_at(ip, Instant::now())
+    }
+
+    fn record_failure_at(&self, ip: IpAddr, now: Instant) -> Option<FailureOutcome> {
+        let config = self.tracked(ip)?;
+        let window = Duration::from_secs(config.window_secs);
+
+        if self.clients.len() > PRUNE_THRESHOLD {
+            self.clients.retain(|_, c| {
+                c.banned_until.is_some_and(|t| t > now)
+                    || now.saturating_duration_since(c.window_start) < window
+            });
+        }
+
+        let mut client = self.clients.entry(ip).or_insert(ClientState {
+            failures: 0,
+            window_start: now,
+            banned_until: None,
+            bans: 0,
+        });
+        if now.saturating_duration_since(client.window_start) >= window {
+            client.failures = 0;
+            client.window_start = now;
+        }
+        client.failures += 1;
+
+        let exponent = (client.failures - 1).min(16);
+        let delay = Duration::from_millis(
+            config
+                .base_delay_ms
+                .saturating_mul(1u64 << exponent)
+                .min(config.max_delay_ms),
+        );
+
+        let mut banned_for = None;
+        if config.max_failures > 0 && client.failures >= config.max_failures {
+            client.bans += 1;
+            let ban = Duration::from_secs(config.ban_secs)
+                .saturating_mul(1u32 << (client.bans - 1).min(16))
+                .min(MAX_BAN);
+            client.banned_until = Some(now + ban);
+            client.failures = 0;
+            client.window_start = now;
+            banned_for = Some(ban);
+        }
+        Some(FailureOutcome {
+            delay,
+            banned_for,
+            bans: client.bans,
+        })
+    }
+
+    /// 鉴权成功后清除失败计数 (保留封禁历史以便再次攻击时加长封禁)
+    pub fn record_success(&self, ip: IpAddr) {
+        if let Some(mut client) = self.clients.get_mut(&ip) {
+            client.failures = 0;
+        }
+    }
+
+    fn webhook_url(&self) -> String {
+        self.current_config().webhook_url.trim().to_string()
+    }
+}
+
+fn ceil_secs(d: Duration) -> u64 {
+    d.as_secs() + u64::from(d.subsec_nanos() > 0)
+}
+
+fn banned_response(ip: IpAddr, remaining: Duration) -> Response {
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(json!({
+            "error": {
+                "message": format!("Too many failed authentication attempts from {}, please retry later", ip),
+                "type": "rate_limit_error",
+                "code": "auth_locked_out"
+            }
+        })),
+    )
+        .into_response();
+    response.headers_mut().insert(
+        axum::http::header::RETRY_AFTER,
+        HeaderValue::from(ceil_secs(remaining).max(1)),
+    );
+    response
+}
+
+/// 发送封禁通知 (失败仅记录日志)
+fn notify_ban(url: String, ip: IpAddr, outcome: FailureOutcome) {
+    tokio::spawn(async move {
+        let payload = json!({
+            "event": "auth_lockout",
+            "ip": ip.to_string(),
+            "ban_secs": outcome.banned_for.map(|d| d.as_secs()),
+            "bans": outcome.bans,
+            "sustained": outcome.bans >= SUSTAINED_BANS,
+            "at": chrono::Utc::now().timestamp(),
+        });
+        let result = reqwest::Client::new()
+            .post(&url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => tracing::warn!("鉴权锁定通知失败: HTTP {}", resp.status()),
+            Err(e) => tracing::warn!("鉴权锁定通知失败: {}", e),
+        }
+    });
+}
+
+/// 鉴权失败锁定中间件 (挂在鉴权中间件外层，客户端地址见 `client_ip_middleware`)
+pub async fn auth_lockout_middleware(
+    State(lockout): State<Arc<AuthLockout>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(ip) = crate::proxy::client_ip::client_ip(&request) else {
+        return next.run(request).await;
+    };
+
+    if let Some(remaining) = lockout.banned_for(ip) {
+        tracing::debug!("客户端 {} 处于鉴权锁定中，拒绝 {}", ip, request.uri().path());
+        return banned_response(ip, remaining);
+    }
+
+    let path = request.uri().path().to_string();
+    let response = next.run(request).await;
+    if response.extensions().get::<AuthRejected>().is_none() {
+        if response.status() != StatusCode::FORBIDDEN {
+            lockout.record_success(ip);
+        }
+        return response;
+    }
+
+    let Some(outcome) = lockout.record_failure(ip) else {
+        return response;
+    };
+    if let Some(ban) = outcome.banned_for {
+        if outcome.bans >= SUSTAINED_BANS {
+            tracing::error!(
+                "客户端 {} 持续尝试破解 API Key (第 {} 次封禁)，封禁 {} 秒",
+                ip,
+                outcome.bans,
+                ban.as_secs()
+            );
+        } else {
+            tracing::warn!("客户端 {} 鉴权失败次数过多，封禁 {} 秒 (最近请求 {})", ip, ban.as_secs(), path);
+        }
+        let url = lockout.webhook_url();
+        if !url.is_empty() {
+            notify_ban(url, ip, outcome);
+        }
+    }
+    tokio::time::sleep(outcome.delay).await;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lockout(max_failures: u32) -> AuthLockout {
+        AuthLockout::new(AuthLockoutConfig {
+            max_failures,
+            ..AuthLockoutConfig::default()
+        })
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([192, 168, 1, last])
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_cap() {
+        let lockout = lockout(100);
+        let now = Instant::now();
+        let delays: Vec<u64> = (0..7)
+            .map(|_| lockout.record_failure_at(ip(1), now).unwrap().delay.as_millis() as u64)
+            .collect();
+        assert_eq!(delays, vec![200, 400, 800, 1600, 3200, 5000, 5000]);
+    }
+
+    #[test]
+    fn bans_after_max_failures_and_doubles_on_repeat() {
+        let lockout = lockout(3);
+        let now = Instant::now();
+        assert!(lockout.record_failure_at(ip(1), now).unwrap().banned_for.is_none());
+        assert!(lockout.record_failure_at(ip(1), now).unwrap().banned_for.is_none());
+        let first = lockout.record_failure_at(ip(1), now).unwrap();
+        assert_eq!(first.banned_for, Some(Duration::from_secs(900)));
+        assert!(lockout.banned_for_at(ip(1), now).is_some());
+        assert!(lockout.banned_for_at(ip(2), now).is_none());
+
+        let later = now + Duration::from_secs(901);
+        assert!(lockout.banned_for_at(ip(1), later).is_none());
+        for _ in 0..2 {
+            lockout.record_failure_at(ip(1), later);
+        }
+        let second = lockout.record_failure_at(ip(1), later).unwrap();
+        assert_eq!(second.banned_for, Some(Duration::from_secs(1800)));
+        assert_eq!(second.bans, 2);
+    }
+
+    #[test]
+    fn failures_outside_window_reset() {
+        let lockout = lockout(2);
+        let now = Instant::now();
+        lockout.record_failure_at(ip(1), now);
+        let outcome = lockout
+            .record_failure_at(ip(1), now + Duration::from_secs(601))
+            .unwrap();
+        assert!(outcome.banned_for.is_none());
+        assert_eq!(outcome.delay, Duration::from_millis(200));
+    }
+
+    #[test]
+    fn success_resets_failures() {
+        let lockout = lockout(2);
+        let now = Instant::now();
+        lockout.record_failure_at(ip(1), now);
+        lockout.record_success(ip(1));
+        assert!(lockout.record_failure_at(ip(1), now).unwrap().banned_for.is_none());
+    }
+
+    #[test]
+    fn loopback_and_disabled_are_not_tracked() {
+        let lockout = lockout(1);
+        assert!(lockout.record_failure(IpAddr::from([127, 0, 0, 1])).is_none());
+
+        let disabled = AuthLockout::new(AuthLockoutConfig {
+            enabled: false,
+            ..AuthLockoutConfig::default()
+        });
+        assert!(disabled.record_failure(ip(1)).is_none());
+    }
+}
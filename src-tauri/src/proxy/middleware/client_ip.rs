@@ -0,0 +1,30 @@
+// 客户端地址中间件：按受信任代理配置解析真实客户端地址，供限流、鉴权锁定与请求日志使用
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+use crate::proxy::client_ip::{ClientIp, TrustedProxies};
+
+/// 写入 `ClientIp` 请求扩展 (需要连接层注入 `ConnectInfo<SocketAddr>`，Unix 套接字连接不写入)
+pub async fn client_ip_middleware(
+    State(trusted): State<Arc<RwLock<TrustedProxies>>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    if let Some(peer) = peer {
+        let ip = trusted
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .resolve(peer, request.headers());
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(request).await
+}
@@ -0,0 +1,377 @@
+// 响应压缩中间件: 按 Accept-Encoding 协商 gzip / br，压缩 JSON 与文本响应；
+// SSE 流逐块压缩并立即刷新，客户端仍能实时收到每个事件
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
+use std::io::Write;
+
+use crate::proxy::server::AppState;
+
+/// 缓冲压缩的响应体上限，超出时不压缩、原样流式转发
+const MAX_BUFFERED_BODY: usize = 100 * 1024 * 1024;
+const BROTLI_BUFFER_SIZE: usize = 4096;
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Brotli,
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Brotli => "br",
+        }
+    }
+}
+
+/// 根据 Accept-Encoding 选择编码: 取 q 值最高者，相同时优先 br；不支持时返回 None
+pub fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+    let mut best: Option<(Encoding, f32, bool)> = None;
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let name = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+        let q = parts
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|v| v.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if q <= 0.0 {
+            continue;
+        }
+        let candidates: &[(Encoding, bool)] = match name.as_str() {
+            "br" => &[(Encoding::Brotli, true)],
+            "gzip" | "x-gzip" => &[(Encoding::Gzip, true)],
+            "*" => &[(Encoding::Brotli, false), (Encoding::Gzip, false)],
+            _ => &[],
+        };
+        for &(encoding, explicit) in candidates {
+            let better = match best {
+                None => true,
+                Some((current, best_q, best_explicit)) => {
+                    q > best_q
+                        || (q == best_q && explicit && !best_explicit)
+                        || (q == best_q
+                            && explicit == best_explicit
+                            && encoding == Encoding::Brotli
+                            && current == Encoding::Gzip)
+                }
+            };
+            if better {
+                best = Some((encoding, q, explicit));
+            }
+        }
+    }
+    best.map(|(encoding, _, _)| encoding)
+}
+
+/// 增量压缩器: 每次写入后刷新，输出可被客户端立即解码的数据
+pub enum Compressor {
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+}
+
+impl Compressor {
+    pub fn new(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Gzip => Compressor::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+            Encoding::Brotli => Compressor::Brotli(Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                BROTLI_BUFFER_SIZE,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            ))),
+        }
+    }
+
+    /// 压缩一个数据块并刷新，返回本次产生的压缩数据
+    pub fn compress(&mut self, data: &[u8]) -> std::io::Result<Bytes> {
+        let out = match self {
+            Compressor::Gzip(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            Compressor::Brotli(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+        };
+        Ok(Bytes::from(std::mem::take(out)))
+    }
+
+    /// 结束压缩流，返回剩余数据 (含结尾标记)
+    pub fn finish(self) -> std::io::Result<Bytes> {
+        let out = match self {
+            Compressor::Gzip(encoder) => encoder.finish()?,
+            Compressor::Brotli(encoder) => encoder.into_inner(),
+        };
+        Ok(Bytes::from(out))
+    }
+}
+
+/// 一次性压缩完整数据
+pub fn compress_all(encoding: Encoding, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut compressor = Compressor::new(encoding);
+    let mut out = compressor.compress(data)?.to_vec();
+    out.extend_from_slice(&compressor.finish()?);
+    Ok(out)
+}
+
+fn content_type(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+}
+
+fn is_compressible(content_type: &str) -> bool {
+    content_type.contains("json") || content_type.starts_with("text/")
+}
+
+fn mark_encoded(headers: &mut HeaderMap, encoding: Encoding) {
+    headers.remove(header::CONTENT_LENGTH);
+    headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.as_str()));
+    headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+}
+
+/// 缓冲响应体的结果
+enum Buffered {
+    Complete(Bytes),
+    /// 超过上限: 已读取的数据与剩余的流，按原样转发
+    Oversized(Body),
+    Failed(axum::Error),
+}
+
+/// 读取完整响应体用于压缩；超过 `limit` 时停止缓冲，不丢弃已读取的数据
+async fn buffer_body(body: Body, limit: usize) -> Buffered {
+    let mut stream = body.into_data_stream();
+    let mut chunks: Vec<Bytes> = Vec::new();
+    let mut len = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => return Buffered::Failed(e),
+        };
+        len += chunk.len();
+        chunks.push(chunk);
+        if len > limit {
+            let head = futures::stream::iter(chunks.into_iter().map(Ok::<_, axum::Error>));
+            return Buffered::Oversized(Body::from_stream(head.chain(stream)));
+        }
+    }
+    let mut bytes = Vec::with_capacity(len);
+    for chunk in &chunks {
+        bytes.extend_from_slice(chunk);
+    }
+    Buffered::Complete(Bytes::from(bytes))
+}
+
+pub async fn compression_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.compression.read().await.clone();
+    if !config.enabled || request.method() == Method::HEAD {
+        return next.run(request).await;
+    }
+    let Some(encoding) = request
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(negotiate)
+    else {
+        return next.run(request).await;
+    };
+
+    let response = next.run(request).await;
+    let status = response.status();
+    // 错误响应保持原样，由外层的错误归一化中间件改写
+    if status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+        || status.is_client_error()
+        || status.is_server_error()
+        || response.headers().contains_key(header::CONTENT_ENCODING)
+    {
+        return response;
+    }
+    let content_type = content_type(response.headers()).to_string();
+
+    if content_type.contains("text/event-stream") {
+        if !config.streaming {
+            return response;
+        }
+        let (mut parts, body) = response.into_parts();
+        mark_encoded(&mut parts.headers, encoding);
+        let mut stream = body.into_data_stream();
+        let (mut tx, rx) = crate::proxy::streaming::forward_channel();
+        tokio::spawn(async move {
+            let mut compressor = Compressor::new(encoding);
+            while let Some(chunk) = stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+                match compressor.compress(&chunk) {
+                    Ok(out) if out.is_empty() => {}
+                    Ok(out) => {
+                        if !tx.send(Ok(out)).await {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("[Compression] SSE 压缩失败: {}", e);
+                        let _ = tx.send(Err(axum::Error::new(e))).await;
+                        return;
+                    }
+                }
+            }
+            match compressor.finish() {
+                Ok(out) => {
+                    let _ = tx.send(Ok(out)).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(axum::Error::new(e))).await;
+                }
+            }
+        });
+        return Response::from_parts(parts, Body::from_stream(rx));
+    }
+
+    if !is_compressible(&content_type) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match buffer_body(body, MAX_BUFFERED_BODY).await {
+        Buffered::Complete(bytes) => bytes,
+        Buffered::Oversized(body) => return Response::from_parts(parts, body),
+        Buffered::Failed(e) => {
+            // 响应体已不完整，不能带着原来的 200 与 Content-Length 返回空内容
+            tracing::warn!("[Compression] 读取响应体失败: {}", e);
+            parts.status = StatusCode::BAD_GATEWAY;
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            let body = serde_json::json!({
+                "error": {
+                    "message": "Failed to read upstream response body",
+                    "type": "upstream_error"
+                }
+            });
+            return Response::from_parts(parts, Body::from(body.to_string()));
+        }
+    };
+    if bytes.len() < config.min_size {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    match compress_all(encoding, &bytes) {
+        Ok(compressed) => {
+            mark_encoded(&mut parts.headers, encoding);
+            Response::from_parts(parts, Body::from(compressed))
+        }
+        Err(e) => {
+            tracing::warn!("[Compression] 响应压缩失败: {}", e);
+            Response::from_parts(parts, Body::from(bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn decode(encoding: Encoding, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        match encoding {
+            Encoding::Gzip => {
+                flate2::read::GzDecoder::new(data).read_to_end(&mut out).unwrap();
+            }
+            Encoding::Brotli => {
+                brotli::Decompressor::new(data, 4096).read_to_end(&mut out).unwrap();
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn negotiates_by_quality() {
+        assert_eq!(negotiate("gzip, deflate, br"), Some(Encoding::Brotli));
+        assert_eq!(negotiate("gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("br;q=0.5, gzip;q=0.8"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("br;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("*;q=0.1, gzip;q=0.1"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("*"), Some(Encoding::Brotli));
+        assert_eq!(negotiate("identity"), None);
+        assert_eq!(negotiate(""), None);
+    }
+
+    #[test]
+    fn compress_all_round_trips() {
+        let body = br#"{"choices":[{"message":{"content":"hello hello hello hello"}}]}"#.repeat(50);
+        for encoding in [Encoding::Gzip, Encoding::Brotli] {
+            let compressed = compress_all(encoding, &body).unwrap();
+            assert!(compressed.len() < body.len());
+            assert_eq!(decode(encoding, &compressed), body);
+        }
+    }
+
+    #[test]
+    fn streaming_chunks_are_flushed_immediately() {
+        for encoding in [Encoding::Gzip, Encoding::Brotli] {
+            let mut compressor = Compressor::new(encoding);
+            let mut all = Vec::new();
+            for event in ["data: {\"a\":1}\n\n", "data: {\"b\":2}\n\n", "data: [DONE]\n\n"] {
+                let out = compressor.compress(event.as_bytes()).unwrap();
+                // 每个事件都产生输出，客户端无需等待后续数据
+                assert!(!out.is_empty());
+                all.extend_from_slice(&out);
+            }
+            all.extend_from_slice(&compressor.finish().unwrap());
+            assert_eq!(
+                decode(encoding, &all),
+                b"data: {\"a\":1}\n\ndata: {\"b\":2}\n\ndata: [DONE]\n\n"
+            );
+        }
+    }
+
+    fn chunked(chunks: Vec<Result<&'static str, std::io::Error>>) -> Body {
+        Body::from_stream(futures::stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn oversized_body_is_forwarded_intact() {
+        let body = chunked(vec![Ok("aaaa"), Ok("bbbb"), Ok("cccc")]);
+        let Buffered::Oversized(body) = buffer_body(body, 6).await else {
+            panic!("expected oversized body");
+        };
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], b"aaaabbbbcccc");
+
+        let body = chunked(vec![Ok("aaaa"), Ok("bbbb")]);
+        let Buffered::Complete(bytes) = buffer_body(body, 8).await else {
+            panic!("expected complete body");
+        };
+        assert_eq!(&bytes[..], b"aaaabbbb");
+    }
+
+    #[tokio::test]
+    async fn body_read_error_is_reported() {
+        let body = chunked(vec![Ok("aaaa"), Err(std::io::Error::other("reset"))]);
+        assert!(matches!(buffer_body(body, 1024).await, Buffered::Failed(_)));
+    }
+}
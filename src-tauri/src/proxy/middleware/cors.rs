@@ -16,6 +16,14 @@ pub fn cors_layer() -> CorsLayer {
             Method::PATCH,
         ])
         .allow_headers(Any)
+        .expose_headers([
+            crate::proxy::middleware::request_id::REQUEST_ID_HEADER,
+            crate::proxy::middleware::response_headers::ACCOUNT_HEADER,
+            crate::proxy::middleware::response_headers::MODEL_MAPPED_HEADER,
+            crate::proxy::middleware::response_headers::QUOTA_REMAINING_HEADER,
+            crate::proxy::middleware::response_headers::STREAM_RESUME_HEADER,
+        ]
+        .map(axum::http::HeaderName::from_static))
         .allow_credentials(false)
         .max_age(std::time::Duration::from_secs(3600))
 }
@@ -0,0 +1,58 @@
+// 错误归一化中间件: 模型接口的错误响应统一改写为客户端协议的原生错误格式 (见 `proxy::errors`)
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::proxy::errors::{self, Protocol};
+
+/// 超过该大小的错误响应体不做改写
+const MAX_ERROR_BODY: usize = 1024 * 1024;
+
+pub async fn error_normalization_middleware(request: Request, next: Next) -> Response {
+    let Some(protocol) = Protocol::from_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let response = next.run(request).await;
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error())
+        || response.headers().contains_key(header::CONTENT_ENCODING)
+        || response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("text/event-stream"))
+        || response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok())
+            .is_some_and(|len| len > MAX_ERROR_BODY)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("读取错误响应体失败: {}", e);
+            Default::default()
+        }
+    };
+    let Some((status, value)) = errors::normalize(protocol, status, &bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if status != parts.status {
+        tracing::debug!("错误状态码修正: {} -> {}", parts.status, status);
+    }
+    parts.status = status;
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
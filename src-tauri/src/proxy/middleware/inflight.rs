@@ -0,0 +1,159 @@
+// 在途请求中间件: 登记请求直到响应体发送完毕，并响应管理接口的取消操作
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde_json::{json, Value};
+
+use crate::proxy::events::ProxyEvent;
+use crate::proxy::inflight::InflightRequest;
+use crate::proxy::server::AppState;
+
+/// 读取请求体的上限 (与路由的 DefaultBodyLimit 一致)
+const MAX_REQUEST_BODY: usize = 100 * 1024 * 1024;
+
+fn cancelled_response() -> Response {
+    (
+        StatusCode::from_u16(499).unwrap_or(StatusCode::SERVICE_UNAVAILABLE),
+        axum::Json(json!({
+            "error": { "type": "cancelled", "message": "Request was cancelled by an administrator" }
+        })),
+    )
+        .into_response()
+}
+
+pub async fn inflight_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    if path.starts_with("/admin/")
+        || path.starts_with("/stats/")
+        || path.starts_with("/debug/")
+        || path.starts_with("/dashboard")
+        || path == "/healthz"
+        || path.contains("event_logging")
+    {
+        return next.run(request).await;
+    }
+
+    let id = request
+        .extensions()
+        .get::<crate::proxy::middleware::request_id::RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let key_id = crate::proxy::middleware::auth::provided_key(request.headers(), request.uri())
+        .map(|key| crate::proxy::secrets::key_fingerprint(&key));
+    let client_ip = crate::proxy::client_ip::client_ip(&request).map(|ip| ip.to_string());
+    let method = request.method().to_string();
+
+    // 模型: Gemini 原生接口在路径中，其余取自请求体
+    let mut model = path
+        .strip_prefix("/v1beta/models/")
+        .and_then(|rest| rest.split(':').next())
+        .map(str::to_string);
+    let request = if model.is_none() && request.method() == axum::http::Method::POST {
+        let (parts, body) = request.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_REQUEST_BODY).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return (StatusCode::BAD_REQUEST, format!("Failed to read request body: {}", e)).into_response()
+            }
+        };
+        model = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|json| json.get("model").and_then(|m| m.as_str()).map(str::to_string));
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        request
+    };
+
+    crate::proxy::events::publish(ProxyEvent::RequestStarted {
+        id: id.clone(),
+        method: method.clone(),
+        path: path.clone(),
+        model: model.clone(),
+    });
+    let started = std::time::Instant::now();
+    let finished = {
+        let (id, model) = (id.clone(), model.clone());
+        move |status: u16, account: Option<String>| {
+            crate::proxy::events::publish(ProxyEvent::RequestFinished {
+                id,
+                status,
+                duration_ms: started.elapsed().as_millis() as u64,
+                model,
+                account,
+            })
+        }
+    };
+    let requested_model = model.clone();
+    let (guard, cancel) = state.inflight.register(InflightRequest {
+        id: id.clone(),
+        method,
+        path,
+        model,
+        account: None,
+        key_id,
+        client_ip,
+        started_at: chrono::Utc::now().timestamp_millis(),
+        age_ms: 0,
+        streaming: false,
+    });
+
+    // 上游响应头到达前取消: 直接丢弃处理中的 future (连同上游连接)
+    let (response, served) = tokio::select! {
+        result = crate::proxy::token_manager::track_served(next.run(request)) => result,
+        _ = cancel.notified() => {
+            let response = cancelled_response();
+            finished(response.status().as_u16(), None);
+            return response;
+        }
+    };
+    let streaming = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/event-stream"));
+    let first_byte_ms = started.elapsed().as_millis() as u64;
+    // 按映射后的上游模型统计，未分配账号的请求 (如 z.ai) 使用请求中的模型名
+    let upstream_model = served.as_ref().and_then(|s| s.model.clone()).or(requested_model);
+    let served = served.map(|s| s.email);
+    state.inflight.update(&id, served.clone(), streaming);
+    let status = response.status().as_u16();
+    let model_stats = state.model_stats.clone();
+
+    // 响应体发送期间取消: 以错误中断流，使客户端能察觉响应不完整
+    let (parts, body) = response.into_parts();
+    let mut stream = body.into_data_stream();
+    let (mut tx, rx) = crate::proxy::streaming::forward_channel();
+    tokio::spawn(async move {
+        let _guard = guard;
+        loop {
+            tokio::select! {
+                chunk = stream.next() => {
+                    let Some(chunk) = chunk else { break };
+                    let failed = chunk.is_err();
+                    if !tx.send(chunk).await || failed {
+                        break;
+                    }
+                }
+                _ = cancel.notified() => {
+                    let _ = tx.send(Err(axum::Error::new("request cancelled by an administrator"))).await;
+                    break;
+                }
+            }
+        }
+        if let Some(model) = upstream_model {
+            model_stats.record(&model, first_byte_ms, started.elapsed().as_millis() as u64, status);
+        }
+        finished(status, served);
+    });
+
+    Response::from_parts(parts, Body::from_stream(rx))
+}
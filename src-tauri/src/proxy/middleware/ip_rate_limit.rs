@@ -0,0 +1,266 @@
+// 按客户端 IP 的令牌桶限流中间件
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use dashmap::DashMap;
+use serde_json::json;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::ClientRateLimitConfig;
+
+/// 超过该数量的客户端桶时清理已回满的桶 (回满的桶与不存在等价)
+const PRUNE_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 单次限流判定结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitOutcome {
+    pub allowed: bool,
+    /// 桶容量 (X-RateLimit-Limit)
+    pub limit: u32,
+    /// 剩余可用请求数 (X-RateLimit-Remaining)
+    pub remaining: u32,
+    /// 桶回满所需时间 (X-RateLimit-Reset)
+    pub reset_after: Duration,
+    /// 被限流时距离下一个可用令牌的时间 (Retry-After)
+    pub retry_after: Duration,
+}
+
+pub struct IpRateLimiter {
+    config: RwLock<ClientRateLimitConfig>,
+    buckets: DashMap<IpAddr, Bucket>,
+}
+
+impl IpRateLimiter {
+    pub fn new(config: ClientRateLimitConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// 热更新限流配置；配置变化时重置所有客户端的桶
+    pub fn configure(&self, config: &ClientRateLimitConfig) {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        if *current != *config {
+            *current = config.clone();
+            self.buckets.clear();
+            tracing::info!("客户端 IP 限流配置已更新: {:?}", config);
+        }
+    }
+
+    fn current_config(&self) -> ClientRateLimitConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 检查并消耗一个令牌；未启用时返回 None
+    pub fn check(&self, ip: IpAddr) -> Option<RateLimitOutcome> {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Option<RateLimitOutcome> {
+        let config = self.current_config();
+        if !config.enabled || config.requests_per_second <= 0.0 || config.burst == 0 {
+            return None;
+        }
+        let rps = config.requests_per_second;
+        let capacity = config.burst as f64;
+
+        if self.buckets.len() > PRUNE_THRESHOLD {
+            self.buckets.retain(|_, b| {
+                b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * rps < capacity
+            });
+        }
+
+        let mut bucket = self.buckets.entry(ip).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rps).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let tokens = bucket.tokens;
+        drop(bucket);
+
+        Some(RateLimitOutcome {
+            allowed,
+            limit: config.burst,
+            remaining: tokens.floor() as u32,
+            reset_after: Duration::from_secs_f64((capacity - tokens) / rps),
+            retry_after: if allowed {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64((1.0 - tokens) / rps)
+            },
+        })
+    }
+}
+
+fn ceil_secs(d: Duration) -> u64 {
+    d.as_secs() + u64::from(d.subsec_nanos() > 0)
+}
+
+fn apply_headers(headers: &mut HeaderMap, outcome: &RateLimitOutcome) {
+    headers.insert("X-RateLimit-Limit", HeaderValue::from(outcome.limit));
+    headers.insert("X-RateLimit-Remaining", HeaderValue::from(outcome.remaining));
+    headers.insert(
+        "X-RateLimit-Reset",
+        HeaderValue::from(ceil_secs(outcome.reset_after)),
+    );
+    if !outcome.allowed {
+        headers.insert(
+            axum::http::header::RETRY_AFTER,
+            HeaderValue::from(ceil_secs(outcome.retry_after).max(1)),
+        );
+    }
+}
+
+/// 客户端 IP 限流中间件 (客户端地址见 `client_ip_middleware`)
+pub async fn ip_rate_limit_middleware(
+    State(limiter): State<Arc<IpRateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() == Method::OPTIONS || request.uri().path() == "/healthz" {
+        return next.run(request).await;
+    }
+
+    let Some(ip) = crate::proxy::client_ip::client_ip(&request) else {
+        return next.run(request).await;
+    };
+
+    let Some(outcome) = limiter.check(ip) else {
+        return next.run(request).await;
+    };
+
+    if !outcome.allowed {
+        tracing::warn!(
+            "客户端 {} 请求过于频繁，已限流 {} {}",
+            ip,
+            request.method(),
+            request.uri().path()
+        );
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({
+                "error": {
+                    "message": format!("Too many requests from {}, please retry later", ip),
+                    "type": "rate_limit_error",
+                    "code": "client_rate_limited"
+                }
+            })),
+        )
+            .into_response();
+        apply_headers(response.headers_mut(), &outcome);
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    apply_headers(response.headers_mut(), &outcome);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(rps: f64, burst: u32) -> IpRateLimiter {
+        IpRateLimiter::new(ClientRateLimitConfig {
+            enabled: true,
+            requests_per_second: rps,
+            burst,
+        })
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([192, 168, 1, last])
+    }
+
+    #[test]
+    fn disabled_limiter_allows_everything() {
+        let limiter = IpRateLimiter::new(ClientRateLimitConfig::default());
+        assert!(limiter.check(ip(1)).is_none());
+    }
+
+    #[test]
+    fn burst_then_limited_then_refilled() {
+        let limiter = limiter(2.0, 3);
+        let now = Instant::now();
+
+        for expected_remaining in [2, 1, 0] {
+            let outcome = limiter.check_at(ip(1), now).unwrap();
+            assert!(outcome.allowed);
+            assert_eq!(outcome.remaining, expected_remaining);
+            assert_eq!(outcome.limit, 3);
+        }
+
+        let limited = limiter.check_at(ip(1), now).unwrap();
+        assert!(!limited.allowed);
+        assert_eq!(limited.retry_after, Duration::from_millis(500));
+        assert_eq!(ceil_secs(limited.reset_after), 2);
+
+        let refilled = limiter
+            .check_at(ip(1), now + Duration::from_millis(500))
+            .unwrap();
+        assert!(refilled.allowed);
+    }
+
+    #[test]
+    fn buckets_are_per_ip() {
+        let limiter = limiter(1.0, 1);
+        let now = Instant::now();
+        assert!(limiter.check_at(ip(1), now).unwrap().allowed);
+        assert!(!limiter.check_at(ip(1), now).unwrap().allowed);
+        assert!(limiter.check_at(ip(2), now).unwrap().allowed);
+    }
+
+    #[test]
+    fn reconfigure_resets_buckets() {
+        let limiter = limiter(1.0, 1);
+        let now = Instant::now();
+        assert!(limiter.check_at(ip(1), now).unwrap().allowed);
+
+        limiter.configure(&ClientRateLimitConfig {
+            enabled: true,
+            requests_per_second: 1.0,
+            burst: 2,
+        });
+        assert!(limiter.check_at(ip(1), now).unwrap().allowed);
+    }
+
+    #[test]
+    fn limited_response_has_retry_after() {
+        let mut headers = HeaderMap::new();
+        let outcome = RateLimitOutcome {
+            allowed: false,
+            limit: 10,
+            remaining: 0,
+            reset_after: Duration::from_millis(4500),
+            retry_after: Duration::from_millis(200),
+        };
+        apply_headers(&mut headers, &outcome);
+        assert_eq!(headers["X-RateLimit-Limit"], "10");
+        assert_eq!(headers["X-RateLimit-Remaining"], "0");
+        assert_eq!(headers["X-RateLimit-Reset"], "5");
+        assert_eq!(headers["Retry-After"], "1");
+    }
+}
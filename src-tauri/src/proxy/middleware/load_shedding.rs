@@ -0,0 +1,90 @@
+// 请求优先级与过载卸载中间件
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::proxy::load_shedding::AdmissionController;
+
+/// 客户端指定优先级的请求头 (`high` / `normal` / `low`)
+pub const PRIORITY_HEADER: &str = "x-request-priority";
+
+pub async fn load_shedding_middleware(
+    State(controller): State<Arc<AdmissionController>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if request.method() == Method::OPTIONS
+        || path == "/healthz"
+        || path.starts_with("/admin/")
+        || path.starts_with("/stats/")
+        || path.starts_with("/dashboard")
+    {
+        return next.run(request).await;
+    }
+
+    let headers = request.headers();
+    let api_key = crate::proxy::middleware::auth::provided_key(headers, request.uri());
+    let priority = controller.resolve_priority(
+        headers.get(PRIORITY_HEADER).and_then(|h| h.to_str().ok()),
+        api_key.as_deref(),
+    );
+
+    let permit = match controller.acquire(priority).await {
+        Ok(permit) => permit,
+        Err(shed) => {
+            tracing::warn!(
+                "反代过载，卸载 {:?} 优先级请求: {} {}",
+                priority,
+                request.method(),
+                request.uri().path()
+            );
+            let mut response = (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({
+                    "error": {
+                        "message": "Proxy is overloaded, please retry later",
+                        "type": "overloaded_error",
+                        "code": "load_shed"
+                    }
+                })),
+            )
+                .into_response();
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(shed.retry_after.as_secs()),
+            );
+            return response;
+        }
+    };
+
+    let response = next.run(request).await;
+
+    // 流式响应在响应体发送完毕前持续占用名额
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !is_stream {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let mut data = body.into_data_stream();
+    let guarded = async_stream::stream! {
+        let _permit = permit;
+        while let Some(chunk) = data.next().await {
+            yield chunk;
+        }
+    };
+    Response::from_parts(parts, Body::from_stream(guarded))
+}
@@ -0,0 +1,213 @@
+// 流量镜像中间件: 抽中的请求在后台以镜像配置再执行一次，与原始响应对比后记录差异
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use std::time::Instant;
+use tokio::sync::oneshot;
+
+use crate::proxy::mirror::{self, MirrorResult, ShadowPlan, ShadowRequest};
+use crate::proxy::server::AppState;
+
+/// 读取请求体的上限 (与路由的 DefaultBodyLimit 一致)
+const MAX_REQUEST_BODY: usize = 100 * 1024 * 1024;
+/// 参与对比的响应体上限，超出时放弃本次对比
+const MAX_CAPTURED_RESPONSE: usize = 4 * 1024 * 1024;
+
+/// 镜像请求不携带的请求头 (鉴权已在外层完成；团队路由由镜像账号组代替；镜像响应需以明文对比)
+const DROPPED_HEADERS: [&str; 7] = [
+    "accept-encoding",
+    "authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "openai-organization",
+    "openai-project",
+    "content-length",
+];
+
+/// 原始请求的执行结果
+struct PrimaryOutcome {
+    status: u16,
+    duration: u64,
+    /// 完整响应体；客户端提前断开或超出上限时为 None
+    body: Option<Bytes>,
+}
+
+pub async fn mirror_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.mirror.is_enabled() || request.extensions().get::<ShadowRequest>().is_some() {
+        return next.run(request).await;
+    }
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+    if !mirror::is_mirrored(&method, &uri) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_REQUEST_BODY).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, format!("Failed to read request body: {}", e)).into_response()
+        }
+    };
+    let Some(plan) = state.mirror.sample(&method, &uri, &bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    let request_id = parts
+        .extensions
+        .get::<crate::proxy::middleware::request_id::RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_default();
+    let shadow = match build_shadow_request(&parts, &plan) {
+        Ok(shadow) => shadow,
+        Err(e) => {
+            tracing::warn!("[Mirror] 构建镜像请求失败: {}", e);
+            return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+        }
+    };
+
+    // 镜像请求与原始请求并行执行，对比在后台完成
+    let (primary_tx, primary_rx) = oneshot::channel();
+    tokio::spawn(run_shadow(state.clone(), request_id, plan, shadow, primary_rx));
+
+    let start = Instant::now();
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    let status = response.status().as_u16();
+    let duration = start.elapsed().as_millis() as u64;
+
+    // 原样转发响应，同时截留一份用于对比
+    let (parts, body) = response.into_parts();
+    let mut stream = body.into_data_stream();
+    let (mut tx, rx) = crate::proxy::streaming::forward_channel();
+    tokio::spawn(async move {
+        let mut captured = Some(Vec::new());
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    if let Some(buf) = captured.as_mut() {
+                        if buf.len() + chunk.len() > MAX_CAPTURED_RESPONSE {
+                            captured = None;
+                        } else {
+                            buf.extend_from_slice(&chunk);
+                        }
+                    }
+                    if !tx.send(Ok::<_, axum::Error>(chunk)).await {
+                        captured = None;
+                        break;
+                    }
+                }
+                Err(e) => {
+                    captured = None;
+                    let _ = tx.send(Err(axum::Error::new(e))).await;
+                    break;
+                }
+            }
+        }
+        let _ = primary_tx.send(PrimaryOutcome {
+            status,
+            duration,
+            body: captured.map(Bytes::from),
+        });
+    });
+
+    Response::from_parts(parts, Body::from_stream(rx))
+}
+
+fn build_shadow_request(
+    parts: &axum::http::request::Parts,
+    plan: &ShadowPlan,
+) -> Result<Request, String> {
+    let mut builder = Request::builder().method(parts.method.clone()).uri(&plan.uri);
+    for (name, value) in &parts.headers {
+        if !DROPPED_HEADERS.contains(&name.as_str()) {
+            builder = builder.header(name, value);
+        }
+    }
+    builder
+        .extension(ShadowRequest)
+        .body(Body::from(plan.body.clone()))
+        .map_err(|e| e.to_string())
+}
+
+async fn run_shadow(
+    state: AppState,
+    request_id: String,
+    plan: ShadowPlan,
+    request: Request,
+    primary: oneshot::Receiver<PrimaryOutcome>,
+) {
+    use tower::Service;
+
+    let Some(router) = state.replay_router.get().and_then(|r| r.upgrade()) else {
+        return;
+    };
+    let mut router = (*router).clone();
+
+    let start = Instant::now();
+    let call = router.call(request);
+    let (response, shadow_account) = crate::proxy::token_manager::track_served_account(async {
+        if plan.accounts.is_empty() {
+            call.await
+        } else {
+            crate::proxy::token_manager::with_account_scope(plan.accounts.clone(), call).await
+        }
+    })
+    .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let shadow_status = response.status().as_u16();
+    let shadow_body = match axum::body::to_bytes(response.into_body(), MAX_CAPTURED_RESPONSE).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::debug!("[Mirror] 读取镜像响应失败 ({}): {}", request_id, e);
+            return;
+        }
+    };
+    let shadow_duration = start.elapsed().as_millis() as u64;
+
+    let Ok(primary) = primary.await else {
+        return;
+    };
+    let Some(primary_body) = primary.body else {
+        tracing::debug!("[Mirror] 原始响应未完整读取，跳过对比 ({})", request_id);
+        return;
+    };
+
+    let (diff, diff_truncated) = mirror::compare(&primary_body, &shadow_body);
+    let identical = diff.is_empty() && primary.status == shadow_status;
+    tracing::info!(
+        "[Mirror] {} {:?} -> {:?} | status {} / {} | identical: {}",
+        plan.uri,
+        plan.model,
+        plan.shadow_model,
+        primary.status,
+        shadow_status,
+        identical
+    );
+    state.mirror.record(MirrorResult {
+        request_id,
+        timestamp: chrono::Utc::now().timestamp(),
+        url: plan.uri,
+        model: plan.model,
+        shadow_model: plan.shadow_model,
+        shadow_account,
+        status: primary.status,
+        shadow_status,
+        duration: primary.duration,
+        shadow_duration,
+        identical,
+        diff,
+        diff_truncated,
+    });
+}
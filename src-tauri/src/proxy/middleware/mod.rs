@@ -1,9 +1,39 @@
 // Middleware 模块 - Axum 中间件
 
+pub mod admin_surface;
+pub mod anthropic_version;
 pub mod auth;
+pub mod auth_lockout;
+pub mod client_ip;
+pub mod compression;
 pub mod cors;
+pub mod error_normalization;
+pub mod inflight;
+pub mod ip_rate_limit;
+pub mod load_shedding;
 pub mod logging;
+pub mod mirror;
+pub mod param_policy;
 pub mod monitor;
+pub mod request_id;
+pub mod response_headers;
+pub mod team_routing;
+pub mod validation;
 
+pub use admin_surface::admin_surface_middleware;
+pub use anthropic_version::anthropic_version_middleware;
 pub use auth::auth_middleware;
+pub use auth_lockout::auth_lockout_middleware;
+pub use client_ip::client_ip_middleware;
+pub use compression::compression_middleware;
 pub use cors::cors_layer;
+pub use error_normalization::error_normalization_middleware;
+pub use inflight::inflight_middleware;
+pub use ip_rate_limit::ip_rate_limit_middleware;
+pub use load_shedding::load_shedding_middleware;
+pub use mirror::mirror_middleware;
+pub use param_policy::param_policy_middleware;
+pub use request_id::request_id_middleware;
+pub use response_headers::response_headers_middleware;
+pub use team_routing::team_routing_middleware;
+pub use validation::request_validation_middleware;
@@ -6,24 +6,64 @@ use axum::{
 };
 use std::time::Instant;
 use crate::proxy::server::AppState;
-use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::monitor::{ProxyMonitor, ProxyRequestLog};
+use crate::proxy::token_manager::TokenManager;
+use crate::proxy::session_manager::SessionManager;
+use axum::http::HeaderValue;
 use serde_json::Value;
 use futures::StreamExt;
+use std::sync::Arc;
+
+/// 记录请求日志，并将 Token 用量计入实际服务的账号 (用量上限)
+async fn finish(
+    monitor: &ProxyMonitor,
+    token_manager: &TokenManager,
+    served: Option<String>,
+    log: ProxyRequestLog,
+    log_enabled: bool,
+) {
+    if let Some(email) = served {
+        let tokens = log.input_tokens.unwrap_or(0) as u64 + log.output_tokens.unwrap_or(0) as u64;
+        if tokens > 0 {
+            token_manager.record_token_usage(&email, tokens).await;
+        }
+    }
+    if log_enabled {
+        monitor.log_request(log).await;
+    }
+}
 
 pub async fn monitor_middleware(
     State(state): State<AppState>,
     request: Request,
     next: Next,
 ) -> Response {
-    if !state.monitor.is_enabled() {
-        return next.run(request).await;
+    // 未开启日志时，仅在启用了 Token 用量上限时才需要解析响应用量
+    let log_enabled = state.monitor.is_enabled();
+    if !log_enabled && !state.token_manager.tracks_token_usage().await {
+        // 仍需建立作用域，使加权映射在同一请求内的多次路由中保持一致
+        return crate::proxy::common::mapping_rules::track_canary(next.run(request)).await.0;
     }
 
     let start = Instant::now();
+    let request_id = request
+        .extensions()
+        .get::<crate::proxy::middleware::request_id::RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
     let method = request.method().to_string();
-    let uri = request.uri().to_string();
+    let key_id = crate::proxy::middleware::auth::provided_key(request.headers(), request.uri())
+        .map(|key| crate::proxy::secrets::key_fingerprint(&key));
+    let client_ip = crate::proxy::client_ip::client_ip(&request).map(|ip| ip.to_string());
+    // URL 中可能带有 Gemini SDK 的 `?key=`，记录前脱敏
+    let uri = crate::proxy::secrets::redact_text(&request.uri().to_string()).into_owned();
     
-    if uri.contains("event_logging") {
+    // 心跳与管理端点 (如实时日志流) 不记录
+    if uri.contains("event_logging") || request.uri().path().starts_with("/admin/")
+        || request.uri().path().starts_with("/stats/")
+        || request.uri().path().starts_with("/debug/")
+        || request.uri().path().starts_with("/dashboard")
+    {
         return next.run(request).await;
     }
     
@@ -37,14 +77,16 @@ pub async fn monitor_middleware(
     };
 
     let request_body_str;
+    let mut session_id = None;
     let request = if method == "POST" {
         let (parts, body) = request.into_parts();
         match axum::body::to_bytes(body, 1024 * 1024).await {
             Ok(bytes) => {
-                if model.is_none() {
-                    model = serde_json::from_slice::<Value>(&bytes).ok().and_then(|v|
-                        v.get("model").and_then(|m| m.as_str()).map(|s| s.to_string())
-                    );
+                if let Ok(json) = serde_json::from_slice::<Value>(&bytes) {
+                    if model.is_none() {
+                        model = json.get("model").and_then(|m| m.as_str()).map(|s| s.to_string());
+                    }
+                    session_id = SessionManager::extract_conversation_id(&parts.headers, parts.uri.path(), &json);
                 }
                 request_body_str = if let Ok(s) = std::str::from_utf8(&bytes) {
                     Some(s.to_string())
@@ -63,7 +105,14 @@ pub async fn monitor_middleware(
         request
     };
     
-    let response = next.run(request).await;
+    let ((mut response, served), canary_target) = crate::proxy::common::mapping_rules::track_canary(
+        crate::proxy::token_manager::track_served_account(next.run(request)),
+    )
+    .await;
+    // 回传会话标识，便于客户端在日志中定位整段对话
+    if let Some(value) = session_id.as_deref().and_then(|id| HeaderValue::from_str(id).ok()) {
+        response.headers_mut().insert("x-antigravity-session", value);
+    }
     
     let duration = start.elapsed().as_millis() as u64;
     let status = response.status().as_u16();
@@ -74,8 +123,9 @@ pub async fn monitor_middleware(
         .to_string();
 
     let monitor = state.monitor.clone();
+    let token_manager: Arc<TokenManager> = state.token_manager.clone();
     let mut log = ProxyRequestLog {
-        id: uuid::Uuid::new_v4().to_string(),
+        id: request_id,
         timestamp: chrono::Utc::now().timestamp_millis(),
         method,
         url: uri,
@@ -87,13 +137,18 @@ pub async fn monitor_middleware(
         response_body: None,
         input_tokens: None,
         output_tokens: None,
+        session_id,
+        account: served.clone(),
+        key_id,
+        client_ip,
+        canary_target,
     };
 
     if content_type.contains("text/event-stream") {
         log.response_body = Some("[Stream Data]".to_string());
         let (parts, body) = response.into_parts();
         let mut stream = body.into_data_stream();
-        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        let (mut tx, rx) = crate::proxy::streaming::forward_channel();
         
         tokio::spawn(async move {
             let mut last_few_bytes = Vec::new();
@@ -107,9 +162,13 @@ pub async fn monitor_middleware(
                             last_few_bytes.drain(0..last_few_bytes.len()-8192);
                         }
                     }
-                    let _ = tx.send(Ok::<_, axum::Error>(chunk)).await;
+                    // 客户端已断开: 停止读取上游
+                    if !tx.send(Ok::<_, axum::Error>(chunk)).await {
+                        break;
+                    }
                 } else if let Err(e) = chunk_res {
                     let _ = tx.send(Err(axum::Error::new(e))).await;
+                    break;
                 }
             }
             
@@ -134,10 +193,10 @@ pub async fn monitor_middleware(
             if log.status >= 400 {
                 log.error = Some("Stream Error or Failed".to_string());
             }
-            monitor.log_request(log).await;
+            finish(&monitor, &token_manager, served, log, log_enabled).await;
         });
 
-        Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+        Response::from_parts(parts, Body::from_stream(rx))
     } else if content_type.contains("application/json") || content_type.contains("text/") {
         let (parts, body) = response.into_parts();
         match axum::body::to_bytes(body, 512 * 1024).await {
@@ -160,18 +219,18 @@ pub async fn monitor_middleware(
                 if log.status >= 400 {
                     log.error = log.response_body.clone();
                 }
-                monitor.log_request(log).await;
+                finish(&monitor, &token_manager, served, log, log_enabled).await;
                 Response::from_parts(parts, Body::from(bytes))
             }
             Err(_) => {
                 log.response_body = Some("[Response too large]".to_string());
-                monitor.log_request(log).await;
+                finish(&monitor, &token_manager, served, log, log_enabled).await;
                 Response::from_parts(parts, Body::empty())
             }
         }
     } else {
         log.response_body = Some(format!("[{}]", content_type));
-        monitor.log_request(log).await;
+        finish(&monitor, &token_manager, served, log, log_enabled).await;
         response
     }
 }
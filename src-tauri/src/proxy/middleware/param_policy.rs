@@ -0,0 +1,73 @@
+// 采样参数策略中间件: 在请求到达处理器之前按配置调整 temperature / top_p / 最大输出 token 数，
+// 并通过响应头告知客户端哪些参数被调整
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::proxy::param_policy::{self, Dialect};
+use crate::proxy::server::AppState;
+
+/// 被策略调整的参数 (逗号分隔)
+pub const PARAMS_ADJUSTED_HEADER: &str = "x-antigravity-params-adjusted";
+
+/// 与路由的请求体上限一致
+const MAX_BODY: usize = 100 * 1024 * 1024;
+
+pub async fn param_policy_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(dialect) = Dialect::from_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let config = state.param_policy.read().await.clone();
+    if config.rules.is_empty() {
+        return next.run(request).await;
+    }
+
+    // 鉴权中间件按请求所在监听器的密钥解析出的名称 (鉴权关闭时没有，按密钥区分的规则不生效)
+    let key_name = request
+        .extensions()
+        .get::<crate::proxy::security::AuthorizedKey>()
+        .map(|key| key.name.clone());
+
+    let (mut parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BODY).await {
+        Ok(bytes) => bytes,
+        // 交由处理器返回协议原生的错误
+        Err(_) => return next.run(Request::from_parts(parts, Body::empty())).await,
+    };
+    let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let model = param_policy::request_model(parts.uri.path(), &json);
+    let Some(rule) = param_policy::find_rule(&config, model.as_deref(), key_name.as_deref()) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let adjusted = param_policy::apply(rule, dialect, &mut json);
+    if adjusted.is_empty() {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    }
+
+    tracing::info!(
+        "参数策略已调整请求参数: model={}, key={}, params={}",
+        model.as_deref().unwrap_or("-"),
+        key_name.as_deref().unwrap_or("-"),
+        adjusted.join(",")
+    );
+    let body = serde_json::to_vec(&json).unwrap_or_else(|_| bytes.to_vec());
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let mut response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if let Ok(value) = HeaderValue::from_str(&adjusted.join(",")) {
+        response.headers_mut().insert(PARAMS_ADJUSTED_HEADER, value);
+    }
+    response
+}
@@ -0,0 +1,99 @@
+// 请求 ID 中间件: 为每个请求分配 ID (或沿用客户端提供的 X-Request-Id)，
+// 回写到响应头，并关联监控日志、日志行 (tracing span) 与上游请求 (traceparent)
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 客户端提供的请求 ID 最大长度
+const MAX_CLIENT_ID_LEN: usize = 128;
+
+/// 当前请求的 ID (存放在请求 extensions 中)
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// 当前任务所处理请求的 ID (上游客户端据此生成 traceparent)
+pub fn current() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// 校验客户端提供的 ID: 仅接受字母、数字与 `.`、`_`、`:`、`-`，避免日志注入
+fn sanitize(value: &str) -> Option<String> {
+    let value = value.trim();
+    let valid = !value.is_empty()
+        && value.len() <= MAX_CLIENT_ID_LEN
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | ':' | '-'));
+    valid.then(|| value.to_string())
+}
+
+/// 由请求 ID 生成 W3C traceparent: trace-id 由 ID 派生 (同一请求的重试保持一致)，span-id 每次随机
+pub fn traceparent(request_id: &str) -> String {
+    let digest = Sha256::digest(request_id.as_bytes());
+    let trace_id: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    let span_id: u64 = rand::thread_rng().gen_range(1..=u64::MAX);
+    format!("00-{}-{:016x}-01", trace_id, span_id)
+}
+
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(sanitize)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    // 内层中间件 (及内部转发的路由) 读取同一个 ID
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        request.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!("request", request_id = %id);
+    let mut response = CURRENT_REQUEST_ID
+        .scope(id.clone(), next.run(request).instrument(span))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_safe_client_ids_only() {
+        assert_eq!(sanitize(" req-123_abc:1.2 ").as_deref(), Some("req-123_abc:1.2"));
+        assert_eq!(sanitize(""), None);
+        assert_eq!(sanitize("bad id\nx"), None);
+        assert_eq!(sanitize(&"a".repeat(129)), None);
+    }
+
+    #[test]
+    fn traceparent_keeps_trace_id_per_request() {
+        let a = traceparent("req-1");
+        let b = traceparent("req-1");
+        let parts: Vec<&str> = a.split('-').collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], "00");
+        assert_eq!(parts[1].len(), 32);
+        assert_eq!(parts[2].len(), 16);
+        assert_eq!(parts[3], "01");
+        assert_eq!(a[..35], b[..35]);
+        assert_ne!(traceparent("req-2")[..35], a[..35]);
+    }
+}
@@ -0,0 +1,135 @@
+// 响应头中间件: 按配置透传上游响应头，并附加账号指纹、映射模型与剩余配额等信息头
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::cell::RefCell;
+
+use crate::proxy::config::ResponseHeaderConfig;
+use crate::proxy::server::AppState;
+use crate::proxy::token_manager::{track_served, ServedAccount};
+
+pub const ACCOUNT_HEADER: &str = "x-antigravity-account";
+pub const MODEL_MAPPED_HEADER: &str = "x-antigravity-model-mapped";
+pub const QUOTA_REMAINING_HEADER: &str = "x-antigravity-quota-remaining";
+/// 流式响应已启用中断续传 (见 `proxy::stream_resume`)
+pub const STREAM_RESUME_HEADER: &str = "x-antigravity-stream-resume";
+
+/// 不允许透传的上游响应头 (由反代自身的响应决定)
+const BLOCKED_HEADERS: &[&str] = &[
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-type",
+    "keep-alive",
+    "set-cookie",
+    "transfer-encoding",
+    "upgrade",
+];
+
+tokio::task_local! {
+    static UPSTREAM_HEADERS: RefCell<Option<HeaderMap>>;
+}
+
+/// 记录本次请求最后一次收到的上游响应头 (由上游客户端调用)
+pub fn record_upstream(headers: &HeaderMap) {
+    let _ = UPSTREAM_HEADERS.try_with(|h| *h.borrow_mut() = Some(headers.clone()));
+}
+
+fn forward_headers(config: &ResponseHeaderConfig, upstream: &HeaderMap, response: &mut HeaderMap) {
+    for name in &config.forward {
+        let name = name.trim().to_ascii_lowercase();
+        if BLOCKED_HEADERS.contains(&name.as_str()) || name.starts_with("x-antigravity-") {
+            continue;
+        }
+        let Ok(header) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        for value in upstream.get_all(&header) {
+            response.append(header.clone(), value.clone());
+        }
+    }
+}
+
+fn augment_headers(served: &ServedAccount, response: &mut HeaderMap) {
+    let mut insert = |name: &'static str, value: String| {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.insert(name, value);
+        }
+    };
+    insert(ACCOUNT_HEADER, crate::proxy::secrets::key_fingerprint(&served.email));
+    if let Some(model) = &served.model {
+        insert(MODEL_MAPPED_HEADER, model.clone());
+    }
+    if let Some(remaining) = served.remaining_quota {
+        insert(QUOTA_REMAINING_HEADER, remaining.to_string());
+    }
+}
+
+pub async fn response_headers_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.response_headers.read().await.clone();
+    if config.forward.is_empty() && config.privacy {
+        return next.run(request).await;
+    }
+
+    let ((mut response, upstream), served) = track_served(UPSTREAM_HEADERS.scope(RefCell::new(None), async {
+        let response = next.run(request).await;
+        (response, UPSTREAM_HEADERS.with(|h| h.borrow_mut().take()))
+    }))
+    .await;
+
+    if let Some(upstream) = &upstream {
+        forward_headers(&config, upstream, response.headers_mut());
+    }
+    if !config.privacy {
+        if let Some(served) = &served {
+            augment_headers(served, response.headers_mut());
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwards_only_allowed_headers() {
+        let mut upstream = HeaderMap::new();
+        upstream.insert("x-ratelimit-remaining", HeaderValue::from_static("10"));
+        upstream.insert("content-length", HeaderValue::from_static("42"));
+        upstream.insert("server", HeaderValue::from_static("ESF"));
+        let config = ResponseHeaderConfig {
+            forward: vec!["X-RateLimit-Remaining".into(), "content-length".into(), "bad header".into()],
+            privacy: false,
+        };
+
+        let mut response = HeaderMap::new();
+        forward_headers(&config, &upstream, &mut response);
+        assert_eq!(response.get("x-ratelimit-remaining").unwrap(), "10");
+        assert!(response.get("content-length").is_none());
+        assert!(response.get("server").is_none());
+    }
+
+    #[test]
+    fn augments_with_hashed_account() {
+        let served = ServedAccount {
+            email: "user@example.com".into(),
+            model: Some("gemini-2.5-pro".into()),
+            remaining_quota: Some(73),
+        };
+        let mut response = HeaderMap::new();
+        augment_headers(&served, &mut response);
+        let account = response.get(ACCOUNT_HEADER).unwrap().to_str().unwrap();
+        assert_eq!(account.len(), 8);
+        assert!(!account.contains("user"));
+        assert_eq!(response.get(MODEL_MAPPED_HEADER).unwrap(), "gemini-2.5-pro");
+        assert_eq!(response.get(QUOTA_REMAINING_HEADER).unwrap(), "73");
+    }
+}
@@ -0,0 +1,78 @@
+// 团队路由中间件: 按 OpenAI-Organization / OpenAI-Project 请求头把请求限定到对应账号组，
+// 并记录请求所用密钥的指纹 (订阅等级策略可按密钥预留账号)
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::proxy::server::AppState;
+use crate::proxy::team_routing::{self, TeamRouteDecision, ORGANIZATION_HEADER, PROJECT_HEADER};
+use crate::proxy::token_manager::{with_account_scope, with_request_key};
+
+pub async fn team_routing_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match crate::proxy::middleware::auth::provided_key(request.headers(), request.uri()) {
+        Some(key) => {
+            let key_id = crate::proxy::secrets::key_fingerprint(&key);
+            with_request_key(key_id, route(state, request, next)).await
+        }
+        None => route(state, request, next).await,
+    }
+}
+
+async fn route(
+    state: AppState,
+    request: Request,
+    next: Next,
+) -> Response {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let organization = header(ORGANIZATION_HEADER);
+    let project = header(PROJECT_HEADER);
+
+    let config = state.token_manager.team_routing().await;
+    let accounts = match team_routing::resolve(&config, organization.as_deref(), project.as_deref()) {
+        TeamRouteDecision::Unscoped => return next.run(request).await,
+        TeamRouteDecision::Scoped(rule) => rule.accounts.clone(),
+        TeamRouteDecision::Rejected => {
+            tracing::warn!(
+                "团队路由未匹配，已拒绝: organization={:?}, project={:?}",
+                organization,
+                project
+            );
+            return (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": {
+                        "message": "No account group is configured for this organization/project",
+                        "type": "invalid_request_error",
+                        "code": "team_not_configured",
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    tracing::debug!(
+        "团队路由: organization={:?}, project={:?} -> {} 个账号",
+        organization,
+        project,
+        accounts.len()
+    );
+    with_account_scope(accounts, next.run(request)).await
+}
@@ -0,0 +1,60 @@
+// 请求校验中间件: 对话 / 补全请求在到达处理器 (及上游) 之前按协议校验，失败时返回原生格式的 400
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::proxy::config::ValidationMode;
+use crate::proxy::errors::{self, ErrorKind, Protocol};
+use crate::proxy::server::AppState;
+use crate::proxy::validation::{self, Endpoint};
+
+/// 与路由的请求体上限一致
+const MAX_BODY: usize = 100 * 1024 * 1024;
+
+pub async fn request_validation_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(endpoint) = Endpoint::from_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let mode = state.validation.read().await.mode;
+    if mode == ValidationMode::Off {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BODY).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return error_response(
+                endpoint.protocol(),
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "",
+                &format!("Failed to read request body: {}", e),
+            )
+        }
+    };
+    if let Err(e) = validation::validate(endpoint, &bytes, mode) {
+        tracing::warn!("请求校验失败 {}: {}", parts.uri.path(), e.message);
+        return error_response(endpoint.protocol(), StatusCode::BAD_REQUEST, &e.param, &e.message);
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+fn error_response(protocol: Protocol, status: StatusCode, param: &str, message: &str) -> Response {
+    let mut body = errors::render(protocol, status, ErrorKind::from_status(status), message);
+    if protocol == Protocol::OpenAI && !param.is_empty() {
+        body["error"]["param"] = param.into();
+    }
+    (status, Json(body)).into_response()
+}
@@ -0,0 +1,327 @@
+// 流量镜像 (影子测试): 按比例把生产请求复制一份，用备用模型映射 / 账号组执行，
+// 记录与原始响应的差异。镜像响应只用于对比，从不返回给客户端。
+use bytes::Bytes;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Mutex, RwLock};
+
+use crate::proxy::config::MirrorConfig;
+
+/// 单条对比结果中保留的最大差异行数
+const MAX_DIFF_LINES: usize = 200;
+
+/// 可镜像的生成类端点
+const MIRRORED_PATHS: [&str; 4] = [
+    "/v1/messages",
+    "/v1/chat/completions",
+    "/v1/completions",
+    "/v1/responses",
+];
+
+/// 一次镜像请求的执行计划
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowPlan {
+    /// 镜像请求地址 (Gemini 原生接口的模型在路径中，可能被改写)
+    pub uri: String,
+    pub body: Bytes,
+    /// 客户端请求的模型
+    pub model: Option<String>,
+    /// 镜像请求使用的模型 (未配置映射时与原模型相同)
+    pub shadow_model: Option<String>,
+    /// 镜像请求可用的账号范围 (为空时使用整个账号池)
+    pub accounts: Vec<String>,
+}
+
+/// 镜像对比结果 (`GET /admin/mirror`)
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorResult {
+    pub request_id: String,
+    pub timestamp: i64,
+    pub url: String,
+    pub model: Option<String>,
+    pub shadow_model: Option<String>,
+    /// 镜像请求实际使用的账号
+    pub shadow_account: Option<String>,
+    pub status: u16,
+    pub shadow_status: u16,
+    pub duration: u64,
+    pub shadow_duration: u64,
+    /// 状态码与响应体 (JSON 按格式化后比较) 是否一致
+    pub identical: bool,
+    /// 行级差异，`-` 为原始响应，`+` 为镜像响应 (超出部分截断)
+    pub diff: Vec<String>,
+    pub diff_truncated: bool,
+}
+
+/// 镜像请求标记 (请求扩展)，避免镜像请求再次被镜像
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowRequest;
+
+pub struct Mirror {
+    config: RwLock<MirrorConfig>,
+    results: Mutex<VecDeque<MirrorResult>>,
+}
+
+impl Mirror {
+    pub fn new(config: MirrorConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            results: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn configure(&self, config: &MirrorConfig) {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        if *current != *config {
+            *current = config.clone();
+            tracing::info!(
+                "流量镜像配置已更新: enabled={}, percent={}",
+                config.enabled,
+                config.percent
+            );
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+        config.enabled && config.percent > 0.0
+    }
+
+    /// 按配置的比例抽样并生成镜像计划；不需要镜像时返回 None
+    pub fn sample(&self, method: &str, uri: &str, body: &Bytes) -> Option<ShadowPlan> {
+        use rand::Rng;
+
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if !config.enabled || !is_mirrored(method, uri) {
+            return None;
+        }
+        if rand::thread_rng().gen_range(0.0..100.0) >= config.percent {
+            return None;
+        }
+        plan(&config, uri, body)
+    }
+
+    pub fn record(&self, result: MirrorResult) {
+        let limit = self
+            .config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .history
+            .max(1);
+        let mut results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        results.push_back(result);
+        while results.len() > limit {
+            results.pop_front();
+        }
+    }
+
+    /// 最近的对比结果 (新的在前)
+    pub fn results(&self, limit: usize) -> Vec<MirrorResult> {
+        let results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        results.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+/// 是否为可镜像的生成类请求
+pub fn is_mirrored(method: &str, uri: &str) -> bool {
+    if method != "POST" {
+        return false;
+    }
+    let path = uri.split('?').next().unwrap_or(uri);
+    MIRRORED_PATHS.contains(&path)
+        || (path.starts_with("/v1beta/models/")
+            && (path.ends_with(":generateContent") || path.ends_with(":streamGenerateContent")))
+}
+
+/// 查找镜像模型映射: 精确匹配优先，其次是最长的 `前缀*` 规则
+pub fn shadow_model(mapping: &std::collections::HashMap<String, String>, model: &str) -> Option<String> {
+    if let Some(target) = mapping.get(model) {
+        return Some(target.clone());
+    }
+    mapping
+        .iter()
+        .filter_map(|(pattern, target)| {
+            let prefix = pattern.strip_suffix('*')?;
+            model.starts_with(prefix).then_some((prefix.len(), target))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, target)| target.clone())
+}
+
+/// 根据配置生成镜像计划；映射未命中且未指定账号组时镜像没有意义，返回 None
+pub fn plan(config: &MirrorConfig, uri: &str, body: &Bytes) -> Option<ShadowPlan> {
+    let (path, query) = match uri.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (uri, None),
+    };
+
+    // Gemini 原生接口: /v1beta/models/{model}:{method}
+    if let Some(rest) = path.strip_prefix("/v1beta/models/") {
+        let (model, method) = rest.split_once(':')?;
+        let target = shadow_model(&config.model_mapping, model);
+        if target.is_none() && config.accounts.is_empty() {
+            return None;
+        }
+        let shadow = target.unwrap_or_else(|| model.to_string());
+        let mut uri = format!("/v1beta/models/{}:{}", shadow, method);
+        if let Some(query) = query {
+            uri.push('?');
+            uri.push_str(query);
+        }
+        return Some(ShadowPlan {
+            uri,
+            body: body.clone(),
+            model: Some(model.to_string()),
+            shadow_model: Some(shadow),
+            accounts: config.accounts.clone(),
+        });
+    }
+
+    let mut json: Value = serde_json::from_slice(body).ok()?;
+    let model = json.get("model").and_then(|m| m.as_str()).map(str::to_string);
+    let target = model
+        .as_deref()
+        .and_then(|m| shadow_model(&config.model_mapping, m));
+    if target.is_none() && config.accounts.is_empty() {
+        return None;
+    }
+    let body = match &target {
+        Some(target) => {
+            json["model"] = Value::String(target.clone());
+            Bytes::from(serde_json::to_vec(&json).ok()?)
+        }
+        None => body.clone(),
+    };
+    Some(ShadowPlan {
+        uri: uri.to_string(),
+        body,
+        shadow_model: target.or_else(|| model.clone()),
+        model,
+        accounts: config.accounts.clone(),
+    })
+}
+
+/// 对比原始与镜像响应，生成截断后的差异
+pub fn compare(original: &[u8], shadow: &[u8]) -> (Vec<String>, bool) {
+    let original = crate::proxy::replay::normalize_body(&String::from_utf8_lossy(original));
+    let shadow = crate::proxy::replay::normalize_body(&String::from_utf8_lossy(shadow));
+    let mut diff = crate::proxy::replay::diff_lines(&original, &shadow);
+    let truncated = diff.len() > MAX_DIFF_LINES;
+    diff.truncate(MAX_DIFF_LINES);
+    (diff, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(mapping: &[(&str, &str)], accounts: &[&str]) -> MirrorConfig {
+        MirrorConfig {
+            enabled: true,
+            percent: 100.0,
+            model_mapping: mapping
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            accounts: accounts.iter().map(|a| a.to_string()).collect(),
+            history: 10,
+        }
+    }
+
+    #[test]
+    fn only_generation_endpoints_are_mirrored() {
+        assert!(is_mirrored("POST", "/v1/messages?beta=true"));
+        assert!(is_mirrored("POST", "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"));
+        assert!(!is_mirrored("POST", "/v1/messages/count_tokens"));
+        assert!(!is_mirrored("POST", "/v1beta/models/gemini-2.5-pro:countTokens"));
+        assert!(!is_mirrored("GET", "/v1/models"));
+    }
+
+    #[test]
+    fn shadow_model_prefers_exact_then_longest_prefix() {
+        let mapping: HashMap<String, String> = [
+            ("claude-*", "gemini-2.5-pro"),
+            ("claude-sonnet-*", "gemini-3-pro"),
+            ("claude-opus-4", "gemini-3-pro-high"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(shadow_model(&mapping, "claude-opus-4").as_deref(), Some("gemini-3-pro-high"));
+        assert_eq!(shadow_model(&mapping, "claude-sonnet-4-5").as_deref(), Some("gemini-3-pro"));
+        assert_eq!(shadow_model(&mapping, "claude-haiku").as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(shadow_model(&mapping, "gpt-4o"), None);
+    }
+
+    #[test]
+    fn plan_rewrites_body_model() {
+        let body = Bytes::from(r#"{"model":"claude-sonnet-4-5","max_tokens":10}"#);
+        let plan = plan(&config(&[("claude-*", "gemini-3-pro")], &[]), "/v1/messages", &body).unwrap();
+        let json: Value = serde_json::from_slice(&plan.body).unwrap();
+        assert_eq!(json["model"], "gemini-3-pro");
+        assert_eq!(json["max_tokens"], 10);
+        assert_eq!(plan.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(plan.shadow_model.as_deref(), Some("gemini-3-pro"));
+    }
+
+    #[test]
+    fn plan_rewrites_gemini_path_model() {
+        let body = Bytes::from("{}");
+        let plan = plan(
+            &config(&[("gemini-2.5-flash", "gemini-3-flash")], &[]),
+            "/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse",
+            &body,
+        )
+        .unwrap();
+        assert_eq!(plan.uri, "/v1beta/models/gemini-3-flash:streamGenerateContent?alt=sse");
+        assert_eq!(plan.body, body);
+    }
+
+    #[test]
+    fn plan_requires_mapping_or_account_group() {
+        let body = Bytes::from(r#"{"model":"gpt-4o"}"#);
+        assert!(plan(&config(&[("claude-*", "gemini-3-pro")], &[]), "/v1/chat/completions", &body).is_none());
+
+        let plan = plan(&config(&[], &["canary@example.com"]), "/v1/chat/completions", &body).unwrap();
+        assert_eq!(plan.body, body);
+        assert_eq!(plan.shadow_model.as_deref(), Some("gpt-4o"));
+        assert_eq!(plan.accounts, vec!["canary@example.com"]);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mirror = Mirror::new(config(&[], &[]));
+        for i in 0..15 {
+            mirror.record(MirrorResult {
+                request_id: i.to_string(),
+                timestamp: i,
+                url: "/v1/messages".to_string(),
+                model: None,
+                shadow_model: None,
+                shadow_account: None,
+                status: 200,
+                shadow_status: 200,
+                duration: 0,
+                shadow_duration: 0,
+                identical: true,
+                diff: Vec::new(),
+                diff_truncated: false,
+            });
+        }
+        let results = mirror.results(100);
+        assert_eq!(results.len(), 10);
+        assert_eq!(results[0].request_id, "14");
+    }
+
+    #[test]
+    fn compare_truncates_long_diffs() {
+        let original: String = (0..300).map(|i| format!("a{}\n", i)).collect();
+        let shadow: String = (0..300).map(|i| format!("b{}\n", i)).collect();
+        let (diff, truncated) = compare(original.as_bytes(), shadow.as_bytes());
+        assert_eq!(diff.len(), MAX_DIFF_LINES);
+        assert!(truncated);
+        assert_eq!(compare(br#"{"a":1}"#, b"{\"a\": 1}"), (Vec::new(), false));
+    }
+}
@@ -6,6 +6,51 @@ pub mod token_manager;
 pub mod project_resolver;
 pub mod server;
 pub mod security;
+pub mod timeouts;
+pub mod streaming;
+pub mod batches;
+pub mod load_shedding;
+pub mod listener;
+pub mod replay;
+pub mod param_policy;
+pub mod quota_threshold;
+pub mod schedule_routing;
+pub mod secrets;
+pub mod key_rotation;
+pub mod account_recovery;
+pub mod warmup;
+pub mod latency;
+pub mod usage_caps;
+pub mod bench;
+pub mod usage_report;
+pub mod team_routing;
+pub mod tier_policy;
+pub mod cluster;
+pub mod status;
+pub mod model_capabilities;
+pub mod context_overflow;
+pub mod transcript;
+pub mod key_scopes;
+pub mod client_ip;
+pub mod pool_quota;
+pub mod mirror;
+pub mod inflight;
+pub mod stats;
+pub mod dashboard;
+pub mod builder;
+pub mod errors;
+pub mod validation;
+pub mod stream_resume;
+pub mod anomalies;
+pub mod log_export;
+pub mod mapping_check;
+pub mod events;
+pub mod doctor;
+pub mod model_stats;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "acme")]
+pub mod acme;
 
 // 新架构模块
 pub mod mappers;           // 协议转换器
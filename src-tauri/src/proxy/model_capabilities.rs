@@ -0,0 +1,355 @@
+// 模型能力注册表: 内置目录 + 上游报告 (fetchAvailableModels) + 配置覆盖，用于校验请求与 /v1/models 元数据
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::quota::ModelLimits;
+use crate::proxy::config::ModelCapabilityOverride;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    pub max_context_tokens: u64,
+    pub max_output_tokens: u64,
+    pub vision: bool,
+    pub tools: bool,
+    pub thinking: bool,
+    /// 能力来源: `catalog` / `upstream` / `override`，多个来源以 `+` 连接
+    pub source: String,
+}
+
+/// 内置目录 (按前缀匹配，取最长前缀): (前缀, 上下文, 输出, 视觉, 工具, 思考)
+const CATALOG: &[(&str, u64, u64, bool, bool, bool)] = &[
+    ("claude-opus-4-5-thinking", 200_000, 64_000, true, true, true),
+    ("claude-sonnet-4-5-thinking", 200_000, 64_000, true, true, true),
+    ("claude-sonnet-4-5", 200_000, 64_000, true, true, false),
+    ("claude-", 200_000, 64_000, true, true, false),
+    ("gemini-3-pro-image", 65_536, 32_768, true, false, false),
+    ("gemini-3-pro", 1_048_576, 65_536, true, true, true),
+    ("gemini-3-flash", 1_048_576, 65_536, true, true, true),
+    ("gemini-2.5-pro", 1_048_576, 65_536, true, true, true),
+    ("gemini-2.5-flash", 1_048_576, 65_536, true, true, true),
+    ("gemini-2.0-flash", 1_048_576, 8_192, true, true, false),
+    ("gemini-", 1_048_576, 8_192, true, true, false),
+];
+
+fn catalog_entry(model: &str) -> Option<ModelCapabilities> {
+    CATALOG
+        .iter()
+        .filter(|(prefix, ..)| model.starts_with(prefix))
+        .max_by_key(|(prefix, ..)| prefix.len())
+        .map(|&(_, max_context_tokens, max_output_tokens, vision, tools, thinking)| ModelCapabilities {
+            max_context_tokens,
+            max_output_tokens,
+            vision,
+            tools,
+            thinking,
+            source: "catalog".to_string(),
+        })
+}
+
+/// 覆盖规则匹配: 精确匹配优先，其次最长的 `prefix*`
+fn find_override<'a>(
+    overrides: &'a std::collections::HashMap<String, ModelCapabilityOverride>,
+    model: &str,
+) -> Option<&'a ModelCapabilityOverride> {
+    overrides.get(model).or_else(|| {
+        overrides
+            .iter()
+            .filter_map(|(pattern, o)| pattern.strip_suffix('*').map(|prefix| (prefix, o)))
+            .filter(|(prefix, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, o)| o)
+    })
+}
+
+/// 解析上游模型的能力: 内置目录为基础，上游报告与配置覆盖依次叠加；三者均无信息时返回 None
+pub fn resolve(
+    model: &str,
+    upstream: Option<&ModelLimits>,
+    overrides: &std::collections::HashMap<String, ModelCapabilityOverride>,
+) -> Option<ModelCapabilities> {
+    let override_entry = find_override(overrides, model);
+    let mut caps = match catalog_entry(model) {
+        Some(caps) => caps,
+        // 目录之外的模型: 上游报告了上下文长度或配置了覆盖时才纳入注册表
+        // (输出上限未报告时以上下文长度为界)
+        None if upstream.and_then(|u| u.max_tokens).is_some() || override_entry.is_some() => ModelCapabilities {
+            max_context_tokens: u64::MAX,
+            max_output_tokens: upstream.and_then(|u| u.max_tokens).unwrap_or(u64::MAX),
+            vision: true,
+            tools: true,
+            thinking: false,
+            source: String::new(),
+        },
+        None => return None,
+    };
+
+    let mut sources: Vec<&str> = caps.source.split('+').filter(|s| !s.is_empty()).collect();
+    if let Some(limits) = upstream.filter(|l| !l.is_empty()) {
+        if let Some(v) = limits.max_tokens {
+            caps.max_context_tokens = v;
+        }
+        if let Some(v) = limits.max_output_tokens {
+            caps.max_output_tokens = v;
+        }
+        if let Some(v) = limits.supports_images {
+            caps.vision = v;
+        }
+        if let Some(v) = limits.supports_thinking {
+            caps.thinking = v;
+        }
+        sources.push("upstream");
+    }
+    if let Some(o) = override_entry {
+        if let Some(v) = o.max_context_tokens {
+            caps.max_context_tokens = v;
+        }
+        if let Some(v) = o.max_output_tokens {
+            caps.max_output_tokens = v;
+        }
+        if let Some(v) = o.vision {
+            caps.vision = v;
+        }
+        if let Some(v) = o.tools {
+            caps.tools = v;
+        }
+        if let Some(v) = o.thinking {
+            caps.thinking = v;
+        }
+        sources.push("override");
+    }
+    caps.source = sources.join("+");
+    Some(caps)
+}
+
+/// 从客户端请求体 (OpenAI / Claude / Gemini 格式) 提取的用量与特性
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestFeatures {
+    /// 输入 Token 粗略估算 (约 4 字符 / token，不计图片等二进制数据)
+    pub input_tokens: u64,
+    /// 客户端请求的最大输出 Token
+    pub max_output_tokens: Option<u64>,
+    pub images: bool,
+    /// 是否声明了函数工具 (联网搜索等内置工具不计)
+    pub tools: bool,
+}
+
+/// 不计入 Token 估算的字段 (角色、类型标记与二进制 / URL 数据)
+const NON_TEXT_KEYS: &[&str] = &[
+    "role", "type", "inlineData", "fileData", "image_url", "source", "data", "mimeType", "media_type",
+];
+
+/// 本地粗略估算 Token 数 (约 4 字符 / token)
+pub fn estimate_tokens(value: &Value) -> u64 {
+    fn collect_chars(value: &Value) -> usize {
+        match value {
+            Value::String(s) => s.chars().count(),
+            Value::Array(items) => items.iter().map(collect_chars).sum(),
+            Value::Object(map) => map
+                .iter()
+                .filter(|(k, _)| !NON_TEXT_KEYS.contains(&k.as_str()))
+                .map(|(_, v)| collect_chars(v))
+                .sum(),
+            _ => 0,
+        }
+    }
+    (collect_chars(value) as u64).div_ceil(4)
+}
+
+fn contains_image(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.iter().any(contains_image),
+        Value::Object(map) => {
+            let typed_image = matches!(
+                map.get("type").and_then(|t| t.as_str()),
+                Some("image" | "image_url" | "input_image")
+            );
+            let inline_image = ["inlineData", "fileData"].iter().any(|key| {
+                map.get(*key)
+                    .and_then(|d| d.get("mimeType"))
+                    .and_then(|m| m.as_str())
+                    .is_some_and(|m| m.starts_with("image/"))
+            });
+            typed_image || inline_image || map.values().any(contains_image)
+        }
+        _ => false,
+    }
+}
+
+/// 是否为函数工具 (排除联网搜索等内置工具)
+fn is_function_tool(tool: &Value) -> bool {
+    if let Some(decls) = tool.get("functionDeclarations") {
+        return decls.as_array().is_some_and(|d| !d.is_empty());
+    }
+    if tool.get("googleSearch").is_some() || tool.get("googleSearchRetrieval").is_some() {
+        return false;
+    }
+    let kind = tool.get("type").and_then(|t| t.as_str()).unwrap_or("function");
+    !kind.contains("web_search") && !kind.contains("google_search")
+}
+
+impl RequestFeatures {
+    pub fn from_body(body: &Value) -> Self {
+        let input_tokens = [
+            "messages",
+            "system",
+            "contents",
+            "systemInstruction",
+            "input",
+            "instructions",
+            "prompt",
+            "tools",
+        ]
+        .iter()
+        .filter_map(|key| body.get(*key))
+        .map(estimate_tokens)
+        .sum();
+        let max_output_tokens = ["max_tokens", "max_completion_tokens", "max_output_tokens"]
+            .iter()
+            .find_map(|key| body.get(*key))
+            .or_else(|| body.pointer("/generationConfig/maxOutputTokens"))
+            .and_then(|v| v.as_u64());
+        let images = ["messages", "contents", "input"]
+            .iter()
+            .filter_map(|key| body.get(*key))
+            .any(contains_image);
+        let tools = body
+            .get("tools")
+            .and_then(|t| t.as_array())
+            .is_some_and(|tools| tools.iter().any(is_function_tool));
+        Self {
+            input_tokens,
+            max_output_tokens,
+            images,
+            tools,
+        }
+    }
+}
+
+/// 校验请求是否超出模型能力，返回面向客户端的错误说明。
+/// 思考模式不在此拒绝: 各协议转换时已对不支持思考的模型自动降级
+pub fn check(model: &str, caps: &ModelCapabilities, features: &RequestFeatures) -> Result<(), String> {
+    if features.input_tokens > caps.max_context_tokens {
+        return Err(format!(
+            "Input is about {} tokens, which exceeds the {} token context window of model {}",
+            features.input_tokens, caps.max_context_tokens, model
+        ));
+    }
+    if let Some(max_output) = features.max_output_tokens.filter(|n| *n > caps.max_output_tokens) {
+        return Err(format!(
+            "max_tokens {} exceeds the {} output token limit of model {}",
+            max_output, caps.max_output_tokens, model
+        ));
+    }
+    if features.images && !caps.vision {
+        return Err(format!("Model {} does not accept image input", model));
+    }
+    if features.tools && !caps.tools {
+        return Err(format!("Model {} does not support tool calling", model));
+    }
+    Ok(())
+}
+
+/// 终端输出的能力说明
+pub fn format_capabilities(model: &str, target: &str, caps: Option<&ModelCapabilities>) -> String {
+    let mut out = if model == target {
+        format!("{}\n", model)
+    } else {
+        format!("{} -> {}\n", model, target)
+    };
+    let Some(caps) = caps else {
+        out.push_str("    no capability information (requests are not validated)\n");
+        return out;
+    };
+    let limit = |n: u64| if n == u64::MAX { "unknown".to_string() } else { n.to_string() };
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    out.push_str(&format!("    context window:  {}\n", limit(caps.max_context_tokens)));
+    out.push_str(&format!("    max output:      {}\n", limit(caps.max_output_tokens)));
+    out.push_str(&format!("    vision:          {}\n", yes_no(caps.vision)));
+    out.push_str(&format!("    tools:           {}\n", yes_no(caps.tools)));
+    out.push_str(&format!("    thinking:        {}\n", yes_no(caps.thinking)));
+    out.push_str(&format!("    source:          {}\n", caps.source));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn catalog_uses_longest_prefix() {
+        let caps = resolve("gemini-3-pro-image-4k", None, &HashMap::new()).unwrap();
+        assert!(!caps.tools);
+        let caps = resolve("gemini-3-pro-high", None, &HashMap::new()).unwrap();
+        assert!(caps.tools && caps.thinking);
+        assert_eq!(caps.source, "catalog");
+        assert!(resolve("gpt-4o", None, &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn upstream_and_overrides_layer_on_catalog() {
+        let upstream = ModelLimits {
+            max_tokens: Some(200_000),
+            max_output_tokens: Some(32_000),
+            ..ModelLimits::default()
+        };
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "gemini-2.5-*".to_string(),
+            ModelCapabilityOverride {
+                vision: Some(false),
+                ..ModelCapabilityOverride::default()
+            },
+        );
+        let caps = resolve("gemini-2.5-flash", Some(&upstream), &overrides).unwrap();
+        assert_eq!(caps.max_context_tokens, 200_000);
+        assert_eq!(caps.max_output_tokens, 32_000);
+        assert!(!caps.vision);
+        assert_eq!(caps.source, "catalog+upstream+override");
+    }
+
+    #[test]
+    fn extracts_features_across_protocols() {
+        let openai = json!({
+            "model": "gpt-4o",
+            "max_tokens": 1000,
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "describe"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+            ]}],
+            "tools": [{"type": "function", "function": {"name": "f"}}]
+        });
+        let features = RequestFeatures::from_body(&openai);
+        assert!(features.images && features.tools);
+        assert_eq!(features.max_output_tokens, Some(1000));
+
+        let gemini = json!({
+            "contents": [{"role": "user", "parts": [{"text": "hi"}]}],
+            "tools": [{"googleSearch": {}}],
+            "generationConfig": {"maxOutputTokens": 100}
+        });
+        let features = RequestFeatures::from_body(&gemini);
+        assert!(!features.images && !features.tools);
+        assert_eq!(features.max_output_tokens, Some(100));
+    }
+
+    #[test]
+    fn rejects_requests_beyond_limits() {
+        let caps = resolve("gemini-3-pro-image", None, &HashMap::new()).unwrap();
+        let ok = RequestFeatures { input_tokens: 10, ..RequestFeatures::default() };
+        assert!(check("gemini-3-pro-image", &caps, &ok).is_ok());
+
+        let tools = RequestFeatures { tools: true, ..ok.clone() };
+        assert!(check("gemini-3-pro-image", &caps, &tools).unwrap_err().contains("tool calling"));
+
+        let long = RequestFeatures { input_tokens: 70_000, ..ok };
+        assert!(check("gemini-3-pro-image", &caps, &long).unwrap_err().contains("context window"));
+    }
+
+    #[test]
+    fn estimate_skips_binary_data() {
+        let contents = json!([{"role": "user", "parts": [{"text": "abcdefgh"}, {"inlineData": {"data": "x".repeat(1000)}}]}]);
+        assert_eq!(estimate_tokens(&contents), 2);
+    }
+}
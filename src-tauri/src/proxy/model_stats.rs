@@ -0,0 +1,231 @@
+// 按上游模型统计滚动窗口内的延迟分位数、成功率与吞吐 (`/stats/models`)，用于比较各映射目标的实际表现
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// 每个模型保留的最近样本数
+const WINDOW_SIZE: usize = 500;
+/// 样本的最长保留时间 (秒)
+pub const WINDOW_SECS: i64 = 15 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    /// 429 与 5xx
+    Failure,
+    /// 其余 4xx: 请求本身有误，不计入成功率
+    ClientError,
+}
+
+impl Outcome {
+    fn from_status(status: u16) -> Self {
+        match status {
+            s if s < 400 => Self::Success,
+            429 => Self::Failure,
+            s if s < 500 => Self::ClientError,
+            _ => Self::Failure,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at_ms: i64,
+    first_byte_ms: u64,
+    duration_ms: u64,
+    outcome: Outcome,
+}
+
+/// 单个上游模型的窗口统计 (延迟仅统计成功的请求，全部失败时统计所有请求)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelStats {
+    pub model: String,
+    pub requests: usize,
+    pub successes: usize,
+    pub failures: usize,
+    pub client_errors: usize,
+    /// 成功数 / (成功数 + 失败数)，没有可计入的请求时为空
+    pub success_rate: Option<f64>,
+    /// 完整请求耗时 (含流式响应) 的分位数
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    /// 响应头到达的耗时中位数
+    pub first_byte_p50_ms: u64,
+    /// 每分钟请求数
+    pub requests_per_min: f64,
+}
+
+#[derive(Default)]
+pub struct ModelScoreboard {
+    samples: DashMap<String, VecDeque<Sample>>,
+}
+
+impl ModelScoreboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, model: &str, first_byte_ms: u64, duration_ms: u64, status: u16) {
+        self.record_at(model, first_byte_ms, duration_ms, status, chrono::Utc::now().timestamp_millis());
+    }
+
+    fn record_at(&self, model: &str, first_byte_ms: u64, duration_ms: u64, status: u16, now_ms: i64) {
+        let mut window = self.samples.entry(model.to_string()).or_default();
+        if window.len() >= WINDOW_SIZE {
+            window.pop_front();
+        }
+        window.push_back(Sample {
+            at_ms: now_ms,
+            first_byte_ms,
+            duration_ms,
+            outcome: Outcome::from_status(status),
+        });
+    }
+
+    /// 所有模型的统计，按请求数降序
+    pub fn snapshot(&self) -> Vec<ModelStats> {
+        self.snapshot_at(chrono::Utc::now().timestamp_millis())
+    }
+
+    fn snapshot_at(&self, now_ms: i64) -> Vec<ModelStats> {
+        let cutoff = now_ms - WINDOW_SECS * 1000;
+        // 顺带清理过期样本与空窗口
+        self.samples.retain(|_, window| {
+            while window.front().is_some_and(|s| s.at_ms < cutoff) {
+                window.pop_front();
+            }
+            !window.is_empty()
+        });
+        let mut all: Vec<ModelStats> = self
+            .samples
+            .iter()
+            .map(|entry| compute_stats(entry.key(), entry.value(), now_ms))
+            .collect();
+        all.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.model.cmp(&b.model)));
+        all
+    }
+}
+
+fn percentile(sorted: &[u64], q: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+fn compute_stats(model: &str, window: &VecDeque<Sample>, now_ms: i64) -> ModelStats {
+    let count = |outcome: Outcome| window.iter().filter(|s| s.outcome == outcome).count();
+    let (successes, failures, client_errors) =
+        (count(Outcome::Success), count(Outcome::Failure), count(Outcome::ClientError));
+
+    let timed: Vec<&Sample> = if successes > 0 {
+        window.iter().filter(|s| s.outcome == Outcome::Success).collect()
+    } else {
+        window.iter().collect()
+    };
+    let mut durations: Vec<u64> = timed.iter().map(|s| s.duration_ms).collect();
+    durations.sort_unstable();
+    let mut first_bytes: Vec<u64> = timed.iter().map(|s| s.first_byte_ms).collect();
+    first_bytes.sort_unstable();
+
+    // 吞吐按实际覆盖的时长计算 (至少 1 分钟)，避免刚启动时被低估
+    let span_ms = window
+        .front()
+        .map(|s| now_ms - s.at_ms)
+        .unwrap_or(0)
+        .clamp(60_000, WINDOW_SECS * 1000);
+
+    ModelStats {
+        model: model.to_string(),
+        requests: window.len(),
+        successes,
+        failures,
+        client_errors,
+        success_rate: (successes + failures > 0).then(|| successes as f64 / (successes + failures) as f64),
+        p50_ms: percentile(&durations, 0.5),
+        p95_ms: percentile(&durations, 0.95),
+        p99_ms: percentile(&durations, 0.99),
+        first_byte_p50_ms: percentile(&first_bytes, 0.5),
+        requests_per_min: window.len() as f64 * 60_000.0 / span_ms as f64,
+    }
+}
+
+/// 模型统计表
+pub fn format_table(stats: &[ModelStats]) -> String {
+    let width = stats.iter().map(|s| s.model.len()).max().unwrap_or(0).max(5);
+    let mut out = format!(
+        "{:<width$}  {:>8}  {:>8}  {:>8}  {:>8}  {:>8}  {:>8}  {:>7}\n",
+        "MODEL", "REQUESTS", "SUCCESS", "P50", "P95", "P99", "TTFB", "REQ/MIN"
+    );
+    for s in stats {
+        let rate = s
+            .success_rate
+            .map(|r| format!("{:.1}%", r * 100.0))
+            .unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "{:<width$}  {:>8}  {:>8}  {:>6}ms  {:>6}ms  {:>6}ms  {:>6}ms  {:>7.1}\n",
+            s.model, s.requests, rate, s.p50_ms, s.p95_ms, s.p99_ms, s.first_byte_p50_ms, s.requests_per_min
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    #[test]
+    fn success_rate_ignores_client_errors() {
+        let board = ModelScoreboard::new();
+        for status in [200, 200, 200, 503, 400, 429] {
+            board.record_at("gemini-2.5-pro", 100, 1_000, status, NOW);
+        }
+        let stats = &board.snapshot_at(NOW)[0];
+        assert_eq!(stats.requests, 6);
+        assert_eq!((stats.successes, stats.failures, stats.client_errors), (3, 2, 1));
+        assert_eq!(stats.success_rate, Some(0.6));
+    }
+
+    #[test]
+    fn latency_percentiles_use_successful_requests() {
+        let board = ModelScoreboard::new();
+        for ms in 1..=100 {
+            board.record_at("gemini-2.5-flash", ms, ms * 10, 200, NOW);
+        }
+        board.record_at("gemini-2.5-flash", 1, 5, 500, NOW);
+        let stats = &board.snapshot_at(NOW)[0];
+        assert_eq!(stats.p50_ms, 510);
+        assert_eq!(stats.p95_ms, 950);
+        assert_eq!(stats.p99_ms, 990);
+        assert_eq!(stats.first_byte_p50_ms, 51);
+    }
+
+    #[test]
+    fn expires_old_samples_and_orders_by_volume() {
+        let board = ModelScoreboard::new();
+        board.record_at("old-model", 10, 10, 200, NOW - (WINDOW_SECS + 1) * 1000);
+        board.record_at("claude-sonnet-4-5", 10, 10, 200, NOW - 120_000);
+        board.record_at("gemini-2.5-pro", 10, 10, 200, NOW - 120_000);
+        board.record_at("gemini-2.5-pro", 10, 10, 200, NOW);
+        let stats = board.snapshot_at(NOW);
+        let models: Vec<&str> = stats.iter().map(|s| s.model.as_str()).collect();
+        assert_eq!(models, ["gemini-2.5-pro", "claude-sonnet-4-5"]);
+        assert_eq!(stats[0].requests_per_min, 1.0);
+        assert_eq!(stats[1].requests_per_min, 0.5);
+    }
+
+    #[test]
+    fn table_has_header_and_row_per_model() {
+        let board = ModelScoreboard::new();
+        board.record_at("gemini-2.5-pro", 120, 900, 200, NOW);
+        let table = format_table(&board.snapshot_at(NOW));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("MODEL"));
+        assert!(lines[1].starts_with("gemini-2.5-pro"));
+        assert!(lines[1].contains("100.0%"));
+    }
+}
@@ -3,6 +3,10 @@ use std::collections::VecDeque;
 use tokio::sync::RwLock;
 use tauri::Emitter;
 use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::broadcast;
+
+/// 实时日志广播缓冲 (订阅者落后超过该数量时丢弃旧条目)
+const LIVE_CHANNEL_CAPACITY: usize = 256;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyRequestLog {
@@ -18,6 +22,35 @@ pub struct ProxyRequestLog {
     pub response_body: Option<String>,
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
+    /// 会话 (对话) 标识，用于按对话归组统计
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// 实际服务该请求的账号 (邮箱)
+    #[serde(default)]
+    pub account: Option<String>,
+    /// 客户端所用 API Key 的指纹 (不记录密钥本身)
+    #[serde(default)]
+    pub key_id: Option<String>,
+    /// 客户端地址 (受信任代理之后为转发的真实地址)
+    #[serde(default)]
+    pub client_ip: Option<String>,
+    /// 加权映射规则 (金丝雀) 选中的目标模型
+    #[serde(default)]
+    pub canary_target: Option<String>,
+}
+
+/// 单个会话的请求与 Token 汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub requests: u64,
+    pub error_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// 首次/最近请求时间 (毫秒)
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub models: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -33,6 +66,8 @@ pub struct ProxyMonitor {
     pub max_logs: usize,
     pub enabled: AtomicBool,
     app_handle: Option<tauri::AppHandle>,
+    live_tx: broadcast::Sender<ProxyRequestLog>,
+    body_capture: std::sync::RwLock<BodyCapture>,
 }
 
 impl ProxyMonitor {
@@ -48,6 +83,8 @@ impl ProxyMonitor {
             max_logs,
             enabled: AtomicBool::new(false), // Default to disabled
             app_handle,
+            live_tx: broadcast::channel(LIVE_CHANNEL_CAPACITY).0,
+            body_capture: std::sync::RwLock::new(BodyCapture::default()),
         }
     }
 
@@ -59,10 +96,29 @@ impl ProxyMonitor {
         self.enabled.load(Ordering::Relaxed)
     }
 
-    pub async fn log_request(&self, log: ProxyRequestLog) {
+    pub fn set_body_capture(&self, config: &crate::proxy::config::BodyCaptureConfig) {
+        *self.body_capture.write().unwrap_or_else(|e| e.into_inner()) = BodyCapture::from_config(config);
+    }
+
+    /// 订阅实时请求日志
+    pub fn subscribe(&self) -> broadcast::Receiver<ProxyRequestLog> {
+        self.live_tx.subscribe()
+    }
+
+    pub async fn log_request(&self, mut log: ProxyRequestLog) {
         if !self.is_enabled() {
             return;
         }
+        // 未被采样的请求只保留元数据 (错误信息仍保留，便于排查)
+        let keep_body = {
+            use rand::Rng;
+            let capture = self.body_capture.read().unwrap_or_else(|e| e.into_inner());
+            capture.keeps(&log, rand::thread_rng().gen_range(0.0..100.0))
+        };
+        if !keep_body {
+            log.request_body = None;
+            log.response_body = None;
+        }
         tracing::info!("[Monitor] Logging request: {} {}", log.method, log.url);
         // Update stats
         {
@@ -90,14 +146,27 @@ impl ProxyMonitor {
             if let Err(e) = crate::modules::proxy_db::save_log(&log_to_save) {
                 tracing::error!("Failed to save proxy log to DB: {}", e);
             }
+            if let Err(e) = crate::modules::proxy_db::record_rollups(&log_to_save) {
+                tracing::error!("Failed to update stats rollups: {}", e);
+            }
         });
 
+        // 推送给实时订阅者 (无订阅者时忽略)
+        let _ = self.live_tx.send(log.clone());
+
         // Emit event
         if let Some(app) = &self.app_handle {
              let _ = app.emit("proxy://request", &log);
         }
     }
 
+    /// 向前端推送事件 (无头模式下忽略)
+    pub fn notify<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(app) = &self.app_handle {
+            let _ = app.emit(event, payload);
+        }
+    }
+
     pub async fn get_logs(&self, limit: usize) -> Vec<ProxyRequestLog> {
         // Try to get from DB first for true history
         match crate::modules::proxy_db::get_logs(limit) {
@@ -111,6 +180,38 @@ impl ProxyMonitor {
         }
     }
 
+    /// 按会话汇总 (数据库不可用时退回内存中的近期日志)
+    pub async fn get_sessions(&self, limit: usize) -> Vec<SessionSummary> {
+        match crate::modules::proxy_db::get_sessions(limit) {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                tracing::error!("Failed to get sessions from DB: {}", e);
+                let logs = self.logs.read().await;
+                let mut sessions = summarize_sessions(logs.iter());
+                sessions.truncate(limit);
+                sessions
+            }
+        }
+    }
+
+    pub async fn get_session_logs(&self, session_id: &str, limit: usize) -> Vec<ProxyRequestLog> {
+        match crate::modules::proxy_db::get_session_logs(session_id, limit) {
+            Ok(logs) => logs,
+            Err(e) => {
+                tracing::error!("Failed to get session logs from DB: {}", e);
+                let logs = self.logs.read().await;
+                let mut matched: Vec<_> = logs
+                    .iter()
+                    .filter(|l| l.session_id.as_deref() == Some(session_id))
+                    .cloned()
+                    .collect();
+                matched.sort_by_key(|l| l.timestamp);
+                matched.truncate(limit);
+                matched
+            }
+        }
+    }
+
     pub async fn get_stats(&self) -> ProxyStats {
         match crate::modules::proxy_db::get_stats() {
             Ok(stats) => stats,
@@ -131,4 +232,315 @@ impl ProxyMonitor {
             tracing::error!("Failed to clear logs in DB: {}", e);
         }
     }
-}
\ No newline at end of file
+}
+/// 状态码过滤条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusMatch {
+    Exact(u16),
+    /// `4xx` / `5xx` 等状态码类别
+    Class(u16),
+    /// `status>=400`
+    AtLeast(u16),
+}
+
+/// 日志过滤条件，由 `key=value` 表达式组成 (多个条件同时满足)
+/// 支持: `model=<子串>`、`status=429|5xx`、`status>=400`、`method=POST`、`path=<子串>`、`session=<ID>`、
+/// `key=<API Key 指纹前缀>`、`error=true`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    model: Option<String>,
+    status: Option<StatusMatch>,
+    method: Option<String>,
+    path: Option<String>,
+    session: Option<String>,
+    key: Option<String>,
+    errors_only: bool,
+}
+
+impl LogFilter {
+    pub fn parse<S: AsRef<str>>(exprs: &[S]) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for expr in exprs {
+            let expr = expr.as_ref().trim();
+            if expr.is_empty() {
+                continue;
+            }
+            let (key, value) = expr
+                .split_once('=')
+                .ok_or_else(|| format!("无效的过滤条件 (应为 key=value): {}", expr))?;
+            let value = value.trim();
+            match key.trim() {
+                "model" => filter.model = Some(value.to_lowercase()),
+                "method" => filter.method = Some(value.to_uppercase()),
+                "path" | "url" => filter.path = Some(value.to_string()),
+                "session" => filter.session = Some(value.to_string()),
+                "key" => filter.key = Some(value.to_lowercase()),
+                "status>" => {
+                    filter.status = Some(StatusMatch::AtLeast(
+                        value.parse().map_err(|_| format!("无效的状态码: {}", value))?,
+                    ));
+                }
+                "status" => {
+                    let lower = value.to_lowercase();
+                    filter.status = Some(match lower.strip_suffix("xx") {
+                        Some(class) => StatusMatch::Class(
+                            class.parse().map_err(|_| format!("无效的状态码: {}", value))?,
+                        ),
+                        None => StatusMatch::Exact(
+                            value.parse().map_err(|_| format!("无效的状态码: {}", value))?,
+                        ),
+                    });
+                }
+                "error" => filter.errors_only = matches!(value, "true" | "1" | "yes"),
+                other => return Err(format!("不支持的过滤字段: {}", other)),
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, log: &ProxyRequestLog) -> bool {
+        if let Some(model) = &self.model {
+            let hit = log
+                .model
+                .as_deref()
+                .is_some_and(|m| m.to_lowercase().contains(model.as_str()));
+            if !hit {
+                return false;
+            }
+        }
+        if let Some(method) = &self.method {
+            if !log.method.eq_ignore_ascii_case(method) {
+                return false;
+            }
+        }
+        if let Some(path) = &self.path {
+            if !log.url.contains(path.as_str()) {
+                return false;
+            }
+        }
+        if let Some(session) = &self.session {
+            if log.session_id.as_deref() != Some(session.as_str()) {
+                return false;
+            }
+        }
+        if let Some(key) = &self.key {
+            if !log.key_id.as_deref().is_some_and(|k| k.to_lowercase().starts_with(key.as_str())) {
+                return false;
+            }
+        }
+        match self.status {
+            Some(StatusMatch::Exact(code)) if log.status != code => return false,
+            Some(StatusMatch::Class(class)) if log.status / 100 != class => return false,
+            Some(StatusMatch::AtLeast(code)) if log.status < code => return false,
+            _ => {}
+        }
+        if self.errors_only && log.status < 400 && log.error.is_none() {
+            return false;
+        }
+        true
+    }
+}
+
+/// 请求/响应体采样: 命中任一规则的请求始终保留，其余按比例抽样
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyCapture {
+    percent: f64,
+    always: Vec<LogFilter>,
+}
+
+impl Default for BodyCapture {
+    fn default() -> Self {
+        Self { percent: 100.0, always: Vec::new() }
+    }
+}
+
+impl BodyCapture {
+    /// 无效的规则告警后忽略 (配置校验时已报告)
+    pub fn from_config(config: &crate::proxy::config::BodyCaptureConfig) -> Self {
+        let always = config
+            .always
+            .iter()
+            .filter_map(|rule| {
+                let exprs: Vec<&str> = rule.split_whitespace().collect();
+                LogFilter::parse(&exprs)
+                    .map_err(|e| tracing::warn!("忽略无效的请求体采样规则 {}: {}", rule, e))
+                    .ok()
+            })
+            .collect();
+        Self {
+            percent: config.percent.clamp(0.0, 100.0),
+            always,
+        }
+    }
+
+    /// `roll` 为 [0, 100) 内的随机数
+    fn keeps(&self, log: &ProxyRequestLog, roll: f64) -> bool {
+        roll < self.percent || self.always.iter().any(|rule| rule.matches(log))
+    }
+}
+
+/// 将日志按会话归组汇总，最近活跃的会话在前
+pub fn summarize_sessions<'a>(logs: impl IntoIterator<Item = &'a ProxyRequestLog>) -> Vec<SessionSummary> {
+    let mut sessions: std::collections::HashMap<&str, SessionSummary> = std::collections::HashMap::new();
+    for log in logs {
+        let Some(id) = log.session_id.as_deref() else {
+            continue;
+        };
+        let entry = sessions.entry(id).or_insert_with(|| SessionSummary {
+            session_id: id.to_string(),
+            requests: 0,
+            error_count: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            first_seen: log.timestamp,
+            last_seen: log.timestamp,
+            models: Vec::new(),
+        });
+        entry.requests += 1;
+        if log.status < 200 || log.status >= 400 {
+            entry.error_count += 1;
+        }
+        entry.input_tokens += log.input_tokens.unwrap_or(0) as u64;
+        entry.output_tokens += log.output_tokens.unwrap_or(0) as u64;
+        entry.first_seen = entry.first_seen.min(log.timestamp);
+        entry.last_seen = entry.last_seen.max(log.timestamp);
+        if let Some(model) = &log.model {
+            if !entry.models.contains(model) {
+                entry.models.push(model.clone());
+            }
+        }
+    }
+    let mut sessions: Vec<_> = sessions.into_values().collect();
+    sessions.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    sessions
+}
+
+/// 单行文本格式 (命令行 tail 输出)
+pub fn format_log_line(log: &ProxyRequestLog) -> String {
+    let time = chrono::DateTime::from_timestamp_millis(log.timestamp)
+        .map(|t| t.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| log.timestamp.to_string());
+    let mut line = format!(
+        "{} {} {} {} {}ms model={}",
+        time,
+        log.status,
+        log.method,
+        log.url,
+        log.duration,
+        log.model.as_deref().unwrap_or("-")
+    );
+    if log.input_tokens.is_some() || log.output_tokens.is_some() {
+        line.push_str(&format!(
+            " tokens={}/{}",
+            log.input_tokens.unwrap_or(0),
+            log.output_tokens.unwrap_or(0)
+        ));
+    }
+    if let Some(error) = &log.error {
+        line.push_str(&format!(" error={}", error));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(model: &str, status: u16) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "1".to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: "/v1/chat/completions".to_string(),
+            status,
+            duration: 10,
+            model: Some(model.to_string()),
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            session_id: None,
+            account: None,
+            key_id: None,
+            client_ip: None,
+            canary_target: None,
+        }
+    }
+
+    #[test]
+    fn filters_by_model_and_status_class() {
+        let filter = LogFilter::parse(&["model=gemini-2.0-pro", "status=5xx"]).unwrap();
+        assert!(filter.matches(&log("gemini-2.0-pro-exp", 503)));
+        assert!(!filter.matches(&log("gemini-2.0-pro", 200)));
+        assert!(!filter.matches(&log("claude-sonnet-4-5", 503)));
+    }
+
+    #[test]
+    fn filters_by_minimum_status_and_key() {
+        let filter = LogFilter::parse(&["status>=400", "key=3F2A"]).unwrap();
+        let mut entry = log("gemini-2.5-flash", 429);
+        assert!(!filter.matches(&entry));
+        entry.key_id = Some("3f2a9c".to_string());
+        assert!(filter.matches(&entry));
+        entry.status = 200;
+        assert!(!filter.matches(&entry));
+    }
+
+    #[test]
+    fn body_capture_samples_unless_a_rule_matches() {
+        let capture = BodyCapture::from_config(&crate::proxy::config::BodyCaptureConfig {
+            percent: 1.0,
+            always: vec!["status>=400".to_string(), "model=gemini-3-pro method=POST".to_string()],
+        });
+        assert!(capture.keeps(&log("gemini-2.5-flash", 200), 0.5));
+        assert!(!capture.keeps(&log("gemini-2.5-flash", 200), 50.0));
+        assert!(capture.keeps(&log("gemini-2.5-flash", 503), 50.0));
+        assert!(capture.keeps(&log("gemini-3-pro-high", 200), 50.0));
+        assert!(BodyCapture::default().keeps(&log("any", 200), 99.9));
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = LogFilter::parse::<&str>(&[]).unwrap();
+        assert!(filter.matches(&log("any", 200)));
+    }
+
+    #[test]
+    fn groups_logs_into_sessions() {
+        let entry = |session: &str, model: &str, status: u16, ts: i64, tokens: (u32, u32)| ProxyRequestLog {
+            timestamp: ts,
+            session_id: Some(session.to_string()),
+            input_tokens: Some(tokens.0),
+            output_tokens: Some(tokens.1),
+            ..log(model, status)
+        };
+        let logs = vec![
+            entry("a", "gemini-2.5-flash", 200, 10, (100, 20)),
+            entry("b", "claude-sonnet-4-5", 200, 20, (50, 5)),
+            entry("a", "gemini-2.5-pro", 429, 30, (120, 0)),
+            log("gemini-2.5-flash", 200),
+        ];
+
+        let sessions = summarize_sessions(&logs);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].session_id, "a");
+        assert_eq!(sessions[0].requests, 2);
+        assert_eq!(sessions[0].error_count, 1);
+        assert_eq!(sessions[0].input_tokens, 220);
+        assert_eq!(sessions[0].output_tokens, 20);
+        assert_eq!((sessions[0].first_seen, sessions[0].last_seen), (10, 30));
+        assert_eq!(sessions[0].models, vec!["gemini-2.5-flash", "gemini-2.5-pro"]);
+
+        let filter = LogFilter::parse(&["session=b"]).unwrap();
+        assert!(filter.matches(&logs[1]));
+        assert!(!filter.matches(&logs[0]));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(LogFilter::parse(&["foo=bar"]).is_err());
+        assert!(LogFilter::parse(&["status=abc"]).is_err());
+        assert!(LogFilter::parse(&["model"]).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+// 采样参数策略: 转发上游之前按 API Key / 模型对 temperature、top_p 与最大输出 token 数执行覆盖、截断或填充默认值，
+// 避免共享反代上的第三方客户端发送极端取值
+use serde_json::{json, Map, Value};
+
+use crate::proxy::config::{ParamLimit, ParamPolicyConfig, ParamPolicyRule};
+use crate::proxy::quota_threshold::wildcard_match;
+
+/// 请求体中采样参数的位置 (按接口区分)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    OpenAIChat,
+    OpenAIResponses,
+    Anthropic,
+    /// 参数位于 `generationConfig` 下
+    Gemini,
+}
+
+impl Dialect {
+    /// 仅处理 POST 的对话 / 补全接口
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "/v1/chat/completions" | "/v1/completions" => Some(Self::OpenAIChat),
+            "/v1/responses" => Some(Self::OpenAIResponses),
+            "/v1/messages" => Some(Self::Anthropic),
+            _ => {
+                let rest = path.strip_prefix("/v1beta/models/")?;
+                (rest.ends_with(":generateContent") || rest.ends_with(":streamGenerateContent"))
+                    .then_some(Self::Gemini)
+            }
+        }
+    }
+
+    /// (temperature, top_p, 最大输出 token 数) 的字段名；OpenAI 对话接口的两种写法都会截断，
+    /// 客户端均未指定时填充第一个
+    fn fields(&self) -> (&'static str, &'static str, &'static [&'static str]) {
+        match self {
+            Self::OpenAIChat => ("temperature", "top_p", &["max_tokens", "max_completion_tokens"]),
+            Self::OpenAIResponses => ("temperature", "top_p", &["max_output_tokens"]),
+            Self::Anthropic => ("temperature", "top_p", &["max_tokens"]),
+            Self::Gemini => ("temperature", "topP", &["maxOutputTokens"]),
+        }
+    }
+}
+
+/// 客户端请求的模型名: Gemini 接口取自路径，其余取自请求体的 `model`
+pub fn request_model(path: &str, body: &Value) -> Option<String> {
+    if let Some(rest) = path.strip_prefix("/v1beta/models/") {
+        return rest.split(':').next().map(str::to_string);
+    }
+    body.get("model").and_then(Value::as_str).map(str::to_string)
+}
+
+/// 第一条同时匹配模型与密钥名称的规则
+pub fn find_rule<'a>(
+    config: &'a ParamPolicyConfig,
+    model: Option<&str>,
+    key_name: Option<&str>,
+) -> Option<&'a ParamPolicyRule> {
+    config.rules.iter().find(|rule| {
+        let model_ok = rule.models.is_empty()
+            || model.is_some_and(|m| rule.models.iter().any(|p| wildcard_match(p, m)));
+        let key_ok = rule.keys.is_empty() || key_name.is_some_and(|k| rule.keys.iter().any(|n| n == k));
+        model_ok && key_ok
+    })
+}
+
+/// 按规则计算参数的新取值；无需调整时为 None
+fn limited(limit: &ParamLimit, current: Option<f64>) -> Option<f64> {
+    let target = match (limit.force, current) {
+        (Some(force), _) => Some(force),
+        (None, Some(value)) => {
+            let value = limit.min.map_or(value, |min| value.max(min));
+            Some(limit.max.map_or(value, |max| value.min(max)))
+        }
+        (None, None) => limit.default,
+    };
+    target.filter(|t| Some(*t) != current)
+}
+
+/// 按规则调整请求体，返回被调整的参数 (统一使用 `temperature` / `top_p` / `max_tokens` 命名)
+pub fn apply(rule: &ParamPolicyRule, dialect: Dialect, body: &mut Value) -> Vec<&'static str> {
+    let (temperature, top_p, max_fields) = dialect.fields();
+    let Some(root) = body.as_object_mut() else {
+        return Vec::new();
+    };
+    let params = if dialect == Dialect::Gemini {
+        let config = root
+            .entry("generationConfig")
+            .or_insert_with(|| Value::Object(Map::new()));
+        match config.as_object_mut() {
+            Some(config) => config,
+            None => return Vec::new(),
+        }
+    } else {
+        root
+    };
+
+    let mut adjusted = Vec::new();
+    for (field, name, limit) in [
+        (temperature, "temperature", &rule.temperature),
+        (top_p, "top_p", &rule.top_p),
+    ] {
+        let Some(limit) = limit else { continue };
+        if let Some(value) = limited(limit, params.get(field).and_then(Value::as_f64)) {
+            params.insert(field.to_string(), json!(value));
+            adjusted.push(name);
+        }
+    }
+
+    if let Some(ceiling) = rule.max_tokens {
+        let mut present = false;
+        let mut clamped = false;
+        for field in max_fields {
+            let Some(value) = params.get(*field).and_then(Value::as_f64) else { continue };
+            present = true;
+            if value > ceiling as f64 {
+                params.insert(field.to_string(), json!(ceiling));
+                clamped = true;
+            }
+        }
+        if !present {
+            params.insert(max_fields[0].to_string(), json!(ceiling));
+            clamped = true;
+        }
+        if clamped {
+            adjusted.push("max_tokens");
+        }
+    }
+
+    // 未做任何调整时不保留新建的空 generationConfig
+    if dialect == Dialect::Gemini && adjusted.is_empty() {
+        if let Some(root) = body.as_object_mut() {
+            if root.get("generationConfig").and_then(Value::as_object).is_some_and(Map::is_empty) {
+                root.remove("generationConfig");
+            }
+        }
+    }
+    adjusted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> ParamPolicyRule {
+        ParamPolicyRule {
+            temperature: Some(ParamLimit {
+                min: Some(0.0),
+                max: Some(1.0),
+                default: Some(0.7),
+                force: None,
+            }),
+            max_tokens: Some(4096),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clamps_extreme_values_and_fills_defaults() {
+        let mut body = json!({"model": "gpt-4o", "temperature": 2.0, "max_tokens": 100000});
+        assert_eq!(apply(&rule(), Dialect::OpenAIChat, &mut body), ["temperature", "max_tokens"]);
+        assert_eq!(body["temperature"], 1.0);
+        assert_eq!(body["max_tokens"], 4096);
+
+        let mut body = json!({"model": "claude-sonnet-4-5", "max_tokens": 1024});
+        assert_eq!(apply(&rule(), Dialect::Anthropic, &mut body), ["temperature"]);
+        assert_eq!(body["temperature"], 0.7);
+        assert_eq!(body["max_tokens"], 1024);
+    }
+
+    #[test]
+    fn override_wins_and_unchanged_values_are_not_reported() {
+        let rule = ParamPolicyRule {
+            top_p: Some(ParamLimit {
+                force: Some(0.9),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut body = json!({"top_p": 0.1});
+        assert_eq!(apply(&rule, Dialect::OpenAIResponses, &mut body), ["top_p"]);
+        assert_eq!(body["top_p"], 0.9);
+        assert!(apply(&rule, Dialect::OpenAIResponses, &mut body).is_empty());
+    }
+
+    #[test]
+    fn gemini_params_live_in_generation_config() {
+        let mut body = json!({"contents": [], "generationConfig": {"temperature": 1.8}});
+        assert_eq!(apply(&rule(), Dialect::Gemini, &mut body), ["temperature", "max_tokens"]);
+        assert_eq!(body["generationConfig"]["temperature"], 1.0);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 4096);
+
+        let untouched = ParamPolicyRule::default();
+        let mut body = json!({"contents": []});
+        assert!(apply(&untouched, Dialect::Gemini, &mut body).is_empty());
+        assert!(body.get("generationConfig").is_none());
+    }
+
+    #[test]
+    fn first_rule_matching_model_and_key_applies() {
+        let config = ParamPolicyConfig {
+            rules: vec![
+                ParamPolicyRule {
+                    keys: vec!["partner".to_string()],
+                    max_tokens: Some(1024),
+                    ..Default::default()
+                },
+                ParamPolicyRule {
+                    models: vec!["gemini-*".to_string()],
+                    max_tokens: Some(8192),
+                    ..Default::default()
+                },
+            ],
+        };
+        let pick = |model, key| find_rule(&config, model, key).and_then(|r| r.max_tokens);
+        assert_eq!(pick(Some("gemini-2.5-pro"), Some("partner")), Some(1024));
+        assert_eq!(pick(Some("Gemini-2.5-Flash"), Some("default")), Some(8192));
+        assert_eq!(pick(Some("claude-sonnet-4-5"), None), None);
+    }
+
+    #[test]
+    fn model_comes_from_path_for_gemini() {
+        assert_eq!(
+            request_model("/v1beta/models/gemini-2.5-pro:streamGenerateContent", &json!({})).as_deref(),
+            Some("gemini-2.5-pro")
+        );
+        assert_eq!(request_model("/v1/messages", &json!({"model": "claude"})).as_deref(), Some("claude"));
+        assert_eq!(Dialect::from_path("/v1/messages/count_tokens"), None);
+    }
+}
@@ -0,0 +1,112 @@
+// 账号池配额汇总：模型列表据此隐藏已无剩余配额的模型，并附带池内剩余配额
+use serde::Serialize;
+
+use crate::models::quota::ModelQuota;
+
+/// 单个模型在账号池中的剩余配额
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolQuota {
+    /// 各账号剩余百分比的平均值 (已过重置时间的按 100 计)
+    pub remaining_percent: u32,
+    /// 仍有配额的账号数
+    pub available_accounts: usize,
+    /// 报告了该模型配额的账号数
+    pub accounts: usize,
+    /// 全部耗尽时最早的重置时间 (Unix 秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_reset_at: Option<i64>,
+}
+
+impl PoolQuota {
+    pub fn is_available(&self) -> bool {
+        self.available_accounts > 0
+    }
+}
+
+/// 汇总各账号报告的同一模型配额
+pub fn summarize(quotas: &[ModelQuota], now: i64) -> Option<PoolQuota> {
+    if quotas.is_empty() {
+        return None;
+    }
+    let mut total = 0u64;
+    let mut available_accounts = 0;
+    let mut next_reset_at: Option<i64> = None;
+    for quota in quotas {
+        // 耗尽但已过重置时间的配额视为已恢复 (快照尚未刷新)
+        let reset_passed = quota.reset_timestamp().is_some_and(|ts| ts <= now);
+        let percent = if quota.is_exhausted() && reset_passed {
+            100
+        } else {
+            quota.percentage.clamp(0, 100) as u64
+        };
+        total += percent;
+        if percent > 0 {
+            available_accounts += 1;
+        } else if let Some(ts) = quota.reset_timestamp() {
+            next_reset_at = Some(next_reset_at.map_or(ts, |current| current.min(ts)));
+        }
+    }
+    Some(PoolQuota {
+        remaining_percent: (total / quotas.len() as u64) as u32,
+        available_accounts,
+        accounts: quotas.len(),
+        next_reset_at: next_reset_at.filter(|_| available_accounts == 0),
+    })
+}
+
+/// 列表中所有有配额数据的模型的平均剩余百分比
+pub fn overall_percent<'a>(quotas: impl IntoIterator<Item = &'a PoolQuota>) -> Option<u32> {
+    let (sum, count) = quotas
+        .into_iter()
+        .fold((0u64, 0u64), |(sum, count), q| (sum + q.remaining_percent as u64, count + 1));
+    (count > 0).then(|| (sum / count) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(percentage: i32, reset_at: Option<i64>) -> ModelQuota {
+        ModelQuota {
+            name: "gemini-2.5-pro".to_string(),
+            percentage,
+            reset_time: String::new(),
+            reset_at,
+            limits: None,
+        }
+    }
+
+    #[test]
+    fn averages_remaining_quota() {
+        let summary = summarize(&[quota(80, None), quota(0, Some(2000)), quota(40, None)], 1000).unwrap();
+        assert_eq!(summary.remaining_percent, 40);
+        assert_eq!(summary.available_accounts, 2);
+        assert_eq!(summary.accounts, 3);
+        assert_eq!(summary.next_reset_at, None);
+        assert!(summary.is_available());
+    }
+
+    #[test]
+    fn exhausted_pool_reports_earliest_reset() {
+        let summary = summarize(&[quota(0, Some(3000)), quota(0, Some(2000)), quota(0, None)], 1000).unwrap();
+        assert!(!summary.is_available());
+        assert_eq!(summary.remaining_percent, 0);
+        assert_eq!(summary.next_reset_at, Some(2000));
+    }
+
+    #[test]
+    fn passed_reset_counts_as_restored() {
+        let summary = summarize(&[quota(0, Some(500))], 1000).unwrap();
+        assert!(summary.is_available());
+        assert_eq!(summary.remaining_percent, 100);
+        assert!(summarize(&[], 1000).is_none());
+    }
+
+    #[test]
+    fn overall_percent_ignores_unknown_models() {
+        let a = summarize(&[quota(100, None)], 0).unwrap();
+        let b = summarize(&[quota(20, None)], 0).unwrap();
+        assert_eq!(overall_percent([&a, &b]), Some(60));
+        assert_eq!(overall_percent(std::iter::empty()), None);
+    }
+}
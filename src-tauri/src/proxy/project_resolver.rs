@@ -16,8 +16,8 @@ pub async fn fetch_project_id(access_token: &str) -> Result<String, String> {
         .post(url)
         .bearer_auth(access_token)
         .header("Host", "cloudcode-pa.googleapis.com")
-        .header("User-Agent", "antigravity/1.11.9 windows/amd64")
         .header("Content-Type", "application/json")
+        .headers(crate::modules::device::headers_for(access_token))
         .json(&request_body)
         .send()
         .await
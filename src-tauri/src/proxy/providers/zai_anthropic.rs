@@ -6,7 +6,6 @@ use axum::{
 use bytes::Bytes;
 use futures::StreamExt;
 use serde_json::Value;
-use tokio::time::Duration;
 
 use crate::proxy::server::AppState;
 
@@ -50,18 +49,7 @@ fn build_client(
     upstream_proxy: Option<crate::proxy::config::UpstreamProxyConfig>,
     timeout_secs: u64,
 ) -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs.max(5)));
-
-    if let Some(config) = upstream_proxy {
-        if config.enabled && !config.url.is_empty() {
-            let proxy = reqwest::Proxy::all(&config.url)
-                .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-            builder = builder.proxy(proxy);
-        }
-    }
-
-    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+    crate::proxy::upstream::pool::global().get("zai", upstream_proxy.as_ref(), timeout_secs)
 }
 
 fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
@@ -170,6 +158,9 @@ pub async fn forward_anthropic_json(
     // This prevents "Extra inputs are not permitted" errors
     deep_remove_cache_control(&mut body);
 
+    if let Err(e) = crate::proxy::upstream::rate_limit::global().acquire(&url).await {
+        return (StatusCode::SERVICE_UNAVAILABLE, e).into_response();
+    }
     let req = client.request(method, &url).headers(headers).json(&body);
 
     let resp = match req.send().await {
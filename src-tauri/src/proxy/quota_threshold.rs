@@ -0,0 +1,187 @@
+// 账号配额保留阈值: 剩余配额低于阈值的账号在配额重置前退出轮换
+use crate::models::quota::ModelQuota;
+use crate::proxy::config::QuotaThresholdConfig;
+
+/// 简单通配匹配，`*` 匹配任意长度字符 (不区分大小写)
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let text = text.to_ascii_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let mut rest = text.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+/// 判断账号对指定模型是否处于保留状态
+///
+/// 返回 `Some(reset_at)` 表示账号应暂停参与轮换，`reset_at` 为配额重置时间
+/// (无法解析时为 `None`，此时保留到下一次配额刷新)；返回 `None` 表示可用。
+pub fn reserved_until(
+    config: &QuotaThresholdConfig,
+    quotas: &[ModelQuota],
+    model: &str,
+    now: i64,
+) -> Option<Option<i64>> {
+    if !config.enabled {
+        return None;
+    }
+    let rule = config
+        .rules
+        .iter()
+        .find(|rule| wildcard_match(&rule.model, model))?;
+    let quota = quotas
+        .iter()
+        .find(|q| q.name.eq_ignore_ascii_case(model))?;
+    if quota.percentage >= rule.min_percentage {
+        return None;
+    }
+
+    match quota.reset_timestamp() {
+        // 已过重置时间，配额数据只是尚未刷新，重新放行
+        Some(reset_at) if reset_at <= now => None,
+        reset_at => Some(reset_at),
+    }
+}
+
+/// 判断账号对指定模型的配额是否已耗尽且尚未重置
+///
+/// 返回 `Some(reset_at)` 表示在 `reset_at` 之前跳过该账号；重置时间一到即自动放行，
+/// 无需等待下一次手动刷新配额。重置时间未知时交由限流跟踪器处理上游 429。
+pub fn exhausted_until(quotas: &[ModelQuota], model: &str, now: i64) -> Option<i64> {
+    quotas
+        .iter()
+        .find(|q| q.name.eq_ignore_ascii_case(model) && q.is_exhausted())
+        .and_then(|q| q.reset_timestamp())
+        .filter(|reset_at| *reset_at > now)
+}
+
+/// 账号对指定模型暂不可用的截止时间: 配额耗尽或低于保留阈值
+pub fn unavailable_until(
+    config: &QuotaThresholdConfig,
+    quotas: &[ModelQuota],
+    model: &str,
+    now: i64,
+) -> Option<Option<i64>> {
+    exhausted_until(quotas, model, now)
+        .map(Some)
+        .or_else(|| reserved_until(config, quotas, model, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::quota::parse_reset_time;
+    use crate::proxy::config::QuotaThresholdRule;
+
+    fn config(model: &str, min_percentage: i32) -> QuotaThresholdConfig {
+        QuotaThresholdConfig {
+            enabled: true,
+            rules: vec![QuotaThresholdRule {
+                model: model.to_string(),
+                min_percentage,
+            }],
+        }
+    }
+
+    fn quota(name: &str, percentage: i32, reset_time: &str) -> ModelQuota {
+        ModelQuota {
+            name: name.to_string(),
+            percentage,
+            reset_time: reset_time.to_string(),
+            reset_at: None,
+            limits: None,
+        }
+    }
+
+    #[test]
+    fn wildcard_patterns() {
+        assert!(wildcard_match("*", "gemini-3-pro-high"));
+        assert!(wildcard_match("claude-*", "claude-sonnet-4-5"));
+        assert!(wildcard_match("*-thinking", "claude-opus-4-5-thinking"));
+        assert!(wildcard_match("gemini-*-pro*", "gemini-3-pro-high"));
+        assert!(wildcard_match("Claude-Sonnet-4-5", "claude-sonnet-4-5"));
+        assert!(!wildcard_match("claude-*", "gemini-3-pro-high"));
+        assert!(!wildcard_match("gemini-3-pro", "gemini-3-pro-high"));
+    }
+
+    #[test]
+    fn below_threshold_is_reserved_until_reset() {
+        let now = parse_reset_time("2026-01-01T00:00:00Z").unwrap();
+        let quotas = vec![quota("claude-sonnet-4-5", 15, "2026-01-01T05:00:00Z")];
+        let cfg = config("claude-*", 20);
+
+        assert_eq!(
+            reserved_until(&cfg, &quotas, "claude-sonnet-4-5", now),
+            Some(Some(now + 5 * 3600))
+        );
+        // 重置时间已过，重新放行
+        assert_eq!(
+            reserved_until(&cfg, &quotas, "claude-sonnet-4-5", now + 5 * 3600),
+            None
+        );
+    }
+
+    #[test]
+    fn above_threshold_or_unmatched_is_available() {
+        let quotas = vec![
+            quota("claude-sonnet-4-5", 40, "2026-01-01T05:00:00Z"),
+            quota("gemini-3-pro-high", 5, ""),
+        ];
+        let cfg = config("claude-*", 20);
+        assert_eq!(reserved_until(&cfg, &quotas, "claude-sonnet-4-5", 0), None);
+        assert_eq!(reserved_until(&cfg, &quotas, "gemini-3-pro-high", 0), None);
+
+        let disabled = QuotaThresholdConfig {
+            enabled: false,
+            ..config("*", 100)
+        };
+        assert_eq!(reserved_until(&disabled, &quotas, "claude-sonnet-4-5", 0), None);
+    }
+
+    #[test]
+    fn unknown_reset_time_stays_reserved() {
+        let quotas = vec![quota("gemini-3-pro-high", 5, "")];
+        let cfg = config("gemini-*", 10);
+        assert_eq!(reserved_until(&cfg, &quotas, "gemini-3-pro-high", 0), Some(None));
+    }
+
+    #[test]
+    fn exhausted_account_is_readmitted_at_reset() {
+        let now = parse_reset_time("2026-01-01T00:00:00Z").unwrap();
+        let quotas = vec![quota("claude-sonnet-4-5", 0, "2026-01-01T00:30:00Z")];
+        let cfg = QuotaThresholdConfig::default();
+
+        assert_eq!(
+            unavailable_until(&cfg, &quotas, "claude-sonnet-4-5", now),
+            Some(Some(now + 1800))
+        );
+        assert_eq!(
+            unavailable_until(&cfg, &quotas, "claude-sonnet-4-5", now + 1800),
+            None
+        );
+        // 重置时间未知时不主动跳过
+        let unknown = vec![quota("claude-sonnet-4-5", 0, "")];
+        assert_eq!(unavailable_until(&cfg, &unknown, "claude-sonnet-4-5", now), None);
+    }
+}
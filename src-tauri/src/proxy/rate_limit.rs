@@ -66,6 +66,19 @@ impl RateLimitTracker {
         status: u16,
         retry_after_header: Option<&str>,
         body: &str,
+    ) -> Option<RateLimitInfo> {
+        self.parse_from_error_with_reset(account_id, status, retry_after_header, body, None)
+    }
+
+    /// 同 `parse_from_error`，`quota_reset_at` 为已知的配额重置时间 (Unix 秒)，
+    /// 配额耗尽且上游未给出重试时间时，锁定到该时间而不是默认的 1 小时
+    pub fn parse_from_error_with_reset(
+        &self,
+        account_id: &str,
+        status: u16,
+        retry_after_header: Option<&str>,
+        body: &str,
+        quota_reset_at: Option<i64>,
     ) -> Option<RateLimitInfo> {
         // 支持 429 (限流) 以及 500/503/529 (后端故障软避让)
         if status != 429 && status != 500 && status != 503 && status != 529 {
@@ -102,9 +115,16 @@ impl RateLimitTracker {
             None => {
                 match reason {
                     RateLimitReason::QuotaExhausted => {
-                        // 配额耗尽：使用较长的默认值（1小时），避免频繁重试
-                        tracing::warn!("检测到配额耗尽 (QUOTA_EXHAUSTED)，使用默认值 3600秒 (1小时)");
-                        3600
+                        // 配额耗尽：优先锁定到已知的配额重置时间
+                        let now = chrono::Utc::now().timestamp();
+                        if let Some(reset_at) = quota_reset_at.filter(|ts| *ts > now) {
+                            tracing::warn!("检测到配额耗尽 (QUOTA_EXHAUSTED)，锁定至配额重置 ({}秒后)", reset_at - now);
+                            (reset_at - now) as u64
+                        } else {
+                            // 重置时间未知：使用较长的默认值（1小时），避免频繁重试
+                            tracing::warn!("检测到配额耗尽 (QUOTA_EXHAUSTED)，使用默认值 3600秒 (1小时)");
+                            3600
+                        }
                     },
                     RateLimitReason::RateLimitExceeded => {
                         // 速率限制：使用较短的默认值（30秒），可以较快恢复
@@ -304,6 +324,24 @@ impl RateLimitTracker {
         None
     }
     
+    /// 锁定账号直到指定时间 (其他实例同步的冷却)；已有更晚的锁定时保持不变
+    pub fn lock_until(&self, account_id: &str, until: SystemTime) {
+        let now = SystemTime::now();
+        if until <= now || self.limits.get(account_id).map_or(false, |l| l.reset_time >= until) {
+            return;
+        }
+        let retry_after_sec = until.duration_since(now).unwrap_or_default().as_secs();
+        self.limits.insert(
+            account_id.to_string(),
+            RateLimitInfo {
+                reset_time: until,
+                retry_after_sec,
+                detected_at: now,
+                reason: RateLimitReason::Unknown,
+            },
+        );
+    }
+
     /// 获取账号的限流信息
     pub fn get(&self, account_id: &str) -> Option<RateLimitInfo> {
         self.limits.get(account_id).map(|r| r.clone())
@@ -415,6 +453,20 @@ mod tests {
         assert!(wait > 25 && wait <= 30);
     }
 
+    #[test]
+    fn test_quota_exhausted_uses_known_reset_time() {
+        let tracker = RateLimitTracker::new();
+        let body = r#"{"error":{"details":[{"reason":"QUOTA_EXHAUSTED"}]}}"#;
+        let reset_at = chrono::Utc::now().timestamp() + 600;
+        tracker.parse_from_error_with_reset("acc1", 429, None, body, Some(reset_at));
+        let wait = tracker.get_remaining_wait("acc1");
+        assert!(wait > 590 && wait <= 600);
+
+        // 重置时间已过时回退到默认值
+        tracker.parse_from_error_with_reset("acc2", 429, None, body, Some(reset_at - 1200));
+        assert!(tracker.get_remaining_wait("acc2") > 3500);
+    }
+
     #[test]
     fn test_safety_buffer() {
         let tracker = RateLimitTracker::new();
@@ -0,0 +1,162 @@
+// 请求重放: 用已捕获的请求重新走一遍当前转换管线，并与原始响应对比
+use axum::body::Body;
+use axum::http::{header, Method, Request};
+use serde::Serialize;
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 重放结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayResult {
+    pub id: String,
+    pub method: String,
+    pub url: String,
+    /// 指定执行的账号 (未指定时按正常调度选择)
+    pub account: Option<String>,
+    pub original_status: u16,
+    pub status: u16,
+    pub duration: u64,
+    /// 响应体是否与原始响应一致 (JSON 按格式化后比较)
+    pub identical: bool,
+    /// 行级差异，`-` 为原始响应，`+` 为本次响应
+    pub diff: Vec<String>,
+    pub response_body: String,
+}
+
+/// 根据日志构建重放请求
+pub fn build_request(log: &ProxyRequestLog) -> Result<Request<Body>, String> {
+    let method: Method = log
+        .method
+        .parse()
+        .map_err(|_| format!("无效的请求方法: {}", log.method))?;
+    let body = match (&method, log.request_body.as_deref()) {
+        (&Method::POST, Some("[Binary Request Data]")) => {
+            return Err("原始请求体为二进制数据，无法重放".to_string())
+        }
+        (&Method::POST, Some(body)) => body.to_string(),
+        (&Method::POST, None) => return Err("该日志未记录请求体，无法重放".to_string()),
+        _ => String::new(),
+    };
+
+    Request::builder()
+        .method(method)
+        .uri(&log.url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .map_err(|e| format!("构建重放请求失败: {}", e))
+}
+
+/// 格式化响应体用于比较: JSON 统一为缩进格式，其余按原文
+pub fn normalize_body(body: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|_| body.to_string()),
+        Err(_) => body.trim_end().to_string(),
+    }
+}
+
+/// LCS 表的最大单元数，超出时不再逐行对齐
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// 行级差异 (基于最长公共子序列)，仅输出变化的行
+pub fn diff_lines(old: &str, new: &str) -> Vec<String> {
+    let mut a: Vec<&str> = old.lines().collect();
+    let mut b: Vec<&str> = new.lines().collect();
+
+    // 去掉公共前后缀，缩小比较范围
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    a.drain(..prefix);
+    b.drain(..prefix);
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    a.truncate(a.len() - suffix);
+    b.truncate(b.len() - suffix);
+    let (n, m) = (a.len(), b.len());
+
+    if (n + 1).saturating_mul(m + 1) > MAX_DIFF_CELLS {
+        let mut out: Vec<String> = a.iter().map(|l| format!("-{}", l)).collect();
+        out.extend(b.iter().map(|l| format!("+{}", l)));
+        return out;
+    }
+
+    // lcs[i][j] = a[i..] 与 b[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(format!("-{}", a[i]));
+            i += 1;
+        } else {
+            out.push(format!("+{}", b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| format!("-{}", l)));
+    out.extend(b[j..].iter().map(|l| format!("+{}", l)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(method: &str, body: Option<&str>) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "log-1".to_string(),
+            timestamp: 0,
+            method: method.to_string(),
+            url: "/v1/messages?beta=true".to_string(),
+            status: 200,
+            duration: 0,
+            model: None,
+            error: None,
+            request_body: body.map(|s| s.to_string()),
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            session_id: None,
+            account: None,
+            key_id: None,
+            client_ip: None,
+            canary_target: None,
+        }
+    }
+
+    #[test]
+    fn diff_reports_only_changed_lines() {
+        let old = "a\nb\nc\nd";
+        let new = "a\nx\nc\nd\ne";
+        assert_eq!(diff_lines(old, new), vec!["-b", "+x", "+e"]);
+        assert!(diff_lines(old, old).is_empty());
+    }
+
+    #[test]
+    fn json_bodies_compare_by_structure() {
+        let old = normalize_body(r#"{"a":1,"b":[1,2]}"#);
+        let new = normalize_body("{\n  \"a\": 1,\n  \"b\": [1, 2]\n}");
+        assert!(diff_lines(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn build_request_requires_captured_body() {
+        let req = build_request(&log("POST", Some(r#"{"model":"x"}"#))).unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), "/v1/messages?beta=true");
+
+        assert!(build_request(&log("POST", None)).is_err());
+        assert!(build_request(&log("POST", Some("[Binary Request Data]"))).is_err());
+        assert!(build_request(&log("GET", None)).is_ok());
+    }
+}
@@ -0,0 +1,218 @@
+// 按时段路由: 按配置的时区在选号时判断当前星期与时段，限定可用的账号 (如工作日白天只用免费账号，
+// 夜间为批量任务开放 Pro 账号池)
+use chrono::{DateTime, Datelike, NaiveDateTime, Timelike, Utc, Weekday};
+
+use crate::models::SubscriptionTier;
+use crate::proxy::config::{ScheduleRoutingConfig, ScheduleRule};
+use crate::proxy::quota_threshold::wildcard_match;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// 解析时区；为空或 `local` 时为 None (使用系统本地时区)
+pub fn parse_timezone(value: &str) -> Result<Option<chrono_tz::Tz>, String> {
+    let value = value.trim();
+    if value.is_empty() || value.eq_ignore_ascii_case("local") {
+        return Ok(None);
+    }
+    value
+        .parse::<chrono_tz::Tz>()
+        .map(Some)
+        .map_err(|_| format!("未知的时区: {}", value))
+}
+
+/// 解析星期: `mon` ... `sun` (亦可写全称)，`weekdays` / `weekends`
+pub fn parse_days(value: &str) -> Result<Vec<Weekday>, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "weekdays" => Ok(vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]),
+        "weekends" => Ok(vec![Weekday::Sat, Weekday::Sun]),
+        other => other
+            .parse::<Weekday>()
+            .map(|d| vec![d])
+            .map_err(|_| format!("无效的星期: {}", value)),
+    }
+}
+
+fn parse_clock(value: &str) -> Option<u32> {
+    let (h, m) = value.trim().split_once(':')?;
+    let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+    // 允许 24:00 作为结束时刻
+    (m < 60 && (h < 24 || (h == 24 && m == 0))).then_some(h * 60 + m)
+}
+
+/// 解析时段 `HH:MM-HH:MM`，返回 (开始, 结束) 的分钟数
+pub fn parse_hours(value: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("无效的时段: {} (格式应为 HH:MM-HH:MM)", value);
+    let (start, end) = value.split_once('-').ok_or_else(invalid)?;
+    let (start, end) = (parse_clock(start).ok_or_else(invalid)?, parse_clock(end).ok_or_else(invalid)?);
+    if start == end || start >= MINUTES_PER_DAY {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+/// 校验规则的星期与时段写法
+pub fn check_rule(rule: &ScheduleRule) -> Result<(), String> {
+    for day in &rule.days {
+        parse_days(day)?;
+    }
+    if let Some(hours) = &rule.hours {
+        parse_hours(hours)?;
+    }
+    Ok(())
+}
+
+/// 当前时刻在配置时区下的本地时间 (时区无效时退回系统本地时区)
+fn local_time(config: &ScheduleRoutingConfig, now: DateTime<Utc>) -> NaiveDateTime {
+    match parse_timezone(&config.timezone) {
+        Ok(Some(tz)) => now.with_timezone(&tz).naive_local(),
+        _ => now.with_timezone(&chrono::Local).naive_local(),
+    }
+}
+
+/// 规则在本地时间 `time` 是否生效 (写法无效的规则不生效)
+fn rule_active(rule: &ScheduleRule, time: NaiveDateTime) -> bool {
+    let Ok(days) = rule
+        .days
+        .iter()
+        .map(|d| parse_days(d))
+        .collect::<Result<Vec<_>, _>>()
+        .map(|d| d.concat())
+    else {
+        return false;
+    };
+    let day_ok = |day: Weekday| days.is_empty() || days.contains(&day);
+    let minute = time.hour() * 60 + time.minute();
+    let today = time.weekday();
+    match rule.hours.as_deref().map(parse_hours) {
+        None => day_ok(today),
+        Some(Err(_)) => false,
+        Some(Ok((start, end))) if start < end => day_ok(today) && (start..end).contains(&minute),
+        // 跨午夜: 午夜之后的部分属于前一天开始的时段
+        Some(Ok((start, end))) => {
+            (minute >= start && day_ok(today)) || (minute < end && day_ok(today.pred()))
+        }
+    }
+}
+
+/// 当前时刻对 `model` 生效的规则 (未启用或没有命中的规则时为 None)
+pub fn active_rule<'a>(
+    config: &'a ScheduleRoutingConfig,
+    model: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<&'a ScheduleRule> {
+    if !config.enabled {
+        return None;
+    }
+    let time = local_time(config, now);
+    config.rules.iter().find(|rule| {
+        let model_ok = rule.models.is_empty()
+            || model.is_some_and(|m| rule.models.iter().any(|p| wildcard_match(p, m)));
+        model_ok && rule_active(rule, time)
+    })
+}
+
+/// 账号在规则生效期间能否使用: 等级或账号 (ID / 邮箱 / 标签) 命中任意一项即可
+pub fn allows(
+    rule: &ScheduleRule,
+    tier: Option<&SubscriptionTier>,
+    account_id: &str,
+    email: &str,
+    tags: &[String],
+) -> bool {
+    tier.is_some_and(|t| rule.tiers.contains(t))
+        || rule.accounts.iter().any(|a| {
+            a == account_id || a.eq_ignore_ascii_case(email) || tags.iter().any(|t| t.eq_ignore_ascii_case(a))
+        })
+}
+
+/// 规则名称 (未命名时显示序号)
+pub fn rule_label(config: &ScheduleRoutingConfig, rule: &ScheduleRule) -> String {
+    if !rule.name.is_empty() {
+        return rule.name.clone();
+    }
+    let index = config.rules.iter().position(|r| std::ptr::eq(r, rule)).unwrap_or(0);
+    format!("#{}", index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn config() -> ScheduleRoutingConfig {
+        ScheduleRoutingConfig {
+            enabled: true,
+            timezone: "Asia/Shanghai".to_string(),
+            rules: vec![
+                ScheduleRule {
+                    name: "workday".to_string(),
+                    days: vec!["weekdays".to_string()],
+                    hours: Some("09:00-18:00".to_string()),
+                    tiers: vec![SubscriptionTier::Free],
+                    ..Default::default()
+                },
+                ScheduleRule {
+                    name: "friday-night".to_string(),
+                    days: vec!["fri".to_string()],
+                    hours: Some("22:00-06:00".to_string()),
+                    models: vec!["gemini-*".to_string()],
+                    accounts: vec!["batch".to_string()],
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    /// 上海时间 (UTC+8)
+    fn shanghai(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        chrono::FixedOffset::east_opt(8 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn workday_window_uses_configured_timezone() {
+        let config = config();
+        // 2025-01-06 为周一
+        let name = |now| active_rule(&config, Some("claude-sonnet-4-5"), now).map(|r| r.name.as_str());
+        assert_eq!(name(shanghai(2025, 1, 6, 9, 0)), Some("workday"));
+        assert_eq!(name(shanghai(2025, 1, 6, 17, 59)), Some("workday"));
+        assert_eq!(name(shanghai(2025, 1, 6, 18, 0)), None);
+        assert_eq!(name(shanghai(2025, 1, 11, 10, 0)), None);
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_start_day() {
+        let config = config();
+        let name = |now| active_rule(&config, Some("gemini-2.5-pro"), now).map(|r| r.name.as_str());
+        // 周五 23:00 与周六 05:59 命中，周六 23:00 不命中
+        assert_eq!(name(shanghai(2025, 1, 10, 23, 0)), Some("friday-night"));
+        assert_eq!(name(shanghai(2025, 1, 11, 5, 59)), Some("friday-night"));
+        assert_eq!(name(shanghai(2025, 1, 11, 23, 0)), None);
+        // 模型不匹配时跳过该规则
+        assert_eq!(active_rule(&config, Some("claude-opus-4-5"), shanghai(2025, 1, 10, 23, 0)), None);
+    }
+
+    #[test]
+    fn allows_matching_tier_or_account() {
+        let rule = &config().rules[1];
+        assert!(allows(rule, None, "id-1", "a@example.com", &["Batch".to_string()]));
+        assert!(!allows(rule, Some(&SubscriptionTier::Pro), "id-1", "a@example.com", &[]));
+        let workday = &config().rules[0];
+        assert!(allows(workday, Some(&SubscriptionTier::Free), "id-1", "a@example.com", &[]));
+        assert!(!allows(workday, None, "id-1", "a@example.com", &[]));
+    }
+
+    #[test]
+    fn rejects_invalid_syntax() {
+        assert!(parse_hours("9-18").is_err());
+        assert!(parse_hours("09:00-09:00").is_err());
+        assert_eq!(parse_hours("18:00-24:00"), Ok((1080, 1440)));
+        assert!(parse_days("weekday").is_err());
+        assert_eq!(parse_days("Saturday"), Ok(vec![Weekday::Sat]));
+        assert!(parse_timezone("Mars/Base").is_err());
+        assert_eq!(parse_timezone("local"), Ok(None));
+    }
+}
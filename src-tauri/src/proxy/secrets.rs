@@ -0,0 +1,319 @@
+// 密钥保护: API Key 生成与强度检查、哈希存储 (argon2)、常量时间比较与日志/输出中的密钥脱敏
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 脱敏后保留的前缀长度
+const VISIBLE_PREFIX: usize = 6;
+/// 生成的 API Key 前缀，便于在日志与密钥扫描中识别
+pub const API_KEY_PREFIX: &str = "agk-";
+/// 生成的 API Key 随机部分长度 (字母数字，约 238 位熵)
+const GENERATED_KEY_LEN: usize = 40;
+/// 用户自定义密钥的最小长度与估算熵
+const MIN_KEY_LEN: usize = 16;
+const MIN_KEY_ENTROPY_BITS: f64 = 64.0;
+
+/// 生成新的 API Key: `agk-` + 操作系统随机源产生的字母数字串
+pub fn generate_api_key() -> String {
+    use rand::distributions::{Alphanumeric, DistString};
+    format!("{}{}", API_KEY_PREFIX, Alphanumeric.sample_string(&mut rand::rngs::OsRng, GENERATED_KEY_LEN))
+}
+
+/// 估算密钥的熵 (位): 长度 × log2(所用字符类别的字符集大小)，重复字符按去重后的个数计
+fn estimated_entropy_bits(key: &str) -> f64 {
+    let mut charset = 0u32;
+    if key.chars().any(|c| c.is_ascii_lowercase()) {
+        charset += 26;
+    }
+    if key.chars().any(|c| c.is_ascii_uppercase()) {
+        charset += 26;
+    }
+    if key.chars().any(|c| c.is_ascii_digit()) {
+        charset += 10;
+    }
+    if key.chars().any(|c| !c.is_ascii_alphanumeric()) {
+        charset += 33;
+    }
+    let distinct = key.chars().collect::<std::collections::HashSet<_>>().len();
+    let length = key.chars().count().min(distinct * 2);
+    length as f64 * (charset.max(1) as f64).log2()
+}
+
+/// 检查用户自定义密钥的强度，过弱时返回原因
+pub fn weak_key_reason(key: &str) -> Option<String> {
+    let key = key.strip_prefix(API_KEY_PREFIX).or_else(|| key.strip_prefix("sk-")).unwrap_or(key);
+    let length = key.chars().count();
+    if length < MIN_KEY_LEN {
+        return Some(format!("长度过短 ({} 个字符，至少 {})", length, MIN_KEY_LEN));
+    }
+    let bits = estimated_entropy_bits(key);
+    if bits < MIN_KEY_ENTROPY_BITS {
+        return Some(format!("随机性不足 (估算 {:.0} 位熵，至少 {:.0} 位)", bits, MIN_KEY_ENTROPY_BITS));
+    }
+    None
+}
+
+/// 常量时间比较，耗时与两者在何处不同无关
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 拆分哈希存储的密钥: (查找标签, argon2 PHC 字符串)。
+/// 新生成的哈希为 `<密钥指纹>:$argon2…`，旧版本保存的哈希没有标签
+fn split_hash(value: &str) -> Option<(Option<&str>, &str)> {
+    if value.starts_with("$argon2") {
+        return Some((None, value));
+    }
+    let (tag, phc) = value.split_once(':')?;
+    phc.starts_with("$argon2").then_some((Some(tag), phc))
+}
+
+/// 是否为 argon2 哈希 (PHC 格式，可带查找标签)
+pub fn is_hashed(value: &str) -> bool {
+    split_hash(value).is_some()
+}
+
+/// 生成 API Key 的 argon2 哈希，前缀密钥指纹作为查找标签，
+/// 校验时只对指纹相符的候选执行 argon2
+pub fn hash_api_key(key: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(key.as_bytes(), &salt)
+        .map(|hash| format!("{}:{}", key_fingerprint(key), hash))
+        .map_err(|e| format!("API Key 哈希失败: {}", e))
+}
+
+/// 将配置中的明文 API Key (全局、宽限期内的旧密钥、附加密钥与各监听器) 替换为哈希；已哈希的保持不变
+pub fn hash_config_keys(config: &mut crate::proxy::config::ProxyConfig) -> Result<(), String> {
+    let keys = std::iter::once(&mut config.api_key)
+        .chain(config.previous_api_keys.iter_mut().map(|k| &mut k.key))
+        .chain(config.api_keys.iter_mut().map(|k| &mut k.key))
+        .chain(config.listeners.iter_mut().filter_map(|l| l.api_key.as_mut()));
+    for key in keys {
+        if !key.is_empty() && !is_hashed(key) {
+            *key = hash_api_key(key)?;
+        }
+    }
+    Ok(())
+}
+
+/// 已验证通过的 (哈希 -> 密钥摘要)，避免每个请求都执行 argon2
+static VERIFIED: Lazy<Mutex<HashMap<String, [u8; 32]>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+fn is_cached(expected: &str, provided_digest: &[u8; 32]) -> bool {
+    VERIFIED
+        .lock()
+        .map(|cache| cache.get(expected).is_some_and(|d| constant_time_eq(d, provided_digest)))
+        .unwrap_or(false)
+}
+
+/// 校验 `provided` 是否需要执行 argon2: 哈希存储、查找标签相符 (或旧版无标签) 且未命中缓存。
+/// 为 false 时 `verify_api_key` 只做摘要比较，可以直接在异步线程上调用
+pub fn needs_slow_verify(provided: &str, expected: &str) -> bool {
+    match split_hash(expected) {
+        None => false,
+        Some((Some(tag), _)) if !constant_time_eq(tag.as_bytes(), key_fingerprint(provided).as_bytes()) => false,
+        Some(_) => !is_cached(expected, &digest(provided)),
+    }
+}
+
+/// 校验客户端提供的密钥；`expected` 可为明文或 argon2 哈希。
+/// 明文按摘要做常量时间比较，不通过响应耗时泄露密钥前缀或长度；
+/// 哈希先比较查找标签，不相符的密钥不会执行 argon2
+pub fn verify_api_key(provided: &str, expected: &str) -> bool {
+    let provided_digest = digest(provided);
+    let Some((tag, phc)) = split_hash(expected) else {
+        return constant_time_eq(&provided_digest, &digest(expected));
+    };
+    if tag.is_some_and(|tag| !constant_time_eq(tag.as_bytes(), key_fingerprint(provided).as_bytes())) {
+        return false;
+    }
+    if is_cached(expected, &provided_digest) {
+        return true;
+    }
+
+    let Ok(parsed) = PasswordHash::new(phc) else {
+        tracing::error!("API Key 哈希格式无效，拒绝请求");
+        return false;
+    };
+    let ok = Argon2::default()
+        .verify_password(provided.as_bytes(), &parsed)
+        .is_ok();
+    if ok {
+        if let Ok(mut cache) = VERIFIED.lock() {
+            cache.insert(expected.to_string(), provided_digest);
+        }
+    }
+    ok
+}
+
+/// 密钥指纹 (SHA-256 前 8 位十六进制)，用于按密钥归组统计而不记录密钥本身
+pub fn key_fingerprint(key: &str) -> String {
+    digest(key)[..4].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 脱敏单个密钥: 仅保留前缀，如 `sk-a1b…`；哈希值显示为 `[hashed]`
+pub fn redact_secret(secret: &str) -> String {
+    if secret.is_empty() {
+        return String::new();
+    }
+    if is_hashed(secret) {
+        return "[hashed]".to_string();
+    }
+    let prefix: String = secret.chars().take(VISIBLE_PREFIX).collect();
+    if prefix.chars().count() >= secret.chars().count() {
+        return "***".to_string();
+    }
+    format!("{}…", prefix)
+}
+
+/// 文本中常见的密钥/令牌格式: 反代 API Key、Google access/refresh token、Bearer 头、URL 中的 key 参数
+static SECRET_PATTERNS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?x)
+        (?P<bearer>(?i:bearer)\s+)(?P<bearer_value>[A-Za-z0-9._~+/=-]{8,})
+        | (?P<param>[?&](?:key|api_key|access_token)=)(?P<param_value>[^&\s\x22']+)
+        | (?P<token>(?:sk|agk)-[A-Za-z0-9_-]{8,}|ya29\.[A-Za-z0-9._-]+|1//[A-Za-z0-9._-]{10,})
+        ",
+    )
+    .expect("invalid secret pattern")
+});
+
+/// 脱敏任意文本 (日志行等) 中出现的密钥
+pub fn redact_text(text: &str) -> std::borrow::Cow<'_, str> {
+    SECRET_PATTERNS.replace_all(text, |caps: &regex::Captures| {
+        if let (Some(prefix), Some(value)) = (caps.name("bearer"), caps.name("bearer_value")) {
+            format!("{}{}", prefix.as_str(), redact_secret(value.as_str()))
+        } else if let (Some(prefix), Some(value)) = (caps.name("param"), caps.name("param_value")) {
+            format!("{}{}", prefix.as_str(), redact_secret(value.as_str()))
+        } else {
+            redact_secret(&caps[0])
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashed_key_verifies_and_rejects() {
+        let hash = hash_api_key("sk-correct-horse-battery").unwrap();
+        assert!(is_hashed(&hash));
+        assert!(verify_api_key("sk-correct-horse-battery", &hash));
+        // 命中缓存
+        assert!(verify_api_key("sk-correct-horse-battery", &hash));
+        assert!(!verify_api_key("sk-wrong", &hash));
+
+        assert!(verify_api_key("sk-plain", "sk-plain"));
+        assert!(!verify_api_key("sk-plain", "sk-other"));
+    }
+
+    #[test]
+    fn lookup_tag_skips_argon2_for_other_keys() {
+        let hash = hash_api_key("sk-tagged-key-0123456789").unwrap();
+        assert!(hash.starts_with(&format!("{}:$argon2", key_fingerprint("sk-tagged-key-0123456789"))));
+        assert!(!needs_slow_verify("sk-junk", &hash));
+        assert!(!verify_api_key("sk-junk", &hash));
+        assert!(needs_slow_verify("sk-tagged-key-0123456789", &hash));
+        assert!(verify_api_key("sk-tagged-key-0123456789", &hash));
+        // 验证通过后命中缓存，不再需要 argon2
+        assert!(!needs_slow_verify("sk-tagged-key-0123456789", &hash));
+        assert!(!needs_slow_verify("sk-plain", "sk-plain"));
+    }
+
+    #[test]
+    fn untagged_legacy_hashes_still_verify() {
+        let hash = hash_api_key("sk-legacy-key-0123456789").unwrap();
+        let (_, phc) = hash.split_once(':').unwrap();
+        assert!(is_hashed(phc));
+        assert!(needs_slow_verify("sk-junk", phc));
+        assert!(!verify_api_key("sk-junk", phc));
+        assert!(verify_api_key("sk-legacy-key-0123456789", phc));
+    }
+
+    #[test]
+    fn generated_keys_are_prefixed_random_and_strong() {
+        let a = generate_api_key();
+        let b = generate_api_key();
+        assert!(a.starts_with(API_KEY_PREFIX));
+        assert_eq!(a.len(), API_KEY_PREFIX.len() + GENERATED_KEY_LEN);
+        assert_ne!(a, b);
+        assert!(weak_key_reason(&a).is_none());
+        // 生成的密钥能被脱敏规则识别
+        assert_eq!(redact_text(&a), redact_secret(&a));
+    }
+
+    #[test]
+    fn rejects_weak_keys() {
+        assert!(weak_key_reason("123456").is_some());
+        assert!(weak_key_reason("sk-password").is_some());
+        assert!(weak_key_reason("aaaaaaaaaaaaaaaaaaaaaaaa").is_some());
+        assert!(weak_key_reason("abababababababababababab").is_some());
+        assert!(weak_key_reason("1234567890123456").is_some());
+        assert!(weak_key_reason("Xk3vQ9mZp2Lr7Tw4Nh8s").is_none());
+    }
+
+    #[test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn hashes_plain_config_keys_once() {
+        let mut config = crate::proxy::config::ProxyConfig {
+            api_key: "sk-global-0123456789".to_string(),
+            ..Default::default()
+        };
+        hash_config_keys(&mut config).unwrap();
+        assert!(is_hashed(&config.api_key));
+        let hashed = config.api_key.clone();
+        hash_config_keys(&mut config).unwrap();
+        assert_eq!(config.api_key, hashed);
+        assert!(verify_api_key("sk-global-0123456789", &config.api_key));
+    }
+
+    #[test]
+    fn redacts_to_prefix() {
+        assert_eq!(redact_secret("sk-0123456789abcdef"), "sk-012…");
+        assert_eq!(redact_secret("short"), "***");
+        assert_eq!(redact_secret("$argon2id$v=19$m=19456"), "[hashed]");
+        assert_eq!(redact_secret("1a2b3c4d:$argon2id$v=19$m=19456"), "[hashed]");
+        assert_eq!(redact_secret(""), "");
+    }
+
+    #[test]
+    fn fingerprint_is_stable_and_short() {
+        assert_eq!(key_fingerprint("sk-a"), key_fingerprint("sk-a"));
+        assert_ne!(key_fingerprint("sk-a"), key_fingerprint("sk-b"));
+        assert_eq!(key_fingerprint("sk-a").len(), 8);
+    }
+
+    #[test]
+    fn redacts_secrets_in_text() {
+        let line = "auth Bearer sk-0123456789abcdef token=ya29.a0AfH6SMBxyz refresh 1//0gAbCdEfGhIjKl url=/v1beta/models/x?key=AIzaSyABCDEF&alt=sse";
+        let redacted = redact_text(line);
+        assert!(!redacted.contains("0123456789abcdef"));
+        assert!(!redacted.contains("a0AfH6SMBxyz"));
+        assert!(!redacted.contains("AbCdEfGhIjKl"));
+        assert!(!redacted.contains("AIzaSyABCDEF"));
+        assert!(redacted.contains("Bearer sk-012…"));
+        assert!(redacted.contains("?key=AIzaSy…&alt=sse"));
+
+        assert_eq!(redact_text("no secrets here"), "no secrets here");
+    }
+}
@@ -1,10 +1,33 @@
-use crate::proxy::config::{ProxyAuthMode, ProxyConfig};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::proxy::config::{KeyScope, ListenerConfig, ProxyAuthMode, ProxyConfig, RetiredApiKey, ScopedApiKey};
+
+/// 主密钥与宽限期内旧密钥的名称 (参数策略等按密钥名称匹配)
+pub const DEFAULT_KEY_NAME: &str = "default";
+
+/// 鉴权通过的密钥，由鉴权中间件放入请求扩展，供后续中间件按密钥区分处理
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizedKey {
+    pub name: String,
+    pub scopes: Vec<KeyScope>,
+}
 
 #[derive(Debug, Clone)]
 pub struct ProxySecurityConfig {
     pub auth_mode: ProxyAuthMode,
     pub api_key: String,
+    /// 轮换宽限期内仍然有效的旧密钥
+    pub previous_keys: Vec<RetiredApiKey>,
+    /// 带权限范围的附加密钥
+    pub scoped_keys: Vec<ScopedApiKey>,
     pub allow_lan_access: bool,
+    /// Claude 接口接受的 `anthropic-version` (为空时不校验)
+    pub anthropic_versions: Vec<String>,
+    /// 是否接受 `X-Antigravity-Account` 固定账号请求头
+    pub allow_account_pinning: bool,
+    /// 集群共享密钥 (未启用多实例协同时为空)
+    pub cluster_secret: String,
 }
 
 impl ProxySecurityConfig {
@@ -12,8 +35,111 @@ impl ProxySecurityConfig {
         Self {
             auth_mode: config.auth_mode.clone(),
             api_key: config.api_key.clone(),
+            previous_keys: config.previous_api_keys.clone(),
+            scoped_keys: config.api_keys.clone(),
             allow_lan_access: config.allow_lan_access,
+            anthropic_versions: config.anthropic_versions.clone(),
+            allow_account_pinning: config.allow_account_pinning,
+            cluster_secret: if config.cluster.enabled {
+                config.cluster.api_key.clone()
+            } else {
+                String::new()
+            },
+        }
+    }
+
+    /// 额外监听器的安全配置: 未覆盖的字段沿用全局配置
+    pub fn for_listener(&self, listener: &ListenerConfig) -> Self {
+        // 监听器单独指定密钥时不继承全局的旧密钥与附加密钥
+        let (previous_keys, scoped_keys) = if listener.api_key.is_some() {
+            (Vec::new(), Vec::new())
+        } else {
+            (self.previous_keys.clone(), self.scoped_keys.clone())
+        };
+        Self {
+            auth_mode: listener
+                .auth_mode
+                .clone()
+                .unwrap_or_else(|| self.auth_mode.clone()),
+            api_key: listener
+                .api_key
+                .clone()
+                .unwrap_or_else(|| self.api_key.clone()),
+            previous_keys,
+            scoped_keys,
+            allow_lan_access: listener.is_lan(),
+            anthropic_versions: self.anthropic_versions.clone(),
+            allow_account_pinning: self.allow_account_pinning,
+            cluster_secret: self.cluster_secret.clone(),
+        }
+    }
+
+    /// 校验客户端提供的密钥 (支持明文与 argon2 哈希存储，含宽限期内的旧密钥与附加密钥)
+    pub fn verify_key(&self, provided: &str) -> bool {
+        self.authorize(provided).is_some()
+    }
+
+    /// 校验密钥并返回其权限范围：主密钥与旧密钥为空 (不限制)，无效密钥为 None
+    pub fn authorize(&self, provided: &str) -> Option<Vec<KeyScope>> {
+        self.resolve(provided).map(|key| key.scopes)
+    }
+
+    /// 校验密钥并返回其名称与权限范围：主密钥 (含监听器单独指定的密钥) 与宽限期内的旧密钥
+    /// 名为 `default`，附加密钥为其 `name`
+    pub fn resolve(&self, provided: &str) -> Option<AuthorizedKey> {
+        let default = || AuthorizedKey {
+            name: DEFAULT_KEY_NAME.to_string(),
+            scopes: Vec::new(),
+        };
+        if crate::proxy::secrets::verify_api_key(provided, &self.api_key) {
+            return Some(default());
+        }
+        let now = chrono::Utc::now().timestamp();
+        if self
+            .previous_keys
+            .iter()
+            .filter(|k| k.expires_at > now)
+            .any(|k| crate::proxy::secrets::verify_api_key(provided, &k.key))
+        {
+            return Some(default());
+        }
+        self.scoped_keys
+            .iter()
+            .find(|k| crate::proxy::secrets::verify_api_key(provided, &k.key))
+            .map(|k| AuthorizedKey {
+                name: k.name.clone(),
+                scopes: k.scopes.clone(),
+            })
+    }
+
+    /// 与 `resolve` 相同；需要执行 argon2 时移到阻塞线程池，避免无效密钥的请求占满异步工作线程
+    pub async fn resolve_async(&self, provided: &str) -> Option<AuthorizedKey> {
+        let now = chrono::Utc::now().timestamp();
+        let slow = std::iter::once(self.api_key.as_str())
+            .chain(self.previous_keys.iter().filter(|k| k.expires_at > now).map(|k| k.key.as_str()))
+            .chain(self.scoped_keys.iter().map(|k| k.key.as_str()))
+            .any(|expected| crate::proxy::secrets::needs_slow_verify(provided, expected));
+        if !slow {
+            return self.resolve(provided);
         }
+        let security = self.clone();
+        let provided = provided.to_string();
+        tokio::task::spawn_blocking(move || security.resolve(&provided))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("API Key 校验任务失败: {}", e);
+                None
+            })
+    }
+
+    /// 校验对等实例携带的集群共享密钥；未配置共享密钥时一律拒绝
+    pub fn verify_cluster_secret(&self, provided: &str) -> bool {
+        !self.cluster_secret.is_empty() && crate::proxy::secrets::verify_api_key(provided, &self.cluster_secret)
+    }
+
+    /// 具有 `scopes` 的密钥能否固定账号: 需开启配置，带权限范围的附加密钥还需 `pin-account`
+    pub fn can_pin_account(&self, scopes: &[KeyScope]) -> bool {
+        self.allow_account_pinning && (scopes.is_empty() || scopes.contains(&KeyScope::PinAccount))
     }
 
     pub fn effective_auth_mode(&self) -> ProxyAuthMode {
@@ -30,6 +156,33 @@ impl ProxySecurityConfig {
     }
 }
 
+/// 全局与各额外监听器的安全配置，热更新时一并刷新
+pub struct SecurityStates {
+    pub global: Arc<RwLock<ProxySecurityConfig>>,
+    pub listeners: Vec<(ListenerConfig, Arc<RwLock<ProxySecurityConfig>>)>,
+}
+
+impl SecurityStates {
+    pub fn new(global: ProxySecurityConfig, listeners: &[ListenerConfig]) -> Self {
+        let listeners = listeners
+            .iter()
+            .map(|l| (l.clone(), Arc::new(RwLock::new(global.for_listener(l)))))
+            .collect();
+        Self {
+            global: Arc::new(RwLock::new(global)),
+            listeners,
+        }
+    }
+
+    pub async fn update(&self, config: &ProxyConfig) {
+        let global = ProxySecurityConfig::from_proxy_config(config);
+        for (listener, state) in &self.listeners {
+            *state.write().await = global.for_listener(listener);
+        }
+        *self.global.write().await = global;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -39,8 +192,47 @@ mod tests {
         let s = ProxySecurityConfig {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
+            previous_keys: Vec::new(),
+            scoped_keys: Vec::new(),
+            allow_lan_access: false,
+            anthropic_versions: Vec::new(),
+            allow_account_pinning: false,
+            cluster_secret: String::new(),
+        };
+        assert!(matches!(s.effective_auth_mode(), ProxyAuthMode::Off));
+    }
+
+    #[test]
+    fn listener_overrides_fall_back_to_global() {
+        let global = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Auto,
+            api_key: "sk-global".to_string(),
+            previous_keys: Vec::new(),
+            scoped_keys: Vec::new(),
             allow_lan_access: false,
+            anthropic_versions: Vec::new(),
+            allow_account_pinning: false,
+            cluster_secret: String::new(),
+        };
+        let listener = ListenerConfig {
+            bind: "0.0.0.0:8443".to_string(),
+            tls: None,
+            auth_mode: None,
+            api_key: Some("sk-lan".to_string()),
+            socket_mode: None,
+            acme: None,
+        };
+        let s = global.for_listener(&listener);
+        assert_eq!(s.api_key, "sk-lan");
+        assert!(matches!(s.effective_auth_mode(), ProxyAuthMode::AllExceptHealth));
+
+        let local = ListenerConfig {
+            bind: "127.0.0.1:9000".to_string(),
+            api_key: None,
+            ..listener
         };
+        let s = global.for_listener(&local);
+        assert_eq!(s.api_key, "sk-global");
         assert!(matches!(s.effective_auth_mode(), ProxyAuthMode::Off));
     }
 
@@ -49,12 +241,97 @@ mod tests {
         let s = ProxySecurityConfig {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
+            previous_keys: Vec::new(),
+            scoped_keys: Vec::new(),
             allow_lan_access: true,
+            anthropic_versions: Vec::new(),
+            allow_account_pinning: false,
+            cluster_secret: String::new(),
         };
         assert!(matches!(
             s.effective_auth_mode(),
             ProxyAuthMode::AllExceptHealth
         ));
     }
-}
 
+    #[test]
+    fn previous_keys_valid_until_expiry() {
+        let now = chrono::Utc::now().timestamp();
+        let s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-new".to_string(),
+            previous_keys: vec![
+                RetiredApiKey {
+                    key: "sk-old".to_string(),
+                    expires_at: now + 60,
+                },
+                RetiredApiKey {
+                    key: "sk-expired".to_string(),
+                    expires_at: now - 1,
+                },
+            ],
+            scoped_keys: vec![ScopedApiKey {
+                name: "dashboard".to_string(),
+                key: "sk-stats".to_string(),
+                scopes: vec![KeyScope::ReadOnlyStats],
+                created_at: 0,
+            }],
+            allow_lan_access: false,
+            anthropic_versions: Vec::new(),
+            allow_account_pinning: false,
+            cluster_secret: String::new(),
+        };
+        assert_eq!(s.authorize("sk-old"), Some(Vec::new()));
+        assert_eq!(s.authorize("sk-stats"), Some(vec![KeyScope::ReadOnlyStats]));
+        assert!(s.verify_key("sk-new"));
+        assert!(s.verify_key("sk-old"));
+        assert!(!s.verify_key("sk-expired"));
+        assert!(!s.verify_key("sk-other"));
+    }
+
+    #[tokio::test]
+    async fn resolve_async_matches_hashed_keys() {
+        let s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: crate::proxy::secrets::hash_api_key("sk-hashed-0123456789").unwrap(),
+            previous_keys: Vec::new(),
+            scoped_keys: vec![ScopedApiKey {
+                name: "stats".to_string(),
+                key: crate::proxy::secrets::hash_api_key("sk-stats-0123456789").unwrap(),
+                scopes: vec![KeyScope::ReadOnlyStats],
+                created_at: 0,
+            }],
+            allow_lan_access: false,
+            anthropic_versions: Vec::new(),
+            allow_account_pinning: false,
+            cluster_secret: String::new(),
+        };
+        let main = s.resolve_async("sk-hashed-0123456789").await.unwrap();
+        assert_eq!(main.name, DEFAULT_KEY_NAME);
+        assert!(main.scopes.is_empty());
+        let stats = s.resolve_async("sk-stats-0123456789").await.unwrap();
+        assert_eq!(stats.name, "stats");
+        assert_eq!(stats.scopes, vec![KeyScope::ReadOnlyStats]);
+        assert_eq!(s.resolve_async("sk-junk").await, None);
+    }
+
+    #[test]
+    fn account_pinning_requires_flag_and_scope() {
+        let mut s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-test".to_string(),
+            previous_keys: Vec::new(),
+            scoped_keys: Vec::new(),
+            allow_lan_access: false,
+            anthropic_versions: Vec::new(),
+            allow_account_pinning: false,
+            cluster_secret: String::new(),
+        };
+        assert!(!s.can_pin_account(&[]));
+
+        s.allow_account_pinning = true;
+        assert!(s.can_pin_account(&[]));
+        assert!(!s.can_pin_account(&[KeyScope::ChatOnly]));
+        assert!(s.can_pin_account(&[KeyScope::ChatOnly, KeyScope::PinAccount]));
+    }
+}
@@ -9,9 +9,15 @@ use axum::{
 use std::sync::Arc;
 use tokio::sync::oneshot;
 use tower_http::trace::TraceLayer;
-use tracing::{debug, error};
 use tokio::sync::RwLock;
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// 切换主监听地址时，旧监听器上在途请求 (含流式会话) 的最长等待时间
+const REBIND_DRAIN_TIMEOUT: Duration = Duration::from_secs(600);
+/// 新旧地址端口相同时需先释放旧套接字，重试绑定的次数与间隔
+const REBIND_ATTEMPTS: usize = 20;
+const REBIND_RETRY_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Axum 应用状态
 #[derive(Clone)]
@@ -20,6 +26,7 @@ pub struct AppState {
     pub anthropic_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
     pub openai_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
     pub custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    pub mapping_rules: Arc<tokio::sync::RwLock<crate::proxy::common::mapping_rules::MappingRules>>,
     #[allow(dead_code)]
     pub request_timeout: u64, // API 请求超时(秒)
     #[allow(dead_code)]
@@ -31,17 +38,73 @@ pub struct AppState {
     pub provider_rr: Arc<AtomicUsize>,
     pub zai_vision_mcp: Arc<crate::proxy::zai_vision_mcp::ZaiVisionMcpState>,
     pub monitor: Arc<crate::proxy::monitor::ProxyMonitor>,
+    pub timeouts: Arc<RwLock<crate::proxy::timeouts::RouteTimeouts>>,
+    pub batches: Arc<crate::proxy::batches::BatchManager>,
+    pub admission: Arc<crate::proxy::load_shedding::AdmissionController>,
+    /// 不含鉴权层的内部路由，供请求重放使用 (弱引用，避免与 AppState 循环引用)
+    pub replay_router: Arc<std::sync::OnceLock<std::sync::Weak<Router>>>,
+    /// 是否开放调试端点 (`/debug/*`)
+    pub debug_endpoints: Arc<AtomicBool>,
+    /// 安全配置 (管理接口轮换密钥后热更新)
+    pub security: Arc<crate::proxy::security::SecurityStates>,
+    /// 响应头策略
+    pub response_headers: Arc<RwLock<crate::proxy::config::ResponseHeaderConfig>>,
+    /// 响应压缩
+    pub compression: Arc<RwLock<crate::proxy::config::CompressionConfig>>,
+    pub validation: Arc<RwLock<crate::proxy::config::RequestValidationConfig>>,
+    /// 采样参数策略
+    pub param_policy: Arc<RwLock<crate::proxy::config::ParamPolicyConfig>>,
+    /// 流量镜像 (配置与最近的对比结果)
+    pub mirror: Arc<crate::proxy::mirror::Mirror>,
+    /// 在途请求 (供管理接口列出 / 取消)
+    pub inflight: Arc<crate::proxy::inflight::InflightRegistry>,
+    /// 按上游模型统计的延迟与成功率 (`/stats/models`)
+    pub model_stats: Arc<crate::proxy::model_stats::ModelScoreboard>,
+    /// 服务启动时间 (Unix 秒)
+    pub started_at: i64,
+}
+
+/// 主 TCP 监听器 (`port` / `allow_lan_access`)，有独立的停止信号以便单独切换地址
+struct PrimaryListener {
+    addr: String,
+    stop: tokio::sync::watch::Sender<Option<Duration>>,
+    /// 切换到同一端口的其他地址时临时开启 SO_REUSEPORT (停止后随之释放)
+    share: Option<crate::proxy::listener::PortShare>,
 }
 
+/// 提交给服务任务运行的监听器及其停止信号
+type SpawnListener = (
+    crate::proxy::listener::Listener,
+    tokio::sync::watch::Receiver<Option<Duration>>,
+);
+
 /// Axum 服务器实例
 pub struct AxumServer {
-    shutdown_tx: Option<oneshot::Sender<()>>,
+    shutdown_tx: Option<oneshot::Sender<Duration>>,
+    primary: Arc<tokio::sync::Mutex<Option<PrimaryListener>>>,
+    /// 主监听器的路由 (切换地址时复用)
+    primary_app: Router,
+    spawn_tx: tokio::sync::mpsc::UnboundedSender<SpawnListener>,
     anthropic_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
     openai_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
     custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    mapping_rules: Arc<tokio::sync::RwLock<crate::proxy::common::mapping_rules::MappingRules>>,
     proxy_state: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
-    security_state: Arc<RwLock<crate::proxy::ProxySecurityConfig>>,
+    /// 全局与各额外监听器的安全配置
+    security: Arc<crate::proxy::security::SecurityStates>,
     zai_state: Arc<RwLock<crate::proxy::ZaiConfig>>,
+    timeouts_state: Arc<RwLock<crate::proxy::timeouts::RouteTimeouts>>,
+    response_headers: Arc<RwLock<crate::proxy::config::ResponseHeaderConfig>>,
+    compression: Arc<RwLock<crate::proxy::config::CompressionConfig>>,
+    validation: Arc<RwLock<crate::proxy::config::RequestValidationConfig>>,
+    param_policy: Arc<RwLock<crate::proxy::config::ParamPolicyConfig>>,
+    ip_rate_limiter: Arc<crate::proxy::middleware::ip_rate_limit::IpRateLimiter>,
+    auth_lockout: Arc<crate::proxy::middleware::auth_lockout::AuthLockout>,
+    trusted_proxies: Arc<std::sync::RwLock<crate::proxy::client_ip::TrustedProxies>>,
+    batches: Arc<crate::proxy::batches::BatchManager>,
+    admission: Arc<crate::proxy::load_shedding::AdmissionController>,
+    debug_endpoints: Arc<AtomicBool>,
+    mirror: Arc<crate::proxy::mirror::Mirror>,
 }
 
 impl AxumServer {
@@ -58,7 +121,11 @@ impl AxumServer {
             let mut m = self.custom_mapping.write().await;
             *m = config.custom_mapping.clone();
         }
-        tracing::debug!("模型映射 (Anthropic/OpenAI/Custom) 已全量热更新");
+        {
+            let mut m = self.mapping_rules.write().await;
+            *m = crate::proxy::common::mapping_rules::MappingRules::from_config(&config.mapping_rules);
+        }
+        tracing::debug!("模型映射 (Anthropic/OpenAI/Custom/Rules) 已全量热更新");
     }
 
     /// 更新代理配置
@@ -69,8 +136,7 @@ impl AxumServer {
     }
 
     pub async fn update_security(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut sec = self.security_state.write().await;
-        *sec = crate::proxy::ProxySecurityConfig::from_proxy_config(config);
+        self.security.update(config).await;
         tracing::info!("反代服务安全配置已热更新");
     }
 
@@ -79,6 +145,172 @@ impl AxumServer {
         *zai = config.zai.clone();
         tracing::info!("z.ai 配置已热更新");
     }
+
+    pub async fn update_timeouts(&self, config: &crate::proxy::config::ProxyConfig) {
+        let mut timeouts = self.timeouts_state.write().await;
+        *timeouts = crate::proxy::timeouts::RouteTimeouts::from_proxy_config(config);
+        tracing::info!("超时配置已热更新");
+    }
+
+    pub async fn update_response_headers(&self, config: &crate::proxy::config::ProxyConfig) {
+        let mut response_headers = self.response_headers.write().await;
+        *response_headers = config.response_headers.clone();
+    }
+
+    pub async fn update_compression(&self, config: &crate::proxy::config::ProxyConfig) {
+        let mut compression = self.compression.write().await;
+        *compression = config.compression.clone();
+    }
+
+    pub async fn update_validation(&self, config: &crate::proxy::config::ProxyConfig) {
+        let mut validation = self.validation.write().await;
+        *validation = config.request_validation.clone();
+    }
+
+    pub async fn update_param_policy(&self, config: &crate::proxy::config::ProxyConfig) {
+        let mut param_policy = self.param_policy.write().await;
+        *param_policy = config.param_policy.clone();
+    }
+
+    pub fn update_client_rate_limit(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.ip_rate_limiter.configure(&config.client_rate_limit);
+    }
+
+    pub fn update_auth_lockout(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.auth_lockout.configure(&config.auth_lockout);
+    }
+
+    pub fn update_trusted_proxies(&self, config: &crate::proxy::config::ProxyConfig) {
+        let trusted = crate::proxy::client_ip::TrustedProxies::from_config(&config.trusted_proxies);
+        let mut current = self.trusted_proxies.write().unwrap_or_else(|e| e.into_inner());
+        if *current != trusted {
+            *current = trusted;
+            tracing::info!("受信任代理已更新: {:?}", config.trusted_proxies);
+        }
+    }
+
+    pub fn update_batches(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.batches.configure(&config.batches);
+    }
+
+    pub fn update_load_shedding(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.admission.configure(&config.load_shedding);
+    }
+
+    pub fn update_debug_endpoints(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.debug_endpoints
+            .store(config.enable_debug_endpoints, Ordering::Relaxed);
+    }
+
+    pub fn update_mirror(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.mirror.configure(&config.mirror);
+    }
+
+    /// 热切换主监听地址 (`listen_tcp` / `allow_lan_access` / `port`)，返回是否发生了切换
+    ///
+    /// 先绑定新地址并开始接收连接，再停止旧监听器；旧监听器上的在途请求继续完成 (最长
+    /// `REBIND_DRAIN_TIMEOUT`)。仅地址变化而端口相同时，主监听器平时为独占绑定 (同端口的
+    /// 其他实例会绑定失败)，切换期间才临时开启 SO_REUSEPORT 让新旧监听器同时持有该端口，
+    /// 完成后恢复独占；不支持 SO_REUSEPORT 的平台 (Windows) 只能先释放旧端口 (短暂拒绝
+    /// 新连接)，新地址绑定失败时恢复旧地址。额外监听器 (`listeners`) 的变更仍需重启服务
+    pub async fn rebind(&self, config: &crate::proxy::config::ProxyConfig) -> Result<bool, String> {
+        let target = config
+            .listen_tcp
+            .then(|| format!("{}:{}", config.get_bind_address(), config.port));
+        let mut primary = self.primary.lock().await;
+        let current = primary.as_ref().map(|p| p.addr.clone());
+        if current == target {
+            return Ok(false);
+        }
+
+        let Some(addr) = target else {
+            if self.security.listeners.is_empty() {
+                return Err("未配置任何监听地址 (listen_tcp 已关闭且 listeners 为空)".to_string());
+            }
+            if let Some(old) = primary.take() {
+                let _ = old.stop.send(Some(REBIND_DRAIN_TIMEOUT));
+            }
+            tracing::info!("主监听地址已关闭");
+            return Ok(true);
+        };
+
+        let same_port = current
+            .as_deref()
+            .and_then(|c| c.rsplit_once(':'))
+            .is_some_and(|(_, port)| port == config.port.to_string());
+        let mut shared = false;
+        let bound = match crate::proxy::listener::BoundListener::bind_tcp(&addr).await {
+            Ok(bound) => bound,
+            Err(e) if !same_port => return Err(e),
+            // 旧监听器为独占绑定，端口不会被其他进程持有: 临时共用端口完成无中断切换
+            Err(_) => match share_port(primary.as_ref(), &addr).await {
+                Some(bound) => {
+                    shared = true;
+                    bound
+                }
+                None => {
+                    let old = primary.take().expect("same_port implies a current listener");
+                    let _ = old.stop.send(Some(REBIND_DRAIN_TIMEOUT));
+                    // 释放复制的套接字，旧端口才会真正关闭
+                    let old_addr = old.addr.clone();
+                    drop(old);
+                    match bind_retrying(&addr).await {
+                        Ok(bound) => bound,
+                        Err(e) => {
+                            // 新地址不可用: 恢复旧地址
+                            let restored = bind_retrying(&old_addr).await?;
+                            *primary = Some(self.spawn_primary(&old_addr, restored)?);
+                            return Err(e);
+                        }
+                    }
+                }
+            },
+        };
+
+        let previous = primary.replace(self.spawn_primary(&addr, bound)?);
+        if let Some(old) = previous {
+            let _ = old.stop.send(Some(REBIND_DRAIN_TIMEOUT));
+        }
+        // 旧监听器已停止接收连接: 新监听器恢复独占
+        if shared {
+            if let Some(share) = primary.as_ref().and_then(|p| p.share.as_ref()) {
+                if let Err(e) = share.set(false) {
+                    tracing::warn!("主监听器恢复独占绑定失败: {}", e);
+                }
+            }
+        }
+        tracing::info!(
+            "主监听地址已切换: {} -> {} (旧地址上的在途请求继续完成)",
+            current.as_deref().unwrap_or("-"),
+            addr
+        );
+        Ok(true)
+    }
+
+    /// 在服务任务中运行新的主监听器
+    fn spawn_primary(
+        &self,
+        addr: &str,
+        bound: crate::proxy::listener::BoundListener,
+    ) -> Result<PrimaryListener, String> {
+        let (stop, stop_rx) = tokio::sync::watch::channel(None);
+        let listener = crate::proxy::listener::Listener {
+            name: format!("http://{}", addr),
+            bound,
+            app: self.primary_app.clone(),
+            tls: None,
+        };
+        tracing::info!("反代服务器启动在 {}", listener.name);
+        let share = listener.bound.port_share();
+        self.spawn_tx
+            .send((listener, stop_rx))
+            .map_err(|_| "反代服务已停止".to_string())?;
+        Ok(PrimaryListener {
+            addr: addr.to_string(),
+            stop,
+            share,
+        })
+    }
     /// 启动 Axum 服务器
     pub async fn start(
         host: String,
@@ -87,20 +319,58 @@ impl AxumServer {
         anthropic_mapping: std::collections::HashMap<String, String>,
         openai_mapping: std::collections::HashMap<String, String>,
         custom_mapping: std::collections::HashMap<String, String>,
-        _request_timeout: u64,
+        mapping_rules: Vec<crate::proxy::config::ModelMappingRule>,
+        timeouts: crate::proxy::timeouts::RouteTimeouts,
         upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
         security_config: crate::proxy::ProxySecurityConfig,
         zai_config: crate::proxy::ZaiConfig,
+        client_rate_limit: crate::proxy::config::ClientRateLimitConfig,
+        auth_lockout: crate::proxy::config::AuthLockoutConfig,
+        trusted_proxies: Vec<String>,
+        batch_config: crate::proxy::config::BatchConfig,
+        load_shedding: crate::proxy::config::LoadSheddingConfig,
+        listen_tcp: bool,
+        listeners: Vec<crate::proxy::config::ListenerConfig>,
+        admin_listen: Option<String>,
+        debug_endpoints: bool,
+        response_headers: crate::proxy::config::ResponseHeaderConfig,
+        mirror: crate::proxy::config::MirrorConfig,
+        compression: crate::proxy::config::CompressionConfig,
+        validation: crate::proxy::config::RequestValidationConfig,
+        param_policy: crate::proxy::config::ParamPolicyConfig,
         monitor: Arc<crate::proxy::monitor::ProxyMonitor>,
 
     ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
         let mapping_state = Arc::new(tokio::sync::RwLock::new(anthropic_mapping));
         let openai_mapping_state = Arc::new(tokio::sync::RwLock::new(openai_mapping));
         let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(custom_mapping));
+        let mapping_rules_state = Arc::new(tokio::sync::RwLock::new(
+            crate::proxy::common::mapping_rules::MappingRules::from_config(&mapping_rules),
+        ));
 	        let proxy_state = Arc::new(tokio::sync::RwLock::new(upstream_proxy.clone()));
-	        let security_state = Arc::new(RwLock::new(security_config));
+	        let security = Arc::new(crate::proxy::security::SecurityStates::new(
+	            security_config,
+	            &listeners,
+	        ));
 	        let zai_state = Arc::new(RwLock::new(zai_config));
+	        let timeouts_state = Arc::new(RwLock::new(timeouts));
+	        let response_headers_state = Arc::new(RwLock::new(response_headers));
+	        let compression_state = Arc::new(RwLock::new(compression));
+	        let validation_state = Arc::new(RwLock::new(validation));
+	        let param_policy_state = Arc::new(RwLock::new(param_policy));
+	        let ip_rate_limiter = Arc::new(
+	            crate::proxy::middleware::ip_rate_limit::IpRateLimiter::new(client_rate_limit),
+	        );
+	        let auth_lockout = Arc::new(crate::proxy::middleware::auth_lockout::AuthLockout::new(auth_lockout));
+	        let trusted_proxies = Arc::new(std::sync::RwLock::new(
+	            crate::proxy::client_ip::TrustedProxies::from_config(&trusted_proxies),
+	        ));
+	        let batches = Arc::new(crate::proxy::batches::BatchManager::new(&batch_config));
+	        let admission = Arc::new(crate::proxy::load_shedding::AdmissionController::new(load_shedding));
 	        let provider_rr = Arc::new(AtomicUsize::new(0));
+        let replay_router = Arc::new(std::sync::OnceLock::new());
+        let debug_endpoints = Arc::new(AtomicBool::new(debug_endpoints));
+        let mirror = Arc::new(crate::proxy::mirror::Mirror::new(mirror));
 	        let zai_vision_mcp_state =
 	            Arc::new(crate::proxy::zai_vision_mcp::ZaiVisionMcpState::new());
 
@@ -109,6 +379,7 @@ impl AxumServer {
 	            anthropic_mapping: mapping_state.clone(),
 	            openai_mapping: openai_mapping_state.clone(),
 	            custom_mapping: custom_mapping_state.clone(),
+	            mapping_rules: mapping_rules_state.clone(),
 	            request_timeout: 300, // 5分钟超时
             thought_signature_map: Arc::new(tokio::sync::Mutex::new(
                 std::collections::HashMap::new(),
@@ -121,7 +392,23 @@ impl AxumServer {
             provider_rr: provider_rr.clone(),
             zai_vision_mcp: zai_vision_mcp_state,
             monitor: monitor.clone(),
+            timeouts: timeouts_state.clone(),
+            batches: batches.clone(),
+            admission: admission.clone(),
+            replay_router: replay_router.clone(),
+            debug_endpoints: debug_endpoints.clone(),
+            security: security.clone(),
+            response_headers: response_headers_state.clone(),
+            compression: compression_state.clone(),
+            validation: validation_state.clone(),
+            param_policy: param_policy_state.clone(),
+            mirror: mirror.clone(),
+            inflight: Arc::new(crate::proxy::inflight::InflightRegistry::new()),
+            model_stats: Arc::new(crate::proxy::model_stats::ModelScoreboard::new()),
+            started_at: chrono::Utc::now().timestamp(),
         };
+        // 续跑上次退出时未完成的批次
+        batches.resume_pending(&state);
 
 
         // 构建路由 - 使用新架构的 handlers！
@@ -139,6 +426,7 @@ impl AxumServer {
                 post(handlers::openai::handle_completions),
             )
             .route("/v1/responses", post(handlers::openai::handle_completions)) // 兼容 Codex CLI
+            .route("/v1/realtime", get(handlers::realtime::handle_realtime)) // WebSocket 实时桥接
             .route(
                 "/v1/images/generations",
                 post(handlers::openai::handle_images_generations),
@@ -153,6 +441,24 @@ impl AxumServer {
                 "/v1/messages/count_tokens",
                 post(handlers::claude::handle_count_tokens),
             )
+            .route(
+                "/v1/messages/batches",
+                post(handlers::batches::handle_create_batch)
+                    .get(handlers::batches::handle_list_batches),
+            )
+            .route(
+                "/v1/messages/batches/:id",
+                get(handlers::batches::handle_get_batch)
+                    .delete(handlers::batches::handle_delete_batch),
+            )
+            .route(
+                "/v1/messages/batches/:id/cancel",
+                post(handlers::batches::handle_cancel_batch),
+            )
+            .route(
+                "/v1/messages/batches/:id/results",
+                get(handlers::batches::handle_batch_results),
+            )
             .route(
                 "/v1/models/claude",
                 get(handlers::claude::handle_list_models),
@@ -184,72 +490,248 @@ impl AxumServer {
             .route("/v1/models/detect", post(handlers::common::handle_detect_model))
             .route("/v1/api/event_logging/batch", post(silent_ok_handler))
             .route("/v1/api/event_logging", post(silent_ok_handler))
+            .route("/admin/status", get(handlers::admin::handle_status))
+            .route("/admin/logs/stream", get(handlers::admin::handle_logs_stream))
+            .route("/admin/events", get(handlers::admin::handle_events))
+            .route("/admin/logs/:id/replay", post(handlers::admin::handle_replay))
+            .route("/admin/latency", get(handlers::admin::handle_latency))
+            .route("/stats/models", get(handlers::admin::handle_model_stats))
+            .route("/admin/usage", get(handlers::admin::handle_usage))
+            .route("/admin/quota-cache", get(handlers::admin::handle_quota_cache))
+            .route(crate::proxy::cluster::EVENTS_PATH, post(handlers::admin::handle_cluster_events))
+            .route("/admin/mirror", get(handlers::admin::handle_mirror_results))
+            .route("/admin/requests", get(handlers::admin::handle_list_requests))
+            .route("/admin/requests/:id/cancel", post(handlers::admin::handle_cancel_request))
+            .route("/admin/sessions", get(handlers::admin::handle_list_sessions))
+            .route("/admin/sessions/:id", get(handlers::admin::handle_session_logs))
+            .route("/admin/keys", get(handlers::admin::handle_list_keys))
+            .route("/admin/keys/rotate", post(handlers::admin::handle_rotate_key))
+            .route(
+                "/admin/log-filter",
+                get(handlers::admin::handle_get_log_filter)
+                    .put(handlers::admin::handle_set_log_filter)
+                    .delete(handlers::admin::handle_reset_log_filter),
+            )
+            .route("/debug/translate", post(handlers::debug::handle_translate))
+            .route("/dashboard", get(handlers::admin::handle_dashboard))
+            .route("/dashboard/data", get(handlers::admin::handle_dashboard_data))
             .route("/healthz", get(health_check_handler))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::param_policy_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::request_validation_middleware))
             .layer(DefaultBodyLimit::max(100 * 1024 * 1024))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::mirror_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::response_headers_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::team_routing_middleware))
             .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::monitor::monitor_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::inflight_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::compression_middleware))
             .layer(TraceLayer::new_for_http())
             .layer(axum::middleware::from_fn_with_state(
-                security_state.clone(),
-                crate::proxy::middleware::auth_middleware,
+                admission.clone(),
+                crate::proxy::middleware::load_shedding_middleware,
             ))
-            .layer(crate::proxy::middleware::cors_layer())
+            .layer(axum::middleware::from_fn(crate::proxy::middleware::request_id_middleware))
             .with_state(state);
 
-        // 绑定地址
-        let addr = format!("{}:{}", host, port);
-        let listener = tokio::net::TcpListener::bind(&addr)
-            .await
-            .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))?;
+        // 重放路由由服务器任务持有强引用，服务停止后自动失效
+        let replay_app = Arc::new(app.clone());
+        let _ = replay_router.set(Arc::downgrade(&replay_app));
+
+        // 鉴权 / 鉴权失败锁定 / 按 IP 限流 / CORS 按监听器挂载，使各监听器拥有独立的安全策略
+        // 配置独立管理监听器时，其余监听器不再提供管理接口
+        use crate::proxy::middleware::admin_surface::ApiSurface;
+        let public_surface = if admin_listen.is_some() { ApiSurface::Public } else { ApiSurface::Shared };
+        let edge = |security: Arc<RwLock<crate::proxy::ProxySecurityConfig>>, surface: ApiSurface| {
+            app.clone()
+                .layer(axum::middleware::from_fn_with_state(
+                    security.clone(),
+                    crate::proxy::middleware::anthropic_version_middleware,
+                ))
+                .layer(axum::middleware::from_fn_with_state(
+                    security,
+                    crate::proxy::middleware::auth_middleware,
+                ))
+                .layer(axum::middleware::from_fn_with_state(
+                    auth_lockout.clone(),
+                    crate::proxy::middleware::auth_lockout_middleware,
+                ))
+                .layer(axum::middleware::from_fn_with_state(
+                    ip_rate_limiter.clone(),
+                    crate::proxy::middleware::ip_rate_limit_middleware,
+                ))
+                // 接口面之外的请求在鉴权前拒绝 (不计入鉴权失败锁定)
+                .layer(axum::middleware::from_fn_with_state(
+                    surface,
+                    crate::proxy::middleware::admin_surface_middleware,
+                ))
+                // 先解析客户端地址 (受信任代理之后的真实 IP)
+                .layer(axum::middleware::from_fn_with_state(
+                    trusted_proxies.clone(),
+                    crate::proxy::middleware::client_ip_middleware,
+                ))
+                // 鉴权 / 限流拒绝与处理器错误统一改写为协议原生的错误格式
+                .layer(axum::middleware::from_fn(
+                    crate::proxy::middleware::error_normalization_middleware,
+                ))
+                .layer(crate::proxy::middleware::cors_layer())
+                // 最外层分配请求 ID，鉴权/限流拒绝的响应同样带 X-Request-Id
+                .layer(axum::middleware::from_fn(crate::proxy::middleware::request_id_middleware))
+        };
+
+        // 绑定地址 (任一监听器失败则整体启动失败)
+        use crate::proxy::listener::{self, BoundListener, Listener};
+        let primary_app = edge(security.global.clone(), public_surface);
+        let mut bound = Vec::new();
+        let mut primary = None;
+        if listen_tcp {
+            let addr = format!("{}:{}", host, port);
+            let (stop, stop_rx) = tokio::sync::watch::channel(None);
+            let tcp = BoundListener::bind_tcp(&addr).await?;
+            let share = tcp.port_share();
+            bound.push((
+                Listener {
+                    name: format!("http://{}", addr),
+                    bound: tcp,
+                    app: primary_app.clone(),
+                    tls: None,
+                },
+                Some(stop_rx),
+            ));
+            primary = Some(PrimaryListener { addr, stop, share });
+        }
+
+        // ACME 证书续期任务 (随服务器一起停止)
+        #[cfg_attr(not(feature = "acme"), allow(unused_mut))]
+        let mut acme_tasks: Vec<tokio::task::JoinHandle<()>> = Vec::new();
+        for (config, listener_security) in &security.listeners {
+            // Unix 套接字仅限本机，不使用 TLS
+            let acme = config.acme.as_ref().filter(|_| config.unix_path().is_none());
+            #[cfg(feature = "acme")]
+            let tls = match acme {
+                Some(acme) => {
+                    let (acceptor, task) = crate::proxy::acme::start(acme)?;
+                    acme_tasks.push(task);
+                    Some(acceptor)
+                }
+                None => config
+                    .tls
+                    .as_ref()
+                    .filter(|_| config.unix_path().is_none())
+                    .map(listener::load_tls_acceptor)
+                    .transpose()?,
+            };
+            #[cfg(not(feature = "acme"))]
+            let tls = {
+                if acme.is_some() {
+                    tracing::warn!("监听器 {} 配置了 ACME，但当前版本未以 acme 特性编译，忽略", config.bind);
+                }
+                config
+                    .tls
+                    .as_ref()
+                    .filter(|_| config.unix_path().is_none())
+                    .map(listener::load_tls_acceptor)
+                    .transpose()?
+            };
+            let (name, socket) = match config.unix_path() {
+                Some(path) => (
+                    config.bind.clone(),
+                    BoundListener::bind_unix(path, config.socket_mode.as_deref())?,
+                ),
+                None => (
+                    format!(
+                        "{}://{}",
+                        if tls.is_some() { "https" } else { "http" },
+                        config.bind
+                    ),
+                    BoundListener::bind_tcp(&config.bind).await?,
+                ),
+            };
+            bound.push((
+                Listener {
+                    name,
+                    bound: socket,
+                    app: edge(listener_security.clone(), public_surface),
+                    tls,
+                },
+                None,
+            ));
+        }
 
-        tracing::info!("反代服务器启动在 http://{}", addr);
+        if bound.is_empty() {
+            return Err("未配置任何监听地址 (listen_tcp 已关闭且 listeners 为空)".to_string());
+        }
+        if let Some(addr) = &admin_listen {
+            bound.push((
+                Listener {
+                    name: format!("http://{} (admin)", addr),
+                    bound: BoundListener::bind_tcp(addr).await?,
+                    app: edge(security.global.clone(), ApiSurface::Admin),
+                    tls: None,
+                },
+                None,
+            ));
+        }
+        for (l, _) in &bound {
+            tracing::info!("反代服务器启动在 {}", l.name);
+        }
 
         // 创建关闭通道
-        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<Duration>();
+        let (spawn_tx, mut spawn_rx) = tokio::sync::mpsc::unbounded_channel::<SpawnListener>();
+        let primary = Arc::new(tokio::sync::Mutex::new(primary));
 
         let server_instance = Self {
             shutdown_tx: Some(shutdown_tx),
+            primary: primary.clone(),
+            primary_app,
+            spawn_tx,
             anthropic_mapping: mapping_state.clone(),
             openai_mapping: openai_mapping_state.clone(),
             custom_mapping: custom_mapping_state.clone(),
+            mapping_rules: mapping_rules_state,
             proxy_state,
-            security_state,
+            security,
             zai_state,
+            timeouts_state,
+            response_headers: response_headers_state,
+            compression: compression_state,
+            validation: validation_state,
+            param_policy: param_policy_state,
+            ip_rate_limiter,
+            auth_lockout,
+            trusted_proxies,
+            batches,
+            admission,
+            debug_endpoints,
+            mirror,
         };
 
-        // 在新任务中启动服务器
+        // 在新任务中启动服务器: 每个监听器一个接收任务，停止信号广播给全部监听器
+        // (主监听器使用独立的停止信号，切换地址时新的主监听器由 `spawn_rx` 提交)
         let handle = tokio::spawn(async move {
-            use hyper::server::conn::http1;
-            use hyper_util::rt::TokioIo;
-            use hyper_util::service::TowerToHyperService;
+            let _replay_app = replay_app;
+            let (stop_tx, stop_rx) = tokio::sync::watch::channel(None);
+            let mut tasks = tokio::task::JoinSet::new();
+            for (l, own_stop) in bound {
+                tasks.spawn(listener::serve(l, own_stop.unwrap_or_else(|| stop_rx.clone())));
+            }
 
-            loop {
+            let drain_timeout = loop {
                 tokio::select! {
-                    res = listener.accept() => {
-                        match res {
-                            Ok((stream, _)) => {
-                                let io = TokioIo::new(stream);
-                                let service = TowerToHyperService::new(app.clone());
-
-                                tokio::task::spawn(async move {
-                                    if let Err(err) = http1::Builder::new()
-                                        .serve_connection(io, service)
-                                        .with_upgrades() // 支持 WebSocket (如果以后需要)
-                                        .await
-                                    {
-                                        debug!("连接处理结束或出错: {:?}", err);
-                                    }
-                                });
-                            }
-                            Err(e) => {
-                                error!("接收连接失败: {:?}", e);
-                            }
-                        }
-                    }
-                    _ = &mut shutdown_rx => {
-                        tracing::info!("反代服务器停止监听");
-                        break;
+                    res = &mut shutdown_rx => break res.unwrap_or(Duration::ZERO),
+                    Some((l, own_stop)) = spawn_rx.recv() => {
+                        tasks.spawn(listener::serve(l, own_stop));
                     }
+                    Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
                 }
+            };
+            if let Some(p) = primary.lock().await.take() {
+                let _ = p.stop.send(Some(drain_timeout));
+            }
+            let _ = stop_tx.send(Some(drain_timeout));
+            while tasks.join_next().await.is_some() {}
+            for task in acme_tasks {
+                task.abort();
             }
         });
 
@@ -257,11 +739,51 @@ impl AxumServer {
     }
 
     /// 停止服务器
-    pub fn stop(mut self) {
+    /// 安全配置 (供其他管理入口校验密钥、轮换后热更新)
+    pub fn security(&self) -> Arc<crate::proxy::security::SecurityStates> {
+        self.security.clone()
+    }
+
+    pub fn stop(self) {
+        self.stop_with_drain(Duration::ZERO);
+    }
+
+    /// 停止监听，并在 `drain_timeout` 内等待在途请求完成 (超时后强制关闭)
+    pub fn stop_with_drain(mut self, drain_timeout: Duration) {
         if let Some(tx) = self.shutdown_tx.take() {
-            let _ = tx.send(());
+            let _ = tx.send(drain_timeout);
+        }
+    }
+}
+
+/// 旧监听器临时开启 SO_REUSEPORT 后以共用端口的方式绑定新地址；失败时关闭共用并返回 None
+async fn share_port(
+    current: Option<&PrimaryListener>,
+    addr: &str,
+) -> Option<crate::proxy::listener::BoundListener> {
+    let share = current?.share.as_ref()?;
+    share.set(true).ok()?;
+    match crate::proxy::listener::BoundListener::bind_tcp_shared(addr).await {
+        Ok(bound) => Some(bound),
+        Err(e) => {
+            let _ = share.set(false);
+            tracing::debug!("无法与旧监听器共用端口，改为先释放旧端口: {}", e);
+            None
+        }
+    }
+}
+
+/// 绑定 TCP 地址，失败时短暂重试 (等待旧监听器释放同一端口)
+async fn bind_retrying(addr: &str) -> Result<crate::proxy::listener::BoundListener, String> {
+    let mut last_error = String::new();
+    for _ in 0..REBIND_ATTEMPTS {
+        match crate::proxy::listener::BoundListener::bind_tcp(addr).await {
+            Ok(bound) => return Ok(bound),
+            Err(e) => last_error = e,
         }
+        tokio::time::sleep(REBIND_RETRY_INTERVAL).await;
     }
+    Err(last_error)
 }
 
 // ===== API 处理器 (旧代码已移除，由 src/proxy/handlers/* 接管) =====
@@ -147,4 +147,80 @@ impl SessionManager {
         tracing::debug!("[SessionManager-Gemini] Generated fingerprint: {}", sid);
         sid
     }
+
+    /// 提取用于日志归组的会话 (对话) 标识
+    ///
+    /// 优先级: 客户端显式头 (`X-Session-Id` / `X-Conversation-Id`) > OpenAI `user` 字段 >
+    /// 与粘性调度相同的会话指纹 (Anthropic 优先取 `metadata.user_id`)
+    pub fn extract_conversation_id(
+        headers: &axum::http::HeaderMap,
+        path: &str,
+        body: &Value,
+    ) -> Option<String> {
+        let header = ["x-session-id", "x-conversation-id"]
+            .iter()
+            .filter_map(|name| headers.get(*name)?.to_str().ok())
+            .map(str::trim)
+            .find(|v| !v.is_empty());
+        if let Some(id) = header {
+            return Some(id.chars().take(MAX_SESSION_ID_LEN).collect());
+        }
+        if let Some(user) = body.get("user").and_then(|v| v.as_str()).filter(|u| !u.is_empty()) {
+            return Some(user.chars().take(MAX_SESSION_ID_LEN).collect());
+        }
+
+        if path.starts_with("/v1/messages") && !path.contains("count_tokens") && !path.contains("batches") {
+            let request: ClaudeRequest = serde_json::from_value(body.clone()).ok()?;
+            Some(Self::extract_session_id(&request))
+        } else if path.starts_with("/v1/chat/completions") {
+            let request: OpenAIRequest = serde_json::from_value(body.clone()).ok()?;
+            Some(Self::extract_openai_session_id(&request))
+        } else if let Some(rest) = path.strip_prefix("/v1beta/models/") {
+            let model = rest.split(':').next().unwrap_or(rest);
+            body.get("contents")?;
+            Some(Self::extract_gemini_session_id(body, model))
+        } else {
+            None
+        }
+    }
+}
+
+/// 会话标识最大长度 (客户端提供的值会被截断)
+const MAX_SESSION_ID_LEN: usize = 128;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderMap;
+    use serde_json::json;
+
+    #[test]
+    fn explicit_header_and_openai_user_take_precedence() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-session-id", "run-42".parse().unwrap());
+        let body = json!({"model": "gpt-4o", "user": "agent-7", "messages": []});
+        assert_eq!(
+            SessionManager::extract_conversation_id(&headers, "/v1/chat/completions", &body).as_deref(),
+            Some("run-42")
+        );
+        assert_eq!(
+            SessionManager::extract_conversation_id(&HeaderMap::new(), "/v1/chat/completions", &body).as_deref(),
+            Some("agent-7")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_stable_fingerprint() {
+        let body = json!({
+            "model": "gemini-2.5-flash",
+            "contents": [{"role": "user", "parts": [{"text": "refactor the parser module please"}]}]
+        });
+        let path = "/v1beta/models/gemini-2.5-flash:generateContent";
+        let first = SessionManager::extract_conversation_id(&HeaderMap::new(), path, &body).unwrap();
+        let second = SessionManager::extract_conversation_id(&HeaderMap::new(), path, &body).unwrap();
+        assert!(first.starts_with("sid-"));
+        assert_eq!(first, second);
+
+        assert!(SessionManager::extract_conversation_id(&HeaderMap::new(), "/v1/models", &json!({})).is_none());
+    }
 }
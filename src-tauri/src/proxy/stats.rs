@@ -0,0 +1,216 @@
+// 统计汇总: 将请求日志按小时 / 天 (UTC) 聚合为 (模型, 账号, 密钥) 维度的汇总行并持久化，
+// 长期趋势与用量报告直接读取汇总表，不受请求日志清理的影响
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+const HOUR_MS: i64 = 3600 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// 小时级汇总保留的天数 (更早的数据只保留天级汇总)
+pub const HOURLY_RETENTION_DAYS: i64 = 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Hour,
+    Day,
+}
+
+impl Period {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Hour => "hour",
+            Period::Day => "day",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hour" | "hourly" => Some(Period::Hour),
+            "day" | "daily" => Some(Period::Day),
+            _ => None,
+        }
+    }
+
+    /// 区间长度 (毫秒)
+    pub fn millis(&self) -> i64 {
+        match self {
+            Period::Hour => HOUR_MS,
+            Period::Day => DAY_MS,
+        }
+    }
+
+    /// 时间戳 (Unix 毫秒) 所在区间的起点
+    pub fn bucket(&self, timestamp: i64) -> i64 {
+        timestamp.div_euclid(self.millis()) * self.millis()
+    }
+}
+
+/// 一个区间内某 (模型, 账号, 密钥) 组合的汇总
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rollup {
+    /// 区间起点 (Unix 毫秒)
+    pub bucket: i64,
+    pub model: Option<String>,
+    pub account: Option<String>,
+    pub key_id: Option<String>,
+    pub requests: u64,
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// 请求耗时总和 (毫秒)，用于计算平均耗时
+    pub duration_ms: u64,
+}
+
+impl Rollup {
+    /// 单条请求日志在指定粒度下的增量
+    pub fn from_log(period: Period, log: &ProxyRequestLog) -> Self {
+        Self {
+            bucket: period.bucket(log.timestamp),
+            model: log.model.clone(),
+            account: log.account.clone(),
+            key_id: log.key_id.clone(),
+            requests: 1,
+            errors: u64::from(log.status < 200 || log.status >= 400),
+            input_tokens: log.input_tokens.unwrap_or(0) as u64,
+            output_tokens: log.output_tokens.unwrap_or(0) as u64,
+            duration_ms: log.duration,
+        }
+    }
+}
+
+/// 按区间合计的趋势点
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub bucket: i64,
+    pub requests: u64,
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// 平均耗时 (毫秒)
+    pub avg_duration_ms: u64,
+}
+
+/// 小时级汇总的覆盖起点: 早于该时间的区间只能从天级汇总读取
+pub fn hourly_cutoff(now_ms: i64) -> i64 {
+    Period::Day.bucket(now_ms) - HOURLY_RETENTION_DAYS * DAY_MS
+}
+
+/// 将汇总行按区间合计为趋势序列 (按时间升序)
+pub fn trend(rollups: &[Rollup]) -> Vec<TrendPoint> {
+    let mut points: BTreeMap<i64, (TrendPoint, u64)> = BTreeMap::new();
+    for r in rollups {
+        let (point, duration) = points.entry(r.bucket).or_insert_with(|| {
+            (
+                TrendPoint {
+                    bucket: r.bucket,
+                    ..Default::default()
+                },
+                0,
+            )
+        });
+        point.requests += r.requests;
+        point.errors += r.errors;
+        point.input_tokens += r.input_tokens;
+        point.output_tokens += r.output_tokens;
+        *duration += r.duration_ms;
+    }
+    points
+        .into_values()
+        .map(|(mut point, duration)| {
+            point.avg_duration_ms = duration.checked_div(point.requests).unwrap_or(0);
+            point
+        })
+        .collect()
+}
+
+/// 趋势表格 (CLI `--usage-trend`)
+pub fn format_trend(period: Period, points: &[TrendPoint]) -> String {
+    if points.is_empty() {
+        return "no usage recorded\n".to_string();
+    }
+    let pattern = match period {
+        Period::Hour => "%Y-%m-%d %H:00",
+        Period::Day => "%Y-%m-%d",
+    };
+    let mut out = format!(
+        "{:<16} {:>8} {:>7} {:>12} {:>12} {:>9}\n",
+        "UTC", "REQ", "ERR", "IN", "OUT", "AVG MS"
+    );
+    for p in points {
+        let label = chrono::DateTime::from_timestamp_millis(p.bucket)
+            .map(|t| t.format(pattern).to_string())
+            .unwrap_or_else(|| p.bucket.to_string());
+        out.push_str(&format!(
+            "{:<16} {:>8} {:>7} {:>12} {:>12} {:>9}\n",
+            label, p.requests, p.errors, p.input_tokens, p.output_tokens, p.avg_duration_ms
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(timestamp: i64, status: u16, input: u32, output: u32) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: timestamp.to_string(),
+            timestamp,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status,
+            duration: 100,
+            model: Some("gemini-2.5-pro".to_string()),
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: Some(input),
+            output_tokens: Some(output),
+            session_id: None,
+            account: Some("a@example.com".to_string()),
+            key_id: None,
+            client_ip: None,
+            canary_target: None,
+        }
+    }
+
+    #[test]
+    fn buckets_align_to_utc_boundaries() {
+        // 2024-01-02 03:04:05 UTC
+        let ts = 1_704_164_645_000;
+        assert_eq!(Period::Hour.bucket(ts), 1_704_164_400_000);
+        assert_eq!(Period::Day.bucket(ts), 1_704_153_600_000);
+        assert_eq!(hourly_cutoff(ts), 1_704_153_600_000 - 31 * DAY_MS);
+    }
+
+    #[test]
+    fn rollup_counts_errors_and_tokens() {
+        let ok = Rollup::from_log(Period::Hour, &log(1_704_164_645_000, 200, 10, 20));
+        assert_eq!((ok.requests, ok.errors, ok.input_tokens, ok.output_tokens), (1, 0, 10, 20));
+        let failed = Rollup::from_log(Period::Day, &log(1_704_164_645_000, 429, 0, 0));
+        assert_eq!(failed.errors, 1);
+        assert_eq!(failed.bucket, 1_704_153_600_000);
+    }
+
+    #[test]
+    fn trend_sums_groups_per_bucket() {
+        let mut a = Rollup::from_log(Period::Day, &log(DAY_MS + 5, 200, 10, 20));
+        a.account = Some("b@example.com".to_string());
+        let b = Rollup::from_log(Period::Day, &log(DAY_MS + 10, 500, 1, 2));
+        let c = Rollup::from_log(Period::Day, &log(5, 200, 0, 0));
+        let points = trend(&[a, b, c]);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].bucket, 0);
+        assert_eq!(points[1].requests, 2);
+        assert_eq!(points[1].errors, 1);
+        assert_eq!(points[1].input_tokens, 11);
+        assert_eq!(points[1].avg_duration_ms, 100);
+
+        let out = format_trend(Period::Day, &points);
+        assert!(out.contains("1970-01-02"));
+        assert_eq!(format_trend(Period::Hour, &[]), "no usage recorded\n");
+    }
+}
@@ -0,0 +1,154 @@
+// 运行时状态 (GET /admin/status)，供 CLI 查询本机或远程的运行中实例
+use serde::{Deserialize, Serialize};
+
+use crate::proxy::monitor::ProxyStats;
+use crate::proxy::token_manager::AccountHealth;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeStatus {
+    pub version: String,
+    /// 启动时间 (Unix 秒)
+    pub started_at: i64,
+    pub uptime_secs: i64,
+    /// 正在处理的请求数 (含未结束的流式响应)
+    pub active_requests: usize,
+    /// 过载卸载队列中等待的请求数
+    pub queued_requests: usize,
+    pub requests: ProxyStats,
+    pub accounts: Vec<AccountHealth>,
+    /// 多实例协同的实例 ID，未启用为 None
+    #[serde(default)]
+    pub cluster_instance: Option<String>,
+    /// 流式转发指标 (慢客户端、背压等待)
+    #[serde(default)]
+    pub streams: crate::proxy::streaming::StreamStats,
+}
+
+impl RuntimeStatus {
+    /// 可参与调度的账号数
+    pub fn available_accounts(&self) -> usize {
+        self.accounts.iter().filter(|a| a.is_available()).count()
+    }
+}
+
+/// 状态摘要，随后每个账号一行
+pub fn format_status(status: &RuntimeStatus) -> String {
+    let mut out = format!(
+        "version {}, up {}\n",
+        status.version,
+        crate::models::quota::format_countdown(status.uptime_secs)
+    );
+    if let Some(instance) = &status.cluster_instance {
+        out.push_str(&format!("cluster instance: {}\n", instance));
+    }
+    out.push_str(&format!(
+        "requests: {} total ({} ok, {} failed), {} active, {} queued\n",
+        status.requests.total_requests,
+        status.requests.success_count,
+        status.requests.error_count,
+        status.active_requests,
+        status.queued_requests
+    ));
+    if status.streams.active_streams > 0 || status.streams.slow_clients > 0 {
+        out.push_str(&format!(
+            "streams: {} active, {} slow clients, {} disconnected, backpressure {}ms (max stall {}ms)\n",
+            status.streams.active_streams,
+            status.streams.slow_clients,
+            status.streams.disconnected_clients,
+            status.streams.backpressure_ms,
+            status.streams.max_stall_ms
+        ));
+    }
+    out.push_str(&format!(
+        "accounts: {}/{} available\n",
+        status.available_accounts(),
+        status.accounts.len()
+    ));
+    for account in &status.accounts {
+        let tier = account
+            .subscription_tier
+            .as_ref()
+            .map(|t| t.as_str().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let mut notes = Vec::new();
+        if let Some(secs) = account.rate_limited_secs {
+            notes.push(format!(
+                "rate limited for {}",
+                crate::models::quota::format_countdown(secs as i64)
+            ));
+        }
+        if !account.exhausted_models.is_empty() {
+            notes.push(format!("exhausted: {}", account.exhausted_models.join(", ")));
+        }
+        if account.token_expires_in <= 0 {
+            notes.push("token expired".to_string());
+        }
+        let state = if notes.is_empty() { "ok".to_string() } else { notes.join("; ") };
+        out.push_str(&format!("    {:<40} {:<8} {}\n", account.email, tier, state));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(email: &str, rate_limited_secs: Option<u64>) -> AccountHealth {
+        AccountHealth {
+            email: email.to_string(),
+            subscription_tier: Some(crate::models::SubscriptionTier::Pro),
+            rate_limited_secs,
+            exhausted_models: Vec::new(),
+            token_expires_in: 1800,
+            quota_updated_at: None,
+        }
+    }
+
+    #[test]
+    fn formats_summary_and_account_states() {
+        let status = RuntimeStatus {
+            version: "1.0.0".to_string(),
+            started_at: 0,
+            uptime_secs: 3720,
+            active_requests: 2,
+            queued_requests: 0,
+            requests: ProxyStats {
+                total_requests: 10,
+                success_count: 9,
+                error_count: 1,
+            },
+            accounts: vec![account("a@example.com", None), account("b@example.com", Some(90))],
+            cluster_instance: None,
+            streams: crate::proxy::streaming::StreamStats {
+                active_streams: 1,
+                slow_clients: 2,
+                ..Default::default()
+            },
+        };
+        let out = format_status(&status);
+        assert!(out.starts_with("version 1.0.0, up 1h02m\n"));
+        assert!(out.contains("10 total (9 ok, 1 failed), 2 active"));
+        assert!(out.contains("accounts: 1/2 available"));
+        assert!(out.contains("rate limited for 1m30s"));
+        assert!(out.contains("streams: 1 active, 2 slow clients"));
+    }
+
+    #[test]
+    fn status_round_trips_through_json() {
+        let status = RuntimeStatus {
+            version: "1.0.0".to_string(),
+            started_at: 1,
+            uptime_secs: 2,
+            active_requests: 0,
+            queued_requests: 0,
+            requests: ProxyStats::default(),
+            accounts: vec![account("a@example.com", None)],
+            cluster_instance: Some("node-1".to_string()),
+            streams: Default::default(),
+        };
+        let json = serde_json::to_string(&status).unwrap();
+        let parsed: RuntimeStatus = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.accounts[0].email, "a@example.com");
+        assert_eq!(parsed.cluster_instance.as_deref(), Some("node-1"));
+    }
+}
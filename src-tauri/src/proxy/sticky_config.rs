@@ -9,6 +9,9 @@ pub enum SchedulingMode {
     Balance,
     /// 性能优先 (Performance-first): 纯轮询模式 (Round-robin)，账号负载最均衡，但不利用缓存
     PerformanceFirst,
+    /// 延迟优先 (Latency-first): 保持粘性会话，新分配时按各账号滚动 p50 延迟加权，偏向更快的账号
+    #[serde(alias = "latency")]
+    LatencyFirst,
 }
 
 impl Default for SchedulingMode {
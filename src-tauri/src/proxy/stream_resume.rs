@@ -0,0 +1,320 @@
+// 流式响应中断续传
+//
+// 上游 SSE 流在给出 finishReason 之前中断 (连接错误或空闲超时) 时，换一个账号重新请求：
+// 已生成的文本作为 model 轮次附在原请求之后，再追加一条续写指令。续写的流直接拼接在原流之后，
+// 协议转换层看到的仍是一条完整的 Gemini 流。已输出工具调用或图片的流不续传 (无法安全拼接)。
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use crate::proxy::config::StreamResumptionConfig;
+use crate::proxy::middleware::response_headers::STREAM_RESUME_HEADER;
+use crate::proxy::streaming::SseLineBuffer;
+use crate::proxy::timeouts::EffectiveTimeouts;
+use crate::proxy::token_manager::TokenManager;
+use crate::proxy::upstream::client::UpstreamClient;
+
+pub type UpstreamStream = Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>;
+
+static CONFIG: Lazy<RwLock<StreamResumptionConfig>> =
+    Lazy::new(|| RwLock::new(StreamResumptionConfig::default()));
+
+/// 更新续传配置
+pub fn configure(config: &StreamResumptionConfig) {
+    let mut current = CONFIG.write().unwrap_or_else(|e| e.into_inner());
+    if *current != *config {
+        *current = config.clone();
+        tracing::info!("流式续传配置已更新: enabled={}, max_attempts={}", config.enabled, config.max_attempts);
+    }
+}
+
+fn current_config() -> StreamResumptionConfig {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 续传所需的原始请求信息
+pub struct ResumeContext {
+    token_manager: Arc<TokenManager>,
+    upstream: Arc<UpstreamClient>,
+    /// 发往上游的 v1internal 请求体
+    body: Value,
+    quota_group: String,
+    model: String,
+    account: String,
+    timeouts: EffectiveTimeouts,
+    config: StreamResumptionConfig,
+}
+
+impl ResumeContext {
+    /// 未启用续传时返回 None (不复制请求体)
+    pub fn new(
+        token_manager: &Arc<TokenManager>,
+        upstream: &Arc<UpstreamClient>,
+        body: &Value,
+        quota_group: &str,
+        model: &str,
+        account: &str,
+        timeouts: &EffectiveTimeouts,
+    ) -> Option<Self> {
+        let config = current_config();
+        if !config.enabled || config.max_attempts == 0 {
+            return None;
+        }
+        Some(Self {
+            token_manager: token_manager.clone(),
+            upstream: upstream.clone(),
+            body: body.clone(),
+            quota_group: quota_group.to_string(),
+            model: model.to_string(),
+            account: account.to_string(),
+            timeouts: *timeouts,
+            config,
+        })
+    }
+
+    /// 换号发起续写请求，返回新的上游流与所用账号
+    async fn reopen(&self, progress: &Progress) -> Result<(UpstreamStream, String), String> {
+        let (access_token, project_id, email) = self
+            .token_manager
+            .get_token_for_model(&self.quota_group, true, None, Some(&self.model))
+            .await?;
+        let body = continuation_body(&self.body, &progress.text, &self.config.continue_prompt, &project_id);
+        let response = self
+            .upstream
+            .call_v1_internal("streamGenerateContent", &access_token, body, Some("alt=sse"), &self.timeouts)
+            .await?;
+        if !response.status().is_success() {
+            return Err(format!("续传请求返回 {}", response.status()));
+        }
+        let stream = crate::proxy::timeouts::with_idle_timeout(response.bytes_stream(), self.timeouts.stream_idle);
+        Ok((stream, email))
+    }
+}
+
+/// 已转发内容的进度
+#[derive(Debug, Default)]
+struct Progress {
+    /// 已生成的正文 (不含思考内容)
+    text: String,
+    finished: bool,
+    /// 出现工具调用、图片等无法续写的内容
+    blocked: bool,
+}
+
+impl Progress {
+    fn observe(&mut self, line: &[u8]) {
+        let Ok(line) = std::str::from_utf8(line) else {
+            return;
+        };
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            return;
+        };
+        let Ok(json) = serde_json::from_str::<Value>(data.trim()) else {
+            return;
+        };
+        let response = json.get("response").unwrap_or(&json);
+        let Some(candidate) = response.get("candidates").and_then(|c| c.get(0)) else {
+            return;
+        };
+        if let Some(parts) = candidate.pointer("/content/parts").and_then(|p| p.as_array()) {
+            for part in parts {
+                if part.get("thought").and_then(|t| t.as_bool()).unwrap_or(false) {
+                    continue;
+                }
+                if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                    self.text.push_str(text);
+                } else if part.get("functionCall").is_some()
+                    || part.get("inlineData").is_some()
+                    || part.get("executableCode").is_some()
+                {
+                    self.blocked = true;
+                }
+            }
+        }
+        if candidate.get("finishReason").is_some() {
+            self.finished = true;
+        }
+    }
+}
+
+/// 续写请求体: 原请求 + 已生成内容 (model 轮次) + 续写指令；尚未生成任何内容时按原请求重发
+fn continuation_body(original: &Value, generated: &str, prompt: &str, project_id: &str) -> Value {
+    let mut body = original.clone();
+    body["project"] = json!(project_id);
+    if generated.is_empty() {
+        return body;
+    }
+    if let Some(contents) = body.pointer_mut("/request/contents").and_then(|c| c.as_array_mut()) {
+        contents.push(json!({ "role": "model", "parts": [{ "text": generated }] }));
+        contents.push(json!({ "role": "user", "parts": [{ "text": prompt }] }));
+    }
+    // 续写部分不再输出思考内容，避免在正文之后重新开始思考块
+    if let Some(generation) = body
+        .pointer_mut("/request/generationConfig")
+        .and_then(|g| g.as_object_mut())
+    {
+        generation.remove("thinkingConfig");
+    }
+    body
+}
+
+/// 已启用续传的流在响应头中注明
+pub fn annotate(builder: axum::http::response::Builder, armed: bool) -> axum::http::response::Builder {
+    if armed {
+        builder.header(STREAM_RESUME_HEADER, "enabled")
+    } else {
+        builder
+    }
+}
+
+/// 上游流式响应 (带空闲超时)；`resume` 为 Some 时，中断后换号续写并拼接到同一条流上
+///
+/// 只转发完整的 SSE 行，中断时残缺的半行会被丢弃，保证拼接处格式正确
+pub fn upstream_stream(
+    response: reqwest::Response,
+    timeouts: &EffectiveTimeouts,
+    resume: Option<ResumeContext>,
+) -> UpstreamStream {
+    let first = crate::proxy::timeouts::with_idle_timeout(response.bytes_stream(), timeouts.stream_idle);
+    let Some(ctx) = resume else {
+        return first;
+    };
+
+    Box::pin(async_stream::stream! {
+        let mut current = first;
+        let mut account = ctx.account.clone();
+        let mut progress = Progress::default();
+        let mut attempts = 0;
+        loop {
+            let mut buffer = SseLineBuffer::new();
+            let mut error: Option<reqwest::Error> = None;
+            let mut reason = "上游流在结束前关闭".to_string();
+            while let Some(item) = current.next().await {
+                match item {
+                    Ok(bytes) => {
+                        if let Err(e) = buffer.push(&bytes) {
+                            reason = e;
+                            break;
+                        }
+                        while let Some(line) = buffer.next_line() {
+                            progress.observe(&line);
+                            yield Ok(line.freeze());
+                        }
+                    }
+                    Err(e) => {
+                        reason = e.to_string();
+                        error = Some(e);
+                        break;
+                    }
+                }
+            }
+
+            if progress.finished || progress.blocked || attempts >= ctx.config.max_attempts {
+                let rest: BytesMut = buffer.take_rest();
+                if !rest.is_empty() {
+                    yield Ok(rest.freeze());
+                }
+                if let Some(e) = error {
+                    yield Err(e);
+                }
+                break;
+            }
+
+            attempts += 1;
+            tracing::warn!(
+                "流式响应中断 (账号: {}, 已生成 {} 字符): {}，尝试续传 {}/{}",
+                account,
+                progress.text.chars().count(),
+                reason,
+                attempts,
+                ctx.config.max_attempts
+            );
+            match ctx.reopen(&progress).await {
+                Ok((stream, email)) => {
+                    tracing::info!("流式续传已接续 (账号: {} -> {})", account, email);
+                    account = email;
+                    current = stream;
+                }
+                Err(e) => {
+                    tracing::warn!("流式续传失败: {}", e);
+                    if let Some(e) = error {
+                        yield Err(e);
+                    }
+                    break;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe(progress: &mut Progress, chunk: Value) {
+        progress.observe(format!("data: {}\n", json!({ "response": chunk })).as_bytes());
+    }
+
+    #[test]
+    fn tracks_text_and_finish() {
+        let mut progress = Progress::default();
+        observe(
+            &mut progress,
+            json!({"candidates": [{"content": {"parts": [{"text": "think", "thought": true}, {"text": "Hello"}]}}]}),
+        );
+        observe(&mut progress, json!({"candidates": [{"content": {"parts": [{"text": ", world"}]}}]}));
+        assert_eq!(progress.text, "Hello, world");
+        assert!(!progress.finished);
+
+        observe(
+            &mut progress,
+            json!({"candidates": [{"content": {"parts": [{"text": "!"}]}, "finishReason": "STOP"}]}),
+        );
+        assert!(progress.finished);
+        assert!(!progress.blocked);
+
+        // 非 data 行与无法解析的行忽略
+        progress.observe(b": keepalive\n");
+        progress.observe(b"data: [DONE]\n");
+        assert_eq!(progress.text, "Hello, world!");
+    }
+
+    #[test]
+    fn tool_calls_block_resumption() {
+        let mut progress = Progress::default();
+        observe(
+            &mut progress,
+            json!({"candidates": [{"content": {"parts": [{"functionCall": {"name": "f", "args": {}}}]}}]}),
+        );
+        assert!(progress.blocked);
+    }
+
+    #[test]
+    fn continuation_appends_prefix_and_prompt() {
+        let original = json!({
+            "project": "p1",
+            "model": "gemini-2.5-pro",
+            "request": {
+                "contents": [{"role": "user", "parts": [{"text": "Write a poem"}]}],
+                "generationConfig": {"maxOutputTokens": 100, "thinkingConfig": {"thinkingBudget": 1024}}
+            }
+        });
+        let body = continuation_body(&original, "Roses are", "continue", "p2");
+        assert_eq!(body["project"], "p2");
+        let contents = body["request"]["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[1]["parts"][0]["text"], "Roses are");
+        assert_eq!(contents[2]["parts"][0]["text"], "continue");
+        assert!(body["request"]["generationConfig"].get("thinkingConfig").is_none());
+        assert_eq!(body["request"]["generationConfig"]["maxOutputTokens"], 100);
+
+        // 尚未生成内容时原样重发
+        let retry = continuation_body(&original, "", "continue", "p2");
+        assert_eq!(retry["request"], original["request"]);
+        assert_eq!(retry["project"], "p2");
+    }
+}
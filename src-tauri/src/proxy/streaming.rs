@@ -0,0 +1,233 @@
+// 流式响应的内存上限与背压
+//
+// 转换管线按需拉取上游数据 (客户端不读取时不再读取上游)，中间件转发使用容量固定的通道，
+// 单条 SSE 行设置长度上限。慢客户端的等待时间计入运行时指标 (`GET /admin/status`)。
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// 中间件转发通道可缓存的数据块数 (超出后等待客户端读取，即背压)
+pub const FORWARD_CHANNEL_CAPACITY: usize = 16;
+/// 单条 SSE 行的最大长度 (含内联图片等大块数据)，超出视为上游异常
+pub const MAX_SSE_LINE_BYTES: usize = 32 * 1024 * 1024;
+/// 单次写入等待超过该时间的流计为慢客户端
+const SLOW_CLIENT_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// 按行切分上游 SSE 数据，行长度受限
+pub struct SseLineBuffer {
+    buffer: BytesMut,
+    /// 已确认不含换行符的前缀长度，避免长行反复从头扫描
+    scanned: usize,
+    max_line: usize,
+}
+
+impl Default for SseLineBuffer {
+    fn default() -> Self {
+        Self::with_limit(MAX_SSE_LINE_BYTES)
+    }
+}
+
+impl SseLineBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limit(max_line: usize) -> Self {
+        Self {
+            buffer: BytesMut::new(),
+            scanned: 0,
+            max_line,
+        }
+    }
+
+    /// 追加数据块；未完成的行超过上限时返回错误
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.buffer.extend_from_slice(chunk);
+        let pending = match self.buffer[self.scanned..].iter().position(|&b| b == b'\n') {
+            Some(pos) => self.scanned + pos,
+            None => self.buffer.len(),
+        };
+        if pending > self.max_line {
+            let len = self.buffer.len();
+            self.buffer.clear();
+            self.scanned = 0;
+            return Err(format!(
+                "SSE line exceeds {} bytes ({} bytes buffered)",
+                self.max_line, len
+            ));
+        }
+        Ok(())
+    }
+
+    /// 取出下一条完整的行 (含结尾换行符)
+    pub fn next_line(&mut self) -> Option<BytesMut> {
+        match self.buffer[self.scanned..].iter().position(|&b| b == b'\n') {
+            Some(pos) => {
+                let line = self.buffer.split_to(self.scanned + pos + 1);
+                self.scanned = 0;
+                Some(line)
+            }
+            None => {
+                self.scanned = self.buffer.len();
+                None
+            }
+        }
+    }
+
+    /// 当前缓存的字节数
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// 取出剩余未以换行结尾的数据
+    pub fn take_rest(&mut self) -> BytesMut {
+        self.scanned = 0;
+        self.buffer.split()
+    }
+}
+
+/// 流式转发指标
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamStats {
+    /// 正在转发的响应流
+    pub active_streams: usize,
+    /// 出现过明显写入等待的流 (慢客户端)
+    pub slow_clients: u64,
+    /// 转发途中断开的客户端
+    pub disconnected_clients: u64,
+    /// 因客户端读取慢而等待的累计时间 (毫秒)
+    pub backpressure_ms: u64,
+    /// 单次最长等待 (毫秒)
+    pub max_stall_ms: u64,
+}
+
+struct StreamMetrics {
+    active: AtomicUsize,
+    slow: AtomicU64,
+    disconnected: AtomicU64,
+    wait_ms: AtomicU64,
+    max_stall_ms: AtomicU64,
+}
+
+static METRICS: StreamMetrics = StreamMetrics {
+    active: AtomicUsize::new(0),
+    slow: AtomicU64::new(0),
+    disconnected: AtomicU64::new(0),
+    wait_ms: AtomicU64::new(0),
+    max_stall_ms: AtomicU64::new(0),
+};
+
+/// 当前进程的流式转发指标
+pub fn stats() -> StreamStats {
+    StreamStats {
+        active_streams: METRICS.active.load(Ordering::Relaxed),
+        slow_clients: METRICS.slow.load(Ordering::Relaxed),
+        disconnected_clients: METRICS.disconnected.load(Ordering::Relaxed),
+        backpressure_ms: METRICS.wait_ms.load(Ordering::Relaxed),
+        max_stall_ms: METRICS.max_stall_ms.load(Ordering::Relaxed),
+    }
+}
+
+/// 有界转发通道的发送端，记录背压等待与客户端断开
+pub struct StreamForwarder<T> {
+    tx: mpsc::Sender<T>,
+    slow: bool,
+}
+
+/// 创建有界转发通道；接收端作为响应体返回给客户端
+pub fn forward_channel<T>() -> (StreamForwarder<T>, ReceiverStream<T>) {
+    let (tx, rx) = mpsc::channel(FORWARD_CHANNEL_CAPACITY);
+    METRICS.active.fetch_add(1, Ordering::Relaxed);
+    (StreamForwarder { tx, slow: false }, ReceiverStream::new(rx))
+}
+
+impl<T> StreamForwarder<T> {
+    /// 发送数据块，通道已满时等待客户端读取；客户端已断开时返回 false
+    pub async fn send(&mut self, item: T) -> bool {
+        let item = match self.tx.try_send(item) {
+            Ok(()) => return true,
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                METRICS.disconnected.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            Err(mpsc::error::TrySendError::Full(item)) => item,
+        };
+
+        let start = Instant::now();
+        let sent = self.tx.send(item).await.is_ok();
+        let waited = start.elapsed();
+        let waited_ms = waited.as_millis() as u64;
+        METRICS.wait_ms.fetch_add(waited_ms, Ordering::Relaxed);
+        METRICS.max_stall_ms.fetch_max(waited_ms, Ordering::Relaxed);
+        if waited >= SLOW_CLIENT_THRESHOLD && !self.slow {
+            self.slow = true;
+            METRICS.slow.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!("客户端读取缓慢，流式响应等待了 {} ms", waited_ms);
+        }
+        if !sent {
+            METRICS.disconnected.fetch_add(1, Ordering::Relaxed);
+        }
+        sent
+    }
+}
+
+impl<T> Drop for StreamForwarder<T> {
+    fn drop(&mut self) {
+        METRICS.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn splits_lines_across_chunks() {
+        let mut buffer = SseLineBuffer::new();
+        buffer.push(b"data: {\"a\"").unwrap();
+        assert!(buffer.next_line().is_none());
+        buffer.push(b":1}\n\ndata: x\n").unwrap();
+        assert_eq!(&buffer.next_line().unwrap()[..], b"data: {\"a\":1}\n");
+        assert_eq!(&buffer.next_line().unwrap()[..], b"\n");
+        assert_eq!(&buffer.next_line().unwrap()[..], b"data: x\n");
+        assert!(buffer.next_line().is_none());
+        assert_eq!(buffer.buffered(), 0);
+    }
+
+    #[test]
+    fn rejects_oversized_lines() {
+        let mut buffer = SseLineBuffer::with_limit(8);
+        buffer.push(b"0123").unwrap();
+        assert!(buffer.next_line().is_none());
+        assert!(buffer.push(b"456789").is_err());
+        assert_eq!(buffer.buffered(), 0);
+
+        // 完整的行不受尚未读取的后续数据影响
+        buffer.push(b"short\n").unwrap();
+        assert_eq!(&buffer.next_line().unwrap()[..], b"short\n");
+    }
+
+    #[tokio::test]
+    async fn forwarder_applies_backpressure_and_detects_disconnect() {
+        let (mut tx, rx) = forward_channel::<u32>();
+        for i in 0..FORWARD_CHANNEL_CAPACITY as u32 {
+            assert!(tx.send(i).await);
+        }
+        // 通道已满: 发送需等待接收端读取
+        let reader = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            rx.take(FORWARD_CHANNEL_CAPACITY + 1).collect::<Vec<_>>().await
+        });
+        assert!(tx.send(99).await);
+        let received = reader.await.unwrap();
+        assert_eq!(received.len(), FORWARD_CHANNEL_CAPACITY + 1);
+        assert!(stats().max_stall_ms >= 10);
+
+        // 接收端已释放
+        assert!(!tx.send(100).await);
+    }
+}
@@ -0,0 +1,95 @@
+// 团队路由: 按 OpenAI-Organization / OpenAI-Project 请求头把不同团队限定到各自的账号组
+use crate::proxy::config::{TeamRoute, TeamRoutingConfig};
+
+pub const ORGANIZATION_HEADER: &str = "openai-organization";
+pub const PROJECT_HEADER: &str = "openai-project";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TeamRouteDecision<'a> {
+    /// 未启用或请求未携带组织 / 项目头: 使用整个账号池
+    Unscoped,
+    /// 限定到规则中的账号
+    Scoped(&'a TeamRoute),
+    /// 未命中任何规则且配置为拒绝
+    Rejected,
+}
+
+fn matches(rule: &TeamRoute, organization: Option<&str>, project: Option<&str>) -> bool {
+    if rule.organization.is_none() && rule.project.is_none() {
+        return false;
+    }
+    let field = |expected: &Option<String>, actual: Option<&str>| match expected {
+        Some(expected) => actual == Some(expected.as_str()),
+        None => true,
+    };
+    field(&rule.organization, organization) && field(&rule.project, project)
+}
+
+pub fn resolve<'a>(
+    config: &'a TeamRoutingConfig,
+    organization: Option<&str>,
+    project: Option<&str>,
+) -> TeamRouteDecision<'a> {
+    if !config.enabled || (organization.is_none() && project.is_none()) {
+        return TeamRouteDecision::Unscoped;
+    }
+    match config.rules.iter().find(|rule| matches(rule, organization, project)) {
+        Some(rule) => TeamRouteDecision::Scoped(rule),
+        None if config.reject_unmatched => TeamRouteDecision::Rejected,
+        None => TeamRouteDecision::Unscoped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(organization: Option<&str>, project: Option<&str>, account: &str) -> TeamRoute {
+        TeamRoute {
+            organization: organization.map(str::to_string),
+            project: project.map(str::to_string),
+            accounts: vec![account.to_string()],
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let config = TeamRoutingConfig {
+            enabled: true,
+            rules: vec![
+                route(Some("org-a"), Some("proj-x"), "x@example.com"),
+                route(Some("org-a"), None, "a@example.com"),
+                route(None, Some("proj-y"), "y@example.com"),
+            ],
+            reject_unmatched: false,
+        };
+
+        let scoped = |org, project| match resolve(&config, org, project) {
+            TeamRouteDecision::Scoped(rule) => Some(rule.accounts[0].clone()),
+            _ => None,
+        };
+        assert_eq!(scoped(Some("org-a"), Some("proj-x")).as_deref(), Some("x@example.com"));
+        assert_eq!(scoped(Some("org-a"), Some("proj-z")).as_deref(), Some("a@example.com"));
+        assert_eq!(scoped(None, Some("proj-y")).as_deref(), Some("y@example.com"));
+        assert_eq!(resolve(&config, Some("org-b"), None), TeamRouteDecision::Unscoped);
+        assert_eq!(resolve(&config, None, None), TeamRouteDecision::Unscoped);
+    }
+
+    #[test]
+    fn unmatched_teams_can_be_rejected() {
+        let config = TeamRoutingConfig {
+            enabled: true,
+            rules: vec![route(Some("org-a"), None, "a@example.com")],
+            reject_unmatched: true,
+        };
+        assert_eq!(resolve(&config, Some("org-b"), None), TeamRouteDecision::Rejected);
+        // 未携带请求头的请求不受影响
+        assert_eq!(resolve(&config, None, None), TeamRouteDecision::Unscoped);
+
+        let disabled = TeamRoutingConfig {
+            enabled: false,
+            ..config
+        };
+        assert_eq!(resolve(&disabled, Some("org-b"), None), TeamRouteDecision::Unscoped);
+    }
+}
@@ -0,0 +1,88 @@
+// 订阅等级策略: 将高等级账号预留给指定模型或 API 密钥
+use crate::models::SubscriptionTier;
+use crate::proxy::config::TierPolicyConfig;
+
+fn model_matches(pattern: &str, model: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => model.starts_with(prefix),
+        None => pattern == model,
+    }
+}
+
+/// 该等级的账号能否服务当前请求
+///
+/// 没有预留规则的等级不受限制；有预留规则时，模型或密钥命中任意一条即可
+pub fn allows(
+    policy: &TierPolicyConfig,
+    tier: Option<&SubscriptionTier>,
+    model: Option<&str>,
+    key_id: Option<&str>,
+) -> bool {
+    if !policy.enabled {
+        return true;
+    }
+    let Some(tier) = tier else {
+        return true;
+    };
+    let mut reservations = policy.reservations.iter().filter(|r| &r.tier == tier).peekable();
+    if reservations.peek().is_none() {
+        return true;
+    }
+    reservations.any(|r| {
+        model.map_or(false, |m| r.models.iter().any(|p| model_matches(p, m)))
+            || key_id.map_or(false, |k| r.keys.iter().any(|key| key == k))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::TierReservation;
+
+    fn policy() -> TierPolicyConfig {
+        TierPolicyConfig {
+            enabled: true,
+            reservations: vec![TierReservation {
+                tier: SubscriptionTier::Ultra,
+                models: vec!["claude-opus*".to_string()],
+                keys: vec!["a1b2c3d4".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn reserved_tier_only_serves_matching_requests() {
+        let policy = policy();
+        let ultra = Some(&SubscriptionTier::Ultra);
+        assert!(allows(&policy, ultra, Some("claude-opus-4-5-thinking"), None));
+        assert!(allows(&policy, ultra, Some("gemini-2.5-flash"), Some("a1b2c3d4")));
+        assert!(!allows(&policy, ultra, Some("gemini-2.5-flash"), Some("ffffffff")));
+        assert!(!allows(&policy, ultra, None, None));
+    }
+
+    #[test]
+    fn unreserved_tiers_and_disabled_policy_are_unrestricted() {
+        let policy = policy();
+        assert!(allows(&policy, Some(&SubscriptionTier::Pro), Some("gemini-2.5-flash"), None));
+        assert!(allows(&policy, None, None, None));
+
+        let disabled = TierPolicyConfig {
+            enabled: false,
+            ..policy
+        };
+        assert!(allows(&disabled, Some(&SubscriptionTier::Ultra), None, None));
+    }
+
+    #[test]
+    fn parses_upstream_tier_ids() {
+        assert_eq!(SubscriptionTier::parse("g1-ultra-tier"), SubscriptionTier::Ultra);
+        assert_eq!(SubscriptionTier::parse("PRO"), SubscriptionTier::Pro);
+        assert_eq!(SubscriptionTier::parse("free-tier"), SubscriptionTier::Free);
+        assert_eq!(
+            SubscriptionTier::parse("enterprise"),
+            SubscriptionTier::Other("enterprise".to_string())
+        );
+        let tier: SubscriptionTier = serde_json::from_str("\"g1-pro-tier\"").unwrap();
+        assert_eq!(serde_json::to_string(&tier).unwrap(), "\"PRO\"");
+    }
+}
@@ -0,0 +1,256 @@
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::proxy::config::{ProxyConfig, TimeoutConfig};
+
+/// 路由超时配置 (从 ProxyConfig 提取，支持热更新)
+#[derive(Debug, Clone)]
+pub struct RouteTimeouts {
+    pub request_timeout: u64,
+    pub config: TimeoutConfig,
+}
+
+/// 某个路由最终生效的超时
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectiveTimeouts {
+    pub connect: Duration,
+    /// 非流式请求总超时
+    pub request: Duration,
+    /// 流式响应空闲超时 (None 表示不限制)
+    pub stream_idle: Option<Duration>,
+    /// 流式响应心跳间隔 (None 表示不发送)
+    pub heartbeat: Option<Duration>,
+}
+
+impl RouteTimeouts {
+    pub fn from_proxy_config(config: &ProxyConfig) -> Self {
+        Self {
+            request_timeout: config.request_timeout,
+            config: config.timeouts.clone(),
+        }
+    }
+
+    /// 按最长前缀匹配路由覆盖并计算生效超时
+    pub fn resolve(&self, route: &str) -> EffectiveTimeouts {
+        let route_override = self
+            .config
+            .routes
+            .iter()
+            .filter(|(prefix, _)| matches_route_prefix(route, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, o)| o);
+
+        let connect = route_override
+            .and_then(|o| o.connect_timeout)
+            .unwrap_or(self.config.connect_timeout);
+        let request = route_override
+            .and_then(|o| o.request_timeout)
+            .unwrap_or(self.request_timeout);
+        let stream_idle = route_override
+            .and_then(|o| o.stream_idle_timeout)
+            .unwrap_or(self.config.stream_idle_timeout);
+        let heartbeat = route_override
+            .and_then(|o| o.stream_heartbeat_interval)
+            .unwrap_or(self.config.stream_heartbeat_interval);
+
+        EffectiveTimeouts {
+            connect: Duration::from_secs(connect.max(1)),
+            request: Duration::from_secs(request.max(5)),
+            stream_idle: (stream_idle > 0).then_some(Duration::from_secs(stream_idle)),
+            heartbeat: (heartbeat > 0).then_some(Duration::from_secs(heartbeat)),
+        }
+    }
+}
+
+/// 按路径段匹配前缀 (`/v1` 匹配 `/v1/messages`，但不匹配 `/v1beta/models`)
+fn matches_route_prefix(route: &str, prefix: &str) -> bool {
+    match route.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'),
+        None => false,
+    }
+}
+
+impl Default for EffectiveTimeouts {
+    fn default() -> Self {
+        RouteTimeouts::from_proxy_config(&ProxyConfig::default()).resolve("")
+    }
+}
+
+/// 为上游流添加空闲超时：超过 `idle` 未收到数据块时结束流
+pub fn with_idle_timeout<T, S>(
+    stream: S,
+    idle: Option<Duration>,
+) -> Pin<Box<dyn Stream<Item = T> + Send>>
+where
+    T: Send + 'static,
+    S: Stream<Item = T> + Send + 'static,
+{
+    let Some(idle) = idle else {
+        return Box::pin(stream);
+    };
+
+    Box::pin(async_stream::stream! {
+        let mut stream = Box::pin(stream);
+        loop {
+            match tokio::time::timeout(idle, stream.next()).await {
+                Ok(Some(item)) => yield item,
+                Ok(None) => break,
+                Err(_) => {
+                    tracing::warn!("上游流式响应超过 {} 秒未返回数据，已中断", idle.as_secs());
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// SSE 注释行，所有符合规范的客户端都会忽略
+pub const SSE_COMMENT_HEARTBEAT: &str = ": keepalive\n\n";
+/// Anthropic 协议的 ping 事件
+pub const CLAUDE_PING_HEARTBEAT: &str = "event: ping\ndata: {\"type\": \"ping\"}\n\n";
+
+/// 为下游 SSE 流注入心跳: 超过 `interval` 未产生事件时发送 `ping`。
+/// 首个事件之前只发送 SSE 注释 (部分客户端要求协议事件以 message_start 开头)。
+/// 流中的每一项必须是完整的 SSE 事件，心跳只会插在事件之间。
+pub fn with_heartbeat<E, S>(
+    stream: S,
+    interval: Option<Duration>,
+    ping: &'static str,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, E>> + Send>>
+where
+    E: Send + 'static,
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+{
+    let Some(interval) = interval else {
+        return Box::pin(stream);
+    };
+
+    Box::pin(async_stream::stream! {
+        let mut stream = Box::pin(stream);
+        let mut started = false;
+        loop {
+            match tokio::time::timeout(interval, stream.next()).await {
+                Ok(Some(item)) => {
+                    started = true;
+                    yield item;
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    let frame = if started { ping } else { SSE_COMMENT_HEARTBEAT };
+                    yield Ok(Bytes::from_static(frame.as_bytes()));
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::RouteTimeoutOverride;
+
+    fn timeouts() -> RouteTimeouts {
+        let mut config = TimeoutConfig::default();
+        config.routes.insert(
+            "/v1".to_string(),
+            RouteTimeoutOverride {
+                connect_timeout: Some(5),
+                ..Default::default()
+            },
+        );
+        config.routes.insert(
+            "/v1/embeddings".to_string(),
+            RouteTimeoutOverride {
+                request_timeout: Some(15),
+                ..Default::default()
+            },
+        );
+        config.routes.insert(
+            "/v1/messages".to_string(),
+            RouteTimeoutOverride {
+                stream_idle_timeout: Some(0),
+                ..Default::default()
+            },
+        );
+        RouteTimeouts {
+            request_timeout: 120,
+            config,
+        }
+    }
+
+    #[test]
+    fn falls_back_to_global_values() {
+        // `/v1` 不应匹配 `/v1beta`
+        let t = timeouts().resolve("/v1beta/models/gemini-3-flash");
+        assert_eq!(t.connect, Duration::from_secs(20));
+        assert_eq!(t.request, Duration::from_secs(120));
+        assert_eq!(t.stream_idle, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let t = timeouts().resolve("/v1/embeddings");
+        assert_eq!(t.request, Duration::from_secs(15));
+        // 最长前缀未设置的项不会回落到较短前缀
+        assert_eq!(t.connect, Duration::from_secs(20));
+
+        let t = timeouts().resolve("/v1/chat/completions");
+        assert_eq!(t.connect, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn zero_disables_stream_idle_timeout() {
+        let t = timeouts().resolve("/v1/messages");
+        assert_eq!(t.stream_idle, None);
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_ends_stalled_stream() {
+        let stalled = futures::stream::iter(vec![1, 2]).chain(futures::stream::pending());
+        let items: Vec<i32> = with_idle_timeout(stalled, Some(Duration::from_millis(20)))
+            .collect()
+            .await;
+        assert_eq!(items, vec![1, 2]);
+    }
+
+    #[test]
+    fn heartbeat_interval_resolves_per_route() {
+        let mut t = timeouts();
+        assert_eq!(t.resolve("/v1/messages").heartbeat, Some(Duration::from_secs(15)));
+        t.config.routes.insert(
+            "/v1beta".to_string(),
+            RouteTimeoutOverride {
+                stream_heartbeat_interval: Some(0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(t.resolve("/v1beta/models/gemini-3-flash").heartbeat, None);
+    }
+
+    #[tokio::test]
+    async fn heartbeat_fills_silent_gaps() {
+        let silent = async_stream::stream! {
+            tokio::time::sleep(Duration::from_millis(70)).await;
+            yield Ok::<_, String>(Bytes::from_static(b"event: message_start\n\n"));
+            tokio::time::sleep(Duration::from_millis(70)).await;
+            yield Ok(Bytes::from_static(b"event: message_stop\n\n"));
+        };
+        let frames: Vec<Bytes> = with_heartbeat(silent, Some(Duration::from_millis(20)), CLAUDE_PING_HEARTBEAT)
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+        let start = frames
+            .iter()
+            .position(|f| f.as_ref() == b"event: message_start\n\n")
+            .unwrap();
+        // 首个事件之前只有 SSE 注释，之后才是协议 ping
+        assert!(start > 0);
+        assert!(frames[..start].iter().all(|f| f.as_ref() == SSE_COMMENT_HEARTBEAT.as_bytes()));
+        let pings = &frames[start + 1..frames.len() - 1];
+        assert!(!pings.is_empty());
+        assert!(pings.iter().all(|f| f.as_ref() == CLAUDE_PING_HEARTBEAT.as_bytes()));
+        assert_eq!(frames.last().unwrap().as_ref(), b"event: message_stop\n\n");
+    }
+}
@@ -1,13 +1,88 @@
 // 移除冗余的顶层导入，因为这些在代码中已由 full path 或局部导入处理
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use std::collections::HashSet;
-use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use crate::models::quota::ModelQuota;
+use crate::modules::storage::{Collection, StorageBackend};
+use crate::proxy::config::QuotaThresholdConfig;
+use crate::models::SubscriptionTier;
+use crate::proxy::config::{
+    ContextOverflowConfig, ContextOverflowStrategy, ModelCapabilityConfig, ModelListConfig, QuotaCacheConfig, RetryPolicyConfig, ScheduleRoutingConfig, TeamRoutingConfig,
+    TierPolicyConfig, UsageCapConfig,
+};
+use crate::proxy::latency::LatencyTracker;
+use crate::proxy::usage_caps::UsageTracker;
 use crate::proxy::rate_limit::RateLimitTracker;
 use crate::proxy::sticky_config::StickySessionConfig;
 
+/// 请求路径上的刷新阈值：距过期不足该秒数时同步刷新
+const REFRESH_AHEAD_SECS: i64 = 300;
+/// 后台预刷新阈值：距过期不足该秒数时由后台任务提前刷新
+const BACKGROUND_REFRESH_AHEAD_SECS: i64 = 600;
+/// 后台预刷新检查间隔
+const BACKGROUND_REFRESH_INTERVAL_SECS: u64 = 60;
+/// 账号池为空且正在重新加载时，分配账号的请求最多排队等待的时长
+const RELOAD_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+tokio::task_local! {
+    /// 当前任务固定使用的账号 (account_id、email 或标签)，用于请求重放、`X-Antigravity-Account` 请求头等调试场景
+    static PINNED_ACCOUNT: String;
+    /// 当前请求可用的账号范围 (account_id 或 email)，由团队路由设置
+    static ACCOUNT_SCOPE: Vec<String>;
+    /// 当前请求所用 API 密钥的指纹，供等级策略按密钥预留账号
+    static REQUEST_KEY_ID: String;
+    /// 当前请求最终使用的账号，供中间件在响应后归集用量、添加响应头
+    static SERVED_ACCOUNT: std::cell::RefCell<Option<ServedAccount>>;
+}
+
+/// 请求最终分配到的账号
+#[derive(Debug, Clone)]
+pub struct ServedAccount {
+    pub email: String,
+    /// 映射后的上游模型
+    pub model: Option<String>,
+    /// 该模型最近一次刷新时的剩余配额百分比
+    pub remaining_quota: Option<i32>,
+}
+
+/// 执行 `future` 并返回期间最后一次分配的账号；嵌套调用时共用外层的记录
+pub async fn track_served<F: std::future::Future>(future: F) -> (F::Output, Option<ServedAccount>) {
+    if SERVED_ACCOUNT.try_with(|_| ()).is_ok() {
+        let output = future.await;
+        return (output, SERVED_ACCOUNT.with(|a| a.borrow().clone()));
+    }
+    SERVED_ACCOUNT
+        .scope(std::cell::RefCell::new(None), async move {
+            let output = future.await;
+            let served = SERVED_ACCOUNT.with(|a| a.borrow().clone());
+            (output, served)
+        })
+        .await
+}
+
+/// 执行 `future` 并返回期间最后一次分配的账号 (email)
+pub async fn track_served_account<F: std::future::Future>(future: F) -> (F::Output, Option<String>) {
+    let (output, served) = track_served(future).await;
+    (output, served.map(|s| s.email))
+}
+
+/// 在 `future` 执行期间仅从指定账号中选择
+pub async fn with_account_scope<F: std::future::Future>(accounts: Vec<String>, future: F) -> F::Output {
+    ACCOUNT_SCOPE.scope(accounts, future).await
+}
+
+/// 在 `future` 执行期间记录请求所用密钥的指纹
+pub async fn with_request_key<F: std::future::Future>(key_id: String, future: F) -> F::Output {
+    REQUEST_KEY_ID.scope(key_id, future).await
+}
+
+/// 在 `future` 执行期间固定使用指定账号
+pub async fn with_pinned_account<F: std::future::Future>(account: String, future: F) -> F::Output {
+    PINNED_ACCOUNT.scope(account, future).await
+}
+
 #[derive(Debug, Clone)]
 pub struct ProxyToken {
     pub account_id: String,
@@ -16,89 +91,190 @@ pub struct ProxyToken {
     pub expires_in: i64,
     pub timestamp: i64,
     pub email: String,
-    pub account_path: PathBuf,  // 账号文件路径，用于更新
     pub project_id: Option<String>,
-    pub subscription_tier: Option<String>, // "FREE" | "PRO" | "ULTRA"
+    pub subscription_tier: Option<SubscriptionTier>,
+    pub model_quotas: Vec<ModelQuota>, // 最近一次刷新的各模型剩余配额
+    pub quota_updated_at: Option<i64>, // 配额快照时间 (Unix 秒)，从未获取为 None
+    pub device: Option<crate::models::DeviceIdentity>, // 账号独立的设备标识
+    pub client_profile: Option<String>, // 客户端标识档案 (None 使用全局默认)
+    pub tags: Vec<String>, // 账号标签 (元数据 `tags`，逗号分隔)，可用于固定账号
+}
+
+impl ProxyToken {
+    /// 固定账号请求 (`X-Antigravity-Account`) 是否指向该账号: 匹配 ID、邮箱或标签
+    pub fn matches_pin(&self, pinned: &str) -> bool {
+        self.account_id == pinned
+            || self.email.eq_ignore_ascii_case(pinned)
+            || self.tags.iter().any(|t| t.eq_ignore_ascii_case(pinned))
+    }
+
+    /// 配额快照是否已超过最长可用时间
+    pub fn quota_is_stale(&self, max_staleness_secs: u64, now: i64) -> bool {
+        match self.quota_updated_at {
+            Some(updated_at) => now - updated_at > max_staleness_secs as i64,
+            None => true,
+        }
+    }
+}
+
+/// 账号池中单个账号的健康状况 (`GET /admin/status`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AccountHealth {
+    pub email: String,
+    pub subscription_tier: Option<SubscriptionTier>,
+    /// 限流剩余秒数，未限流为 None
+    pub rate_limited_secs: Option<u64>,
+    /// 配额已耗尽的模型
+    pub exhausted_models: Vec<String>,
+    /// access_token 剩余有效秒数 (负数表示已过期，下次使用时刷新)
+    pub token_expires_in: i64,
+    pub quota_updated_at: Option<i64>,
+}
+
+impl AccountHealth {
+    /// 可参与调度 (未处于限流冷却)
+    pub fn is_available(&self) -> bool {
+        self.rate_limited_secs.is_none()
+    }
+}
+
+/// 单飞刷新中最近一次失败，供排队中的等待者共享
+struct RefreshFailure {
+    at: std::time::Instant,
+    error: String,
 }
 
 pub struct TokenManager {
-    tokens: Arc<DashMap<String, ProxyToken>>,  // account_id -> ProxyToken
+    tokens: Arc<std::sync::RwLock<Arc<DashMap<String, ProxyToken>>>>,  // account_id -> ProxyToken (写时复制: 重新加载时整体替换)
+    reload_lock: Arc<tokio::sync::Mutex<()>>, // 串行化账号重新加载
+    reloading: Arc<tokio::sync::watch::Sender<bool>>, // 是否正在重新加载，供排队的请求等待
     current_index: Arc<AtomicUsize>,
     last_used_account: Arc<tokio::sync::Mutex<Option<(String, std::time::Instant)>>>,
-    data_dir: PathBuf,
+    storage: Arc<dyn StorageBackend>, // 账号读写的存储后端
     rate_limit_tracker: Arc<RateLimitTracker>,  // 新增: 限流跟踪器
     sticky_config: Arc<tokio::sync::RwLock<StickySessionConfig>>, // 新增：调度配置
     session_accounts: Arc<DashMap<String, String>>, // 新增：会话与账号映射 (SessionID -> AccountID)
+    refresh_locks: Arc<DashMap<String, Arc<tokio::sync::Mutex<Option<RefreshFailure>>>>>, // 单飞刷新锁 (AccountID -> 最近一次失败)
+    quota_thresholds: Arc<tokio::sync::RwLock<QuotaThresholdConfig>>, // 配额保留阈值
+    latency: Arc<LatencyTracker>, // 各账号上游延迟 (email -> 滚动窗口)
+    usage_caps: Arc<tokio::sync::RwLock<UsageCapConfig>>, // 单账号用量上限
+    usage: Arc<UsageTracker>, // 各账号当前窗口用量 (email -> 计数)
+    team_routing: Arc<tokio::sync::RwLock<TeamRoutingConfig>>, // 按组织/项目限定账号组
+    tier_policy: Arc<tokio::sync::RwLock<TierPolicyConfig>>, // 按订阅等级预留账号
+    schedule_routing: Arc<tokio::sync::RwLock<ScheduleRoutingConfig>>, // 按时段限定可用账号
+    retry_policy: Arc<tokio::sync::RwLock<RetryPolicyConfig>>, // 上游错误重试策略 (各 handler 读取)
+    quota_cache: Arc<tokio::sync::RwLock<QuotaCacheConfig>>, // 配额缓存过期策略
+    quota_revalidating: Arc<DashSet<String>>, // 等待 / 正在后台拉取配额的账号
+    quota_revalidate_notify: Arc<tokio::sync::Notify>, // 唤醒后台配额拉取任务
+    cluster: Arc<std::sync::RwLock<Option<Arc<crate::proxy::cluster::Cluster>>>>, // 多实例协同 (未启用为 None)
+    model_capabilities: Arc<tokio::sync::RwLock<ModelCapabilityConfig>>, // 模型能力覆盖与是否拒绝超限请求
+    context_overflow: Arc<tokio::sync::RwLock<ContextOverflowConfig>>, // 上下文超限处理策略
+    model_list: Arc<tokio::sync::RwLock<ModelListConfig>>, // 模型列表展示策略
 }
 
 impl TokenManager {
-    /// 创建新的 TokenManager
-    pub fn new(data_dir: PathBuf) -> Self {
+    /// 创建新的 TokenManager，账号从 `storage` 的 `Collection::Accounts` 加载并写回
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
         Self {
-            tokens: Arc::new(DashMap::new()),
+            tokens: Arc::new(std::sync::RwLock::new(Arc::new(DashMap::new()))),
+            reload_lock: Arc::new(tokio::sync::Mutex::new(())),
+            reloading: Arc::new(tokio::sync::watch::channel(false).0),
             current_index: Arc::new(AtomicUsize::new(0)),
             last_used_account: Arc::new(tokio::sync::Mutex::new(None)),
-            data_dir,
+            storage,
             rate_limit_tracker: Arc::new(RateLimitTracker::new()),
             sticky_config: Arc::new(tokio::sync::RwLock::new(StickySessionConfig::default())),
             session_accounts: Arc::new(DashMap::new()),
+            refresh_locks: Arc::new(DashMap::new()),
+            quota_thresholds: Arc::new(tokio::sync::RwLock::new(QuotaThresholdConfig::default())),
+            latency: Arc::new(LatencyTracker::new()),
+            usage_caps: Arc::new(tokio::sync::RwLock::new(UsageCapConfig::default())),
+            usage: Arc::new(UsageTracker::new()),
+            team_routing: Arc::new(tokio::sync::RwLock::new(TeamRoutingConfig::default())),
+            tier_policy: Arc::new(tokio::sync::RwLock::new(TierPolicyConfig::default())),
+            schedule_routing: Arc::new(tokio::sync::RwLock::new(ScheduleRoutingConfig::default())),
+            retry_policy: Arc::new(tokio::sync::RwLock::new(RetryPolicyConfig::default())),
+            quota_cache: Arc::new(tokio::sync::RwLock::new(QuotaCacheConfig::default())),
+            quota_revalidating: Arc::new(DashSet::new()),
+            quota_revalidate_notify: Arc::new(tokio::sync::Notify::new()),
+            cluster: Arc::new(std::sync::RwLock::new(None)),
+            model_capabilities: Arc::new(tokio::sync::RwLock::new(ModelCapabilityConfig::default())),
+            context_overflow: Arc::new(tokio::sync::RwLock::new(ContextOverflowConfig::default())),
+            model_list: Arc::new(tokio::sync::RwLock::new(ModelListConfig::default())),
         }
     }
     
-    /// 从主应用账号目录加载所有账号
-    pub async fn load_accounts(&self) -> Result<usize, String> {
-        let accounts_dir = self.data_dir.join("accounts");
-        
-        if !accounts_dir.exists() {
-            return Err(format!("账号目录不存在: {:?}", accounts_dir));
-        }
+    /// 当前账号池快照；重新加载时整体替换，已取得快照的请求不受影响
+    fn pool(&self) -> Arc<DashMap<String, ProxyToken>> {
+        self.tokens.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
 
-        // Reload should reflect current on-disk state (accounts can be added/removed/disabled).
-        self.tokens.clear();
-        self.current_index.store(0, Ordering::SeqCst);
-        {
+    /// 从存储后端加载所有账号
+    ///
+    /// 先在新的账号表中完成加载，再一次性替换当前账号池：加载期间请求继续使用旧账号池，
+    /// 不会看到部分加载的结果；加载失败时保留旧账号池。
+    pub async fn load_accounts(&self) -> Result<usize, String> {
+        let _guard = self.reload_lock.lock().await;
+        self.reloading.send_replace(true);
+        let result = self.read_accounts().await;
+        if let Ok(pool) = &result {
+            *self.tokens.write().unwrap_or_else(|e| e.into_inner()) = pool.clone();
+            self.current_index.store(0, Ordering::SeqCst);
             let mut last_used = self.last_used_account.lock().await;
             *last_used = None;
         }
-        
-        let entries = std::fs::read_dir(&accounts_dir)
-            .map_err(|e| format!("读取账号目录失败: {}", e))?;
-        
-        let mut count = 0;
-        
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
-            let path = entry.path();
-            
-            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+        self.reloading.send_replace(false);
+        let count = result?.len();
+        crate::proxy::events::publish(crate::proxy::events::ProxyEvent::AccountsReloaded { count });
+        Ok(count)
+    }
+
+    /// 从存储后端读取账号，生成新的账号表 (不影响当前账号池)
+    async fn read_accounts(&self) -> Result<Arc<DashMap<String, ProxyToken>>, String> {
+        // Reload should reflect current stored state (accounts can be added/removed/disabled).
+        let pool = DashMap::new();
+
+        for key in self.storage.keys(Collection::Accounts)? {
+            let Some(content) = self.storage.read(Collection::Accounts, &key)? else {
                 continue;
-            }
-            
+            };
+
             // 尝试加载账号
-            match self.load_single_account(&path).await {
+            match self.load_single_account(&key, &content).await {
                 Ok(Some(token)) => {
                     let account_id = token.account_id.clone();
-                    self.tokens.insert(account_id, token);
-                    count += 1;
+                    pool.insert(account_id, token);
                 },
                 Ok(None) => {
                     // 跳过无效账号
                 },
                 Err(e) => {
-                    tracing::debug!("加载账号失败 {:?}: {}", path, e);
+                    tracing::debug!("加载账号失败 {}: {}", key, e);
                 }
             }
         }
-        
-        Ok(count)
+
+        Ok(Arc::new(pool))
+    }
+
+    /// 账号池为空且正在重新加载时短暂排队 (最长 `RELOAD_WAIT`)，等加载完成后再取快照，而不是直接报错
+    async fn selection_snapshot(&self) -> Vec<ProxyToken> {
+        let snapshot = |pool: Arc<DashMap<String, ProxyToken>>| -> Vec<ProxyToken> {
+            pool.iter().map(|e| e.value().clone()).collect()
+        };
+        let tokens = snapshot(self.pool());
+        let mut reloading = self.reloading.subscribe();
+        if !tokens.is_empty() || !*reloading.borrow() {
+            return tokens;
+        }
+        tracing::debug!("账号池正在重新加载，等待加载完成后再分配账号");
+        let _ = tokio::time::timeout(RELOAD_WAIT, reloading.wait_for(|r| !*r)).await;
+        snapshot(self.pool())
     }
     
     /// 加载单个账号
-    async fn load_single_account(&self, path: &PathBuf) -> Result<Option<ProxyToken>, String> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| format!("读取文件失败: {}", e))?;
-        
-        let account: serde_json::Value = serde_json::from_str(&content)
+    async fn load_single_account(&self, key: &str, content: &str) -> Result<Option<ProxyToken>, String> {
+        let account: serde_json::Value = serde_json::from_str(content)
             .map_err(|e| format!("解析 JSON 失败: {}", e))?;
 
         if account
@@ -107,8 +283,8 @@ impl TokenManager {
             .unwrap_or(false)
         {
             tracing::debug!(
-                "Skipping disabled account file: {:?} (email={})",
-                path,
+                "Skipping disabled account: {} (email={})",
+                key,
                 account.get("email").and_then(|v| v.as_str()).unwrap_or("<unknown>")
             );
             return Ok(None);
@@ -121,8 +297,22 @@ impl TokenManager {
             .unwrap_or(false)
         {
             tracing::debug!(
-                "Skipping proxy-disabled account file: {:?} (email={})",
-                path,
+                "Skipping proxy-disabled account: {} (email={})",
+                key,
+                account.get("email").and_then(|v| v.as_str()).unwrap_or("<unknown>")
+            );
+            return Ok(None);
+        }
+
+        // 403 Forbidden 的账号由恢复探测任务定期检查，恢复后重新加载
+        if account
+            .pointer("/quota/is_forbidden")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            tracing::debug!(
+                "Skipping forbidden account: {} (email={})",
+                key,
                 account.get("email").and_then(|v| v.as_str()).unwrap_or("<unknown>")
             );
             return Ok(None);
@@ -158,11 +348,40 @@ impl TokenManager {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
         
-        // 【新增】提取订阅等级 (subscription_tier 为 "FREE" | "PRO" | "ULTRA")
+        // 【新增】提取订阅等级
         let subscription_tier = account.get("quota")
             .and_then(|q| q.get("subscription_tier"))
             .and_then(|v| v.as_str())
+            .map(SubscriptionTier::parse);
+
+        let model_quotas = parse_model_quotas(&account);
+        let quota_updated_at = parse_quota_updated_at(&account);
+
+        // 旧账号文件没有设备标识时，通过 load_account 生成并落盘
+        let device = match account.get("device").cloned() {
+            Some(value) => serde_json::from_value(value).ok(),
+            None => crate::modules::account::load_account(&account_id)
+                .ok()
+                .and_then(|a| a.device),
+        };
+        let client_profile = account
+            .get("client_profile")
+            .and_then(|v| v.as_str())
             .map(|s| s.to_string());
+        let tags = account
+            .pointer("/metadata/tags")
+            .and_then(|v| v.as_str())
+            .map(|s| {
+                s.split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(device) = &device {
+            crate::modules::device::register_token(&access_token, device, client_profile.as_deref());
+        }
         
         Ok(Some(ProxyToken {
             account_id,
@@ -171,9 +390,13 @@ impl TokenManager {
             expires_in,
             timestamp,
             email,
-            account_path: path.clone(),
             project_id,
             subscription_tier,
+            model_quotas,
+            quota_updated_at,
+            device,
+            client_profile,
+            tags,
         }))
     }
     
@@ -182,28 +405,154 @@ impl TokenManager {
     /// 参数 `force_rotate` 为 true 时将忽略锁定，强制切换账号
     /// 参数 `session_id` 用于跨请求维持会话粘性
     pub async fn get_token(&self, quota_group: &str, force_rotate: bool, session_id: Option<&str>) -> Result<(String, String, String), String> {
-        let mut tokens_snapshot: Vec<ProxyToken> = self.tokens.iter().map(|e| e.value().clone()).collect();
-        let total = tokens_snapshot.len();
-        if total == 0 {
+        self.get_token_for_model(quota_group, force_rotate, session_id, None).await
+    }
+
+    /// 同 `get_token`，额外按 `model` 跳过剩余配额低于保留阈值的账号
+    pub async fn get_token_for_model(
+        &self,
+        quota_group: &str,
+        force_rotate: bool,
+        session_id: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<(String, String, String), String> {
+        let mut tokens_snapshot = self.selection_snapshot().await;
+        if tokens_snapshot.is_empty() {
             return Err("Token pool is empty".to_string());
         }
 
+        // 团队路由: 仅使用该团队的账号组
+        if let Ok(scope) = ACCOUNT_SCOPE.try_with(|s| s.clone()) {
+            tokens_snapshot.retain(|t| {
+                scope
+                    .iter()
+                    .any(|a| *a == t.account_id || a.eq_ignore_ascii_case(&t.email))
+            });
+            if tokens_snapshot.is_empty() {
+                return Err("No accounts are available for this organization/project".to_string());
+            }
+        }
+
+        // 固定账号: 只在该账号上执行 (不做配额过滤，便于复现账号相关问题)
+        if let Ok(pinned) = PINNED_ACCOUNT.try_with(|a| a.clone()) {
+            tokens_snapshot.retain(|t| t.matches_pin(&pinned));
+            if tokens_snapshot.is_empty() {
+                return Err(format!("Pinned account {} is not available in the pool", pinned));
+            }
+        } else if let Some(model) = model {
+            // 配额耗尽或低于保留阈值的账号在配额重置前退出轮换，重置时间一到自动恢复
+            let thresholds = self.quota_thresholds.read().await.clone();
+            let now = chrono::Utc::now().timestamp();
+            let mut earliest_reset: Option<i64> = None;
+            tokens_snapshot.retain(|t| {
+                match crate::proxy::quota_threshold::unavailable_until(&thresholds, &t.model_quotas, model, now) {
+                    Some(reset_at) => {
+                        tracing::debug!("账号 {} 的 {} 配额已耗尽或低于保留阈值，暂不参与轮换", t.email, model);
+                        if let Some(ts) = reset_at {
+                            earliest_reset = Some(earliest_reset.map_or(ts, |e| e.min(ts)));
+                        }
+                        false
+                    }
+                    None => true,
+                }
+            });
+            if tokens_snapshot.is_empty() {
+                return Err(match earliest_reset {
+                    Some(ts) => format!(
+                        "All accounts are out of quota (or below the configured reserve) for {}. Earliest reset in {}.",
+                        model,
+                        crate::models::quota::format_countdown(ts - now)
+                    ),
+                    None => format!("All accounts are below the configured quota reserve for {}.", model),
+                });
+            }
+        }
+
+        // 等级预留: 高等级账号只服务于策略指定的模型或密钥 (固定账号时不限制)
+        if PINNED_ACCOUNT.try_with(|_| ()).is_err() {
+            let policy = self.tier_policy.read().await.clone();
+            if policy.enabled {
+                let key_id = REQUEST_KEY_ID.try_with(|k| k.clone()).ok();
+                tokens_snapshot.retain(|t| {
+                    crate::proxy::tier_policy::allows(&policy, t.subscription_tier.as_ref(), model, key_id.as_deref())
+                });
+                if tokens_snapshot.is_empty() {
+                    return Err("No accounts of an eligible subscription tier are available for this request".to_string());
+                }
+            }
+        }
+
+        // 按时段路由: 当前时段命中规则时只使用规则允许的账号 (固定账号时不限制)
+        if PINNED_ACCOUNT.try_with(|_| ()).is_err() {
+            let schedule = self.schedule_routing.read().await.clone();
+            if let Some(rule) = crate::proxy::schedule_routing::active_rule(&schedule, model, chrono::Utc::now()) {
+                let label = crate::proxy::schedule_routing::rule_label(&schedule, rule);
+                tokens_snapshot.retain(|t| {
+                    crate::proxy::schedule_routing::allows(
+                        rule,
+                        t.subscription_tier.as_ref(),
+                        &t.account_id,
+                        &t.email,
+                        &t.tags,
+                    )
+                });
+                tracing::debug!("按时段路由: 规则 {} 生效，可用账号 {} 个", label, tokens_snapshot.len());
+                if tokens_snapshot.is_empty() {
+                    return Err(format!("No accounts are scheduled for the current time window (rule {})", label));
+                }
+            }
+        }
+
+        // 达到用量上限的账号在窗口结束前退出轮换 (固定账号时不限制)
+        if PINNED_ACCOUNT.try_with(|_| ()).is_err() {
+            let caps = self.usage_caps.read().await.clone();
+            if caps.enabled {
+                let now = chrono::Utc::now().timestamp();
+                let mut earliest: Option<i64> = None;
+                tokens_snapshot.retain(|t| match self.usage.capped_until(&caps, &t.account_id, &t.email, now) {
+                    Some(until) => {
+                        tracing::debug!("账号 {} 已达到用量上限，暂不参与轮换", t.email);
+                        earliest = Some(earliest.map_or(until, |e| e.min(until)));
+                        false
+                    }
+                    None => true,
+                });
+                if let Some(until) = earliest.filter(|_| tokens_snapshot.is_empty()) {
+                    return Err(format!(
+                        "All accounts have reached their usage caps. Next window starts in {}.",
+                        crate::models::quota::format_countdown(until - now)
+                    ));
+                }
+            }
+        }
+
+        let total = tokens_snapshot.len();
+
         // ===== 【优化】根据订阅等级排序 (优先级: ULTRA > PRO > FREE) =====
         // 理由: ULTRA/PRO 重置快，优先消耗；FREE 重置慢，用于兜底
-        tokens_snapshot.sort_by(|a, b| {
-            let tier_priority = |tier: &Option<String>| match tier.as_deref() {
-                Some("ULTRA") => 0,
-                Some("PRO") => 1,
-                Some("FREE") => 2,
-                _ => 3,
-            };
-            tier_priority(&a.subscription_tier).cmp(&tier_priority(&b.subscription_tier))
-        });
+        tokens_snapshot.sort_by_key(|t| t.subscription_tier.as_ref().map_or(3, |tier| tier.priority()));
 
         // 0. 读取当前调度配置
         let scheduling = self.sticky_config.read().await.clone();
         use crate::proxy::sticky_config::SchedulingMode;
 
+        // 延迟优先: 按滚动 p50 加权随机排序，新分配时从头选取
+        if scheduling.mode == SchedulingMode::LatencyFirst {
+            let p50s: Vec<Option<u64>> = tokens_snapshot
+                .iter()
+                .map(|t| self.latency.stats(&t.email).map(|s| s.p50_ms))
+                .collect();
+            let order = crate::proxy::latency::weighted_order(&p50s, &mut rand::thread_rng());
+            tokens_snapshot = order.into_iter().map(|i| tokens_snapshot[i].clone()).collect();
+        }
+        let next_start = || {
+            if scheduling.mode == SchedulingMode::LatencyFirst {
+                0
+            } else {
+                self.current_index.fetch_add(1, Ordering::SeqCst) % total
+            }
+        };
+
         let mut attempted: HashSet<String> = HashSet::new();
         let mut last_error: Option<String> = None;
 
@@ -263,7 +612,7 @@ impl TokenManager {
                 
                 // 若无锁定，则轮询选择新账号
                 if target_token.is_none() {
-                    let start_idx = self.current_index.fetch_add(1, Ordering::SeqCst) % total;
+                    let start_idx = next_start();
                     for offset in 0..total {
                         let idx = (start_idx + offset) % total;
                         let candidate = &tokens_snapshot[idx];
@@ -291,7 +640,7 @@ impl TokenManager {
                 }
             } else if target_token.is_none() {
                 // 模式 C: 纯轮询模式 (Round-robin) 或强制轮换
-                let start_idx = self.current_index.fetch_add(1, Ordering::SeqCst) % total;
+                let start_idx = next_start();
                 for offset in 0..total {
                     let idx = (start_idx + offset) % total;
                     let candidate = &tokens_snapshot[idx];
@@ -327,45 +676,13 @@ impl TokenManager {
             };
 
         
-            // 3. 检查 token 是否过期（提前5分钟刷新）
+            // 3. 检查 token 是否过期（提前5分钟刷新，通常已由后台任务预先刷新）
             let now = chrono::Utc::now().timestamp();
-            if now >= token.timestamp - 300 {
-                tracing::debug!("账号 {} 的 token 即将过期，正在刷新...", token.email);
-
-                // 调用 OAuth 刷新 token
-                match crate::modules::oauth::refresh_access_token(&token.refresh_token).await {
-                    Ok(token_response) => {
-                        tracing::debug!("Token 刷新成功！");
-
-                        // 更新本地内存对象供后续使用
-                        token.access_token = token_response.access_token.clone();
-                        token.expires_in = token_response.expires_in;
-                        token.timestamp = now + token_response.expires_in;
-
-                        // 同步更新跨线程共享的 DashMap
-                        if let Some(mut entry) = self.tokens.get_mut(&token.account_id) {
-                            entry.access_token = token.access_token.clone();
-                            entry.expires_in = token.expires_in;
-                            entry.timestamp = token.timestamp;
-                        }
-
-                        // 同步落盘（避免重启后继续使用过期 timestamp 导致频繁刷新）
-                        if let Err(e) = self.save_refreshed_token(&token.account_id, &token_response).await {
-                            tracing::debug!("保存刷新后的 token 失败 ({}): {}", token.email, e);
-                        }
-                    }
+            if now >= token.timestamp - REFRESH_AHEAD_SECS {
+                match self.refresh_account_token(&token.account_id, REFRESH_AHEAD_SECS).await {
+                    Ok(refreshed) => token = refreshed,
                     Err(e) => {
                         tracing::error!("Token 刷新失败 ({}): {}，尝试下一个账号", token.email, e);
-                        if e.contains("\"invalid_grant\"") || e.contains("invalid_grant") {
-                            tracing::error!(
-                                "Disabling account due to invalid_grant ({}): refresh_token likely revoked/expired",
-                                token.email
-                            );
-                            let _ = self
-                                .disable_account(&token.account_id, &format!("invalid_grant: {}", e))
-                                .await;
-                            self.tokens.remove(&token.account_id);
-                        }
                         // Avoid leaking account emails to API clients; details are still in logs.
                         last_error = Some(format!("Token refresh failed: {}", e));
                         attempted.insert(token.account_id.clone());
@@ -389,7 +706,7 @@ impl TokenManager {
                 tracing::debug!("账号 {} 缺少 project_id，尝试获取...", token.email);
                 match crate::proxy::project_resolver::fetch_project_id(&token.access_token).await {
                     Ok(pid) => {
-                        if let Some(mut entry) = self.tokens.get_mut(&token.account_id) {
+                        if let Some(mut entry) = self.pool().get_mut(&token.account_id) {
                             entry.project_id = Some(pid.clone());
                         }
                         let _ = self.save_project_id(&token.account_id, &pid).await;
@@ -411,86 +728,437 @@ impl TokenManager {
                 }
             };
 
+            let window = self.usage_caps.read().await.window_secs;
+            self.usage.record_request(&token.email, chrono::Utc::now().timestamp(), window);
+            self.schedule_quota_revalidation(&token).await;
+            let _ = SERVED_ACCOUNT.try_with(|a| {
+                *a.borrow_mut() = Some(ServedAccount {
+                    email: token.email.clone(),
+                    model: model.map(str::to_string),
+                    remaining_quota: model
+                        .and_then(|m| token.model_quotas.iter().find(|q| q.name == m))
+                        .map(|q| q.percentage),
+                })
+            });
             return Ok((token.access_token, project_id, token.email));
         }
 
         Err(last_error.unwrap_or_else(|| "All accounts failed".to_string()))
     }
 
-    async fn disable_account(&self, account_id: &str, reason: &str) -> Result<(), String> {
-        let path = if let Some(entry) = self.tokens.get(account_id) {
-            entry.account_path.clone()
-        } else {
-            self.data_dir
-                .join("accounts")
-                .join(format!("{}.json", account_id))
+    /// 刷新指定账号的 access_token (单飞)
+    ///
+    /// 同一账号的并发刷新会排队等待同一把锁；拿到锁后若 token 已被其他任务刷新
+    /// (距过期超过 `ahead_secs`)，直接返回最新 token，不再重复请求 OAuth。
+    /// 若排队期间持锁任务刷新失败，等待者直接共享该错误，不再逐个重试。
+    async fn refresh_account_token(&self, account_id: &str, ahead_secs: i64) -> Result<ProxyToken, String> {
+        let lock = self
+            .refresh_locks
+            .entry(account_id.to_string())
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(None)))
+            .clone();
+        let waited_since = std::time::Instant::now();
+        let mut last_failure = lock.lock().await;
+        if let Some(failure) = last_failure.as_ref().filter(|f| f.at >= waited_since) {
+            tracing::debug!("账号 {} 的 token 刷新刚刚失败，共享该结果", account_id);
+            return Err(failure.error.clone());
+        }
+
+        let current = self
+            .pool()
+            .get(account_id)
+            .map(|entry| entry.value().clone())
+            .ok_or("账号不存在")?;
+
+        let now = chrono::Utc::now().timestamp();
+        if now < current.timestamp - ahead_secs {
+            tracing::debug!("账号 {} 的 token 已被其他任务刷新，跳过", current.email);
+            return Ok(current);
+        }
+
+        tracing::debug!("账号 {} 的 token 即将过期，正在刷新...", current.email);
+        match crate::modules::oauth::refresh_access_token(&current.refresh_token).await {
+            Ok(token_response) => {
+                tracing::debug!("Token 刷新成功！");
+                let mut token = current;
+                token.access_token = token_response.access_token.clone();
+                token.expires_in = token_response.expires_in;
+                token.timestamp = now + token_response.expires_in;
+
+                // 同步更新跨线程共享的 DashMap
+                if let Some(mut entry) = self.pool().get_mut(account_id) {
+                    entry.access_token = token.access_token.clone();
+                    entry.expires_in = token.expires_in;
+                    entry.timestamp = token.timestamp;
+                }
+                if let Some(device) = &token.device {
+                    crate::modules::device::register_token(
+                        &token.access_token,
+                        device,
+                        token.client_profile.as_deref(),
+                    );
+                }
+
+                // 同步落盘（避免重启后继续使用过期 timestamp 导致频繁刷新）
+                if let Err(e) = self.save_refreshed_token(account_id, &token_response).await {
+                    tracing::debug!("保存刷新后的 token 失败 ({}): {}", token.email, e);
+                }
+                *last_failure = None;
+                Ok(token)
+            }
+            Err(e) => {
+                *last_failure = Some(RefreshFailure {
+                    at: std::time::Instant::now(),
+                    error: e.clone(),
+                });
+                self.record_refresh_failure(account_id, &e).await;
+                if e.contains("invalid_grant") {
+                    tracing::error!(
+                        "Disabling account due to invalid_grant ({}): refresh_token likely revoked/expired",
+                        current.email
+                    );
+                    let _ = self
+                        .disable_account(account_id, &format!("invalid_grant: {}", e))
+                        .await;
+                    self.pool().remove(account_id);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// 启动后台预刷新任务，在 token 过期前提前续期，避免刷新阻塞请求
+    ///
+    /// 任务只持有弱引用，TokenManager 被释放后自动退出。
+    pub fn start_auto_refresh(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let weak = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                BACKGROUND_REFRESH_INTERVAL_SECS,
+            ));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(manager) = weak.upgrade() else {
+                    break;
+                };
+                manager.refresh_expiring_tokens().await;
+                manager.sync_model_quotas();
+                manager.enqueue_stale_quotas().await;
+            }
+            tracing::debug!("Token 后台预刷新任务已退出");
+        })
+    }
+
+    /// 启动后台配额拉取任务: 处理调度时发现的过期配额，请求路径上从不等待配额接口
+    ///
+    /// 任务只持有弱引用，TokenManager 被释放后自动退出。
+    pub fn start_quota_revalidation(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let weak = Arc::downgrade(self);
+        let notify = self.quota_revalidate_notify.clone();
+        tokio::spawn(async move {
+            loop {
+                let _ = tokio::time::timeout(
+                    std::time::Duration::from_secs(BACKGROUND_REFRESH_INTERVAL_SECS),
+                    notify.notified(),
+                )
+                .await;
+                let Some(manager) = weak.upgrade() else {
+                    break;
+                };
+                manager.revalidate_pending_quotas().await;
+            }
+            tracing::debug!("配额后台拉取任务已退出");
+        })
+    }
+
+    /// 选中账号的配额已过期时排队后台拉取 (同一账号只排队一次)
+    async fn schedule_quota_revalidation(&self, token: &ProxyToken) {
+        let config = self.quota_cache.read().await.clone();
+        if !config.revalidate || !token.quota_is_stale(config.max_staleness_secs, chrono::Utc::now().timestamp()) {
+            return;
+        }
+        if self.quota_revalidating.insert(token.account_id.clone()) {
+            tracing::debug!("账号 {} 的配额已过期，排队后台拉取", token.email);
+            self.quota_revalidate_notify.notify_one();
+        }
+    }
+
+    /// 将所有配额过期的账号加入后台拉取队列
+    async fn enqueue_stale_quotas(&self) {
+        let config = self.quota_cache.read().await.clone();
+        if !config.revalidate {
+            return;
+        }
+        let now = chrono::Utc::now().timestamp();
+        let mut queued = false;
+        for entry in self.pool().iter() {
+            if entry.quota_is_stale(config.max_staleness_secs, now) {
+                queued |= self.quota_revalidating.insert(entry.account_id.clone());
+            }
+        }
+        if queued {
+            self.quota_revalidate_notify.notify_one();
+        }
+    }
+
+    async fn revalidate_pending_quotas(&self) {
+        let pending: Vec<String> = self.quota_revalidating.iter().map(|id| id.clone()).collect();
+        for account_id in pending {
+            self.revalidate_quota(&account_id).await;
+            self.quota_revalidating.remove(&account_id);
+        }
+    }
+
+    /// 重新拉取单个账号的配额并写回账号文件与内存缓存
+    async fn revalidate_quota(&self, account_id: &str) {
+        let Some(email) = self.pool().get(account_id).map(|t| t.email.clone()) else {
+            return;
+        };
+        let access_token = match self.ensure_fresh_token(account_id).await {
+            Ok(token) => token,
+            Err(e) => {
+                tracing::warn!("后台拉取配额失败 ({}): {}", email, e);
+                return;
+            }
         };
+        match crate::modules::quota::fetch_quota(&access_token, &email).await {
+            Ok((quota, _)) => {
+                let (models, updated_at) = (quota.models.clone(), quota.last_updated);
+                if let Err(e) = crate::modules::update_account_quota(account_id, quota) {
+                    tracing::warn!("保存配额失败 ({}): {}", email, e);
+                }
+                if let Some(mut entry) = self.pool().get_mut(account_id) {
+                    entry.model_quotas = models;
+                    entry.quota_updated_at = Some(updated_at);
+                }
+                tracing::debug!("后台拉取配额成功: {}", email);
+            }
+            Err(e) => tracing::warn!("后台拉取配额失败 ({}): {}", email, e),
+        }
+    }
 
-        let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?,
-        )
-        .map_err(|e| format!("解析 JSON 失败: {}", e))?;
+    /// 等待或正在后台拉取配额的账号数
+    pub fn quota_revalidating(&self) -> usize {
+        self.quota_revalidating.len()
+    }
+
+    /// 配额缓存状态: (email, 快照时间)，从未获取为 None
+    pub fn quota_staleness(&self) -> Vec<(String, Option<i64>)> {
+        let mut entries: Vec<(String, Option<i64>)> = self
+            .pool()
+            .iter()
+            .map(|t| (t.email.clone(), t.quota_updated_at))
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    /// 从存储后端同步最新的模型配额 (配额由主应用刷新后写入)
+    fn sync_model_quotas(&self) {
+        for mut entry in self.pool().iter_mut() {
+            let Ok(Some(content)) = self.storage.read(Collection::Accounts, &entry.account_id) else {
+                continue;
+            };
+            if let Ok(account) = serde_json::from_str::<serde_json::Value>(&content) {
+                entry.model_quotas = parse_model_quotas(&account);
+                entry.quota_updated_at = parse_quota_updated_at(&account);
+            }
+        }
+    }
 
+    /// 刷新所有即将过期的 token
+    async fn refresh_expiring_tokens(&self) {
         let now = chrono::Utc::now().timestamp();
-        content["disabled"] = serde_json::Value::Bool(true);
-        content["disabled_at"] = serde_json::Value::Number(now.into());
-        content["disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
+        let expiring: Vec<(String, String)> = self
+            .pool()
+            .iter()
+            .filter(|entry| now >= entry.timestamp - BACKGROUND_REFRESH_AHEAD_SECS)
+            .map(|entry| (entry.account_id.clone(), entry.email.clone()))
+            .collect();
+
+        for (account_id, email) in expiring {
+            match self
+                .refresh_account_token(&account_id, BACKGROUND_REFRESH_AHEAD_SECS)
+                .await
+            {
+                Ok(_) => tracing::debug!("后台预刷新成功: {}", email),
+                Err(e) => tracing::warn!("后台预刷新失败 ({}): {}", email, e),
+            }
+        }
+    }
 
-        std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+    /// 读取账号文档，修改后写回存储后端
+    fn update_account(
+        &self,
+        account_id: &str,
+        update: impl FnOnce(&mut serde_json::Value),
+    ) -> Result<serde_json::Value, String> {
+        let raw = self
+            .storage
+            .read(Collection::Accounts, account_id)?
+            .ok_or_else(|| format!("账号不存在: {}", account_id))?;
+        let mut content: serde_json::Value =
+            serde_json::from_str(&raw).map_err(|e| format!("解析 JSON 失败: {}", e))?;
+        update(&mut content);
+        let json = serde_json::to_string_pretty(&content)
+            .map_err(|e| format!("序列化账号失败: {}", e))?;
+        self.storage.write(Collection::Accounts, account_id, &json)?;
+        Ok(content)
+    }
 
-        tracing::warn!("Account disabled: {} ({:?})", account_id, path);
+    async fn disable_account(&self, account_id: &str, reason: &str) -> Result<(), String> {
+        let now = chrono::Utc::now().timestamp();
+        let content = self.update_account(account_id, |content| {
+            content["disabled"] = serde_json::Value::Bool(true);
+            content["disabled_at"] = serde_json::Value::Number(now.into());
+            content["disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
+        })?;
+
+        tracing::warn!("Account disabled: {} (storage={})", account_id, self.storage.name());
+        let account = content["email"].as_str().unwrap_or(account_id).to_string();
+        crate::proxy::events::publish(crate::proxy::events::ProxyEvent::AccountDisabled {
+            account,
+            reason: truncate_reason(reason, 800),
+        });
         Ok(())
     }
 
-    /// 保存 project_id 到账号文件
+    /// 保存 project_id 到账号数据
     async fn save_project_id(&self, account_id: &str, project_id: &str) -> Result<(), String> {
-        let entry = self.tokens.get(account_id)
-            .ok_or("账号不存在")?;
-        
-        let path = &entry.account_path;
-        
-        let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
-        ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
-        
-        content["token"]["project_id"] = serde_json::Value::String(project_id.to_string());
-        
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
-        
+        if !self.pool().contains_key(account_id) {
+            return Err("账号不存在".to_string());
+        }
+
+        self.update_account(account_id, |content| {
+            content["token"]["project_id"] = serde_json::Value::String(project_id.to_string());
+        })?;
+
         tracing::debug!("已保存 project_id 到账号 {}", account_id);
         Ok(())
     }
-    
-    /// 保存刷新后的 token 到账号文件
+
+    /// 保存刷新后的 token 到账号数据
     async fn save_refreshed_token(&self, account_id: &str, token_response: &crate::modules::oauth::TokenResponse) -> Result<(), String> {
-        let entry = self.tokens.get(account_id)
-            .ok_or("账号不存在")?;
-        
-        let path = &entry.account_path;
-        
-        let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
-        ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
-        
+        if !self.pool().contains_key(account_id) {
+            return Err("账号不存在".to_string());
+        }
+
         let now = chrono::Utc::now().timestamp();
-        
-        content["token"]["access_token"] = serde_json::Value::String(token_response.access_token.clone());
-        content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
-        content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
-        
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
-        
+        self.update_account(account_id, |content| {
+            content["token"]["access_token"] = serde_json::Value::String(token_response.access_token.clone());
+            content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
+            content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
+            let mut refresh: crate::models::RefreshStats =
+                serde_json::from_value(content["refresh"].clone()).unwrap_or_default();
+            refresh.record_success(now);
+            content["refresh"] = serde_json::to_value(&refresh).unwrap_or_default();
+        })?;
+
         tracing::debug!("已保存刷新后的 token 到账号 {}", account_id);
         Ok(())
     }
-    
+
     pub fn len(&self) -> usize {
-        self.tokens.len()
+        self.pool().len()
+    }
+
+    /// 当前池中的账号: (account_id, email)
+    pub fn account_ids(&self) -> Vec<(String, String)> {
+        self.pool()
+            .iter()
+            .map(|entry| (entry.account_id.clone(), entry.email.clone()))
+            .collect()
+    }
+
+    /// 确保账号 token 有效 (即将过期时提前刷新)，返回 access_token
+    pub async fn ensure_fresh_token(&self, account_id: &str) -> Result<String, String> {
+        self.refresh_account_token(account_id, BACKGROUND_REFRESH_AHEAD_SECS)
+            .await
+            .map(|token| token.access_token)
+    }
+
+    /// 将账号移出轮换池 (不修改账号文件，重新加载后恢复)
+    pub fn evict(&self, account_id: &str) -> bool {
+        self.pool().remove(account_id).is_some()
     }
     
+    /// 记录账号的上游延迟 (调用方传入 email)
+    pub fn record_latency(&self, email: &str, latency: std::time::Duration) {
+        self.latency.record(email, latency.as_millis() as u64);
+    }
+
+    /// 记录账号消耗的 Token (调用方传入 email)
+    pub async fn record_token_usage(&self, email: &str, tokens: u64) {
+        let window = self.usage_caps.read().await.window_secs;
+        self.usage.record_tokens(email, tokens, chrono::Utc::now().timestamp(), window);
+    }
+
+    /// 是否需要统计 Token 用量 (启用了 Token 上限)
+    pub async fn tracks_token_usage(&self) -> bool {
+        let caps = self.usage_caps.read().await;
+        caps.enabled && (caps.max_tokens.is_some() || caps.accounts.values().any(|c| c.max_tokens.is_some()))
+    }
+
+    /// 当前窗口内各账号的用量
+    pub async fn usage_snapshot(&self) -> Vec<(String, crate::proxy::usage_caps::UsageWindow)> {
+        let window = self.usage_caps.read().await.window_secs;
+        self.usage.snapshot(window, chrono::Utc::now().timestamp())
+    }
+
+    pub async fn update_usage_caps(&self, new_config: UsageCapConfig) {
+        let mut config = self.usage_caps.write().await;
+        if *config != new_config {
+            tracing::info!(
+                "账号用量上限已更新: enabled={}, requests={:?}, tokens={:?}",
+                new_config.enabled,
+                new_config.max_requests,
+                new_config.max_tokens
+            );
+            *config = new_config;
+        }
+    }
+
+    /// 各账号的上游延迟统计，按 p50 升序
+    pub fn latency_stats(&self) -> Vec<(String, crate::proxy::latency::LatencyStats)> {
+        self.latency.snapshot()
+    }
+
+    /// 记录账号的上游错误 (后台落盘，不阻塞请求)；请求本身有误的 4xx 与账号状态无关，不记录
+    pub fn record_upstream_error(&self, account: &str, status: Option<u16>, message: &str) {
+        if status.is_some_and(|s| s < 500 && !matches!(s, 401 | 403 | 429)) {
+            return;
+        }
+        // 调用方可能传入 account_id 或 email
+        let Some(account_id) = self
+            .pool()
+            .iter()
+            .find(|t| t.account_id == account || t.email == account)
+            .map(|t| t.account_id.clone())
+        else {
+            return;
+        };
+        let message = message.to_string();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = crate::modules::account::record_account_error(&account_id, status, &message) {
+                tracing::debug!("记录账号错误失败 ({}): {}", account_id, e);
+            }
+        });
+    }
+
+    /// 记录 token 刷新失败 (失败次数与最近错误，供 token 健康报告使用)；
+    /// 等待写入完成，避免与随后禁用账号的写入互相覆盖
+    async fn record_refresh_failure(&self, account_id: &str, error: &str) {
+        let account_id = account_id.to_string();
+        let error = error.to_string();
+        let _ = tokio::task::spawn_blocking(move || {
+            if let Err(e) = crate::modules::account::record_token_refresh(&account_id, Some(&error)) {
+                tracing::debug!("记录 token 刷新失败次数失败 ({}): {}", account_id, e);
+            }
+        })
+        .await;
+    }
+
     // ===== 限流管理方法 =====
     
     /// 标记账号限流(从外部调用,通常在 handler 中)
@@ -501,21 +1169,165 @@ impl TokenManager {
         retry_after_header: Option<&str>,
         error_body: &str,
     ) {
-        self.rate_limit_tracker.parse_from_error(
+        // 调用方可能传入 account_id 或 email
+        let now = chrono::Utc::now().timestamp();
+        let quota_reset_at = self
+            .pool()
+            .iter()
+            .find(|t| t.account_id == account_id || t.email == account_id)
+            .and_then(|t| {
+                t.model_quotas
+                    .iter()
+                    .filter(|q| q.is_exhausted())
+                    .filter_map(|q| q.reset_timestamp())
+                    .filter(|ts| *ts > now)
+                    .min()
+            });
+        let info = self.rate_limit_tracker.parse_from_error_with_reset(
             account_id,
             status,
             retry_after_header,
             error_body,
+            quota_reset_at,
         );
+        let Some(info) = info else {
+            return;
+        };
+        let until = info
+            .reset_time
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(now, |d| d.as_secs() as i64);
+        crate::proxy::events::publish(crate::proxy::events::ProxyEvent::AccountRateLimited {
+            account: account_id.to_string(),
+            status,
+            until,
+        });
+        if let Some(cluster) = self.cluster() {
+            cluster.emit(crate::proxy::cluster::ClusterEvent::Cooldown {
+                account: account_id.to_string(),
+                until,
+            });
+        }
+    }
+
+    /// 当前的多实例协同 (未启用为 None)
+    pub fn cluster(&self) -> Option<Arc<crate::proxy::cluster::Cluster>> {
+        self.cluster.read().ok().and_then(|c| c.clone())
+    }
+
+    /// 启用 / 停用多实例协同 (替换时旧的同步任务随之停止)
+    pub fn set_cluster(&self, cluster: Option<Arc<crate::proxy::cluster::Cluster>>) {
+        if let Ok(mut current) = self.cluster.write() {
+            *current = cluster;
+        }
+    }
+
+    /// 本实例当前窗口的用量 (集群广播用)
+    pub async fn local_usage_event(&self) -> Option<crate::proxy::cluster::ClusterEvent> {
+        let window = self.usage_caps.read().await.window_secs;
+        let now = chrono::Utc::now().timestamp();
+        let snapshot = self.usage.snapshot(window, now);
+        let window_start = snapshot.first()?.1.started_at;
+        Some(crate::proxy::cluster::ClusterEvent::Usage {
+            window_start,
+            accounts: snapshot
+                .into_iter()
+                .map(|(email, w)| crate::proxy::cluster::AccountUsage {
+                    email,
+                    requests: w.requests,
+                    tokens: w.tokens,
+                })
+                .collect(),
+        })
+    }
+
+    /// 应用其他实例广播的事件；返回是否已处理 (自身发出的消息忽略)
+    pub fn apply_cluster_message(&self, message: crate::proxy::cluster::ClusterMessage) -> bool {
+        use crate::proxy::cluster::ClusterEvent;
+
+        if self.cluster().map_or(true, |c| c.instance_id() == message.instance) {
+            return false;
+        }
+        let max_until = chrono::Utc::now().timestamp() + crate::proxy::cluster::MAX_REMOTE_COOLDOWN_SECS;
+        for event in message.events {
+            match event {
+                ClusterEvent::Cooldown { account, until } => {
+                    let until = until.min(max_until);
+                    tracing::debug!("实例 {} 同步冷却: {} 至 {}", message.instance, account, until);
+                    let until = std::time::UNIX_EPOCH + std::time::Duration::from_secs(until.max(0) as u64);
+                    self.rate_limit_tracker.lock_until(&account, until);
+                }
+                ClusterEvent::Usage { window_start, accounts } => {
+                    for usage in accounts {
+                        self.usage.set_remote(
+                            &message.instance,
+                            &usage.email,
+                            crate::proxy::usage_caps::UsageWindow {
+                                started_at: window_start,
+                                requests: usage.requests,
+                                tokens: usage.tokens,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// 配额已耗尽、等待重置的账号模型列表: (email, model, reset_at)，按重置时间排序
+    pub fn pending_quota_resets(&self) -> Vec<(String, String, i64)> {
+        let now = chrono::Utc::now().timestamp();
+        let mut resets: Vec<(String, String, i64)> = self
+            .pool()
+            .iter()
+            .flat_map(|t| {
+                let email = t.email.clone();
+                t.model_quotas
+                    .iter()
+                    .filter(|q| q.is_exhausted())
+                    .filter_map(|q| q.reset_timestamp().filter(|ts| *ts > now).map(|ts| (q.name.clone(), ts)))
+                    .map(|(model, ts)| (email.clone(), model, ts))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        resets.sort_by_key(|(_, _, ts)| *ts);
+        resets
     }
     
+    /// 账号池健康状况，按 email 排序
+    pub fn pool_health(&self) -> Vec<AccountHealth> {
+        let now = chrono::Utc::now().timestamp();
+        let mut accounts: Vec<AccountHealth> = self
+            .pool()
+            .iter()
+            .map(|t| AccountHealth {
+                email: t.email.clone(),
+                subscription_tier: t.subscription_tier.clone(),
+                // 限流记录可能以 account_id 或 email 为键
+                rate_limited_secs: self
+                    .get_rate_limit_reset_seconds(&t.account_id)
+                    .or_else(|| self.get_rate_limit_reset_seconds(&t.email)),
+                exhausted_models: t
+                    .model_quotas
+                    .iter()
+                    .filter(|q| q.is_exhausted())
+                    .map(|q| q.name.clone())
+                    .collect(),
+                token_expires_in: t.timestamp - now,
+                quota_updated_at: t.quota_updated_at,
+            })
+            .collect();
+        accounts.sort_by(|a, b| a.email.cmp(&b.email));
+        accounts
+    }
+
     /// 检查账号是否在限流中
     pub fn is_rate_limited(&self, account_id: &str) -> bool {
         self.rate_limit_tracker.is_rate_limited(account_id)
     }
     
     /// 获取距离限流重置还有多少秒
-    #[allow(dead_code)]
     pub fn get_rate_limit_reset_seconds(&self, account_id: &str) -> Option<u64> {
         self.rate_limit_tracker.get_reset_seconds(account_id)
     }
@@ -546,6 +1358,171 @@ impl TokenManager {
         tracing::debug!("Scheduling configuration updated: {:?}", *config);
     }
 
+    /// 更新配额保留阈值
+    /// 当前的团队路由配置
+    pub async fn team_routing(&self) -> TeamRoutingConfig {
+        self.team_routing.read().await.clone()
+    }
+
+    pub async fn update_team_routing(&self, new_config: TeamRoutingConfig) {
+        let mut config = self.team_routing.write().await;
+        if *config != new_config {
+            tracing::info!("团队路由已更新: enabled={}, rules={}", new_config.enabled, new_config.rules.len());
+            *config = new_config;
+        }
+    }
+
+    pub async fn update_tier_policy(&self, new_policy: TierPolicyConfig) {
+        let mut policy = self.tier_policy.write().await;
+        if *policy != new_policy {
+            tracing::info!("订阅等级策略已更新: enabled={}, reservations={}", new_policy.enabled, new_policy.reservations.len());
+            *policy = new_policy;
+        }
+    }
+
+    pub async fn update_schedule_routing(&self, new_config: ScheduleRoutingConfig) {
+        let mut config = self.schedule_routing.write().await;
+        if *config != new_config {
+            tracing::info!("按时段路由已更新: enabled={}, rules={}", new_config.enabled, new_config.rules.len());
+            *config = new_config;
+        }
+    }
+
+    /// 当前的重试策略
+    pub async fn retry_policy(&self) -> RetryPolicyConfig {
+        self.retry_policy.read().await.clone()
+    }
+
+    /// 上游模型的能力 (内置目录 + 池中账号最近一次报告 + 配置覆盖)
+    pub async fn model_capabilities(&self, model: &str) -> Option<crate::proxy::model_capabilities::ModelCapabilities> {
+        let upstream = self.pool().iter().find_map(|t| {
+            t.model_quotas
+                .iter()
+                .find(|q| q.name == model)
+                .and_then(|q| q.limits.clone())
+        });
+        let config = self.model_capabilities.read().await;
+        crate::proxy::model_capabilities::resolve(model, upstream.as_ref(), &config.overrides)
+    }
+
+    /// 校验请求是否超出上游模型的能力；未开启校验或模型不在注册表中时放行
+    ///
+    /// 上下文超限策略为截断 / 摘要时不在此处拒绝超长输入，留给转换后的 `context_overflow::fit_request` 处理
+    pub async fn check_model_request(
+        &self,
+        model: &str,
+        features: &crate::proxy::model_capabilities::RequestFeatures,
+    ) -> Result<(), String> {
+        if !self.model_capabilities.read().await.enforce {
+            return Ok(());
+        }
+        let Some(caps) = self.model_capabilities(model).await else {
+            return Ok(());
+        };
+        if self.context_overflow.read().await.strategy == ContextOverflowStrategy::Reject {
+            return crate::proxy::model_capabilities::check(model, &caps, features);
+        }
+        let features = crate::proxy::model_capabilities::RequestFeatures {
+            input_tokens: 0,
+            ..features.clone()
+        };
+        crate::proxy::model_capabilities::check(model, &caps, &features)
+    }
+
+    /// 当前的上下文超限处理配置
+    pub async fn context_overflow(&self) -> ContextOverflowConfig {
+        self.context_overflow.read().await.clone()
+    }
+
+    pub async fn update_context_overflow(&self, new_config: ContextOverflowConfig) {
+        let mut config = self.context_overflow.write().await;
+        if *config != new_config {
+            tracing::info!(
+                "上下文超限策略已更新: {:?} (保留最近 {} 条)",
+                new_config.strategy,
+                new_config.keep_recent_messages
+            );
+            *config = new_config;
+        }
+    }
+
+    /// 当前的模型列表展示策略
+    pub async fn model_list(&self) -> ModelListConfig {
+        self.model_list.read().await.clone()
+    }
+
+    pub async fn update_model_list(&self, new_config: ModelListConfig) {
+        let mut config = self.model_list.write().await;
+        if *config != new_config {
+            tracing::info!("模型列表策略已更新: 按配额过滤 = {}", new_config.quota_aware);
+            *config = new_config;
+        }
+    }
+
+    /// 账号池中至少一个账号报告了配额的上游模型
+    pub fn served_models(&self) -> std::collections::HashSet<String> {
+        self.pool()
+            .iter()
+            .flat_map(|t| t.model_quotas.iter().map(|q| q.name.clone()).collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// 账号池中某个上游模型的剩余配额汇总，没有任何账号报告该模型时为 None
+    pub fn pool_quota(&self, model: &str) -> Option<crate::proxy::pool_quota::PoolQuota> {
+        let now = chrono::Utc::now().timestamp();
+        let quotas: Vec<ModelQuota> = self
+            .pool()
+            .iter()
+            .filter_map(|t| t.model_quotas.iter().find(|q| q.name == model).cloned())
+            .collect();
+        crate::proxy::pool_quota::summarize(&quotas, now)
+    }
+
+    pub async fn update_model_capabilities(&self, new_config: ModelCapabilityConfig) {
+        let mut config = self.model_capabilities.write().await;
+        if *config != new_config {
+            tracing::info!(
+                "模型能力配置已更新: enforce={}, overrides={}",
+                new_config.enforce,
+                new_config.overrides.len()
+            );
+            *config = new_config;
+        }
+    }
+
+    pub async fn update_quota_cache(&self, new_config: QuotaCacheConfig) {
+        let mut config = self.quota_cache.write().await;
+        if *config != new_config {
+            tracing::info!(
+                "配额缓存策略已更新: max_staleness={}s, revalidate={}",
+                new_config.max_staleness_secs,
+                new_config.revalidate
+            );
+            *config = new_config;
+        }
+    }
+
+    pub async fn update_retry_policy(&self, new_policy: RetryPolicyConfig) {
+        let mut policy = self.retry_policy.write().await;
+        if *policy != new_policy {
+            tracing::info!(
+                "重试策略已更新: max_attempts={}, rotate_account={}, retry_streaming={}",
+                new_policy.max_attempts,
+                new_policy.rotate_account,
+                new_policy.retry_streaming
+            );
+            *policy = new_policy;
+        }
+    }
+
+    pub async fn update_quota_thresholds(&self, new_config: QuotaThresholdConfig) {
+        let mut config = self.quota_thresholds.write().await;
+        if *config != new_config {
+            tracing::info!("配额保留阈值已更新: enabled={}, rules={}", new_config.enabled, new_config.rules.len());
+            *config = new_config;
+        }
+    }
+
     /// 清除特定会话的粘性映射
     #[allow(dead_code)]
     pub fn clear_session_binding(&self, session_id: &str) {
@@ -566,3 +1543,119 @@ fn truncate_reason(reason: &str, max_len: usize) -> String {
     s.push('…');
     s
 }
+
+/// 从账号 JSON 中提取配额快照时间
+fn parse_quota_updated_at(account: &serde_json::Value) -> Option<i64> {
+    account
+        .get("quota")
+        .and_then(|q| q.get("last_updated"))
+        .and_then(|v| v.as_i64())
+}
+
+/// 从账号 JSON 中提取各模型配额
+fn parse_model_quotas(account: &serde_json::Value) -> Vec<ModelQuota> {
+    account
+        .get("quota")
+        .and_then(|q| q.get("models"))
+        .and_then(|m| serde_json::from_value(m.clone()).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::storage::MemoryStorage;
+
+    fn account_json(id: &str, extra: serde_json::Value) -> String {
+        let mut account = serde_json::json!({
+            "id": id,
+            "email": format!("{}@example.com", id),
+            "token": {
+                "access_token": format!("access-{}", id),
+                "refresh_token": format!("refresh-{}", id),
+                "expires_in": 3600,
+                "expiry_timestamp": 4_000_000_000i64,
+            },
+            "device": {
+                "machine_id": format!("machine-{}", id),
+                "session_id": format!("session-{}", id),
+                "created_at": 0,
+            },
+        });
+        if let (Some(account), Some(extra)) = (account.as_object_mut(), extra.as_object()) {
+            for (key, value) in extra {
+                account.insert(key.clone(), value.clone());
+            }
+        }
+        account.to_string()
+    }
+
+    fn stored(storage: &MemoryStorage, id: &str) -> serde_json::Value {
+        let raw = storage.read(Collection::Accounts, id).unwrap().unwrap();
+        serde_json::from_str(&raw).unwrap()
+    }
+
+    fn manager_with(accounts: &[(&str, serde_json::Value)]) -> (Arc<MemoryStorage>, TokenManager) {
+        let storage = Arc::new(MemoryStorage::new());
+        for (id, extra) in accounts {
+            storage
+                .write(Collection::Accounts, id, &account_json(id, extra.clone()))
+                .unwrap();
+        }
+        let manager = TokenManager::new(storage.clone());
+        (storage, manager)
+    }
+
+    #[tokio::test]
+    async fn loads_usable_accounts_from_storage_backend() {
+        let (_storage, manager) = manager_with(&[
+            ("active", serde_json::json!({})),
+            ("disabled", serde_json::json!({ "disabled": true })),
+            ("paused", serde_json::json!({ "proxy_disabled": true })),
+            ("forbidden", serde_json::json!({ "quota": { "is_forbidden": true } })),
+        ]);
+
+        assert_eq!(manager.load_accounts().await.unwrap(), 1);
+        assert_eq!(
+            manager.account_ids(),
+            vec![("active".to_string(), "active@example.com".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_token_updates_back_to_storage_backend() {
+        let (storage, manager) = manager_with(&[("acc", serde_json::json!({}))]);
+        manager.load_accounts().await.unwrap();
+
+        let response = crate::modules::oauth::TokenResponse {
+            access_token: "access-new".to_string(),
+            expires_in: 1800,
+            token_type: "Bearer".to_string(),
+            refresh_token: None,
+        };
+        manager.save_refreshed_token("acc", &response).await.unwrap();
+        manager.save_project_id("acc", "project-1").await.unwrap();
+
+        let account = stored(&storage, "acc");
+        assert_eq!(account["token"]["access_token"], "access-new");
+        assert_eq!(account["token"]["expires_in"], 1800);
+        assert_eq!(account["token"]["project_id"], "project-1");
+        assert_eq!(account["token"]["refresh_token"], "refresh-acc");
+    }
+
+    #[tokio::test]
+    async fn disabled_account_is_persisted_and_skipped_on_reload() {
+        let (storage, manager) = manager_with(&[
+            ("keep", serde_json::json!({})),
+            ("revoked", serde_json::json!({})),
+        ]);
+        assert_eq!(manager.load_accounts().await.unwrap(), 2);
+
+        manager.disable_account("revoked", "invalid_grant").await.unwrap();
+
+        let account = stored(&storage, "revoked");
+        assert_eq!(account["disabled"], true);
+        assert_eq!(account["disabled_reason"], "invalid_grant");
+        assert_eq!(manager.load_accounts().await.unwrap(), 1);
+    }
+}
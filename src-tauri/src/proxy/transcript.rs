@@ -0,0 +1,470 @@
+// 会话记录导出：从持久化的请求日志还原某个会话的完整对话 (消息、模型回复与工具调用)
+//
+// 客户端每次请求都会重发此前的历史，因此每个请求只记录相对上一请求 (及其回复) 新增的消息。
+// 流式响应的正文不落库，对应的回复会出现在下一次请求的历史中。
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 消息中的一个内容块
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Block {
+    Text { text: String },
+    Thinking { text: String },
+    ToolCall {
+        #[serde(default)]
+        id: Option<String>,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        #[serde(default)]
+        id: Option<String>,
+        content: String,
+    },
+    Image,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    /// system / user / assistant / tool
+    pub role: String,
+    pub blocks: Vec<Block>,
+}
+
+/// 会话中的一次请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub id: String,
+    pub timestamp: i64,
+    pub model: Option<String>,
+    pub status: u16,
+    pub duration: u64,
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub error: Option<String>,
+    /// 相对上一请求新增的消息
+    pub messages: Vec<Message>,
+    pub response: Option<Message>,
+    /// 流式响应 (正文未记录)
+    pub streamed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub session_id: String,
+    pub exchanges: Vec<Exchange>,
+}
+
+/// 从会话的请求日志 (按时间顺序) 还原对话
+pub fn build(session_id: &str, logs: &[ProxyRequestLog]) -> Transcript {
+    let mut previous: Vec<Message> = Vec::new();
+    let mut exchanges = Vec::new();
+    for log in logs {
+        let request: Value = log
+            .request_body
+            .as_deref()
+            .and_then(|b| serde_json::from_str(b).ok())
+            .unwrap_or(Value::Null);
+        let history = parse_request(&log.url, &request);
+        let response_body = log.response_body.as_deref();
+        let streamed = response_body == Some("[Stream Data]");
+        let response = response_body
+            .and_then(|b| serde_json::from_str::<Value>(b).ok())
+            .and_then(|v| parse_response(&v));
+
+        let common = previous
+            .iter()
+            .zip(history.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let messages = history[common..].to_vec();
+
+        previous = history;
+        previous.extend(response.clone());
+        exchanges.push(Exchange {
+            id: log.id.clone(),
+            timestamp: log.timestamp,
+            model: log.model.clone(),
+            status: log.status,
+            duration: log.duration,
+            input_tokens: log.input_tokens,
+            output_tokens: log.output_tokens,
+            error: log.error.clone(),
+            messages,
+            response,
+            streamed,
+        });
+    }
+    Transcript {
+        session_id: session_id.to_string(),
+        exchanges,
+    }
+}
+
+/// 按协议解析请求中的完整历史 (含系统提示)
+fn parse_request(url: &str, body: &Value) -> Vec<Message> {
+    if body.get("contents").is_some() || body.pointer("/request/contents").is_some() {
+        let body = body.get("request").unwrap_or(body);
+        let mut messages = Vec::new();
+        if let Some(system) = body.get("systemInstruction") {
+            messages.push(gemini_message("system", system));
+        }
+        for content in body["contents"].as_array().into_iter().flatten() {
+            let role = match content["role"].as_str() {
+                Some("model") => "assistant",
+                _ => "user",
+            };
+            messages.push(gemini_message(role, content));
+        }
+        return messages;
+    }
+
+    let mut messages = Vec::new();
+    let is_claude = url.contains("/messages");
+    if is_claude {
+        if let Some(system) = body.get("system") {
+            let blocks = content_blocks(system);
+            if !blocks.is_empty() {
+                messages.push(Message { role: "system".to_string(), blocks });
+            }
+        }
+    }
+    for message in body["messages"].as_array().into_iter().flatten() {
+        let role = message["role"].as_str().unwrap_or("user").to_string();
+        let mut blocks = content_blocks(&message["content"]);
+        // OpenAI: 工具调用与工具结果不在 content 中
+        for call in message["tool_calls"].as_array().into_iter().flatten() {
+            let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+            blocks.push(Block::ToolCall {
+                id: call["id"].as_str().map(str::to_string),
+                name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+                input: serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.to_string())),
+            });
+        }
+        if role == "tool" {
+            let content = blocks_text(&blocks);
+            blocks = vec![Block::ToolResult {
+                id: message["tool_call_id"].as_str().map(str::to_string),
+                content,
+            }];
+        }
+        messages.push(Message { role, blocks });
+    }
+    // 旧版补全接口
+    if let Some(prompt) = body.get("prompt").and_then(|p| p.as_str()) {
+        messages.push(Message {
+            role: "user".to_string(),
+            blocks: vec![Block::Text { text: prompt.to_string() }],
+        });
+    }
+    messages
+}
+
+/// 按协议解析非流式响应中的模型回复
+fn parse_response(body: &Value) -> Option<Message> {
+    let body = body.get("response").unwrap_or(body);
+    let blocks = if let Some(candidate) = body.pointer("/candidates/0/content") {
+        gemini_message("assistant", candidate).blocks
+    } else if let Some(message) = body.pointer("/choices/0/message") {
+        parse_request("", &serde_json::json!({ "messages": [message] }))
+            .pop()
+            .map(|m| m.blocks)
+            .unwrap_or_default()
+    } else if let Some(text) = body.pointer("/choices/0/text").and_then(|t| t.as_str()) {
+        vec![Block::Text { text: text.to_string() }]
+    } else if body["type"] == "message" {
+        content_blocks(&body["content"])
+    } else {
+        return None;
+    };
+    Some(Message {
+        role: "assistant".to_string(),
+        blocks,
+    })
+}
+
+fn gemini_message(role: &str, content: &Value) -> Message {
+    let mut blocks = Vec::new();
+    for part in content["parts"].as_array().into_iter().flatten() {
+        if let Some(text) = part["text"].as_str() {
+            if part["thought"].as_bool() == Some(true) {
+                blocks.push(Block::Thinking { text: text.to_string() });
+            } else {
+                blocks.push(Block::Text { text: text.to_string() });
+            }
+        } else if let Some(call) = part.get("functionCall") {
+            blocks.push(Block::ToolCall {
+                id: call["id"].as_str().map(str::to_string),
+                name: call["name"].as_str().unwrap_or_default().to_string(),
+                input: call["args"].clone(),
+            });
+        } else if let Some(result) = part.get("functionResponse") {
+            blocks.push(Block::ToolResult {
+                id: result["id"]
+                    .as_str()
+                    .or_else(|| result["name"].as_str())
+                    .map(str::to_string),
+                content: value_text(&result["response"]),
+            });
+        } else if part.get("inlineData").is_some() || part.get("fileData").is_some() {
+            blocks.push(Block::Image);
+        }
+    }
+    Message {
+        role: role.to_string(),
+        blocks,
+    }
+}
+
+/// Claude / OpenAI 的 content (字符串或内容块数组)
+fn content_blocks(content: &Value) -> Vec<Block> {
+    if let Some(text) = content.as_str() {
+        return vec![Block::Text { text: text.to_string() }];
+    }
+    let mut blocks = Vec::new();
+    for block in content.as_array().into_iter().flatten() {
+        match block["type"].as_str().unwrap_or_default() {
+            "text" | "input_text" | "output_text" => blocks.push(Block::Text {
+                text: block["text"].as_str().unwrap_or_default().to_string(),
+            }),
+            "thinking" => blocks.push(Block::Thinking {
+                text: block["thinking"].as_str().unwrap_or_default().to_string(),
+            }),
+            "tool_use" => blocks.push(Block::ToolCall {
+                id: block["id"].as_str().map(str::to_string),
+                name: block["name"].as_str().unwrap_or_default().to_string(),
+                input: block["input"].clone(),
+            }),
+            "tool_result" => blocks.push(Block::ToolResult {
+                id: block["tool_use_id"].as_str().map(str::to_string),
+                content: blocks_text(&content_blocks(&block["content"])),
+            }),
+            "image" | "image_url" | "input_image" => blocks.push(Block::Image),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn blocks_text(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .filter_map(|b| match b {
+            Block::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
+
+/// Markdown 格式的会话记录
+pub fn format_markdown(transcript: &Transcript) -> String {
+    let mut out = format!("# Conversation {}\n\n", transcript.session_id);
+    let input: u64 = transcript.exchanges.iter().filter_map(|e| e.input_tokens).map(u64::from).sum();
+    let output: u64 = transcript.exchanges.iter().filter_map(|e| e.output_tokens).map(u64::from).sum();
+    out.push_str(&format!(
+        "{} requests, {} input / {} output tokens\n",
+        transcript.exchanges.len(),
+        input,
+        output
+    ));
+
+    for (index, exchange) in transcript.exchanges.iter().enumerate() {
+        let time = chrono::DateTime::from_timestamp_millis(exchange.timestamp)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| exchange.timestamp.to_string());
+        out.push_str(&format!(
+            "\n## Request {} · {} · {} · {} · {} ms\n",
+            index + 1,
+            time,
+            exchange.model.as_deref().unwrap_or("-"),
+            exchange.status,
+            exchange.duration
+        ));
+        for message in &exchange.messages {
+            push_message(&mut out, message);
+        }
+        if let Some(response) = &exchange.response {
+            push_message(&mut out, response);
+        } else if exchange.streamed {
+            out.push_str("\n_(streamed response not recorded; it appears in the next request's history)_\n");
+        }
+        if let Some(error) = &exchange.error {
+            out.push_str(&format!("\n**Error:** {}\n", error));
+        }
+    }
+    out
+}
+
+fn push_message(out: &mut String, message: &Message) {
+    let mut role = message.role.clone();
+    if let Some(first) = role.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    out.push_str(&format!("\n### {}\n", role));
+    for block in &message.blocks {
+        match block {
+            Block::Text { text } => out.push_str(&format!("\n{}\n", text)),
+            Block::Thinking { text } => {
+                out.push_str("\n<details><summary>Thinking</summary>\n\n");
+                out.push_str(text);
+                out.push_str("\n\n</details>\n");
+            }
+            Block::ToolCall { id, name, input } => {
+                out.push_str(&format!("\n**Tool call** `{}`{}\n", name, id_suffix(id)));
+                out.push_str(&format!(
+                    "\n```json\n{}\n```\n",
+                    serde_json::to_string_pretty(input).unwrap_or_default()
+                ));
+            }
+            Block::ToolResult { id, content } => {
+                out.push_str(&format!("\n**Tool result**{}\n", id_suffix(id)));
+                out.push_str(&format!("\n```\n{}\n```\n", content));
+            }
+            Block::Image => out.push_str("\n_[image]_\n"),
+        }
+    }
+}
+
+fn id_suffix(id: &Option<String>) -> String {
+    id.as_ref().map(|id| format!(" ({})", id)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn log(id: &str, url: &str, request: Value, response: Option<&str>) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: id.to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: url.to_string(),
+            status: 200,
+            duration: 10,
+            model: Some("claude-sonnet-4-5".to_string()),
+            error: None,
+            request_body: Some(request.to_string()),
+            response_body: response.map(str::to_string),
+            input_tokens: None,
+            output_tokens: None,
+            session_id: Some("s1".to_string()),
+            account: None,
+            key_id: None,
+            client_ip: None,
+            canary_target: None,
+        }
+    }
+
+    #[test]
+    fn records_only_new_messages_per_request() {
+        let first = json!({
+            "system": "be brief",
+            "messages": [{ "role": "user", "content": "hi" }]
+        });
+        let reply = json!({ "type": "message", "content": [{ "type": "text", "text": "hello" }] }).to_string();
+        let second = json!({
+            "system": "be brief",
+            "messages": [
+                { "role": "user", "content": "hi" },
+                { "role": "assistant", "content": [{ "type": "text", "text": "hello" }] },
+                { "role": "user", "content": [{ "type": "text", "text": "bye" }] }
+            ]
+        });
+        let transcript = build(
+            "s1",
+            &[
+                log("1", "/v1/messages", first, Some(&reply)),
+                log("2", "/v1/messages", second, Some("[Stream Data]")),
+            ],
+        );
+        assert_eq!(transcript.exchanges[0].messages.len(), 2);
+        assert_eq!(transcript.exchanges[0].messages[0].role, "system");
+        assert_eq!(
+            transcript.exchanges[1].messages,
+            vec![Message {
+                role: "user".to_string(),
+                blocks: vec![Block::Text { text: "bye".to_string() }],
+            }]
+        );
+        assert!(transcript.exchanges[1].streamed);
+        assert!(transcript.exchanges[1].response.is_none());
+    }
+
+    #[test]
+    fn parses_openai_tool_calls_and_results() {
+        let request = json!({
+            "messages": [
+                { "role": "user", "content": "weather?" },
+                { "role": "assistant", "content": null, "tool_calls": [
+                    { "id": "call_1", "type": "function", "function": { "name": "weather", "arguments": "{\"city\":\"Paris\"}" } }
+                ] },
+                { "role": "tool", "tool_call_id": "call_1", "content": "sunny" }
+            ]
+        });
+        let messages = parse_request("/v1/chat/completions", &request);
+        assert_eq!(
+            messages[1].blocks,
+            vec![Block::ToolCall {
+                id: Some("call_1".to_string()),
+                name: "weather".to_string(),
+                input: json!({ "city": "Paris" }),
+            }]
+        );
+        assert_eq!(
+            messages[2].blocks,
+            vec![Block::ToolResult {
+                id: Some("call_1".to_string()),
+                content: "sunny".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_gemini_contents_and_response() {
+        let request = json!({
+            "systemInstruction": { "parts": [{ "text": "sys" }] },
+            "contents": [{ "role": "user", "parts": [{ "text": "q" }, { "inlineData": { "mimeType": "image/png", "data": "..." } }] }]
+        });
+        let messages = parse_request("/v1beta/models/gemini-2.5-pro:generateContent", &request);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].blocks[1], Block::Image);
+
+        let response = json!({ "candidates": [{ "content": { "role": "model", "parts": [
+            { "text": "plan", "thought": true },
+            { "functionCall": { "name": "search", "args": { "q": "x" } } }
+        ] } }] });
+        let reply = parse_response(&response).unwrap();
+        assert_eq!(reply.role, "assistant");
+        assert_eq!(reply.blocks[0], Block::Thinking { text: "plan".to_string() });
+    }
+
+    #[test]
+    fn markdown_includes_tool_calls() {
+        let request = json!({
+            "messages": [
+                { "role": "user", "content": "hi" },
+                { "role": "assistant", "content": [{ "type": "tool_use", "id": "t1", "name": "ls", "input": { "path": "." } }] },
+                { "role": "user", "content": [{ "type": "tool_result", "tool_use_id": "t1", "content": "a.txt" }] }
+            ]
+        });
+        let transcript = build("s1", &[log("1", "/v1/messages", request, Some("[Stream Data]"))]);
+        let markdown = format_markdown(&transcript);
+        assert!(markdown.starts_with("# Conversation s1\n"));
+        assert!(markdown.contains("### Assistant"));
+        assert!(markdown.contains("**Tool call** `ls` (t1)"));
+        assert!(markdown.contains("**Tool result** (t1)\n\n```\na.txt\n```"));
+        assert!(markdown.contains("streamed response not recorded"));
+    }
+}
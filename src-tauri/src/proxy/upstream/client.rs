@@ -5,6 +5,8 @@ use reqwest::{header, Client, Response, StatusCode};
 use serde_json::Value;
 use tokio::time::Duration;
 
+use crate::proxy::timeouts::EffectiveTimeouts;
+
 // Cloud Code v1internal endpoints (fallback order: prod → daily)
 // 优先使用稳定的 prod 端点，避免影响缓存命中率
 const V1_INTERNAL_BASE_URL_PROD: &str = "https://cloudcode-pa.googleapis.com/v1internal";
@@ -15,32 +17,57 @@ const V1_INTERNAL_BASE_URL_FALLBACKS: [&str; 2] = [
 ];
 
 pub struct UpstreamClient {
-    http_client: Client,
+    proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>,
+    /// 按 (连接池配置版本, 连接超时秒) 缓存的客户端 (reqwest 的连接超时只能在客户端级别设置)
+    clients: dashmap::DashMap<(u64, u64), Client>,
 }
 
 impl UpstreamClient {
     pub fn new(proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>) -> Self {
-        let mut builder = Client::builder()
-            // Connection settings (优化连接复用，减少建立开销)
-            .connect_timeout(Duration::from_secs(20))
-            .pool_max_idle_per_host(16)                  // 每主机最多 16 个空闲连接
-            .pool_idle_timeout(Duration::from_secs(90))  // 空闲连接保持 90 秒
-            .tcp_keepalive(Duration::from_secs(60))      // TCP 保活探测 60 秒
-            .timeout(Duration::from_secs(600))
+        if let Some(config) = &proxy_config {
+            if config.enabled && !config.url.is_empty() {
+                tracing::info!("UpstreamClient enabled proxy: {}", config.url);
+            }
+        }
+
+        Self {
+            proxy_config,
+            clients: dashmap::DashMap::new(),
+        }
+    }
+
+    /// 获取指定连接超时的客户端
+    ///
+    /// 不设置客户端级总超时：非流式请求按路由设置单次超时，流式请求由空闲超时控制
+    fn client_for(&self, connect_timeout: Duration) -> Client {
+        self.client_from(crate::proxy::upstream::pool::global(), connect_timeout)
+    }
+
+    /// 按 `pool` 的当前配置获取客户端；热重载修改 proxy.upstream_pool 后丢弃按旧配置创建的客户端
+    fn client_from(&self, pool: &crate::proxy::upstream::pool::ClientPool, connect_timeout: Duration) -> Client {
+        let (generation, pool_config) = pool.versioned_config();
+        let key = (generation, connect_timeout.as_secs());
+        if let Some(client) = self.clients.get(&key) {
+            return client.clone();
+        }
+        self.clients.retain(|(g, _), _| *g == generation);
+
+        let builder = Client::builder()
+            .connect_timeout(connect_timeout)
             .user_agent("antigravity/1.11.9 windows/amd64");
+        // Connection settings (连接池/保活参数来自 proxy.upstream_pool 配置)
+        let mut builder = crate::proxy::upstream::pool::apply_pool_config(builder, &pool_config);
 
-        if let Some(config) = proxy_config {
+        if let Some(config) = &self.proxy_config {
             if config.enabled && !config.url.is_empty() {
                 if let Ok(proxy) = reqwest::Proxy::all(&config.url) {
                     builder = builder.proxy(proxy);
-                    tracing::info!("UpstreamClient enabled proxy: {}", config.url);
                 }
             }
         }
 
-        let http_client = builder.build().expect("Failed to create HTTP client");
-
-        Self { http_client }
+        let client = builder.build().expect("Failed to create HTTP client");
+        self.clients.entry(key).or_insert(client).clone()
     }
 
     /// 构建 v1internal URL
@@ -71,13 +98,19 @@ impl UpstreamClient {
     /// 调用 v1internal API（基础方法）
     /// 
     /// 发起基础网络请求，支持多端点自动 Fallback
+    /// 流式请求 (`alt=sse`) 不设总超时，等待响应头的时间受流式空闲超时限制；
+    /// 非流式请求使用路由的总超时。
     pub async fn call_v1_internal(
         &self,
         method: &str,
         access_token: &str,
         body: Value,
         query_string: Option<&str>,
+        timeouts: &EffectiveTimeouts,
     ) -> Result<Response, String> {
+        let is_stream = query_string.is_some_and(|qs| qs.contains("alt=sse"));
+        let http_client = self.client_for(timeouts.connect);
+
         // 构建 Headers (所有端点复用)
         let mut headers = header::HeaderMap::new();
         headers.insert(
@@ -89,10 +122,19 @@ impl UpstreamClient {
             header::HeaderValue::from_str(&format!("Bearer {}", access_token))
                 .map_err(|e| e.to_string())?,
         );
-        headers.insert(
-            header::USER_AGENT,
-            header::HeaderValue::from_static("antigravity/1.11.9 windows/amd64"),
-        );
+        insert_traceparent(&mut headers);
+        // User-Agent 等客户端标识按账号档案设置
+        crate::modules::device::apply_headers(&mut headers, access_token);
+
+        // 会话 ID 按账号隔离，避免不同账号出现相同的 sessionId
+        let mut body = body;
+        if let Some(device) = crate::modules::device::lookup(access_token) {
+            if let Some(request) = body.get_mut("request").and_then(|r| r.as_object_mut()) {
+                let client_session = request.get("sessionId").and_then(|v| v.as_str());
+                let session_id = crate::modules::device::scoped_session_id(&device, client_session);
+                request.insert("sessionId".to_string(), Value::String(session_id));
+            }
+        }
 
         let mut last_err: Option<String> = None;
 
@@ -101,16 +143,22 @@ impl UpstreamClient {
             let url = Self::build_url(base_url, method, query_string);
             let has_next = idx + 1 < V1_INTERNAL_BASE_URL_FALLBACKS.len();
 
-            let response = self
-                .http_client
-                .post(&url)
-                .headers(headers.clone())
-                .json(&body)
-                .send()
-                .await;
+            crate::proxy::upstream::rate_limit::global().acquire(&url).await?;
+            let mut request = http_client.post(&url).headers(headers.clone()).json(&body);
+            if !is_stream {
+                request = request.timeout(timeouts.request);
+            }
+            let response = match (is_stream, timeouts.stream_idle) {
+                (true, Some(idle)) => match tokio::time::timeout(idle, request.send()).await {
+                    Ok(res) => res.map_err(|e| e.to_string()),
+                    Err(_) => Err(format!("no response headers within {}s", idle.as_secs())),
+                },
+                _ => request.send().await.map_err(|e| e.to_string()),
+            };
 
             match response {
                 Ok(resp) => {
+                    crate::proxy::middleware::response_headers::record_upstream(resp.headers());
                     let status = resp.status();
                     if status.is_success() {
                         if idx > 0 {
@@ -192,10 +240,9 @@ impl UpstreamClient {
             header::HeaderValue::from_str(&format!("Bearer {}", access_token))
                 .map_err(|e| e.to_string())?,
         );
-        headers.insert(
-            header::USER_AGENT,
-            header::HeaderValue::from_static("antigravity/1.11.9 windows/amd64"),
-        );
+        insert_traceparent(&mut headers);
+        // User-Agent 等客户端标识按账号档案设置
+        crate::modules::device::apply_headers(&mut headers, access_token);
 
         let mut last_err: Option<String> = None;
 
@@ -203,10 +250,13 @@ impl UpstreamClient {
         for (idx, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS.iter().enumerate() {
             let url = Self::build_url(base_url, "fetchAvailableModels", None);
 
+            let timeouts = EffectiveTimeouts::default();
+            crate::proxy::upstream::rate_limit::global().acquire(&url).await?;
             let response = self
-                .http_client
+                .client_for(timeouts.connect)
                 .post(&url)
                 .headers(headers.clone())
+                .timeout(timeouts.request)
                 .json(&serde_json::json!({}))
                 .send()
                 .await;
@@ -264,6 +314,17 @@ impl UpstreamClient {
     }
 }
 
+/// 附加 W3C traceparent，关联客户端请求 ID 便于端到端排查
+fn insert_traceparent(headers: &mut header::HeaderMap) {
+    use crate::proxy::middleware::request_id;
+
+    if let Some(value) = request_id::current()
+        .and_then(|id| header::HeaderValue::from_str(&request_id::traceparent(&id)).ok())
+    {
+        headers.insert("traceparent", value);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,4 +346,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pool_reconfigure_rebuilds_cached_clients() {
+        use crate::proxy::config::UpstreamPoolConfig;
+        use crate::proxy::upstream::pool::ClientPool;
+
+        let pool = ClientPool::new(UpstreamPoolConfig::default());
+        let client = UpstreamClient::new(None);
+        client.client_from(&pool, Duration::from_secs(10));
+        client.client_from(&pool, Duration::from_secs(10));
+        client.client_from(&pool, Duration::from_secs(20));
+        assert_eq!(client.clients.len(), 2);
+
+        pool.configure(&UpstreamPoolConfig {
+            max_idle_per_host: 4,
+            ..UpstreamPoolConfig::default()
+        });
+        client.client_from(&pool, Duration::from_secs(10));
+        let keys: Vec<(u64, u64)> = client.clients.iter().map(|e| *e.key()).collect();
+        assert_eq!(keys, vec![(1, 10)]);
+    }
+
 }
@@ -4,3 +4,6 @@
 pub mod client;
 pub mod retry;
 pub mod models;
+pub mod pool;
+pub mod profiles;
+pub mod rate_limit;
@@ -0,0 +1,192 @@
+// 上游 HTTP 客户端池
+// 按 (账号/通道, 上游代理, 超时) 复用 reqwest::Client，避免每个请求重新建立连接
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use tokio::time::Duration;
+
+use crate::proxy::config::{UpstreamPoolConfig, UpstreamProxyConfig};
+
+/// 缓存键: (账号/通道标识, 代理地址, 超时秒数)
+type ClientKey = (String, String, u64);
+
+pub struct ClientPool {
+    config: RwLock<UpstreamPoolConfig>,
+    clients: DashMap<ClientKey, Client>,
+    /// 配置版本，每次配置变化加一 (池外自行缓存客户端的 UpstreamClient 据此重建)
+    generation: AtomicU64,
+}
+
+static GLOBAL_POOL: Lazy<ClientPool> = Lazy::new(|| ClientPool::new(UpstreamPoolConfig::default()));
+
+/// 全局客户端池
+pub fn global() -> &'static ClientPool {
+    &GLOBAL_POOL
+}
+
+/// 根据连接池配置创建 ClientBuilder (UpstreamClient 与客户端池共用)
+pub fn apply_pool_config(
+    mut builder: reqwest::ClientBuilder,
+    config: &UpstreamPoolConfig,
+) -> reqwest::ClientBuilder {
+    builder = builder
+        .pool_max_idle_per_host(config.max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(config.idle_timeout));
+
+    if config.tcp_keepalive > 0 {
+        builder = builder.tcp_keepalive(Duration::from_secs(config.tcp_keepalive));
+    }
+    if config.http2_only {
+        builder = builder.http2_prior_knowledge();
+    }
+    if config.http2_keep_alive_interval > 0 {
+        builder = builder
+            .http2_keep_alive_interval(Duration::from_secs(config.http2_keep_alive_interval))
+            .http2_keep_alive_while_idle(true);
+    }
+    if config.http2_adaptive_window {
+        builder = builder.http2_adaptive_window(true);
+    }
+    builder
+}
+
+fn proxy_url(upstream_proxy: Option<&UpstreamProxyConfig>) -> String {
+    match upstream_proxy {
+        Some(config) if config.enabled && !config.url.is_empty() => config.url.clone(),
+        _ => String::new(),
+    }
+}
+
+impl ClientPool {
+    pub fn new(config: UpstreamPoolConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            clients: DashMap::new(),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// 更新连接池配置；配置变化时清空已缓存的客户端
+    pub fn configure(&self, config: &UpstreamPoolConfig) {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        if *current != *config {
+            *current = config.clone();
+            self.clients.clear();
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            tracing::info!("上游连接池配置已更新: {:?}", config);
+        }
+    }
+
+    pub fn pool_config(&self) -> UpstreamPoolConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 当前配置及其版本 (在同一读锁内读取，二者一致)
+    pub fn versioned_config(&self) -> (u64, UpstreamPoolConfig) {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+        (self.generation.load(Ordering::SeqCst), config.clone())
+    }
+
+    /// 获取 (或创建) 指定账号/通道与代理组合的共享客户端
+    pub fn get(
+        &self,
+        account: &str,
+        upstream_proxy: Option<&UpstreamProxyConfig>,
+        timeout_secs: u64,
+    ) -> Result<Client, String> {
+        let timeout_secs = timeout_secs.max(5);
+        let url = proxy_url(upstream_proxy);
+        let key = (account.to_string(), url.clone(), timeout_secs);
+
+        if let Some(client) = self.clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let mut builder = apply_pool_config(
+            Client::builder().timeout(Duration::from_secs(timeout_secs)),
+            &self.pool_config(),
+        );
+        if !url.is_empty() {
+            let proxy = reqwest::Proxy::all(&url)
+                .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        Ok(self.clients.entry(key).or_insert(client).clone())
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy(url: &str) -> UpstreamProxyConfig {
+        UpstreamProxyConfig {
+            enabled: true,
+            url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn reuses_client_per_account_and_proxy() {
+        let pool = ClientPool::new(UpstreamPoolConfig::default());
+        let a = proxy("http://127.0.0.1:7890");
+        let b = proxy("http://127.0.0.1:7891");
+
+        pool.get("zai", Some(&a), 60).unwrap();
+        pool.get("zai", Some(&a), 60).unwrap();
+        assert_eq!(pool.len(), 1);
+
+        pool.get("zai", Some(&b), 60).unwrap();
+        pool.get("zai-mcp", Some(&a), 60).unwrap();
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn disabled_proxy_shares_direct_client() {
+        let pool = ClientPool::new(UpstreamPoolConfig::default());
+        let disabled = UpstreamProxyConfig {
+            enabled: false,
+            url: "http://127.0.0.1:7890".to_string(),
+        };
+
+        pool.get("zai", None, 60).unwrap();
+        pool.get("zai", Some(&disabled), 60).unwrap();
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn reconfigure_clears_cached_clients() {
+        let pool = ClientPool::new(UpstreamPoolConfig::default());
+        pool.get("zai", None, 60).unwrap();
+
+        pool.configure(&UpstreamPoolConfig::default());
+        assert_eq!(pool.len(), 1);
+
+        pool.configure(&UpstreamPoolConfig {
+            max_idle_per_host: 4,
+            ..UpstreamPoolConfig::default()
+        });
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn invalid_proxy_url_is_error() {
+        let pool = ClientPool::new(UpstreamPoolConfig::default());
+        assert!(pool.get("zai", Some(&proxy("::not a url::")), 60).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+// 上游客户端标识档案
+// 上游对不同客户端 (IDE 版本 / 平台) 的处理存在差异，请求头按档案统一设置，
+// 档案可全局指定，也可按账号单独指定
+
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::proxy::config::{ClientProfile, ClientProfileConfig};
+
+static GLOBAL: Lazy<RwLock<ClientProfileConfig>> =
+    Lazy::new(|| RwLock::new(ClientProfileConfig::default()));
+
+/// 内置档案
+pub fn builtin() -> Vec<ClientProfile> {
+    [
+        ("antigravity-windows", "antigravity/1.11.9 windows/amd64"),
+        ("antigravity-macos", "antigravity/1.11.9 darwin/arm64"),
+        ("antigravity-linux", "antigravity/1.11.9 linux/amd64"),
+    ]
+    .into_iter()
+    .map(|(name, user_agent)| ClientProfile {
+        name: name.to_string(),
+        user_agent: user_agent.to_string(),
+        headers: HashMap::new(),
+    })
+    .collect()
+}
+
+/// 更新全局档案配置
+pub fn configure(config: &ClientProfileConfig) {
+    let mut current = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+    if *current != *config {
+        *current = config.clone();
+        tracing::info!("客户端标识档案已更新，默认档案: {}", config.active);
+    }
+}
+
+/// 配置中可用的全部档案 (自定义档案覆盖同名内置档案)
+pub fn available(config: &ClientProfileConfig) -> Vec<ClientProfile> {
+    let mut profiles: Vec<ClientProfile> = builtin()
+        .into_iter()
+        .filter(|b| !config.profiles.iter().any(|p| p.name == b.name))
+        .collect();
+    profiles.extend(config.profiles.iter().cloned());
+    profiles
+}
+
+/// 按名称查找档案；名称为空或不存在时依次回退到默认档案、第一个内置档案
+pub fn resolve_in(config: &ClientProfileConfig, name: Option<&str>) -> ClientProfile {
+    let profiles = available(config);
+    name.into_iter()
+        .chain(std::iter::once(config.active.as_str()))
+        .find_map(|n| profiles.iter().find(|p| p.name == n))
+        .cloned()
+        .unwrap_or_else(|| builtin().remove(0))
+}
+
+/// 使用全局配置解析档案
+pub fn resolve(name: Option<&str>) -> ClientProfile {
+    let config = GLOBAL.read().unwrap_or_else(|e| e.into_inner());
+    resolve_in(&config, name)
+}
+
+/// 将档案的请求头写入 HeaderMap (覆盖已有的同名请求头)
+pub fn apply(headers: &mut HeaderMap, profile: &ClientProfile) {
+    if let Ok(value) = HeaderValue::from_str(&profile.user_agent) {
+        headers.insert(USER_AGENT, value);
+    }
+    for (name, value) in &profile.headers {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!("客户端标识档案 {} 中的请求头无效: {}", profile.name, name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(name: &str, user_agent: &str) -> ClientProfile {
+        ClientProfile {
+            name: name.to_string(),
+            user_agent: user_agent.to_string(),
+            headers: HashMap::from([("x-client-version".to_string(), "1.12.0".to_string())]),
+        }
+    }
+
+    #[test]
+    fn resolves_account_profile_then_active() {
+        let config = ClientProfileConfig {
+            active: "antigravity-macos".to_string(),
+            profiles: vec![custom("ide-1.12", "antigravity/1.12.0 windows/amd64")],
+        };
+        assert_eq!(resolve_in(&config, Some("ide-1.12")).name, "ide-1.12");
+        assert_eq!(resolve_in(&config, None).name, "antigravity-macos");
+        assert_eq!(resolve_in(&config, Some("missing")).name, "antigravity-macos");
+    }
+
+    #[test]
+    fn custom_profile_overrides_builtin() {
+        let config = ClientProfileConfig {
+            active: "antigravity-windows".to_string(),
+            profiles: vec![custom("antigravity-windows", "antigravity/1.12.0 windows/amd64")],
+        };
+        assert_eq!(available(&config).len(), builtin().len());
+        assert_eq!(
+            resolve_in(&config, None).user_agent,
+            "antigravity/1.12.0 windows/amd64"
+        );
+    }
+
+    #[test]
+    fn unknown_active_falls_back_to_builtin() {
+        let config = ClientProfileConfig {
+            active: "nope".to_string(),
+            profiles: Vec::new(),
+        };
+        assert_eq!(resolve_in(&config, None).name, "antigravity-windows");
+    }
+
+    #[test]
+    fn apply_sets_user_agent_and_extra_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("old"));
+        apply(&mut headers, &custom("ide-1.12", "antigravity/1.12.0 windows/amd64"));
+        assert_eq!(headers[USER_AGENT], "antigravity/1.12.0 windows/amd64");
+        assert_eq!(headers["x-client-version"], "1.12.0");
+    }
+}
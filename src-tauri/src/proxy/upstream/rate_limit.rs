@@ -0,0 +1,246 @@
+// 上游请求速率平滑
+// 多个客户端同时涌入时，上游请求按全局与按主机的令牌桶排队发出 (预约令牌后等待，而非直接拒绝)，
+// 避免短时间的突发流量触发上游的异常流量检测
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::{UpstreamBucketConfig, UpstreamRateLimitConfig};
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// 可用令牌数，允许为负 (负值表示已预约的未来令牌)
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(config: &UpstreamBucketConfig, now: Instant) -> Self {
+        Self {
+            tokens: config.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, config: &UpstreamBucketConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * config.qps).min(config.burst as f64);
+        self.updated = now;
+    }
+
+    /// 预约一个令牌后需要等待的时间
+    fn wait_after_take(&self, config: &UpstreamBucketConfig) -> Duration {
+        let remaining = self.tokens - 1.0;
+        if remaining >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-remaining / config.qps)
+        }
+    }
+}
+
+fn usable(config: &UpstreamBucketConfig) -> bool {
+    config.qps > 0.0 && config.burst > 0
+}
+
+#[derive(Default)]
+struct Buckets {
+    global: Option<Bucket>,
+    hosts: HashMap<String, Bucket>,
+}
+
+pub struct UpstreamRateLimiter {
+    config: RwLock<UpstreamRateLimitConfig>,
+    buckets: Mutex<Buckets>,
+}
+
+static GLOBAL_LIMITER: Lazy<UpstreamRateLimiter> =
+    Lazy::new(|| UpstreamRateLimiter::new(UpstreamRateLimitConfig::default()));
+
+/// 全局上游限速器
+pub fn global() -> &'static UpstreamRateLimiter {
+    &GLOBAL_LIMITER
+}
+
+impl UpstreamRateLimiter {
+    pub fn new(config: UpstreamRateLimitConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// 热更新限速配置；配置变化时重置所有桶
+    pub fn configure(&self, config: &UpstreamRateLimitConfig) {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+        if *current != *config {
+            *current = config.clone();
+            *self.buckets.lock().unwrap_or_else(|e| e.into_inner()) = Buckets::default();
+            tracing::info!("上游速率平滑配置已更新: {:?}", config);
+        }
+    }
+
+    /// 发往 `url` 之前调用：按需等待令牌；排队时间超过上限时返回错误
+    pub async fn acquire(&self, url: &str) -> Result<(), String> {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_ascii_lowercase()))
+            .unwrap_or_default();
+        let wait = self.reserve_at(&host, Instant::now())?;
+        if !wait.is_zero() {
+            tracing::debug!("上游速率平滑: {} 排队 {}ms", host, wait.as_millis());
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// 同时从全局桶与主机桶预约令牌，返回需要等待的时间 (取两者较大值)
+    fn reserve_at(&self, host: &str, now: Instant) -> Result<Duration, String> {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+        if !config.enabled {
+            return Ok(Duration::ZERO);
+        }
+        let host_config = config
+            .per_host
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(host))
+            .map(|(_, c)| c)
+            .filter(|c| usable(c));
+        let global_config = Some(&config.global).filter(|c| usable(c));
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let mut wait = Duration::ZERO;
+        if let Some(c) = global_config {
+            let bucket = buckets.global.get_or_insert_with(|| Bucket::full(c, now));
+            bucket.refill(c, now);
+            wait = wait.max(bucket.wait_after_take(c));
+        }
+        if let Some(c) = host_config {
+            let bucket = buckets
+                .hosts
+                .entry(host.to_string())
+                .or_insert_with(|| Bucket::full(c, now));
+            bucket.refill(c, now);
+            wait = wait.max(bucket.wait_after_take(c));
+        }
+
+        if config.max_wait_secs > 0 && wait > Duration::from_secs(config.max_wait_secs) {
+            return Err(format!(
+                "上游请求排队超时: 需等待 {}s，超过上限 {}s",
+                wait.as_secs(),
+                config.max_wait_secs
+            ));
+        }
+
+        // 未超限才真正扣减令牌，被拒绝的请求不占用后续配额
+        if global_config.is_some() {
+            if let Some(bucket) = buckets.global.as_mut() {
+                bucket.tokens -= 1.0;
+            }
+        }
+        if host_config.is_some() {
+            if let Some(bucket) = buckets.hosts.get_mut(host) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(wait)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(qps: f64, burst: u32) -> UpstreamRateLimiter {
+        UpstreamRateLimiter::new(UpstreamRateLimitConfig {
+            enabled: true,
+            global: UpstreamBucketConfig { qps, burst },
+            per_host: HashMap::new(),
+            max_wait_secs: 0,
+        })
+    }
+
+    #[test]
+    fn disabled_never_waits() {
+        let limiter = UpstreamRateLimiter::new(UpstreamRateLimitConfig::default());
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn burst_passes_then_requests_are_spaced() {
+        let limiter = limiter(2.0, 3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::ZERO);
+        }
+        assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::from_millis(500));
+        assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn tokens_refill_over_time() {
+        let limiter = limiter(1.0, 1);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::ZERO);
+        assert!(limiter.reserve_at("a", now).unwrap() > Duration::ZERO);
+        // 第二次预约已占用 t+1s 的令牌，t+2s 时重新可用
+        let later = now + Duration::from_secs(2);
+        assert_eq!(limiter.reserve_at("a", later).unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn per_host_bucket_is_independent_and_case_insensitive() {
+        let mut config = UpstreamRateLimitConfig {
+            enabled: true,
+            global: UpstreamBucketConfig { qps: 100.0, burst: 100 },
+            per_host: HashMap::new(),
+            max_wait_secs: 0,
+        };
+        config
+            .per_host
+            .insert("Slow.Example.com".to_string(), UpstreamBucketConfig { qps: 1.0, burst: 1 });
+        let limiter = UpstreamRateLimiter::new(config);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at("slow.example.com", now).unwrap(), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve_at("slow.example.com", now).unwrap(),
+            Duration::from_secs(1)
+        );
+        assert_eq!(limiter.reserve_at("fast.example.com", now).unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn rejects_beyond_max_wait_without_consuming() {
+        let limiter = UpstreamRateLimiter::new(UpstreamRateLimitConfig {
+            enabled: true,
+            global: UpstreamBucketConfig { qps: 1.0, burst: 1 },
+            per_host: HashMap::new(),
+            max_wait_secs: 1,
+        });
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::ZERO);
+        assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::from_secs(1));
+        assert!(limiter.reserve_at("a", now).is_err());
+        assert!(limiter.reserve_at("a", now).is_err());
+        // 被拒绝的请求没有占用令牌
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.reserve_at("a", later).unwrap(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn configure_resets_buckets() {
+        let limiter = limiter(1.0, 1);
+        let now = Instant::now();
+        limiter.reserve_at("a", now).unwrap();
+        assert!(limiter.reserve_at("a", now).unwrap() > Duration::ZERO);
+        let mut config = limiter.config.read().unwrap().clone();
+        config.global.burst = 2;
+        limiter.configure(&config);
+        assert_eq!(limiter.reserve_at("a", now).unwrap(), Duration::ZERO);
+    }
+}
@@ -3,6 +3,10 @@
 
 use regex::Regex;
 use once_cell::sync::Lazy;
+use std::time::Duration;
+
+use crate::proxy::config::RetryPolicyConfig;
+use crate::proxy::TokenManager;
 
 static DURATION_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"([\d.]+)\s*(ms|s|m|h)").unwrap()
@@ -66,10 +70,82 @@ pub fn parse_retry_delay(error_text: &str) -> Option<u64> {
     None
 }
 
+impl RetryPolicyConfig {
+    /// 本次请求的最大尝试次数
+    pub fn attempts(&self, pool_size: usize, stream: bool) -> usize {
+        if stream && !self.retry_streaming {
+            return 1;
+        }
+        let attempts = self.max_attempts.max(1);
+        if self.rotate_account {
+            attempts.min(pool_size.max(1))
+        } else {
+            attempts
+        }
+    }
+
+    pub fn should_retry(&self, status: u16) -> bool {
+        self.retry_statuses.contains(&status)
+    }
+
+    /// 第 `attempt` 次尝试 (从 0 开始) 失败后的退避时长
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let factor = 1u64.checked_shl(attempt.min(16) as u32).unwrap_or(u64::MAX);
+        Duration::from_millis(self.backoff_base_ms.saturating_mul(factor).min(self.backoff_max_ms))
+    }
+}
+
+/// 按重试策略获取账号: 轮换时重试换号，否则固定在上一次使用的账号上
+pub async fn token_for_attempt(
+    token_manager: &TokenManager,
+    policy: &RetryPolicyConfig,
+    previous: Option<&str>,
+    quota_group: &str,
+    session_id: Option<&str>,
+    model: Option<&str>,
+) -> Result<(String, String, String), String> {
+    match previous {
+        Some(email) if !policy.rotate_account => {
+            crate::proxy::token_manager::with_pinned_account(
+                email.to_string(),
+                token_manager.get_token_for_model(quota_group, false, session_id, model),
+            )
+            .await
+        }
+        _ => {
+            token_manager
+                .get_token_for_model(quota_group, previous.is_some(), session_id, model)
+                .await
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn policy_limits_attempts_and_backoff() {
+        let policy = RetryPolicyConfig::default();
+        assert_eq!(policy.attempts(2, false), 2);
+        assert_eq!(policy.attempts(10, true), 3);
+        assert_eq!(policy.attempts(0, false), 1);
+        assert!(policy.should_retry(429));
+        assert!(!policy.should_retry(404));
+        assert_eq!(policy.backoff(0), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(2), Duration::from_millis(4000));
+        assert_eq!(policy.backoff(10), Duration::from_millis(8000));
+
+        let same_account = RetryPolicyConfig {
+            rotate_account: false,
+            retry_streaming: false,
+            max_attempts: 5,
+            ..policy
+        };
+        assert_eq!(same_account.attempts(1, false), 5);
+        assert_eq!(same_account.attempts(10, true), 1);
+    }
+
     #[test]
     fn test_parse_duration_ms() {
         assert_eq!(parse_duration_ms("1.5s"), Some(1500));
@@ -0,0 +1,181 @@
+// 单账号用量上限: 按固定时间窗口统计各账号的请求数与 Token 数，达到上限的账号在窗口结束前退出轮换
+//
+// 计数仅保存在内存中，重启后从零开始。启用多实例协同时，其他实例上报的用量一并计入上限。
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::proxy::config::UsageCapConfig;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageWindow {
+    /// 窗口起始时间 (Unix 秒)
+    pub started_at: i64,
+    pub requests: u64,
+    pub tokens: u64,
+}
+
+/// 各账号当前窗口的用量 (key: 账号邮箱)
+#[derive(Default)]
+pub struct UsageTracker {
+    windows: DashMap<String, UsageWindow>,
+    /// 其他实例上报的用量 (key: (实例 ID, 账号邮箱))
+    remote: DashMap<(String, String), UsageWindow>,
+}
+
+/// 按 UTC 对齐的窗口起点 (窗口为 1 天时即 UTC 零点)
+fn window_start(now: i64, window_secs: u64) -> i64 {
+    let window = window_secs.max(1) as i64;
+    now - now.rem_euclid(window)
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_window<R>(&self, email: &str, now: i64, window_secs: u64, f: impl FnOnce(&mut UsageWindow) -> R) -> R {
+        let start = window_start(now, window_secs);
+        let mut entry = self.windows.entry(email.to_string()).or_default();
+        if entry.started_at != start {
+            *entry = UsageWindow {
+                started_at: start,
+                ..Default::default()
+            };
+        }
+        f(&mut entry)
+    }
+
+    pub fn record_request(&self, email: &str, now: i64, window_secs: u64) {
+        self.with_window(email, now, window_secs, |w| w.requests += 1);
+    }
+
+    pub fn record_tokens(&self, email: &str, tokens: u64, now: i64, window_secs: u64) {
+        self.with_window(email, now, window_secs, |w| w.tokens += tokens);
+    }
+
+    /// 记录其他实例当前窗口的用量 (覆盖该实例此前的上报)
+    pub fn set_remote(&self, instance: &str, email: &str, window: UsageWindow) {
+        self.remote.insert((instance.to_string(), email.to_string()), window);
+    }
+
+    /// 账号在指定窗口内的总用量 (本实例 + 其他实例): (请求数, Token 数)
+    fn totals(&self, email: &str, start: i64) -> (u64, u64) {
+        let local = self
+            .windows
+            .get(email)
+            .filter(|w| w.started_at == start)
+            .map_or((0, 0), |w| (w.requests, w.tokens));
+        self.remote
+            .iter()
+            .filter(|e| e.key().1 == email && e.started_at == start)
+            .fold(local, |(requests, tokens), e| (requests + e.requests, tokens + e.tokens))
+    }
+
+    /// 账号已达上限时返回窗口结束时间 (Unix 秒)
+    pub fn capped_until(&self, config: &UsageCapConfig, account_id: &str, email: &str, now: i64) -> Option<i64> {
+        if !config.enabled {
+            return None;
+        }
+        let (max_requests, max_tokens) = config.caps_for(account_id, email);
+        if max_requests.is_none() && max_tokens.is_none() {
+            return None;
+        }
+        let start = window_start(now, config.window_secs);
+        let (requests, tokens) = self.totals(email, start);
+        let over = max_requests.map_or(false, |max| requests >= max)
+            || max_tokens.map_or(false, |max| tokens >= max);
+        over.then(|| start + config.window_secs.max(1) as i64)
+    }
+
+    /// 当前窗口内各账号的用量
+    pub fn snapshot(&self, window_secs: u64, now: i64) -> Vec<(String, UsageWindow)> {
+        let start = window_start(now, window_secs);
+        let mut all: Vec<_> = self
+            .windows
+            .iter()
+            .filter(|e| e.started_at == start)
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect();
+        all.sort_by(|a, b| a.0.cmp(&b.0));
+        all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::AccountUsageCap;
+
+    const DAY: u64 = 86400;
+
+    #[test]
+    fn caps_apply_within_window_and_reset_after() {
+        let mut config = UsageCapConfig {
+            enabled: true,
+            max_requests: Some(2),
+            max_tokens: Some(1000),
+            ..Default::default()
+        };
+        config.accounts.insert(
+            "big@example.com".to_string(),
+            AccountUsageCap {
+                max_requests: Some(10),
+                max_tokens: None,
+            },
+        );
+        let tracker = UsageTracker::new();
+        let now = 10 * DAY as i64 + 3600;
+
+        tracker.record_request("a@example.com", now, DAY);
+        assert_eq!(tracker.capped_until(&config, "id-a", "a@example.com", now), None);
+        tracker.record_request("a@example.com", now, DAY);
+        assert_eq!(
+            tracker.capped_until(&config, "id-a", "a@example.com", now),
+            Some(11 * DAY as i64)
+        );
+        // 下一个窗口自动恢复
+        assert_eq!(tracker.capped_until(&config, "id-a", "a@example.com", 11 * DAY as i64), None);
+
+        // 覆盖项提高请求上限，Token 上限沿用全局
+        tracker.record_request("big@example.com", now, DAY);
+        tracker.record_request("big@example.com", now, DAY);
+        assert_eq!(tracker.capped_until(&config, "id-b", "big@example.com", now), None);
+        tracker.record_tokens("big@example.com", 1200, now, DAY);
+        assert!(tracker.capped_until(&config, "id-b", "big@example.com", now).is_some());
+
+        config.enabled = false;
+        assert_eq!(tracker.capped_until(&config, "id-a", "a@example.com", now), None);
+    }
+
+    #[test]
+    fn remote_usage_counts_towards_caps() {
+        let config = UsageCapConfig {
+            enabled: true,
+            max_requests: Some(3),
+            ..Default::default()
+        };
+        let tracker = UsageTracker::new();
+        let now = 10 * DAY as i64 + 3600;
+        let remote = |requests| UsageWindow {
+            started_at: 10 * DAY as i64,
+            requests,
+            tokens: 0,
+        };
+
+        tracker.record_request("a@example.com", now, DAY);
+        tracker.set_remote("node-2", "a@example.com", remote(1));
+        assert_eq!(tracker.capped_until(&config, "id-a", "a@example.com", now), None);
+        tracker.set_remote("node-3", "a@example.com", remote(1));
+        assert!(tracker.capped_until(&config, "id-a", "a@example.com", now).is_some());
+
+        // 上报覆盖旧值；旧窗口的上报不计入
+        tracker.set_remote("node-3", "a@example.com", UsageWindow { started_at: 9 * DAY as i64, ..remote(5) });
+        assert_eq!(tracker.capped_until(&config, "id-a", "a@example.com", now), None);
+    }
+
+    #[test]
+    fn windows_align_to_utc_boundaries() {
+        assert_eq!(window_start(86400 * 3 + 5, DAY), 86400 * 3);
+        assert_eq!(window_start(7250, 3600), 7200);
+    }
+}
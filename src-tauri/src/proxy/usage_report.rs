@@ -0,0 +1,202 @@
+// 用量报告: 按模型 / 账号 / API Key 汇总请求日志中的 Token 用量，并按单价表估算等值费用
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::proxy::config::ModelPrice;
+
+/// 数据库按 (模型, 账号, 密钥) 聚合后的一行
+#[derive(Debug, Clone, Default)]
+pub struct UsageRow {
+    pub model: Option<String>,
+    pub account: Option<String>,
+    pub key_id: Option<String>,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageGroup {
+    pub name: String,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// 估算费用 (美元)；未提供单价表时为空，部分模型无单价时仅累计有单价的部分
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    /// 统计起点 (Unix 毫秒)
+    pub since: i64,
+    pub total: UsageGroup,
+    pub by_model: Vec<UsageGroup>,
+    pub by_account: Vec<UsageGroup>,
+    pub by_key: Vec<UsageGroup>,
+    /// 有用量但没有配置单价的模型
+    pub unpriced_models: Vec<String>,
+}
+
+/// 查找模型单价: 精确匹配优先，其次为最长的 `前缀*` 匹配
+pub fn price_for<'a>(pricing: &'a HashMap<String, ModelPrice>, model: &str) -> Option<&'a ModelPrice> {
+    if let Some(price) = pricing.get(model) {
+        return Some(price);
+    }
+    pricing
+        .iter()
+        .filter_map(|(pattern, price)| {
+            let prefix = pattern.strip_suffix('*')?;
+            model.starts_with(prefix).then_some((prefix.len(), price))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, price)| price)
+}
+
+fn row_cost(row: &UsageRow, pricing: &HashMap<String, ModelPrice>) -> Option<f64> {
+    let price = price_for(pricing, row.model.as_deref()?)?;
+    Some(
+        (row.input_tokens as f64 * price.input_per_mtok + row.output_tokens as f64 * price.output_per_mtok)
+            / 1_000_000.0,
+    )
+}
+
+fn add(group: &mut UsageGroup, row: &UsageRow, cost: Option<f64>, with_costs: bool) {
+    group.requests += row.requests;
+    group.input_tokens += row.input_tokens;
+    group.output_tokens += row.output_tokens;
+    if with_costs {
+        group.cost = Some(group.cost.unwrap_or(0.0) + cost.unwrap_or(0.0));
+    }
+}
+
+/// 汇总用量；`pricing` 为空时不计算费用
+pub fn build(rows: &[UsageRow], pricing: Option<&HashMap<String, ModelPrice>>, since: i64) -> UsageReport {
+    let mut total = UsageGroup {
+        name: "total".to_string(),
+        ..Default::default()
+    };
+    let mut by_model: BTreeMap<String, UsageGroup> = BTreeMap::new();
+    let mut by_account: BTreeMap<String, UsageGroup> = BTreeMap::new();
+    let mut by_key: BTreeMap<String, UsageGroup> = BTreeMap::new();
+    let mut unpriced = BTreeSet::new();
+
+    for row in rows {
+        let cost = pricing.and_then(|p| row_cost(row, p));
+        if pricing.is_some() && cost.is_none() {
+            unpriced.insert(row.model.clone().unwrap_or_else(|| "-".to_string()));
+        }
+        let with_costs = pricing.is_some();
+        add(&mut total, row, cost, with_costs);
+        for (groups, key) in [
+            (&mut by_model, &row.model),
+            (&mut by_account, &row.account),
+            (&mut by_key, &row.key_id),
+        ] {
+            let name = key.clone().unwrap_or_else(|| "-".to_string());
+            let group = groups.entry(name.clone()).or_insert_with(|| UsageGroup {
+                name,
+                ..Default::default()
+            });
+            add(group, row, cost, with_costs);
+        }
+    }
+
+    let sorted = |groups: BTreeMap<String, UsageGroup>| {
+        let mut groups: Vec<UsageGroup> = groups.into_values().collect();
+        groups.sort_by(|a, b| (b.input_tokens + b.output_tokens).cmp(&(a.input_tokens + a.output_tokens)));
+        groups
+    };
+    UsageReport {
+        since,
+        total,
+        by_model: sorted(by_model),
+        by_account: sorted(by_account),
+        by_key: sorted(by_key),
+        unpriced_models: unpriced.into_iter().collect(),
+    }
+}
+
+/// 文本报告
+pub fn format_report(report: &UsageReport) -> String {
+    let line = |g: &UsageGroup| {
+        let cost = g.cost.map(|c| format!("  ${:>10.4}", c)).unwrap_or_default();
+        format!(
+            "    {:<40} {:>7} req  {:>12} in  {:>12} out{}\n",
+            g.name, g.requests, g.input_tokens, g.output_tokens, cost
+        )
+    };
+
+    let mut out = String::new();
+    for (title, groups) in [
+        ("by model", &report.by_model),
+        ("by account", &report.by_account),
+        ("by key", &report.by_key),
+    ] {
+        out.push_str(&format!("{}:\n", title));
+        for group in groups {
+            out.push_str(&line(group));
+        }
+    }
+    out.push_str(&line(&report.total));
+    if !report.unpriced_models.is_empty() {
+        out.push_str(&format!("no price configured for: {}\n", report.unpriced_models.join(", ")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(input: f64, output: f64) -> ModelPrice {
+        ModelPrice {
+            input_per_mtok: input,
+            output_per_mtok: output,
+        }
+    }
+
+    #[test]
+    fn exact_price_wins_over_longest_prefix() {
+        let mut pricing = HashMap::new();
+        pricing.insert("gemini-*".to_string(), price(1.0, 1.0));
+        pricing.insert("gemini-2.5-pro*".to_string(), price(2.0, 2.0));
+        pricing.insert("gemini-2.5-pro-high".to_string(), price(3.0, 3.0));
+
+        assert_eq!(price_for(&pricing, "gemini-2.5-pro-high").unwrap().input_per_mtok, 3.0);
+        assert_eq!(price_for(&pricing, "gemini-2.5-pro-low").unwrap().input_per_mtok, 2.0);
+        assert_eq!(price_for(&pricing, "gemini-2.5-flash").unwrap().input_per_mtok, 1.0);
+        assert!(price_for(&pricing, "claude-sonnet-4-5").is_none());
+    }
+
+    #[test]
+    fn groups_usage_and_costs() {
+        let mut pricing = HashMap::new();
+        pricing.insert("gemini-2.5-pro".to_string(), price(1.25, 10.0));
+        let row = |model: &str, account: &str, key: &str, input: u64, output: u64| UsageRow {
+            model: Some(model.to_string()),
+            account: Some(account.to_string()),
+            key_id: Some(key.to_string()),
+            requests: 1,
+            input_tokens: input,
+            output_tokens: output,
+        };
+        let rows = vec![
+            row("gemini-2.5-pro", "a@example.com", "k1", 1_000_000, 100_000),
+            row("gemini-2.5-pro", "b@example.com", "k2", 2_000_000, 0),
+            row("claude-sonnet-4-5", "a@example.com", "k1", 10, 10),
+        ];
+
+        let report = build(&rows, Some(&pricing), 0);
+        assert_eq!(report.total.requests, 3);
+        assert!((report.total.cost.unwrap() - 4.75).abs() < 1e-9);
+        assert_eq!(report.by_account[0].name, "b@example.com");
+        let a = report.by_account.iter().find(|g| g.name == "a@example.com").unwrap();
+        assert!((a.cost.unwrap() - 2.25).abs() < 1e-9);
+        assert_eq!(report.unpriced_models, vec!["claude-sonnet-4-5"]);
+
+        let without_costs = build(&rows, None, 0);
+        assert!(without_costs.total.cost.is_none());
+        assert!(without_costs.unpriced_models.is_empty());
+        assert!(!format_report(&without_costs).contains('$'));
+    }
+}
@@ -0,0 +1,430 @@
+// 请求校验: 在转发上游之前按各协议的请求格式检查必填字段、角色与数值范围，
+// 返回指明具体字段的 400 错误；严格模式下额外拒绝未知的顶层字段
+use serde_json::{Map, Value};
+
+use crate::proxy::config::ValidationMode;
+use crate::proxy::errors::Protocol;
+
+/// 需要校验的接口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    AnthropicMessages,
+    AnthropicCountTokens,
+    OpenAIChat,
+    OpenAICompletions,
+    GeminiGenerate,
+}
+
+impl Endpoint {
+    /// 仅校验 POST 的对话 / 补全接口
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "/v1/messages" => Some(Endpoint::AnthropicMessages),
+            "/v1/messages/count_tokens" => Some(Endpoint::AnthropicCountTokens),
+            "/v1/chat/completions" => Some(Endpoint::OpenAIChat),
+            "/v1/completions" => Some(Endpoint::OpenAICompletions),
+            _ => {
+                let rest = path.strip_prefix("/v1beta/models/")?;
+                (rest.ends_with(":generateContent") || rest.ends_with(":streamGenerateContent"))
+                    .then_some(Endpoint::GeminiGenerate)
+            }
+        }
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        match self {
+            Endpoint::AnthropicMessages | Endpoint::AnthropicCountTokens => Protocol::Anthropic,
+            Endpoint::OpenAIChat | Endpoint::OpenAICompletions => Protocol::OpenAI,
+            Endpoint::GeminiGenerate => Protocol::Gemini,
+        }
+    }
+
+    /// 严格模式下允许的顶层字段
+    fn known_fields(&self) -> &'static [&'static str] {
+        match self {
+            Endpoint::AnthropicMessages => &[
+                "model", "messages", "system", "max_tokens", "metadata", "stop_sequences", "stream",
+                "temperature", "top_p", "top_k", "tools", "tool_choice", "thinking", "output_config",
+                "service_tier", "container", "mcp_servers", "context_management",
+            ],
+            Endpoint::AnthropicCountTokens => &[
+                "model", "messages", "system", "tools", "tool_choice", "thinking", "mcp_servers",
+                "context_management",
+            ],
+            Endpoint::OpenAIChat => &[
+                "model", "messages", "max_tokens", "max_completion_tokens", "temperature", "top_p", "n",
+                "stream", "stream_options", "stop", "presence_penalty", "frequency_penalty", "logit_bias",
+                "logprobs", "top_logprobs", "user", "response_format", "seed", "tools", "tool_choice",
+                "parallel_tool_calls", "functions", "function_call", "reasoning_effort", "modalities",
+                "audio", "prediction", "store", "metadata", "service_tier",
+            ],
+            Endpoint::OpenAICompletions => &[
+                "model", "prompt", "suffix", "max_tokens", "temperature", "top_p", "n", "stream",
+                "stream_options", "logprobs", "echo", "stop", "presence_penalty", "frequency_penalty",
+                "best_of", "logit_bias", "user", "seed",
+            ],
+            Endpoint::GeminiGenerate => &[
+                "contents", "systemInstruction", "system_instruction", "tools", "toolConfig", "tool_config",
+                "safetySettings", "safety_settings", "generationConfig", "generation_config",
+                "cachedContent", "cached_content", "labels",
+            ],
+        }
+    }
+}
+
+/// 校验失败: 出错的字段路径 (如 `messages[1].role`) 与说明
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub param: String,
+    pub message: String,
+}
+
+type Check = Result<(), ValidationError>;
+
+fn fail(param: impl Into<String>, message: impl Into<String>) -> ValidationError {
+    ValidationError {
+        param: param.into(),
+        message: message.into(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn required<'a>(body: &'a Map<String, Value>, field: &str) -> Result<&'a Value, ValidationError> {
+    body.get(field)
+        .filter(|v| !v.is_null())
+        .ok_or_else(|| fail(field, format!("`{}` is required", field)))
+}
+
+fn required_string(body: &Map<String, Value>, field: &str) -> Check {
+    match required(body, field)? {
+        Value::String(s) if !s.trim().is_empty() => Ok(()),
+        Value::String(_) => Err(fail(field, format!("`{}` must not be empty", field))),
+        other => Err(fail(field, format!("`{}` must be a string, got {}", field, type_name(other)))),
+    }
+}
+
+fn required_array<'a>(body: &'a Map<String, Value>, field: &str) -> Result<&'a Vec<Value>, ValidationError> {
+    match required(body, field)? {
+        Value::Array(items) => Ok(items),
+        other => Err(fail(field, format!("`{}` must be an array, got {}", field, type_name(other)))),
+    }
+}
+
+/// 可选整数字段: 存在时必须为不小于 `min` 的整数
+fn optional_integer(body: &Map<String, Value>, param: &str, field: &str, min: i64) -> Check {
+    match body.get(field) {
+        None | Some(Value::Null) => Ok(()),
+        Some(value) => match value.as_i64() {
+            Some(n) if n >= min => Ok(()),
+            Some(n) => Err(fail(param, format!("`{}` must be at least {}, got {}", param, min, n))),
+            None => Err(fail(param, format!("`{}` must be an integer, got {}", param, value))),
+        },
+    }
+}
+
+/// 可选数值字段: 存在时必须在 [min, max] 范围内
+fn optional_number(body: &Map<String, Value>, param: &str, field: &str, min: f64, max: f64) -> Check {
+    match body.get(field) {
+        None | Some(Value::Null) => Ok(()),
+        Some(value) => match value.as_f64() {
+            Some(n) if (min..=max).contains(&n) => Ok(()),
+            Some(n) => Err(fail(param, format!("`{}` must be between {} and {}, got {}", param, min, max, n))),
+            None => Err(fail(param, format!("`{}` must be a number, got {}", param, type_name(value)))),
+        },
+    }
+}
+
+fn optional_bool(body: &Map<String, Value>, field: &str) -> Check {
+    match body.get(field) {
+        None | Some(Value::Null) | Some(Value::Bool(_)) => Ok(()),
+        Some(other) => Err(fail(field, format!("`{}` must be a boolean, got {}", field, type_name(other)))),
+    }
+}
+
+fn check_role(param: String, role: Option<&Value>, allowed: &[&str], hint: &str) -> Check {
+    match role {
+        Some(Value::String(role)) if allowed.contains(&role.as_str()) => Ok(()),
+        Some(Value::String(role)) => Err(fail(
+            param.clone(),
+            format!("`{}` must be one of: {} (got \"{}\"){}", param, allowed.join(", "), role, hint),
+        )),
+        Some(other) => Err(fail(param.clone(), format!("`{}` must be a string, got {}", param, type_name(other)))),
+        None => Err(fail(param.clone(), format!("`{}` is required", param))),
+    }
+}
+
+fn unknown_fields(endpoint: Endpoint, body: &Map<String, Value>) -> Check {
+    let known = endpoint.known_fields();
+    match body.keys().find(|k| !known.contains(&k.as_str())) {
+        Some(field) => Err(fail(
+            field.as_str(),
+            format!("Unknown field `{}` (rejected in strict validation mode)", field),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn anthropic_messages(body: &Map<String, Value>, mode: ValidationMode, count_tokens: bool) -> Check {
+    required_string(body, "model")?;
+    let messages = required_array(body, "messages")?;
+    if messages.is_empty() {
+        return Err(fail("messages", "`messages` must contain at least one message"));
+    }
+    for (i, message) in messages.iter().enumerate() {
+        let Some(message) = message.as_object() else {
+            return Err(fail(format!("messages[{}]", i), format!("`messages[{}]` must be an object", i)));
+        };
+        check_role(
+            format!("messages[{}].role", i),
+            message.get("role"),
+            &["user", "assistant"],
+            "; put system prompts in the top-level `system` field",
+        )?;
+        match message.get("content") {
+            Some(Value::String(_)) => {}
+            Some(Value::Array(blocks)) => {
+                for (j, block) in blocks.iter().enumerate() {
+                    if !block.get("type").is_some_and(Value::is_string) {
+                        let param = format!("messages[{}].content[{}].type", i, j);
+                        return Err(fail(param.clone(), format!("`{}` is required", param)));
+                    }
+                }
+            }
+            Some(other) => {
+                let param = format!("messages[{}].content", i);
+                return Err(fail(
+                    param.clone(),
+                    format!("`{}` must be a string or an array of content blocks, got {}", param, type_name(other)),
+                ));
+            }
+            None => {
+                let param = format!("messages[{}].content", i);
+                return Err(fail(param.clone(), format!("`{}` is required", param)));
+            }
+        }
+    }
+    if count_tokens {
+        return Ok(());
+    }
+    if mode == ValidationMode::Strict {
+        required(body, "max_tokens")?;
+    }
+    optional_integer(body, "max_tokens", "max_tokens", 1)?;
+    optional_number(body, "temperature", "temperature", 0.0, 1.0)?;
+    optional_number(body, "top_p", "top_p", 0.0, 1.0)?;
+    optional_integer(body, "top_k", "top_k", 0)?;
+    optional_bool(body, "stream")
+}
+
+fn openai_chat(body: &Map<String, Value>, mode: ValidationMode) -> Check {
+    required_string(body, "model")?;
+    let messages = required_array(body, "messages")?;
+    // 宽松模式沿用原有行为: 空消息列表由处理器补一条占位消息
+    if messages.is_empty() && mode == ValidationMode::Strict {
+        return Err(fail("messages", "`messages` must contain at least one message"));
+    }
+    for (i, message) in messages.iter().enumerate() {
+        let Some(message) = message.as_object() else {
+            return Err(fail(format!("messages[{}]", i), format!("`messages[{}]` must be an object", i)));
+        };
+        check_role(
+            format!("messages[{}].role", i),
+            message.get("role"),
+            &["system", "developer", "user", "assistant", "tool", "function"],
+            "",
+        )?;
+    }
+    openai_sampling(body)?;
+    optional_integer(body, "max_completion_tokens", "max_completion_tokens", 1)
+}
+
+fn openai_completions(body: &Map<String, Value>) -> Check {
+    required_string(body, "model")?;
+    match required(body, "prompt")? {
+        Value::String(_) => {}
+        Value::Array(items) if items.iter().all(Value::is_string) => {}
+        other => {
+            return Err(fail(
+                "prompt",
+                format!("`prompt` must be a string or an array of strings, got {}", type_name(other)),
+            ))
+        }
+    }
+    openai_sampling(body)
+}
+
+fn openai_sampling(body: &Map<String, Value>) -> Check {
+    optional_integer(body, "max_tokens", "max_tokens", 1)?;
+    optional_integer(body, "n", "n", 1)?;
+    optional_number(body, "temperature", "temperature", 0.0, 2.0)?;
+    optional_number(body, "top_p", "top_p", 0.0, 1.0)?;
+    optional_number(body, "presence_penalty", "presence_penalty", -2.0, 2.0)?;
+    optional_number(body, "frequency_penalty", "frequency_penalty", -2.0, 2.0)?;
+    optional_bool(body, "stream")
+}
+
+fn gemini_generate(body: &Map<String, Value>) -> Check {
+    let contents = required_array(body, "contents")?;
+    if contents.is_empty() {
+        return Err(fail("contents", "`contents` must contain at least one item"));
+    }
+    for (i, content) in contents.iter().enumerate() {
+        let Some(content) = content.as_object() else {
+            return Err(fail(format!("contents[{}]", i), format!("`contents[{}]` must be an object", i)));
+        };
+        if content.contains_key("role") {
+            check_role(format!("contents[{}].role", i), content.get("role"), &["user", "model", "function"], "")?;
+        }
+        if !content.get("parts").is_some_and(Value::is_array) {
+            let param = format!("contents[{}].parts", i);
+            return Err(fail(param.clone(), format!("`{}` must be an array of parts", param)));
+        }
+    }
+    let config_field = if body.contains_key("generationConfig") {
+        "generationConfig"
+    } else {
+        "generation_config"
+    };
+    match body.get(config_field) {
+        None | Some(Value::Null) => Ok(()),
+        Some(Value::Object(config)) => {
+            let param = |name: &str| format!("{}.{}", config_field, name);
+            optional_integer(config, &param("maxOutputTokens"), "maxOutputTokens", 1)?;
+            optional_integer(config, &param("candidateCount"), "candidateCount", 1)?;
+            optional_integer(config, &param("topK"), "topK", 0)?;
+            optional_number(config, &param("temperature"), "temperature", 0.0, 2.0)?;
+            optional_number(config, &param("topP"), "topP", 0.0, 1.0)
+        }
+        Some(other) => Err(fail(
+            config_field,
+            format!("`{}` must be an object, got {}", config_field, type_name(other)),
+        )),
+    }
+}
+
+/// 校验请求体 (`mode` 为 Off 时不校验)
+pub fn validate(endpoint: Endpoint, body: &[u8], mode: ValidationMode) -> Check {
+    if mode == ValidationMode::Off {
+        return Ok(());
+    }
+    let value: Value = serde_json::from_slice(body)
+        .map_err(|e| fail("", format!("Request body is not valid JSON: {}", e)))?;
+    let Value::Object(body) = value else {
+        return Err(fail("", format!("Request body must be a JSON object, got {}", type_name(&value))));
+    };
+    if mode == ValidationMode::Strict {
+        unknown_fields(endpoint, &body)?;
+    }
+    match endpoint {
+        Endpoint::AnthropicMessages => anthropic_messages(&body, mode, false),
+        Endpoint::AnthropicCountTokens => anthropic_messages(&body, mode, true),
+        Endpoint::OpenAIChat => openai_chat(&body, mode),
+        Endpoint::OpenAICompletions => openai_completions(&body),
+        Endpoint::GeminiGenerate => gemini_generate(&body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(endpoint: Endpoint, body: Value, mode: ValidationMode) -> Check {
+        validate(endpoint, body.to_string().as_bytes(), mode)
+    }
+
+    #[test]
+    fn endpoints_follow_paths() {
+        assert_eq!(Endpoint::from_path("/v1/messages"), Some(Endpoint::AnthropicMessages));
+        assert_eq!(
+            Endpoint::from_path("/v1beta/models/gemini-2.5-pro:streamGenerateContent"),
+            Some(Endpoint::GeminiGenerate)
+        );
+        assert_eq!(Endpoint::from_path("/v1beta/models/gemini-2.5-pro:countTokens"), None);
+        assert_eq!(Endpoint::from_path("/v1/models"), None);
+    }
+
+    #[test]
+    fn anthropic_reports_the_offending_field() {
+        let err = check(
+            Endpoint::AnthropicMessages,
+            json!({"model": "claude-sonnet-4-5", "max_tokens": 1024}),
+            ValidationMode::Lenient,
+        )
+        .unwrap_err();
+        assert_eq!(err.param, "messages");
+
+        let err = check(
+            Endpoint::AnthropicMessages,
+            json!({
+                "model": "claude-sonnet-4-5",
+                "max_tokens": 1024,
+                "messages": [{"role": "user", "content": "hi"}, {"role": "system", "content": "be brief"}]
+            }),
+            ValidationMode::Lenient,
+        )
+        .unwrap_err();
+        assert_eq!(err.param, "messages[1].role");
+        assert!(err.message.contains("top-level `system`"));
+
+        let err = check(
+            Endpoint::AnthropicMessages,
+            json!({"model": "claude-sonnet-4-5", "max_tokens": 0, "messages": [{"role": "user", "content": "hi"}]}),
+            ValidationMode::Lenient,
+        )
+        .unwrap_err();
+        assert_eq!(err.param, "max_tokens");
+    }
+
+    #[test]
+    fn strict_mode_requires_max_tokens_and_rejects_unknown_fields() {
+        let body = json!({"model": "claude-sonnet-4-5", "messages": [{"role": "user", "content": "hi"}]});
+        assert!(check(Endpoint::AnthropicMessages, body.clone(), ValidationMode::Lenient).is_ok());
+        assert_eq!(
+            check(Endpoint::AnthropicMessages, body, ValidationMode::Strict).unwrap_err().param,
+            "max_tokens"
+        );
+
+        let body = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hi"}], "temprature": 0.5});
+        assert!(check(Endpoint::OpenAIChat, body.clone(), ValidationMode::Lenient).is_ok());
+        assert_eq!(check(Endpoint::OpenAIChat, body, ValidationMode::Strict).unwrap_err().param, "temprature");
+    }
+
+    #[test]
+    fn openai_and_gemini_ranges() {
+        let err = check(
+            Endpoint::OpenAIChat,
+            json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hi"}], "temperature": 3}),
+            ValidationMode::Lenient,
+        )
+        .unwrap_err();
+        assert_eq!(err.param, "temperature");
+        // 宽松模式允许空消息列表 (由处理器补占位消息)
+        assert!(check(Endpoint::OpenAIChat, json!({"model": "gpt-4o", "messages": []}), ValidationMode::Lenient).is_ok());
+
+        let err = check(
+            Endpoint::GeminiGenerate,
+            json!({"contents": [{"role": "user", "parts": [{"text": "hi"}]}], "generationConfig": {"maxOutputTokens": -1}}),
+            ValidationMode::Lenient,
+        )
+        .unwrap_err();
+        assert_eq!(err.param, "generationConfig.maxOutputTokens");
+    }
+
+    #[test]
+    fn off_mode_and_invalid_json() {
+        assert!(validate(Endpoint::OpenAIChat, b"not json", ValidationMode::Off).is_ok());
+        let err = validate(Endpoint::OpenAIChat, b"not json", ValidationMode::Lenient).unwrap_err();
+        assert!(err.message.starts_with("Request body is not valid JSON"));
+    }
+}
@@ -0,0 +1,125 @@
+// 启动预热: 为每个账号提前刷新 token 并发送一次轻量的配额查询，尽早发现失效账号
+use futures::StreamExt;
+use serde::Serialize;
+
+use crate::proxy::token_manager::TokenManager;
+
+/// 同时预热的账号数
+const WARMUP_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupStatus {
+    Ready,
+    /// 403 Forbidden，已移出轮换池
+    Forbidden,
+    /// token 刷新或配额查询失败 (invalid_grant 时账号已被禁用)
+    Failed,
+}
+
+impl WarmupStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ready => "ready",
+            Self::Forbidden => "forbidden",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupResult {
+    pub account_id: String,
+    pub email: String,
+    pub status: WarmupStatus,
+    pub latency_ms: u64,
+    pub detail: Option<String>,
+}
+
+async fn warmup_account(token_manager: &TokenManager, account_id: String, email: String) -> WarmupResult {
+    let start = std::time::Instant::now();
+    let (status, detail) = match token_manager.ensure_fresh_token(&account_id).await {
+        Err(e) => (WarmupStatus::Failed, Some(e)),
+        Ok(access_token) => match crate::modules::quota::fetch_quota(&access_token, &email).await {
+            Ok((quota, _)) if quota.is_forbidden => {
+                let _ = crate::modules::update_account_quota(&account_id, quota);
+                (WarmupStatus::Forbidden, None)
+            }
+            Ok((quota, _)) => {
+                let detail = quota.subscription_tier.as_ref().map(|t| t.to_string());
+                let _ = crate::modules::update_account_quota(&account_id, quota);
+                (WarmupStatus::Ready, detail)
+            }
+            Err(e) => (WarmupStatus::Failed, Some(e.to_string())),
+        },
+    };
+    // 失效账号不参与后续轮换，避免首批真实请求踩坑
+    if status != WarmupStatus::Ready {
+        token_manager.evict(&account_id);
+    }
+    WarmupResult {
+        account_id,
+        email,
+        status,
+        latency_ms: start.elapsed().as_millis() as u64,
+        detail,
+    }
+}
+
+/// 预热池中的所有账号，结果按邮箱排序
+pub async fn warmup_accounts(token_manager: &TokenManager) -> Vec<WarmupResult> {
+    let mut results: Vec<WarmupResult> = futures::stream::iter(token_manager.account_ids())
+        .map(|(id, email)| warmup_account(token_manager, id, email))
+        .buffer_unordered(WARMUP_CONCURRENCY)
+        .collect()
+        .await;
+    results.sort_by(|a, b| a.email.cmp(&b.email));
+    results
+}
+
+/// 预热结果汇总表
+pub fn format_summary(results: &[WarmupResult]) -> String {
+    let width = results.iter().map(|r| r.email.len()).max().unwrap_or(0).max(7);
+    let mut out = format!("{:<width$}  {:<9}  {:>7}  {}\n", "ACCOUNT", "STATUS", "LATENCY", "DETAIL");
+    for r in results {
+        out.push_str(&format!(
+            "{:<width$}  {:<9}  {:>5}ms  {}\n",
+            r.email,
+            r.status.as_str(),
+            r.latency_ms,
+            r.detail.as_deref().unwrap_or("-")
+        ));
+    }
+    let ready = results.iter().filter(|r| r.status == WarmupStatus::Ready).count();
+    out.push_str(&format!("{}/{} accounts ready\n", ready, results.len()));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(email: &str, status: WarmupStatus, detail: Option<&str>) -> WarmupResult {
+        WarmupResult {
+            account_id: email.to_string(),
+            email: email.to_string(),
+            status,
+            latency_ms: 120,
+            detail: detail.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn summary_lists_each_account_and_ready_count() {
+        let summary = format_summary(&[
+            result("a@example.com", WarmupStatus::Ready, Some("PRO")),
+            result("b@example.com", WarmupStatus::Failed, Some("invalid_grant")),
+        ]);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("ACCOUNT"));
+        assert!(lines[1].contains("ready") && lines[1].contains("PRO") && lines[1].contains("120ms"));
+        assert!(lines[2].contains("failed") && lines[2].contains("invalid_grant"));
+        assert_eq!(lines[3], "1/2 accounts ready");
+    }
+}
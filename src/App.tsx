@@ -12,6 +12,7 @@ import { useConfigStore } from './stores/useConfigStore';
 import { useAccountStore } from './stores/useAccountStore';
 import { useTranslation } from 'react-i18next';
 import { listen } from '@tauri-apps/api/event';
+import { showToast } from './components/common/ToastContainer';
 
 const router = createBrowserRouter([
   {
@@ -80,6 +81,23 @@ function App() {
       })
     );
 
+    // 监听 Forbidden 账号自动恢复事件
+    unlistenPromises.push(
+      listen<{ id: string; email: string }>('proxy://account-recovered', (event) => {
+        showToast(`${event.payload.email} recovered and returned to the proxy pool`, 'success');
+        fetchAccounts();
+      })
+    );
+
+    // 监听反代事件总线: 账号被禁用或账号池重新加载后刷新账号列表
+    unlistenPromises.push(
+      listen<{ type: string; account?: string }>('proxy://event', (event) => {
+        if (event.payload.type === 'account_disabled' || event.payload.type === 'accounts_reloaded') {
+          fetchAccounts();
+        }
+      })
+    );
+
     // Cleanup
     return () => {
       Promise.all(unlistenPromises).then(unlisteners => {
@@ -20,6 +20,11 @@ interface ProxyRequestLog {
     response_body?: string;
     input_tokens?: number;
     output_tokens?: number;
+    session_id?: string;
+    account?: string;
+    key_id?: string;
+    client_ip?: string;
+    canary_target?: string;
 }
 
 interface ProxyStats {
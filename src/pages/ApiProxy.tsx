@@ -34,6 +34,13 @@ interface ProxyStatus {
     port: number;
     base_url: string;
     active_accounts: number;
+    quota_resets?: {
+        email: string;
+        model: string;
+        reset_at: number;
+        resets_in: string;
+    }[];
+    api_key_hint?: string;
 }
 
 
@@ -1163,7 +1170,7 @@ print(response.text)`;
                                                 </button>
                                             </div>
                                             <div className="grid grid-cols-1 gap-2">
-                                                {(['CacheFirst', 'Balance', 'PerformanceFirst'] as const).map(mode => (
+                                                {(['CacheFirst', 'Balance', 'PerformanceFirst', 'LatencyFirst'] as const).map(mode => (
                                                     <label
                                                         key={mode}
                                                         className={`flex items-start gap-3 p-3 rounded-xl border cursor-pointer transition-all duration-200 ${(appConfig.proxy.scheduling?.mode || 'Balance') === mode
@@ -30,6 +30,23 @@ export async function deleteAccounts(accountIds: string[]): Promise<void> {
     return await invoke('delete_accounts', { accountIds });
 }
 
+export interface TrashedAccount {
+    deleted_at: number;
+    account: Account;
+}
+
+export async function listTrashedAccounts(): Promise<TrashedAccount[]> {
+    return await invoke('list_trashed_accounts');
+}
+
+export async function restoreAccount(accountId: string): Promise<Account> {
+    return await invoke('restore_account', { accountId });
+}
+
+export async function purgeTrashedAccounts(olderThanSecs?: number): Promise<number> {
+    return await invoke('purge_trashed_accounts', { olderThanSecs });
+}
+
 export async function switchAccount(accountId: string): Promise<void> {
     return await invoke('switch_account', { accountId });
 }
@@ -42,11 +59,18 @@ export interface RefreshStats {
     total: number;
     success: number;
     failed: number;
+    skipped: number;
     details: string[];
 }
 
-export async function refreshAllQuotas(): Promise<RefreshStats> {
-    return await invoke('refresh_all_quotas');
+export interface RefreshFilter {
+    stale_after_secs?: number;
+    only_forbidden?: boolean;
+    accounts?: string[];
+}
+
+export async function refreshAllQuotas(filter?: RefreshFilter): Promise<RefreshStats> {
+    return await invoke('refresh_all_quotas', { filter });
 }
 
 // OAuth
@@ -10,10 +10,28 @@ export interface Account {
     proxy_disabled?: boolean;
     proxy_disabled_reason?: string;
     proxy_disabled_at?: number;
+    note?: string;
+    metadata?: Record<string, string>;
+    device?: DeviceIdentity;
+    client_profile?: string;
+    last_errors?: AccountError[];
+    refresh?: RefreshStats;
     created_at: number;
     last_used: number;
 }
 
+export interface AccountError {
+    timestamp: number;
+    status?: number;
+    message: string;
+}
+
+export interface DeviceIdentity {
+    machine_id: string;
+    session_id: string;
+    created_at: number;
+}
+
 export interface TokenData {
     access_token: string;
     refresh_token: string;
@@ -23,15 +41,34 @@ export interface TokenData {
     email?: string;
 }
 
+// 订阅等级: 已识别的等级归一为 FREE/PRO/ULTRA，其余保留上游原始 ID
+export type SubscriptionTier = 'FREE' | 'PRO' | 'ULTRA' | (string & {});
+
 export interface QuotaData {
     models: ModelQuota[];
     last_updated: number;
     is_forbidden?: boolean;
-    subscription_tier?: string;  // 订阅类型: FREE/PRO/ULTRA
+    subscription_tier?: SubscriptionTier;
 }
 
 export interface ModelQuota {
     name: string;
     percentage: number;
     reset_time: string;
+    reset_at?: number;
+    limits?: ModelLimits;
+}
+
+export interface ModelLimits {
+    max_tokens?: number | null;
+    max_output_tokens?: number | null;
+    supports_images?: boolean | null;
+    supports_thinking?: boolean | null;
+}
+
+export interface RefreshStats {
+    last_success_at?: number;
+    last_failure_at?: number;
+    failure_streak: number;
+    token_obtained_at?: number;
 }
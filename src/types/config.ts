@@ -1,26 +1,392 @@
+import type { SubscriptionTier } from './account';
+
 export interface UpstreamProxyConfig {
     enabled: boolean;
     url: string;
 }
 
+export interface RouteTimeoutOverride {
+    connect_timeout?: number | null;
+    request_timeout?: number | null;
+    stream_idle_timeout?: number | null;
+    stream_heartbeat_interval?: number | null;
+}
+
+export interface TimeoutConfig {
+    connect_timeout: number;
+    stream_idle_timeout: number;
+    stream_heartbeat_interval?: number;
+    routes?: Record<string, RouteTimeoutOverride>;
+}
+
+export interface UpstreamPoolConfig {
+    max_idle_per_host: number;
+    idle_timeout: number;
+    tcp_keepalive: number;
+    http2_only: boolean;
+    http2_keep_alive_interval: number;
+    http2_adaptive_window: boolean;
+}
+
+export interface ClientRateLimitConfig {
+    enabled: boolean;
+    requests_per_second: number;
+    burst: number;
+}
+
+export interface LogAnomalyConfig {
+    bucket_secs: number;
+    error_spike_factor: number;
+    error_spike_min_errors: number;
+    account_failure_rate: number;
+    account_min_requests: number;
+    latency_regression_factor: number;
+    latency_min_samples: number;
+    malformed_min_requests: number;
+}
+
+export interface StreamResumptionConfig {
+    enabled: boolean;
+    max_attempts: number;
+    continue_prompt: string;
+}
+
+export interface UpstreamBucketConfig {
+    qps: number;
+    burst: number;
+}
+
+export interface UpstreamRateLimitConfig {
+    enabled: boolean;
+    global: UpstreamBucketConfig;
+    per_host: Record<string, UpstreamBucketConfig>;
+    max_wait_secs: number;
+}
+
+export interface AuthLockoutConfig {
+    enabled: boolean;
+    max_failures: number;
+    window_secs: number;
+    ban_secs: number;
+    base_delay_ms: number;
+    max_delay_ms: number;
+    webhook_url: string;
+}
+
+export interface BatchConfig {
+    max_concurrency: number;
+}
+
+export type RequestPriority = 'low' | 'normal' | 'high';
+
+export interface LoadSheddingConfig {
+    enabled: boolean;
+    max_concurrent: number;
+    max_queue: number;
+    queue_timeout: number;
+    retry_after: number;
+    default_priority: RequestPriority;
+    key_priorities: Record<string, RequestPriority>;
+}
+
+export interface QuotaThresholdRule {
+    model: string;
+    min_percentage: number;
+}
+
+export interface AccountUsageCap {
+    max_requests?: number | null;
+    max_tokens?: number | null;
+}
+
+export interface ModelPrice {
+    input_per_mtok: number;
+    output_per_mtok: number;
+}
+
+export interface UsageCapConfig {
+    enabled: boolean;
+    window_secs: number;
+    max_requests?: number | null;
+    max_tokens?: number | null;
+    accounts: Record<string, AccountUsageCap>;
+}
+
+export interface TeamRoute {
+    organization?: string | null;
+    project?: string | null;
+    accounts: string[];
+}
+
+export interface TeamRoutingConfig {
+    enabled: boolean;
+    rules: TeamRoute[];
+    reject_unmatched: boolean;
+}
+
+export interface TierReservation {
+    tier: SubscriptionTier;
+    models: string[];
+    keys: string[];
+}
+
+export interface TierPolicyConfig {
+    enabled: boolean;
+    reservations: TierReservation[];
+}
+
+export interface ScheduleRule {
+    name?: string;
+    days?: string[];
+    hours?: string;
+    models?: string[];
+    tiers?: SubscriptionTier[];
+    accounts?: string[];
+}
+
+export interface ScheduleRoutingConfig {
+    enabled: boolean;
+    timezone?: string;
+    rules: ScheduleRule[];
+}
+
+export interface ClusterConfig {
+    enabled: boolean;
+    instance_id: string;
+    peers: string[];
+    api_key: string;
+    sync_interval_secs: number;
+}
+
+export interface ModelCapabilityOverride {
+    max_context_tokens?: number | null;
+    max_output_tokens?: number | null;
+    vision?: boolean | null;
+    tools?: boolean | null;
+    thinking?: boolean | null;
+}
+
+export interface ModelCapabilityConfig {
+    enforce: boolean;
+    overrides: Record<string, ModelCapabilityOverride>;
+}
+
+export type ContextOverflowStrategy = 'reject' | 'truncate' | 'summarize';
+
+export interface ContextOverflowConfig {
+    strategy: ContextOverflowStrategy;
+    keep_recent_messages: number;
+    summary_model: string;
+}
+
+export interface QuotaCacheConfig {
+    max_staleness_secs: number;
+    revalidate: boolean;
+}
+
+export interface ModelListConfig {
+    quota_aware: boolean;
+}
+
+export interface ClientProfile {
+    name: string;
+    user_agent: string;
+    headers?: Record<string, string>;
+}
+
+export interface ClientProfileConfig {
+    active: string;
+    profiles: ClientProfile[];
+}
+
+export interface MirrorConfig {
+    enabled: boolean;
+    percent: number;
+    model_mapping: Record<string, string>;
+    accounts: string[];
+    history: number;
+}
+
+export interface BodyCaptureConfig {
+    percent: number;
+    always: string[];
+}
+
+export interface CompressionConfig {
+    enabled: boolean;
+    min_size: number;
+    streaming: boolean;
+}
+
+export type ValidationMode = 'off' | 'lenient' | 'strict';
+
+export interface RequestValidationConfig {
+    mode: ValidationMode;
+}
+
+export interface ParamLimit {
+    min?: number;
+    max?: number;
+    default?: number;
+    override?: number;
+}
+
+export interface ParamPolicyRule {
+    models?: string[];
+    keys?: string[];
+    temperature?: ParamLimit;
+    top_p?: ParamLimit;
+    max_tokens?: number;
+}
+
+export interface ParamPolicyConfig {
+    rules: ParamPolicyRule[];
+}
+
+export interface ResponseHeaderConfig {
+    forward: string[];
+    privacy: boolean;
+}
+
+export interface RetryPolicyConfig {
+    max_attempts: number;
+    retry_statuses: number[];
+    backoff_base_ms: number;
+    backoff_max_ms: number;
+    rotate_account: boolean;
+    retry_streaming: boolean;
+}
+
+export interface QuotaThresholdConfig {
+    enabled: boolean;
+    rules: QuotaThresholdRule[];
+}
+
+export interface ListenerTlsConfig {
+    cert_path: string;
+    key_path: string;
+}
+
+export interface ListenerConfig {
+    bind: string;
+    tls?: ListenerTlsConfig;
+    auth_mode?: 'off' | 'strict' | 'all_except_health' | 'auto';
+    api_key?: string;
+    socket_mode?: string;
+    acme?: AcmeConfig;
+}
+
+export interface AcmeConfig {
+    domains: string[];
+    email?: string;
+    directory_url: string;
+    challenge: 'http-01' | 'tls-alpn-01';
+    http_bind: string;
+}
+
+export interface RetiredApiKey {
+    key: string;
+    expires_at: number; // Unix 秒
+}
+
+export type KeyScope = 'chat-only' | 'no-embeddings' | 'no-admin' | 'read-only-stats' | 'pin-account';
+
+export interface ScopedApiKey {
+    name: string;
+    key: string;
+    scopes: KeyScope[];
+    created_at: number; // Unix 秒
+}
+
+export interface GrpcConfig {
+    enabled: boolean;
+    bind: string;
+    tls?: ListenerTlsConfig;
+}
+
+export interface AccountRecoveryConfig {
+    enabled: boolean;
+    interval_secs: number;
+}
+
+export interface KeyRotationConfig {
+    grace_period_secs: number;
+    webhook_url: string;
+}
+
+export interface WeightedTarget {
+    model: string;
+    weight: number;
+}
+
+export interface ModelMappingRule {
+    pattern: string; // 通配符 (* / ?) 或 re: 开头的正则
+    target: string;
+    targets?: WeightedTarget[]; // 按权重分流 (金丝雀)，非空时替代 target
+}
+
 export interface ProxyConfig {
     enabled: boolean;
     allow_lan_access?: boolean;
     auth_mode?: 'off' | 'strict' | 'all_except_health' | 'auto';
     port: number;
+    listeners?: ListenerConfig[];
+    listen_tcp?: boolean;
+    admin_listen?: string;
     api_key: string;
+    hash_api_keys?: boolean;
+    previous_api_keys?: RetiredApiKey[];
+    api_keys?: ScopedApiKey[];
+    key_rotation?: KeyRotationConfig;
     auto_start: boolean;
     anthropic_mapping?: Record<string, string>;
     openai_mapping?: Record<string, string>;
     custom_mapping?: Record<string, string>;
+    mapping_rules?: ModelMappingRule[];
     request_timeout: number;
+    timeouts?: TimeoutConfig;
     enable_logging: boolean;
+    enable_debug_endpoints?: boolean;
+    allow_account_pinning?: boolean;
     upstream_proxy: UpstreamProxyConfig;
+    upstream_pool?: UpstreamPoolConfig;
+    upstream_rate_limit?: UpstreamRateLimitConfig;
+    client_rate_limit?: ClientRateLimitConfig;
+    auth_lockout?: AuthLockoutConfig;
+    trusted_proxies?: string[];
+    batches?: BatchConfig;
+    load_shedding?: LoadSheddingConfig;
+    quota_thresholds?: QuotaThresholdConfig;
+    warmup_on_start?: boolean;
+    strict_mappings?: boolean;
+    usage_caps?: UsageCapConfig;
+    anthropic_versions?: string[];
+    pricing?: Record<string, ModelPrice>;
+    log_anomalies?: LogAnomalyConfig;
+    body_capture?: BodyCaptureConfig;
+    team_routing?: TeamRoutingConfig;
+    tier_policy?: TierPolicyConfig;
+    schedule_routing?: ScheduleRoutingConfig;
+    retry_policy?: RetryPolicyConfig;
+    response_headers?: ResponseHeaderConfig;
+    quota_cache?: QuotaCacheConfig;
+    cluster?: ClusterConfig;
+    model_capabilities?: ModelCapabilityConfig;
+    context_overflow?: ContextOverflowConfig;
+    model_list?: ModelListConfig;
+    client_profiles?: ClientProfileConfig;
+    mirror?: MirrorConfig;
+    compression?: CompressionConfig;
+    request_validation?: RequestValidationConfig;
+    param_policy?: ParamPolicyConfig;
+    stream_resumption?: StreamResumptionConfig;
+    grpc?: GrpcConfig;
+    account_recovery?: AccountRecoveryConfig;
     zai?: ZaiConfig;
     scheduling?: StickySessionConfig;
 }
 
-export type SchedulingMode = 'CacheFirst' | 'Balance' | 'PerformanceFirst';
+export type SchedulingMode = 'CacheFirst' | 'Balance' | 'PerformanceFirst' | 'LatencyFirst';
 
 export interface StickySessionConfig {
     mode: SchedulingMode;